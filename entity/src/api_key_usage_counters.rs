@@ -0,0 +1,42 @@
+//! # 服务 API 用量计数实体定义
+//!
+//! 按服务 API、按天（UTC）汇总的请求数、token 数与费用，由内存计数器定期写入
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 服务 API 用量计数实体
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "api_key_usage_counters")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub user_service_api_id: i32,
+    /// 统计日期（UTC）
+    pub usage_date: Date,
+    pub request_count: i64,
+    pub total_tokens: i64,
+    pub total_cost: f64,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user_service_apis::Entity",
+        from = "Column::UserServiceApiId",
+        to = "super::user_service_apis::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    UserServiceApi,
+}
+
+impl Related<super::user_service_apis::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserServiceApi.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,20 +2,32 @@
 //!
 //! 包含所有 Sea-ORM 实体定义
 
+pub mod api_key_usage_counters;
 pub mod model_pricing;
 pub mod model_pricing_tiers;
 pub mod oauth_client_sessions;
 pub mod provider_types;
 pub mod proxy_tracing;
+pub mod shadow_comparisons;
+pub mod spend_anomaly_flags;
+pub mod task_run_history;
+pub mod user_included_quota_usage;
+pub mod user_provider_blocks;
 pub mod user_provider_keys;
 pub mod user_service_apis;
 pub mod users;
 
+pub use api_key_usage_counters::Entity as ApiKeyUsageCounters;
 pub use model_pricing::Entity as ModelPricing;
 pub use model_pricing_tiers::Entity as ModelPricingTiers;
 pub use oauth_client_sessions::Entity as OAuthClientSessions;
 pub use provider_types::Entity as ProviderTypes;
 pub use proxy_tracing::Entity as ProxyTracing;
+pub use shadow_comparisons::Entity as ShadowComparisons;
+pub use spend_anomaly_flags::Entity as SpendAnomalyFlags;
+pub use task_run_history::Entity as TaskRunHistory;
+pub use user_included_quota_usage::Entity as UserIncludedQuotaUsage;
+pub use user_provider_blocks::Entity as UserProviderBlocks;
 pub use user_provider_keys::Entity as UserProviderKeys;
 pub use user_service_apis::Entity as UserServiceApis;
 pub use users::Entity as Users;
@@ -19,10 +19,12 @@ pub struct Model {
     pub provider_type_id: Option<i32>,
     pub code_verifier: String,
     pub code_challenge: String,
+    /// 发起授权时选定的回调地址；为空表示使用提供商默认 `redirect_uri`
+    pub redirect_uri: Option<String>,
     pub state: String,
     pub name: String,
     pub description: Option<String>,
-    pub status: String, // pending, authorized, error, expired, revoked
+    pub status: String, // pending, authorized, error, expired, revoked, needs_attention
     pub access_token: Option<String>,
     pub refresh_token: Option<String>,
     pub id_token: Option<String>,
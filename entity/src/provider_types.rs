@@ -76,9 +76,15 @@ pub struct OAuthConfig {
     pub client_id: String,
     pub client_secret: Option<String>,
     pub redirect_uri: Option<String>,
+    /// 额外允许的回调地址，发起授权时可从中选择
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirect_uris: Vec<String>,
     /// 空格分隔的 scopes 字符串
     pub scopes: String,
     pub pkce_required: bool,
+    /// PKCE code challenge 方法（`S256`/`plain`），缺省为 `S256`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pkce_method: Option<String>,
     pub authorize: OAuthAuthorizeFlow,
     pub exchange: OAuthTokenFlow,
     pub refresh: OAuthTokenFlow,
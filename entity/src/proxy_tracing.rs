@@ -25,11 +25,19 @@ pub struct Model {
     pub tokens_completion: Option<i32>,
     pub tokens_total: Option<i32>,
     pub token_efficiency_ratio: Option<f64>,
+    /// Token 数由本地分词器估算（上游未返回用量）
+    pub tokens_estimated: bool,
 
     // === 缓存Token统计 ===
     pub cache_create_tokens: Option<i32>,
     pub cache_read_tokens: Option<i32>,
 
+    // === 传输字节统计 ===
+    /// 客户端发送的请求体字节数
+    pub request_bytes: Option<i64>,
+    /// 上游返回的响应体字节数（流式响应为全部分块之和）
+    pub response_bytes: Option<i64>,
+
     // === 费用统计 ===
     pub cost: Option<f64>,
     pub cost_currency: Option<String>,
@@ -40,10 +48,22 @@ pub struct Model {
     // === 业务信息 ===
     pub model_used: Option<String>,
     pub client_ip: Option<String>,
+    /// 客户端 IP 所属国家代码（配置 `geoip` 时写入）
+    pub client_country: Option<String>,
+    /// 客户端 IP 所属自治系统号（配置 `geoip` 时写入）
+    pub client_asn: Option<i64>,
     pub user_agent: Option<String>,
     pub error_type: Option<String>,
     pub error_message: Option<String>,
     pub retry_count: Option<i32>,
+    /// 代理对请求所做调整等附加信息（JSON）
+    #[sea_orm(column_type = "Json", nullable)]
+    pub request_metadata: Option<Json>,
+    /// 按配置从响应体中提取的字段（JSON）
+    #[sea_orm(column_type = "Json", nullable)]
+    pub response_metadata: Option<Json>,
+    /// 发往上游的请求体（开启 `trace.capture_request_body` 时写入，用于回放）
+    pub request_body: Option<String>,
 
     // === 提供商信息 ===
     pub provider_type_id: Option<i32>,
@@ -52,6 +72,8 @@ pub struct Model {
     pub start_time: Option<DateTime>,
     pub end_time: Option<DateTime>,
     pub duration_ms: Option<i64>,
+    /// 总耗时超过服务 API 的响应时间目标（`sla_target_ms`）
+    pub sla_breached: bool,
     pub is_success: bool,
 
     // === 创建时间 ===
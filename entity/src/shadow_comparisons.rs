@@ -0,0 +1,37 @@
+//! # 影子路由对比记录实体定义
+//!
+//! 影子路由把同一请求同时发往主服务商与影子服务商，每个请求写入一条对比记录，
+//! 供管理端按时间范围汇总状态码一致率、Token 差值与延迟差值
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 影子路由对比记录实体
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shadow_comparisons")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    /// 主请求的请求 ID
+    pub request_id: String,
+    pub user_service_api_id: Option<i32>,
+    pub model: Option<String>,
+    pub primary_provider_type_id: i32,
+    pub shadow_provider_type_id: i32,
+    pub primary_status_code: i32,
+    /// 影子请求未收到响应（连接失败、超时等）时为空
+    pub shadow_status_code: Option<i32>,
+    pub primary_tokens_total: Option<i32>,
+    pub shadow_tokens_total: Option<i32>,
+    pub primary_duration_ms: i64,
+    pub shadow_duration_ms: Option<i64>,
+    /// 影子请求失败原因
+    #[sea_orm(column_type = "Text", nullable)]
+    pub shadow_error: Option<String>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,47 @@
+//! # 消费异常标记实体定义
+//!
+//! 消费异常检测任务发现用户小时消费超过基线倍数时写入的标记，供管理端查看与确认
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 消费异常标记实体
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "spend_anomaly_flags")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub user_id: i32,
+    /// 检测窗口（最近一小时）的起止时间
+    pub window_start: DateTime,
+    pub window_end: DateTime,
+    /// 检测窗口内的消费
+    pub current_spend: f64,
+    /// 基线窗口内的平均小时消费
+    pub baseline_hourly_spend: f64,
+    /// 标记时使用的倍数阈值
+    pub multiplier: f64,
+    /// 管理员确认时间，未确认为空
+    pub acknowledged_at: Option<DateTime>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,30 @@
+//! # 后台任务执行历史实体定义
+//!
+//! 调度器每次执行后台任务后写入一条记录，供管理端按任务类型与时间范围查询
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 后台任务执行历史实体
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "task_run_history")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    /// 任务标识（与 `GET /api/system/tasks` 的 `task` 一致）
+    pub task_type: String,
+    /// 执行结果：`success` 或 `failed`
+    pub status: String,
+    /// 开始与结束时间（UTC）
+    pub started_at: DateTime,
+    pub finished_at: DateTime,
+    pub duration_ms: i64,
+    /// 失败时的错误信息
+    #[sea_orm(column_type = "Text", nullable)]
+    pub error: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
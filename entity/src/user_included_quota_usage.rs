@@ -0,0 +1,43 @@
+//! # 免费额度用量实体定义
+//!
+//! 按用户、按月累计已消耗的套餐内免费额度（token 数与请求数）
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 免费额度用量实体
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_included_quota_usage")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub user_id: i32,
+    /// 计费月份（UTC），格式 `YYYY-MM`
+    pub period: String,
+    /// 本月已由免费额度抵扣的 token 数
+    pub used_tokens: i64,
+    /// 本月已由免费额度抵扣的请求数
+    pub used_requests: i64,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,51 @@
+//! # 用户服务商封禁实体定义
+//!
+//! 管理员按用户禁用指定服务商，被封禁的服务商在代理端直接拒绝
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 用户服务商封禁实体
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_provider_blocks")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub user_id: i32,
+    pub provider_type_id: i32,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+    #[sea_orm(
+        belongs_to = "super::provider_types::Entity",
+        from = "Column::ProviderTypeId",
+        to = "super::provider_types::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    ProviderType,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl Related<super::provider_types::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProviderType.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
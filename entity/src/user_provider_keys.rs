@@ -38,6 +38,11 @@ pub struct Model {
     pub last_auth_check: Option<DateTime>, // 最后认证检查时间
     // Gemini项目ID - 支持Gemini Code Assist功能（仅OAuth类型使用）
     pub project_id: Option<String>, // Google Cloud/Workspace项目ID
+    /// 密钥标签(JSON字符串数组)，用于分组与按标签调度
+    #[sea_orm(column_type = "Json", nullable)]
+    pub tags: Option<sea_orm::prelude::Json>,
+    // 软删除时间，非空表示已删除（保留行以维持追踪记录的关联）
+    pub deleted_at: Option<DateTime>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
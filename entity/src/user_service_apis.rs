@@ -18,17 +18,52 @@ pub struct Model {
     pub user_provider_keys_ids: sea_orm::prelude::Json,
     #[sea_orm(unique)]
     pub api_key: String,
+    /// 轮换前的 API Key，宽限期内仍可用于认证
+    pub previous_api_key: Option<String>,
+    /// 旧 API Key 的失效时间，为空或已过期时旧 Key 不可用
+    pub previous_api_key_expires_at: Option<DateTime>,
     pub name: Option<String>,
     pub description: Option<String>,
     pub scheduling_strategy: Option<String>,
     pub retry_count: Option<i32>,
     pub timeout_seconds: Option<i32>,
+    /// 请求总时长上限(秒)，超过后中断请求（含流式响应）；为空时使用全局配置
+    pub max_response_duration_seconds: Option<i32>,
+    /// 响应时间目标(毫秒)，请求总耗时超过后在追踪记录中标记 `sla_breached`；为空时不标记
+    pub sla_target_ms: Option<i32>,
     pub max_request_per_min: Option<i32>,
     pub max_requests_per_day: Option<i32>,
     pub max_tokens_per_day: Option<i64>,
     pub max_cost_per_day: Option<Decimal>,
+    /// 单次请求费用上限，流式响应按已输出的 Token 估算费用，超过后中断；为空时不限制
+    pub max_cost_per_request: Option<Decimal>,
     /// 是否开启日志模式（记录完整请求/响应内容到服务日志）
     pub log_mode: bool,
+    /// 是否在追踪记录中保存密钥选择依据（候选集与各候选评分）
+    pub selection_debug: bool,
+    /// 流式策略：`allow` 允许、`deny` 拒绝流式请求、`force-off` 强制改为非流式
+    pub stream_policy: String,
+    /// 上游错误返回方式：`sanitized` 规范化并脱敏（默认）、`verbatim` 原样透传
+    pub upstream_error_mode: String,
+    /// 全局并发准入优先级，越高越先获得空位
+    pub priority: i32,
+    /// 请求体改写规则(JSON数组)，转发上游前按顺序执行
+    #[sea_orm(column_type = "Json", nullable)]
+    pub request_transform_rules: Option<sea_orm::prelude::Json>,
+    /// 自定义响应头(JSON对象，头部名称 -> 值)，返回客户端前写入上游响应
+    #[sea_orm(column_type = "Json", nullable)]
+    pub response_headers: Option<sea_orm::prelude::Json>,
+    /// 允许访问的请求路径模式(JSON数组)，支持前缀与 `*` 通配符；为空时不限制
+    #[sea_orm(column_type = "Json", nullable)]
+    pub allowed_paths: Option<sea_orm::prelude::Json>,
+    /// 强制注入的系统提示词(JSON对象，`content` 与 `mode`)，为空时不注入
+    #[sea_orm(column_type = "Json", nullable)]
+    pub system_prompt: Option<sea_orm::prelude::Json>,
+    /// 只在带有该标签的密钥之间调度，为空时不限制
+    pub key_tag: Option<String>,
+    /// 按模型路由到不同服务商的规则(JSON数组)，按顺序匹配；为空时只使用 `provider_type_id`
+    #[sea_orm(column_type = "Json", nullable)]
+    pub model_routes: Option<sea_orm::prelude::Json>,
     pub expires_at: Option<DateTime>,
     pub is_active: bool,
     pub created_at: DateTime,
@@ -20,6 +20,14 @@ pub struct Model {
     pub is_active: bool,
     pub is_admin: bool,
     pub last_login: Option<DateTime>,
+    /// 每月免费 token 额度，为空表示没有
+    pub included_tokens_per_month: Option<i64>,
+    /// 每月免费请求数额度，为空表示没有
+    pub included_requests_per_month: Option<i64>,
+    /// 积分余额，为空表示不启用积分额度
+    pub credits_balance: Option<i64>,
+    /// 进行中请求预留的积分
+    pub credits_reserved: i64,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
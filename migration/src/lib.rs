@@ -8,6 +8,40 @@ mod m20240101_000008_create_proxy_tracing_table;
 mod m20240101_000009_create_model_pricing_table;
 mod m20240101_000010_create_model_pricing_tiers_table;
 mod m20250126_000003_create_oauth_client_sessions_table;
+mod m20250220_000001_add_proxy_tracing_request_metadata;
+mod m20250220_000002_add_oauth_client_sessions_redirect_uri;
+mod m20250220_000003_add_user_service_apis_request_transform_rules;
+mod m20250220_000004_add_proxy_tracing_response_metadata;
+mod m20250220_000005_add_proxy_tracing_byte_counts;
+mod m20250220_000006_add_proxy_tracing_request_body;
+mod m20250220_000007_add_user_service_apis_max_response_duration;
+mod m20250301_000001_create_spend_anomaly_flags_table;
+mod m20250305_000001_add_proxy_tracing_tokens_estimated;
+mod m20250305_000002_add_user_service_apis_response_headers;
+mod m20250305_000003_add_user_service_apis_selection_debug;
+mod m20250305_000004_add_user_service_apis_force_non_streaming;
+mod m20250305_000005_add_user_service_apis_priority;
+mod m20250305_000006_add_user_provider_keys_deleted_at;
+mod m20250315_000001_create_user_included_quota_usage_table;
+mod m20250315_000002_add_users_included_quota_columns;
+mod m20250320_000001_create_user_provider_blocks_table;
+mod m20250322_000001_add_proxy_tracing_client_geo;
+mod m20250325_000001_create_api_key_usage_counters_table;
+mod m20250325_000002_add_user_service_apis_allowed_paths;
+mod m20250326_000001_create_task_run_history_table;
+mod m20250328_000001_add_user_service_apis_max_cost_per_request;
+mod m20250328_000002_add_user_service_apis_previous_api_key;
+mod m20250328_000003_add_user_service_apis_stream_policy;
+mod m20250328_000004_add_user_service_apis_system_prompt;
+mod m20250401_000001_create_shadow_comparisons_table;
+mod m20250405_000001_add_users_credits_columns;
+mod m20250405_000002_add_user_provider_keys_tags;
+mod m20250405_000003_add_user_service_apis_key_tag;
+mod m20250405_000004_add_user_service_apis_upstream_error_mode;
+mod m20250405_000005_add_user_service_apis_model_routes;
+mod m20250405_000006_add_user_service_apis_sla_target_ms;
+mod m20250405_000007_add_proxy_tracing_sla_breached;
+mod m20250412_000001_fold_force_non_streaming_into_stream_policy;
 
 pub struct Migrator;
 
@@ -23,6 +57,40 @@ impl MigratorTrait for Migrator {
             Box::new(m20240101_000009_create_model_pricing_table::Migration),
             Box::new(m20240101_000010_create_model_pricing_tiers_table::Migration),
             Box::new(m20250126_000003_create_oauth_client_sessions_table::Migration),
+            Box::new(m20250220_000001_add_proxy_tracing_request_metadata::Migration),
+            Box::new(m20250220_000002_add_oauth_client_sessions_redirect_uri::Migration),
+            Box::new(m20250220_000003_add_user_service_apis_request_transform_rules::Migration),
+            Box::new(m20250220_000004_add_proxy_tracing_response_metadata::Migration),
+            Box::new(m20250220_000005_add_proxy_tracing_byte_counts::Migration),
+            Box::new(m20250220_000006_add_proxy_tracing_request_body::Migration),
+            Box::new(m20250220_000007_add_user_service_apis_max_response_duration::Migration),
+            Box::new(m20250301_000001_create_spend_anomaly_flags_table::Migration),
+            Box::new(m20250305_000001_add_proxy_tracing_tokens_estimated::Migration),
+            Box::new(m20250305_000002_add_user_service_apis_response_headers::Migration),
+            Box::new(m20250305_000003_add_user_service_apis_selection_debug::Migration),
+            Box::new(m20250305_000004_add_user_service_apis_force_non_streaming::Migration),
+            Box::new(m20250305_000005_add_user_service_apis_priority::Migration),
+            Box::new(m20250305_000006_add_user_provider_keys_deleted_at::Migration),
+            Box::new(m20250315_000001_create_user_included_quota_usage_table::Migration),
+            Box::new(m20250315_000002_add_users_included_quota_columns::Migration),
+            Box::new(m20250320_000001_create_user_provider_blocks_table::Migration),
+            Box::new(m20250322_000001_add_proxy_tracing_client_geo::Migration),
+            Box::new(m20250325_000001_create_api_key_usage_counters_table::Migration),
+            Box::new(m20250325_000002_add_user_service_apis_allowed_paths::Migration),
+            Box::new(m20250326_000001_create_task_run_history_table::Migration),
+            Box::new(m20250328_000001_add_user_service_apis_max_cost_per_request::Migration),
+            Box::new(m20250328_000002_add_user_service_apis_previous_api_key::Migration),
+            Box::new(m20250328_000003_add_user_service_apis_stream_policy::Migration),
+            Box::new(m20250328_000004_add_user_service_apis_system_prompt::Migration),
+            Box::new(m20250401_000001_create_shadow_comparisons_table::Migration),
+            Box::new(m20250405_000001_add_users_credits_columns::Migration),
+            Box::new(m20250405_000002_add_user_provider_keys_tags::Migration),
+            Box::new(m20250405_000003_add_user_service_apis_key_tag::Migration),
+            Box::new(m20250405_000004_add_user_service_apis_upstream_error_mode::Migration),
+            Box::new(m20250405_000005_add_user_service_apis_model_routes::Migration),
+            Box::new(m20250405_000006_add_user_service_apis_sla_target_ms::Migration),
+            Box::new(m20250405_000007_add_proxy_tracing_sla_breached::Migration),
+            Box::new(m20250412_000001_fold_force_non_streaming_into_stream_policy::Migration),
         ]
     }
 }
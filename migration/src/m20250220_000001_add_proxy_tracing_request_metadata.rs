@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // proxy_tracing 表新增请求元数据字段
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProxyTracing::Table)
+                    .add_column(ColumnDef::new(ProxyTracing::RequestMetadata).json())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProxyTracing::Table)
+                    .drop_column(ProxyTracing::RequestMetadata)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProxyTracing {
+    Table,
+    RequestMetadata,
+}
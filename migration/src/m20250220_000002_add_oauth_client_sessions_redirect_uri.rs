@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // oauth_client_sessions 表新增回调地址字段
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OAuthClientSessions::Table)
+                    .add_column(ColumnDef::new(OAuthClientSessions::RedirectUri).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OAuthClientSessions::Table)
+                    .drop_column(OAuthClientSessions::RedirectUri)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OAuthClientSessions {
+    #[sea_orm(iden = "oauth_client_sessions")]
+    Table,
+    RedirectUri,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // user_service_apis 表新增请求体改写规则字段
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserServiceApis::Table)
+                    .add_column(ColumnDef::new(UserServiceApis::RequestTransformRules).json())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserServiceApis::Table)
+                    .drop_column(UserServiceApis::RequestTransformRules)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserServiceApis {
+    Table,
+    RequestTransformRules,
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // proxy_tracing 表新增请求/响应传输字节字段
+        // SQLite 的 ALTER TABLE 每次只能添加一列
+        for column in columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ProxyTracing::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [ProxyTracing::ResponseBytes, ProxyTracing::RequestBytes] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ProxyTracing::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn columns() -> Vec<ColumnDef> {
+    vec![
+        // 传输字节统计
+        ColumnDef::new(ProxyTracing::RequestBytes)
+            .big_integer()
+            .to_owned(),
+        ColumnDef::new(ProxyTracing::ResponseBytes)
+            .big_integer()
+            .to_owned(),
+    ]
+}
+
+#[derive(DeriveIden)]
+enum ProxyTracing {
+    Table,
+    RequestBytes,
+    ResponseBytes,
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // user_service_apis 表新增请求总时长上限字段
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserServiceApis::Table)
+                    .add_column(
+                        ColumnDef::new(UserServiceApis::MaxResponseDurationSeconds).integer(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserServiceApis::Table)
+                    .drop_column(UserServiceApis::MaxResponseDurationSeconds)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserServiceApis {
+    Table,
+    MaxResponseDurationSeconds,
+}
@@ -0,0 +1,113 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 spend_anomaly_flags 表 - 存储消费异常检测标记
+        manager
+            .create_table(
+                Table::create()
+                    .table(SpendAnomalyFlags::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SpendAnomalyFlags::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SpendAnomalyFlags::UserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SpendAnomalyFlags::WindowStart)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SpendAnomalyFlags::WindowEnd)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SpendAnomalyFlags::CurrentSpend)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SpendAnomalyFlags::BaselineHourlySpend)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SpendAnomalyFlags::Multiplier)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SpendAnomalyFlags::AcknowledgedAt).timestamp())
+                    .col(
+                        ColumnDef::new(SpendAnomalyFlags::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_spend_anomaly_flags_user_id")
+                            .from(SpendAnomalyFlags::Table, SpendAnomalyFlags::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_spend_anomaly_flags_user_created")
+                    .table(SpendAnomalyFlags::Table)
+                    .col(SpendAnomalyFlags::UserId)
+                    .col(SpendAnomalyFlags::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 删除spend_anomaly_flags表
+        manager
+            .drop_table(Table::drop().table(SpendAnomalyFlags::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SpendAnomalyFlags {
+    #[sea_orm(iden = "spend_anomaly_flags")]
+    Table,
+    Id,
+    UserId,
+    WindowStart,
+    WindowEnd,
+    CurrentSpend,
+    BaselineHourlySpend,
+    Multiplier,
+    AcknowledgedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    #[sea_orm(iden = "users")]
+    Table,
+    Id,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // user_service_apis 表新增强制非流式开关
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserServiceApis::Table)
+                    .add_column(
+                        ColumnDef::new(UserServiceApis::ForceNonStreaming)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserServiceApis::Table)
+                    .drop_column(UserServiceApis::ForceNonStreaming)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserServiceApis {
+    Table,
+    ForceNonStreaming,
+}
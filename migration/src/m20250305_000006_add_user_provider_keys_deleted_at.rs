@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // user_provider_keys 表新增软删除字段
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserProviderKeys::Table)
+                    .add_column(
+                        ColumnDef::new(UserProviderKeys::DeletedAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_provider_keys_deleted_at")
+                    .table(UserProviderKeys::Table)
+                    .col(UserProviderKeys::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_user_provider_keys_deleted_at")
+                    .table(UserProviderKeys::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserProviderKeys::Table)
+                    .drop_column(UserProviderKeys::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserProviderKeys {
+    Table,
+    DeletedAt,
+}
@@ -0,0 +1,116 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 user_included_quota_usage 表 - 按月累计已消耗的免费额度
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserIncludedQuotaUsage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserIncludedQuotaUsage::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserIncludedQuotaUsage::UserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserIncludedQuotaUsage::Period)
+                            .string_len(7)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserIncludedQuotaUsage::UsedTokens)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(UserIncludedQuotaUsage::UsedRequests)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(UserIncludedQuotaUsage::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(UserIncludedQuotaUsage::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_included_quota_usage_user_id")
+                            .from(
+                                UserIncludedQuotaUsage::Table,
+                                UserIncludedQuotaUsage::UserId,
+                            )
+                            .to(Users::Table, Users::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 每个用户每月一条记录
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_included_quota_usage_user_period")
+                    .table(UserIncludedQuotaUsage::Table)
+                    .col(UserIncludedQuotaUsage::UserId)
+                    .col(UserIncludedQuotaUsage::Period)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(UserIncludedQuotaUsage::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserIncludedQuotaUsage {
+    #[sea_orm(iden = "user_included_quota_usage")]
+    Table,
+    Id,
+    UserId,
+    Period,
+    UsedTokens,
+    UsedRequests,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    #[sea_orm(iden = "users")]
+    Table,
+    Id,
+}
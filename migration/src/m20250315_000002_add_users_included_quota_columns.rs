@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // users 表新增每月免费额度字段
+        // SQLite 的 ALTER TABLE 每次只能添加一列
+        for column in columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Users::IncludedRequestsPerMonth,
+            Users::IncludedTokensPerMonth,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn columns() -> Vec<ColumnDef> {
+    vec![
+        // 套餐内每月免费额度，为空表示没有免费额度
+        ColumnDef::new(Users::IncludedTokensPerMonth)
+            .big_integer()
+            .to_owned(),
+        ColumnDef::new(Users::IncludedRequestsPerMonth)
+            .big_integer()
+            .to_owned(),
+    ]
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    IncludedTokensPerMonth,
+    IncludedRequestsPerMonth,
+}
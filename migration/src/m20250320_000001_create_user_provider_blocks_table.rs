@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 user_provider_blocks 表 - 按用户禁用的服务商
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserProviderBlocks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserProviderBlocks::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserProviderBlocks::UserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserProviderBlocks::ProviderTypeId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserProviderBlocks::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_provider_blocks_user_id")
+                            .from(UserProviderBlocks::Table, UserProviderBlocks::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_provider_blocks_provider_type_id")
+                            .from(
+                                UserProviderBlocks::Table,
+                                UserProviderBlocks::ProviderTypeId,
+                            )
+                            .to(ProviderTypes::Table, ProviderTypes::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 同一用户对同一服务商只保留一条封禁记录
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_provider_blocks_user_provider")
+                    .table(UserProviderBlocks::Table)
+                    .col(UserProviderBlocks::UserId)
+                    .col(UserProviderBlocks::ProviderTypeId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserProviderBlocks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserProviderBlocks {
+    #[sea_orm(iden = "user_provider_blocks")]
+    Table,
+    Id,
+    UserId,
+    ProviderTypeId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    #[sea_orm(iden = "users")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum ProviderTypes {
+    #[sea_orm(iden = "provider_types")]
+    Table,
+    Id,
+}
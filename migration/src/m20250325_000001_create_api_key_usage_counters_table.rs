@@ -0,0 +1,119 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 api_key_usage_counters 表 - 按服务 API 按天汇总的用量计数（内存聚合后定期写入）
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiKeyUsageCounters::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ApiKeyUsageCounters::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeyUsageCounters::UserServiceApiId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeyUsageCounters::UsageDate)
+                            .date()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeyUsageCounters::RequestCount)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeyUsageCounters::TotalTokens)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeyUsageCounters::TotalCost)
+                            .double()
+                            .not_null()
+                            .default(0.0),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeyUsageCounters::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeyUsageCounters::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_api_key_usage_counters_user_service_api_id")
+                            .from(
+                                ApiKeyUsageCounters::Table,
+                                ApiKeyUsageCounters::UserServiceApiId,
+                            )
+                            .to(UserServiceApis::Table, UserServiceApis::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 每个服务 API 每天一条记录
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_api_key_usage_counters_api_date")
+                    .table(ApiKeyUsageCounters::Table)
+                    .col(ApiKeyUsageCounters::UserServiceApiId)
+                    .col(ApiKeyUsageCounters::UsageDate)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiKeyUsageCounters::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ApiKeyUsageCounters {
+    #[sea_orm(iden = "api_key_usage_counters")]
+    Table,
+    Id,
+    UserServiceApiId,
+    UsageDate,
+    RequestCount,
+    TotalTokens,
+    TotalCost,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum UserServiceApis {
+    #[sea_orm(iden = "user_service_apis")]
+    Table,
+    Id,
+}
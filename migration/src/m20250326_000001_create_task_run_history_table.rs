@@ -0,0 +1,96 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 task_run_history 表 - 调度器每次执行后台任务的记录
+        manager
+            .create_table(
+                Table::create()
+                    .table(TaskRunHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TaskRunHistory::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TaskRunHistory::TaskType)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TaskRunHistory::Status)
+                            .string_len(16)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TaskRunHistory::StartedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TaskRunHistory::FinishedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TaskRunHistory::DurationMs)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(TaskRunHistory::Error).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        // 按任务类型与时间范围查询
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_task_run_history_task_started")
+                    .table(TaskRunHistory::Table)
+                    .col(TaskRunHistory::TaskType)
+                    .col(TaskRunHistory::StartedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_task_run_history_started")
+                    .table(TaskRunHistory::Table)
+                    .col(TaskRunHistory::StartedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TaskRunHistory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaskRunHistory {
+    #[sea_orm(iden = "task_run_history")]
+    Table,
+    Id,
+    TaskType,
+    Status,
+    StartedAt,
+    FinishedAt,
+    DurationMs,
+    Error,
+}
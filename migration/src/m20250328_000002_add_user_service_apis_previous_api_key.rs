@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // user_service_apis 表新增密钥轮换字段
+        // SQLite 的 ALTER TABLE 每次只能添加一列
+        for column in columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(UserServiceApis::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_service_apis_previous_api_key")
+                    .table(UserServiceApis::Table)
+                    .col(UserServiceApis::PreviousApiKey)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_user_service_apis_previous_api_key")
+                    .table(UserServiceApis::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            UserServiceApis::PreviousApiKeyExpiresAt,
+            UserServiceApis::PreviousApiKey,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(UserServiceApis::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn columns() -> Vec<ColumnDef> {
+    vec![
+        // 轮换前的 API Key，宽限期内仍可用于认证
+        ColumnDef::new(UserServiceApis::PreviousApiKey)
+            .string_len(64)
+            .to_owned(),
+        ColumnDef::new(UserServiceApis::PreviousApiKeyExpiresAt)
+            .timestamp()
+            .to_owned(),
+    ]
+}
+
+#[derive(DeriveIden)]
+enum UserServiceApis {
+    Table,
+    PreviousApiKey,
+    PreviousApiKeyExpiresAt,
+}
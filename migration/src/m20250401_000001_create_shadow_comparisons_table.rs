@@ -0,0 +1,104 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建 shadow_comparisons 表 - 影子路由中主请求与影子请求的逐请求对比
+        manager
+            .create_table(
+                Table::create()
+                    .table(ShadowComparisons::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ShadowComparisons::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ShadowComparisons::RequestId)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ShadowComparisons::UserServiceApiId).integer())
+                    .col(ColumnDef::new(ShadowComparisons::Model).string_len(128))
+                    .col(
+                        ColumnDef::new(ShadowComparisons::PrimaryProviderTypeId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ShadowComparisons::ShadowProviderTypeId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ShadowComparisons::PrimaryStatusCode)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ShadowComparisons::ShadowStatusCode).integer())
+                    .col(ColumnDef::new(ShadowComparisons::PrimaryTokensTotal).integer())
+                    .col(ColumnDef::new(ShadowComparisons::ShadowTokensTotal).integer())
+                    .col(
+                        ColumnDef::new(ShadowComparisons::PrimaryDurationMs)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(ShadowComparisons::ShadowDurationMs).big_integer())
+                    .col(ColumnDef::new(ShadowComparisons::ShadowError).text())
+                    .col(
+                        ColumnDef::new(ShadowComparisons::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 按时间范围查询
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_shadow_comparisons_created_at")
+                    .table(ShadowComparisons::Table)
+                    .col(ShadowComparisons::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ShadowComparisons::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ShadowComparisons {
+    #[sea_orm(iden = "shadow_comparisons")]
+    Table,
+    Id,
+    RequestId,
+    UserServiceApiId,
+    Model,
+    PrimaryProviderTypeId,
+    ShadowProviderTypeId,
+    PrimaryStatusCode,
+    ShadowStatusCode,
+    PrimaryTokensTotal,
+    ShadowTokensTotal,
+    PrimaryDurationMs,
+    ShadowDurationMs,
+    ShadowError,
+    CreatedAt,
+}
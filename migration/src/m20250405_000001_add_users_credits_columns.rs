@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // users 表新增积分余额字段
+        // SQLite 的 ALTER TABLE 每次只能添加一列
+        for column in columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Users::CreditsReserved, Users::CreditsBalance] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn columns() -> Vec<ColumnDef> {
+    vec![
+        // 积分余额，为空表示不启用积分额度
+        ColumnDef::new(Users::CreditsBalance)
+            .big_integer()
+            .to_owned(),
+        ColumnDef::new(Users::CreditsReserved)
+            .big_integer()
+            .not_null()
+            .default(0)
+            .to_owned(),
+    ]
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    CreditsBalance,
+    CreditsReserved,
+}
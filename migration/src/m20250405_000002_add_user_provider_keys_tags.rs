@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // user_provider_keys 表新增密钥标签字段
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserProviderKeys::Table)
+                    .add_column(ColumnDef::new(UserProviderKeys::Tags).json().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserProviderKeys::Table)
+                    .drop_column(UserProviderKeys::Tags)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserProviderKeys {
+    Table,
+    Tags,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // proxy_tracing 表新增 SLA 超标标记字段
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProxyTracing::Table)
+                    .add_column(
+                        ColumnDef::new(ProxyTracing::SlaBreached)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProxyTracing::Table)
+                    .drop_column(ProxyTracing::SlaBreached)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProxyTracing {
+    Table,
+    SlaBreached,
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // force_non_streaming 与 stream_policy = "force-off" 含义相同，合并到 stream_policy 后删除旧列
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(UserServiceApis::Table)
+                    .value(UserServiceApis::StreamPolicy, "force-off")
+                    .and_where(Expr::col(UserServiceApis::ForceNonStreaming).eq(true))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserServiceApis::Table)
+                    .drop_column(UserServiceApis::ForceNonStreaming)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserServiceApis::Table)
+                    .add_column(
+                        ColumnDef::new(UserServiceApis::ForceNonStreaming)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(UserServiceApis::Table)
+                    .value(UserServiceApis::ForceNonStreaming, true)
+                    .and_where(Expr::col(UserServiceApis::StreamPolicy).eq("force-off"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserServiceApis {
+    Table,
+    ForceNonStreaming,
+    StreamPolicy,
+}
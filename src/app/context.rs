@@ -21,9 +21,9 @@ impl AppContext {
         config: Arc<AppConfig>,
         database: Arc<DatabaseConnection>,
     ) -> Result<Arc<Self>> {
-        let resources = AppResources::build(config, database)?;
+        let resources = AppResources::build(config.clone(), database)?;
         let services = AppServices::initialize(&resources)?;
-        let tasks = AppTasks::initialize(&services).await?;
+        let tasks = AppTasks::initialize(&services, &config).await?;
 
         Ok(Arc::new(Self {
             resources,
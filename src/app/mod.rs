@@ -2,11 +2,15 @@ pub mod context;
 pub mod resources;
 pub mod service_registry;
 pub mod shared_services;
+pub mod task_heartbeat;
+pub mod task_history;
 pub mod task_scheduler;
 pub mod tasks;
 
 pub use resources::AppResources;
 pub use service_registry::AppServices;
 pub use shared_services::SharedServices;
-pub use task_scheduler::{ScheduledTask, TaskScheduler};
+pub use task_heartbeat::{TaskHeartbeat, TaskHeartbeatRegistry};
+pub use task_history::{TaskRunHistory, TaskRunHistoryQuery};
+pub use task_scheduler::{ScheduledTask, TaskRunRecorder, TaskScheduler};
 pub use tasks::{AppTasks, TaskType};
@@ -1,4 +1,4 @@
-use crate::cache::CacheManager;
+use crate::cache::{CacheInvalidator, CacheManager, InvalidationTransport};
 use crate::config::AppConfig;
 use crate::error::Result;
 use sea_orm::DatabaseConnection;
@@ -9,16 +9,22 @@ pub struct AppResources {
     config: Arc<AppConfig>,
     database: Arc<DatabaseConnection>,
     cache: Arc<CacheManager>,
+    cache_invalidator: Arc<CacheInvalidator>,
 }
 
 impl AppResources {
     /// 根据配置与数据库连接构建资源层
     pub fn build(config: Arc<AppConfig>, database: Arc<DatabaseConnection>) -> Result<Arc<Self>> {
         let cache = Arc::new(CacheManager::new(&config.cache)?);
+        let cache_invalidator = Arc::new(CacheInvalidator::new(
+            cache.clone(),
+            InvalidationTransport::from_config(&config.cache)?,
+        ));
         Ok(Arc::new(Self {
             config,
             database,
             cache,
+            cache_invalidator,
         }))
     }
 
@@ -36,4 +42,9 @@ impl AppResources {
     pub fn cache(&self) -> Arc<CacheManager> {
         Arc::clone(&self.cache)
     }
+
+    #[must_use]
+    pub fn cache_invalidator(&self) -> Arc<CacheInvalidator> {
+        Arc::clone(&self.cache_invalidator)
+    }
 }
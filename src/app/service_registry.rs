@@ -6,8 +6,11 @@ use crate::auth::{
     api_key_usage_limit_service::ApiKeyUsageLimitService, jwt::JwtManager,
     service::ApiKeyAuthenticationService,
 };
+use crate::cache::{CacheInvalidator, InvalidationEvent};
 use crate::error::{Context, Result};
-use crate::key_pool::{ApiKeyHealthService, ApiKeySchedulerService};
+use crate::key_pool::{ApiKeyDailyQuota, ApiKeyHealthService, ApiKeySchedulerService};
+use crate::proxy::maintenance::MaintenanceService;
+use crate::proxy::upstream_circuit::UpstreamCircuitBreaker;
 use crate::trace::ApiKeyTraceService;
 use sea_orm::DatabaseConnection;
 use std::sync::Arc;
@@ -32,6 +35,10 @@ pub struct AppServices {
     scheduler: Arc<ApiKeySchedulerService>,
     refresh: Arc<ApiKeyOAuthRefreshService>,
     health: Arc<ApiKeyHealthService>,
+    maintenance: Arc<MaintenanceService>,
+    cache_invalidator: Arc<CacheInvalidator>,
+    /// 上游熔断器：代理请求与服务商健康检查共用
+    circuit_breaker: Arc<UpstreamCircuitBreaker>,
 }
 
 impl AppServices {
@@ -55,16 +62,41 @@ impl AppServices {
             database.clone(),
         ));
 
-        let usage_limit = Arc::new(ApiKeyUsageLimitService::new(cache, database.clone()));
+        let usage_limit = Arc::new(
+            ApiKeyUsageLimitService::new(cache.clone(), database.clone())
+                .with_config(config.rate_limit.clone()),
+        );
 
         let trace = Arc::new(ApiKeyTraceService::new_immediate(database.clone()));
 
-        let health = Arc::new(ApiKeyHealthService::new(database.clone()));
-
-        let scheduler = Arc::new(ApiKeySchedulerService::new(
-            database.clone(),
-            health.clone(),
-        ));
+        let health = Arc::new(
+            ApiKeyHealthService::new(database.clone())
+                .with_cooldown(config.rate_limit.cooldown.clone()),
+        );
+
+        let scheduler = Arc::new(
+            ApiKeySchedulerService::new(database.clone(), health.clone())
+                .with_daily_quota(ApiKeyDailyQuota::new(
+                    cache.clone(),
+                    config.rate_limit.daily_reset_tz(),
+                ))
+                .with_cost_aware(config.cost_aware.clone())
+                .with_key_selection(config.key_selection.clone()),
+        );
+
+        let maintenance = Arc::new(MaintenanceService::new(cache, config.maintenance.clone()));
+        let circuit_breaker = Arc::new(UpstreamCircuitBreaker::new(config.circuit_breaker.clone()));
+
+        // 服务 API 变更（本实例或其他实例）时丢弃本地候选集缓存
+        let cache_invalidator = resources.cache_invalidator();
+        let candidate_cache = Arc::downgrade(&scheduler);
+        cache_invalidator.add_listener(Box::new(move |event| {
+            if let InvalidationEvent::ServiceApi { service_api_id, .. } = event
+                && let Some(scheduler) = candidate_cache.upgrade()
+            {
+                scheduler.invalidate_service_api(*service_api_id);
+            }
+        }));
 
         let oauth = Arc::new(ApiKeyOauthService::new(database.clone()));
         let oauth_state = oauth.api_key_oauth_state_service();
@@ -80,6 +112,9 @@ impl AppServices {
             scheduler,
             refresh,
             health,
+            maintenance,
+            cache_invalidator,
+            circuit_breaker,
         }))
     }
 
@@ -127,4 +162,19 @@ impl AppServices {
     pub fn api_key_health_service(&self) -> Arc<ApiKeyHealthService> {
         Arc::clone(&self.health)
     }
+
+    #[must_use]
+    pub fn maintenance_service(&self) -> Arc<MaintenanceService> {
+        Arc::clone(&self.maintenance)
+    }
+
+    #[must_use]
+    pub fn cache_invalidator(&self) -> Arc<CacheInvalidator> {
+        Arc::clone(&self.cache_invalidator)
+    }
+
+    #[must_use]
+    pub fn upstream_circuit_breaker(&self) -> Arc<UpstreamCircuitBreaker> {
+        Arc::clone(&self.circuit_breaker)
+    }
 }
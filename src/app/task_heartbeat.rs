@@ -0,0 +1,176 @@
+//! # 后台任务心跳注册表
+//!
+//! 记录每个调度任务最近一次执行的时间、耗时与错误，供管理端 `GET /api/system/tasks` 查询。
+
+use crate::app::tasks::TaskType;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// 单个任务的心跳记录
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHeartbeat {
+    pub task: TaskType,
+    /// 最近一次开始执行的时间
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// 最近一次执行耗时（毫秒）
+    pub last_duration_ms: Option<u64>,
+    /// 最近一次成功完成的时间
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// 最近一次执行的错误；成功执行后清空
+    pub last_error: Option<String>,
+    pub run_count: u64,
+    pub error_count: u64,
+}
+
+impl TaskHeartbeat {
+    const fn new(task: TaskType) -> Self {
+        Self {
+            task,
+            last_run_at: None,
+            last_duration_ms: None,
+            last_success_at: None,
+            last_error: None,
+            run_count: 0,
+            error_count: 0,
+        }
+    }
+}
+
+/// 后台任务心跳注册表
+#[derive(Default)]
+pub struct TaskHeartbeatRegistry {
+    heartbeats: DashMap<TaskType, TaskHeartbeat>,
+}
+
+impl TaskHeartbeatRegistry {
+    /// 创建空注册表
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记任务，使尚未执行的任务也出现在查询结果中
+    pub fn register(&self, task: TaskType) {
+        self.heartbeats
+            .entry(task)
+            .or_insert_with(|| TaskHeartbeat::new(task));
+    }
+
+    /// 执行任务并记录心跳
+    pub async fn track<F>(&self, task: TaskType, future: F) -> Result<()>
+    where
+        F: Future<Output = Result<()>>,
+    {
+        let started_at = Utc::now();
+        let timer = Instant::now();
+        let result = future.await;
+        let error = result.as_ref().err().map(ToString::to_string);
+        self.record(task, started_at, timer.elapsed(), error);
+        result
+    }
+
+    /// 记录一次执行结果；周期性任务也可在每轮执行后直接调用
+    pub fn record(
+        &self,
+        task: TaskType,
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        error: Option<String>,
+    ) {
+        let mut heartbeat = self
+            .heartbeats
+            .entry(task)
+            .or_insert_with(|| TaskHeartbeat::new(task));
+        heartbeat.last_run_at = Some(started_at);
+        heartbeat.last_duration_ms = Some(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX));
+        heartbeat.run_count += 1;
+        if error.is_some() {
+            heartbeat.error_count += 1;
+        } else {
+            heartbeat.last_success_at = Some(Utc::now());
+        }
+        heartbeat.last_error = error;
+    }
+
+    /// 获取指定任务的心跳
+    #[must_use]
+    pub fn get(&self, task: TaskType) -> Option<TaskHeartbeat> {
+        self.heartbeats.get(&task).map(|entry| entry.clone())
+    }
+
+    /// 获取所有任务的心跳快照（按任务名排序）
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<TaskHeartbeat> {
+        let mut heartbeats: Vec<_> = self
+            .heartbeats
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        heartbeats.sort_by_key(|heartbeat| heartbeat.task.as_str());
+        heartbeats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::task_scheduler::{ScheduledTask, TaskScheduler};
+    use crate::error::ProxyError;
+
+    #[tokio::test]
+    async fn heartbeat_updated_after_task_runs() {
+        let scheduler = TaskScheduler::new();
+        scheduler
+            .register(
+                ScheduledTask::builder(TaskType::ModelPricingRefresh)
+                    .on_start(|| async { Ok(()) })
+                    .build(),
+            )
+            .await;
+
+        let before = scheduler
+            .heartbeats()
+            .get(TaskType::ModelPricingRefresh)
+            .unwrap();
+        assert!(before.last_run_at.is_none());
+
+        scheduler.start_all().await.unwrap();
+
+        let after = scheduler
+            .heartbeats()
+            .get(TaskType::ModelPricingRefresh)
+            .unwrap();
+        assert!(after.last_run_at.is_some());
+        assert!(after.last_success_at.is_some());
+        assert!(after.last_duration_ms.is_some());
+        assert!(after.last_error.is_none());
+        assert_eq!(after.run_count, 1);
+    }
+
+    #[tokio::test]
+    async fn errored_task_records_error() {
+        let scheduler = TaskScheduler::new();
+        scheduler
+            .register(
+                ScheduledTask::builder(TaskType::ApiKeyRateLimitCache)
+                    .on_start(|| async { Err(ProxyError::from("warmup failed")) })
+                    .build(),
+            )
+            .await;
+
+        assert!(scheduler.start_all().await.is_err());
+
+        let heartbeat = scheduler
+            .heartbeats()
+            .get(TaskType::ApiKeyRateLimitCache)
+            .unwrap();
+        assert!(heartbeat.last_run_at.is_some());
+        assert!(heartbeat.last_success_at.is_none());
+        assert_eq!(heartbeat.error_count, 1);
+        assert!(heartbeat.last_error.unwrap().contains("warmup failed"));
+    }
+}
@@ -0,0 +1,104 @@
+//! # 后台任务执行历史
+//!
+//! 心跳只保留最近一次执行结果；执行历史把调度器的每次执行写入 `task_run_history`，
+//! 供管理端 `GET /api/system/tasks/history` 按任务类型与时间范围查询。
+
+use crate::app::tasks::TaskType;
+use crate::error::{Context, Result};
+use chrono::{DateTime, Utc};
+use entity::task_run_history;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 执行成功
+pub const TASK_RUN_SUCCESS: &str = "success";
+/// 执行失败
+pub const TASK_RUN_FAILED: &str = "failed";
+
+const DEFAULT_HISTORY_LIMIT: u64 = 50;
+const MAX_HISTORY_LIMIT: u64 = 500;
+
+/// 执行历史查询条件
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskRunHistoryQuery {
+    /// 任务标识，如 `model_pricing_refresh`
+    pub task_type: Option<String>,
+    /// 只返回该时间之后开始的执行
+    pub start_time: Option<DateTime<Utc>>,
+    /// 只返回该时间之前开始的执行
+    pub end_time: Option<DateTime<Utc>>,
+    pub limit: Option<u64>,
+}
+
+/// 后台任务执行历史
+#[derive(Clone)]
+pub struct TaskRunHistory {
+    db: Arc<DatabaseConnection>,
+}
+
+impl TaskRunHistory {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// 写入一次执行记录
+    pub async fn record(
+        &self,
+        task: TaskType,
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        error: Option<&str>,
+    ) -> Result<task_run_history::Model> {
+        let duration_ms = i64::try_from(duration.as_millis()).unwrap_or(i64::MAX);
+        let finished_at = started_at + chrono::Duration::milliseconds(duration_ms);
+        task_run_history::ActiveModel {
+            task_type: Set(task.as_str().to_string()),
+            status: Set(if error.is_some() {
+                TASK_RUN_FAILED
+            } else {
+                TASK_RUN_SUCCESS
+            }
+            .to_string()),
+            started_at: Set(started_at.naive_utc()),
+            finished_at: Set(finished_at.naive_utc()),
+            duration_ms: Set(duration_ms),
+            error: Set(error.map(str::to_string)),
+            ..Default::default()
+        }
+        .insert(self.db.as_ref())
+        .await
+        .context("Failed to record task run history")
+    }
+
+    /// 按条件查询执行记录，按开始时间倒序
+    pub async fn list(&self, query: &TaskRunHistoryQuery) -> Result<Vec<task_run_history::Model>> {
+        let mut select = task_run_history::Entity::find();
+        if let Some(task_type) = query.task_type.as_deref() {
+            select = select.filter(task_run_history::Column::TaskType.eq(task_type));
+        }
+        if let Some(start_time) = query.start_time {
+            select = select.filter(task_run_history::Column::StartedAt.gte(start_time.naive_utc()));
+        }
+        if let Some(end_time) = query.end_time {
+            select = select.filter(task_run_history::Column::StartedAt.lte(end_time.naive_utc()));
+        }
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_HISTORY_LIMIT)
+            .clamp(1, MAX_HISTORY_LIMIT);
+
+        select
+            .order_by_desc(task_run_history::Column::StartedAt)
+            .order_by_desc(task_run_history::Column::Id)
+            .limit(limit)
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to load task run history")
+    }
+}
@@ -1,14 +1,21 @@
 //! # 后台任务调度器
 //!
 //! 提供统一的任务注册、启动与停止能力，避免在各个模块中分散管理后台任务。
+//!
+//! 一次性任务的启动即一次执行；周期性任务的启动只是拉起后台循环，
+//! 由循环通过 [`TaskRunRecorder`] 在每轮执行后记录心跳与执行历史。
 
+use crate::app::task_heartbeat::TaskHeartbeatRegistry;
+use crate::app::task_history::TaskRunHistory;
 use crate::app::tasks::TaskType;
 use crate::error::Result;
 use crate::logging::{LogComponent, LogStage};
 use crate::{lerror, linfo, lwarn};
+use chrono::{DateTime, Utc};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 type TaskFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
@@ -20,6 +27,7 @@ pub struct ScheduledTask {
     task_type: TaskType,
     start: TaskAction,
     stop: Option<TaskAction>,
+    periodic: bool,
 }
 
 impl ScheduledTask {
@@ -30,6 +38,7 @@ impl ScheduledTask {
             task_type,
             start: None,
             stop: None,
+            periodic: false,
         }
     }
 
@@ -77,6 +86,7 @@ pub struct ScheduledTaskBuilder {
     task_type: TaskType,
     start: Option<TaskAction>,
     stop: Option<TaskAction>,
+    periodic: bool,
 }
 
 impl ScheduledTaskBuilder {
@@ -102,6 +112,13 @@ impl ScheduledTaskBuilder {
         self
     }
 
+    /// 标记为周期性任务：每轮执行自行记录，启动只在失败时记录
+    #[must_use]
+    pub const fn periodic(mut self) -> Self {
+        self.periodic = true;
+        self
+    }
+
     /// 构建最终任务
     #[must_use]
     pub fn build(self) -> ScheduledTask {
@@ -110,6 +127,7 @@ impl ScheduledTaskBuilder {
             task_type: self.task_type,
             start,
             stop: self.stop,
+            periodic: self.periodic,
         }
     }
 }
@@ -118,6 +136,8 @@ impl ScheduledTaskBuilder {
 #[derive(Default)]
 pub struct TaskScheduler {
     tasks: RwLock<Vec<ScheduledTask>>,
+    heartbeats: Arc<TaskHeartbeatRegistry>,
+    history: Option<TaskRunHistory>,
 }
 
 impl TaskScheduler {
@@ -126,17 +146,46 @@ impl TaskScheduler {
     pub fn new() -> Self {
         Self {
             tasks: RwLock::new(Vec::new()),
+            heartbeats: Arc::new(TaskHeartbeatRegistry::new()),
+            history: None,
+        }
+    }
+
+    /// 将每次任务执行写入执行历史表
+    #[must_use]
+    pub fn with_run_history(mut self, history: TaskRunHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// 获取任务心跳注册表
+    #[must_use]
+    pub fn heartbeats(&self) -> Arc<TaskHeartbeatRegistry> {
+        Arc::clone(&self.heartbeats)
+    }
+
+    /// 获取指定任务的执行记录器，供周期性任务在每轮执行后记录
+    #[must_use]
+    pub fn recorder(&self, task: TaskType) -> TaskRunRecorder {
+        TaskRunRecorder {
+            task,
+            heartbeats: Arc::clone(&self.heartbeats),
+            history: self.history.clone(),
         }
     }
 
     /// 注册任务
     pub async fn register(&self, task: ScheduledTask) {
+        self.heartbeats.register(task.task_type);
         let mut guard = self.tasks.write().await;
         guard.push(task);
     }
 
     /// 批量注册任务
     pub async fn register_many(&self, tasks: Vec<ScheduledTask>) {
+        for task in &tasks {
+            self.heartbeats.register(task.task_type);
+        }
         let mut guard = self.tasks.write().await;
         guard.extend(tasks);
     }
@@ -145,7 +194,7 @@ impl TaskScheduler {
     pub async fn start_all(&self) -> Result<()> {
         let tasks = { self.tasks.read().await.clone() };
         for task in tasks {
-            if let Err(err) = task.start().await {
+            if let Err(err) = self.run(&task).await {
                 lerror!(
                     "system",
                     LogStage::BackgroundTask,
@@ -161,6 +210,24 @@ impl TaskScheduler {
         Ok(())
     }
 
+    /// 执行任务启动逻辑，记录心跳与执行历史
+    async fn run(&self, task: &ScheduledTask) -> Result<()> {
+        let recorder = self.recorder(task.task_type);
+        if !task.periodic {
+            return recorder.track(task.start()).await;
+        }
+
+        let started_at = Utc::now();
+        let timer = Instant::now();
+        let result = task.start().await;
+        if let Err(err) = &result {
+            recorder
+                .record(started_at, timer.elapsed(), Some(err.to_string()))
+                .await;
+        }
+        result
+    }
+
     /// 停止所有任务（逆序执行）
     pub async fn shutdown(&self) -> Result<()> {
         let tasks = { self.tasks.read().await.clone() };
@@ -181,3 +248,64 @@ impl TaskScheduler {
         Ok(())
     }
 }
+
+/// 单个任务的执行记录器：更新心跳并写入执行历史
+#[derive(Clone)]
+pub struct TaskRunRecorder {
+    task: TaskType,
+    heartbeats: Arc<TaskHeartbeatRegistry>,
+    history: Option<TaskRunHistory>,
+}
+
+impl TaskRunRecorder {
+    /// 执行一轮任务并记录实际耗时与结果
+    pub async fn track<T, F>(&self, future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let started_at = Utc::now();
+        let timer = Instant::now();
+        let result = future.await;
+        let error = result.as_ref().err().map(ToString::to_string);
+        self.record(started_at, timer.elapsed(), error).await;
+        result
+    }
+
+    /// 记录一轮执行结果
+    pub async fn record(
+        &self,
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        error: Option<String>,
+    ) {
+        if let Some(history) = &self.history
+            && let Err(err) = history
+                .record(self.task, started_at, duration, error.as_deref())
+                .await
+        {
+            // 历史写入失败不影响任务本身
+            lwarn!(
+                "system",
+                LogStage::BackgroundTask,
+                LogComponent::ServerSetup,
+                "task_history_record_failed",
+                "Failed to record background task run history",
+                task = ?self.task,
+                error = %err
+            );
+        }
+        self.heartbeats
+            .record(self.task, started_at, duration, error);
+    }
+}
+
+/// 有记录器时记录本轮执行，否则直接执行
+pub async fn track_run<T, F>(recorder: Option<&TaskRunRecorder>, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    match recorder {
+        Some(recorder) => recorder.track(future).await,
+        None => future.await,
+    }
+}
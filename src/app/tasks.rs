@@ -1,26 +1,65 @@
 use crate::app::service_registry::AppServices;
+use crate::app::task_history::TaskRunHistory;
 use crate::app::task_scheduler::{ScheduledTask, TaskScheduler};
 use crate::auth::api_key_oauth_refresh_service::ApiKeyOAuthRefreshService;
 use crate::auth::api_key_oauth_state_service::ApiKeyOAuthStateService;
 use crate::auth::api_key_oauth_token_refresh_task::ApiKeyOAuthTokenRefreshTask;
+use crate::config::AppConfig;
 use crate::database::ModelPricingRefreshTask;
 use crate::error::Result;
-use crate::key_pool::ApiKeyRateLimitResetTask;
+use crate::key_pool::{
+    ApiKeyRateLimitResetTask, ProviderHealthCheckTask, UpstreamReachabilityProbe,
+};
+use crate::trace::{SpendAnomalyDetectionTask, TraceExporter, TraceWriter, UsageCounter};
+use serde::Serialize;
 use std::any::Any;
 use std::collections::HashMap;
 use std::sync::Arc;
 
 /// 后台任务类型枚举
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TaskType {
     /// 速率限制缓存预热
     ApiKeyRateLimitCache,
     /// 限流状态自动恢复
     ApiKeyRateLimitReset,
     /// OAuth Token 刷新
+    #[serde(rename = "api_key_oauth_token_refresh")]
     ApiKeyOAuthTokenRefresh,
     /// 模型定价每日刷新
     ModelPricingRefresh,
+    /// 服务商定时健康检查
+    ProviderHealthCheck,
+    /// 用户消费异常检测
+    SpendAnomalyDetection,
+    /// 追踪记录异步写入
+    TraceWriter,
+    /// 追踪记录外部输出
+    TraceExporter,
+    /// 服务 API 用量计数定期写入
+    UsageCounter,
+    /// 跨实例缓存失效订阅
+    CacheInvalidation,
+}
+
+impl TaskType {
+    /// 任务标识（与序列化名称一致）
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ApiKeyRateLimitCache => "api_key_rate_limit_cache",
+            Self::ApiKeyRateLimitReset => "api_key_rate_limit_reset",
+            Self::ApiKeyOAuthTokenRefresh => "api_key_oauth_token_refresh",
+            Self::ModelPricingRefresh => "model_pricing_refresh",
+            Self::ProviderHealthCheck => "provider_health_check",
+            Self::SpendAnomalyDetection => "spend_anomaly_detection",
+            Self::TraceWriter => "trace_writer",
+            Self::TraceExporter => "trace_exporter",
+            Self::UsageCounter => "usage_counter",
+            Self::CacheInvalidation => "cache_invalidation",
+        }
+    }
 }
 
 /// 后台任务集合：调度器及任务实例统一管理
@@ -37,8 +76,10 @@ pub struct AppTasks {
 
 impl AppTasks {
     /// 初始化调度器并注册所有后台任务
-    pub async fn initialize(services: &Arc<AppServices>) -> Result<Arc<Self>> {
-        let scheduler = Arc::new(TaskScheduler::new());
+    pub async fn initialize(services: &Arc<AppServices>, config: &AppConfig) -> Result<Arc<Self>> {
+        let database = services.database();
+        let scheduler =
+            Arc::new(TaskScheduler::new().with_run_history(TaskRunHistory::new(database.clone())));
         let mut task_instances: HashMap<TaskType, Arc<dyn Any + Send + Sync>> = HashMap::new();
 
         // 从 services 获取核心服务
@@ -46,15 +87,43 @@ impl AppTasks {
         let api_refresh: Arc<ApiKeyOAuthRefreshService> = services.api_key_refresh_service();
         let api_oauth_state: Arc<ApiKeyOAuthStateService> = services.api_key_oauth_state_service();
         let api_key_health_service = services.api_key_health_service();
-        let database = services.database();
 
         // 在 AppTasks 中创建任务实例（Task 依赖 Service）
-        let refresh = Arc::new(ApiKeyOAuthTokenRefreshTask::new(
-            api_refresh.clone(),
-            api_oauth_state.clone(),
+        // 周期性任务每轮执行后通过调度器提供的记录器更新心跳与执行历史
+        let refresh = Arc::new(
+            ApiKeyOAuthTokenRefreshTask::new(api_refresh.clone(), api_oauth_state.clone())
+                .with_run_recorder(scheduler.recorder(TaskType::ApiKeyOAuthTokenRefresh)),
+        );
+        let reset = Arc::new(
+            ApiKeyRateLimitResetTask::new(&api_key_health_service)
+                .with_run_recorder(scheduler.recorder(TaskType::ApiKeyRateLimitReset)),
+        );
+        let pricing_refresh = Arc::new(
+            ModelPricingRefreshTask::new(database.clone())
+                .with_run_recorder(scheduler.recorder(TaskType::ModelPricingRefresh)),
+        );
+        let spend_anomaly_detection = Arc::new(
+            SpendAnomalyDetectionTask::new(database.clone(), config.spend_anomaly.clone())
+                .with_run_recorder(scheduler.recorder(TaskType::SpendAnomalyDetection)),
+        );
+        let trace_writer = Arc::new(TraceWriter::new(
+            services.api_key_trace_service().tracer(),
+            config.trace_writer.clone(),
         ));
-        let reset = Arc::new(ApiKeyRateLimitResetTask::new(&api_key_health_service));
-        let pricing_refresh = Arc::new(ModelPricingRefreshTask::new(database));
+        let usage_counter = Arc::new(
+            UsageCounter::new(database.clone(), config.usage_counter.clone())
+                .with_run_recorder(scheduler.recorder(TaskType::UsageCounter)),
+        );
+        let cache_invalidator = services.cache_invalidator();
+        let provider_health_check = Arc::new(
+            ProviderHealthCheckTask::new(
+                database,
+                Arc::new(UpstreamReachabilityProbe::new(reqwest::Client::new())),
+                config.health_check.clone(),
+            )
+            .with_circuit_breaker(services.upstream_circuit_breaker())
+            .with_run_recorder(scheduler.recorder(TaskType::ProviderHealthCheck)),
+        );
 
         // 将恢复任务注册到健康服务，内部通过弱引用避免循环依赖
         api_key_health_service.set_reset_task(&reset).await;
@@ -63,6 +132,14 @@ impl AppTasks {
         task_instances.insert(TaskType::ApiKeyOAuthTokenRefresh, refresh.clone());
         task_instances.insert(TaskType::ApiKeyRateLimitReset, reset.clone());
         task_instances.insert(TaskType::ModelPricingRefresh, pricing_refresh.clone());
+        task_instances.insert(TaskType::ProviderHealthCheck, provider_health_check.clone());
+        task_instances.insert(
+            TaskType::SpendAnomalyDetection,
+            spend_anomaly_detection.clone(),
+        );
+        task_instances.insert(TaskType::TraceWriter, trace_writer.clone());
+        task_instances.insert(TaskType::UsageCounter, usage_counter.clone());
+        task_instances.insert(TaskType::CacheInvalidation, cache_invalidator.clone());
 
         // 注册任务到调度器
         scheduler
@@ -74,6 +151,7 @@ impl AppTasks {
                     })
                     .build(),
                 ScheduledTask::builder(TaskType::ApiKeyRateLimitReset)
+                    .periodic()
                     .on_start({
                         let task = reset.clone();
                         move || {
@@ -93,6 +171,7 @@ impl AppTasks {
                     })
                     .build(),
                 ScheduledTask::builder(TaskType::ApiKeyOAuthTokenRefresh)
+                    .periodic()
                     .on_start({
                         let task = refresh.clone();
                         move || {
@@ -121,9 +200,118 @@ impl AppTasks {
                         }
                     })
                     .build(),
+                ScheduledTask::builder(TaskType::ProviderHealthCheck)
+                    .periodic()
+                    .on_start({
+                        let task = provider_health_check.clone();
+                        move || {
+                            let task = task.clone();
+                            async move { task.start().await }
+                        }
+                    })
+                    .on_stop(move || {
+                        let task = provider_health_check.clone();
+                        async move {
+                            task.stop().await;
+                            Ok(())
+                        }
+                    })
+                    .build(),
+                ScheduledTask::builder(TaskType::SpendAnomalyDetection)
+                    .periodic()
+                    .on_start({
+                        let task = spend_anomaly_detection.clone();
+                        move || {
+                            let task = task.clone();
+                            async move { task.start().await }
+                        }
+                    })
+                    .on_stop(move || {
+                        let task = spend_anomaly_detection.clone();
+                        async move {
+                            task.stop().await;
+                            Ok(())
+                        }
+                    })
+                    .build(),
+                ScheduledTask::builder(TaskType::TraceWriter)
+                    .on_start({
+                        let task = trace_writer.clone();
+                        move || {
+                            let task = task.clone();
+                            async move { task.start().await }
+                        }
+                    })
+                    .on_stop(move || {
+                        let task = trace_writer.clone();
+                        async move {
+                            task.stop().await;
+                            Ok(())
+                        }
+                    })
+                    .build(),
+                ScheduledTask::builder(TaskType::UsageCounter)
+                    .periodic()
+                    .on_start({
+                        let task = usage_counter.clone();
+                        move || {
+                            let task = task.clone();
+                            async move { task.start().await }
+                        }
+                    })
+                    .on_stop(move || {
+                        let task = usage_counter.clone();
+                        async move {
+                            task.stop().await;
+                            Ok(())
+                        }
+                    })
+                    .build(),
+                ScheduledTask::builder(TaskType::CacheInvalidation)
+                    .on_start({
+                        let task = cache_invalidator.clone();
+                        move || {
+                            let task = task.clone();
+                            async move { task.start().await }
+                        }
+                    })
+                    .on_stop(move || {
+                        let task = cache_invalidator.clone();
+                        async move {
+                            task.stop().await;
+                            Ok(())
+                        }
+                    })
+                    .build(),
             ])
             .await;
 
+        // 配置了外部输出时才注册推送任务
+        if let Some(trace_exporter) = TraceExporter::from_config(&config.trace_sink) {
+            let trace_exporter = Arc::new(trace_exporter);
+            task_instances.insert(TaskType::TraceExporter, trace_exporter.clone());
+            scheduler
+                .register(
+                    ScheduledTask::builder(TaskType::TraceExporter)
+                        .on_start({
+                            let task = trace_exporter.clone();
+                            move || {
+                                let task = task.clone();
+                                async move { task.start().await }
+                            }
+                        })
+                        .on_stop(move || {
+                            let task = trace_exporter.clone();
+                            async move {
+                                task.stop().await;
+                                Ok(())
+                            }
+                        })
+                        .build(),
+                )
+                .await;
+        }
+
         Ok(Arc::new(Self {
             scheduler,
             task_instances,
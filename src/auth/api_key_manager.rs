@@ -120,6 +120,7 @@ impl ApiKeyManager {
         user_provider_keys::Entity::find()
             .filter(user_provider_keys::Column::ApiKey.eq(api_key))
             .filter(user_provider_keys::Column::IsActive.eq(true))
+            .filter(user_provider_keys::Column::DeletedAt.is_null())
             .one(self.db.as_ref())
             .await
             .context(format!("Database error when fetching API key {sanitized}"))
@@ -3,7 +3,7 @@
 //! 基于公共 OAuth 客户端的统一授权流程封装。
 
 use crate::auth::api_key_oauth_refresh_service::ApiKeyOAuthRefreshService;
-use crate::auth::api_key_oauth_state_service::ApiKeyOAuthStateService;
+use crate::auth::api_key_oauth_state_service::{ApiKeyOAuthStateService, CreateSessionParams};
 use crate::auth::types::{AuthStatus, OAuthProviderConfig};
 use crate::error::Result;
 use crate::provider::{ApiKeyProviderConfig, build_authorize_url};
@@ -86,12 +86,22 @@ impl ApiKeyOauthService {
         provider_name: &str,
         name: &str,
         description: Option<&str>,
+        redirect_uri: Option<&str>,
     ) -> Result<AuthorizeUrlResponse> {
         let config = self.config.get_config(provider_name).await?;
 
+        let params = CreateSessionParams {
+            user_id,
+            provider_name: provider_name.to_string(),
+            provider_type_id: None,
+            name: name.to_string(),
+            description: description.map(ToString::to_string),
+            expires_in_minutes: None,
+            redirect_uri: redirect_uri.map(ToString::to_string),
+        };
         let session = self
             .state
-            .create_session(user_id, provider_name, None, name, description, &config)
+            .create_session_with_params(&params, &config)
             .await?;
 
         let authorize_url = build_authorize_url(&config, &session)?;
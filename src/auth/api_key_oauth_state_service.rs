@@ -6,12 +6,13 @@
 use crate::auth::api_key_oauth_refresh_service::ApiKeyOAuthRefreshResult;
 use crate::auth::api_key_oauth_service::{OAuthSessionInfo, OAuthTokenResponse};
 use crate::auth::pkce::PkceParams;
-use crate::auth::types::{AuthStatus, OAuthProviderConfig};
+use crate::auth::types::{AuthStatus, AuthType, OAuthProviderConfig};
 use crate::key_pool::types::ApiKeyHealthStatus;
 use crate::types::ProviderTypeId;
 use crate::{ensure, error::ProxyError};
 use chrono::{DateTime, Duration, Utc};
 use entity::{OAuthClientSessions, oauth_client_sessions, user_provider_keys};
+use rand::Rng;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
     QuerySelect, Set, TransactionTrait,
@@ -28,10 +29,14 @@ use crate::error::{Context, Result, auth::OAuthError};
 
 /// 距离过期多久开始预刷新（默认提前2分钟）
 pub const REFRESH_LEAD_TIME: Duration = Duration::seconds(120);
-/// 刷新失败后的退避间隔
+/// 刷新失败后的退避间隔（指数退避的基数）
 const RETRY_INTERVAL_SECS: i64 = 60;
-/// 允许的最大重试次数
-const MAX_RETRY_ATTEMPTS: u32 = 3;
+/// 指数退避的最大间隔
+const RETRY_BACKOFF_MAX_SECS: i64 = 30 * 60;
+/// 退避抖动比例（±20%），避免多个会话同时重试冲击提供商
+const RETRY_JITTER_RATIO: f64 = 0.2;
+/// 允许的最大重试次数，耗尽后会话标记为需要人工处理
+const MAX_RETRY_ATTEMPTS: u32 = 5;
 /// pending 会话的保留时长（分钟）
 const PENDING_EXPIRE_MINUTES: i64 = 30;
 /// expired 会话的保留天数
@@ -46,6 +51,8 @@ pub struct CreateSessionParams {
     pub name: String,
     pub description: Option<String>,
     pub expires_in_minutes: Option<i32>,
+    /// 指定的回调地址，须在提供商配置中登记；为空时使用默认地址
+    pub redirect_uri: Option<String>,
 }
 
 /// OAuth 会话状态管理服务
@@ -223,7 +230,7 @@ impl ApiKeyOAuthStateService {
         session_id: &str,
     ) -> Result<Option<user_provider_keys::Model>> {
         user_provider_keys::Entity::find()
-            .filter(user_provider_keys::Column::AuthType.eq("oauth"))
+            .filter(user_provider_keys::Column::AuthType.eq(AuthType::OAuth.as_str()))
             .filter(user_provider_keys::Column::ApiKey.eq(session_id))
             .one(self.db.as_ref())
             .await
@@ -253,7 +260,7 @@ impl ApiKeyOAuthStateService {
     pub async fn has_oauth_association(&self, user_id: i32, session_id: &str) -> Result<bool> {
         let record = user_provider_keys::Entity::find()
             .filter(user_provider_keys::Column::UserId.eq(user_id))
-            .filter(user_provider_keys::Column::AuthType.eq("oauth"))
+            .filter(user_provider_keys::Column::AuthType.eq(AuthType::OAuth.as_str()))
             .filter(user_provider_keys::Column::ApiKey.eq(session_id))
             .one(self.db.as_ref())
             .await
@@ -265,7 +272,7 @@ impl ApiKeyOAuthStateService {
     /// 列出所有授权并具备刷新条件的 OAuth 会话
     pub async fn list_authorized_sessions(&self) -> Result<Vec<oauth_client_sessions::Model>> {
         let linked_session_ids: Vec<String> = user_provider_keys::Entity::find()
-            .filter(user_provider_keys::Column::AuthType.eq("oauth"))
+            .filter(user_provider_keys::Column::AuthType.eq(AuthType::OAuth.as_str()))
             .select_only()
             .column(user_provider_keys::Column::ApiKey)
             .into_tuple::<String>()
@@ -485,7 +492,30 @@ impl ApiKeyOAuthStateService {
         })
     }
 
+    /// 第 `attempts` 次失败后的基础退避间隔：`60s * 2^(attempts-1)`，上限 30 分钟
+    #[must_use]
+    pub fn retry_backoff(attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(16);
+        let secs = RETRY_INTERVAL_SECS
+            .saturating_mul(1_i64 << exponent)
+            .min(RETRY_BACKOFF_MAX_SECS);
+        Duration::seconds(secs)
+    }
+
+    /// 在基础退避间隔上叠加 ±20% 的随机抖动
+    #[must_use]
+    pub fn jittered_retry_backoff(attempts: u32) -> Duration {
+        let base = Self::retry_backoff(attempts);
+        let factor = 1.0 + rand::thread_rng().gen_range(-RETRY_JITTER_RATIO..=RETRY_JITTER_RATIO);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+        let millis = (base.num_milliseconds() as f64 * factor) as i64;
+        Duration::milliseconds(millis)
+    }
+
     /// 刷新失败后更新重试状态
+    ///
+    /// 按指数退避加抖动安排下一次重试；达到最大重试次数后不再调度，
+    /// 并将会话与关联密钥标记为 `needs_attention`，等待人工重新授权。
     pub async fn fail_refresh(
         &self,
         session_id: &str,
@@ -497,7 +527,7 @@ impl ApiKeyOAuthStateService {
         let next_retry_at = if attempts >= MAX_RETRY_ATTEMPTS {
             None
         } else {
-            Some(now + Duration::seconds(RETRY_INTERVAL_SECS))
+            Some(now + Self::jittered_retry_backoff(attempts))
         };
 
         let detail = RefreshStatusDetail {
@@ -516,6 +546,7 @@ impl ApiKeyOAuthStateService {
         self.release_refresh_slot(session_id).await;
 
         if attempts >= MAX_RETRY_ATTEMPTS {
+            self.mark_needs_attention(session_id, error_message).await?;
             return Ok(None);
         }
 
@@ -533,6 +564,25 @@ impl ApiKeyOAuthStateService {
         }))
     }
 
+    /// 将会话及其关联密钥标记为需要人工处理
+    async fn mark_needs_attention(&self, session_id: &str, error_message: &str) -> Result<()> {
+        if let Some(session) = self.fetch_session(session_id).await? {
+            let mut active: oauth_client_sessions::ActiveModel = session.into();
+            active.status = Set(AuthStatus::NeedsAttention.to_string());
+            active.error_message = Set(Some(error_message.to_string()));
+            active.updated_at = Set(Utc::now().naive_utc());
+            active.update(self.db.as_ref()).await?;
+        }
+
+        if let Some(key) = self.find_provider_key(session_id).await? {
+            let mut active: user_provider_keys::ActiveModel = key.into();
+            active.auth_status = Set(Some(AuthStatus::NeedsAttention.to_string()));
+            active.updated_at = Set(Utc::now().naive_utc());
+            active.update(self.db.as_ref()).await?;
+        }
+        Ok(())
+    }
+
     /// 停止调度某个会话的刷新计划
     pub async fn stop_refresh(&self, session_id: &str, reason: Option<&str>) -> Result<()> {
         let detail = RefreshStatusDetail {
@@ -607,7 +657,7 @@ impl ApiKeyOAuthStateService {
         };
 
         let linked_session_ids: HashSet<String> = user_provider_keys::Entity::find()
-            .filter(user_provider_keys::Column::AuthType.eq("oauth"))
+            .filter(user_provider_keys::Column::AuthType.eq(AuthType::OAuth.as_str()))
             .select_only()
             .column(user_provider_keys::Column::ApiKey)
             .into_tuple::<String>()
@@ -660,10 +710,32 @@ impl ApiKeyOAuthStateService {
         provider_type_id: Option<ProviderTypeId>,
         name: &str,
         description: Option<&str>,
-        _config: &OAuthProviderConfig,
+        config: &OAuthProviderConfig,
     ) -> Result<oauth_client_sessions::Model> {
-        // 生成PKCE参数
-        let pkce = PkceParams::new();
+        let params = CreateSessionParams {
+            user_id,
+            provider_name: provider_name.to_string(),
+            provider_type_id,
+            name: name.to_string(),
+            description: description.map(ToString::to_string),
+            expires_in_minutes: None,
+            redirect_uri: None,
+        };
+        self.create_session_with_params(&params, config).await
+    }
+
+    /// `使用参数结构创建OAuth会话`
+    pub async fn create_session_with_params(
+        &self,
+        params: &CreateSessionParams,
+        config: &OAuthProviderConfig,
+    ) -> Result<oauth_client_sessions::Model> {
+        // 仅在指定了非默认回调地址时记录到会话，避免提供商配置变更后旧会话仍使用过期地址
+        let redirect_uri = config.select_redirect_uri(params.redirect_uri.as_deref())?;
+        let redirect_uri = (redirect_uri != config.redirect_uri).then_some(redirect_uri);
+
+        // 按提供商配置的方法生成PKCE参数
+        let pkce = PkceParams::with_method(config.pkce_method);
 
         // 生成唯一的会话ID和状态参数
         let session_id = Uuid::new_v4().to_string();
@@ -676,14 +748,15 @@ impl ApiKeyOAuthStateService {
         // 创建会话记录
         let session = oauth_client_sessions::ActiveModel {
             session_id: Set(session_id),
-            user_id: Set(user_id),
-            provider_name: Set(provider_name.to_string()),
-            provider_type_id: Set(provider_type_id),
+            user_id: Set(params.user_id),
+            provider_name: Set(params.provider_name.clone()),
+            provider_type_id: Set(params.provider_type_id),
             code_verifier: Set(pkce.verifier.into_string()),
             code_challenge: Set(pkce.challenge.as_str().to_string()),
+            redirect_uri: Set(redirect_uri),
             state: Set(state),
-            name: Set(name.to_string()),
-            description: Set(description.map(std::string::ToString::to_string)),
+            name: Set(params.name.clone()),
+            description: Set(params.description.clone()),
             status: Set(AuthStatus::Pending.to_string()),
             expires_at: Set(expires_at),
             created_at: Set(now),
@@ -695,23 +768,6 @@ impl ApiKeyOAuthStateService {
         Ok(inserted_session)
     }
 
-    /// `使用参数结构创建OAuth会话`
-    pub async fn create_session_with_params(
-        &self,
-        params: &CreateSessionParams,
-        config: &OAuthProviderConfig,
-    ) -> Result<oauth_client_sessions::Model> {
-        self.create_session(
-            params.user_id,
-            &params.provider_name,
-            params.provider_type_id,
-            &params.name,
-            params.description.as_deref(),
-            config,
-        )
-        .await
-    }
-
     /// 根据状态参数获取会话
     pub async fn get_session_by_state(&self, state: &str) -> Result<oauth_client_sessions::Model> {
         let session = OAuthClientSessions::find()
@@ -925,3 +981,118 @@ impl ApiKeyOAuthStateService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::Database;
+    use serial_test::serial;
+
+    async fn setup_session(session_id: &str) -> ApiKeyOAuthStateService {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to test database");
+        Migrator::up(&db, None)
+            .await
+            .expect("Failed to run migrations");
+
+        let now = Utc::now().naive_utc();
+        entity::users::Entity::insert(entity::users::ActiveModel {
+            id: Set(1134),
+            username: Set("oauth_refresh_user".to_string()),
+            password_hash: Set("...".to_string()),
+            email: Set("oauth_refresh@test.com".to_string()),
+            salt: Set("salt".to_string()),
+            is_admin: Set(false),
+            is_active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .exec(&db)
+        .await
+        .unwrap();
+        OAuthClientSessions::insert(oauth_client_sessions::ActiveModel {
+            session_id: Set(session_id.to_string()),
+            user_id: Set(1134),
+            provider_name: Set("claude".to_string()),
+            code_verifier: Set("verifier".to_string()),
+            code_challenge: Set("challenge".to_string()),
+            state: Set("state".to_string()),
+            name: Set("Refresh Session".to_string()),
+            status: Set(AuthStatus::Authorized.to_string()),
+            access_token: Set(Some("access".to_string())),
+            refresh_token: Set(Some("refresh".to_string())),
+            expires_at: Set(now),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .exec(&db)
+        .await
+        .unwrap();
+
+        ApiKeyOAuthStateService::new(Arc::new(db))
+    }
+
+    #[test]
+    fn retry_backoff_grows_exponentially_with_jitter() {
+        let expected = [60, 120, 240, 480, 960, 1800, 1800];
+        for (index, secs) in expected.into_iter().enumerate() {
+            let attempts = u32::try_from(index + 1).unwrap();
+            assert_eq!(
+                ApiKeyOAuthStateService::retry_backoff(attempts),
+                Duration::seconds(secs)
+            );
+
+            let jittered = ApiKeyOAuthStateService::jittered_retry_backoff(attempts);
+            assert!(jittered >= Duration::milliseconds(secs * 800));
+            assert!(jittered <= Duration::milliseconds(secs * 1200));
+        }
+        assert_eq!(
+            ApiKeyOAuthStateService::retry_backoff(u32::MAX),
+            Duration::seconds(RETRY_BACKOFF_MAX_SECS)
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn session_needs_attention_after_max_attempts() {
+        let service = setup_session("refresh-session-1134").await;
+
+        let mut attempts = 0;
+        let mut last_retry_at = Utc::now();
+        for _ in 1..MAX_RETRY_ATTEMPTS {
+            let schedule = service
+                .fail_refresh("refresh-session-1134", attempts, "token endpoint down")
+                .await
+                .unwrap()
+                .expect("retry should be scheduled before exhausting attempts");
+            assert!(schedule.next_refresh_at > last_retry_at);
+            attempts = schedule.retry_attempts;
+            last_retry_at = schedule.next_refresh_at;
+        }
+        let session = service.get_session("refresh-session-1134").await.unwrap();
+        assert_eq!(session.status, AuthStatus::Authorized.to_string());
+
+        let exhausted = service
+            .fail_refresh("refresh-session-1134", attempts, "token endpoint down")
+            .await
+            .unwrap();
+        assert!(exhausted.is_none());
+
+        let session = service.get_session("refresh-session-1134").await.unwrap();
+        assert_eq!(session.status, AuthStatus::NeedsAttention.to_string());
+        assert_eq!(
+            session.error_message.as_deref(),
+            Some("token endpoint down")
+        );
+        assert!(
+            !service
+                .refresh_target_exists("refresh-session-1134")
+                .await
+                .unwrap()
+        );
+    }
+}
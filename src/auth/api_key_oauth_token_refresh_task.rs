@@ -6,6 +6,7 @@
 //! - 监控和统计刷新任务的执行情况
 //! - 提供任务控制接口（启动、停止、暂停）
 
+use crate::app::task_scheduler::TaskRunRecorder;
 use crate::auth::api_key_oauth_refresh_service::{
     ApiKeyOAuthRefreshResult, ApiKeyOAuthRefreshService,
 };
@@ -18,8 +19,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
-use std::time::Duration as StdDuration;
-use tokio::sync::{RwLock, broadcast, mpsc};
+use std::time::{Duration as StdDuration, Instant};
+use tokio::sync::{RwLock, broadcast, mpsc, oneshot};
 use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
 use tokio_util::time::{DelayQueue, delay_queue::Key};
@@ -48,6 +49,9 @@ pub struct ApiKeyOAuthTokenRefreshTask {
 
     /// 任务句柄
     task_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+
+    /// 每次刷新后记录心跳与执行历史
+    recorder: Option<TaskRunRecorder>,
 }
 
 /// 任务状态
@@ -87,10 +91,12 @@ enum RefreshQueueItem {
     Session(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 enum RefreshCommand {
     Add(ScheduledTokenRefresh),
     Remove(String),
+    /// 查询当前已调度的会话
+    Snapshot(oneshot::Sender<Vec<String>>),
 }
 
 impl From<mpsc::error::SendError<RefreshCommand>> for ProxyError {
@@ -115,9 +121,17 @@ impl ApiKeyOAuthTokenRefreshTask {
             control_sender,
             command_sender: Arc::new(RwLock::new(None)),
             task_handle: Arc::new(RwLock::new(None)),
+            recorder: None,
         }
     }
 
+    /// 每次刷新会话后记录心跳与执行历史
+    #[must_use]
+    pub fn with_run_recorder(mut self, recorder: TaskRunRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     /// 启动后台任务
     pub async fn start(&self) -> Result<()> {
         let mut state = self.task_state.write().await;
@@ -302,6 +316,25 @@ impl ApiKeyOAuthTokenRefreshTask {
             .context("Failed to remove refresh schedule")
     }
 
+    /// 当前在刷新队列中的会话（按会话ID排序），每个会话至多一项
+    pub async fn scheduled_sessions(&self) -> Result<Vec<String>> {
+        let sender = {
+            let guard = self.command_sender.read().await;
+            guard.as_ref().cloned().ok_or_else(|| {
+                crate::error::auth::AuthError::Message("Refresh task is not running".to_string())
+            })?
+        };
+
+        let (reply, receiver) = oneshot::channel();
+        sender
+            .send(RefreshCommand::Snapshot(reply))
+            .await
+            .context("Failed to query refresh schedules")?;
+        receiver.await.map_err(|err| {
+            AuthError::Message(format!("Refresh task stopped before replying: {err}")).into()
+        })
+    }
+
     /// 获取任务状态
     pub async fn get_state(&self) -> TaskState {
         self.task_state.read().await.clone()
@@ -318,6 +351,7 @@ impl ApiKeyOAuthTokenRefreshTask {
         let refresh_service = Arc::clone(&self.refresh_service);
         let oauth_state_service = Arc::clone(&self.oauth_state_service);
         let task_state = Arc::clone(&self.task_state);
+        let recorder = self.recorder.clone();
         let mut control_receiver = self.control_sender.subscribe();
 
         tokio::spawn(async move {
@@ -370,6 +404,8 @@ impl ApiKeyOAuthTokenRefreshTask {
                         };
                         session_keys.remove(&session_id);
 
+                        let started_at = Utc::now();
+                        let timer = Instant::now();
                         let success = Self::process_session_entry(
                             &refresh_service,
                             &oauth_state_service,
@@ -379,6 +415,11 @@ impl ApiKeyOAuthTokenRefreshTask {
                             &mut session_schedules,
                         )
                         .await;
+                        if let Some(recorder) = &recorder {
+                            let error = (!success)
+                                .then(|| format!("Failed to refresh OAuth session {session_id}"));
+                            recorder.record(started_at, timer.elapsed(), error).await;
+                        }
 
                         if success {
                             consecutive_errors = 0;
@@ -429,6 +470,12 @@ impl ApiKeyOAuthTokenRefreshTask {
                                     &format!("Removed OAuth session {session_id} from refresh queue")
                                 );
                             }
+                            Some(RefreshCommand::Snapshot(reply)) => {
+                                let mut session_ids: Vec<String> =
+                                    session_schedules.keys().cloned().collect();
+                                session_ids.sort();
+                                let _ = reply.send(session_ids);
+                            }
                             None => {
                                 // 命令通道关闭，等待现有任务处理完成
                             }
@@ -663,13 +710,14 @@ impl ApiKeyOAuthTokenRefreshTask {
                 Self::insert_or_update_entry(queue, session_keys, session_schedules, &schedule);
             }
             Ok(None) => {
-                linfo!(
+                lwarn!(
                     request_id,
                     stage,
                     component,
-                    "refresh_disabled",
-                    "Refresh disabled after exceeding retry attempts",
-                    session_id = %session_id
+                    "refresh_needs_attention",
+                    "Refresh retries exhausted, session marked as needing manual attention",
+                    session_id = %session_id,
+                    error = %error_message
                 );
             }
             Err(state_err) => {
@@ -719,7 +767,7 @@ impl ApiKeyOAuthTokenRefreshTask {
         let session_id = entry.session_id.clone();
         let stage = LogStage::BackgroundTask;
         let component = LogComponent::OAuth;
-        let request_id = uuid::Uuid::new_v4().to_string();
+        let request_id = crate::utils::request_id::generate();
         let acquired = oauth_state_service.acquire_refresh_slot(&session_id).await;
         if !acquired {
             lwarn!(
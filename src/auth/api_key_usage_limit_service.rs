@@ -2,13 +2,17 @@
 //!
 //! 使用 CacheManager（UnifiedCacheManager） 的 `incr` + `expire` 实现跨实例一致的 QPS/日配额计数。
 //! 先提供最小实现与接口；集成到 `ApiKeyManager` 可作为后续任务。
+//! 缓存后端不可用时计数检查放行（fail-open），记录告警并累计放行次数。
 
-use crate::cache::{CacheManager, keys::CacheKeyBuilder};
+use crate::cache::{CacheManager, keys::CacheKeyBuilder, record_fail_open};
+use crate::config::RateLimitConfig;
 use crate::error::{
     ProxyError, Result,
     auth::{AuthError, UsageLimitInfo, UsageLimitKind},
     conversion::ConversionError,
 };
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
 use entity::{proxy_tracing, user_service_apis};
 use sea_orm::prelude::Decimal;
 use sea_orm::{
@@ -18,6 +22,7 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::time::Instant;
 
 /// 分布式速率限制检查结果
 #[derive(Debug, Clone)]
@@ -28,10 +33,25 @@ pub struct DistRateLimitOutcome {
     pub ttl_seconds: i64,
 }
 
+impl DistRateLimitOutcome {
+    /// 超限时按限制类型构建结构化限流错误，重置时间取计数窗口剩余时长
+    pub(crate) fn rate_limit_error(&self, kind: UsageLimitKind) -> ProxyError {
+        ApiKeyUsageLimitService::rate_limit_error(
+            kind,
+            Some(ApiKeyUsageLimitService::to_f64(self.limit)),
+            Some(ApiKeyUsageLimitService::to_f64(self.current)),
+            u64::try_from(self.ttl_seconds)
+                .ok()
+                .map(Duration::from_secs),
+        )
+    }
+}
+
 /// 简单的分布式限流器
 pub struct ApiKeyUsageLimitService {
     cache: Arc<CacheManager>,
     db: Arc<DatabaseConnection>,
+    config: RateLimitConfig,
 }
 
 #[derive(Debug, FromQueryResult)]
@@ -54,8 +74,19 @@ impl ApiKeyUsageLimitService {
     const TOKEN_PREFIX: &'static str = "ratelimit:daily:tokens";
     const COST_PREFIX: &'static str = "ratelimit:daily:cost";
     /// 创建新的限流器实例，要求提供缓存与数据库
-    pub const fn new(cache: Arc<CacheManager>, db: Arc<DatabaseConnection>) -> Self {
-        Self { cache, db }
+    pub fn new(cache: Arc<CacheManager>, db: Arc<DatabaseConnection>) -> Self {
+        Self {
+            cache,
+            db,
+            config: RateLimitConfig::default(),
+        }
+    }
+
+    /// 设置限流配置（超限排队、提供商全局限流）
+    #[must_use]
+    pub fn with_config(mut self, config: RateLimitConfig) -> Self {
+        self.config = config;
+        self
     }
 
     pub(crate) fn rate_limit_error(
@@ -81,13 +112,45 @@ impl ApiKeyUsageLimitService {
         limit: i64,
     ) -> Result<DistRateLimitOutcome> {
         let key = CacheKeyBuilder::rate_limit(user_id, endpoint).build();
+        self.incr_minute_window(&key, limit).await
+    }
 
+    /// 提供商级全局每分钟请求限制，所有用户与密钥共享计数；未配置该提供商时返回 `None`
+    pub async fn check_provider_per_minute(
+        &self,
+        provider_type_id: i32,
+    ) -> Result<Option<DistRateLimitOutcome>> {
+        let Some(limit) = self.config.provider_limit(provider_type_id) else {
+            return Ok(None);
+        };
+        let key = CacheKeyBuilder::provider_rate_limit(provider_type_id).build();
+        self.incr_minute_window(&key, limit).await.map(Some)
+    }
+
+    /// 模型级每分钟请求限制，同一服务商下该模型的所有请求共享计数
+    pub async fn check_model_per_minute(
+        &self,
+        provider_type_id: i32,
+        model: &str,
+        limit: i64,
+    ) -> Result<DistRateLimitOutcome> {
+        let key = CacheKeyBuilder::model_rate_limit(provider_type_id, model).build();
+        self.incr_minute_window(&key, limit).await
+    }
+
+    async fn incr_minute_window(&self, key: &str, limit: i64) -> Result<DistRateLimitOutcome> {
         // 使用 INCR 原子自增
-        let current = self.cache.incr(&key, 1).await?;
+        let current = match self.cache.incr(key, 1).await {
+            Ok(current) => current,
+            Err(err) if err.is_cache_unavailable() => {
+                return Ok(Self::fail_open(key, limit, 60, &err));
+            }
+            Err(err) => return Err(err),
+        };
 
         // 初次创建时设置 60s 过期，形成分片计数窗口
         if current == 1 {
-            let _ = self.cache.expire(&key, Duration::from_secs(60)).await;
+            let _ = self.cache.expire(key, Duration::from_secs(60)).await;
         }
 
         Ok(DistRateLimitOutcome {
@@ -98,6 +161,58 @@ impl ApiKeyUsageLimitService {
         })
     }
 
+    /// 按配置执行每分钟请求限制：启用排队时超限请求等待窗口释放，否则立即返回检查结果
+    pub async fn acquire_per_minute(
+        &self,
+        user_id: i32,
+        endpoint: &str,
+        limit: i64,
+    ) -> Result<DistRateLimitOutcome> {
+        if self.config.queue.enabled {
+            self.check_per_minute_queued(user_id, endpoint, limit, self.config.queue.max_wait())
+                .await
+        } else {
+            self.check_per_minute(user_id, endpoint, limit).await
+        }
+    }
+
+    /// 带有限等待的每分钟请求限制
+    ///
+    /// 等待期间只读取计数、不占用窗口名额，按轮询间隔异步等待；`max_wait` 内窗口释放则计数并放行，
+    /// 超时后直接拒绝。被拒绝的请求不计入窗口。
+    pub async fn check_per_minute_queued(
+        &self,
+        user_id: i32,
+        endpoint: &str,
+        limit: i64,
+        max_wait: Duration,
+    ) -> Result<DistRateLimitOutcome> {
+        let key = CacheKeyBuilder::rate_limit(user_id, endpoint).build();
+        let deadline = Instant::now() + max_wait;
+        loop {
+            let current = self.current_per_minute(user_id, endpoint).await?;
+            if current < limit {
+                let outcome = self.incr_minute_window(&key, limit).await?;
+                if outcome.allowed {
+                    return Ok(outcome);
+                }
+                // 并发排队者同时抢占了最后的名额：撤销本次计数后继续等待
+                let _ = self.cache.incr(&key, -1).await;
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(DistRateLimitOutcome {
+                    allowed: false,
+                    current,
+                    limit,
+                    ttl_seconds: 60,
+                });
+            }
+            tokio::time::sleep(self.config.queue.poll_interval().min(remaining)).await;
+        }
+    }
+
     /// 简单的每日请求限制（自然日）
     pub async fn check_per_day(
         &self,
@@ -108,9 +223,17 @@ impl ApiKeyUsageLimitService {
         // 将 endpoint 继续复用，若需更细粒度可在外层区分
         let (_, key, _) = Self::daily_request_cache_key(user_id, endpoint);
 
-        let current = self.cache.incr(&key, 1).await?;
         // 设置到当天结束的 TTL
         let ttl = Self::seconds_until_end_of_day();
+        #[allow(clippy::cast_possible_wrap)]
+        let ttl_seconds = ttl as i64;
+        let current = match self.cache.incr(&key, 1).await {
+            Ok(current) => current,
+            Err(err) if err.is_cache_unavailable() => {
+                return Ok(Self::fail_open(&key, limit, ttl_seconds, &err));
+            }
+            Err(err) => return Err(err),
+        };
         if current == 1 {
             let _ = self.cache.expire(&key, Duration::from_secs(ttl)).await;
         }
@@ -119,11 +242,37 @@ impl ApiKeyUsageLimitService {
             allowed: current <= limit,
             current,
             limit,
-            #[allow(clippy::cast_possible_wrap)]
-            ttl_seconds: ttl as i64,
+            ttl_seconds,
         })
     }
 
+    /// 缓存不可用时放行请求：无法计数总比拒绝全部请求好，但需要告警提示限流暂时失效
+    fn fail_open(
+        key: &str,
+        limit: i64,
+        ttl_seconds: i64,
+        err: &ProxyError,
+    ) -> DistRateLimitOutcome {
+        let fail_open_total = record_fail_open();
+        lwarn!(
+            "system",
+            LogStage::Cache,
+            LogComponent::Cache,
+            "rate_limit_fail_open",
+            "缓存不可用，限流检查放行请求",
+            key = %key,
+            limit = limit,
+            fail_open_total = fail_open_total,
+            error = %err
+        );
+        DistRateLimitOutcome {
+            allowed: true,
+            current: 0,
+            limit,
+            ttl_seconds,
+        }
+    }
+
     /// 查询当前分钟窗口内的请求计数
     pub async fn current_per_minute(&self, user_id: i32, endpoint: &str) -> Result<i64> {
         let key = CacheKeyBuilder::rate_limit(user_id, endpoint).build();
@@ -391,6 +540,7 @@ impl ApiKeyUsageLimitService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{ProviderRateLimit, RateLimitQueueConfig};
 
     #[tokio::test]
     async fn smoke_test_memory_backend() {
@@ -412,4 +562,117 @@ mod tests {
             }
         }
     }
+
+    async fn fill_minute_window(rl: &ApiKeyUsageLimitService, limit: i64) {
+        for _ in 0..limit {
+            assert!(
+                rl.check_per_minute(1, "/v1/queue", limit)
+                    .await
+                    .unwrap()
+                    .allowed
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn queued_request_proceeds_when_window_frees() {
+        let cache = Arc::new(CacheManager::memory_only());
+        let db = Arc::new(
+            sea_orm::Database::connect("sqlite::memory:")
+                .await
+                .expect("create in-memory db"),
+        );
+        let rl = ApiKeyUsageLimitService::new(cache.clone(), db);
+        fill_minute_window(&rl, 2).await;
+
+        // 将窗口缩短到 100ms，模拟排队期间窗口到期
+        let key = CacheKeyBuilder::rate_limit(1, "/v1/queue").build();
+        cache
+            .expire(&key, Duration::from_millis(100))
+            .await
+            .unwrap();
+
+        let started = Instant::now();
+        let outcome = rl
+            .check_per_minute_queued(1, "/v1/queue", 2, Duration::from_secs(2))
+            .await
+            .unwrap();
+
+        assert!(outcome.allowed);
+        assert_eq!(outcome.current, 1);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn queued_request_rejected_after_max_wait() {
+        let cache = Arc::new(CacheManager::memory_only());
+        let db = Arc::new(
+            sea_orm::Database::connect("sqlite::memory:")
+                .await
+                .expect("create in-memory db"),
+        );
+        let rl = ApiKeyUsageLimitService::new(cache, db).with_config(RateLimitConfig {
+            queue: RateLimitQueueConfig {
+                enabled: true,
+                max_wait_ms: 150,
+                poll_interval_ms: 20,
+            },
+            ..Default::default()
+        });
+        fill_minute_window(&rl, 2).await;
+
+        let started = Instant::now();
+        let outcome = rl.acquire_per_minute(1, "/v1/queue", 2).await.unwrap();
+
+        assert!(!outcome.allowed);
+        assert!(outcome.current <= 2);
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        // 排队后被拒绝的请求不占用窗口名额
+        assert_eq!(rl.current_per_minute(1, "/v1/queue").await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn provider_limit_shared_across_users() {
+        let cache = Arc::new(CacheManager::memory_only());
+        let db = Arc::new(
+            sea_orm::Database::connect("sqlite::memory:")
+                .await
+                .expect("create in-memory db"),
+        );
+        let rl = Arc::new(
+            ApiKeyUsageLimitService::new(cache, db).with_config(RateLimitConfig {
+                providers: vec![ProviderRateLimit {
+                    provider_type_id: 1,
+                    max_requests_per_min: 5,
+                }],
+                ..Default::default()
+            }),
+        );
+
+        // 10 个不同用户并发请求同一提供商，各自的用户级限制均未触发
+        let handles: Vec<_> = (1..=10)
+            .map(|user_id| {
+                let rl = rl.clone();
+                tokio::spawn(async move {
+                    let user = rl
+                        .check_per_minute(user_id, "service_api:1", 100)
+                        .await
+                        .unwrap();
+                    assert!(user.allowed);
+                    rl.check_provider_per_minute(1).await.unwrap().unwrap()
+                })
+            })
+            .collect();
+
+        let mut allowed = 0;
+        for handle in handles {
+            if handle.await.unwrap().allowed {
+                allowed += 1;
+            }
+        }
+        assert_eq!(allowed, 5);
+
+        // 未配置全局限流的提供商不受影响
+        assert!(rl.check_provider_per_minute(2).await.unwrap().is_none());
+    }
 }
@@ -196,6 +196,17 @@ impl PkceParams {
         }
     }
 
+    /// 生成指定`Challenge`方法的PKCE参数对
+    #[must_use]
+    pub fn with_method(method: ChallengeMethod) -> Self {
+        let verifier = PkceVerifier::new();
+        let challenge = PkceChallenge::from_verifier_with_method(&verifier, method);
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+
     /// 生成指定长度的PKCE参数对
     #[must_use]
     pub fn with_length(length: usize) -> Self {
@@ -342,6 +353,30 @@ mod tests {
         assert_eq!(auth_params[1].1, "S256");
     }
 
+    #[test]
+    fn test_s256_challenge_known_vector() {
+        let verifier = PkceVerifier::from_string(
+            "dBjftJeZ4CVP-mA92Q6uQXvLYbaXlWFcUwhBGxAJrSmaHWo6ntqFQzQNBxDS5wE8".to_string(),
+        )
+        .unwrap();
+        let challenge = PkceChallenge::from_verifier_with_method(&verifier, ChallengeMethod::S256);
+
+        assert_eq!(
+            challenge.as_str(),
+            "zyS__SncIS5ZVhg1KSjeZJ3VLinDxczp4KROyTosAwA"
+        );
+        assert_eq!(challenge.method_str(), "S256");
+    }
+
+    #[test]
+    fn test_params_with_plain_method() {
+        let params = PkceParams::with_method(ChallengeMethod::Plain);
+
+        assert_eq!(params.challenge.as_str(), params.verifier.as_str());
+        assert_eq!(params.authorization_params()[1].1, "plain");
+        assert!(params.verify().unwrap());
+    }
+
     #[test]
     fn test_token_params() {
         let params = PkceParams::new();
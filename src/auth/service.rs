@@ -5,7 +5,7 @@
 use bcrypt::verify;
 use chrono::Utc;
 use entity::{users, users::Entity as Users};
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter};
 use std::sync::Arc;
 
 use crate::auth::api_key_manager::ApiKeyManager;
@@ -110,10 +110,19 @@ impl ApiKeyAuthenticationService {
         &self,
         api_key: &str,
     ) -> Result<entity::user_service_apis::Model> {
-        // 从数据库查询user_service_apis
+        use entity::user_service_apis::Column;
+
+        // 从数据库查询user_service_apis（轮换后的旧 Key 在宽限期内仍可认证）
+        let now = Utc::now().naive_utc();
         let user_api = entity::user_service_apis::Entity::find()
-            .filter(entity::user_service_apis::Column::ApiKey.eq(api_key))
-            .filter(entity::user_service_apis::Column::IsActive.eq(true))
+            .filter(
+                Condition::any().add(Column::ApiKey.eq(api_key)).add(
+                    Condition::all()
+                        .add(Column::PreviousApiKey.eq(api_key))
+                        .add(Column::PreviousApiKeyExpiresAt.gt(now)),
+                ),
+            )
+            .filter(Column::IsActive.eq(true))
             .one(&*self.db)
             .await
             .context("Failed to fetch user_service_api by api_key")?
@@ -121,7 +130,7 @@ impl ApiKeyAuthenticationService {
 
         // 检查API密钥是否过期
         if let Some(expires_at) = user_api.expires_at
-            && expires_at < chrono::Utc::now().naive_utc()
+            && expires_at < now
         {
             return Err(invalid_credentials_error());
         }
@@ -7,7 +7,10 @@ use entity::user_service_apis;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
+use std::str::FromStr;
 
+use crate::auth::pkce::ChallengeMethod;
+use crate::error::auth::AuthError;
 use crate::types::ProviderTypeId;
 
 /// 用户信息
@@ -92,9 +95,15 @@ pub struct OAuthProviderConfig {
     /// 固定的 `client_secret`（如 `Google`/`OpenAI`）。若某些提供商需要动态值（如基于会话的 `PKCE verifier`），可省略此字段。
     pub client_secret: Option<String>,
     pub redirect_uri: String,
+    /// 额外允许的回调地址（`redirect_uri` 之外），发起授权时可指定其中之一
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
     /// 空格分隔的 scope 字符串（保持与数据库一致）
     pub scopes: String,
     pub pkce_required: bool,
+    /// PKCE code challenge 方法，默认 `S256`
+    #[serde(default)]
+    pub pkce_method: ChallengeMethod,
     pub authorize: OAuthAuthorizeConfig,
     pub exchange: OAuthTokenConfig,
     pub refresh: OAuthTokenConfig,
@@ -105,6 +114,35 @@ pub struct OAuthProviderConfig {
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+impl OAuthProviderConfig {
+    /// 选择本次授权使用的回调地址
+    ///
+    /// - 指定了地址时必须是 `redirect_uri` 或 `redirect_uris` 之一
+    /// - 未指定时使用 `redirect_uri`，为空则回退到 `redirect_uris` 的第一项
+    pub fn select_redirect_uri(&self, requested: Option<&str>) -> crate::error::Result<String> {
+        if let Some(requested) = requested {
+            let allowed = requested == self.redirect_uri
+                || self.redirect_uris.iter().any(|uri| uri == requested);
+            crate::ensure!(
+                allowed,
+                crate::error::auth::AuthError::Message(format!(
+                    "回调地址未在 {} 的配置中登记: {requested}",
+                    self.provider_name
+                ))
+            );
+            return Ok(requested.to_string());
+        }
+
+        if !self.redirect_uri.is_empty() {
+            return Ok(self.redirect_uri.clone());
+        }
+        self.redirect_uris.first().cloned().ok_or_else(|| {
+            crate::error::auth::AuthError::Message(format!("{} 未配置回调地址", self.provider_name))
+                .into()
+        })
+    }
+}
+
 /// JWT 载荷
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtClaims {
@@ -343,32 +381,51 @@ impl AuthConfig {
 /// - `AuthMethod` 表示请求经过哪种方式完成了认证（结果状态）
 ///
 /// 例如：`AuthType::GoogleOAuth` 策略执行后，可能产生 `AuthMethod::OAuth` 结果
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+///
+/// 数据库中 `auth_type` 列保存字符串，读取时通过 [`FromStr`] 解析（未知类型返回
+/// [`AuthError::UnsupportedAuthType`]），写入时通过 [`AuthType::as_str`]，业务代码只比较枚举。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AuthType {
     /// API密钥认证策略
     ApiKey,
     /// 统一OAuth认证策略（支持所有OAuth 2.0提供商）
     OAuth,
+    /// AWS SigV4 请求签名（如 Amazon Bedrock）
+    AwsSigV4,
+    /// HMAC-SHA256 请求签名（自建网关）
+    HmacSignature,
 }
 
 impl fmt::Display for AuthType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::ApiKey => write!(f, "api_key"),
-            Self::OAuth => write!(f, "oauth"),
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuthType {
+    type Err = AuthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "api_key" => Ok(Self::ApiKey),
+            "oauth" => Ok(Self::OAuth),
+            "aws_sigv4" => Ok(Self::AwsSigV4),
+            "hmac_signature" => Ok(Self::HmacSignature),
+            _ => Err(AuthError::UnsupportedAuthType(s.to_string())),
         }
     }
 }
 
 impl AuthType {
-    /// 安全解析认证类型字符串，未知类型返回 None
+    /// 数据库与接口中使用的字符串表示
     #[must_use]
-    pub fn from(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
-            "api_key" => Some(Self::ApiKey),
-            "oauth" => Some(Self::OAuth),
-            _ => None,
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ApiKey => "api_key",
+            Self::OAuth => "oauth",
+            Self::AwsSigV4 => "aws_sigv4",
+            Self::HmacSignature => "hmac_signature",
         }
     }
 }
@@ -387,6 +444,8 @@ pub enum AuthStatus {
     Error,
     /// 已撤销
     Revoked,
+    /// 自动刷新多次失败，需要人工重新授权
+    NeedsAttention,
 }
 
 impl fmt::Display for AuthStatus {
@@ -397,6 +456,7 @@ impl fmt::Display for AuthStatus {
             Self::Expired => write!(f, "expired"),
             Self::Error => write!(f, "error"),
             Self::Revoked => write!(f, "revoked"),
+            Self::NeedsAttention => write!(f, "needs_attention"),
         }
     }
 }
@@ -408,6 +468,7 @@ impl From<&str> for AuthStatus {
             "expired" => Self::Expired,
             "error" => Self::Error,
             "revoked" => Self::Revoked,
+            "needs_attention" => Self::NeedsAttention,
             _ => Self::Pending,
         }
     }
@@ -457,11 +518,23 @@ mod tests {
     }
 
     #[test]
-    fn test_auth_type_from() {
-        assert_eq!(AuthType::from("api_key"), Some(AuthType::ApiKey));
-        assert_eq!(AuthType::from("oauth"), Some(AuthType::OAuth));
-        assert_eq!(AuthType::from("API_KEY"), Some(AuthType::ApiKey)); // 测试大小写
-        assert_eq!(AuthType::from("unknown"), None); // 未知类型返回 None
+    fn test_auth_type_parse() {
+        assert_eq!("api_key".parse::<AuthType>().unwrap(), AuthType::ApiKey);
+        assert_eq!("oauth".parse::<AuthType>().unwrap(), AuthType::OAuth);
+        assert_eq!("API_KEY".parse::<AuthType>().unwrap(), AuthType::ApiKey); // 测试大小写
+        for auth_type in [
+            AuthType::ApiKey,
+            AuthType::OAuth,
+            AuthType::AwsSigV4,
+            AuthType::HmacSignature,
+        ] {
+            assert_eq!(auth_type.as_str().parse::<AuthType>().unwrap(), auth_type);
+        }
+        // 未知类型显式报错，而不是按非 OAuth 处理
+        for unknown in ["unknown", "", "oauth2"] {
+            let err = unknown.parse::<AuthType>().unwrap_err();
+            assert!(matches!(err, AuthError::UnsupportedAuthType(ref value) if value == unknown));
+        }
     }
 
     #[test]
@@ -471,5 +544,10 @@ mod tests {
         assert_eq!(AuthStatus::Expired.to_string(), "expired");
         assert_eq!(AuthStatus::Error.to_string(), "error");
         assert_eq!(AuthStatus::Revoked.to_string(), "revoked");
+        assert_eq!(AuthStatus::NeedsAttention.to_string(), "needs_attention");
+        assert_eq!(
+            AuthStatus::from("needs_attention"),
+            AuthStatus::NeedsAttention
+        );
     }
 }
@@ -1,23 +1,52 @@
 //! # 缓存抽象层
 //!
 //! 提供统一的缓存接口，支持内存缓存和 Redis 缓存
+//!
+//! Redis 连接失败时缓存进入降级状态：后续操作不再访问 Redis 而是立即失败，读取视为未命中
+//! （认证回退到数据库）、写入跳过，限流计数由调用方放行；后台任务按配置间隔探测，
+//! Redis 恢复后自动退出降级。
+//!
+//! 降级期间跳过的删除（如密钥吊销后的认证缓存失效）会被记录下来，退出降级前先在 Redis 上补删，
+//! 避免恢复后继续命中降级前写入的陈旧数据；记录过多时改为清空当前库。
 
 use crate::config::{CacheConfig, CacheType, RedisConfig};
-use crate::error::{Context, Result, cache::CacheError};
+use crate::error::{
+    Context, Result,
+    cache::{CacheError, is_connection_error},
+};
 use crate::{
-    linfo,
+    ldebug, lerror, linfo,
     logging::{LogComponent, LogStage},
+    lwarn,
 };
 use async_trait::async_trait;
 use dashmap::DashMap;
 use moka::future::Cache;
 use redis::AsyncCommands;
 use serde::{Serialize, de::DeserializeOwned};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError};
 use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, OnceCell};
 
+/// 降级期间最多记录的待补删键数，超出后恢复时改为清空当前库
+const MAX_PENDING_DELETES: usize = 10_000;
+
+/// 进程启动以来因缓存不可用而放行的限流与配额检查次数
+static FAIL_OPEN_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// 记录一次缓存不可用时的放行，返回累计次数
+pub fn record_fail_open() -> u64 {
+    FAIL_OPEN_TOTAL.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// 进程启动以来因缓存不可用而放行的检查总数
+#[must_use]
+pub fn fail_open_total() -> u64 {
+    FAIL_OPEN_TOTAL.load(Ordering::Relaxed)
+}
+
 #[derive(Clone)]
 struct CacheEntry {
     data: Arc<Vec<u8>>,
@@ -267,12 +296,54 @@ impl CacheProvider for MemoryCache {
     }
 }
 
+/// 降级期间跳过、待 Redis 恢复后补做的删除
+#[derive(Debug, Default)]
+struct PendingDeletes {
+    keys: HashSet<String>,
+    /// 记录数超过上限，恢复时清空当前库
+    overflowed: bool,
+}
+
+impl PendingDeletes {
+    fn record(&mut self, key: &str) {
+        if self.overflowed {
+            return;
+        }
+        if self.keys.len() >= MAX_PENDING_DELETES {
+            self.keys.clear();
+            self.overflowed = true;
+        } else {
+            self.keys.insert(key.to_string());
+        }
+    }
+
+    /// 补删失败时把取出的记录放回
+    fn merge(&mut self, other: Self) {
+        if other.overflowed {
+            self.keys.clear();
+            self.overflowed = true;
+        }
+        for key in other.keys {
+            self.record(&key);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.keys.is_empty() && !self.overflowed
+    }
+}
+
 /// Redis 缓存实现
 pub struct RedisCache {
     client: redis::Client,
     connection_manager: OnceCell<redis::aio::ConnectionManager>,
     hit_count: AtomicU64,
     miss_count: AtomicU64,
+    /// Redis 不可达时置位，由后台重连任务清除
+    unavailable: Arc<AtomicBool>,
+    /// 降级期间跳过的删除，由后台重连任务在恢复前补做
+    pending_deletes: Arc<std::sync::Mutex<PendingDeletes>>,
+    reconnect_interval: Duration,
 }
 
 impl RedisCache {
@@ -285,22 +356,158 @@ impl RedisCache {
             connection_manager: OnceCell::new(),
             hit_count: AtomicU64::new(0),
             miss_count: AtomicU64::new(0),
+            unavailable: Arc::new(AtomicBool::new(false)),
+            pending_deletes: Arc::default(),
+            reconnect_interval: redis_config.reconnect_interval(),
         })
     }
 
+    /// Redis 是否处于不可用（降级）状态
+    #[must_use]
+    pub fn is_unavailable(&self) -> bool {
+        self.unavailable.load(Ordering::Acquire)
+    }
+
     async fn connection(&self) -> Result<redis::aio::ConnectionManager> {
+        if self.is_unavailable() {
+            return Err(CacheError::unavailable("Redis 不可用，等待后台重连").into());
+        }
         let client = self.client.clone();
         let manager = self
             .connection_manager
             .get_or_try_init(|| async {
-                redis::aio::ConnectionManager::new(client)
+                // 连接失败时尽快进入降级，由后台任务负责重连，避免请求长时间等待重试
+                let config = redis::aio::ConnectionManagerConfig::new().set_number_of_retries(1);
+                redis::aio::ConnectionManager::new_with_config(client, config)
                     .await
+                    .inspect_err(|err| self.observe_error(err))
                     .context("建立 Redis 连接失败")
             })
             .await?;
         Ok(manager.clone())
     }
 
+    /// 连接类错误将 Redis 标记为不可用并启动后台重连，命令本身的错误不影响可用状态
+    fn observe_error(&self, err: &redis::RedisError) {
+        if !is_connection_error(err) || self.unavailable.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        lerror!(
+            "system",
+            LogStage::Cache,
+            LogComponent::Cache,
+            "redis_unavailable",
+            "Redis 不可用，缓存进入降级：认证回退数据库，限流放行",
+            error = %err,
+            reconnect_interval_ms = self.reconnect_interval.as_millis()
+        );
+        self.spawn_reconnect();
+    }
+
+    /// 后台按间隔探测 Redis，补做降级期间跳过的删除后退出降级
+    fn spawn_reconnect(&self) {
+        let client = self.client.clone();
+        let unavailable = Arc::clone(&self.unavailable);
+        let pending_deletes = Arc::clone(&self.pending_deletes);
+        let interval = self.reconnect_interval;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let recovered = match Self::ping(&client).await {
+                    Ok(()) => Self::replay_pending_deletes(&client, &pending_deletes).await,
+                    Err(err) => Err(err),
+                };
+                match recovered {
+                    Ok(replayed) => {
+                        unavailable.store(false, Ordering::Release);
+                        // 退出降级前一刻仍可能有删除被跳过，再补做一次
+                        let late = Self::replay_pending_deletes(&client, &pending_deletes)
+                            .await
+                            .unwrap_or_default();
+                        linfo!(
+                            "system",
+                            LogStage::Cache,
+                            LogComponent::Cache,
+                            "redis_reconnected",
+                            "Redis 已恢复，缓存退出降级",
+                            replayed_deletes = replayed + late
+                        );
+                        return;
+                    }
+                    Err(err) => {
+                        ldebug!(
+                            "system",
+                            LogStage::Cache,
+                            LogComponent::Cache,
+                            "redis_reconnect_fail",
+                            "Redis 重连探测失败",
+                            error = %err
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    /// 记录降级期间跳过的删除
+    fn record_pending_delete(&self, key: &str) {
+        self.pending_deletes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record(key);
+    }
+
+    /// 在 Redis 上补做记录的删除，返回补删的键数；失败时记录放回，等待下次探测
+    async fn replay_pending_deletes(
+        client: &redis::Client,
+        pending_deletes: &std::sync::Mutex<PendingDeletes>,
+    ) -> redis::RedisResult<usize> {
+        let pending = std::mem::take(
+            &mut *pending_deletes
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let result = Self::apply_deletes(client, &pending).await;
+        if result.is_err() {
+            pending_deletes
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .merge(pending);
+        }
+        result
+    }
+
+    async fn apply_deletes(
+        client: &redis::Client,
+        pending: &PendingDeletes,
+    ) -> redis::RedisResult<usize> {
+        let mut conn = client.get_multiplexed_async_connection().await?;
+        if pending.overflowed {
+            redis::cmd("FLUSHDB").query_async::<()>(&mut conn).await?;
+            lwarn!(
+                "system",
+                LogStage::Cache,
+                LogComponent::Cache,
+                "redis_pending_deletes_overflow",
+                "降级期间跳过的删除超过上限，已清空 Redis 当前库",
+                max_pending_deletes = MAX_PENDING_DELETES
+            );
+            return Ok(0);
+        }
+        let keys: Vec<&String> = pending.keys.iter().collect();
+        let _: usize = conn.del(keys).await?;
+        Ok(pending.keys.len())
+    }
+
+    async fn ping(client: &redis::Client) -> redis::RedisResult<()> {
+        let mut conn = client.get_multiplexed_async_connection().await?;
+        redis::cmd("PING").query_async::<String>(&mut conn).await?;
+        Ok(())
+    }
+
     fn encode<T>(value: &T) -> Result<Vec<u8>>
     where
         T: Serialize + Send + Sync,
@@ -327,16 +534,25 @@ impl CacheProvider for RedisCache {
 
         match ttl {
             Some(duration) if duration.is_zero() => {
-                let _: () = conn.set(key, serialized).await.context("Redis SET 失败")?;
+                let _: () = conn
+                    .set(key, serialized)
+                    .await
+                    .inspect_err(|err| self.observe_error(err))
+                    .context("Redis SET 失败")?;
             }
             Some(duration) => {
                 let _: () = conn
                     .set_ex(key, serialized, duration.as_secs())
                     .await
+                    .inspect_err(|err| self.observe_error(err))
                     .context("Redis SETEX 失败")?;
             }
             None => {
-                let _: () = conn.set(key, serialized).await.context("Redis SET 失败")?;
+                let _: () = conn
+                    .set(key, serialized)
+                    .await
+                    .inspect_err(|err| self.observe_error(err))
+                    .context("Redis SET 失败")?;
             }
         }
 
@@ -348,7 +564,11 @@ impl CacheProvider for RedisCache {
         T: DeserializeOwned + Send,
     {
         let mut conn = self.connection().await?;
-        let result: Option<Vec<u8>> = conn.get(key).await.context("Redis GET 失败")?;
+        let result: Option<Vec<u8>> = conn
+            .get(key)
+            .await
+            .inspect_err(|err| self.observe_error(err))
+            .context("Redis GET 失败")?;
 
         result.map_or_else(
             || {
@@ -363,14 +583,30 @@ impl CacheProvider for RedisCache {
     }
 
     async fn delete(&self, key: &str) -> Result<()> {
-        let mut conn = self.connection().await?;
-        let _: usize = conn.del(key).await.context("Redis DEL 失败")?;
-        Ok(())
+        let result: Result<()> = async {
+            let mut conn = self.connection().await?;
+            let _: usize = conn
+                .del(key)
+                .await
+                .inspect_err(|err| self.observe_error(err))
+                .context("Redis DEL 失败")?;
+            Ok(())
+        }
+        .await;
+        if let Err(err) = &result
+            && err.is_cache_unavailable()
+        {
+            self.record_pending_delete(key);
+        }
+        result
     }
 
     async fn exists(&self, key: &str) -> Result<bool> {
         let mut conn = self.connection().await?;
-        conn.exists(key).await.context("Redis EXISTS 失败")
+        conn.exists(key)
+            .await
+            .inspect_err(|err| self.observe_error(err))
+            .context("Redis EXISTS 失败")
     }
 
     async fn expire(&self, key: &str, ttl: Duration) -> Result<()> {
@@ -383,18 +619,26 @@ impl CacheProvider for RedisCache {
         let _: bool = conn
             .expire(key, expire_seconds)
             .await
+            .inspect_err(|err| self.observe_error(err))
             .context("Redis EXPIRE 失败")?;
         Ok(())
     }
 
     async fn incr(&self, key: &str, delta: i64) -> Result<i64> {
         let mut conn = self.connection().await?;
-        conn.incr(key, delta).await.context("Redis INCRBY 失败")
+        conn.incr(key, delta)
+            .await
+            .inspect_err(|err| self.observe_error(err))
+            .context("Redis INCRBY 失败")
     }
 
     async fn clear(&self) -> Result<()> {
         let mut conn = self.connection().await?;
-        let _: () = conn.flushdb().await.context("Redis FLUSHDB 失败")?;
+        let _: () = conn
+            .flushdb()
+            .await
+            .inspect_err(|err| self.observe_error(err))
+            .context("Redis FLUSHDB 失败")?;
         Ok(())
     }
 
@@ -404,6 +648,7 @@ impl CacheProvider for RedisCache {
             .arg("keyspace")
             .query_async(&mut conn)
             .await
+            .inspect_err(|err| self.observe_error(err))
             .context("Redis INFO 失败")?;
 
         let total_keys = info
@@ -576,42 +821,74 @@ impl CacheManager {
         true
     }
 
-    /// 设置缓存值
+    /// 缓存后端是否处于不可用（降级）状态
+    #[must_use]
+    pub fn is_degraded(&self) -> bool {
+        match &self.provider {
+            CacheProviderType::Memory(_) => false,
+            CacheProviderType::Redis(cache) => cache.is_unavailable(),
+        }
+    }
+
+    /// 设置缓存值；缓存不可用时跳过
     pub async fn set<T>(&self, key: &str, value: &T, ttl: Option<Duration>) -> Result<()>
     where
         T: Serialize + Send + Sync,
     {
-        self.provider.set(key, value, ttl).await
+        Self::degrade(self.provider.set(key, value, ttl).await, (), "set", key)
     }
 
-    /// 获取缓存值
+    /// 获取缓存值；缓存不可用时视为未命中
     pub async fn get<T>(&self, key: &str) -> Result<Option<T>>
     where
         T: DeserializeOwned + Send,
     {
-        self.provider.get(key).await
+        Self::degrade(self.provider.get(key).await, None, "get", key)
     }
 
-    /// 删除缓存值
+    /// 删除缓存值；缓存不可用时跳过，Redis 恢复后补删
     pub async fn delete(&self, key: &str) -> Result<()> {
-        self.provider.delete(key).await
+        Self::degrade(self.provider.delete(key).await, (), "delete", key)
     }
 
-    /// 检查键是否存在
+    /// 检查键是否存在；缓存不可用时视为不存在
     pub async fn exists(&self, key: &str) -> Result<bool> {
-        self.provider.exists(key).await
+        Self::degrade(self.provider.exists(key).await, false, "exists", key)
     }
 
-    /// 设置过期时间
+    /// 设置过期时间；缓存不可用时跳过
     pub async fn expire(&self, key: &str, ttl: Duration) -> Result<()> {
-        self.provider.expire(key, ttl).await
+        Self::degrade(self.provider.expire(key, ttl).await, (), "expire", key)
     }
 
     /// 增加数字值
+    ///
+    /// 计数无法降级为缓存未命中，缓存不可用时照常返回错误，由调用方决定是否放行
+    /// （见 `ProxyError::is_cache_unavailable`）。
     pub async fn incr(&self, key: &str, delta: i64) -> Result<i64> {
         self.provider.incr(key, delta).await
     }
 
+    /// 缓存不可用导致的错误替换为降级结果，其余错误照常返回
+    fn degrade<T>(result: Result<T>, fallback: T, operation: &str, key: &str) -> Result<T> {
+        match result {
+            Err(err) if err.is_cache_unavailable() => {
+                ldebug!(
+                    "system",
+                    LogStage::Cache,
+                    LogComponent::Cache,
+                    "cache_degraded_skip",
+                    "缓存不可用，跳过缓存操作",
+                    operation = operation,
+                    key = %key,
+                    error = %err
+                );
+                Ok(fallback)
+            }
+            result => result,
+        }
+    }
+
     /// 清空所有缓存
     pub async fn clear(&self) -> Result<()> {
         self.provider.clear().await
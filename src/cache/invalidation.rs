@@ -0,0 +1,344 @@
+//! # 跨实例缓存失效
+//!
+//! 多实例部署时，除共享的 Redis 缓存外，各实例仍持有本地缓存（如密钥候选集）。变更数据的实例通过
+//! Redis 发布/订阅广播失效事件，所有实例订阅后清理对应的统一缓存条目，并通知注册的本地缓存：
+//! - 发布时先在本实例内生效，再广播给其他实例；订阅方跳过自己发布的事件；
+//! - 内存缓存后端只有单实例，不广播也不订阅；
+//! - 订阅连接断开后按固定间隔重新订阅，期间错过的事件依赖各缓存的 TTL 兜底。
+
+use crate::cache::CacheManager;
+use crate::cache::keys::CacheKey;
+use crate::config::{CacheConfig, CacheType};
+use crate::error::{Context, Result, cache::CacheError};
+use crate::logging::{LogComponent, LogStage};
+use crate::{ldebug, linfo, lwarn};
+use futures::StreamExt;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{Mutex, OnceCell};
+use tokio::task::JoinHandle;
+
+/// 失效事件的发布/订阅频道
+pub const INVALIDATION_CHANNEL: &str = "api_proxy:cache_invalidation";
+
+/// 订阅断开后重新订阅的间隔
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+/// 缓存失效事件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InvalidationEvent {
+    /// 删除统一缓存中的指定键
+    CacheKey { key: String },
+    /// 服务 API 配置已变更（更新、轮换、启停或删除）
+    ServiceApi { user_id: i32, service_api_id: i32 },
+}
+
+impl InvalidationEvent {
+    /// 事件对应的统一缓存键
+    #[must_use]
+    pub fn cache_key(&self) -> String {
+        match self {
+            Self::CacheKey { key } => key.clone(),
+            Self::ServiceApi {
+                user_id,
+                service_api_id,
+            } => CacheKey::UserApiConfig {
+                user_id: *user_id,
+                api_id: *service_api_id,
+            }
+            .build(),
+        }
+    }
+}
+
+/// 广播的消息：附带来源实例，订阅方据此跳过自己发布的事件
+#[derive(Serialize, Deserialize)]
+struct InvalidationMessage {
+    origin: String,
+    event: InvalidationEvent,
+}
+
+/// 本地缓存的失效回调
+pub type InvalidationListener = Box<dyn Fn(&InvalidationEvent) + Send + Sync>;
+
+/// 失效事件的传输通道
+pub enum InvalidationTransport {
+    /// 单实例部署（内存缓存后端），不广播
+    Disabled,
+    /// Redis 发布/订阅
+    Redis {
+        client: redis::Client,
+        publisher: OnceCell<redis::aio::ConnectionManager>,
+    },
+    /// 进程内广播通道，多个实例共享同一发送端（用于测试与嵌入式部署）
+    Local(broadcast::Sender<String>),
+}
+
+impl InvalidationTransport {
+    /// 按缓存配置选择传输通道：Redis 后端使用发布/订阅，内存后端不广播
+    pub fn from_config(config: &CacheConfig) -> Result<Self> {
+        match config.cache_type {
+            CacheType::Memory => Ok(Self::Disabled),
+            CacheType::Redis => {
+                let redis_config = config
+                    .redis
+                    .as_ref()
+                    .ok_or_else(|| CacheError::config("Redis 缓存未提供配置"))?;
+                let client = redis::Client::open(redis_config.url.as_str())
+                    .context("创建 Redis 客户端失败")?;
+                Ok(Self::Redis {
+                    client,
+                    publisher: OnceCell::new(),
+                })
+            }
+        }
+    }
+
+    async fn publish(&self, payload: String) -> Result<()> {
+        match self {
+            Self::Disabled => Ok(()),
+            Self::Redis { client, publisher } => {
+                let mut conn = publisher
+                    .get_or_try_init(|| async {
+                        redis::aio::ConnectionManager::new(client.clone())
+                            .await
+                            .context("建立 Redis 连接失败")
+                    })
+                    .await?
+                    .clone();
+                let _: usize = conn
+                    .publish(INVALIDATION_CHANNEL, payload)
+                    .await
+                    .context("Redis PUBLISH 失败")?;
+                Ok(())
+            }
+            Self::Local(sender) => {
+                // 没有订阅方时发送失败，与 Redis 无订阅者时一致，不视为错误
+                let _ = sender.send(payload);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 缓存失效广播器：每个实例持有一个
+pub struct CacheInvalidator {
+    instance_id: String,
+    transport: InvalidationTransport,
+    cache: Arc<CacheManager>,
+    listeners: RwLock<Vec<InvalidationListener>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl CacheInvalidator {
+    #[must_use]
+    pub fn new(cache: Arc<CacheManager>, transport: InvalidationTransport) -> Self {
+        Self {
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            transport,
+            cache,
+            listeners: RwLock::new(Vec::new()),
+            handle: Mutex::new(None),
+        }
+    }
+
+    /// 是否向其他实例广播
+    #[must_use]
+    pub const fn is_distributed(&self) -> bool {
+        !matches!(self.transport, InvalidationTransport::Disabled)
+    }
+
+    /// 注册本地缓存的失效回调
+    pub fn add_listener(&self, listener: InvalidationListener) {
+        self.listeners
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(listener);
+    }
+
+    /// 发布失效事件：先在本实例内生效，再广播给其他实例
+    pub async fn publish(&self, event: InvalidationEvent) -> Result<()> {
+        self.apply(&event).await;
+        if !self.is_distributed() {
+            return Ok(());
+        }
+        let payload = serde_json::to_string(&InvalidationMessage {
+            origin: self.instance_id.clone(),
+            event,
+        })
+        .context("序列化缓存失效事件失败")?;
+        self.transport.publish(payload).await
+    }
+
+    /// 启动订阅任务（内存缓存后端不订阅）
+    pub async fn start(self: &Arc<Self>) -> Result<()> {
+        let mut handle = self.handle.lock().await;
+        if handle.is_some() {
+            return Ok(());
+        }
+        let invalidator = Arc::clone(self);
+        *handle = match &self.transport {
+            InvalidationTransport::Disabled => {
+                linfo!(
+                    "system",
+                    LogStage::Startup,
+                    LogComponent::Cache,
+                    "cache_invalidation_local_only",
+                    "内存缓存为单实例部署，缓存失效不跨实例广播"
+                );
+                return Ok(());
+            }
+            InvalidationTransport::Redis { client, .. } => {
+                let client = client.clone();
+                Some(tokio::spawn(
+                    async move { invalidator.run_redis(client).await },
+                ))
+            }
+            // 先订阅再返回，保证启动后发布的事件不会丢失
+            InvalidationTransport::Local(sender) => {
+                let receiver = sender.subscribe();
+                Some(tokio::spawn(async move {
+                    invalidator.run_local(receiver).await;
+                }))
+            }
+        };
+        linfo!(
+            "system",
+            LogStage::Startup,
+            LogComponent::Cache,
+            "cache_invalidation_started",
+            "缓存失效订阅任务已启动",
+            channel = INVALIDATION_CHANNEL
+        );
+        Ok(())
+    }
+
+    /// 停止订阅任务
+    pub async fn stop(&self) {
+        if let Some(handle) = self.handle.lock().await.take() {
+            handle.abort();
+            let _ = handle.await;
+        }
+    }
+
+    async fn run_redis(&self, client: redis::Client) {
+        loop {
+            if let Err(err) = self.subscribe_redis(&client).await {
+                lwarn!(
+                    "system",
+                    LogStage::Cache,
+                    LogComponent::Cache,
+                    "cache_invalidation_subscribe_failed",
+                    "缓存失效订阅中断，稍后重新订阅",
+                    error = %err
+                );
+            }
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    }
+
+    async fn subscribe_redis(&self, client: &redis::Client) -> Result<()> {
+        let mut pubsub = client
+            .get_async_pubsub()
+            .await
+            .context("建立 Redis 订阅连接失败")?;
+        pubsub
+            .subscribe(INVALIDATION_CHANNEL)
+            .await
+            .context("订阅缓存失效频道失败")?;
+        let mut messages = pubsub.into_on_message();
+        while let Some(message) = messages.next().await {
+            match message.get_payload::<String>() {
+                Ok(payload) => self.receive(&payload).await,
+                Err(err) => {
+                    lwarn!(
+                        "system",
+                        LogStage::Cache,
+                        LogComponent::Cache,
+                        "cache_invalidation_payload_invalid",
+                        "缓存失效消息读取失败",
+                        error = %err
+                    );
+                }
+            }
+        }
+        Err(CacheError::operation("缓存失效订阅连接已关闭").into())
+    }
+
+    async fn run_local(&self, mut receiver: broadcast::Receiver<String>) {
+        loop {
+            match receiver.recv().await {
+                Ok(payload) => self.receive(&payload).await,
+                Err(RecvError::Lagged(skipped)) => {
+                    lwarn!(
+                        "system",
+                        LogStage::Cache,
+                        LogComponent::Cache,
+                        "cache_invalidation_lagged",
+                        "缓存失效事件积压，部分事件被丢弃",
+                        skipped = skipped
+                    );
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// 处理其他实例广播的事件
+    async fn receive(&self, payload: &str) {
+        let message = match serde_json::from_str::<InvalidationMessage>(payload) {
+            Ok(message) => message,
+            Err(err) => {
+                lwarn!(
+                    "system",
+                    LogStage::Cache,
+                    LogComponent::Cache,
+                    "cache_invalidation_payload_invalid",
+                    "无法解析缓存失效事件",
+                    error = %err
+                );
+                return;
+            }
+        };
+        if message.origin != self.instance_id {
+            self.apply(&message.event).await;
+        }
+    }
+
+    /// 清理统一缓存条目并通知本地缓存
+    async fn apply(&self, event: &InvalidationEvent) {
+        let key = event.cache_key();
+        if let Err(err) = self.cache.delete(&key).await {
+            lwarn!(
+                "system",
+                LogStage::Cache,
+                LogComponent::Cache,
+                "cache_invalidation_delete_failed",
+                "删除失效缓存条目失败",
+                cache_key = %key,
+                error = %err
+            );
+        }
+        for listener in self
+            .listeners
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            listener(event);
+        }
+        ldebug!(
+            "system",
+            LogStage::Cache,
+            LogComponent::Cache,
+            "cache_invalidated",
+            "缓存条目已失效",
+            cache_key = %key,
+            event = ?event
+        );
+    }
+}
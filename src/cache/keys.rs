@@ -32,6 +32,15 @@ pub enum CacheKey {
     /// 速率限制缓存 - `ratelimit:{user_id}:{endpoint}`
     RateLimit { user_id: i32, endpoint: String },
 
+    /// 提供商全局速率限制缓存 - `ratelimit:provider:{provider_type_id}`
+    ProviderRateLimit { provider_type_id: i32 },
+
+    /// 模型级速率限制缓存 - `ratelimit:model:{provider_type_id}:{model}`
+    ModelRateLimit {
+        provider_type_id: i32,
+        model: String,
+    },
+
     /// 提供商配置缓存 - `provider:config:{provider}`
     ProviderConfig { provider: String },
 
@@ -74,6 +83,18 @@ impl CacheKey {
             Self::RateLimit { user_id, endpoint } => {
                 format!("ratelimit:{user_id}:{}", sanitize_endpoint(endpoint))
             }
+            Self::ProviderRateLimit { provider_type_id } => {
+                format!("ratelimit:provider:{provider_type_id}")
+            }
+            Self::ModelRateLimit {
+                provider_type_id,
+                model,
+            } => {
+                format!(
+                    "ratelimit:model:{provider_type_id}:{}",
+                    sanitize_endpoint(model)
+                )
+            }
             Self::ProviderConfig { provider } => {
                 format!("provider:config:{provider}")
             }
@@ -102,6 +123,10 @@ impl CacheKey {
             }
             Self::Config { .. } => "config:*".to_string(),
             Self::RateLimit { user_id, .. } => format!("ratelimit:{user_id}:*"),
+            Self::ProviderRateLimit { .. } => "ratelimit:provider:*".to_string(),
+            Self::ModelRateLimit {
+                provider_type_id, ..
+            } => format!("ratelimit:model:{provider_type_id}:*"),
             Self::ProviderConfig { .. } => "provider:config:*".to_string(),
             Self::AuthToken { .. } => "auth:token:*".to_string(),
             Self::Custom { prefix, .. } => format!("custom:{prefix}:*"),
@@ -118,7 +143,9 @@ impl CacheKey {
             Self::ApiHealth { .. } => "health",
             Self::RequestStats { .. } | Self::DailyStats { .. } => "stats",
             Self::Config { .. } => "config",
-            Self::RateLimit { .. } => "ratelimit",
+            Self::RateLimit { .. }
+            | Self::ProviderRateLimit { .. }
+            | Self::ModelRateLimit { .. } => "ratelimit",
             Self::ProviderConfig { .. } => "provider",
             Self::AuthToken { .. } => "auth",
             Self::Custom { .. } => "custom",
@@ -130,7 +157,11 @@ impl CacheKey {
     pub const fn is_temporary(&self) -> bool {
         matches!(
             self,
-            Self::UserSession { .. } | Self::AuthToken { .. } | Self::RateLimit { .. }
+            Self::UserSession { .. }
+                | Self::AuthToken { .. }
+                | Self::RateLimit { .. }
+                | Self::ProviderRateLimit { .. }
+                | Self::ModelRateLimit { .. }
         )
     }
 
@@ -232,6 +263,21 @@ impl CacheKeyBuilder {
         }
     }
 
+    /// 构建提供商全局速率限制缓存键
+    #[must_use]
+    pub const fn provider_rate_limit(provider_type_id: i32) -> CacheKey {
+        CacheKey::ProviderRateLimit { provider_type_id }
+    }
+
+    /// 构建模型级速率限制缓存键
+    #[must_use]
+    pub fn model_rate_limit(provider_type_id: i32, model: &str) -> CacheKey {
+        CacheKey::ModelRateLimit {
+            provider_type_id,
+            model: model.to_string(),
+        }
+    }
+
     /// 构建提供商配置缓存键
     #[must_use]
     pub fn provider_config(provider: &str) -> CacheKey {
@@ -285,6 +331,9 @@ mod tests {
 
         let health_key = CacheKeyBuilder::api_health("openai", "chat");
         assert_eq!(health_key.build(), "health:api:openai:chat");
+
+        let model_key = CacheKeyBuilder::model_rate_limit(3, "gpt-4o");
+        assert_eq!(model_key.build(), "ratelimit:model:3:gpt-4o");
     }
 
     #[test]
@@ -5,13 +5,16 @@
 pub mod abstract_cache;
 pub mod client;
 pub mod integration;
+pub mod invalidation;
 pub mod keys;
 pub mod strategies;
 
 pub use abstract_cache::{
     CacheManager, CacheProvider, CacheProviderType, CacheStats, MemoryCache, RedisCache,
+    fail_open_total, record_fail_open,
 };
 pub use client::{CacheClient, RedisConfig};
 pub use integration::{CacheDecorator, CacheFacade};
+pub use invalidation::{CacheInvalidator, InvalidationEvent, InvalidationTransport};
 pub use keys::{CacheKey, CacheKeyBuilder};
 pub use strategies::{CacheStrategies, CacheStrategy, CacheTtl};
@@ -9,6 +9,7 @@ use crate::{
     types::TokenCount,
 };
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -849,3 +850,195 @@ fn json_path_lookup(v: &Value, path: &str) -> Option<Value> {
     }
     Some(cur.clone())
 }
+
+/// 请求体改写规则
+///
+/// 按声明顺序依次作用于请求体 JSON，路径使用 `.` 分隔，数字段表示数组下标。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformRule {
+    /// 设置字段值（中间缺失的对象会自动创建）
+    Set { path: String, value: Value },
+    /// 删除字段
+    Remove { path: String },
+    /// 重命名（移动）字段，源字段不存在时跳过
+    Rename { from: String, to: String },
+}
+
+/// 单个服务 API 允许配置的改写规则上限
+const MAX_TRANSFORM_RULES: usize = 32;
+
+impl TransformRule {
+    fn validate(&self, index: usize) -> Result<()> {
+        let invalid = |reason: &str| -> crate::error::ProxyError {
+            crate::error::conversion::ConversionError::message(format!(
+                "request_transform_rules[{index}]: {reason}"
+            ))
+            .into()
+        };
+
+        match self {
+            Self::Set { path, .. } | Self::Remove { path } => {
+                if split_transform_path(path).is_none() {
+                    return Err(invalid("path 不能为空且不能包含空段"));
+                }
+            }
+            Self::Rename { from, to } => {
+                if split_transform_path(from).is_none() || split_transform_path(to).is_none() {
+                    return Err(invalid("from/to 不能为空且不能包含空段"));
+                }
+                if from == to || to.starts_with(&format!("{from}.")) {
+                    return Err(invalid("to 不能等于 from 或位于 from 之下"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 作用于请求体，返回 `true` 表示请求体被修改
+    pub fn apply(&self, body: &mut Value) -> bool {
+        match self {
+            Self::Set { path, value } => set_by_path(body, path, value.clone()).is_ok(),
+            Self::Remove { path } => remove_by_path(body, path).is_some(),
+            Self::Rename { from, to } => {
+                let Some(value) = remove_by_path(body, from) else {
+                    return false;
+                };
+                match set_by_path(body, to, value) {
+                    Ok(()) => true,
+                    Err(value) => {
+                        // 目标路径不可写时放回原处，保证请求体不丢字段
+                        let _ = set_by_path(body, from, value);
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `严格解析 request_transform_rules（JSON 数组）并校验每条规则`
+pub fn parse_transform_rules(value: &Value) -> Result<Vec<TransformRule>> {
+    let items = value.as_array().ok_or_else(|| {
+        crate::error::conversion::ConversionError::message("request_transform_rules 必须是数组")
+    })?;
+    if items.len() > MAX_TRANSFORM_RULES {
+        return Err(crate::error::conversion::ConversionError::message(format!(
+            "request_transform_rules 最多 {MAX_TRANSFORM_RULES} 条"
+        ))
+        .into());
+    }
+
+    let mut rules = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        let rule = serde_json::from_value::<TransformRule>(item.clone()).map_err(|e| {
+            crate::error::conversion::ConversionError::message(format!(
+                "request_transform_rules[{index}]: {e}"
+            ))
+        })?;
+        rule.validate(index)?;
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+/// 依次应用改写规则，返回 `true` 表示至少有一条规则生效
+pub fn apply_transform_rules(rules: &[TransformRule], body: &mut Value) -> bool {
+    rules
+        .iter()
+        .fold(false, |modified, rule| rule.apply(body) || modified)
+}
+
+fn split_transform_path(path: &str) -> Option<(Vec<&str>, &str)> {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    if segments.iter().any(|seg| seg.trim().is_empty()) {
+        return None;
+    }
+    let last = segments.pop()?;
+    Some((segments, last))
+}
+
+fn parent_mut<'a>(root: &'a mut Value, parents: &[&str], create: bool) -> Option<&'a mut Value> {
+    let mut cur = root;
+    for seg in parents {
+        cur = match cur {
+            Value::Object(map) if create => map
+                .entry((*seg).to_string())
+                .or_insert_with(|| Value::Object(serde_json::Map::new())),
+            Value::Object(map) => map.get_mut(*seg)?,
+            Value::Array(items) => items.get_mut(seg.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(cur)
+}
+
+fn set_by_path(root: &mut Value, path: &str, value: Value) -> std::result::Result<(), Value> {
+    let Some((parents, last)) = split_transform_path(path) else {
+        return Err(value);
+    };
+    match parent_mut(root, &parents, true) {
+        Some(Value::Object(map)) => {
+            map.insert(last.to_string(), value);
+            Ok(())
+        }
+        Some(Value::Array(items)) => {
+            match last.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+                Some(slot) => {
+                    *slot = value;
+                    Ok(())
+                }
+                None => Err(value),
+            }
+        }
+        _ => Err(value),
+    }
+}
+
+fn remove_by_path(root: &mut Value, path: &str) -> Option<Value> {
+    let (parents, last) = split_transform_path(path)?;
+    match parent_mut(root, &parents, false)? {
+        Value::Object(map) => map.remove(last),
+        Value::Array(items) => {
+            let index = last.parse::<usize>().ok()?;
+            (index < items.len()).then(|| items.remove(index))
+        }
+        _ => None,
+    }
+}
+
+/// 响应元数据提取器
+///
+/// 按配置的路径从响应 JSON 中提取字段；可依次观察多个流式帧，后出现的非空值覆盖先前的值
+/// （`model` 每帧都有，`finish_reason` 只在末尾帧非空）。
+#[derive(Debug, Clone)]
+pub struct ResponseMetadataExtractor {
+    paths: Vec<String>,
+    fields: serde_json::Map<String, Value>,
+}
+
+impl ResponseMetadataExtractor {
+    /// 创建提取器
+    #[must_use]
+    pub fn new(paths: &[String]) -> Self {
+        Self {
+            paths: paths.to_vec(),
+            fields: serde_json::Map::new(),
+        }
+    }
+
+    /// 观察一个响应 JSON（非流式为整个响应体，流式为单个事件）
+    pub fn observe(&mut self, json: &Value) {
+        for path in &self.paths {
+            if let Some(value) = json_path_lookup(json, path).filter(|v| !v.is_null()) {
+                self.fields.insert(path.clone(), value);
+            }
+        }
+    }
+
+    /// 输出提取结果（`path -> value`），未提取到任何字段时返回 `None`
+    #[must_use]
+    pub fn finish(self) -> Option<Value> {
+        (!self.fields.is_empty()).then_some(Value::Object(self.fields))
+    }
+}
@@ -6,6 +6,9 @@ pub mod field_extractor;
 pub mod request;
 pub mod response;
 pub mod service;
+pub mod stream_usage;
+pub mod token_estimator;
 pub mod types;
 pub mod usage_model;
+pub mod usage_reconcile;
 pub mod util;
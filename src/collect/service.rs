@@ -5,13 +5,19 @@
 use std::sync::Arc;
 
 use crate::collect::{
-    request, response,
-    types::{CollectedCost, CollectedMetrics, RequestDetails, RequestStats, ResponseStats},
-    usage_model,
+    request, response, token_estimator,
+    types::{
+        CollectedCost, CollectedMetrics, ComputedStats, RequestDetails, RequestStats,
+        ResponseStats, TokenUsageMetrics,
+    },
+    usage_model, usage_reconcile,
 };
+use crate::config::{ResponseBodyConfig, TokenEstimationConfig};
 use crate::pricing::{PricingCalculatorService, TokenUsage};
 use crate::proxy::ProxyContext;
+use crate::proxy::upstream_timing;
 use crate::{
+    linfo,
     logging::{LogComponent, LogStage},
     lwarn,
 };
@@ -21,12 +27,64 @@ use pingora_proxy::Session;
 /// 采集服务，实现“Collect”阶段的全部逻辑
 pub struct CollectService {
     pricing: Arc<PricingCalculatorService>,
+    /// 需要写入追踪记录的响应字段路径
+    response_metadata_fields: Vec<String>,
+    /// 响应体处理配置（区分 JSON 与不透明内容）
+    response_body: ResponseBodyConfig,
+    /// 上游未返回用量时的本地估算配置
+    token_estimation: TokenEstimationConfig,
 }
 
 impl CollectService {
     #[must_use]
-    pub const fn new(pricing: Arc<PricingCalculatorService>) -> Self {
-        Self { pricing }
+    pub fn new(
+        pricing: Arc<PricingCalculatorService>,
+        response_metadata_fields: Vec<String>,
+    ) -> Self {
+        Self {
+            pricing,
+            response_metadata_fields,
+            response_body: ResponseBodyConfig::default(),
+            token_estimation: TokenEstimationConfig::default(),
+        }
+    }
+
+    /// 设置响应体处理配置
+    #[must_use]
+    pub fn with_response_body_config(mut self, config: ResponseBodyConfig) -> Self {
+        self.response_body = config;
+        self
+    }
+
+    /// 设置 Token 估算配置
+    #[must_use]
+    pub fn with_token_estimation(mut self, config: TokenEstimationConfig) -> Self {
+        self.token_estimation = config;
+        self
+    }
+
+    /// 是否在上游未返回用量时估算 Token（流式响应需要逐帧拼接输出文本）
+    #[must_use]
+    pub const fn estimates_tokens(&self) -> bool {
+        self.token_estimation.enabled
+    }
+
+    /// 需要写入追踪记录的响应字段路径
+    #[must_use]
+    pub fn response_metadata_fields(&self) -> &[String] {
+        &self.response_metadata_fields
+    }
+
+    /// 是否缓存流式响应的响应体
+    #[must_use]
+    pub const fn captures_streaming_body(&self) -> bool {
+        self.response_body.capture_streaming_body
+    }
+
+    /// 费用计算服务（流式响应的单请求费用上限需要预先加载定价）
+    #[must_use]
+    pub const fn pricing(&self) -> &Arc<PricingCalculatorService> {
+        &self.pricing
     }
 
     /// 收集请求摘要（供认证阶段启动追踪时使用）
@@ -52,7 +110,11 @@ impl CollectService {
         upstream_response: &ResponseHeader,
         ctx: &mut ProxyContext,
     ) -> ResponseStats {
-        response::collect_details(upstream_response, ctx)
+        let stats = response::collect_details(upstream_response, ctx);
+        ctx.response.is_opaque_body = !self
+            .response_body
+            .is_json(ctx.response.details.content_type.as_deref());
+        stats
     }
 
     /// 在请求结束时生成指标快照
@@ -62,13 +124,38 @@ impl CollectService {
         status_code: u16,
     ) -> CollectedMetrics {
         let computed = usage_model::finalize_eos(ctx);
-        let usage = computed.usage.clone();
+        let mut usage = computed.usage.clone();
+        if let Some(estimated) = self.estimate_missing_usage(ctx, &computed, status_code) {
+            usage = estimated;
+        }
         ctx.response.usage_final = Some(usage.clone());
         // 尝试更新最终模型名称
         ctx.request.requested_model.clone_from(&computed.model_name);
+        // WebSocket 会话记录传输统计；流式响应优先使用逐帧提取的字段，普通响应按配置路径提取
+        let mut response_metadata = ctx.response.websocket.as_ref().map_or_else(
+            || {
+                computed.response_metadata.clone().or_else(|| {
+                    usage_model::extract_response_metadata(ctx, &self.response_metadata_fields)
+                })
+            },
+            |session| Some(session.metadata(ctx.request.body_received_size)),
+        );
+        if ctx.response.body_capture_skipped {
+            response_metadata = Some(usage_model::mark_body_not_captured(response_metadata));
+        }
+        response_metadata =
+            upstream_timing::mark_phases(response_metadata, ctx.trace.upstream_timing.phases());
+        if let Some(source) = computed.usage_source {
+            response_metadata = Some(usage_reconcile::mark_usage_source(
+                response_metadata,
+                source,
+            ));
+        }
 
+        let user_id = ctx.routing.user_service_api.as_ref().map(|u| u.user_id);
         let (cost_value, cost_currency) = self
             .calculate_cost(
+                user_id,
                 ctx.routing.provider_type.as_ref(),
                 ctx.request.requested_model.as_deref(),
                 &usage,
@@ -78,7 +165,7 @@ impl CollectService {
 
         CollectedMetrics {
             request_id: ctx.request_id.clone(),
-            user_id: ctx.routing.user_service_api.as_ref().map(|u| u.user_id),
+            user_id,
             user_service_api_id: ctx.routing.user_service_api.as_ref().map(|u| u.id),
             provider_type_id: ctx.routing.provider_type.as_ref().map(|p| p.id),
             model: ctx.request.requested_model.clone(),
@@ -89,11 +176,49 @@ impl CollectService {
             },
             duration_ms: ctx.start_time.elapsed().as_millis(),
             status_code,
+            request_bytes: ctx.request_bytes(),
+            response_bytes: ctx.response_bytes(),
+            response_metadata,
         }
     }
 
+    /// 上游未返回用量（输入输出均为 0）的成功请求按本地分词器估算
+    fn estimate_missing_usage(
+        &self,
+        ctx: &ProxyContext,
+        computed: &ComputedStats,
+        status_code: u16,
+    ) -> Option<TokenUsageMetrics> {
+        if !self.token_estimation.enabled
+            || status_code >= 400
+            || ctx.response.websocket.is_some()
+            || computed.usage.total_tokens.unwrap_or(0) > 0
+        {
+            return None;
+        }
+        let model = computed
+            .model_name
+            .as_deref()
+            .or(ctx.request.requested_model.as_deref())?;
+        let usage =
+            token_estimator::estimate_usage(ctx, model, computed.completion_text.as_deref())?;
+        linfo!(
+            &ctx.request_id,
+            LogStage::Response,
+            LogComponent::Statistics,
+            "token_usage_estimated",
+            "上游未返回用量，使用本地分词器估算",
+            model = model,
+            prompt_tokens = usage.prompt_tokens,
+            completion_tokens = usage.completion_tokens
+        );
+        Some(usage)
+    }
+
+    /// 计算请求费用；用户有免费额度时返回抵扣后的应付费用
     async fn calculate_cost(
         &self,
+        user_id: Option<i32>,
         provider: Option<&entity::provider_types::Model>,
         model_used: Option<&str>,
         usage: &crate::collect::types::TokenUsageMetrics,
@@ -113,12 +238,12 @@ impl CollectService {
             cache_read_tokens: usage.cache_read_tokens,
         };
 
-        match self
+        let result = match self
             .pricing
             .calculate_cost(model, provider_model.id, &token_usage, request_id)
             .await
         {
-            Ok(cost) => (Some(cost.total_cost), Some(cost.currency)),
+            Ok(cost) => cost,
             Err(err) => {
                 lwarn!(
                     request_id,
@@ -127,7 +252,30 @@ impl CollectService {
                     "cost_calculation_failed",
                     &format!("Failed to calculate cost: {err}")
                 );
-                (None, None)
+                return (None, None);
+            }
+        };
+
+        let Some(user_id) = user_id else {
+            return (Some(result.total_cost), Some(result.currency));
+        };
+        let list_cost = result.total_cost;
+        let currency = result.currency.clone();
+        match self
+            .pricing
+            .apply_included_quota(user_id, &token_usage, result, request_id)
+            .await
+        {
+            Ok(billed) => (Some(billed.total_cost), Some(billed.currency)),
+            Err(err) => {
+                lwarn!(
+                    request_id,
+                    LogStage::Internal,
+                    LogComponent::Statistics,
+                    "included_quota_failed",
+                    &format!("Failed to apply included quota, billing full cost: {err}")
+                );
+                (Some(list_cost), Some(currency))
             }
         }
     }
@@ -0,0 +1,506 @@
+//! 流式响应的最终用量汇总
+//!
+//! 各服务商在流式响应中上报用量的方式不同，逐事件简单累加会重复计数或漏计：
+//! - Anthropic：`message_start` 携带输入 Token，`message_delta` 携带截至当前的累计输出 Token；
+//! - Gemini：每个分块的 `usageMetadata` 都是累计值，以最后一次为准；
+//! - `OpenAI`：只有结束事件（最后一个 `usage` 非空的分块或 `response.completed`）携带用量。
+//!
+//! SSE 事件在响应分块到达时逐个解析，不依赖响应体缓存：长响应的缓存被截断时，
+//! 末尾的用量事件也不会丢失。`[DONE]` 等无数据事件直接忽略，连接提前关闭时保留已收到的最新用量。
+//!
+//! 跟踪器只缓存尚未结束的一个事件，且不超过 [`MAX_PENDING_EVENT_BYTES`]：超长事件（如内嵌大段 base64
+//! 的分块）直接丢弃到下一个事件边界，用量事件都很小，不受影响。
+
+use bytes::BytesMut;
+use entity::provider_types;
+use serde_json::Value;
+use tokio_util::codec::Decoder as _;
+
+use crate::collect::field_extractor::ResponseMetadataExtractor;
+use crate::collect::token_estimator::append_completion_text;
+use crate::collect::types::TokenUsageMetrics;
+use crate::collect::usage_model::{
+    extract_model_from_json, extract_raw_tokens_from_json, normalize,
+};
+use crate::proxy::provider_strategy::ProviderType;
+use crate::utils::event_stream::EventStreamData;
+
+/// 用量汇总方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamUsageMode {
+    /// 未识别的服务商：逐事件累加
+    Accumulate,
+    /// 用量为累计值或只在结束事件出现：以最后一次上报为准
+    LastReported,
+    /// Anthropic：输入取自 `message_start`，输出取自最后一个 `message_delta`
+    Anthropic,
+}
+
+impl StreamUsageMode {
+    /// 按服务商类型选择汇总方式
+    #[must_use]
+    pub fn for_provider(provider: Option<&provider_types::Model>) -> Self {
+        match provider.and_then(|p| ProviderType::from_str(&p.name)) {
+            Some(ProviderType::Anthropic) => Self::Anthropic,
+            Some(ProviderType::OpenAI | ProviderType::Gemini) => Self::LastReported,
+            None => Self::Accumulate,
+        }
+    }
+}
+
+/// 汇总结果
+#[derive(Debug, Clone, Default)]
+pub struct StreamUsageSummary {
+    pub usage: TokenUsageMetrics,
+    pub model: Option<String>,
+    /// 是否收到了携带最终用量的事件；为 `false` 时用量可能不完整（如连接提前关闭）
+    pub complete: bool,
+    /// 逐事件提取的响应元数据（未配置提取路径时为 `None`）
+    pub metadata: Option<Value>,
+    /// 逐事件拼接的输出文本（未开启收集时为 `None`）
+    pub completion_text: Option<String>,
+}
+
+/// 逐事件汇总流式响应用量
+#[derive(Debug, Clone)]
+pub struct StreamUsageAggregator {
+    mode: StreamUsageMode,
+    usage: TokenUsageMetrics,
+    model: Option<String>,
+    complete: bool,
+}
+
+impl StreamUsageAggregator {
+    #[must_use]
+    pub fn new(mode: StreamUsageMode) -> Self {
+        Self {
+            mode,
+            usage: TokenUsageMetrics::default(),
+            model: None,
+            complete: false,
+        }
+    }
+
+    /// 观察一个事件的 JSON 数据
+    pub fn observe(&mut self, provider: Option<&provider_types::Model>, event: &Value) {
+        if event.is_null() {
+            return;
+        }
+        if let Some(model) = extract_model_from_json(event) {
+            self.model = Some(model);
+        }
+
+        match self.mode {
+            StreamUsageMode::Accumulate => {
+                let usage = extract_raw_tokens_from_json(provider, event);
+                self.complete |= has_usage(&usage);
+                accumulate(&mut self.usage, &usage);
+            }
+            StreamUsageMode::LastReported => {
+                let usage = extract_raw_tokens_from_json(provider, event);
+                if has_usage(&usage) {
+                    self.usage = usage;
+                    self.complete = true;
+                }
+            }
+            StreamUsageMode::Anthropic => match event.get("type").and_then(Value::as_str) {
+                Some("message_start") => {
+                    if let Some(message) = event.get("message") {
+                        let usage = extract_raw_tokens_from_json(provider, message);
+                        merge_reported(&mut self.usage, &usage);
+                    }
+                }
+                Some("message_delta") => {
+                    let usage = extract_raw_tokens_from_json(provider, event);
+                    self.complete |= usage.completion_tokens.is_some_and(|n| n > 0);
+                    merge_reported(&mut self.usage, &usage);
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// 结束汇总，补齐缺失字段
+    #[must_use]
+    pub fn finish(self) -> StreamUsageSummary {
+        let mut usage = self.usage;
+        if self.mode == StreamUsageMode::Anthropic {
+            // 总量以汇总后的输入 + 输出为准，避免沿用 message_delta 单事件的计算结果
+            usage.total_tokens =
+                Some(usage.prompt_tokens.unwrap_or(0) + usage.completion_tokens.unwrap_or(0));
+        }
+        normalize(&mut usage);
+        StreamUsageSummary {
+            usage,
+            model: self.model,
+            complete: self.complete,
+            metadata: None,
+            completion_text: None,
+        }
+    }
+}
+
+/// 单个未结束事件的缓存上限，超过后丢弃该事件
+pub const MAX_PENDING_EVENT_BYTES: usize = 1024 * 1024;
+
+/// 增量解析 SSE 响应分块并汇总用量
+#[derive(Debug, Clone)]
+pub struct SseUsageTracker {
+    decoder: EventStreamData,
+    buffer: BytesMut,
+    /// 正在丢弃超长事件，直到遇到空行（事件边界）
+    discarding: bool,
+    /// 丢弃时上一个字节是否为换行，用于跨分块识别空行
+    discard_at_line_start: bool,
+    discarded_events: usize,
+    aggregator: StreamUsageAggregator,
+    metadata: Option<ResponseMetadataExtractor>,
+    completion_text: Option<String>,
+}
+
+impl SseUsageTracker {
+    #[must_use]
+    pub fn new(provider: Option<&provider_types::Model>) -> Self {
+        Self {
+            decoder: EventStreamData::new(),
+            buffer: BytesMut::new(),
+            discarding: false,
+            discard_at_line_start: false,
+            discarded_events: 0,
+            aggregator: StreamUsageAggregator::new(StreamUsageMode::for_provider(provider)),
+            metadata: None,
+            completion_text: None,
+        }
+    }
+
+    /// 同时按路径逐事件提取响应元数据（响应体不缓存时仍可记录元数据）
+    #[must_use]
+    pub fn with_metadata_fields(mut self, paths: &[String]) -> Self {
+        self.metadata = (!paths.is_empty()).then(|| ResponseMetadataExtractor::new(paths));
+        self
+    }
+
+    /// 同时拼接输出文本，供上游未返回用量时估算 Token
+    #[must_use]
+    pub fn with_completion_text(mut self, enabled: bool) -> Self {
+        self.completion_text = enabled.then(String::new);
+        self
+    }
+
+    fn observe_event(&mut self, provider: Option<&provider_types::Model>, event: &Value) {
+        self.aggregator.observe(provider, event);
+        if let Some(metadata) = self.metadata.as_mut() {
+            metadata.observe(event);
+        }
+        if let Some(text) = self.completion_text.as_mut() {
+            append_completion_text(event, text);
+        }
+    }
+
+    /// 观察一个响应分块；不完整的事件留待后续分块补齐
+    pub fn observe_chunk(&mut self, chunk: &[u8], provider: Option<&provider_types::Model>) {
+        let chunk = if self.discarding {
+            match self.skip_discarded(chunk) {
+                Some(rest) => rest,
+                None => return,
+            }
+        } else {
+            chunk
+        };
+        self.buffer.extend_from_slice(chunk);
+        loop {
+            match self.decoder.decode(&mut self.buffer) {
+                Ok(Some(event)) => self.observe_event(provider, &event.data),
+                Ok(None) => break,
+                // 非 UTF-8 行已被消费，跳过后继续解析后续事件
+                Err(_) => {}
+            }
+        }
+
+        if self.pending_bytes() > MAX_PENDING_EVENT_BYTES {
+            // 未结束的事件超过上限：丢弃已缓存部分，并跳过其余内容直到事件边界
+            let at_line_start = self.buffer.is_empty() || self.buffer.ends_with(b"\n");
+            self.buffer = BytesMut::new();
+            self.decoder = EventStreamData::new();
+            self.discarding = true;
+            self.discard_at_line_start = at_line_start;
+            self.discarded_events += 1;
+        }
+    }
+
+    /// 跳过被丢弃事件的剩余内容，返回事件边界之后的部分；本分块内没有边界时返回 `None`
+    fn skip_discarded<'a>(&mut self, chunk: &'a [u8]) -> Option<&'a [u8]> {
+        for (index, byte) in chunk.iter().enumerate() {
+            match byte {
+                b'\r' => {}
+                b'\n' if self.discard_at_line_start => {
+                    self.discarding = false;
+                    return Some(&chunk[index + 1..]);
+                }
+                b'\n' => self.discard_at_line_start = true,
+                _ => self.discard_at_line_start = false,
+            }
+        }
+        None
+    }
+
+    /// 截至目前已上报的用量（未补齐缺失字段，流结束前可能不完整）
+    #[must_use]
+    pub const fn usage_so_far(&self) -> &TokenUsageMetrics {
+        &self.aggregator.usage
+    }
+
+    /// 截至目前拼接的输出文本（未开启收集时为 `None`）
+    #[must_use]
+    pub fn completion_text(&self) -> Option<&str> {
+        self.completion_text.as_deref()
+    }
+
+    /// 当前缓存的未结束事件字节数（分块缓冲与已累积的 `data`）
+    #[must_use]
+    pub fn pending_bytes(&self) -> usize {
+        self.buffer.len() + self.decoder.pending_data_len()
+    }
+
+    /// 因超过缓存上限而丢弃的事件数
+    #[must_use]
+    pub const fn discarded_events(&self) -> usize {
+        self.discarded_events
+    }
+
+    /// 响应结束：处理缓冲区中未以空行结尾的最后一个事件
+    #[must_use]
+    pub fn finish(mut self, provider: Option<&provider_types::Model>) -> StreamUsageSummary {
+        if let Ok(Some(event)) = self.decoder.decode_eof(&mut self.buffer) {
+            self.observe_event(provider, &event.data);
+        }
+        let mut summary = self.aggregator.finish();
+        summary.metadata = self.metadata.and_then(ResponseMetadataExtractor::finish);
+        summary.completion_text = self.completion_text;
+        summary
+    }
+}
+
+fn has_usage(usage: &TokenUsageMetrics) -> bool {
+    [
+        usage.prompt_tokens,
+        usage.completion_tokens,
+        usage.total_tokens,
+    ]
+    .into_iter()
+    .any(|count| count.is_some_and(|n| n > 0))
+}
+
+fn accumulate(total: &mut TokenUsageMetrics, usage: &TokenUsageMetrics) {
+    let add = |acc: Option<u64>, value: Option<u64>| Some(acc.unwrap_or(0) + value.unwrap_or(0));
+    total.prompt_tokens = add(total.prompt_tokens, usage.prompt_tokens);
+    total.completion_tokens = add(total.completion_tokens, usage.completion_tokens);
+    total.total_tokens = add(total.total_tokens, usage.total_tokens);
+    total.cache_create_tokens = add(total.cache_create_tokens, usage.cache_create_tokens);
+    total.cache_read_tokens = add(total.cache_read_tokens, usage.cache_read_tokens);
+}
+
+/// 用事件中上报的非零值覆盖已有值（累计值语义）
+fn merge_reported(current: &mut TokenUsageMetrics, usage: &TokenUsageMetrics) {
+    let pick =
+        |current: Option<u64>, reported: Option<u64>| reported.filter(|n| *n > 0).or(current);
+    current.prompt_tokens = pick(current.prompt_tokens, usage.prompt_tokens);
+    current.completion_tokens = pick(current.completion_tokens, usage.completion_tokens);
+    current.cache_create_tokens = pick(current.cache_create_tokens, usage.cache_create_tokens);
+    current.cache_read_tokens = pick(current.cache_read_tokens, usage.cache_read_tokens);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn provider(id: i32, name: &str, token_mappings: &Value) -> provider_types::Model {
+        let now = chrono::Utc::now().naive_utc();
+        provider_types::Model {
+            id,
+            name: name.to_string(),
+            display_name: name.to_string(),
+            auth_type: "api_key".to_string(),
+            base_url: "example.com".to_string(),
+            is_active: true,
+            config_json: None,
+            token_mappings_json: Some(token_mappings.to_string()),
+            model_extraction_json: None,
+            auth_configs_json: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// 按任意长度切分，模拟网络分块到达
+    fn track(
+        provider: &provider_types::Model,
+        body: &str,
+        chunk_size: usize,
+    ) -> StreamUsageSummary {
+        let mut tracker = SseUsageTracker::new(Some(provider));
+        for chunk in body.as_bytes().chunks(chunk_size) {
+            tracker.observe_chunk(chunk, Some(provider));
+        }
+        tracker.finish(Some(provider))
+    }
+
+    fn anthropic() -> provider_types::Model {
+        provider(
+            9301,
+            "anthropic",
+            &json!({
+                "tokens_prompt": {"type": "direct", "path": "usage.input_tokens"},
+                "tokens_completion": {"type": "direct", "path": "usage.output_tokens"},
+                "tokens_total": {"type": "expression", "formula": "usage.input_tokens + usage.output_tokens"},
+                "cache_create_tokens": {"type": "direct", "path": "usage.cache_creation_input_tokens"},
+                "cache_read_tokens": {"type": "direct", "path": "usage.cache_read_input_tokens"}
+            }),
+        )
+    }
+
+    #[test]
+    fn anthropic_combines_message_start_and_final_delta() {
+        let body = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"model\":\"claude-sonnet-4\",\"usage\":{\"input_tokens\":25,\"output_tokens\":1,\"cache_read_input_tokens\":10}}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"Hi\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":15}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":42}}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+
+        let summary = track(&anthropic(), body, 7);
+        assert!(summary.complete);
+        assert_eq!(summary.usage.prompt_tokens, Some(25));
+        assert_eq!(summary.usage.completion_tokens, Some(42));
+        assert_eq!(summary.usage.total_tokens, Some(67));
+        assert_eq!(summary.usage.cache_read_tokens, Some(10));
+        assert_eq!(summary.model.as_deref(), Some("claude-sonnet-4"));
+    }
+
+    #[test]
+    fn oversized_event_is_discarded_until_next_boundary() {
+        let provider = anthropic();
+        let huge = "A".repeat(MAX_PENDING_EVENT_BYTES + 1024);
+        let body = format!(
+            concat!(
+                "data: {{\"type\":\"message_start\",\"message\":{{\"usage\":{{\"input_tokens\":25,\"output_tokens\":1}}}}}}\n\n",
+                "data: {{\"type\":\"content_block_delta\",\"delta\":{{\"text\":\"{}\"}}}}\n",
+                "data: {{\"still\":\"part of the oversized event\"}}\n\n",
+                "data: {{\"type\":\"message_delta\",\"usage\":{{\"output_tokens\":42}}}}\n\n",
+            ),
+            huge
+        );
+
+        let mut tracker = SseUsageTracker::new(Some(&provider));
+        let mut peak = 0;
+        for chunk in body.as_bytes().chunks(64 * 1024) {
+            tracker.observe_chunk(chunk, Some(&provider));
+            peak = peak.max(tracker.pending_bytes());
+        }
+        assert!(peak <= MAX_PENDING_EVENT_BYTES + 64 * 1024);
+        assert_eq!(tracker.discarded_events(), 1);
+
+        let summary = tracker.finish(Some(&provider));
+        assert!(summary.complete);
+        assert_eq!(summary.usage.prompt_tokens, Some(25));
+        assert_eq!(summary.usage.completion_tokens, Some(42));
+    }
+
+    #[test]
+    fn anthropic_stream_closed_early_keeps_input_usage() {
+        let body = "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":25,\"output_tokens\":1}}}\n\n\
+                    data: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"Hi\"}}\n\n";
+
+        let summary = track(&anthropic(), body, 16);
+        assert!(!summary.complete);
+        assert_eq!(summary.usage.prompt_tokens, Some(25));
+        assert_eq!(summary.usage.total_tokens, Some(26));
+    }
+
+    #[test]
+    fn gemini_uses_final_cumulative_usage_metadata() {
+        let gemini = provider(
+            9302,
+            "gemini",
+            &json!({
+                "tokens_prompt": {"type": "direct", "path": "usageMetadata.promptTokenCount"},
+                "tokens_completion": {"type": "direct", "path": "usageMetadata.candidatesTokenCount"},
+                "tokens_total": {"type": "direct", "path": "usageMetadata.totalTokenCount"}
+            }),
+        );
+        // 每个分块都携带累计用量，最后一个分块（含 finishReason）为最终值；最后一行没有结尾空行
+        let body = concat!(
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"A\"}]}}],\"usageMetadata\":{\"promptTokenCount\":12,\"candidatesTokenCount\":3,\"totalTokenCount\":15},\"modelVersion\":\"gemini-2.0-flash\"}\r\n\r\n",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"B\"}]}}],\"usageMetadata\":{\"promptTokenCount\":12,\"candidatesTokenCount\":9,\"totalTokenCount\":21}}\r\n\r\n",
+            "data: {\"candidates\":[{\"finishReason\":\"STOP\"}],\"usageMetadata\":{\"promptTokenCount\":12,\"candidatesTokenCount\":20,\"totalTokenCount\":32}}",
+        );
+
+        let summary = track(&gemini, body, 11);
+        assert!(summary.complete);
+        assert_eq!(summary.usage.prompt_tokens, Some(12));
+        assert_eq!(summary.usage.completion_tokens, Some(20));
+        assert_eq!(summary.usage.total_tokens, Some(32));
+    }
+
+    #[test]
+    fn openai_takes_usage_from_final_chunk_and_ignores_done() {
+        let openai = provider(
+            9303,
+            "openai",
+            &json!({
+                "tokens_prompt": {"type": "direct", "path": "usage.prompt_tokens"},
+                "tokens_completion": {"type": "direct", "path": "usage.completion_tokens"},
+                "tokens_total": {"type": "direct", "path": "usage.total_tokens"}
+            }),
+        );
+        let body = concat!(
+            "data: {\"model\":\"gpt-4o\",\"choices\":[{\"delta\":{\"content\":\"Hel\"}}],\"usage\":null}\n\n",
+            "data: {\"model\":\"gpt-4o\",\"choices\":[{\"delta\":{\"content\":\"lo\"}}],\"usage\":null}\n\n",
+            "data: {\"model\":\"gpt-4o\",\"choices\":[],\"usage\":{\"prompt_tokens\":8,\"completion_tokens\":2,\"total_tokens\":10}}\n\n",
+            "data: [DONE]\n\n",
+        );
+
+        let summary = track(&openai, body, 5);
+        assert!(summary.complete);
+        assert!(summary.completion_text.is_none());
+        assert_eq!(summary.usage.prompt_tokens, Some(8));
+        assert_eq!(summary.usage.completion_tokens, Some(2));
+        assert_eq!(summary.usage.total_tokens, Some(10));
+        assert_eq!(summary.model.as_deref(), Some("gpt-4o"));
+    }
+
+    #[test]
+    fn completion_text_is_collected_when_enabled() {
+        let openai = provider(
+            9306,
+            "openai",
+            &json!({
+                "tokens_prompt": {"type": "direct", "path": "usage.prompt_tokens"},
+                "tokens_completion": {"type": "direct", "path": "usage.completion_tokens"},
+                "tokens_total": {"type": "direct", "path": "usage.total_tokens"}
+            }),
+        );
+        // 网关未返回用量事件
+        let body = concat!(
+            "data: {\"model\":\"gpt-4o\",\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"model\":\"gpt-4o\",\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let mut tracker = SseUsageTracker::new(Some(&openai)).with_completion_text(true);
+        for chunk in body.as_bytes().chunks(9) {
+            tracker.observe_chunk(chunk, Some(&openai));
+        }
+
+        let summary = tracker.finish(Some(&openai));
+        assert!(!summary.complete);
+        assert_eq!(summary.usage.total_tokens, Some(0));
+        assert_eq!(summary.completion_text.as_deref(), Some("Hello"));
+    }
+}
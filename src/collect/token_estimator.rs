@@ -0,0 +1,323 @@
+//! # 本地 Token 估算
+//!
+//! 上游未返回用量时，用 `tiktoken` 分词器从请求与响应文本估算 Token 数：
+//! - 输入：按 `OpenAI` Chat 格式计入每条消息的固定开销（角色、分隔符）与内容；
+//! - 输出：非流式取 `choices[].message.content`，流式拼接 `choices[].delta.content`
+//!   与 Responses API 的 `response.output_text.delta`；Anthropic 的 `content_block_delta` 与 Gemini 的
+//!   `candidates[].content.parts[].text` 同样拼接（供流式费用估算使用）。
+//!
+//! 只对分词器能识别的模型（`OpenAI` 系列）估算，其它模型返回 `None`。
+
+use std::sync::LazyLock;
+
+use bytes::BytesMut;
+use serde_json::Value;
+use tiktoken_rs::CoreBPE;
+use tiktoken_rs::tokenizer::{Tokenizer, get_tokenizer};
+use tokio_util::codec::Decoder as _;
+
+use crate::collect::types::TokenUsageMetrics;
+use crate::collect::util::decompress_for_stats;
+use crate::proxy::ProxyContext;
+use crate::types::TokenCount;
+use crate::utils::event_stream::EventStreamData;
+
+/// 每条消息的固定开销（`<|start|>{role}\n{content}<|end|>\n`）
+const TOKENS_PER_MESSAGE: TokenCount = 3;
+/// 消息带 `name` 字段时的额外开销
+const TOKENS_PER_NAME: TokenCount = 1;
+/// 回复前缀（`<|start|>assistant<|message|>`）
+const REPLY_PRIMING_TOKENS: TokenCount = 3;
+/// 解析响应体时的解压上限，与用量解析保持一致
+const MAX_DECODED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+static O200K_BASE: LazyLock<Option<CoreBPE>> = LazyLock::new(|| tiktoken_rs::o200k_base().ok());
+static CL100K_BASE: LazyLock<Option<CoreBPE>> = LazyLock::new(|| tiktoken_rs::cl100k_base().ok());
+
+/// 按模型名称选择分词器；无法识别的模型返回 `None`
+#[must_use]
+pub fn tokenizer_for_model(model: &str) -> Option<&'static CoreBPE> {
+    let model = model.trim().to_ascii_lowercase();
+    // 去掉 `openai/gpt-4o` 这类网关前缀
+    let model = model.rsplit('/').next().unwrap_or(&model);
+    match get_tokenizer(model)? {
+        Tokenizer::O200kBase => O200K_BASE.as_ref(),
+        Tokenizer::Cl100kBase => CL100K_BASE.as_ref(),
+        _ => None,
+    }
+}
+
+/// 文本的 Token 数
+#[must_use]
+pub fn count_tokens(bpe: &CoreBPE, text: &str) -> TokenCount {
+    if text.is_empty() {
+        return 0;
+    }
+    TokenCount::try_from(bpe.encode_ordinary(text).len()).unwrap_or(TokenCount::MAX)
+}
+
+/// 估算请求体的输入 Token 数（Chat `messages`、Responses `input`/`instructions`、Completions `prompt`）
+#[must_use]
+pub fn estimate_prompt_tokens(bpe: &CoreBPE, request: &Value) -> TokenCount {
+    let mut total = 0;
+    let mut has_messages = false;
+
+    if let Some(messages) = request.get("messages").and_then(Value::as_array) {
+        has_messages = true;
+        total += messages
+            .iter()
+            .map(|message| message_tokens(bpe, message))
+            .sum::<TokenCount>();
+    }
+    if let Some(instructions) = request.get("instructions").and_then(Value::as_str) {
+        total += count_tokens(bpe, instructions);
+    }
+    for field in ["input", "prompt"] {
+        match request.get(field) {
+            Some(Value::String(text)) => total += count_tokens(bpe, text),
+            Some(Value::Array(items)) => {
+                for item in items {
+                    if let Some(text) = item.as_str() {
+                        total += count_tokens(bpe, text);
+                    } else if item.get("role").is_some() {
+                        has_messages = true;
+                        total += message_tokens(bpe, item);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if has_messages {
+        total += REPLY_PRIMING_TOKENS;
+    }
+    total
+}
+
+fn message_tokens(bpe: &CoreBPE, message: &Value) -> TokenCount {
+    let mut total = TOKENS_PER_MESSAGE;
+    if let Some(role) = message.get("role").and_then(Value::as_str) {
+        total += count_tokens(bpe, role);
+    }
+    if let Some(name) = message.get("name").and_then(Value::as_str) {
+        total += TOKENS_PER_NAME + count_tokens(bpe, name);
+    }
+    if let Some(content) = message.get("content") {
+        let mut text = String::new();
+        push_content_text(content, &mut text);
+        total += count_tokens(bpe, &text);
+    }
+    total
+}
+
+/// 内容可能是字符串，也可能是 `[{"type": "text", "text": "..."}]` 形式的分段
+fn push_content_text(content: &Value, out: &mut String) {
+    match content {
+        Value::String(text) => out.push_str(text),
+        Value::Array(parts) => {
+            for part in parts {
+                if let Some(text) = part.get("text").and_then(Value::as_str) {
+                    out.push_str(text);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 把响应 JSON 或流式事件中的输出文本追加到 `out`
+pub fn append_completion_text(event: &Value, out: &mut String) {
+    if let Some(choices) = event.get("choices").and_then(Value::as_array) {
+        for choice in choices {
+            for key in ["delta", "message"] {
+                if let Some(content) = choice.get(key).and_then(|part| part.get("content")) {
+                    push_content_text(content, out);
+                }
+            }
+            if let Some(text) = choice.get("text").and_then(Value::as_str) {
+                out.push_str(text);
+            }
+        }
+    }
+
+    // Responses API：流式增量事件与非流式的 output 列表
+    if event.get("type").and_then(Value::as_str) == Some("response.output_text.delta")
+        && let Some(delta) = event.get("delta").and_then(Value::as_str)
+    {
+        out.push_str(delta);
+    }
+    if let Some(output) = event.get("output").and_then(Value::as_array) {
+        for item in output {
+            if let Some(content) = item.get("content") {
+                push_content_text(content, out);
+            }
+        }
+    }
+
+    // Anthropic：流式文本增量
+    if event.get("type").and_then(Value::as_str) == Some("content_block_delta")
+        && let Some(text) = event
+            .get("delta")
+            .and_then(|delta| delta.get("text"))
+            .and_then(Value::as_str)
+    {
+        out.push_str(text);
+    }
+    // Gemini：每个分块的候选内容
+    if let Some(candidates) = event.get("candidates").and_then(Value::as_array) {
+        for candidate in candidates {
+            if let Some(parts) = candidate.get("content").and_then(|c| c.get("parts")) {
+                push_content_text(parts, out);
+            }
+        }
+    }
+}
+
+/// 从缓存的响应体中提取输出文本（JSON、SSE 或 NDJSON）
+#[must_use]
+pub fn completion_text_from_body(ctx: &ProxyContext) -> Option<String> {
+    if ctx.response.body.is_empty() || ctx.response.is_opaque_body {
+        return None;
+    }
+    let decoded = decompress_for_stats(
+        ctx.response.details.content_encoding.as_deref(),
+        &ctx.response.body,
+        MAX_DECODED_BODY_BYTES,
+    );
+    let body = std::str::from_utf8(&decoded).ok()?;
+    let content_type = ctx
+        .response
+        .details
+        .content_type
+        .as_deref()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    let mut text = String::new();
+    if content_type.contains("text/event-stream") {
+        let mut decoder = EventStreamData::new();
+        let mut buf = BytesMut::from(body.as_bytes());
+        while let Ok(Some(event)) = decoder.decode(&mut buf) {
+            append_completion_text(&event.data, &mut text);
+        }
+        if let Ok(Some(event)) = decoder.decode_eof(&mut buf) {
+            append_completion_text(&event.data, &mut text);
+        }
+    } else if let Ok(json) = serde_json::from_str::<Value>(body) {
+        append_completion_text(&json, &mut text);
+    } else {
+        for line in body.lines() {
+            let line = line.trim();
+            let line = line.strip_prefix("data:").map_or(line, str::trim_start);
+            if let Ok(json) = serde_json::from_str::<Value>(line) {
+                append_completion_text(&json, &mut text);
+            }
+        }
+    }
+    Some(text)
+}
+
+/// 估算本次请求的用量；模型无法识别或请求与响应都没有可用文本时返回 `None`
+///
+/// `completion_text` 为流式传输中逐帧拼接的输出，缺失时回退到缓存的响应体。
+/// 请求体缓存被截断时不估算输入，避免按残缺内容少计。
+#[must_use]
+pub fn estimate_usage(
+    ctx: &ProxyContext,
+    model: &str,
+    completion_text: Option<&str>,
+) -> Option<TokenUsageMetrics> {
+    let bpe = tokenizer_for_model(model)?;
+
+    let prompt_tokens = (!ctx.request.body_truncated)
+        .then(|| serde_json::from_slice::<Value>(&ctx.request.body).ok())
+        .flatten()
+        .map(|request| estimate_prompt_tokens(bpe, &request));
+    let completion_tokens = match completion_text {
+        Some(text) => Some(count_tokens(bpe, text)),
+        None => completion_text_from_body(ctx).map(|text| count_tokens(bpe, &text)),
+    };
+    if prompt_tokens.is_none() && completion_tokens.is_none() {
+        return None;
+    }
+
+    let prompt_tokens = prompt_tokens.unwrap_or(0);
+    let completion_tokens = completion_tokens.unwrap_or(0);
+    Some(TokenUsageMetrics {
+        prompt_tokens: Some(prompt_tokens),
+        completion_tokens: Some(completion_tokens),
+        total_tokens: Some(prompt_tokens + completion_tokens),
+        estimated: true,
+        ..TokenUsageMetrics::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 估算值与上游实际计数允许的误差
+    const TOLERANCE: TokenCount = 2;
+
+    fn assert_close(actual: TokenCount, expected: TokenCount) {
+        assert!(
+            actual.abs_diff(expected) <= TOLERANCE,
+            "estimated {actual}, expected {expected} ± {TOLERANCE}"
+        );
+    }
+
+    #[test]
+    fn tokenizer_is_model_aware() {
+        assert!(tokenizer_for_model("gpt-4o-mini").is_some());
+        assert!(tokenizer_for_model("gpt-4-0613").is_some());
+        assert!(tokenizer_for_model("openai/gpt-4o").is_some());
+        assert!(tokenizer_for_model("claude-sonnet-4").is_none());
+        assert!(tokenizer_for_model("gemini-2.0-flash").is_none());
+    }
+
+    #[test]
+    fn chat_prompt_matches_reported_usage() {
+        // 上游对该请求上报的 prompt_tokens 为 19
+        let request = json!({
+            "model": "gpt-4",
+            "messages": [
+                {"role": "system", "content": "You are a helpful assistant."},
+                {"role": "user", "content": [{"type": "text", "text": "Hello!"}]}
+            ]
+        });
+        let bpe = tokenizer_for_model("gpt-4").unwrap();
+        assert_close(estimate_prompt_tokens(bpe, &request), 19);
+    }
+
+    #[test]
+    fn completion_text_matches_known_counts() {
+        let bpe = tokenizer_for_model("gpt-4").unwrap();
+        assert_eq!(count_tokens(bpe, ""), 0);
+        assert_close(count_tokens(bpe, "Hello world"), 2);
+        assert_close(
+            count_tokens(bpe, "The quick brown fox jumps over the lazy dog."),
+            10,
+        );
+    }
+
+    #[test]
+    fn completion_text_is_collected_from_all_response_shapes() {
+        let mut text = String::new();
+        append_completion_text(
+            &json!({"choices": [{"message": {"role": "assistant", "content": "Hello"}}]}),
+            &mut text,
+        );
+        append_completion_text(
+            &json!({"choices": [{"delta": {"content": " wor"}}]}),
+            &mut text,
+        );
+        append_completion_text(
+            &json!({"type": "response.output_text.delta", "delta": "ld"}),
+            &mut text,
+        );
+        append_completion_text(&json!({"choices": [], "usage": null}), &mut text);
+        assert_eq!(text, "Hello world");
+    }
+}
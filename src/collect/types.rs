@@ -2,6 +2,7 @@
 
 use serde::Serialize;
 
+use crate::collect::usage_reconcile::UsageSource;
 use crate::types::{ProviderTypeId, TokenCount};
 
 // === 请求/响应概览类型（采集层） ===
@@ -51,6 +52,8 @@ pub struct TokenUsageMetrics {
     pub total_tokens: Option<TokenCount>,
     pub cache_create_tokens: Option<TokenCount>,
     pub cache_read_tokens: Option<TokenCount>,
+    /// 上游未返回用量、由本地分词器估算
+    pub estimated: bool,
 }
 
 #[derive(Clone, Debug, Default, Serialize)]
@@ -59,6 +62,13 @@ pub struct ComputedStats {
     pub model_name: Option<String>,
     pub cost: Option<f64>,
     pub cost_currency: Option<String>,
+    /// 流式响应逐事件提取的元数据（响应体未缓存时使用）
+    pub response_metadata: Option<serde_json::Value>,
+    /// 流式响应逐帧拼接的输出文本（仅开启 Token 估算时收集）
+    #[serde(skip)]
+    pub completion_text: Option<String>,
+    /// 流式响应最终采用的用量来源（流式事件或响应头）
+    pub usage_source: Option<UsageSource>,
 }
 
 /// 成本快照
@@ -80,4 +90,10 @@ pub struct CollectedMetrics {
     pub cost: CollectedCost,
     pub duration_ms: u128,
     pub status_code: u16,
+    /// 客户端发送的请求体字节数
+    pub request_bytes: u64,
+    /// 上游返回的响应体字节数（流式响应为全部分块之和）
+    pub response_bytes: u64,
+    /// 按 `trace.response_metadata_fields` 从响应体中提取的字段
+    pub response_metadata: Option<serde_json::Value>,
 }
@@ -6,10 +6,21 @@ use std::sync::{Arc, RwLock};
 use serde_json::Value;
 use std::sync::LazyLock;
 
+use crate::collect::stream_usage::{
+    SseUsageTracker, StreamUsageAggregator, StreamUsageMode, StreamUsageSummary,
+};
 use crate::collect::types::{ComputedStats, TokenUsageMetrics};
+use crate::collect::usage_reconcile::{reconcile, usage_from_headers};
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
 use crate::proxy::ProxyContext;
 use tokio_util::codec::Decoder as _; // for EventStreamData decode
 
+/// 响应元数据中记录响应体采集状态的字段
+pub const BODY_CAPTURE_KEY: &str = "body_capture";
+/// 流式响应体未缓存时的标记
+pub const BODY_NOT_CAPTURED_MARKER: &str = "streamed, body not captured";
+
 // 预编译模型路径（按优先级）
 static MODEL_PATHS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
     vec![
@@ -19,6 +30,7 @@ static MODEL_PATHS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
         "data.0.model",
         "choices.0.model",
         "candidates.0.model",
+        "message.model",         // anthropic message_start
         "response.model",        // openai
         "response.modelVersion", // gemini
     ]
@@ -90,8 +102,9 @@ fn get_or_build_extractor(
     Some(extractor)
 }
 
+/// 按服务商映射提取单个 JSON 中的用量（未归一化，缺失字段保持 `None`）
 #[must_use]
-pub fn extract_tokens_from_json(
+pub fn extract_raw_tokens_from_json(
     provider: Option<&entity::provider_types::Model>,
     json: &Value,
 ) -> TokenUsageMetrics {
@@ -105,6 +118,15 @@ pub fn extract_tokens_from_json(
         usage.cache_create_tokens = extractor.extract_token_count(json, "cache_create_tokens");
         usage.cache_read_tokens = extractor.extract_token_count(json, "cache_read_tokens");
     }
+    usage
+}
+
+#[must_use]
+pub fn extract_tokens_from_json(
+    provider: Option<&entity::provider_types::Model>,
+    json: &Value,
+) -> TokenUsageMetrics {
+    let mut usage = extract_raw_tokens_from_json(provider, json);
     normalize(&mut usage);
     usage
 }
@@ -122,24 +144,68 @@ pub fn normalize(usage: &mut TokenUsageMetrics) {
     }
 }
 
-// 流式请求一律采用“累加”策略（单事件/单行提取的用量按字段相加）。
-
-// 已统一仅使用 extract_tokens_from_json（数据库驱动 + 归一化），
-// 流式路径在合并阶段采用“累加”策略，不再需要 partial 版本。
+/// 流式汇总结果转为统计并与响应头上报的用量核对；
+/// 未收到最终用量事件且响应头也未上报时记录告警，便于排查少计费
+fn stream_stats(ctx: &ProxyContext, summary: StreamUsageSummary) -> ComputedStats {
+    let header_usage = usage_from_headers(&ctx.response.details.headers);
+    if !summary.complete && header_usage.is_none() {
+        lwarn!(
+            &ctx.request_id,
+            LogStage::Response,
+            LogComponent::Statistics,
+            "stream_usage_incomplete",
+            "流式响应结束时未收到最终用量事件，用量可能偏低",
+            provider = ?ctx.routing.provider_type.as_ref().map(|p| p.name.as_str()),
+            prompt_tokens = ?summary.usage.prompt_tokens,
+            completion_tokens = ?summary.usage.completion_tokens
+        );
+    }
+    let reconciled = reconcile(
+        &ctx.request_id,
+        summary.usage,
+        summary.complete,
+        header_usage,
+    );
+    ComputedStats {
+        usage: reconciled.usage,
+        model_name: summary
+            .model
+            .or_else(|| ctx.request.requested_model.clone()),
+        response_metadata: summary.metadata,
+        completion_text: summary.completion_text,
+        usage_source: Some(reconciled.source),
+        ..ComputedStats::default()
+    }
+}
 
 /// 统一在 `EOS（end_of_stream）时进行解析与统计`。
 ///
 /// 逻辑：
 /// - 使用完整的 `ctx.body` 进行解压与解析；
 /// - Content-Type 决定解析方式：SSE（按事件）、NDJSON（按行）、普通 JSON（整体/窗口）。
-/// - 用量字段采用“累加”策略；模型名称取最后一次出现或整体 JSON 中的字段。
-#[allow(clippy::too_many_lines)]
+/// - 流式用量按服务商语义汇总（见 [`crate::collect::stream_usage`]）；模型名称取最后一次出现或整体 JSON 中的字段。
 pub fn finalize_eos(ctx: &mut ProxyContext) -> ComputedStats {
     use crate::collect::util::{decompress_for_stats, find_last_balanced_json};
-    use bytes::BytesMut;
 
     let mut stats = ComputedStats::default();
 
+    // WebSocket 会话：用量来自透传期间解析的会话事件
+    if let Some(session) = &ctx.response.websocket {
+        stats.usage = session.usage.clone();
+        normalize(&mut stats.usage);
+        stats.model_name = session
+            .model
+            .clone()
+            .or_else(|| ctx.request.requested_model.clone());
+        return stats;
+    }
+
+    // SSE：事件已在分块到达时逐个解析，不受响应体缓存截断影响
+    if let Some(tracker) = ctx.response.stream_usage.take() {
+        let summary = tracker.finish(ctx.routing.provider_type.as_ref());
+        return stream_stats(ctx, summary);
+    }
+
     let content_type = ctx
         .response
         .details
@@ -150,8 +216,8 @@ pub fn finalize_eos(ctx: &mut ProxyContext) -> ComputedStats {
     let encoding = ctx.response.details.content_encoding.as_deref();
     let raw = ctx.response.body.clone();
 
-    // 无正文：置零并回退模型
-    if raw.is_empty() {
+    // 无正文或非 JSON 响应（text/plain、图片等）：置零并回退模型
+    if raw.is_empty() || ctx.response.is_opaque_body {
         stats.usage.prompt_tokens = Some(0);
         stats.usage.completion_tokens = Some(0);
         stats.usage.total_tokens = Some(0);
@@ -170,95 +236,19 @@ pub fn finalize_eos(ctx: &mut ProxyContext) -> ComputedStats {
         return stats;
     };
 
-    // SSE：text/event-stream
+    // SSE：text/event-stream（未在传输中跟踪时按缓存的完整响应体解析）
     if content_type.contains("text/event-stream") {
-        let mut event_stream_decoder = crate::utils::event_stream::EventStreamData::new();
-        let mut buf = BytesMut::new();
-        buf.extend_from_slice(body_str.as_bytes());
-        let mut last_json: Option<serde_json::Value> = None;
-        loop {
-            match event_stream_decoder.decode(&mut buf) {
-                Ok(Some(ev)) => {
-                    let json = ev.data;
-                    if !json.is_null() {
-                        let usage =
-                            extract_tokens_from_json(ctx.routing.provider_type.as_ref(), &json);
-                        // 累加策略
-                        stats.usage.prompt_tokens = Some(
-                            stats.usage.prompt_tokens.unwrap_or(0)
-                                + usage.prompt_tokens.unwrap_or(0),
-                        );
-                        stats.usage.completion_tokens = Some(
-                            stats.usage.completion_tokens.unwrap_or(0)
-                                + usage.completion_tokens.unwrap_or(0),
-                        );
-                        stats.usage.total_tokens = Some(
-                            stats.usage.total_tokens.unwrap_or(0) + usage.total_tokens.unwrap_or(0),
-                        );
-                        stats.usage.cache_create_tokens = Some(
-                            stats.usage.cache_create_tokens.unwrap_or(0)
-                                + usage.cache_create_tokens.unwrap_or(0),
-                        );
-                        stats.usage.cache_read_tokens = Some(
-                            stats.usage.cache_read_tokens.unwrap_or(0)
-                                + usage.cache_read_tokens.unwrap_or(0),
-                        );
-                        last_json = Some(json);
-                    }
-                }
-                Ok(None) => {
-                    // flush EOF
-                    if let Ok(Some(ev)) = event_stream_decoder.decode_eof(&mut buf) {
-                        let json = ev.data;
-                        if !json.is_null() {
-                            let usage =
-                                extract_tokens_from_json(ctx.routing.provider_type.as_ref(), &json);
-                            // 累加策略
-                            stats.usage.prompt_tokens = Some(
-                                stats.usage.prompt_tokens.unwrap_or(0)
-                                    + usage.prompt_tokens.unwrap_or(0),
-                            );
-                            stats.usage.completion_tokens = Some(
-                                stats.usage.completion_tokens.unwrap_or(0)
-                                    + usage.completion_tokens.unwrap_or(0),
-                            );
-                            stats.usage.total_tokens = Some(
-                                stats.usage.total_tokens.unwrap_or(0)
-                                    + usage.total_tokens.unwrap_or(0),
-                            );
-                            stats.usage.cache_create_tokens = Some(
-                                stats.usage.cache_create_tokens.unwrap_or(0)
-                                    + usage.cache_create_tokens.unwrap_or(0),
-                            );
-                            stats.usage.cache_read_tokens = Some(
-                                stats.usage.cache_read_tokens.unwrap_or(0)
-                                    + usage.cache_read_tokens.unwrap_or(0),
-                            );
-                            last_json = Some(json);
-                        }
-                    }
-                    break;
-                }
-                Err(_) => break,
-            }
-        }
-        if stats.usage.total_tokens.is_none() {
-            stats.usage.prompt_tokens = Some(0);
-            stats.usage.completion_tokens = Some(0);
-            stats.usage.total_tokens = Some(0);
-        }
-        if let Some(j) = last_json {
-            stats.model_name = extract_model_from_json(&j);
-        }
-        if stats.model_name.is_none() {
-            stats.model_name.clone_from(&ctx.request.requested_model);
-        }
-        return stats;
+        let provider = ctx.routing.provider_type.as_ref();
+        let mut tracker = SseUsageTracker::new(provider);
+        tracker.observe_chunk(body_str.as_bytes(), provider);
+        let summary = tracker.finish(provider);
+        return stream_stats(ctx, summary);
     }
 
     // NDJSON：application/stream+json 或行式 JSON 退化
     if content_type.contains("application/stream+json") {
-        let mut last_json: Option<serde_json::Value> = None;
+        let provider = ctx.routing.provider_type.as_ref();
+        let mut aggregator = StreamUsageAggregator::new(StreamUsageMode::for_provider(provider));
         for raw in body_str.lines() {
             let mut line = raw.trim();
             if line.is_empty() || line.starts_with(':') {
@@ -267,44 +257,13 @@ pub fn finalize_eos(ctx: &mut ProxyContext) -> ComputedStats {
             if let Some(rest) = line.strip_prefix("data:") {
                 line = rest.trim_start();
             }
-            if let Some(pos) = line.find('{') {
-                let json_str = &line[pos..];
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(json_str) {
-                    let usage = extract_tokens_from_json(ctx.routing.provider_type.as_ref(), &json);
-                    stats.usage.prompt_tokens = Some(
-                        stats.usage.prompt_tokens.unwrap_or(0) + usage.prompt_tokens.unwrap_or(0),
-                    );
-                    stats.usage.completion_tokens = Some(
-                        stats.usage.completion_tokens.unwrap_or(0)
-                            + usage.completion_tokens.unwrap_or(0),
-                    );
-                    stats.usage.total_tokens = Some(
-                        stats.usage.total_tokens.unwrap_or(0) + usage.total_tokens.unwrap_or(0),
-                    );
-                    stats.usage.cache_create_tokens = Some(
-                        stats.usage.cache_create_tokens.unwrap_or(0)
-                            + usage.cache_create_tokens.unwrap_or(0),
-                    );
-                    stats.usage.cache_read_tokens = Some(
-                        stats.usage.cache_read_tokens.unwrap_or(0)
-                            + usage.cache_read_tokens.unwrap_or(0),
-                    );
-                    last_json = Some(json);
-                }
+            if let Some(pos) = line.find('{')
+                && let Ok(json) = serde_json::from_str::<serde_json::Value>(&line[pos..])
+            {
+                aggregator.observe(provider, &json);
             }
         }
-        if stats.usage.total_tokens.is_none() {
-            stats.usage.prompt_tokens = Some(0);
-            stats.usage.completion_tokens = Some(0);
-            stats.usage.total_tokens = Some(0);
-        }
-        if let Some(j) = last_json {
-            stats.model_name = extract_model_from_json(&j);
-        }
-        if stats.model_name.is_none() {
-            stats.model_name.clone_from(&ctx.request.requested_model);
-        }
-        return stats;
+        return stream_stats(ctx, aggregator.finish());
     }
 
     // 普通 JSON：整体/窗口解析
@@ -344,4 +303,75 @@ pub fn finalize_eos(ctx: &mut ProxyContext) -> ComputedStats {
     stats
 }
 
+/// 在响应元数据中标记流式响应体未缓存
+#[must_use]
+pub fn mark_body_not_captured(metadata: Option<Value>) -> Value {
+    let mut fields = match metadata {
+        Some(Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    fields.insert(
+        BODY_CAPTURE_KEY.to_string(),
+        Value::String(BODY_NOT_CAPTURED_MARKER.to_string()),
+    );
+    Value::Object(fields)
+}
+
+/// 按配置路径从响应体中提取元数据，写入追踪记录 `response_metadata`
+///
+/// - 普通 JSON：整体解析后提取；
+/// - SSE / NDJSON：依次观察每个事件，后出现的非空值覆盖先前的值；
+/// - 未配置路径或无法解析时返回 `None`。
+#[must_use]
+pub fn extract_response_metadata(ctx: &ProxyContext, paths: &[String]) -> Option<Value> {
+    use crate::collect::field_extractor::ResponseMetadataExtractor;
+    use crate::collect::util::{decompress_for_stats, find_last_balanced_json};
+    use bytes::BytesMut;
+
+    if paths.is_empty() || ctx.response.body.is_empty() || ctx.response.is_opaque_body {
+        return None;
+    }
+
+    let content_type = ctx
+        .response
+        .details
+        .content_type
+        .as_deref()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let decoded = decompress_for_stats(
+        ctx.response.details.content_encoding.as_deref(),
+        &ctx.response.body,
+        2 * 1024 * 1024,
+    );
+    let body_str = std::str::from_utf8(&decoded).ok()?;
+    let mut extractor = ResponseMetadataExtractor::new(paths);
+
+    if content_type.contains("text/event-stream") {
+        let mut decoder = crate::utils::event_stream::EventStreamData::new();
+        let mut buf = BytesMut::from(body_str.as_bytes());
+        while let Ok(Some(ev)) = decoder.decode(&mut buf) {
+            extractor.observe(&ev.data);
+        }
+        if let Ok(Some(ev)) = decoder.decode_eof(&mut buf) {
+            extractor.observe(&ev.data);
+        }
+    } else if content_type.contains("application/stream+json") {
+        for line in body_str.lines() {
+            let line = line.trim();
+            let line = line.strip_prefix("data:").map_or(line, str::trim_start);
+            if let Ok(json) = serde_json::from_str::<Value>(line) {
+                extractor.observe(&json);
+            }
+        }
+    } else if let Some(json) = serde_json::from_str::<Value>(body_str)
+        .ok()
+        .or_else(|| find_last_balanced_json(body_str))
+    {
+        extractor.observe(&json);
+    }
+
+    extractor.finish()
+}
+
 // 注意：不再提供 finalize_streaming 别名，统一使用 finalize_eos。
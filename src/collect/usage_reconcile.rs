@@ -0,0 +1,231 @@
+//! # 流式用量与响应头用量核对
+//!
+//! 部分服务商（如 AWS Bedrock）在流式事件与响应头/尾部响应头（trailer）中同时上报最终用量，
+//! 两者不一致时容易引发计费争议。核对规则：
+//! - 收到最终用量事件时以流式事件为准；响应头同时上报且差异超过容差时记录告警；
+//! - 未收到最终用量事件（如连接提前关闭）时回退到响应头上报的用量；
+//! - 实际采用的来源写入追踪记录 `response_metadata.usage_source`。
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::collect::types::TokenUsageMetrics;
+use crate::collect::usage_model::normalize;
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
+use crate::types::TokenCount;
+
+/// 响应元数据中记录用量来源的字段
+pub const USAGE_SOURCE_KEY: &str = "usage_source";
+
+/// 输入 Token 数响应头（按优先级）
+const PROMPT_TOKEN_HEADERS: &[&str] = &[
+    "x-amzn-bedrock-input-token-count",
+    "x-usage-input-tokens",
+    "x-usage-prompt-tokens",
+];
+
+/// 输出 Token 数响应头（按优先级）
+const COMPLETION_TOKEN_HEADERS: &[&str] = &[
+    "x-amzn-bedrock-output-token-count",
+    "x-usage-output-tokens",
+    "x-usage-completion-tokens",
+];
+
+/// 允许的差异比例（百分比），差异不超过 1 个 Token 时始终视为一致
+const MISMATCH_TOLERANCE_PERCENT: TokenCount = 1;
+
+/// 最终采用的用量来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageSource {
+    /// 流式事件
+    Stream,
+    /// 响应头或尾部响应头
+    Header,
+}
+
+impl UsageSource {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Stream => "stream",
+            Self::Header => "header",
+        }
+    }
+}
+
+/// 核对结果
+#[derive(Debug, Clone)]
+pub struct ReconciledUsage {
+    pub usage: TokenUsageMetrics,
+    pub source: UsageSource,
+    /// 流式事件与响应头用量差异超过容差
+    pub mismatch: bool,
+}
+
+/// 从响应头（含尾部响应头）中读取上报的用量；输入输出均未上报时返回 `None`
+#[must_use]
+pub fn usage_from_headers(headers: &HashMap<String, String>) -> Option<TokenUsageMetrics> {
+    let read = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .and_then(|(_, value)| value.trim().parse::<TokenCount>().ok())
+        })
+    };
+    let prompt_tokens = read(PROMPT_TOKEN_HEADERS);
+    let completion_tokens = read(COMPLETION_TOKEN_HEADERS);
+    if prompt_tokens.is_none() && completion_tokens.is_none() {
+        return None;
+    }
+
+    let mut usage = TokenUsageMetrics {
+        prompt_tokens,
+        completion_tokens,
+        ..TokenUsageMetrics::default()
+    };
+    normalize(&mut usage);
+    Some(usage)
+}
+
+/// 核对流式事件与响应头上报的用量
+///
+/// `stream_complete` 表示是否收到携带最终用量的事件。
+#[must_use]
+pub fn reconcile(
+    request_id: &str,
+    streamed: TokenUsageMetrics,
+    stream_complete: bool,
+    header: Option<TokenUsageMetrics>,
+) -> ReconciledUsage {
+    let Some(header) = header else {
+        return ReconciledUsage {
+            usage: streamed,
+            source: UsageSource::Stream,
+            mismatch: false,
+        };
+    };
+
+    if !stream_complete {
+        return ReconciledUsage {
+            usage: header,
+            source: UsageSource::Header,
+            mismatch: false,
+        };
+    }
+
+    let mismatch = differs(streamed.prompt_tokens, header.prompt_tokens)
+        || differs(streamed.completion_tokens, header.completion_tokens);
+    if mismatch {
+        lwarn!(
+            request_id,
+            LogStage::Response,
+            LogComponent::Statistics,
+            "stream_usage_header_mismatch",
+            "流式事件与响应头上报的用量不一致，按流式事件计费",
+            stream_prompt_tokens = ?streamed.prompt_tokens,
+            stream_completion_tokens = ?streamed.completion_tokens,
+            header_prompt_tokens = ?header.prompt_tokens,
+            header_completion_tokens = ?header.completion_tokens
+        );
+    }
+    ReconciledUsage {
+        usage: streamed,
+        source: UsageSource::Stream,
+        mismatch,
+    }
+}
+
+/// 在响应元数据中记录用量来源
+#[must_use]
+pub fn mark_usage_source(metadata: Option<Value>, source: UsageSource) -> Value {
+    let mut fields = match metadata {
+        Some(Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    fields.insert(
+        USAGE_SOURCE_KEY.to_string(),
+        Value::String(source.as_str().to_string()),
+    );
+    Value::Object(fields)
+}
+
+/// 双方都上报时比较差异；任一方缺失不视为不一致
+fn differs(streamed: Option<TokenCount>, header: Option<TokenCount>) -> bool {
+    let (Some(streamed), Some(header)) = (streamed, header) else {
+        return false;
+    };
+    let diff = streamed.abs_diff(header);
+    diff > 1 && diff.saturating_mul(100) > streamed.max(header) * MISMATCH_TOLERANCE_PERCENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt: TokenCount, completion: TokenCount) -> TokenUsageMetrics {
+        let mut usage = TokenUsageMetrics {
+            prompt_tokens: Some(prompt),
+            completion_tokens: Some(completion),
+            ..TokenUsageMetrics::default()
+        };
+        normalize(&mut usage);
+        usage
+    }
+
+    fn bedrock_headers(prompt: &str, completion: &str) -> HashMap<String, String> {
+        HashMap::from([
+            (
+                "x-amzn-bedrock-input-token-count".to_string(),
+                prompt.to_string(),
+            ),
+            (
+                "X-Amzn-Bedrock-Output-Token-Count".to_string(),
+                completion.to_string(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn matching_sources_use_stream_without_mismatch() {
+        let header = usage_from_headers(&bedrock_headers("120", "45"));
+        let reconciled = reconcile("req", usage(120, 45), true, header);
+
+        assert_eq!(reconciled.source, UsageSource::Stream);
+        assert!(!reconciled.mismatch);
+        assert_eq!(reconciled.usage.total_tokens, Some(165));
+    }
+
+    #[test]
+    fn header_usage_is_used_when_stream_has_no_final_event() {
+        let header = usage_from_headers(&bedrock_headers("120", "45"));
+        let reconciled = reconcile("req", usage(120, 0), false, header);
+
+        assert_eq!(reconciled.source, UsageSource::Header);
+        assert_eq!(reconciled.usage.prompt_tokens, Some(120));
+        assert_eq!(reconciled.usage.completion_tokens, Some(45));
+        assert_eq!(reconciled.usage.total_tokens, Some(165));
+
+        assert!(usage_from_headers(&HashMap::new()).is_none());
+        let without_header = reconcile("req", usage(120, 0), false, None);
+        assert_eq!(without_header.source, UsageSource::Stream);
+    }
+
+    #[test]
+    fn mismatch_beyond_tolerance_keeps_stream_usage() {
+        let header = usage_from_headers(&bedrock_headers("120", "60"));
+        let reconciled = reconcile("req", usage(120, 45), true, header);
+
+        assert_eq!(reconciled.source, UsageSource::Stream);
+        assert!(reconciled.mismatch);
+        assert_eq!(reconciled.usage.completion_tokens, Some(45));
+
+        // 1% 以内的差异视为一致
+        let header = usage_from_headers(&bedrock_headers("1000", "201"));
+        assert!(!reconcile("req", usage(1008, 200), true, header).mismatch);
+    }
+}
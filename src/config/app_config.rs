@@ -1,6 +1,35 @@
 //! # 应用配置结构定义
 
+use super::circuit_breaker_config::CircuitBreakerConfig;
+use super::concurrency_config::ConcurrencyConfig;
+use super::cost_aware_config::CostAwareConfig;
+use super::credits_config::CreditsConfig;
+use super::debug_capture_config::DebugCaptureConfig;
 use super::dual_port_config::DualPortServerConfig;
+use super::geoip_config::GeoIpConfig;
+use super::health_check_config::HealthCheckConfig;
+use super::key_selection_config::KeySelectionConfig;
+use super::maintenance_config::MaintenanceConfig;
+use super::model_check_config::ModelCheckConfig;
+use super::oauth_session_config::OAuthSessionConfig;
+use super::parameter_policy_config::ParameterPolicyConfig;
+use super::rate_limit_config::RateLimitConfig;
+use super::request_body_config::RequestBodyConfig;
+use super::response_body_config::ResponseBodyConfig;
+use super::response_headers_config::ResponseHeadersConfig;
+use super::retry_config::RetryConfig;
+use super::spend_anomaly_config::SpendAnomalyConfig;
+use super::streaming_config::StreamingConfig;
+use super::timeout_bounds_config::TimeoutBoundsConfig;
+use super::token_estimation_config::TokenEstimationConfig;
+use super::total_timeout_config::TotalTimeoutConfig;
+use super::trace_config::TraceConfig;
+use super::trace_sink_config::TraceSinkConfig;
+use super::trace_writer_config::TraceWriterConfig;
+use super::upstream_headers_config::UpstreamHeadersConfig;
+use super::upstream_pool_config::UpstreamPoolConfig;
+use super::usage_counter_config::UsageCounterConfig;
+use super::white_label_config::WhiteLabelConfig;
 use crate::auth::types::AuthConfig;
 use crate::ensure;
 use crate::error::{self, Context};
@@ -19,6 +48,93 @@ pub struct AppConfig {
     /// 认证配置
     #[serde(default)]
     pub auth: AuthConfig,
+    /// 请求参数策略配置
+    #[serde(default)]
+    pub parameter_policy: ParameterPolicyConfig,
+    /// 限流配置
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// 流式响应配置
+    #[serde(default)]
+    pub streaming: StreamingConfig,
+    /// 追踪配置
+    #[serde(default)]
+    pub trace: TraceConfig,
+    /// 服务商健康检查配置
+    #[serde(default)]
+    pub health_check: HealthCheckConfig,
+    /// 上游熔断配置
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// 上游请求头转发配置
+    #[serde(default)]
+    pub upstream_headers: UpstreamHeadersConfig,
+    /// 下游响应头清理配置
+    #[serde(default)]
+    pub response_headers: ResponseHeadersConfig,
+    /// 模型预检配置
+    #[serde(default)]
+    pub model_check: ModelCheckConfig,
+    /// 响应体处理配置
+    #[serde(default)]
+    pub response_body: ResponseBodyConfig,
+    /// 请求总时长配置
+    #[serde(default)]
+    pub total_timeout: TotalTimeoutConfig,
+    /// 上游超时范围配置
+    #[serde(default)]
+    pub timeout_bounds: TimeoutBoundsConfig,
+    /// 成本感知调度配置
+    #[serde(default)]
+    pub cost_aware: CostAwareConfig,
+    /// 密钥选择候选集配置
+    #[serde(default)]
+    pub key_selection: KeySelectionConfig,
+    /// 维护模式配置
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    /// 消费异常检测配置
+    #[serde(default)]
+    pub spend_anomaly: SpendAnomalyConfig,
+    /// Token 估算配置
+    #[serde(default)]
+    pub token_estimation: TokenEstimationConfig,
+    /// 追踪写入队列配置
+    #[serde(default)]
+    pub trace_writer: TraceWriterConfig,
+    /// 追踪外部输出（HTTP 批量推送）配置
+    #[serde(default)]
+    pub trace_sink: TraceSinkConfig,
+    /// 全局并发与优先级准入配置
+    #[serde(default)]
+    pub concurrency: ConcurrencyConfig,
+    /// 上游连接池与保活配置
+    #[serde(default)]
+    pub upstream_pool: UpstreamPoolConfig,
+    /// 上游重试配置
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// 客户端 IP 地理信息（国家、ASN）配置
+    #[serde(default)]
+    pub geoip: GeoIpConfig,
+    /// 服务 API 用量计数（内存聚合、定期写库）配置
+    #[serde(default)]
+    pub usage_counter: UsageCounterConfig,
+    /// 白标配置（移除服务商标识响应头、改写响应模型名）
+    #[serde(default)]
+    pub white_label: WhiteLabelConfig,
+    /// 定向调试采集配置
+    #[serde(default)]
+    pub debug_capture: DebugCaptureConfig,
+    /// 积分额度配置（按模型权重扣减用户积分）
+    #[serde(default)]
+    pub credits: CreditsConfig,
+    /// 请求体 JSON 复杂度配置（嵌套深度、元素总数）
+    #[serde(default)]
+    pub request_body: RequestBodyConfig,
+    /// OAuth 会话共享配置（同一会话能否被多个密钥引用）
+    #[serde(default)]
+    pub oauth_session: OAuthSessionConfig,
 }
 
 // PingoraConfig 已删除，超时配置现在从数据库 user_service_apis.timeout_seconds 获取
@@ -78,6 +194,21 @@ pub struct RedisConfig {
     pub connection_timeout: u64,
     /// 最大连接数
     pub max_connections: u32,
+    /// Redis 不可用期间后台探测重连的间隔（毫秒）
+    #[serde(default = "default_reconnect_interval_ms")]
+    pub reconnect_interval_ms: u64,
+}
+
+const fn default_reconnect_interval_ms() -> u64 {
+    5000
+}
+
+impl RedisConfig {
+    /// 后台重连间隔，至少 10ms
+    #[must_use]
+    pub fn reconnect_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.reconnect_interval_ms.max(10))
+    }
 }
 
 impl Default for RedisConfig {
@@ -91,6 +222,7 @@ impl Default for RedisConfig {
             password: None,
             connection_timeout: 10,
             max_connections: 10,
+            reconnect_interval_ms: default_reconnect_interval_ms(),
         }
     }
 }
@@ -102,6 +234,35 @@ impl Default for AppConfig {
             database: super::DatabaseConfig::default(),
             cache: CacheConfig::default(),
             auth: AuthConfig::default(),
+            parameter_policy: ParameterPolicyConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            streaming: StreamingConfig::default(),
+            trace: TraceConfig::default(),
+            health_check: HealthCheckConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            upstream_headers: UpstreamHeadersConfig::default(),
+            response_headers: ResponseHeadersConfig::default(),
+            model_check: ModelCheckConfig::default(),
+            response_body: ResponseBodyConfig::default(),
+            total_timeout: TotalTimeoutConfig::default(),
+            timeout_bounds: TimeoutBoundsConfig::default(),
+            cost_aware: CostAwareConfig::default(),
+            key_selection: KeySelectionConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            spend_anomaly: SpendAnomalyConfig::default(),
+            token_estimation: TokenEstimationConfig::default(),
+            trace_writer: TraceWriterConfig::default(),
+            trace_sink: TraceSinkConfig::default(),
+            concurrency: ConcurrencyConfig::default(),
+            upstream_pool: UpstreamPoolConfig::default(),
+            retry: RetryConfig::default(),
+            geoip: GeoIpConfig::default(),
+            usage_counter: UsageCounterConfig::default(),
+            white_label: WhiteLabelConfig::default(),
+            debug_capture: DebugCaptureConfig::default(),
+            credits: CreditsConfig::default(),
+            request_body: RequestBodyConfig::default(),
+            oauth_session: OAuthSessionConfig::default(),
         }
     }
 }
@@ -197,6 +358,34 @@ impl AppConfig {
             )
         );
 
+        self.parameter_policy.validate()?;
+        self.rate_limit.validate()?;
+        self.streaming.validate()?;
+        self.trace.validate()?;
+        self.health_check.validate()?;
+        self.circuit_breaker.validate()?;
+        self.upstream_headers.validate()?;
+        self.response_headers.validate()?;
+        self.model_check.validate()?;
+        self.response_body.validate()?;
+        self.total_timeout.validate()?;
+        self.timeout_bounds.validate()?;
+        self.cost_aware.validate()?;
+        self.key_selection.validate()?;
+        self.maintenance.validate()?;
+        self.spend_anomaly.validate()?;
+        self.trace_writer.validate()?;
+        self.trace_sink.validate()?;
+        self.concurrency.validate()?;
+        self.upstream_pool.validate()?;
+        self.retry.validate()?;
+        self.geoip.validate()?;
+        self.usage_counter.validate()?;
+        self.white_label.validate()?;
+        self.debug_capture.validate()?;
+        self.credits.validate()?;
+        self.request_body.validate()?;
+
         Ok(())
     }
 
@@ -0,0 +1,76 @@
+//! # 上游熔断配置
+//!
+//! 服务商上游连续失败达到阈值后熔断一段时间：熔断期间直接快速失败，
+//! 或改走为该服务商配置的备用地址，避免请求卡在大概率超时的连接上。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use crate::proxy::upstream_url::parse_base_url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 上游熔断配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// 是否启用上游熔断
+    #[serde(default)]
+    pub enabled: bool,
+    /// 连续失败多少次后熔断
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// 熔断持续时间（秒），到期后放行一个探测请求
+    #[serde(default = "default_open_secs")]
+    pub open_secs: u64,
+    /// 按服务商类型名称（`provider_types.name`）配置的备用上游地址
+    #[serde(default)]
+    pub fallback_base_urls: HashMap<String, String>,
+}
+
+const fn default_failure_threshold() -> u32 {
+    5
+}
+
+const fn default_open_secs() -> u64 {
+    30
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failure_threshold: default_failure_threshold(),
+            open_secs: default_open_secs(),
+            fallback_base_urls: HashMap::new(),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// 熔断持续时间
+    #[must_use]
+    pub const fn open_duration(&self) -> Duration {
+        Duration::from_secs(self.open_secs)
+    }
+
+    /// 校验阈值与备用地址
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.failure_threshold > 0,
+            ConfigError::Load("circuit_breaker.failure_threshold 必须为正数".to_string())
+        );
+        ensure!(
+            self.open_secs > 0,
+            ConfigError::Load("circuit_breaker.open_secs 必须为正数".to_string())
+        );
+        for (provider, url) in &self.fallback_base_urls {
+            ensure!(
+                parse_base_url(url).is_ok(),
+                ConfigError::Load(format!(
+                    "circuit_breaker.fallback_base_urls.{provider} 不是有效的上游地址: {url}"
+                ))
+            );
+        }
+        Ok(())
+    }
+}
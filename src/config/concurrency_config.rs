@@ -0,0 +1,79 @@
+//! # 全局并发配置
+//!
+//! 限制代理端口同时处理的请求数。达到上限后新请求进入按优先级排序的准入队列：
+//! 服务 API 的 `priority` 越高越先获得空位，同优先级按到达顺序；
+//! 队列已满时挤出优先级更低的排队请求，否则拒绝本请求。排队超时或被拒绝时返回 503。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 全局并发配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcurrencyConfig {
+    /// 是否启用全局并发限制
+    #[serde(default)]
+    pub enabled: bool,
+    /// 同时处理的最大请求数
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// 准入队列最多容纳的等待请求数，为 0 时达到上限直接拒绝
+    #[serde(default = "default_max_queue_size")]
+    pub max_queue_size: usize,
+    /// 单个请求最长排队时间（毫秒）
+    #[serde(default = "default_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
+    /// 拒绝时返回的 `Retry-After` 秒数
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+const fn default_max_concurrent_requests() -> usize {
+    512
+}
+
+const fn default_max_queue_size() -> usize {
+    1024
+}
+
+const fn default_queue_timeout_ms() -> u64 {
+    10_000
+}
+
+const fn default_retry_after_secs() -> u64 {
+    1
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent_requests: default_max_concurrent_requests(),
+            max_queue_size: default_max_queue_size(),
+            queue_timeout_ms: default_queue_timeout_ms(),
+            retry_after_secs: default_retry_after_secs(),
+        }
+    }
+}
+
+impl ConcurrencyConfig {
+    /// 最长排队时间
+    #[must_use]
+    pub const fn queue_timeout(&self) -> Duration {
+        Duration::from_millis(self.queue_timeout_ms)
+    }
+
+    /// 校验并发上限与排队时间
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.max_concurrent_requests > 0,
+            ConfigError::Load("concurrency.max_concurrent_requests 必须为正数".to_string())
+        );
+        ensure!(
+            self.queue_timeout_ms > 0,
+            ConfigError::Load("concurrency.queue_timeout_ms 必须为正数".to_string())
+        );
+        Ok(())
+    }
+}
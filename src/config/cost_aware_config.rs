@@ -0,0 +1,59 @@
+//! # 成本感知调度配置
+//!
+//! `cost_aware` 调度策略按定价选择最便宜的健康密钥；延迟上限用于避开便宜但响应过慢的密钥。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 成本感知调度配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostAwareConfig {
+    /// 密钥平均响应延迟上限（毫秒），超过上限的密钥不参与比价；0 表示不限制
+    #[serde(default)]
+    pub max_latency_ms: u64,
+    /// 定价数据缓存时间（秒）
+    #[serde(default = "default_price_cache_secs")]
+    pub price_cache_secs: u64,
+}
+
+const fn default_price_cache_secs() -> u64 {
+    60
+}
+
+impl Default for CostAwareConfig {
+    fn default() -> Self {
+        Self {
+            max_latency_ms: 0,
+            price_cache_secs: default_price_cache_secs(),
+        }
+    }
+}
+
+impl CostAwareConfig {
+    /// 延迟上限，未配置时为 `None`
+    #[must_use]
+    pub const fn max_latency(&self) -> Option<Duration> {
+        if self.max_latency_ms == 0 {
+            None
+        } else {
+            Some(Duration::from_millis(self.max_latency_ms))
+        }
+    }
+
+    /// 定价数据缓存时间
+    #[must_use]
+    pub const fn price_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.price_cache_secs)
+    }
+
+    /// 校验缓存时间
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.price_cache_secs > 0,
+            ConfigError::Load("cost_aware.price_cache_secs 必须为正数".to_string())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+//! # 积分额度配置
+//!
+//! 用户配置了积分余额（`users.credits_balance`）时，每个请求按模型权重折算积分扣减。
+//! 权重以“每 1000 个 token 消耗的积分”表示，输入（含缓存 token）与输出分别计价；
+//! 未单独配置的模型使用默认权重。转发前先预留 `reserve_credits` 积分，余额不足直接拒绝。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use crate::pricing::TokenUsage;
+use crate::types::TokenCount;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 单个模型的积分权重
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCreditCost {
+    /// 每 1000 个输入 token 消耗的积分
+    pub input_per_1k: u64,
+    /// 每 1000 个输出 token 消耗的积分
+    pub output_per_1k: u64,
+}
+
+/// 积分额度配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreditsConfig {
+    /// 转发前为每个请求预留的积分，请求结束后按实际用量结算
+    #[serde(default = "default_reserve_credits")]
+    pub reserve_credits: u64,
+    /// 未单独配置的模型每 1000 个输入 token 消耗的积分
+    #[serde(default = "default_per_1k")]
+    pub default_input_per_1k: u64,
+    /// 未单独配置的模型每 1000 个输出 token 消耗的积分
+    #[serde(default = "default_per_1k")]
+    pub default_output_per_1k: u64,
+    /// 按模型名称覆盖的积分权重
+    #[serde(default)]
+    pub models: HashMap<String, ModelCreditCost>,
+}
+
+const fn default_reserve_credits() -> u64 {
+    1
+}
+
+const fn default_per_1k() -> u64 {
+    1
+}
+
+impl Default for CreditsConfig {
+    fn default() -> Self {
+        Self {
+            reserve_credits: default_reserve_credits(),
+            default_input_per_1k: default_per_1k(),
+            default_output_per_1k: default_per_1k(),
+            models: HashMap::new(),
+        }
+    }
+}
+
+impl CreditsConfig {
+    /// 获取模型的积分权重
+    #[must_use]
+    pub fn cost_for(&self, model: Option<&str>) -> ModelCreditCost {
+        model
+            .and_then(|model| self.models.get(model))
+            .copied()
+            .unwrap_or(ModelCreditCost {
+                input_per_1k: self.default_input_per_1k,
+                output_per_1k: self.default_output_per_1k,
+            })
+    }
+
+    /// 按用量折算积分，不足 1 积分的部分向上取整
+    #[must_use]
+    pub fn credits_for(&self, model: Option<&str>, usage: &TokenUsage) -> i64 {
+        let cost = self.cost_for(model);
+        let input = [
+            usage.prompt_tokens,
+            usage.cache_create_tokens,
+            usage.cache_read_tokens,
+        ]
+        .into_iter()
+        .flatten()
+        .fold(0, TokenCount::saturating_add);
+        let output = usage.completion_tokens.unwrap_or(0);
+        let weighted = input
+            .saturating_mul(cost.input_per_1k)
+            .saturating_add(output.saturating_mul(cost.output_per_1k));
+        i64::try_from(weighted.div_ceil(1000)).unwrap_or(i64::MAX)
+    }
+
+    /// 每个请求预留的积分
+    #[must_use]
+    pub fn reserve_amount(&self) -> i64 {
+        i64::try_from(self.reserve_credits).unwrap_or(i64::MAX)
+    }
+
+    /// 校验预留积分
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.reserve_credits > 0,
+            ConfigError::Load("credits.reserve_credits 必须为正数".to_string())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+//! # 定向调试采集配置
+//!
+//! 排查某个用户、服务 API、后端密钥或模型的问题时，临时对命中条件的请求开启完整采集：
+//! 等同于服务 API 开启 `log_mode`，并且日志中的请求头与请求/响应体不截断、附带耗时，
+//! 追踪记录采集请求体，流式响应体始终缓存。其它请求仍按常规配置处理。
+//!
+//! 配置必须设置过期时间，过期后自动失效，避免忘记关闭导致敏感内容长期落盘。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 定向调试采集配置
+///
+/// 各条件之间为“且”关系，条件内部为“或”关系；未配置的条件不参与匹配。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugCaptureConfig {
+    /// 是否开启
+    #[serde(default)]
+    pub enabled: bool,
+    /// 过期时间（RFC 3339），过期后不再采集
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 匹配的用户 ID
+    #[serde(default)]
+    pub user_ids: Vec<i32>,
+    /// 匹配的服务 API ID
+    #[serde(default)]
+    pub user_service_api_ids: Vec<i32>,
+    /// 匹配的后端密钥 ID
+    #[serde(default)]
+    pub provider_key_ids: Vec<i32>,
+    /// 匹配的模型名称
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+/// 参与匹配的请求信息
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DebugCaptureTarget<'a> {
+    pub user_id: Option<i32>,
+    pub user_service_api_id: Option<i32>,
+    pub provider_key_id: Option<i32>,
+    pub model: Option<&'a str>,
+}
+
+impl DebugCaptureConfig {
+    /// 已开启且未过期
+    #[must_use]
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && self.expires_at.is_some_and(|expires_at| now < expires_at)
+    }
+
+    /// 按用户、服务 API 与后端密钥匹配，不检查模型（请求早期模型尚未确定时使用）
+    #[must_use]
+    pub fn matches_identity(&self, target: &DebugCaptureTarget<'_>, now: DateTime<Utc>) -> bool {
+        self.is_active(now)
+            && matches_any(&self.user_ids, target.user_id)
+            && matches_any(&self.user_service_api_ids, target.user_service_api_id)
+            && matches_any(&self.provider_key_ids, target.provider_key_id)
+    }
+
+    /// 按全部条件匹配
+    #[must_use]
+    pub fn matches(&self, target: &DebugCaptureTarget<'_>, now: DateTime<Utc>) -> bool {
+        self.matches_identity(target, now)
+            && (self.models.is_empty()
+                || target
+                    .model
+                    .is_some_and(|model| self.models.iter().any(|m| m == model)))
+    }
+
+    /// 校验过期时间与匹配条件
+    pub fn validate(&self) -> error::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        ensure!(
+            self.expires_at.is_some(),
+            ConfigError::Load("debug_capture.expires_at 不能为空".to_string())
+        );
+        ensure!(
+            !(self.user_ids.is_empty()
+                && self.user_service_api_ids.is_empty()
+                && self.provider_key_ids.is_empty()
+                && self.models.is_empty()),
+            ConfigError::Load(
+                "debug_capture 至少需要配置 user_ids、user_service_api_ids、provider_key_ids、models 之一"
+                    .to_string()
+            )
+        );
+        ensure!(
+            self.models.iter().all(|model| !model.trim().is_empty()),
+            ConfigError::Load("debug_capture.models 不能包含空值".to_string())
+        );
+        Ok(())
+    }
+}
+
+fn matches_any(ids: &[i32], id: Option<i32>) -> bool {
+    ids.is_empty() || id.is_some_and(|id| ids.contains(&id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn config(expires_at: DateTime<Utc>) -> DebugCaptureConfig {
+        DebugCaptureConfig {
+            enabled: true,
+            expires_at: Some(expires_at),
+            user_ids: vec![7],
+            models: vec!["gpt-4o".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_only_targeted_requests_until_expiry() {
+        let now = Utc::now();
+        let config = config(now + Duration::minutes(30));
+        let target = DebugCaptureTarget {
+            user_id: Some(7),
+            user_service_api_id: Some(1),
+            provider_key_id: Some(2),
+            model: Some("gpt-4o"),
+        };
+
+        assert!(config.matches(&target, now));
+        assert!(!config.matches(
+            &DebugCaptureTarget {
+                user_id: Some(8),
+                ..target
+            },
+            now
+        ));
+        assert!(!config.matches(
+            &DebugCaptureTarget {
+                model: None,
+                ..target
+            },
+            now
+        ));
+        assert!(config.matches_identity(
+            &DebugCaptureTarget {
+                model: None,
+                ..target
+            },
+            now
+        ));
+
+        assert!(!config.matches(&target, now + Duration::minutes(31)));
+    }
+
+    #[test]
+    fn enabled_config_requires_expiry_and_criteria() {
+        let now = Utc::now();
+        assert!(config(now).validate().is_ok());
+        assert!(
+            DebugCaptureConfig {
+                expires_at: None,
+                ..config(now)
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            DebugCaptureConfig {
+                enabled: true,
+                expires_at: Some(now),
+                ..Default::default()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(DebugCaptureConfig::default().validate().is_ok());
+    }
+}
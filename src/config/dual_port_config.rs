@@ -3,9 +3,9 @@
 //! 仅保留核心必需的配置字段
 
 use crate::ensure;
-use crate::error::{self, Context};
+use crate::error::{self, Context, config::ConfigError};
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// 双端口服务器配置 - 简化版
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +45,9 @@ pub struct ProxyPortConfig {
 pub struct ListenerConfig {
     /// 监听主机
     pub host: String,
+    /// 额外监听主机，与 `host` 共用端口（如同时监听 IPv4 与 IPv6：`host = "0.0.0.0"`，`additional_hosts = ["::"]`）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_hosts: Vec<String>,
     /// 监听端口
     pub port: u16,
     /// 绑定地址（自动计算）
@@ -79,6 +82,7 @@ impl Default for ManagementPortConfig {
         Self {
             http: ListenerConfig {
                 host: "127.0.0.1".to_string(),
+                additional_hosts: Vec::new(),
                 port: 9090,
                 bind_addr: None,
             },
@@ -92,6 +96,7 @@ impl Default for ProxyPortConfig {
         Self {
             http: ListenerConfig {
                 host: "0.0.0.0".to_string(),
+                additional_hosts: Vec::new(),
                 port: 8080,
                 bind_addr: None,
             },
@@ -114,12 +119,36 @@ impl Default for AccessControlConfig {
 impl ListenerConfig {
     /// 获取绑定地址
     pub fn bind_address(&self) -> error::Result<SocketAddr> {
-        let addr = format!("{}:{}", self.host, self.port);
-        addr.parse()
-            .with_context(|| format!("监听地址解析失败: {addr}"))
+        Ok(SocketAddr::new(parse_host(&self.host)?, self.port))
+    }
+
+    /// 获取全部绑定地址（`host` 在前，随后是 `additional_hosts`）
+    pub fn bind_addresses(&self) -> error::Result<Vec<SocketAddr>> {
+        let mut addrs = Vec::with_capacity(1 + self.additional_hosts.len());
+        for host in std::iter::once(&self.host).chain(&self.additional_hosts) {
+            let addr = SocketAddr::new(parse_host(host)?, self.port);
+            ensure!(
+                !addrs.contains(&addr),
+                ConfigError::Load(format!("监听地址重复: {addr}"))
+            );
+            addrs.push(addr);
+        }
+        Ok(addrs)
     }
 }
 
+/// 解析监听主机，IPv6 地址可带方括号（`[::1]`）
+fn parse_host(host: &str) -> error::Result<IpAddr> {
+    let trimmed = host.trim();
+    let literal = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(trimmed);
+    literal
+        .parse()
+        .with_context(|| format!("监听主机解析失败: {host}"))
+}
+
 impl DualPortServerConfig {
     /// 验证配置的有效性
     pub fn validate(&self) -> error::Result<()> {
@@ -130,7 +159,7 @@ impl DualPortServerConfig {
 
         ensure!(
             mgmt_port != proxy_port,
-            error::config::ConfigError::Load(format!(
+            ConfigError::Load(format!(
                 "Management port ({mgmt_port}) conflicts with proxy port ({proxy_port})"
             ))
         );
@@ -138,18 +167,18 @@ impl DualPortServerConfig {
         // 检查工作线程数
         ensure!(
             self.workers > 0,
-            error::config::ConfigError::Load("Worker count must be greater than 0".to_string())
+            ConfigError::Load("Worker count must be greater than 0".to_string())
         );
 
         // 验证监听配置 - 简化版（仅验证HTTP）
         self.management
             .http
-            .bind_address()
+            .bind_addresses()
             .context("管理 HTTP 监听地址无效")?;
 
         self.proxy
             .http
-            .bind_address()
+            .bind_addresses()
             .context("代理 HTTP 监听地址无效")?;
 
         Ok(())
@@ -161,11 +190,11 @@ impl DualPortServerConfig {
         let mut listeners = Vec::new();
 
         // 管理和代理服务现在始终启用
-        if let Ok(addr) = self.management.http.bind_address() {
+        for addr in self.management.http.bind_addresses().unwrap_or_default() {
             listeners.push(("management-http".to_string(), addr, "HTTP".to_string()));
         }
 
-        if let Ok(addr) = self.proxy.http.bind_address() {
+        for addr in self.proxy.http.bind_addresses().unwrap_or_default() {
             listeners.push(("proxy-http".to_string(), addr, "HTTP".to_string()));
         }
 
@@ -199,6 +228,7 @@ mod tests {
     fn test_listener_bind_address() {
         let listener = ListenerConfig {
             host: "127.0.0.1".to_string(),
+            additional_hosts: Vec::new(),
             port: 8080,
             bind_addr: None,
         };
@@ -219,4 +249,63 @@ mod tests {
         assert!(names.contains(&"management-http"));
         assert!(names.contains(&"proxy-http"));
     }
+
+    #[test]
+    fn test_parse_multiple_listen_addresses() {
+        let config: DualPortServerConfig = toml::from_str(
+            r#"
+            [management.http]
+            host = "127.0.0.1"
+            additional_hosts = ["::1"]
+            port = 9090
+
+            [proxy.http]
+            host = "0.0.0.0"
+            additional_hosts = ["[::]", "fe80::1"]
+            port = 8080
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let proxy_addrs: Vec<String> = config
+            .proxy
+            .http
+            .bind_addresses()
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(proxy_addrs, ["0.0.0.0:8080", "[::]:8080", "[fe80::1]:8080"]);
+        assert_eq!(
+            config.management.http.bind_addresses().unwrap()[1].to_string(),
+            "[::1]:9090"
+        );
+        assert_eq!(config.get_all_listeners().len(), 5);
+    }
+
+    #[test]
+    fn test_ipv6_host_only() {
+        let listener = ListenerConfig {
+            host: "::".to_string(),
+            additional_hosts: Vec::new(),
+            port: 8080,
+            bind_addr: None,
+        };
+        assert_eq!(listener.bind_address().unwrap().to_string(), "[::]:8080");
+    }
+
+    #[test]
+    fn test_invalid_listen_addresses_rejected() {
+        let mut config = DualPortServerConfig::default();
+        config.proxy.http.additional_hosts = vec!["not-an-ip".to_string()];
+        assert!(config.validate().is_err());
+
+        // 与 `host` 重复
+        config.proxy.http.additional_hosts = vec!["0.0.0.0".to_string()];
+        assert!(config.validate().is_err());
+
+        config.proxy.http.additional_hosts = vec!["::".to_string()];
+        assert!(config.validate().is_ok());
+    }
 }
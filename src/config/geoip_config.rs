@@ -0,0 +1,43 @@
+//! # `GeoIP` 配置
+//!
+//! 配置 `MaxMind` 数据库后，代理按真实客户端 IP 查询国家与 ASN，写入
+//! `proxy_tracing.client_country` / `proxy_tracing.client_asn` 供滥用分析使用。
+//! 国家库与 ASN 库可分别配置（如 `GeoLite2-Country.mmdb` 与 `GeoLite2-ASN.mmdb`），
+//! 也可指向同一个同时包含两类数据的库。数据库缺失或无法打开时不影响请求转发。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+
+/// `GeoIP` 配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GeoIpConfig {
+    /// 国家数据库路径（`MaxMind` DB 格式），不配置时不记录国家
+    #[serde(default)]
+    pub country_database: Option<String>,
+    /// ASN 数据库路径（`MaxMind` DB 格式），不配置时不记录 ASN
+    #[serde(default)]
+    pub asn_database: Option<String>,
+}
+
+impl GeoIpConfig {
+    /// 是否配置了任一数据库
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.country_database.is_some() || self.asn_database.is_some()
+    }
+
+    /// 校验数据库路径
+    pub fn validate(&self) -> error::Result<()> {
+        for (field, path) in [
+            ("country_database", &self.country_database),
+            ("asn_database", &self.asn_database),
+        ] {
+            ensure!(
+                path.as_ref().is_none_or(|path| !path.trim().is_empty()),
+                ConfigError::Load(format!("geoip.{field} 不能为空字符串"))
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! # 头部名称匹配规则
+//!
+//! 配置中的请求头/响应头名称不区分大小写，以 `*` 结尾表示前缀匹配（如 `x-ratelimit-*`）。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+
+/// 判断头部名称是否命中配置的模式
+pub(crate) fn matches(pattern: &str, name: &str) -> bool {
+    pattern.strip_suffix('*').map_or_else(
+        || pattern.eq_ignore_ascii_case(name),
+        |prefix| {
+            name.len() >= prefix.len()
+                && name.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+        },
+    )
+}
+
+/// 判断头部名称是否命中任一模式
+pub(crate) fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| matches(pattern, name))
+}
+
+/// 校验模式列表，`field` 为配置项路径（用于错误信息）
+pub(crate) fn validate(field: &str, patterns: &[String]) -> error::Result<()> {
+    for (index, pattern) in patterns.iter().enumerate() {
+        let name = pattern.strip_suffix('*').unwrap_or(pattern);
+        ensure!(
+            !name.is_empty(),
+            ConfigError::Load(format!("{field}[{index}]: 头部名称不能为空"))
+        );
+        ensure!(
+            name.bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'),
+            ConfigError::Load(format!("{field}[{index}]: 非法的头部名称: {pattern}"))
+        );
+    }
+    Ok(())
+}
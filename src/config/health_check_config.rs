@@ -0,0 +1,90 @@
+//! # 服务商健康检查配置
+//!
+//! 每个服务商类型使用独立的检查间隔：关键服务商可以检查得更频繁，不常用的服务商放慢频率。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 检查间隔下限（秒），避免对上游造成无意义的探测压力
+const MIN_HEALTH_CHECK_INTERVAL_SECS: u64 = 10;
+
+/// 服务商健康检查配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheckConfig {
+    /// 是否启用定时健康检查
+    #[serde(default)]
+    pub enabled: bool,
+    /// 未单独配置的服务商使用的检查间隔（秒）
+    #[serde(default = "default_interval_secs")]
+    pub default_interval_secs: u64,
+    /// 单次检查超时（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 按服务商类型名称（`provider_types.name`）覆盖的检查间隔（秒）
+    #[serde(default)]
+    pub providers: HashMap<String, u64>,
+}
+
+const fn default_interval_secs() -> u64 {
+    300
+}
+
+const fn default_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_interval_secs: default_interval_secs(),
+            timeout_secs: default_timeout_secs(),
+            providers: HashMap::new(),
+        }
+    }
+}
+
+impl HealthCheckConfig {
+    /// 获取指定服务商的检查间隔
+    #[must_use]
+    pub fn interval_for(&self, provider_name: &str) -> Duration {
+        Duration::from_secs(
+            self.providers
+                .get(provider_name)
+                .copied()
+                .unwrap_or(self.default_interval_secs),
+        )
+    }
+
+    /// 单次检查超时
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    /// 校验检查间隔与超时
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.default_interval_secs >= MIN_HEALTH_CHECK_INTERVAL_SECS,
+            ConfigError::Load(format!(
+                "health_check.default_interval_secs 不能小于 {MIN_HEALTH_CHECK_INTERVAL_SECS}"
+            ))
+        );
+        ensure!(
+            self.timeout_secs > 0,
+            ConfigError::Load("health_check.timeout_secs 必须为正数".to_string())
+        );
+        for (provider, interval) in &self.providers {
+            ensure!(
+                *interval >= MIN_HEALTH_CHECK_INTERVAL_SECS,
+                ConfigError::Load(format!(
+                    "health_check.providers.{provider} 不能小于 {MIN_HEALTH_CHECK_INTERVAL_SECS}"
+                ))
+            );
+        }
+        Ok(())
+    }
+}
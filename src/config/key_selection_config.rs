@@ -0,0 +1,79 @@
+//! # 密钥选择候选集配置
+//!
+//! 服务 API 绑定大量密钥时，每次选择都加载全部密钥代价较高。开启候选上限后只加载按健康状态、
+//! 权重排序的前 N 个密钥交给调度算法，并在短时间内缓存候选集，避免每个请求都扫描整个密钥池。
+//! 健康状态与权重相同的密钥按轮换起点排序，每次重新加载候选集时起点后移，同层密钥轮流进入候选集。
+//! 新添加的密钥可配置预热窗口，窗口内按创建时长逐步提高参与选择的比例（见 `key_pool::key_warmup`）。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 候选集缓存时间上限（毫秒），过长会让健康状态变化迟迟不生效
+const MAX_CANDIDATE_CACHE_MS: u64 = 60_000;
+/// 新密钥预热窗口上限（秒）
+const MAX_NEW_KEY_WARMUP_SECS: u64 = 7 * 24 * 3600;
+
+/// 密钥选择候选集配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeySelectionConfig {
+    /// 每次选择最多考虑的密钥数，0 表示不限制
+    #[serde(default)]
+    pub max_candidates: usize,
+    /// 候选集缓存时间（毫秒），0 表示不缓存
+    #[serde(default)]
+    pub candidate_cache_ms: u64,
+    /// 新密钥预热窗口（秒），0 表示不预热
+    #[serde(default)]
+    pub new_key_warmup_secs: u64,
+}
+
+impl KeySelectionConfig {
+    /// 候选上限，未配置时为 `None`
+    #[must_use]
+    pub const fn max_candidates(&self) -> Option<usize> {
+        if self.max_candidates == 0 {
+            None
+        } else {
+            Some(self.max_candidates)
+        }
+    }
+
+    /// 候选集缓存时间，未配置时为 `None`
+    #[must_use]
+    pub const fn candidate_cache_ttl(&self) -> Option<Duration> {
+        if self.candidate_cache_ms == 0 {
+            None
+        } else {
+            Some(Duration::from_millis(self.candidate_cache_ms))
+        }
+    }
+
+    /// 新密钥预热窗口，未配置时为 `None`
+    #[must_use]
+    pub const fn new_key_warmup(&self) -> Option<Duration> {
+        if self.new_key_warmup_secs == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(self.new_key_warmup_secs))
+        }
+    }
+
+    /// 校验缓存时间与预热窗口
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.candidate_cache_ms <= MAX_CANDIDATE_CACHE_MS,
+            ConfigError::Load(format!(
+                "key_selection.candidate_cache_ms 不能超过 {MAX_CANDIDATE_CACHE_MS}"
+            ))
+        );
+        ensure!(
+            self.new_key_warmup_secs <= MAX_NEW_KEY_WARMUP_SECS,
+            ConfigError::Load(format!(
+                "key_selection.new_key_warmup_secs 不能超过 {MAX_NEW_KEY_WARMUP_SECS}"
+            ))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+//! # 维护模式配置
+//!
+//! 维护模式开启后代理端口对所有请求返回 503，管理端口不受影响。
+//! 这里的配置是启动时的初始状态，运行时可通过管理接口切换，切换结果保存在缓存中。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+
+/// 维护模式配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// 启动时是否处于维护模式
+    #[serde(default)]
+    pub enabled: bool,
+    /// 返回给客户端的提示信息
+    #[serde(default = "default_message")]
+    pub message: String,
+    /// `Retry-After` 响应头的秒数
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+fn default_message() -> String {
+    "服务维护中，请稍后再试".to_string()
+}
+
+const fn default_retry_after_secs() -> u64 {
+    300
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            message: default_message(),
+            retry_after_secs: default_retry_after_secs(),
+        }
+    }
+}
+
+impl MaintenanceConfig {
+    /// 校验提示信息与重试时间
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            !self.message.trim().is_empty(),
+            ConfigError::Load("maintenance.message 不能为空".to_string())
+        );
+        ensure!(
+            self.retry_after_secs > 0,
+            ConfigError::Load("maintenance.retry_after_secs 必须为正数".to_string())
+        );
+        Ok(())
+    }
+}
@@ -3,14 +3,75 @@
 //! 处理应用配置加载、验证和管理
 
 mod app_config;
+mod circuit_breaker_config;
+mod concurrency_config;
+mod cost_aware_config;
+mod credits_config;
 mod database;
+mod debug_capture_config;
 mod dual_port_config;
+mod geoip_config;
+mod header_pattern;
+mod health_check_config;
+mod key_selection_config;
+mod maintenance_config;
 mod manager;
+mod model_check_config;
+mod oauth_session_config;
+mod parameter_policy_config;
+mod rate_limit_config;
+mod request_body_config;
+mod response_body_config;
+mod response_headers_config;
+mod retry_config;
+mod spend_anomaly_config;
+mod streaming_config;
+mod timeout_bounds_config;
+mod token_estimation_config;
+mod total_timeout_config;
+mod trace_config;
+mod trace_sink_config;
+mod trace_writer_config;
+mod upstream_headers_config;
+mod upstream_pool_config;
+mod usage_counter_config;
+mod white_label_config;
 
 pub use app_config::{AppConfig, CacheConfig, CacheType, RedisConfig};
+pub use circuit_breaker_config::CircuitBreakerConfig;
+pub use concurrency_config::ConcurrencyConfig;
+pub use cost_aware_config::CostAwareConfig;
+pub use credits_config::{CreditsConfig, ModelCreditCost};
 pub use database::DatabaseConfig;
+pub use debug_capture_config::{DebugCaptureConfig, DebugCaptureTarget};
 pub use dual_port_config::{DualPortServerConfig, ManagementPortConfig, ProxyPortConfig};
+pub use geoip_config::GeoIpConfig;
+pub use health_check_config::HealthCheckConfig;
+pub use key_selection_config::KeySelectionConfig;
+pub use maintenance_config::MaintenanceConfig;
 pub use manager::ConfigManager;
+pub use model_check_config::ModelCheckConfig;
+pub use oauth_session_config::{OAuthSessionConfig, OAuthSessionPolicy};
+pub use parameter_policy_config::{ParameterPolicyConfig, ParameterPolicyRule, ParameterValues};
+pub use rate_limit_config::{
+    ProviderRateLimit, RateLimitConfig, RateLimitCooldownConfig, RateLimitQueueConfig,
+};
+pub use request_body_config::RequestBodyConfig;
+pub use response_body_config::ResponseBodyConfig;
+pub use response_headers_config::{ResponseHeaderRules, ResponseHeadersConfig};
+pub use retry_config::{NonIdempotentRetry, RetryBudgetConfig, RetryConfig};
+pub use spend_anomaly_config::SpendAnomalyConfig;
+pub use streaming_config::StreamingConfig;
+pub use timeout_bounds_config::TimeoutBoundsConfig;
+pub use token_estimation_config::TokenEstimationConfig;
+pub use total_timeout_config::TotalTimeoutConfig;
+pub use trace_config::{MAX_CAPTURED_REQUEST_BODY_BYTES, TraceConfig};
+pub use trace_sink_config::{HttpTraceSinkConfig, TraceSinkConfig};
+pub use trace_writer_config::{TraceOverflowPolicy, TraceWriterConfig};
+pub use upstream_headers_config::UpstreamHeadersConfig;
+pub use upstream_pool_config::UpstreamPoolConfig;
+pub use usage_counter_config::UsageCounterConfig;
+pub use white_label_config::WhiteLabelConfig;
 
 use crate::error::Context;
 use std::env;
@@ -99,5 +160,33 @@ fn validate_config(config: &AppConfig) -> crate::error::Result<()> {
         .into());
     }
 
+    config.parameter_policy.validate()?;
+    config.rate_limit.validate()?;
+    config.streaming.validate()?;
+    config.trace.validate()?;
+    config.health_check.validate()?;
+    config.circuit_breaker.validate()?;
+    config.upstream_headers.validate()?;
+    config.response_headers.validate()?;
+    config.model_check.validate()?;
+    config.response_body.validate()?;
+    config.total_timeout.validate()?;
+    config.timeout_bounds.validate()?;
+    config.cost_aware.validate()?;
+    config.key_selection.validate()?;
+    config.maintenance.validate()?;
+    config.spend_anomaly.validate()?;
+    config.trace_writer.validate()?;
+    config.trace_sink.validate()?;
+    config.concurrency.validate()?;
+    config.upstream_pool.validate()?;
+    config.retry.validate()?;
+    config.geoip.validate()?;
+    config.usage_counter.validate()?;
+    config.white_label.validate()?;
+    config.debug_capture.validate()?;
+    config.credits.validate()?;
+    config.request_body.validate()?;
+
     Ok(())
 }
@@ -0,0 +1,81 @@
+//! # 模型预检配置
+//!
+//! 转发前按服务商 `/models` 列表校验请求的模型，不存在时直接返回 404 并给出相近模型，
+//! 避免把请求发给上游后只拿到含义不明的错误。模型列表按服务商缓存并定期刷新。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 刷新间隔下限（秒），避免频繁拉取模型列表
+const MIN_MODEL_CHECK_REFRESH_SECS: u64 = 60;
+
+/// 模型预检配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCheckConfig {
+    /// 是否启用模型预检
+    #[serde(default)]
+    pub enabled: bool,
+    /// 模型列表刷新间隔（秒）
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+    /// 拉取模型列表的超时（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 拒绝时最多返回的相近模型数量
+    #[serde(default = "default_max_suggestions")]
+    pub max_suggestions: usize,
+}
+
+const fn default_refresh_interval_secs() -> u64 {
+    600
+}
+
+const fn default_timeout_secs() -> u64 {
+    10
+}
+
+const fn default_max_suggestions() -> usize {
+    5
+}
+
+impl Default for ModelCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            refresh_interval_secs: default_refresh_interval_secs(),
+            timeout_secs: default_timeout_secs(),
+            max_suggestions: default_max_suggestions(),
+        }
+    }
+}
+
+impl ModelCheckConfig {
+    /// 模型列表刷新间隔
+    #[must_use]
+    pub const fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.refresh_interval_secs)
+    }
+
+    /// 拉取模型列表的超时
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    /// 校验刷新间隔与超时
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.refresh_interval_secs >= MIN_MODEL_CHECK_REFRESH_SECS,
+            ConfigError::Load(format!(
+                "model_check.refresh_interval_secs 不能小于 {MIN_MODEL_CHECK_REFRESH_SECS}"
+            ))
+        );
+        ensure!(
+            self.timeout_secs > 0,
+            ConfigError::Load("model_check.timeout_secs 必须为正数".to_string())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+//! # OAuth 会话共享配置
+//!
+//! OAuth 认证的提供商密钥通过 `api_key` 字段引用已授权的会话。`sharing` 决定同一会话能否被多个密钥引用：
+//! - `strict`：一个会话只能被一个有效密钥使用，重复引用返回错误；
+//! - `shared`：允许多个密钥（如不同权重、标签或限额）共用同一会话。
+//!
+//! 无论哪种策略，token 刷新都按会话调度一次：会话已被其他密钥调度时不再重复入队，
+//! 最后一个引用该会话的密钥删除或改用其他会话后才移出刷新队列。
+
+use serde::{Deserialize, Serialize};
+
+/// 同一 OAuth 会话被多个密钥引用时的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthSessionPolicy {
+    /// 一个会话只能被一个有效密钥使用
+    #[default]
+    Strict,
+    /// 多个密钥可以共用同一会话
+    Shared,
+}
+
+impl OAuthSessionPolicy {
+    /// 是否允许多个密钥共用同一会话
+    #[must_use]
+    pub const fn allows_sharing(self) -> bool {
+        matches!(self, Self::Shared)
+    }
+}
+
+/// OAuth 会话共享配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OAuthSessionConfig {
+    /// 同一会话被多个密钥引用时的处理策略
+    #[serde(default)]
+    pub sharing: OAuthSessionPolicy,
+}
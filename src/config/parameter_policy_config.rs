@@ -0,0 +1,139 @@
+//! # 请求参数策略配置
+//!
+//! 按模型或服务 API 为请求体注入缺省参数，并对超出上限的参数进行钳制。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+
+/// 请求参数策略配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParameterPolicyConfig {
+    /// 策略规则，按配置顺序取第一条匹配的规则
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ParameterPolicyRule>,
+}
+
+/// 单条参数策略规则
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParameterPolicyRule {
+    /// 匹配的模型名，支持以 `*` 结尾的前缀匹配；为空表示匹配所有模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 限定生效的服务 API；为空表示所有服务 API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_api_id: Option<i32>,
+    /// 请求未携带时注入的缺省值
+    #[serde(default)]
+    pub defaults: ParameterValues,
+    /// 允许的最大值，超出时钳制到该值
+    #[serde(default)]
+    pub max: ParameterValues,
+}
+
+/// 与提供商无关的通用参数集合
+///
+/// 实际写入请求体时会映射为各提供商的字段名（如 Gemini 的 `generationConfig.maxOutputTokens`）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParameterValues {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+}
+
+impl ParameterPolicyConfig {
+    /// 是否存在可能作用于指定服务 API 的规则（模型在请求体解析后才能确定）
+    #[must_use]
+    pub fn may_apply_to(&self, service_api_id: i32) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.service_api_id.is_none_or(|id| id == service_api_id))
+    }
+
+    /// 查找第一条匹配的规则
+    #[must_use]
+    pub fn find_rule(
+        &self,
+        service_api_id: i32,
+        model: Option<&str>,
+    ) -> Option<&ParameterPolicyRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(service_api_id, model))
+    }
+
+    /// 校验规则取值范围
+    pub fn validate(&self) -> error::Result<()> {
+        for (index, rule) in self.rules.iter().enumerate() {
+            rule.validate(index)?;
+        }
+        Ok(())
+    }
+}
+
+impl ParameterPolicyRule {
+    /// 判断规则是否匹配当前服务 API 与模型
+    #[must_use]
+    pub fn matches(&self, service_api_id: i32, model: Option<&str>) -> bool {
+        if self.service_api_id.is_some_and(|id| id != service_api_id) {
+            return false;
+        }
+        let Some(pattern) = self.model.as_deref() else {
+            return true;
+        };
+        let Some(model) = model else {
+            return false;
+        };
+        pattern
+            .strip_suffix('*')
+            .map_or_else(|| pattern == model, |prefix| model.starts_with(prefix))
+    }
+
+    fn validate(&self, index: usize) -> error::Result<()> {
+        let invalid = |reason: String| {
+            ConfigError::Load(format!("parameter_policy.rules[{index}]: {reason}"))
+        };
+
+        for values in [&self.defaults, &self.max] {
+            if let Some(temperature) = values.temperature {
+                ensure!(
+                    (0.0..=2.0).contains(&temperature),
+                    invalid(format!("temperature 必须位于 [0, 2]，当前为 {temperature}"))
+                );
+            }
+            if let Some(top_p) = values.top_p {
+                ensure!(
+                    top_p > 0.0 && top_p <= 1.0,
+                    invalid(format!("top_p 必须位于 (0, 1]，当前为 {top_p}"))
+                );
+            }
+            if let Some(max_tokens) = values.max_tokens {
+                ensure!(max_tokens > 0, invalid("max_tokens 必须大于 0".to_string()));
+            }
+        }
+
+        let exceeds = |default: Option<f64>, max: Option<f64>| {
+            default.zip(max).is_some_and(|(default, max)| default > max)
+        };
+        ensure!(
+            !exceeds(self.defaults.temperature, self.max.temperature),
+            invalid("temperature 缺省值不能超过上限".to_string())
+        );
+        ensure!(
+            !exceeds(self.defaults.top_p, self.max.top_p),
+            invalid("top_p 缺省值不能超过上限".to_string())
+        );
+        ensure!(
+            !self
+                .defaults
+                .max_tokens
+                .zip(self.max.max_tokens)
+                .is_some_and(|(default, max)| default > max),
+            invalid("max_tokens 缺省值不能超过上限".to_string())
+        );
+        Ok(())
+    }
+}
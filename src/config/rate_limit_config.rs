@@ -0,0 +1,236 @@
+//! # 限流配置
+//!
+//! - 控制超出每分钟请求限制时的处理方式：默认立即返回 429，开启排队后在限定时间内等待窗口释放。
+//! - 提供商级全局限流：上游按账户统一限制 RPM 时，所有用户与密钥共享同一计数窗口。
+//! - 服务商密钥每日请求配额按配置时区的本地午夜重置。
+//! - 上游 429 未携带 `Retry-After` 时密钥的默认冷却时间，可选对连续 429 指数递增。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use crate::types::timezone_utils;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// 每分钟计数窗口长度（毫秒），排队等待时间不应超过该值
+const RATE_LIMIT_WINDOW_MS: u64 = 60_000;
+
+/// 限流配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// 超限排队配置
+    #[serde(default)]
+    pub queue: RateLimitQueueConfig,
+    /// 提供商级全局限流
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<ProviderRateLimit>,
+    /// 服务商密钥每日请求配额（`max_requests_per_day`）的重置时区，默认 UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_reset_timezone: Option<String>,
+    /// 上游 429 未携带 `Retry-After` 时的密钥冷却
+    #[serde(default)]
+    pub cooldown: RateLimitCooldownConfig,
+}
+
+/// 单个提供商的全局限流
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderRateLimit {
+    /// 提供商类型 ID（`provider_types.id`）
+    pub provider_type_id: i32,
+    /// 所有用户合计的每分钟请求上限
+    pub max_requests_per_min: i64,
+}
+
+/// 超限排队配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitQueueConfig {
+    /// 是否启用排队模式，关闭时超限请求立即返回 429
+    #[serde(default)]
+    pub enabled: bool,
+    /// 单个请求最长排队时间（毫秒），超时后返回 429
+    #[serde(default = "default_max_wait_ms")]
+    pub max_wait_ms: u64,
+    /// 排队期间检查窗口是否释放的间隔（毫秒）
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+/// 上游 429 密钥冷却配置
+///
+/// 第 n 次连续 429 的冷却时间为 `default_secs * escalation_multiplier^(n-1)`，不超过 `max_secs`；
+/// 距上次 429 超过 `escalation_window_secs` 后重新从默认冷却开始计算。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitCooldownConfig {
+    /// 默认冷却时间（秒）
+    #[serde(default = "default_cooldown_secs")]
+    pub default_secs: u64,
+    /// 连续 429 的冷却倍数，1 表示不递增
+    #[serde(default = "default_escalation_multiplier")]
+    pub escalation_multiplier: u32,
+    /// 冷却时间上限（秒）
+    #[serde(default = "default_max_cooldown_secs")]
+    pub max_secs: u64,
+    /// 视为连续 429 的时间窗口（秒）
+    #[serde(default = "default_escalation_window_secs")]
+    pub escalation_window_secs: u64,
+}
+
+const fn default_cooldown_secs() -> u64 {
+    60
+}
+
+const fn default_escalation_multiplier() -> u32 {
+    1
+}
+
+const fn default_max_cooldown_secs() -> u64 {
+    3_600
+}
+
+const fn default_escalation_window_secs() -> u64 {
+    600
+}
+
+impl Default for RateLimitCooldownConfig {
+    fn default() -> Self {
+        Self {
+            default_secs: default_cooldown_secs(),
+            escalation_multiplier: default_escalation_multiplier(),
+            max_secs: default_max_cooldown_secs(),
+            escalation_window_secs: default_escalation_window_secs(),
+        }
+    }
+}
+
+impl RateLimitCooldownConfig {
+    /// 第 `streak` 次连续 429（从 1 开始）的冷却时间
+    #[must_use]
+    pub fn cooldown_for(&self, streak: u32) -> Duration {
+        let factor = u64::from(self.escalation_multiplier).saturating_pow(streak.saturating_sub(1));
+        Duration::from_secs(self.default_secs.saturating_mul(factor).min(self.max_secs))
+    }
+
+    /// 连续 429 的判定窗口
+    #[must_use]
+    pub const fn escalation_window(&self) -> Duration {
+        Duration::from_secs(self.escalation_window_secs)
+    }
+
+    fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.default_secs > 0,
+            ConfigError::Load("rate_limit.cooldown.default_secs 必须大于 0".to_string())
+        );
+        ensure!(
+            self.escalation_multiplier >= 1,
+            ConfigError::Load("rate_limit.cooldown.escalation_multiplier 不能小于 1".to_string())
+        );
+        ensure!(
+            self.max_secs >= self.default_secs,
+            ConfigError::Load(format!(
+                "rate_limit.cooldown.max_secs 不能小于 default_secs（{}）",
+                self.default_secs
+            ))
+        );
+        Ok(())
+    }
+}
+
+const fn default_max_wait_ms() -> u64 {
+    5_000
+}
+
+const fn default_poll_interval_ms() -> u64 {
+    100
+}
+
+impl Default for RateLimitQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_wait_ms: default_max_wait_ms(),
+            poll_interval_ms: default_poll_interval_ms(),
+        }
+    }
+}
+
+impl RateLimitQueueConfig {
+    /// 最长排队时间
+    #[must_use]
+    pub const fn max_wait(&self) -> Duration {
+        Duration::from_millis(self.max_wait_ms)
+    }
+
+    /// 轮询间隔
+    #[must_use]
+    pub const fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+}
+
+impl RateLimitConfig {
+    /// 获取提供商的全局每分钟请求上限
+    #[must_use]
+    pub fn provider_limit(&self, provider_type_id: i32) -> Option<i64> {
+        self.providers
+            .iter()
+            .find(|provider| provider.provider_type_id == provider_type_id)
+            .map(|provider| provider.max_requests_per_min)
+    }
+
+    /// 每日配额重置时区
+    #[must_use]
+    pub fn daily_reset_tz(&self) -> Tz {
+        self.daily_reset_timezone
+            .as_deref()
+            .map_or(Tz::UTC, timezone_utils::parse_timezone_safe)
+    }
+
+    /// 校验排队参数与提供商限流配置
+    pub fn validate(&self) -> error::Result<()> {
+        if let Some(timezone) = &self.daily_reset_timezone {
+            ensure!(
+                timezone_utils::is_valid_timezone(timezone),
+                ConfigError::Load(format!(
+                    "rate_limit.daily_reset_timezone 不是有效的时区: {timezone}"
+                ))
+            );
+        }
+        self.cooldown.validate()?;
+
+        let mut seen = HashSet::new();
+        for provider in &self.providers {
+            ensure!(
+                provider.max_requests_per_min > 0,
+                ConfigError::Load(format!(
+                    "rate_limit.providers: provider_type_id {} 的 max_requests_per_min 必须大于 0",
+                    provider.provider_type_id
+                ))
+            );
+            ensure!(
+                seen.insert(provider.provider_type_id),
+                ConfigError::Load(format!(
+                    "rate_limit.providers: provider_type_id {} 重复配置",
+                    provider.provider_type_id
+                ))
+            );
+        }
+
+        if !self.queue.enabled {
+            return Ok(());
+        }
+        ensure!(
+            self.queue.max_wait_ms > 0 && self.queue.max_wait_ms <= RATE_LIMIT_WINDOW_MS,
+            ConfigError::Load(format!(
+                "rate_limit.queue.max_wait_ms 必须位于 (0, {RATE_LIMIT_WINDOW_MS}]，当前为 {}",
+                self.queue.max_wait_ms
+            ))
+        );
+        ensure!(
+            self.queue.poll_interval_ms > 0,
+            ConfigError::Load("rate_limit.queue.poll_interval_ms 必须大于 0".to_string())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+//! # 请求体 JSON 复杂度配置
+//!
+//! 字节数上限拦不住嵌套极深或包含超大数组的 JSON：这类请求体在字节数范围内也会让解析耗费
+//! 大量内存与时间。改写请求体前先按嵌套深度与元素总数扫描，超过上限直接返回 400。
+//! 同一阶段按服务商格式校验工具定义（见 `proxy::tool_validation`），可通过 `validate_tools` 关闭。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+
+/// 嵌套深度上限的最大可配置值（`serde_json` 默认递归上限为 128）
+const MAX_JSON_DEPTH_LIMIT: usize = 128;
+
+/// 请求体 JSON 复杂度配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestBodyConfig {
+    /// 对象与数组的最大嵌套深度，0 表示不限制
+    #[serde(default = "default_max_json_depth")]
+    pub max_json_depth: usize,
+    /// 数组元素与对象成员的总数上限，0 表示不限制
+    #[serde(default = "default_max_json_elements")]
+    pub max_json_elements: usize,
+    /// 转发前校验工具（函数调用）定义，定义无效时直接返回 400
+    #[serde(default = "default_validate_tools")]
+    pub validate_tools: bool,
+}
+
+const fn default_max_json_depth() -> usize {
+    64
+}
+
+const fn default_max_json_elements() -> usize {
+    1_000_000
+}
+
+const fn default_validate_tools() -> bool {
+    true
+}
+
+impl Default for RequestBodyConfig {
+    fn default() -> Self {
+        Self {
+            max_json_depth: default_max_json_depth(),
+            max_json_elements: default_max_json_elements(),
+            validate_tools: default_validate_tools(),
+        }
+    }
+}
+
+impl RequestBodyConfig {
+    /// 嵌套深度上限，未配置时为 `None`
+    #[must_use]
+    pub const fn max_depth(&self) -> Option<usize> {
+        if self.max_json_depth == 0 {
+            None
+        } else {
+            Some(self.max_json_depth)
+        }
+    }
+
+    /// 元素总数上限，未配置时为 `None`
+    #[must_use]
+    pub const fn max_elements(&self) -> Option<usize> {
+        if self.max_json_elements == 0 {
+            None
+        } else {
+            Some(self.max_json_elements)
+        }
+    }
+
+    /// 校验嵌套深度上限
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.max_json_depth <= MAX_JSON_DEPTH_LIMIT,
+            ConfigError::Load(format!(
+                "request_body.max_json_depth 不能超过 {MAX_JSON_DEPTH_LIMIT}"
+            ))
+        );
+        Ok(())
+    }
+}
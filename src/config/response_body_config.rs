@@ -0,0 +1,122 @@
+//! # 响应体处理配置
+//!
+//! 用量提取、元数据提取等响应体处理都按 JSON 解析。Content-Type 不在列表内的响应
+//! （如 `text/plain`、图片、音频）视为不透明内容：原样透传并统计字节数，不缓存、不解析。
+//! 流式响应（`text/event-stream`）可配置为不缓存响应体，用量与元数据仍从事件中逐帧提取。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+
+/// 响应体处理配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseBodyConfig {
+    /// 按 JSON 处理的 Content-Type（不区分大小写，忽略参数），以 `*` 结尾表示前缀匹配；
+    /// `+json` 结尾的类型（如 `application/problem+json`）始终按 JSON 处理
+    #[serde(default = "default_json_content_types")]
+    pub json_content_types: Vec<String>,
+    /// 是否缓存流式成功响应的响应体；关闭后只统计字节数，追踪记录标记为未采集
+    #[serde(default = "default_capture_streaming_body")]
+    pub capture_streaming_body: bool,
+}
+
+const fn default_capture_streaming_body() -> bool {
+    true
+}
+
+fn default_json_content_types() -> Vec<String> {
+    [
+        "application/json",
+        "application/x-ndjson",
+        "application/stream+json",
+        "text/event-stream",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+impl Default for ResponseBodyConfig {
+    fn default() -> Self {
+        Self {
+            json_content_types: default_json_content_types(),
+            capture_streaming_body: default_capture_streaming_body(),
+        }
+    }
+}
+
+impl ResponseBodyConfig {
+    /// 响应体是否按 JSON 处理；缺少 Content-Type 时沿用 JSON 处理
+    #[must_use]
+    pub fn is_json(&self, content_type: Option<&str>) -> bool {
+        let Some(content_type) = content_type else {
+            return true;
+        };
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        if essence.is_empty() || essence.ends_with("+json") {
+            return true;
+        }
+
+        self.json_content_types.iter().any(|pattern| {
+            let pattern = pattern.to_ascii_lowercase();
+            pattern
+                .strip_suffix('*')
+                .map_or(essence == pattern, |prefix| essence.starts_with(prefix))
+        })
+    }
+
+    /// 校验 Content-Type 条目
+    pub fn validate(&self) -> error::Result<()> {
+        for pattern in &self.json_content_types {
+            let prefix = pattern.strip_suffix('*').unwrap_or(pattern);
+            ensure!(
+                !prefix.is_empty() && !prefix.contains(['*', ';', ' ']),
+                ConfigError::Load(format!(
+                    "response_body.json_content_types 包含无效条目: '{pattern}'"
+                ))
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_content_types() {
+        let config = ResponseBodyConfig::default();
+
+        assert!(config.is_json(None));
+        assert!(config.is_json(Some("application/json; charset=utf-8")));
+        assert!(config.is_json(Some("Text/Event-Stream")));
+        assert!(config.is_json(Some("application/problem+json")));
+        assert!(!config.is_json(Some("text/plain; charset=utf-8")));
+        assert!(!config.is_json(Some("image/png")));
+        assert!(!config.is_json(Some("audio/mpeg")));
+    }
+
+    #[test]
+    fn supports_prefix_patterns_and_rejects_invalid_entries() {
+        let config = ResponseBodyConfig {
+            json_content_types: vec!["text/*".to_string()],
+            ..ResponseBodyConfig::default()
+        };
+        assert!(config.is_json(Some("text/plain")));
+        assert!(!config.is_json(Some("image/png")));
+
+        for pattern in ["", "*", "text/*/x", "text/plain; charset=utf-8"] {
+            let config = ResponseBodyConfig {
+                json_content_types: vec![pattern.to_string()],
+                ..ResponseBodyConfig::default()
+            };
+            assert!(config.validate().is_err(), "{pattern} should be rejected");
+        }
+    }
+}
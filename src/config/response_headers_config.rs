@@ -0,0 +1,94 @@
+//! # 下游响应头配置
+//!
+//! 上游响应返回给客户端前会按清理列表移除头部（服务器信息、上游账号的限流状态等）；
+//! 保留列表优先于清理列表，并可按服务商单独追加，例如让客户端看到 `x-ratelimit-*`。
+
+use super::header_pattern;
+use crate::error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 单个服务商追加的响应头规则
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseHeaderRules {
+    /// 额外需要移除的响应头
+    #[serde(default)]
+    pub strip: Vec<String>,
+    /// 额外需要保留的响应头（优先于清理列表）
+    #[serde(default)]
+    pub preserve: Vec<String>,
+}
+
+/// 下游响应头配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseHeadersConfig {
+    /// 需要移除的响应头名称（不区分大小写），以 `*` 结尾表示前缀匹配
+    #[serde(default = "default_strip_headers")]
+    pub strip: Vec<String>,
+    /// 需要保留的响应头，命中后即使在清理列表中也不移除
+    #[serde(default)]
+    pub preserve: Vec<String>,
+    /// 按服务商类型名称（`provider_types.name`）追加的规则
+    #[serde(default)]
+    pub providers: HashMap<String, ResponseHeaderRules>,
+}
+
+fn default_strip_headers() -> Vec<String> {
+    [
+        "server",
+        "x-powered-by",
+        // 上游账号的限流与组织信息，对代理的客户端没有意义
+        "x-ratelimit-*",
+        "anthropic-ratelimit-*",
+        "anthropic-organization-id",
+        "openai-organization",
+        "openai-project",
+        "set-cookie",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+impl Default for ResponseHeadersConfig {
+    fn default() -> Self {
+        Self {
+            strip: default_strip_headers(),
+            preserve: Vec::new(),
+            providers: HashMap::new(),
+        }
+    }
+}
+
+impl ResponseHeadersConfig {
+    /// 判断响应头是否需要在返回客户端前移除
+    #[must_use]
+    pub fn should_strip(&self, provider: Option<&str>, name: &str) -> bool {
+        let rules = provider.and_then(|provider| self.providers.get(provider));
+        let preserved = header_pattern::matches_any(&self.preserve, name)
+            || rules.is_some_and(|rules| header_pattern::matches_any(&rules.preserve, name));
+        if preserved {
+            return false;
+        }
+
+        header_pattern::matches_any(&self.strip, name)
+            || rules.is_some_and(|rules| header_pattern::matches_any(&rules.strip, name))
+    }
+
+    /// 校验清理与保留列表
+    pub fn validate(&self) -> error::Result<()> {
+        header_pattern::validate("response_headers.strip", &self.strip)?;
+        header_pattern::validate("response_headers.preserve", &self.preserve)?;
+        for (provider, rules) in &self.providers {
+            header_pattern::validate(
+                &format!("response_headers.providers.{provider}.strip"),
+                &rules.strip,
+            )?;
+            header_pattern::validate(
+                &format!("response_headers.providers.{provider}.preserve"),
+                &rules.preserve,
+            )?;
+        }
+        Ok(())
+    }
+}
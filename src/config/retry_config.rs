@@ -0,0 +1,152 @@
+//! # 上游重试配置
+//!
+//! 服务 API 的 `retry_count` 决定重试预算，这里决定哪些请求可以被自动重试。
+//! GET/HEAD 与携带幂等键的请求可以放心重放；其余请求（主要是 POST）在上游可能已经执行的
+//! 失败（连接中途断开、500/504 等）后重放会导致重复执行与重复计费。
+//!
+//! 上游持续故障时，大量请求各自按 `retry_count` 重试会把流量放大数倍，`[retry.budget]`
+//! 按服务商限制全局重试速率（令牌桶），预算耗尽后失败的请求不再重试、直接返回。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use pingora_http::RequestHeader;
+use serde::{Deserialize, Serialize};
+
+/// 非幂等请求的重试策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonIdempotentRetry {
+    /// 从不自动重试
+    Never,
+    /// 仅在请求确定未被上游执行时重试（连接失败、429、503）
+    #[default]
+    NotExecuted,
+    /// 与幂等请求一样重试（可能重复执行）
+    Always,
+}
+
+/// 上游重试配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// 非幂等请求的重试策略
+    #[serde(default)]
+    pub non_idempotent: NonIdempotentRetry,
+    /// 标记请求幂等的请求头，携带非空值的请求按幂等请求重试
+    #[serde(default = "default_idempotency_header")]
+    pub idempotency_header: String,
+    /// 按服务商的重试预算
+    #[serde(default)]
+    pub budget: RetryBudgetConfig,
+}
+
+/// 按服务商的重试预算（令牌桶）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryBudgetConfig {
+    /// 是否启用重试预算
+    #[serde(default)]
+    pub enabled: bool,
+    /// 令牌桶容量，即允许的突发重试次数
+    #[serde(default = "default_budget_capacity")]
+    pub capacity: u32,
+    /// 每秒补充的重试次数
+    #[serde(default = "default_budget_refill_per_sec")]
+    pub refill_per_sec: f64,
+}
+
+fn default_idempotency_header() -> String {
+    "idempotency-key".to_string()
+}
+
+const fn default_budget_capacity() -> u32 {
+    20
+}
+
+const fn default_budget_refill_per_sec() -> f64 {
+    2.0
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            non_idempotent: NonIdempotentRetry::default(),
+            idempotency_header: default_idempotency_header(),
+            budget: RetryBudgetConfig::default(),
+        }
+    }
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: default_budget_capacity(),
+            refill_per_sec: default_budget_refill_per_sec(),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// 返回请求适用的非幂等重试限制；幂等请求返回 `None`（不受限制）
+    #[must_use]
+    pub fn guard_for(&self, request: &RequestHeader) -> Option<NonIdempotentRetry> {
+        let idempotent_method = matches!(request.method.as_str(), "GET" | "HEAD");
+        let has_idempotency_key = request
+            .headers
+            .get(self.idempotency_header.as_str())
+            .is_some_and(|value| !value.as_bytes().iter().all(u8::is_ascii_whitespace));
+        (!idempotent_method && !has_idempotency_key).then_some(self.non_idempotent)
+    }
+
+    /// 校验重试配置
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            http::HeaderName::from_bytes(self.idempotency_header.as_bytes()).is_ok(),
+            ConfigError::Load(format!(
+                "retry.idempotency_header 不是合法的请求头名称: {}",
+                self.idempotency_header
+            ))
+        );
+        ensure!(
+            self.budget.capacity > 0,
+            ConfigError::Load("retry.budget.capacity 必须为正数".to_string())
+        );
+        ensure!(
+            self.budget.refill_per_sec.is_finite() && self.budget.refill_per_sec > 0.0,
+            ConfigError::Load("retry.budget.refill_per_sec 必须为正数".to_string())
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, headers: &[(&'static str, &'static str)]) -> RequestHeader {
+        let mut req = RequestHeader::build(method, b"/v1/chat/completions", None).unwrap();
+        for (name, value) in headers {
+            req.append_header(*name, *value).unwrap();
+        }
+        req
+    }
+
+    #[test]
+    fn only_non_idempotent_requests_are_guarded() {
+        let config = RetryConfig::default();
+
+        assert_eq!(config.guard_for(&request("GET", &[])), None);
+        assert_eq!(config.guard_for(&request("HEAD", &[])), None);
+        assert_eq!(
+            config.guard_for(&request("POST", &[("idempotency-key", "req-1")])),
+            None
+        );
+        assert_eq!(
+            config.guard_for(&request("POST", &[])),
+            Some(NonIdempotentRetry::NotExecuted)
+        );
+        assert_eq!(
+            config.guard_for(&request("POST", &[("idempotency-key", " ")])),
+            Some(NonIdempotentRetry::NotExecuted)
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! # 消费异常检测配置
+//!
+//! 后台任务按用户统计最近一小时的消费，并与过去一段时间的平均小时消费（基线）比较，
+//! 超过基线的指定倍数时记录异常标记，可选推送到 webhook。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 消费异常检测配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendAnomalyConfig {
+    /// 是否启用检测任务
+    #[serde(default)]
+    pub enabled: bool,
+    /// 检测间隔（秒）
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// 计算基线使用的历史小时数（不含当前一小时）
+    #[serde(default = "default_baseline_hours")]
+    pub baseline_hours: u32,
+    /// 当前小时消费超过基线的倍数即视为异常
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// 当前小时消费低于该值时不标记，避免低消费用户的小额波动产生噪声
+    #[serde(default = "default_min_hourly_spend")]
+    pub min_hourly_spend: f64,
+    /// 异常通知 webhook 地址，为空时只记录日志与标记
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+const fn default_check_interval_secs() -> u64 {
+    300
+}
+
+const fn default_baseline_hours() -> u32 {
+    7 * 24
+}
+
+const fn default_multiplier() -> f64 {
+    3.0
+}
+
+const fn default_min_hourly_spend() -> f64 {
+    1.0
+}
+
+impl Default for SpendAnomalyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            check_interval_secs: default_check_interval_secs(),
+            baseline_hours: default_baseline_hours(),
+            multiplier: default_multiplier(),
+            min_hourly_spend: default_min_hourly_spend(),
+            webhook_url: None,
+        }
+    }
+}
+
+impl SpendAnomalyConfig {
+    /// 检测间隔
+    #[must_use]
+    pub const fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_secs)
+    }
+
+    /// 校验检测参数与 webhook 地址
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.check_interval_secs > 0,
+            ConfigError::Load("spend_anomaly.check_interval_secs 必须为正数".to_string())
+        );
+        ensure!(
+            self.baseline_hours > 0,
+            ConfigError::Load("spend_anomaly.baseline_hours 必须为正数".to_string())
+        );
+        ensure!(
+            self.multiplier.is_finite() && self.multiplier > 1.0,
+            ConfigError::Load("spend_anomaly.multiplier 必须大于 1".to_string())
+        );
+        ensure!(
+            self.min_hourly_spend.is_finite() && self.min_hourly_spend >= 0.0,
+            ConfigError::Load("spend_anomaly.min_hourly_spend 不能为负数".to_string())
+        );
+        if let Some(url) = &self.webhook_url {
+            let valid = url
+                .parse::<reqwest::Url>()
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            ensure!(
+                valid,
+                ConfigError::Load(format!(
+                    "spend_anomaly.webhook_url 必须是 http(s) 地址: {url}"
+                ))
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! # 流式响应配置
+//!
+//! SSE keepalive：上游在首个数据块之前长时间无输出（如模型思考阶段）时，
+//! 按间隔向客户端发送注释帧，避免客户端或负载均衡因连接空闲而超时断开。
+//!
+//! 按事件刷新：上游数据块默认到达即转发；开启后 SSE 响应按事件边界发送，每次写出的都是完整事件。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// keepalive 最小间隔（毫秒），过于频繁的注释帧只会浪费带宽
+const MIN_SSE_KEEPALIVE_INTERVAL_MS: u64 = 1_000;
+
+/// 流式响应配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamingConfig {
+    /// SSE keepalive 间隔（毫秒），0 表示关闭；开启后流式请求的上游连接改由中转任务建立并固定使用 HTTP/1.1
+    #[serde(default)]
+    pub sse_keepalive_interval_ms: u64,
+    /// SSE 响应按事件边界刷新：完整事件立即发出，数据块末尾未结束的事件暂存，
+    /// 补全、超过单事件缓存上限或流结束时才发出；关闭时数据块原样转发，客户端可能收到半个事件
+    #[serde(default)]
+    pub flush_per_event: bool,
+}
+
+impl StreamingConfig {
+    /// 获取 keepalive 间隔；未开启时返回 `None`
+    #[must_use]
+    pub const fn sse_keepalive_interval(&self) -> Option<Duration> {
+        if self.sse_keepalive_interval_ms == 0 {
+            None
+        } else {
+            Some(Duration::from_millis(self.sse_keepalive_interval_ms))
+        }
+    }
+
+    /// 校验 keepalive 间隔
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.sse_keepalive_interval_ms == 0
+                || self.sse_keepalive_interval_ms >= MIN_SSE_KEEPALIVE_INTERVAL_MS,
+            ConfigError::Load(format!(
+                "streaming.sse_keepalive_interval_ms 必须为 0 或不小于 {MIN_SSE_KEEPALIVE_INTERVAL_MS}，当前为 {}",
+                self.sse_keepalive_interval_ms
+            ))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! # 上游超时范围配置
+//!
+//! 服务 API 的 `timeout_seconds` 由用户自行配置，过小会让正常请求频繁超时，过大则会长期占用上游连接。
+//! 这里配置全局的下限与上限，选取超时时把用户配置收敛到该范围内。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+
+/// 上限的最大可配置值（秒）
+const MAX_TIMEOUT_CEILING_SECS: u64 = 24 * 60 * 60;
+
+/// 上游超时范围配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeoutBoundsConfig {
+    /// 超时下限（秒）
+    #[serde(default = "default_min_secs")]
+    pub min_secs: u64,
+    /// 超时上限（秒）
+    #[serde(default = "default_max_secs")]
+    pub max_secs: u64,
+}
+
+const fn default_min_secs() -> u64 {
+    5
+}
+
+const fn default_max_secs() -> u64 {
+    600
+}
+
+impl Default for TimeoutBoundsConfig {
+    fn default() -> Self {
+        Self {
+            min_secs: default_min_secs(),
+            max_secs: default_max_secs(),
+        }
+    }
+}
+
+impl TimeoutBoundsConfig {
+    /// 把超时收敛到 `[min_secs, max_secs]`
+    #[must_use]
+    pub fn clamp(&self, secs: u64) -> u64 {
+        secs.clamp(self.min_secs, self.max_secs)
+    }
+
+    /// 校验上下限
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.min_secs > 0,
+            ConfigError::Load("timeout_bounds.min_secs 必须大于 0".to_string())
+        );
+        ensure!(
+            self.min_secs <= self.max_secs,
+            ConfigError::Load("timeout_bounds.min_secs 不能大于 max_secs".to_string())
+        );
+        ensure!(
+            self.max_secs <= MAX_TIMEOUT_CEILING_SECS,
+            ConfigError::Load(format!(
+                "timeout_bounds.max_secs 不能大于 {MAX_TIMEOUT_CEILING_SECS}"
+            ))
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_low_timeout_is_raised_to_floor() {
+        let bounds = TimeoutBoundsConfig {
+            min_secs: 10,
+            max_secs: 300,
+        };
+        assert_eq!(bounds.clamp(1), 10);
+        assert_eq!(bounds.clamp(10), 10);
+        assert_eq!(bounds.clamp(120), 120);
+    }
+
+    #[test]
+    fn too_high_timeout_is_lowered_to_ceiling() {
+        let bounds = TimeoutBoundsConfig {
+            min_secs: 10,
+            max_secs: 300,
+        };
+        assert_eq!(bounds.clamp(86_400), 300);
+        assert_eq!(bounds.clamp(300), 300);
+    }
+
+    #[test]
+    fn invalid_bounds_are_rejected() {
+        assert!(TimeoutBoundsConfig::default().validate().is_ok());
+        assert!(
+            TimeoutBoundsConfig {
+                min_secs: 0,
+                max_secs: 300,
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            TimeoutBoundsConfig {
+                min_secs: 600,
+                max_secs: 300,
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
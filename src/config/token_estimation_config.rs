@@ -0,0 +1,14 @@
+//! # Token 估算配置
+//!
+//! 部分 OpenAI 兼容网关不在响应中返回用量，此时按 0 计费。开启后对这类请求用本地分词器
+//! 从请求与响应文本估算 Token 数，追踪记录标记为估算值。只对分词器能识别的模型生效。
+
+use serde::{Deserialize, Serialize};
+
+/// Token 估算配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenEstimationConfig {
+    /// 上游未返回用量时是否用本地分词器估算
+    #[serde(default)]
+    pub enabled: bool,
+}
@@ -0,0 +1,91 @@
+//! # 请求总时长配置
+//!
+//! 上游读超时按单次读取计算，持续慢速吐数据的流式上游可以无限期占用连接。
+//! 这里配置整个请求的墙钟时长上限，超过后无论是否仍有数据块到达都中断请求。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 总时长上限的最大可配置值（秒）
+const MAX_TOTAL_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+
+/// 请求总时长配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TotalTimeoutConfig {
+    /// 未单独配置的服务商使用的总时长上限（秒），0 表示不限制
+    #[serde(default)]
+    pub default_secs: u64,
+    /// 按服务商类型名称（`provider_types.name`）覆盖的总时长上限（秒），0 表示不限制
+    #[serde(default)]
+    pub providers: HashMap<String, u64>,
+}
+
+impl TotalTimeoutConfig {
+    /// 解析请求的总时长上限
+    ///
+    /// 优先级：服务 API 的 `max_response_duration_seconds` > 服务商覆盖 > 默认值；
+    /// 服务 API 配置为非正数时表示该服务 API 不限制。
+    #[must_use]
+    pub fn resolve(&self, provider_name: &str, service_api_secs: Option<i32>) -> Option<Duration> {
+        let secs = match service_api_secs {
+            Some(secs) => u64::try_from(secs).unwrap_or(0),
+            None => self
+                .providers
+                .get(provider_name)
+                .copied()
+                .unwrap_or(self.default_secs),
+        };
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+
+    /// 校验总时长上限
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.default_secs <= MAX_TOTAL_TIMEOUT_SECS,
+            ConfigError::Load(format!(
+                "total_timeout.default_secs 不能大于 {MAX_TOTAL_TIMEOUT_SECS}"
+            ))
+        );
+        for (provider, secs) in &self.providers {
+            ensure!(
+                *secs <= MAX_TOTAL_TIMEOUT_SECS,
+                ConfigError::Load(format!(
+                    "total_timeout.providers.{provider} 不能大于 {MAX_TOTAL_TIMEOUT_SECS}"
+                ))
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_api_overrides_provider_and_default() {
+        let config = TotalTimeoutConfig {
+            default_secs: 600,
+            providers: HashMap::from([("openai".to_string(), 300), ("gemini".to_string(), 0)]),
+        };
+
+        assert_eq!(
+            config.resolve("anthropic", None),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(
+            config.resolve("openai", None),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(config.resolve("gemini", None), None);
+        assert_eq!(
+            config.resolve("openai", Some(30)),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(config.resolve("openai", Some(0)), None);
+        assert_eq!(TotalTimeoutConfig::default().resolve("openai", None), None);
+    }
+}
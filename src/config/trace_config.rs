@@ -0,0 +1,92 @@
+//! # 追踪配置
+//!
+//! 响应元数据：按配置的 JSON 路径从响应体中提取字段（如 `model`、`system_fingerprint`），
+//! 写入 `proxy_tracing.response_metadata` 供分析使用。
+//!
+//! 请求体采集：开启后将发往上游的请求体写入 `proxy_tracing.request_body`，
+//! 管理端可据此回放请求排查问题。请求体可能包含敏感内容，默认关闭。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 响应元数据字段数量上限，避免每个请求都执行大量路径查找
+const MAX_RESPONSE_METADATA_FIELDS: usize = 32;
+
+/// 单条请求体采集上限的最大值，与代理端请求体缓存上限一致
+pub const MAX_CAPTURED_REQUEST_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// 追踪配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceConfig {
+    /// 需要写入追踪记录的响应字段路径（`.` 分隔，数字段表示数组下标），为空时不提取
+    #[serde(default)]
+    pub response_metadata_fields: Vec<String>,
+    /// 是否采集请求体（用于管理端回放）
+    #[serde(default)]
+    pub capture_request_body: bool,
+    /// 采集的请求体字节数上限，超过时不采集
+    #[serde(default = "default_max_captured_request_body_bytes")]
+    pub max_captured_request_body_bytes: usize,
+}
+
+const fn default_max_captured_request_body_bytes() -> usize {
+    64 * 1024
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            response_metadata_fields: Vec::new(),
+            capture_request_body: false,
+            max_captured_request_body_bytes: default_max_captured_request_body_bytes(),
+        }
+    }
+}
+
+impl TraceConfig {
+    /// 请求体采集上限；未开启采集时为 `None`
+    #[must_use]
+    pub const fn request_body_capture_limit(&self) -> Option<usize> {
+        if self.capture_request_body {
+            Some(self.max_captured_request_body_bytes)
+        } else {
+            None
+        }
+    }
+
+    /// 校验响应字段路径与请求体采集上限
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.max_captured_request_body_bytes > 0
+                && self.max_captured_request_body_bytes <= MAX_CAPTURED_REQUEST_BODY_BYTES,
+            ConfigError::Load(format!(
+                "trace.max_captured_request_body_bytes 必须在 1 到 {MAX_CAPTURED_REQUEST_BODY_BYTES} 之间"
+            ))
+        );
+        ensure!(
+            self.response_metadata_fields.len() <= MAX_RESPONSE_METADATA_FIELDS,
+            ConfigError::Load(format!(
+                "trace.response_metadata_fields 最多 {MAX_RESPONSE_METADATA_FIELDS} 项"
+            ))
+        );
+
+        let mut seen = HashSet::new();
+        for (index, path) in self.response_metadata_fields.iter().enumerate() {
+            ensure!(
+                !path.split('.').any(|seg| seg.trim().is_empty()),
+                ConfigError::Load(format!(
+                    "trace.response_metadata_fields[{index}]: 路径不能为空且不能包含空段"
+                ))
+            );
+            ensure!(
+                seen.insert(path.as_str()),
+                ConfigError::Load(format!(
+                    "trace.response_metadata_fields[{index}]: 路径重复: {path}"
+                ))
+            );
+        }
+        Ok(())
+    }
+}
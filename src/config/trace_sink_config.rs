@@ -0,0 +1,155 @@
+//! # 追踪外部输出配置
+//!
+//! 追踪记录默认只写入数据库。配置 `[trace_sink.http]` 后，请求完成时的记录（含采集的请求体与
+//! 响应元数据）还会按批推送到外部 HTTP 接收端（如数据湖采集服务、Kafka REST 代理），
+//! 推送失败按退避重试。数据库仍是统计、限流与日志查询的数据来源。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 单批记录数上限
+const MAX_BATCH_SIZE: usize = 10_000;
+/// 待推送记录数上限
+const MAX_CAPACITY: usize = 1_000_000;
+/// 重试次数上限
+const MAX_RETRIES: u32 = 10;
+
+/// 追踪外部输出配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TraceSinkConfig {
+    /// HTTP 批量接收端，未配置时不推送
+    #[serde(default)]
+    pub http: Option<HttpTraceSinkConfig>,
+}
+
+/// HTTP 批量接收端配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpTraceSinkConfig {
+    /// 接收端地址，记录以 `{"records": [...]}` 的 JSON 格式 POST
+    pub url: String,
+    /// 附加请求头（如鉴权）
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 每批最多记录数
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// 未攒满一批时的最长等待时间（毫秒）
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    /// 待推送记录数上限，超过后丢弃新记录并计数
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// 推送失败后的重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// 首次重试前的等待时间（毫秒），之后每次翻倍
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// 单次推送超时（毫秒）
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+const fn default_batch_size() -> usize {
+    100
+}
+
+const fn default_flush_interval_ms() -> u64 {
+    1000
+}
+
+const fn default_capacity() -> usize {
+    10_000
+}
+
+const fn default_max_retries() -> u32 {
+    3
+}
+
+const fn default_retry_backoff_ms() -> u64 {
+    500
+}
+
+const fn default_timeout_ms() -> u64 {
+    5000
+}
+
+impl TraceSinkConfig {
+    /// 是否配置了外部输出
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.http.is_some()
+    }
+
+    /// 校验接收端地址与批量参数
+    pub fn validate(&self) -> error::Result<()> {
+        if let Some(http) = &self.http {
+            http.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HttpTraceSinkConfig {
+    /// 未攒满一批时的最长等待时间
+    #[must_use]
+    pub const fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+
+    /// 首次重试前的等待时间
+    #[must_use]
+    pub const fn retry_backoff(&self) -> Duration {
+        Duration::from_millis(self.retry_backoff_ms)
+    }
+
+    /// 单次推送超时
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    fn validate(&self) -> error::Result<()> {
+        let valid = self
+            .url
+            .parse::<reqwest::Url>()
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+        ensure!(
+            valid,
+            ConfigError::Load(format!(
+                "trace_sink.http.url 必须是 http(s) 地址: {}",
+                self.url
+            ))
+        );
+        ensure!(
+            self.batch_size > 0 && self.batch_size <= MAX_BATCH_SIZE,
+            ConfigError::Load(format!(
+                "trace_sink.http.batch_size 必须在 1 到 {MAX_BATCH_SIZE} 之间"
+            ))
+        );
+        ensure!(
+            self.capacity >= self.batch_size && self.capacity <= MAX_CAPACITY,
+            ConfigError::Load(format!(
+                "trace_sink.http.capacity 必须在 batch_size 到 {MAX_CAPACITY} 之间"
+            ))
+        );
+        ensure!(
+            self.flush_interval_ms > 0,
+            ConfigError::Load("trace_sink.http.flush_interval_ms 必须为正数".to_string())
+        );
+        ensure!(
+            self.max_retries <= MAX_RETRIES,
+            ConfigError::Load(format!(
+                "trace_sink.http.max_retries 不能超过 {MAX_RETRIES}"
+            ))
+        );
+        ensure!(
+            self.timeout_ms > 0,
+            ConfigError::Load("trace_sink.http.timeout_ms 必须为正数".to_string())
+        );
+        Ok(())
+    }
+}
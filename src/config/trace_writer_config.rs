@@ -0,0 +1,93 @@
+//! # 追踪写入队列配置
+//!
+//! 请求结束时追踪记录先进入有界队列，由后台任务写入数据库，数据库变慢时不再拖慢请求本身。
+//! 队列写满时按 `overflow_policy` 处理：
+//! - `drop_oldest`：丢弃最早排队的记录并计数，请求不等待；
+//! - `block`：请求最多等待 `block_timeout_ms`，仍无空位时丢弃本条记录并计数。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 队列容量上限
+const MAX_CAPACITY: usize = 65_536;
+
+/// `block` 策略下的最长等待时间（毫秒）
+const MAX_BLOCK_TIMEOUT_MS: u64 = 5_000;
+
+/// 队列写满时的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceOverflowPolicy {
+    /// 丢弃最早排队的记录
+    #[default]
+    DropOldest,
+    /// 短暂等待空位，超时后丢弃本条记录
+    Block,
+}
+
+/// 追踪写入队列配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceWriterConfig {
+    /// 是否异步写入；关闭时在请求结束时同步写库
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 队列容量（条）
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// 队列写满时的处理策略
+    #[serde(default)]
+    pub overflow_policy: TraceOverflowPolicy,
+    /// `block` 策略下等待空位的最长时间（毫秒）
+    #[serde(default = "default_block_timeout_ms")]
+    pub block_timeout_ms: u64,
+}
+
+const fn default_enabled() -> bool {
+    true
+}
+
+const fn default_capacity() -> usize {
+    1024
+}
+
+const fn default_block_timeout_ms() -> u64 {
+    50
+}
+
+impl Default for TraceWriterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            capacity: default_capacity(),
+            overflow_policy: TraceOverflowPolicy::default(),
+            block_timeout_ms: default_block_timeout_ms(),
+        }
+    }
+}
+
+impl TraceWriterConfig {
+    /// `block` 策略下等待空位的最长时间
+    #[must_use]
+    pub const fn block_timeout(&self) -> Duration {
+        Duration::from_millis(self.block_timeout_ms)
+    }
+
+    /// 校验队列容量与等待时间
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.capacity > 0 && self.capacity <= MAX_CAPACITY,
+            ConfigError::Load(format!(
+                "trace_writer.capacity 必须在 1 到 {MAX_CAPACITY} 之间"
+            ))
+        );
+        ensure!(
+            self.block_timeout_ms > 0 && self.block_timeout_ms <= MAX_BLOCK_TIMEOUT_MS,
+            ConfigError::Load(format!(
+                "trace_writer.block_timeout_ms 必须在 1 到 {MAX_BLOCK_TIMEOUT_MS} 之间"
+            ))
+        );
+        Ok(())
+    }
+}
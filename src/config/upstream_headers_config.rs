@@ -0,0 +1,132 @@
+//! # 上游请求头配置
+//!
+//! 白名单模式：客户端请求头只有在白名单内才会转发给上游，避免内部或客户端私有头泄露；
+//! 代理自身设置的头（Host、认证头、策略注入的头）不受影响，固定的清理列表仍作为额外的拒绝层生效。
+
+use super::header_pattern;
+use crate::error;
+use serde::{Deserialize, Serialize};
+
+/// 协议层必须保留的请求头（请求体长度、连接升级等）
+const REQUIRED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+    "upgrade",
+    "sec-websocket-key",
+    "sec-websocket-version",
+    "sec-websocket-protocol",
+];
+
+/// 上游请求头配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamHeadersConfig {
+    /// 是否启用白名单；关闭后除清理列表外的请求头全部转发
+    #[serde(default = "default_allowlist_enabled")]
+    pub allowlist_enabled: bool,
+    /// 允许转发的请求头名称（不区分大小写），以 `*` 结尾表示前缀匹配，如 `x-stainless-*`
+    #[serde(default = "default_allowed_headers")]
+    pub allowed: Vec<String>,
+}
+
+const fn default_allowlist_enabled() -> bool {
+    true
+}
+
+fn default_allowed_headers() -> Vec<String> {
+    [
+        "accept",
+        "accept-encoding",
+        "accept-language",
+        "content-type",
+        "content-encoding",
+        "user-agent",
+        "idempotency-key",
+        // Anthropic
+        "anthropic-version",
+        "anthropic-beta",
+        "anthropic-dangerous-direct-browser-access",
+        "x-app",
+        // OpenAI
+        "openai-beta",
+        "openai-organization",
+        "openai-project",
+        "originator",
+        "session_id",
+        // Gemini
+        "x-goog-api-client",
+        // 官方 SDK 附带的运行环境信息
+        "x-stainless-*",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+impl Default for UpstreamHeadersConfig {
+    fn default() -> Self {
+        Self {
+            allowlist_enabled: default_allowlist_enabled(),
+            allowed: default_allowed_headers(),
+        }
+    }
+}
+
+impl UpstreamHeadersConfig {
+    /// 判断客户端请求头是否允许转发给上游
+    #[must_use]
+    pub fn is_allowed(&self, name: &str) -> bool {
+        if !self.allowlist_enabled
+            || REQUIRED_HEADERS
+                .iter()
+                .any(|required| required.eq_ignore_ascii_case(name))
+        {
+            return true;
+        }
+
+        header_pattern::matches_any(&self.allowed, name)
+    }
+
+    /// 校验白名单条目
+    pub fn validate(&self) -> error::Result<()> {
+        header_pattern::validate("upstream_headers.allowed", &self.allowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_allowlist_covers_common_headers() {
+        let config = UpstreamHeadersConfig::default();
+
+        assert!(config.is_allowed("content-type"));
+        assert!(config.is_allowed("Anthropic-Version"));
+        assert!(config.is_allowed("x-stainless-os"));
+        assert!(config.is_allowed("content-length"));
+        assert!(!config.is_allowed("x-internal-user"));
+        assert!(!config.is_allowed("cookie"));
+    }
+
+    #[test]
+    fn disabled_allowlist_forwards_everything() {
+        let config = UpstreamHeadersConfig {
+            allowlist_enabled: false,
+            allowed: Vec::new(),
+        };
+        assert!(config.is_allowed("x-internal-user"));
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        for pattern in ["", "*", "x-bad*header", "bad header"] {
+            let config = UpstreamHeadersConfig {
+                allowlist_enabled: true,
+                allowed: vec![pattern.to_string()],
+            };
+            assert!(config.validate().is_err(), "{pattern} should be rejected");
+        }
+    }
+}
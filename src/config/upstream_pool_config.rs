@@ -0,0 +1,118 @@
+//! # 上游连接池配置
+//!
+//! 控制上游连接的复用：连接池容量、空闲连接保活时长、HTTP/2 保活与 TCP Keepalive。
+//! 高 QPS 场景下调大连接池并延长空闲保活时间，可以减少重复的 TCP/TLS 握手。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 上游连接池配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamPoolConfig {
+    /// 每个代理进程保留的空闲上游连接数上限
+    #[serde(default = "default_pool_size")]
+    pub pool_size: usize,
+    /// 空闲连接在连接池中保留的时间（秒），超时后关闭
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// HTTP/2 连接的 PING 间隔（秒），0 表示不发送
+    #[serde(default = "default_h2_ping_interval_secs")]
+    pub h2_ping_interval_secs: u64,
+    /// 单个 HTTP/2 连接上的最大并发流数
+    #[serde(default = "default_max_h2_streams")]
+    pub max_h2_streams: usize,
+    /// TCP Keepalive：连接空闲多久后开始探测（秒）
+    #[serde(default = "default_tcp_keepalive_idle_secs")]
+    pub tcp_keepalive_idle_secs: u64,
+    /// TCP Keepalive：探测间隔（秒）
+    #[serde(default = "default_tcp_keepalive_interval_secs")]
+    pub tcp_keepalive_interval_secs: u64,
+    /// TCP Keepalive：连续探测失败多少次后断开
+    #[serde(default = "default_tcp_keepalive_count")]
+    pub tcp_keepalive_count: usize,
+}
+
+const fn default_pool_size() -> usize {
+    128
+}
+
+const fn default_idle_timeout_secs() -> u64 {
+    20
+}
+
+const fn default_h2_ping_interval_secs() -> u64 {
+    20
+}
+
+const fn default_max_h2_streams() -> usize {
+    100
+}
+
+const fn default_tcp_keepalive_idle_secs() -> u64 {
+    20
+}
+
+const fn default_tcp_keepalive_interval_secs() -> u64 {
+    5
+}
+
+const fn default_tcp_keepalive_count() -> usize {
+    5
+}
+
+impl Default for UpstreamPoolConfig {
+    fn default() -> Self {
+        Self {
+            pool_size: default_pool_size(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+            h2_ping_interval_secs: default_h2_ping_interval_secs(),
+            max_h2_streams: default_max_h2_streams(),
+            tcp_keepalive_idle_secs: default_tcp_keepalive_idle_secs(),
+            tcp_keepalive_interval_secs: default_tcp_keepalive_interval_secs(),
+            tcp_keepalive_count: default_tcp_keepalive_count(),
+        }
+    }
+}
+
+impl UpstreamPoolConfig {
+    /// 空闲连接保活时长
+    #[must_use]
+    pub const fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout_secs)
+    }
+
+    /// HTTP/2 PING 间隔，未启用时为 `None`
+    #[must_use]
+    pub const fn h2_ping_interval(&self) -> Option<Duration> {
+        if self.h2_ping_interval_secs == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(self.h2_ping_interval_secs))
+        }
+    }
+
+    /// 校验连接池容量与保活参数
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.pool_size > 0,
+            ConfigError::Load("upstream_pool.pool_size 必须为正数".to_string())
+        );
+        ensure!(
+            self.idle_timeout_secs > 0,
+            ConfigError::Load("upstream_pool.idle_timeout_secs 必须为正数".to_string())
+        );
+        ensure!(
+            self.max_h2_streams > 0,
+            ConfigError::Load("upstream_pool.max_h2_streams 必须为正数".to_string())
+        );
+        ensure!(
+            self.tcp_keepalive_idle_secs > 0
+                && self.tcp_keepalive_interval_secs > 0
+                && self.tcp_keepalive_count > 0,
+            ConfigError::Load("upstream_pool.tcp_keepalive_* 必须为正数".to_string())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+//! # 用量计数配置
+//!
+//! 每个请求的请求数、token 数与费用先累加到内存计数器，按 `flush_interval_secs`
+//! 或累计 `flush_max_updates` 次更新后批量写入 `api_key_usage_counters`，
+//! 避免每个请求单独写库；停机时会写入剩余计数。
+
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 写入间隔上限（秒）
+const MAX_FLUSH_INTERVAL_SECS: u64 = 3600;
+
+/// 用量计数配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageCounterConfig {
+    /// 是否启用用量计数
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 定期写入间隔（秒）
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+    /// 累计更新次数达到该值时提前写入
+    #[serde(default = "default_flush_max_updates")]
+    pub flush_max_updates: usize,
+}
+
+const fn default_enabled() -> bool {
+    true
+}
+
+const fn default_flush_interval_secs() -> u64 {
+    10
+}
+
+const fn default_flush_max_updates() -> usize {
+    1000
+}
+
+impl Default for UsageCounterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            flush_interval_secs: default_flush_interval_secs(),
+            flush_max_updates: default_flush_max_updates(),
+        }
+    }
+}
+
+impl UsageCounterConfig {
+    /// 定期写入间隔
+    #[must_use]
+    pub const fn flush_interval(&self) -> Duration {
+        Duration::from_secs(self.flush_interval_secs)
+    }
+
+    /// 校验写入间隔与更新次数阈值
+    pub fn validate(&self) -> error::Result<()> {
+        ensure!(
+            self.flush_interval_secs > 0 && self.flush_interval_secs <= MAX_FLUSH_INTERVAL_SECS,
+            ConfigError::Load(format!(
+                "usage_counter.flush_interval_secs 必须在 1 到 {MAX_FLUSH_INTERVAL_SECS} 之间"
+            ))
+        );
+        ensure!(
+            self.flush_max_updates > 0,
+            ConfigError::Load("usage_counter.flush_max_updates 必须大于 0".to_string())
+        );
+        Ok(())
+    }
+}
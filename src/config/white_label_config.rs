@@ -0,0 +1,80 @@
+//! # 白标配置
+//!
+//! 开启后返回客户端前移除暴露上游服务商的响应头（如 `anthropic-*`、`openai-*` 与上游的 `x-request-id`），
+//! 并可把响应体中的 `model` 字段改写为统一的别名。与 `[response_headers]` 的清理列表不同，
+//! 白标清理不受保留列表影响。
+
+use super::header_pattern;
+use crate::ensure;
+use crate::error::{self, config::ConfigError};
+use serde::{Deserialize, Serialize};
+
+/// 白标配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhiteLabelConfig {
+    /// 是否启用白标模式
+    #[serde(default)]
+    pub enabled: bool,
+    /// 需要移除的服务商标识响应头（不区分大小写），以 `*` 结尾表示前缀匹配
+    #[serde(default = "default_strip_headers")]
+    pub strip_headers: Vec<String>,
+    /// 响应体 `model` 字段改写后的别名，不配置时不改写响应体
+    #[serde(default)]
+    pub model_alias: Option<String>,
+}
+
+fn default_strip_headers() -> Vec<String> {
+    [
+        "anthropic-*",
+        "openai-*",
+        // 上游请求 ID 的格式可识别服务商，代理会写入自己的 x-request-id
+        "x-request-id",
+        "request-id",
+        "x-goog-*",
+        "x-amzn-*",
+        "x-envoy-*",
+        "cf-ray",
+        "cf-cache-status",
+        "via",
+        "alt-svc",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+impl Default for WhiteLabelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strip_headers: default_strip_headers(),
+            model_alias: None,
+        }
+    }
+}
+
+impl WhiteLabelConfig {
+    /// 判断响应头是否暴露上游服务商，需要在返回客户端前移除
+    #[must_use]
+    pub fn should_strip(&self, name: &str) -> bool {
+        self.enabled && header_pattern::matches_any(&self.strip_headers, name)
+    }
+
+    /// 启用时返回响应体 `model` 字段的别名
+    #[must_use]
+    pub fn model_alias(&self) -> Option<&str> {
+        self.model_alias.as_deref().filter(|_| self.enabled)
+    }
+
+    /// 校验清理列表与模型别名
+    pub fn validate(&self) -> error::Result<()> {
+        header_pattern::validate("white_label.strip_headers", &self.strip_headers)?;
+        ensure!(
+            self.model_alias
+                .as_ref()
+                .is_none_or(|alias| !alias.trim().is_empty()),
+            ConfigError::Load("white_label.model_alias 不能为空字符串".to_string())
+        );
+        Ok(())
+    }
+}
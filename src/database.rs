@@ -2,6 +2,7 @@
 //!
 //! 数据库连接和迁移管理
 
+use crate::app::task_scheduler::{TaskRunRecorder, track_run};
 use crate::ensure;
 use crate::error::config::ConfigError;
 use crate::error::{self, Context, ProxyError};
@@ -926,6 +927,7 @@ fn parse_pricing_tiers(price_info: &ModelPriceInfo) -> Vec<PricingTier> {
 pub struct ModelPricingRefreshTask {
     db: Arc<DatabaseConnection>,
     handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    recorder: Option<TaskRunRecorder>,
 }
 
 impl ModelPricingRefreshTask {
@@ -934,26 +936,36 @@ impl ModelPricingRefreshTask {
         Self {
             db,
             handle: Arc::new(RwLock::new(None)),
+            recorder: None,
         }
     }
 
+    /// 每次定时刷新后记录心跳与执行历史
+    #[must_use]
+    pub fn with_run_recorder(mut self, recorder: TaskRunRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     /// 启动每日刷新任务（立即跑一次，然后每24小时运行）
     pub async fn start(&self) -> crate::error::Result<()> {
         if self.handle.read().await.is_some() {
             return Ok(());
         }
 
-        // 同步执行首次刷新，失败则阻断启动
+        // 同步执行首次刷新，失败则阻断启动；首次刷新由调度器作为启动执行记录
         ensure_model_pricing_data(&self.db).await?;
 
         let db = self.db.clone();
+        let recorder = self.recorder.clone();
         let handle = tokio::spawn(async move {
             let mut ticker = time::interval(Duration::from_secs(24 * 60 * 60));
             // 跳过 interval 的即时首 tick，首次刷新已同步完成
             ticker.tick().await;
             loop {
                 ticker.tick().await;
-                if let Err(err) = ensure_model_pricing_data(&db).await {
+                let result = track_run(recorder.as_ref(), ensure_model_pricing_data(&db)).await;
+                if let Err(err) = result {
                     lerror!(
                         "system",
                         LogStage::BackgroundTask,
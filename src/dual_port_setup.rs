@@ -1,21 +1,29 @@
 use crate::{
-    app::{context::AppContext, shared_services::SharedServices, task_scheduler::TaskScheduler},
+    app::{
+        context::AppContext, shared_services::SharedServices, task_scheduler::TaskScheduler,
+        tasks::TaskType,
+    },
     collect::service::CollectService,
     config::{AppConfig, ConfigManager},
     error::{Context, Result},
     linfo,
     logging::{LogComponent, LogStage, log_proxy_error},
     management::server::{ManagementConfig, ManagementServer, ManagementState},
-    pricing::PricingCalculatorService,
+    pricing::{CreditsService, PricingCalculatorService},
     proxy::{
         PingoraProxyServer,
+        admission::AdmissionController,
         authentication_service::AuthenticationService,
+        health_probe::HealthProbeService,
+        model_availability::{HttpModelListFetcher, ModelAvailabilityService},
         request_transform_service::RequestTransformService,
         response_transform_service::ResponseTransformService,
+        retry_budget::RetryBudget,
+        sse_keepalive::SseKeepaliveRelay,
         state::{ProxyServices, ProxyState},
         upstream_service::UpstreamService,
     },
-    trace::TraceManager,
+    trace::{GeoIpLookup, TraceExporter, TraceManager, TraceWriter, UsageCounter},
 };
 use crate::{lerror, lwarn};
 use sea_orm::DatabaseConnection;
@@ -29,18 +37,21 @@ fn create_servers(
     management_state: Arc<ManagementState>,
     proxy_state: Arc<ProxyState>,
 ) -> Result<(ManagementServer, PingoraProxyServer)> {
-    let (management_host, management_port) = config.dual_port.as_ref().map_or_else(
-        || ("127.0.0.1".to_string(), 9090),
-        |dual_port| {
-            (
-                dual_port.management.http.host.clone(),
-                dual_port.management.http.port,
-            )
-        },
-    );
+    let (management_host, management_additional_hosts, management_port) =
+        config.dual_port.as_ref().map_or_else(
+            || ("127.0.0.1".to_string(), Vec::new(), 9090),
+            |dual_port| {
+                (
+                    dual_port.management.http.host.clone(),
+                    dual_port.management.http.additional_hosts.clone(),
+                    dual_port.management.http.port,
+                )
+            },
+        );
 
     let management_config = ManagementConfig {
         bind_address: management_host.clone(),
+        additional_bind_addresses: management_additional_hosts.clone(),
         port: management_port,
         ..Default::default()
     };
@@ -50,25 +61,23 @@ fn create_servers(
         LogStage::Startup,
         LogComponent::ServerSetup,
         "management_listen_info",
-        &format!("[INFO] Management server will listen on {management_host}:{management_port}")
+        &format!("[INFO] Management server will listen on {management_host}:{management_port}"),
+        additional_hosts = ?management_additional_hosts
     );
 
-    let (proxy_host, proxy_port) = config.dual_port.as_ref().map_or_else(
-        || ("0.0.0.0".to_string(), 8080),
-        |d| (d.proxy.http.host.clone(), d.proxy.http.port),
-    );
+    let proxy_server = PingoraProxyServer::new(proxy_state);
+    let proxy_addresses = proxy_server.get_server_addresses()?;
 
     linfo!(
         "system",
         LogStage::Startup,
         LogComponent::ServerSetup,
         "proxy_listen_info",
-        &format!("[INFO] Proxy server will listen on {proxy_host}:{proxy_port}")
+        &format!("[INFO] Proxy server will listen on {proxy_addresses:?}")
     );
 
     let management_server = ManagementServer::new(management_config, management_state)
         .context("Failed to create management server")?;
-    let proxy_server = PingoraProxyServer::new(proxy_state);
 
     Ok((management_server, proxy_server))
 }
@@ -372,7 +381,8 @@ fn build_management_state(app_context: Arc<AppContext>) -> Result<Arc<Management
 }
 
 /// 构建代理端状态
-fn build_proxy_state(app_context: &Arc<AppContext>) -> Arc<ProxyState> {
+#[must_use]
+pub fn build_proxy_state(app_context: &Arc<AppContext>) -> Arc<ProxyState> {
     linfo!(
         "system",
         LogStage::Startup,
@@ -392,14 +402,91 @@ fn build_proxy_state(app_context: &Arc<AppContext>) -> Arc<ProxyState> {
     let trace_system = services_ctx.api_key_trace_service();
 
     let pricing_calculator = Arc::new(PricingCalculatorService::new(db.clone()));
-    let collect_service = Arc::new(CollectService::new(pricing_calculator));
-    let trace_manager = Arc::new(TraceManager::new(
-        trace_system.immediate_tracer(),
-        rate_limiter.clone(),
+    let collect_service = Arc::new(
+        CollectService::new(
+            pricing_calculator,
+            app_context.config().trace.response_metadata_fields.clone(),
+        )
+        .with_response_body_config(app_context.config().response_body.clone())
+        .with_token_estimation(app_context.config().token_estimation.clone()),
+    );
+    let mut trace_manager =
+        TraceManager::new(trace_system.immediate_tracer(), rate_limiter.clone())
+            .with_request_body_capture(app_context.config().trace.request_body_capture_limit());
+    if let Some(writer) = app_context
+        .tasks()
+        .get_task::<TraceWriter>(TaskType::TraceWriter)
+        && writer.is_enabled()
+    {
+        trace_manager = trace_manager.with_writer(writer);
+    }
+    if let Some(exporter) = app_context
+        .tasks()
+        .get_task::<TraceExporter>(TaskType::TraceExporter)
+    {
+        trace_manager = trace_manager.with_exporter(exporter);
+    }
+    if let Some(counter) = app_context
+        .tasks()
+        .get_task::<UsageCounter>(TaskType::UsageCounter)
+        && counter.is_enabled()
+    {
+        trace_manager = trace_manager.with_usage_counter(counter);
+    }
+    if app_context.config().geoip.is_enabled() {
+        trace_manager =
+            trace_manager.with_geoip(Arc::new(GeoIpLookup::open(&app_context.config().geoip)));
+    }
+    let trace_manager = Arc::new(trace_manager);
+    let sse_keepalive = app_context
+        .config()
+        .streaming
+        .sse_keepalive_interval()
+        .and_then(|interval| {
+            SseKeepaliveRelay::new(interval)
+                .map_err(|e| {
+                    lwarn!(
+                        "system",
+                        LogStage::Startup,
+                        LogComponent::ServerSetup,
+                        "sse_keepalive_disabled",
+                        &format!("SSE keepalive 初始化失败，已关闭: {e}")
+                    );
+                })
+                .ok()
+        });
+    let upstream_service = Arc::new(
+        UpstreamService::new(
+            db.clone(),
+            app_context.services().upstream_circuit_breaker(),
+        )
+        .with_pool_config(app_context.config().upstream_pool.clone())
+        .with_sse_keepalive(sse_keepalive),
+    );
+    let req_transform_service = Arc::new(RequestTransformService::new(
+        db.clone(),
+        app_context.config(),
+    ));
+    let resp_transform_service = Arc::new(ResponseTransformService::new(app_context.config()));
+    let model_check_config = app_context.config().model_check.clone();
+    let model_availability = Arc::new(ModelAvailabilityService::new(
+        model_check_config.clone(),
+        Arc::new(HttpModelListFetcher::new(
+            reqwest::Client::new(),
+            model_check_config,
+        )),
+    ));
+
+    let health_probe = Arc::new(HealthProbeService::new(db.clone(), cache_manager.clone()));
+    let admission = Arc::new(AdmissionController::new(
+        app_context.config().concurrency.clone(),
+    ));
+
+    let credits = Arc::new(CreditsService::new(
+        db.clone(),
+        app_context.config().credits.clone(),
     ));
-    let upstream_service = Arc::new(UpstreamService::new(db.clone()));
-    let req_transform_service = Arc::new(RequestTransformService::new(db.clone()));
-    let resp_transform_service = Arc::new(ResponseTransformService::new());
+    let retry_budget = Arc::new(RetryBudget::new(app_context.config().retry.budget.clone()));
 
     let proxy_auth_service = Arc::new(AuthenticationService::new(
         auth_service,
@@ -418,6 +505,12 @@ fn build_proxy_state(app_context: &Arc<AppContext>) -> Arc<ProxyState> {
         resp_transform_service,
         key_scheduler_service: api_key_scheduler_service,
         rate_limiter,
+        model_availability,
+        maintenance: services_ctx.maintenance_service(),
+        health_probe,
+        admission,
+        credits,
+        retry_budget,
     };
 
     let proxy_state = Arc::new(ProxyState::new(app_context.clone(), services));
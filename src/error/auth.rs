@@ -10,6 +10,11 @@ pub enum UsageLimitKind {
     DailyRequests,
     DailyTokens,
     DailyCost,
+    ProviderPerMinute,
+    /// 模型级每分钟请求上限
+    ModelPerMinute,
+    /// 积分余额不足
+    Credits,
 }
 
 /// 限制被触发时的完整上下文
@@ -46,6 +51,15 @@ pub enum AuthError {
     #[error("Permission denied: requires {required}, but user only has {actual}")]
     PermissionDenied { required: String, actual: String },
 
+    #[error("Provider is not allowed for this service API: {0}")]
+    ProviderNotAllowed(String),
+
+    #[error("Request path is not allowed for this service API: {0}")]
+    PathNotAllowed(String),
+
+    #[error("Unsupported auth type: {0}")]
+    UnsupportedAuthType(String),
+
     #[error("Failed to parse authentication header: {0}")]
     HeaderParse(#[from] AuthParseError),
 
@@ -17,6 +17,9 @@ pub enum CacheError {
 
     #[error("Redis 客户端错误: {0}")]
     Redis(#[from] redis::RedisError),
+
+    #[error("缓存后端不可用: {0}")]
+    Unavailable(String),
 }
 
 impl CacheError {
@@ -36,4 +39,27 @@ impl CacheError {
     pub fn unexpected_response(message: impl Into<String>) -> Self {
         Self::UnexpectedResponse(message.into())
     }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::Unavailable(message.into())
+    }
+
+    /// 是否表示缓存后端无法访问（连接失败或处于降级状态），而不是单次命令出错
+    #[must_use]
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            Self::Unavailable(_) => true,
+            Self::Redis(err) => is_connection_error(err),
+            _ => false,
+        }
+    }
+}
+
+/// Redis 错误是否由连接问题引起（拒绝连接、连接断开、超时或 IO 错误）
+#[must_use]
+pub fn is_connection_error(err: &redis::RedisError) -> bool {
+    err.is_io_error()
+        || err.is_connection_refusal()
+        || err.is_connection_dropped()
+        || err.is_timeout()
 }
@@ -1,5 +1,15 @@
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
+use sea_orm::{ConnAcquireErr, DbErr};
+use std::sync::atomic::{AtomicU64, Ordering};
 use thiserror::Error;
 
+/// 连接池耗尽时建议客户端重试的间隔（秒）
+pub const POOL_EXHAUSTED_RETRY_AFTER_SECS: u64 = 5;
+
+/// 进程启动以来获取数据库连接超时的次数，与普通数据库错误分开统计
+static POOL_EXHAUSTED_TOTAL: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Debug, Error)]
 pub enum DatabaseError {
     #[error("Database connection failed: {0}")]
@@ -14,3 +24,29 @@ pub enum DatabaseError {
     #[error("Transaction error: {0}")]
     Transaction(String),
 }
+
+/// 是否为等待连接池空闲连接超时
+#[must_use]
+pub const fn is_pool_exhausted(err: &DbErr) -> bool {
+    matches!(err, DbErr::ConnectionAcquire(ConnAcquireErr::Timeout))
+}
+
+/// 记录一次连接池耗尽
+pub fn record_pool_exhausted(err: &DbErr) {
+    let total = POOL_EXHAUSTED_TOTAL.fetch_add(1, Ordering::Relaxed) + 1;
+    lwarn!(
+        "system",
+        LogStage::Db,
+        LogComponent::Database,
+        "db_pool_exhausted",
+        "获取数据库连接超时，连接池已耗尽",
+        pool_exhausted_total = total,
+        error = %err
+    );
+}
+
+/// 连接池耗尽累计次数
+#[must_use]
+pub fn pool_exhausted_total() -> u64 {
+    POOL_EXHAUSTED_TOTAL.load(Ordering::Relaxed)
+}
@@ -29,6 +29,15 @@ pub enum KeyPoolError {
     #[error("user_service_api {service_api_id} 没有可用的活跃 provider key")]
     NoActiveProviderKeys { service_api_id: i32 },
 
+    #[error("user_service_api {service_api_id} 的 provider key 均已达到每日请求上限")]
+    DailyQuotaExhausted { service_api_id: i32 },
+
+    #[error("user_service_api {service_api_id} 没有带标签 {tag} 的可用 provider key")]
+    NoKeysMatchingTag { service_api_id: i32, tag: String },
+
     #[error("API key health service is unavailable")]
     HealthServiceUnavailable,
+
+    #[error("Provider {provider} health check failed: {reason}")]
+    ProviderUnhealthy { provider: String, reason: String },
 }
@@ -26,4 +26,7 @@ pub enum ManagementError {
 
     #[error("System metrics collection failed")]
     MetricsUnavailable,
+
+    #[error("追踪记录 {trace_id} 无法回放: {reason}")]
+    TraceNotReplayable { trace_id: i32, reason: String },
 }
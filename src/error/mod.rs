@@ -22,6 +22,8 @@ pub mod management;
 pub mod network;
 pub mod prelude;
 pub mod provider;
+pub mod reject;
+pub mod tls;
 pub mod types;
 
 // 4. Context Trait for adding context to errors.
@@ -0,0 +1,133 @@
+//! # 拒绝原因码
+//!
+//! 请求在维护模式、认证、限流、预算、IP 或模型校验阶段被拒绝时，响应体的 `reason_code` 字段与
+//! `X-Reject-Reason` 响应头携带同一个原因码，客户端据此区分拒绝类型，而不必解析提示文案。
+
+use super::auth::{AuthError, UsageLimitKind};
+use serde::Serialize;
+
+/// 携带原因码的响应头
+pub const REJECT_REASON_HEADER: &str = "x-reject-reason";
+
+/// 请求被拒绝的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// 未提供凭证
+    AuthMissing,
+    /// 凭证无效
+    AuthInvalid,
+    /// 凭证格式错误
+    AuthMalformed,
+    /// 凭证已禁用
+    AuthInactive,
+    /// 权限不足
+    AuthPermissionDenied,
+    /// `X-Provider` 指定的服务商不在服务 API 允许范围内
+    ProviderNotAllowed,
+    /// 请求路径不在服务 API 的路径白名单内
+    PathNotAllowed,
+    /// 其他认证失败
+    AuthFailed,
+    /// 每分钟请求数超限
+    RateLimitPerMinute,
+    /// 每日请求数超限
+    RateLimitDailyRequests,
+    /// 服务商（全局）每分钟请求数超限
+    RateLimitProviderPerMinute,
+    /// 模型每分钟请求数超限
+    RateLimitModelPerMinute,
+    /// 每日 Token 预算用尽
+    BudgetDailyTokens,
+    /// 每日成本预算用尽
+    BudgetDailyCost,
+    /// 积分余额不足
+    CreditsInsufficient,
+    /// 服务商密钥每日请求配额用尽
+    KeyQuotaExhausted,
+    /// 客户端 IP 不在允许范围内
+    IpNotAllowed,
+    /// 请求的模型不存在
+    ModelNotFound,
+    /// 请求体格式与目标服务商接口不匹配
+    FormatMismatch,
+    /// 工具（函数调用）定义无效
+    InvalidToolDefinition,
+    /// 请求体 JSON 嵌套过深或元素过多
+    JsonTooComplex,
+    /// 服务 API 不允许流式请求
+    StreamingNotAllowed,
+    /// 服务处于维护模式
+    Maintenance,
+    /// 依赖资源暂时不可用（如数据库连接池耗尽）
+    ServiceUnavailable,
+}
+
+impl RejectReason {
+    /// 原因码字符串（与序列化结果一致）
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::AuthMissing => "auth_missing",
+            Self::AuthInvalid => "auth_invalid",
+            Self::AuthMalformed => "auth_malformed",
+            Self::AuthInactive => "auth_inactive",
+            Self::AuthPermissionDenied => "auth_permission_denied",
+            Self::ProviderNotAllowed => "provider_not_allowed",
+            Self::PathNotAllowed => "path_not_allowed",
+            Self::AuthFailed => "auth_failed",
+            Self::RateLimitPerMinute => "rate_limit_per_minute",
+            Self::RateLimitDailyRequests => "rate_limit_daily_requests",
+            Self::RateLimitProviderPerMinute => "rate_limit_provider_per_minute",
+            Self::RateLimitModelPerMinute => "rate_limit_model_per_minute",
+            Self::BudgetDailyTokens => "budget_daily_tokens",
+            Self::BudgetDailyCost => "budget_daily_cost",
+            Self::CreditsInsufficient => "credits_insufficient",
+            Self::KeyQuotaExhausted => "key_quota_exhausted",
+            Self::IpNotAllowed => "ip_not_allowed",
+            Self::ModelNotFound => "model_not_found",
+            Self::FormatMismatch => "format_mismatch",
+            Self::InvalidToolDefinition => "invalid_tool_definition",
+            Self::JsonTooComplex => "json_too_complex",
+            Self::StreamingNotAllowed => "streaming_not_allowed",
+            Self::Maintenance => "maintenance",
+            Self::ServiceUnavailable => "service_unavailable",
+        }
+    }
+
+    /// 认证错误对应的原因码
+    #[must_use]
+    pub const fn from_auth_error(err: &AuthError) -> Self {
+        match err {
+            AuthError::ApiKeyMissing | AuthError::NotAuthenticated => Self::AuthMissing,
+            AuthError::ApiKeyInvalid(_) => Self::AuthInvalid,
+            AuthError::ApiKeyMalformed | AuthError::HeaderParse(_) => Self::AuthMalformed,
+            AuthError::ApiKeyInactive => Self::AuthInactive,
+            AuthError::PermissionDenied { .. } => Self::AuthPermissionDenied,
+            AuthError::ProviderNotAllowed(_) => Self::ProviderNotAllowed,
+            AuthError::PathNotAllowed(_) => Self::PathNotAllowed,
+            AuthError::UsageLimitExceeded(info) => Self::from_usage_limit(info.kind),
+            AuthError::OAuth(_)
+            | AuthError::Pkce(_)
+            | AuthError::Message(_)
+            | AuthError::UnsupportedAuthType(_)
+            | AuthError::TaskAlreadyRunning
+            | AuthError::TaskNotRunning
+            | AuthError::TaskNotPaused => Self::AuthFailed,
+        }
+    }
+
+    /// 限流类型对应的原因码
+    #[must_use]
+    pub const fn from_usage_limit(kind: UsageLimitKind) -> Self {
+        match kind {
+            UsageLimitKind::PerMinute => Self::RateLimitPerMinute,
+            UsageLimitKind::DailyRequests => Self::RateLimitDailyRequests,
+            UsageLimitKind::DailyTokens => Self::BudgetDailyTokens,
+            UsageLimitKind::DailyCost => Self::BudgetDailyCost,
+            UsageLimitKind::ProviderPerMinute => Self::RateLimitProviderPerMinute,
+            UsageLimitKind::ModelPerMinute => Self::RateLimitModelPerMinute,
+            UsageLimitKind::Credits => Self::CreditsInsufficient,
+        }
+    }
+}
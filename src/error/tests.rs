@@ -199,3 +199,60 @@ fn provider_error_macro_typed() {
             .into();
     assert_eq!(err_auth.error_code(), "PROVIDER_AUTH_FAILED");
 }
+
+#[test]
+fn pingora_tls_errors_map_to_tls_variant() {
+    use crate::error::tls::TlsError;
+    use pingora_core::{Error as PingoraError, ErrorType};
+
+    let cases = [
+        (
+            ErrorType::InvalidCert,
+            "TLS connect() failed: certificate verify failed, certificate has expired",
+            "certificate_expired",
+            "Upstream TLS certificate expired",
+        ),
+        (
+            ErrorType::InvalidCert,
+            "TLS connect() failed: certificate verify failed, Hostname mismatch, SNI: api.example.com",
+            "hostname_mismatch",
+            "Upstream TLS hostname mismatch",
+        ),
+        (
+            ErrorType::TLSHandshakeFailure,
+            "TLS connect() failed: wrong version number",
+            "handshake_failed",
+            "Upstream TLS handshake failed",
+        ),
+        (
+            ErrorType::TLSHandshakeTimedout,
+            "TLS handshake timed out after 10s",
+            "handshake_timeout",
+            "Upstream TLS handshake timed out",
+        ),
+    ];
+
+    for (etype, context, kind, message) in cases {
+        let pingora_err = PingoraError::explain(etype, context);
+        let tls = TlsError::from_pingora(&pingora_err).expect("tls error detected");
+        assert_eq!(tls.kind(), kind);
+
+        let err: ProxyError = pingora_err.into();
+        assert!(matches!(err, ProxyError::Tls(_)));
+        assert_eq!(err.error_code(), "UPSTREAM_TLS_ERROR");
+        assert_eq!(err.status_code(), http::StatusCode::BAD_GATEWAY);
+        assert!(err.to_string().starts_with(message), "{err}");
+    }
+}
+
+#[test]
+fn non_tls_pingora_error_stays_internal() {
+    let pingora_err = pingora_core::Error::explain(
+        pingora_core::ErrorType::ConnectRefused,
+        "connection refused",
+    );
+    assert!(ProxyError::tls(&pingora_err).is_none());
+
+    let err: ProxyError = pingora_err.into();
+    assert!(matches!(err, ProxyError::Internal(_)));
+}
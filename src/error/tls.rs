@@ -0,0 +1,86 @@
+use pingora_core::{Error as PingoraError, ErrorType as PingoraErrorType};
+use thiserror::Error;
+
+/// 与上游建立 TLS 连接失败的具体原因
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TlsError {
+    #[error("Upstream TLS certificate expired: {0}")]
+    CertificateExpired(String),
+
+    #[error("Upstream TLS hostname mismatch: {0}")]
+    HostnameMismatch(String),
+
+    #[error("Upstream TLS certificate invalid: {0}")]
+    InvalidCertificate(String),
+
+    #[error("Upstream TLS handshake timed out: {0}")]
+    HandshakeTimeout(String),
+
+    #[error("Upstream TLS handshake failed: {0}")]
+    HandshakeFailed(String),
+}
+
+impl TlsError {
+    /// 从 Pingora 错误中识别 TLS 失败；非 TLS 错误返回 None
+    ///
+    /// 证书校验失败时 Pingora 只给出 `InvalidCert`，过期与主机名不匹配需要从
+    /// OpenSSL 的校验信息中区分。
+    #[must_use]
+    pub fn from_pingora(err: &PingoraError) -> Option<Self> {
+        let detail = err.to_string();
+        match err.etype {
+            PingoraErrorType::InvalidCert => Some(Self::certificate_error(detail)),
+            PingoraErrorType::TLSHandshakeTimedout => Some(Self::HandshakeTimeout(detail)),
+            PingoraErrorType::TLSHandshakeFailure
+            | PingoraErrorType::TLSWantX509Lookup
+            | PingoraErrorType::HandshakeError => {
+                // 握手阶段的证书校验失败同样带有 OpenSSL 校验信息
+                if detail.to_ascii_lowercase().contains("certificate") {
+                    Some(Self::certificate_error(detail))
+                } else {
+                    Some(Self::HandshakeFailed(detail))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// 按 OpenSSL 校验信息区分证书失败原因
+    fn certificate_error(detail: String) -> Self {
+        let lowered = detail.to_ascii_lowercase();
+        if lowered.contains("expired") {
+            Self::CertificateExpired(detail)
+        } else if lowered.contains("hostname mismatch")
+            || lowered.contains("host name mismatch")
+            || lowered.contains("subject alternative name")
+        {
+            Self::HostnameMismatch(detail)
+        } else {
+            Self::InvalidCertificate(detail)
+        }
+    }
+
+    /// 稳定的失败类型标识，写入追踪记录
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::CertificateExpired(_) => "certificate_expired",
+            Self::HostnameMismatch(_) => "hostname_mismatch",
+            Self::InvalidCertificate(_) => "invalid_certificate",
+            Self::HandshakeTimeout(_) => "handshake_timeout",
+            Self::HandshakeFailed(_) => "handshake_failed",
+        }
+    }
+
+    /// 中文描述，用于日志
+    #[must_use]
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Self::CertificateExpired(_) => "上游证书已过期",
+            Self::HostnameMismatch(_) => "上游证书与主机名不匹配",
+            Self::InvalidCertificate(_) => "上游证书校验失败",
+            Self::HandshakeTimeout(_) => "TLS握手超时",
+            Self::HandshakeFailed(_) => "TLS握手失败",
+        }
+    }
+}
@@ -1,7 +1,7 @@
 //! Defines the core `ProxyError` enum, which is the central error type for the application.
 
 use crate::error::{
-    auth, cache, config, conversion, database, key_pool, management, network, provider,
+    auth, cache, config, conversion, database, key_pool, management, network, provider, tls,
 };
 use http::StatusCode;
 use pingora_core::{Error as PingoraError, ErrorType as PingoraErrorType};
@@ -26,6 +26,10 @@ pub enum ProxyError {
     #[error(transparent)]
     Authentication(#[from] auth::AuthError),
 
+    /// 与上游建立 TLS 连接失败（证书过期、主机名不匹配、握手失败等）
+    #[error(transparent)]
+    Tls(#[from] tls::TlsError),
+
     #[error(transparent)]
     KeyPool(#[from] key_pool::KeyPoolError),
 
@@ -44,6 +48,13 @@ pub enum ProxyError {
     #[error(transparent)]
     Internal(#[from] anyhow::Error),
 
+    /// 依赖资源暂时不可用（如数据库连接池耗尽），客户端可在 `retry_after_secs` 秒后重试
+    #[error("Service unavailable: {message}")]
+    ServiceUnavailable {
+        message: String,
+        retry_after_secs: u64,
+    },
+
     /// Context wrapper to preserve error type while adding context
     #[error("{context}: {source}")]
     Context {
@@ -75,7 +86,8 @@ impl From<std::io::Error> for ProxyError {
 
 impl From<Box<pingora_core::Error>> for ProxyError {
     fn from(e: Box<pingora_core::Error>) -> Self {
-        Self::Internal(anyhow::anyhow!(e.to_string()))
+        tls::TlsError::from_pingora(&e)
+            .map_or_else(|| Self::Internal(anyhow::anyhow!(e.to_string())), Self::Tls)
     }
 }
 
@@ -141,6 +153,13 @@ impl From<reqwest::Error> for ProxyError {
 
 impl From<sea_orm::DbErr> for ProxyError {
     fn from(e: sea_orm::DbErr) -> Self {
+        if database::is_pool_exhausted(&e) {
+            database::record_pool_exhausted(&e);
+            return Self::service_unavailable(
+                "数据库连接池繁忙，请稍后重试",
+                database::POOL_EXHAUSTED_RETRY_AFTER_SECS,
+            );
+        }
         Self::Database(database::DatabaseError::Query(e))
     }
 }
@@ -153,6 +172,42 @@ impl ProxyError {
         Self::Network(network::NetworkError::UpstreamNotAvailable(message.into()))
     }
 
+    /// Classifies a Pingora error as an upstream TLS failure, if it is one.
+    #[must_use]
+    pub fn tls(err: &PingoraError) -> Option<Self> {
+        tls::TlsError::from_pingora(err).map(Self::Tls)
+    }
+
+    /// Creates a temporary service-unavailable error carrying a `Retry-After` hint.
+    pub fn service_unavailable(message: impl Into<String>, retry_after_secs: u64) -> Self {
+        Self::ServiceUnavailable {
+            message: message.into(),
+            retry_after_secs,
+        }
+    }
+
+    /// Returns the suggested `Retry-After` delay in seconds, if any.
+    #[must_use]
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            Self::ServiceUnavailable {
+                retry_after_secs, ..
+            } => Some(*retry_after_secs),
+            Self::Context { source, .. } => source.retry_after_secs(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` when the cache backend is unreachable rather than a single command failing.
+    #[must_use]
+    pub fn is_cache_unavailable(&self) -> bool {
+        match self {
+            Self::Cache(err) => err.is_unavailable(),
+            Self::Context { source, .. } => source.is_cache_unavailable(),
+            _ => false,
+        }
+    }
+
     /// Returns a stable, machine-readable error code for API responses.
     #[must_use]
     pub fn error_code(&self) -> &'static str {
@@ -169,9 +224,13 @@ impl ProxyError {
                 network::NetworkError::BadGateway(_) => "BAD_GATEWAY",
                 _ => "NETWORK_ERROR",
             },
+            Self::Tls(_) => "UPSTREAM_TLS_ERROR",
             Self::Authentication(auth_err) => match auth_err {
                 auth::AuthError::ApiKeyInvalid(_) => "API_KEY_INVALID",
                 auth::AuthError::PermissionDenied { .. } => "PERMISSION_DENIED",
+                auth::AuthError::ProviderNotAllowed(_) => "PROVIDER_NOT_ALLOWED",
+                auth::AuthError::PathNotAllowed(_) => "PATH_NOT_ALLOWED",
+                auth::AuthError::UnsupportedAuthType(_) => "UNSUPPORTED_AUTH_TYPE",
                 auth::AuthError::NotAuthenticated => "NOT_AUTHENTICATED",
                 auth::AuthError::ApiKeyMissing => "API_KEY_MISSING",
                 auth::AuthError::UsageLimitExceeded(_) => "RATE_LIMIT_EXCEEDED",
@@ -196,9 +255,16 @@ impl ProxyError {
                 key_pool::KeyPoolError::NoActiveProviderKeys { .. } => {
                     "SCHEDULER_PROVIDER_KEYS_INACTIVE"
                 }
+                key_pool::KeyPoolError::DailyQuotaExhausted { .. } => {
+                    "SCHEDULER_DAILY_QUOTA_EXHAUSTED"
+                }
+                key_pool::KeyPoolError::NoKeysMatchingTag { .. } => {
+                    "SCHEDULER_NO_KEYS_MATCHING_TAG"
+                }
                 key_pool::KeyPoolError::HealthServiceUnavailable => {
                     "SCHEDULER_HEALTH_SERVICE_UNAVAILABLE"
                 }
+                key_pool::KeyPoolError::ProviderUnhealthy { .. } => "HEALTH_CHECK_FAILURE",
             },
             Self::Cache(cache_err) => match cache_err {
                 cache::CacheError::Config(_) => "CACHE_CONFIG_ERROR",
@@ -206,6 +272,7 @@ impl ProxyError {
                 cache::CacheError::Operation(_) => "CACHE_OPERATION_ERROR",
                 cache::CacheError::UnexpectedResponse(_) => "CACHE_UNEXPECTED_RESPONSE",
                 cache::CacheError::Redis(_) => "CACHE_BACKEND_ERROR",
+                cache::CacheError::Unavailable(_) => "CACHE_UNAVAILABLE",
             },
             Self::Management(err) => match err {
                 management::ManagementError::ProviderKeyNotFound { .. } => {
@@ -225,6 +292,9 @@ impl ProxyError {
                 }
                 management::ManagementError::MissingTask { .. } => "MANAGEMENT_TASK_MISSING",
                 management::ManagementError::MetricsUnavailable => "MANAGEMENT_METRICS_UNAVAILABLE",
+                management::ManagementError::TraceNotReplayable { .. } => {
+                    "MANAGEMENT_TRACE_NOT_REPLAYABLE"
+                }
             },
             Self::Conversion(_) => "CONVERSION_ERROR",
             Self::Provider(err) => match err {
@@ -238,6 +308,7 @@ impl ProxyError {
                 provider::ProviderError::General { .. } => "AI_PROVIDER_ERROR",
             },
             Self::Internal(_) => "INTERNAL_SERVER_ERROR",
+            Self::ServiceUnavailable { .. } => "SERVICE_UNAVAILABLE",
             Self::Context { source, .. } => source.error_code(),
         }
     }
@@ -250,6 +321,7 @@ impl ProxyError {
             // Client-side errors (typically 4xx)
             Self::Authentication(_)
             | Self::Conversion(_)
+            | Self::Management(management::ManagementError::TraceNotReplayable { .. })
             | Self::Network(network::NetworkError::RateLimitExceeded)
             | Self::Provider(
                 provider::ProviderError::ModelNotFound { .. }
@@ -294,7 +366,9 @@ impl ProxyError {
         match self {
             Self::Authentication(auth_err) => match auth_err {
                 auth::AuthError::UsageLimitExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
-                auth::AuthError::PermissionDenied { .. } => StatusCode::FORBIDDEN,
+                auth::AuthError::PermissionDenied { .. }
+                | auth::AuthError::ProviderNotAllowed(_)
+                | auth::AuthError::PathNotAllowed(_) => StatusCode::FORBIDDEN,
                 auth::AuthError::TaskAlreadyRunning
                 | auth::AuthError::TaskNotRunning
                 | auth::AuthError::TaskNotPaused => StatusCode::CONFLICT,
@@ -302,6 +376,7 @@ impl ProxyError {
                 auth::AuthError::OAuth(_)
                 | auth::AuthError::Pkce(_)
                 | auth::AuthError::HeaderParse(_)
+                | auth::AuthError::UnsupportedAuthType(_)
                 | auth::AuthError::Message(_) => StatusCode::BAD_REQUEST,
                 auth::AuthError::NotAuthenticated
                 | auth::AuthError::ApiKeyMissing
@@ -320,7 +395,7 @@ impl ProxyError {
                 | network::NetworkError::ReadTimeout(_)
                 | network::NetworkError::WriteTimeout(_),
             ) => StatusCode::GATEWAY_TIMEOUT,
-            Self::Network(_) => StatusCode::BAD_GATEWAY,
+            Self::Network(_) | Self::Tls(_) => StatusCode::BAD_GATEWAY,
 
             Self::Provider(err) => match err {
                 provider::ProviderError::AuthFailed(_)
@@ -335,9 +410,16 @@ impl ProxyError {
                 provider::ProviderError::RateLimitExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
             },
 
+            Self::KeyPool(key_pool::KeyPoolError::DailyQuotaExhausted { .. }) => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
             Self::KeyPool(_) => StatusCode::SERVICE_UNAVAILABLE,
-            Self::Conversion(_) => StatusCode::BAD_REQUEST,
+            Self::Conversion(_)
+            | Self::Management(management::ManagementError::TraceNotReplayable { .. }) => {
+                StatusCode::BAD_REQUEST
+            }
             Self::Cache(_) | Self::Management(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ServiceUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
 
             Self::Context { source, .. } => source.status_code(),
         }
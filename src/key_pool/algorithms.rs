@@ -2,6 +2,7 @@
 //!
 //! 专注于从用户的多个API密钥中选择合适的密钥进行请求
 
+use super::api_key_latency::ApiKeyLatencyStats;
 use super::types::SchedulingStrategy;
 use crate::error::{ProxyError, Result};
 use crate::types::ProviderTypeId;
@@ -11,9 +12,13 @@ use crate::{
     lwarn,
 };
 use dashmap::DashMap;
-use entity::user_provider_keys;
+use entity::{model_pricing, model_pricing_tiers, user_provider_keys};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde_json::{Value, json};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 /// 选择上下文
 #[derive(Debug, Clone)]
@@ -28,6 +33,14 @@ pub struct SelectionContext {
     pub provider_type_id: ProviderTypeId,
     /// 路由分组（通常为请求路径）
     pub route_group: String,
+    /// 请求的模型（可从路径或查询参数得知时填充，用于成本感知调度）
+    pub model: Option<String>,
+    /// 只在 `provider_type_id` 对应服务商的密钥中选择（请求通过 `X-Provider` 指定服务商或命中模型路由时）
+    pub pin_provider: bool,
+    /// 替代服务 API 密钥池的密钥（命中的模型路由规则指定了密钥时）
+    pub key_ids: Option<Vec<i32>>,
+    /// 记录选择依据（候选集与各候选评分），由服务 API 的 `selection_debug` 开关控制
+    pub capture_debug: bool,
 }
 
 impl SelectionContext {
@@ -45,8 +58,40 @@ impl SelectionContext {
             user_service_api_id,
             provider_type_id,
             route_group,
+            model: None,
+            pin_provider: false,
+            key_ids: None,
+            capture_debug: false,
         }
     }
+
+    /// 设置请求的模型
+    #[must_use]
+    pub fn with_model(mut self, model: Option<String>) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// 限定只使用 `provider_type_id` 对应服务商的密钥
+    #[must_use]
+    pub const fn with_pinned_provider(mut self, pinned: bool) -> Self {
+        self.pin_provider = pinned;
+        self
+    }
+
+    /// 使用指定的密钥替代服务 API 的密钥池
+    #[must_use]
+    pub fn with_key_ids(mut self, key_ids: Option<Vec<i32>>) -> Self {
+        self.key_ids = key_ids;
+        self
+    }
+
+    /// 设置是否记录选择依据
+    #[must_use]
+    pub const fn with_selection_debug(mut self, enabled: bool) -> Self {
+        self.capture_debug = enabled;
+        self
+    }
 }
 
 /// API密钥选择结果
@@ -62,6 +107,8 @@ pub struct ApiKeySelectionResult {
     pub strategy: SchedulingStrategy,
     /// 选择时间戳
     pub timestamp: std::time::Instant,
+    /// 选择依据（仅在 `SelectionContext::capture_debug` 开启时填充）
+    pub selection_debug: Option<Value>,
 }
 
 impl ApiKeySelectionResult {
@@ -78,8 +125,41 @@ impl ApiKeySelectionResult {
             reason,
             strategy,
             timestamp: std::time::Instant::now(),
+            selection_debug: None,
         }
     }
+
+    /// 附加选择依据
+    #[must_use]
+    pub fn with_selection_debug(mut self, selection_debug: Option<Value>) -> Self {
+        self.selection_debug = selection_debug;
+        self
+    }
+}
+
+/// 单个候选密钥的基础调试信息，各策略在此基础上补充评分
+fn candidate_debug(key: &user_provider_keys::Model) -> serde_json::Map<String, Value> {
+    let mut candidate = serde_json::Map::new();
+    candidate.insert("key_id".to_string(), json!(key.id));
+    candidate.insert("name".to_string(), json!(key.name));
+    candidate.insert("health_status".to_string(), json!(key.health_status));
+    candidate
+}
+
+/// 组装选择依据：策略、候选集大小、各候选评分与选中的密钥
+fn selection_debug(
+    strategy: SchedulingStrategy,
+    candidates: Vec<serde_json::Map<String, Value>>,
+    selected_key: &user_provider_keys::Model,
+    counter: usize,
+) -> Value {
+    json!({
+        "strategy": strategy.as_str(),
+        "candidate_count": candidates.len(),
+        "candidates": candidates,
+        "counter": counter,
+        "selected_key_id": selected_key.id,
+    })
 }
 
 /// API密钥选择器特质
@@ -201,12 +281,23 @@ impl ApiKeySelector for RoundRobinApiKeySelector {
             reason = %reason
         );
 
+        let debug = context.capture_debug.then(|| {
+            let candidates = active_keys.iter().map(|key| candidate_debug(key)).collect();
+            selection_debug(
+                SchedulingStrategy::RoundRobin,
+                candidates,
+                selected_key,
+                counter,
+            )
+        });
+
         Ok(ApiKeySelectionResult::new(
             selected_index,
             selected_key.clone(),
             reason,
             SchedulingStrategy::RoundRobin,
-        ))
+        )
+        .with_selection_debug(debug))
     }
 
     fn name(&self) -> &'static str {
@@ -263,6 +354,8 @@ impl ApiKeySelector for WeightedApiKeySelector {
 
         // 根据权重创建扩展列表
         let mut weighted_list: Vec<&user_provider_keys::Model> = Vec::new();
+        let mut key_weights: Vec<(&user_provider_keys::Model, usize)> =
+            Vec::with_capacity(active_keys.len());
         for key in &active_keys {
             // 如果权重为None或无效，则默认为1
             let weight_i32 = key.weight.unwrap_or(1).max(0);
@@ -278,6 +371,7 @@ impl ApiKeySelector for WeightedApiKeySelector {
                 );
                 1
             });
+            key_weights.push((*key, weight));
             for _ in 0..weight {
                 weighted_list.push(key);
             }
@@ -334,12 +428,35 @@ impl ApiKeySelector for WeightedApiKeySelector {
             reason = %reason
         );
 
+        let debug = context.capture_debug.then(|| {
+            let total_weight = f64::from(u32::try_from(weighted_list.len()).unwrap_or(u32::MAX));
+            let candidates = key_weights
+                .iter()
+                .map(|(key, weight)| {
+                    let mut candidate = candidate_debug(key);
+                    candidate.insert("weight".to_string(), json!(weight));
+                    // 评分为该密钥在加权列表中所占份额
+                    let share =
+                        f64::from(u32::try_from(*weight).unwrap_or(u32::MAX)) / total_weight;
+                    candidate.insert("score".to_string(), json!(share));
+                    candidate
+                })
+                .collect();
+            selection_debug(
+                SchedulingStrategy::Weighted,
+                candidates,
+                selected_key,
+                counter,
+            )
+        });
+
         Ok(ApiKeySelectionResult::new(
             selected_index,
             selected_key.clone(),
             reason,
             SchedulingStrategy::Weighted,
-        ))
+        )
+        .with_selection_debug(debug))
     }
 
     fn name(&self) -> &'static str {
@@ -351,11 +468,277 @@ impl ApiKeySelector for WeightedApiKeySelector {
     }
 }
 
-/// 创建API密钥选择器
+/// 成本感知API密钥选择器
+///
+/// 按密钥所属服务商的模型定价估算单价（基础阶梯的输入 + 输出单价），选择最便宜的密钥，
+/// 同价密钥之间轮询。请求模型未知时使用服务商已定价模型的平均单价；没有定价的服务商排在最后。
+/// 平均响应延迟超过上限的密钥不参与比价，全部超限时忽略延迟上限。
+pub struct CostAwareApiKeySelector {
+    db: Arc<DatabaseConnection>,
+    latency: Arc<ApiKeyLatencyStats>,
+    max_latency: Option<Duration>,
+    price_cache_ttl: Duration,
+    /// (服务商类型ID, 模型) -> (估算单价, 缓存时间, 定价缓存版本号)
+    prices: DashMap<(ProviderTypeId, Option<String>), (Option<f64>, Instant, u64)>,
+    counters: DashMap<(i32, String), Arc<AtomicUsize>>,
+}
+
+impl CostAwareApiKeySelector {
+    #[must_use]
+    pub fn new(
+        db: Arc<DatabaseConnection>,
+        latency: Arc<ApiKeyLatencyStats>,
+        max_latency: Option<Duration>,
+        price_cache_ttl: Duration,
+    ) -> Self {
+        Self {
+            db,
+            latency,
+            max_latency,
+            price_cache_ttl,
+            prices: DashMap::new(),
+            counters: DashMap::new(),
+        }
+    }
+
+    /// 过滤平均延迟超过上限的密钥；全部超限时返回原列表
+    fn within_latency_guard<'a>(
+        &self,
+        keys: &[&'a user_provider_keys::Model],
+        context: &SelectionContext,
+    ) -> Vec<&'a user_provider_keys::Model> {
+        let Some(max_latency) = self.max_latency else {
+            return keys.to_vec();
+        };
+        let within: Vec<&user_provider_keys::Model> = keys
+            .iter()
+            .copied()
+            .filter(|key| {
+                self.latency
+                    .average(key.id)
+                    .is_none_or(|average| average <= max_latency)
+            })
+            .collect();
+        if within.is_empty() {
+            lwarn!(
+                &context.request_id,
+                LogStage::Scheduling,
+                LogComponent::KeyPool,
+                "cost_aware_latency_guard_exhausted",
+                "All keys exceed the latency limit, ignoring it for this selection",
+                max_latency_ms = max_latency.as_millis()
+            );
+            return keys.to_vec();
+        }
+        within
+    }
+
+    /// 估算单价（带缓存，定价被修改后缓存立即失效）
+    async fn unit_price(
+        &self,
+        provider_type_id: ProviderTypeId,
+        model: Option<&str>,
+    ) -> Result<Option<f64>> {
+        let cache_key = (provider_type_id, model.map(ToString::to_string));
+        let generation = crate::pricing::pricing_cache_generation();
+        if let Some(entry) = self.prices.get(&cache_key)
+            && entry.2 == generation
+            && entry.1.elapsed() < self.price_cache_ttl
+        {
+            return Ok(entry.0);
+        }
+        let price = self.load_unit_price(provider_type_id, model).await?;
+        self.prices
+            .insert(cache_key, (price, Instant::now(), generation));
+        Ok(price)
+    }
+
+    async fn load_unit_price(
+        &self,
+        provider_type_id: ProviderTypeId,
+        model: Option<&str>,
+    ) -> Result<Option<f64>> {
+        let mut query = model_pricing::Entity::find()
+            .filter(model_pricing::Column::ProviderTypeId.eq(provider_type_id));
+        if let Some(model) = model {
+            query = query.filter(model_pricing::Column::ModelName.eq(model));
+        }
+        let pricing_ids: Vec<i32> = query
+            .all(&*self.db)
+            .await?
+            .into_iter()
+            .map(|pricing| pricing.id)
+            .collect();
+        if pricing_ids.is_empty() {
+            return Ok(None);
+        }
+
+        let base_tiers = model_pricing_tiers::Entity::find()
+            .filter(model_pricing_tiers::Column::ModelPricingId.is_in(pricing_ids))
+            .filter(model_pricing_tiers::Column::MinTokens.eq(0))
+            .filter(model_pricing_tiers::Column::TokenType.is_in(["prompt", "completion"]))
+            .all(&*self.db)
+            .await?;
+        let mut per_model: HashMap<i32, f64> = HashMap::new();
+        for tier in base_tiers {
+            *per_model.entry(tier.model_pricing_id).or_default() += tier.price_per_token;
+        }
+        if per_model.is_empty() {
+            return Ok(None);
+        }
+        let count = f64::from(u32::try_from(per_model.len()).unwrap_or(u32::MAX));
+        Ok(Some(per_model.values().sum::<f64>() / count))
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeySelector for CostAwareApiKeySelector {
+    async fn select_key(
+        &self,
+        keys: &[user_provider_keys::Model],
+        context: &SelectionContext,
+    ) -> Result<ApiKeySelectionResult> {
+        if keys.is_empty() {
+            return Err(ProxyError::upstream_not_available(
+                "No API keys available for selection".to_string(),
+            ));
+        }
+
+        let active_keys: Vec<&user_provider_keys::Model> =
+            keys.iter().filter(|key| key.is_active).collect();
+        if active_keys.is_empty() {
+            return Err(ProxyError::upstream_not_available(
+                "No active API keys available for selection".to_string(),
+            ));
+        }
+
+        let candidates = self.within_latency_guard(&active_keys, context);
+        let mut priced = Vec::with_capacity(candidates.len());
+        for key in candidates {
+            let price = self
+                .unit_price(key.provider_type_id, context.model.as_deref())
+                .await?;
+            priced.push((key, price));
+        }
+
+        // 没有定价的密钥只在所有候选都没有定价时参与选择
+        let cheapest_price = priced
+            .iter()
+            .filter_map(|(_, price)| *price)
+            .min_by(f64::total_cmp);
+        let cheapest: Vec<&user_provider_keys::Model> = priced
+            .iter()
+            .filter(|(_, price)| {
+                cheapest_price.is_none_or(|cheapest| price.is_some_and(|p| p <= cheapest))
+            })
+            .map(|(key, _)| *key)
+            .collect();
+
+        let group_key = (context.user_service_api_id, context.route_group.clone());
+        let counter = self
+            .counters
+            .entry(group_key)
+            .or_insert_with(|| Arc::new(AtomicUsize::new(0)))
+            .fetch_add(1, Ordering::SeqCst);
+        let selected_key = cheapest[counter % cheapest.len()];
+
+        let selected_index = keys
+            .iter()
+            .position(|key| key.id == selected_key.id)
+            .expect("selected key should exist in the original keys array");
+
+        let reason = format!(
+            "Cost aware selection: model={:?}, unit_price={:?}, tied_keys={}, selected_key_id={}",
+            context.model,
+            cheapest_price,
+            cheapest.len(),
+            selected_key.id
+        );
+
+        ldebug!(
+            &context.request_id,
+            LogStage::Scheduling,
+            LogComponent::KeyPool,
+            "select_key",
+            "Selected API key using cost aware strategy",
+            selected_key_id = selected_key.id,
+            route_group = context.route_group.as_str(),
+            latency_ms = ?self.latency.average(selected_key.id).map(|avg| avg.as_millis()),
+            reason = %reason
+        );
+
+        let debug = context.capture_debug.then(|| {
+            let candidates = priced
+                .iter()
+                .map(|(key, price)| {
+                    let mut candidate = candidate_debug(key);
+                    // 评分为估算单价，越低越优先
+                    candidate.insert("score".to_string(), json!(price));
+                    candidate.insert(
+                        "latency_ms".to_string(),
+                        json!(self.latency.average(key.id).map(|avg| avg.as_millis())),
+                    );
+                    candidate
+                })
+                .collect();
+            selection_debug(
+                SchedulingStrategy::CostAware,
+                candidates,
+                selected_key,
+                counter,
+            )
+        });
+
+        Ok(ApiKeySelectionResult::new(
+            selected_index,
+            selected_key.clone(),
+            reason,
+            SchedulingStrategy::CostAware,
+        )
+        .with_selection_debug(debug))
+    }
+
+    fn name(&self) -> &'static str {
+        "CostAwareApiKeySelector"
+    }
+
+    async fn reset(&self) {
+        self.prices.clear();
+        self.counters.clear();
+    }
+}
+
+type SelectorFactory = fn() -> Arc<dyn ApiKeySelector>;
+
+/// 已注册的选择器工厂；写入时校验的调度策略名称由这里得出，新增策略只需在此注册
+///
+/// 成本感知调度依赖定价数据与延迟统计，由 `ApiKeySchedulerService` 创建；
+/// 脱离调度服务单独创建时退化为轮询。
+const SELECTOR_FACTORIES: [(SchedulingStrategy, SelectorFactory); 3] = [
+    (SchedulingStrategy::RoundRobin, || {
+        Arc::new(RoundRobinApiKeySelector::new())
+    }),
+    (SchedulingStrategy::Weighted, || {
+        Arc::new(WeightedApiKeySelector::new())
+    }),
+    (SchedulingStrategy::CostAware, || {
+        Arc::new(RoundRobinApiKeySelector::new())
+    }),
+];
+
+/// 创建API密钥选择器；未注册的策略退化为轮询
 #[must_use]
 pub fn create_api_key_selector(strategy: SchedulingStrategy) -> Arc<dyn ApiKeySelector> {
-    match strategy {
-        SchedulingStrategy::RoundRobin => Arc::new(RoundRobinApiKeySelector::new()),
-        SchedulingStrategy::Weighted => Arc::new(WeightedApiKeySelector::new()),
-    }
+    SELECTOR_FACTORIES
+        .iter()
+        .find(|(registered, _)| *registered == strategy)
+        .map_or_else(
+            || Arc::new(RoundRobinApiKeySelector::new()) as Arc<dyn ApiKeySelector>,
+            |(_, factory)| factory(),
+        )
+}
+
+/// 选择器工厂已注册的调度策略
+pub fn registered_strategies() -> impl Iterator<Item = SchedulingStrategy> {
+    SELECTOR_FACTORIES.iter().map(|(strategy, _)| *strategy)
 }
@@ -0,0 +1,214 @@
+//! # 服务商密钥每日请求配额
+//!
+//! 按 `user_provider_keys.max_requests_per_day` 限制单个密钥每天转发的请求数。
+//! 计数保存在缓存中，键按配置时区的本地日期区分，到本地午夜自然切换到新的计数。
+//! 缓存不可用时配额检查放行，避免缓存故障导致密钥全部不可选。
+
+use crate::cache::{CacheManager, record_fail_open};
+use crate::error::Result;
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
+use crate::types::timezone_utils;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use entity::user_provider_keys;
+use std::sync::Arc;
+
+const KEY_REQUEST_PREFIX: &str = "ratelimit:daily:key_requests";
+
+/// 单次配额占用结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DailyQuotaOutcome {
+    /// 未配置每日上限
+    Unlimited,
+    /// 占用成功，`exhausted` 表示本次请求用掉了当天最后一个名额
+    Allowed { exhausted: bool },
+    /// 当天配额已用尽
+    Exceeded,
+}
+
+/// 服务商密钥每日请求配额
+pub struct ApiKeyDailyQuota {
+    cache: Arc<CacheManager>,
+    timezone: Tz,
+}
+
+impl ApiKeyDailyQuota {
+    #[must_use]
+    pub const fn new(cache: Arc<CacheManager>, timezone: Tz) -> Self {
+        Self { cache, timezone }
+    }
+
+    /// 下一次配额重置时间（配置时区的下一个午夜）
+    #[must_use]
+    pub fn next_reset(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        timezone_utils::local_day_bounds(&now, &self.timezone)
+            .map_or_else(|| now + Duration::days(1), |(_, end)| end)
+    }
+
+    /// 密钥当天已使用的请求数
+    pub async fn current(&self, key_id: i32, now: DateTime<Utc>) -> Result<i64> {
+        Ok(self
+            .cache
+            .get::<i64>(&self.counter_key(key_id, now))
+            .await?
+            .unwrap_or(0))
+    }
+
+    /// 密钥当天配额是否已用尽
+    pub async fn is_exhausted(
+        &self,
+        key: &user_provider_keys::Model,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        match Self::daily_limit(key) {
+            Some(limit) => Ok(self.current(key.id, now).await? >= limit),
+            None => Ok(false),
+        }
+    }
+
+    /// 为一次请求占用配额；并发请求以缓存自增结果为准，超出上限的占用不放行
+    pub async fn acquire(
+        &self,
+        key: &user_provider_keys::Model,
+        now: DateTime<Utc>,
+    ) -> Result<DailyQuotaOutcome> {
+        let Some(limit) = Self::daily_limit(key) else {
+            return Ok(DailyQuotaOutcome::Unlimited);
+        };
+
+        let counter_key = self.counter_key(key.id, now);
+        let current = match self.cache.incr(&counter_key, 1).await {
+            Ok(current) => current,
+            Err(err) if err.is_cache_unavailable() => {
+                let fail_open_total = record_fail_open();
+                lwarn!(
+                    "system",
+                    LogStage::Cache,
+                    LogComponent::KeyPool,
+                    "key_daily_quota_fail_open",
+                    "缓存不可用，密钥每日配额检查放行请求",
+                    key_id = key.id,
+                    fail_open_total = fail_open_total,
+                    error = %err
+                );
+                return Ok(DailyQuotaOutcome::Allowed { exhausted: false });
+            }
+            Err(err) => return Err(err),
+        };
+        if current == 1 {
+            let ttl = (self.next_reset(now) - now)
+                .to_std()
+                .unwrap_or_default()
+                .max(std::time::Duration::from_secs(60));
+            let _ = self.cache.expire(&counter_key, ttl).await;
+        }
+
+        Ok(if current > limit {
+            DailyQuotaOutcome::Exceeded
+        } else {
+            DailyQuotaOutcome::Allowed {
+                exhausted: current == limit,
+            }
+        })
+    }
+
+    fn daily_limit(key: &user_provider_keys::Model) -> Option<i64> {
+        key.max_requests_per_day
+            .filter(|limit| *limit > 0)
+            .map(i64::from)
+    }
+
+    fn counter_key(&self, key_id: i32, now: DateTime<Utc>) -> String {
+        let date = now.with_timezone(&self.timezone).format("%Y%m%d");
+        format!("{KEY_REQUEST_PREFIX}:{key_id}:{date}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn provider_key(id: i32, max_requests_per_day: Option<i32>) -> user_provider_keys::Model {
+        let now = Utc::now().naive_utc();
+        user_provider_keys::Model {
+            id,
+            user_id: 1,
+            provider_type_id: 1,
+            api_key: format!("sk-test-{id}"),
+            auth_type: "api_key".to_string(),
+            name: format!("key-{id}"),
+            weight: Some(1),
+            max_requests_per_minute: None,
+            max_tokens_prompt_per_minute: None,
+            max_requests_per_day,
+            is_active: true,
+            health_status: "healthy".to_string(),
+            health_status_detail: None,
+            rate_limit_resets_at: None,
+            last_error_time: None,
+            auth_status: None,
+            expires_at: None,
+            last_auth_check: None,
+            project_id: None,
+            tags: None,
+            deleted_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn counter_resets_at_local_midnight() {
+        let quota = ApiKeyDailyQuota::new(
+            Arc::new(CacheManager::memory_only()),
+            chrono_tz::Asia::Shanghai,
+        );
+        let key = provider_key(1, Some(2));
+
+        // 上海时间 23:30（UTC 15:30），距离本地午夜 30 分钟
+        let before_midnight = Utc.with_ymd_and_hms(2025, 1, 1, 15, 30, 0).unwrap();
+        assert_eq!(
+            quota.acquire(&key, before_midnight).await.unwrap(),
+            DailyQuotaOutcome::Allowed { exhausted: false }
+        );
+        assert_eq!(
+            quota.acquire(&key, before_midnight).await.unwrap(),
+            DailyQuotaOutcome::Allowed { exhausted: true }
+        );
+        assert_eq!(
+            quota.acquire(&key, before_midnight).await.unwrap(),
+            DailyQuotaOutcome::Exceeded
+        );
+        assert!(quota.is_exhausted(&key, before_midnight).await.unwrap());
+        assert_eq!(
+            quota.next_reset(before_midnight),
+            Utc.with_ymd_and_hms(2025, 1, 1, 16, 0, 0).unwrap()
+        );
+
+        // 跨过本地午夜后计数归零，UTC 日期仍是同一天
+        let after_midnight = Utc.with_ymd_and_hms(2025, 1, 1, 16, 1, 0).unwrap();
+        assert_eq!(quota.current(key.id, after_midnight).await.unwrap(), 0);
+        assert!(!quota.is_exhausted(&key, after_midnight).await.unwrap());
+        assert_eq!(
+            quota.acquire(&key, after_midnight).await.unwrap(),
+            DailyQuotaOutcome::Allowed { exhausted: false }
+        );
+    }
+
+    #[tokio::test]
+    async fn keys_without_limit_are_unlimited() {
+        let quota = ApiKeyDailyQuota::new(Arc::new(CacheManager::memory_only()), Tz::UTC);
+        let now = Utc::now();
+
+        for max in [None, Some(0)] {
+            let key = provider_key(2, max);
+            assert_eq!(
+                quota.acquire(&key, now).await.unwrap(),
+                DailyQuotaOutcome::Unlimited
+            );
+            assert!(!quota.is_exhausted(&key, now).await.unwrap());
+        }
+    }
+}
@@ -2,25 +2,31 @@
 //!
 //! 结合用户反馈，移除了主动探测与本地缓存逻辑，仅保留基于数据库的状态读写接口。
 
+use crate::config::RateLimitCooldownConfig;
 use crate::error::{Context, Result};
 use crate::logging::{LogComponent, LogStage};
 use crate::{ldebug, lerror, linfo, lwarn};
 use chrono::{NaiveDateTime, Utc};
+use dashmap::DashMap;
 use entity::user_provider_keys;
 use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 // 前向声明
 use crate::key_pool::api_key_rate_limit_reset_task::ApiKeyRateLimitResetTask;
 use serde::Serialize;
 
-use super::types::ApiKeyHealthStatus;
+use super::types::{ApiKeyHealthStatus, HealthUpdate};
 
 /// API密钥健康状态服务
 pub struct ApiKeyHealthService {
     db: Arc<DatabaseConnection>,
     reset_task: RwLock<Option<Weak<ApiKeyRateLimitResetTask>>>,
+    cooldown: RateLimitCooldownConfig,
+    /// 各密钥的连续 429 次数与最近一次时间（仅统计未携带 `Retry-After` 的 429）
+    rate_limit_streaks: DashMap<i32, (u32, Instant)>,
 }
 
 impl ApiKeyHealthService {
@@ -30,9 +36,18 @@ impl ApiKeyHealthService {
         Self {
             db,
             reset_task: RwLock::new(None),
+            cooldown: RateLimitCooldownConfig::default(),
+            rate_limit_streaks: DashMap::new(),
         }
     }
 
+    /// 设置上游 429 未携带 `Retry-After` 时的冷却配置
+    #[must_use]
+    pub fn with_cooldown(mut self, cooldown: RateLimitCooldownConfig) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
     /// 设置恢复任务引用
     pub async fn set_reset_task(&self, reset_task: &Arc<ApiKeyRateLimitResetTask>) {
         *self.reset_task.write().await = Some(Arc::downgrade(reset_task));
@@ -45,6 +60,7 @@ impl ApiKeyHealthService {
                 user_provider_keys::Column::HealthStatus
                     .eq(ApiKeyHealthStatus::Healthy.to_string()),
             )
+            .filter(user_provider_keys::Column::DeletedAt.is_null())
             .all(self.db.as_ref())
             .await
         {
@@ -260,6 +276,74 @@ impl ApiKeyHealthService {
         Ok(())
     }
 
+    /// 应用服务商策略根据上游响应得出的健康状态变更
+    ///
+    /// 降级与限流按限流处理并在冷却结束后由恢复任务自动恢复，失效则标记为不健康。
+    pub async fn apply_health_update(&self, key_id: i32, update: &HealthUpdate) -> Result<()> {
+        match update {
+            HealthUpdate::Unchanged => Ok(()),
+            HealthUpdate::Degraded { cooldown, reason } => {
+                self.cool_down_key(key_id, *cooldown, reason).await
+            }
+            HealthUpdate::RateLimited {
+                retry_after: Some(retry_after),
+                reason,
+            } => self.cool_down_key(key_id, *retry_after, reason).await,
+            HealthUpdate::RateLimited {
+                retry_after: None,
+                reason,
+            } => {
+                let streak = self.record_rate_limit(key_id);
+                let cooldown = self.cooldown.cooldown_for(streak);
+                ldebug!(
+                    "system",
+                    LogStage::HealthCheck,
+                    LogComponent::HealthChecker,
+                    "default_rate_limit_cooldown",
+                    "上游 429 未携带 Retry-After，使用默认冷却时间",
+                    key_id = key_id,
+                    streak = streak,
+                    cooldown_secs = cooldown.as_secs()
+                );
+                self.cool_down_key(key_id, cooldown, reason).await
+            }
+            HealthUpdate::Unhealthy { reason } => {
+                self.mark_key_unhealthy(key_id, reason.clone()).await
+            }
+        }
+    }
+
+    /// 将密钥标记为限流，冷却结束后恢复
+    async fn cool_down_key(&self, key_id: i32, cooldown: Duration, reason: &str) -> Result<()> {
+        let now = Utc::now();
+        let resets_at = chrono::Duration::from_std(cooldown)
+            .ok()
+            .map(|cooldown| (now + cooldown).naive_utc());
+        let details = serde_json::json!({
+            "error_message": reason,
+            "cooldown_secs": cooldown.as_secs(),
+            "updated_at": now.naive_utc(),
+        })
+        .to_string();
+        self.mark_key_rate_limited(key_id, resets_at, &details)
+            .await
+    }
+
+    /// 记录一次未携带 `Retry-After` 的 429，返回连续次数（距上次超过递增窗口则重新计数）
+    fn record_rate_limit(&self, key_id: i32) -> u32 {
+        let now = Instant::now();
+        let window = self.cooldown.escalation_window();
+        let mut entry = self.rate_limit_streaks.entry(key_id).or_insert((0, now));
+        let (streak, last_at) = *entry;
+        let streak = if streak > 0 && now.duration_since(last_at) <= window {
+            streak.saturating_add(1)
+        } else {
+            1
+        };
+        *entry = (streak, now);
+        streak
+    }
+
     /// 将密钥直接标记为健康
     pub async fn mark_key_healthy(&self, key_id: i32) -> Result<()> {
         let now = Utc::now().naive_utc();
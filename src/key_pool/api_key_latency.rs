@@ -0,0 +1,42 @@
+//! # API密钥响应延迟统计
+//!
+//! 记录每个密钥从请求开始到收到上游响应头的耗时（指数移动平均），供成本感知调度避开过慢的密钥。
+
+use dashmap::DashMap;
+use std::time::Duration;
+
+/// 新样本在移动平均中的权重
+const LATENCY_EWMA_ALPHA: f64 = 0.3;
+
+/// 密钥响应延迟统计（进程内）
+#[derive(Debug, Default)]
+pub struct ApiKeyLatencyStats {
+    /// 密钥ID -> 平均延迟（毫秒）
+    averages: DashMap<i32, f64>,
+}
+
+impl ApiKeyLatencyStats {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次响应延迟
+    pub fn record(&self, key_id: i32, latency: Duration) {
+        let sample_ms = latency.as_secs_f64() * 1000.0;
+        self.averages
+            .entry(key_id)
+            .and_modify(|average| {
+                *average = (sample_ms - *average).mul_add(LATENCY_EWMA_ALPHA, *average);
+            })
+            .or_insert(sample_ms);
+    }
+
+    /// 密钥的平均响应延迟，尚无样本时为 `None`
+    #[must_use]
+    pub fn average(&self, key_id: i32) -> Option<Duration> {
+        self.averages
+            .get(&key_id)
+            .map(|average| Duration::from_secs_f64(*average / 1000.0))
+    }
+}
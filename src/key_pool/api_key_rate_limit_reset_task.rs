@@ -1,3 +1,4 @@
+use crate::app::task_scheduler::{TaskRunRecorder, track_run};
 use crate::error::{Result, key_pool::KeyPoolError};
 use crate::key_pool::api_key_health::ApiKeyHealthService;
 use crate::logging::{LogComponent, LogStage};
@@ -24,6 +25,7 @@ pub struct ApiKeyRateLimitResetTask {
     health_service: Weak<ApiKeyHealthService>,
     command_sender: Arc<RwLock<Option<mpsc::Sender<ScheduleResetCommand>>>>,
     task_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    recorder: Option<TaskRunRecorder>,
 }
 
 impl ApiKeyRateLimitResetTask {
@@ -33,9 +35,17 @@ impl ApiKeyRateLimitResetTask {
             health_service: Arc::downgrade(health_service),
             command_sender: Arc::new(RwLock::new(None)),
             task_handle: Arc::new(RwLock::new(None)),
+            recorder: None,
         }
     }
 
+    /// 每次恢复密钥状态后记录心跳与执行历史
+    #[must_use]
+    pub fn with_run_recorder(mut self, recorder: TaskRunRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     pub async fn start(&self) -> Result<()> {
         let health_service = self
             .health_service
@@ -50,6 +60,7 @@ impl ApiKeyRateLimitResetTask {
             health_service.clone(),
             command_receiver,
             pending_resets,
+            self.recorder.clone(),
         ));
         *self.command_sender.write().await = Some(command_sender);
         *self.task_handle.write().await = Some(task_handle);
@@ -116,6 +127,7 @@ async fn run(
     health_service: Arc<ApiKeyHealthService>,
     mut command_receiver: mpsc::Receiver<ScheduleResetCommand>,
     pending_resets: Vec<(i32, chrono::NaiveDateTime)>,
+    recorder: Option<TaskRunRecorder>,
 ) {
     let mut queue: DelayQueue<i32> = DelayQueue::new();
 
@@ -151,8 +163,9 @@ async fn run(
 
                 // 异步执行重置，延迟验证：只有当 key 确实处于 rate_limited 状态时才重置
                 let health_service = health_service.clone();
+                let recorder = recorder.clone();
                 tokio::spawn(async move {
-                    if let Err(e) = health_service.reset_key_status(key_id).await {
+                    if let Err(e) = track_run(recorder.as_ref(), health_service.reset_key_status(key_id)).await {
                         lerror!("system", LogStage::HealthCheck, LogComponent::HealthChecker, "key_reset_failed", "Failed to reset key status", key_id = key_id, error = %e);
                     }
                 });
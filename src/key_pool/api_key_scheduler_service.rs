@@ -2,17 +2,56 @@
 //!
 //! 专门管理用户API密钥池的选择和调度，替代传统的负载均衡器概念
 
-use super::algorithms::{ApiKeySelectionResult, ApiKeySelector, SelectionContext};
+use super::algorithms::{
+    ApiKeySelectionResult, ApiKeySelector, CostAwareApiKeySelector, SelectionContext,
+};
+use super::api_key_daily_quota::{ApiKeyDailyQuota, DailyQuotaOutcome};
 use super::api_key_health::ApiKeyHealthService;
+use super::api_key_latency::ApiKeyLatencyStats;
+use super::key_tags::key_has_tag;
+use super::key_warmup::KeyWarmup;
 use super::types::{ApiKeyHealthStatus, SchedulingStrategy};
 use crate::auth::types::AuthStatus;
+use crate::config::{CostAwareConfig, KeySelectionConfig};
 use crate::error::{Context, Result, key_pool::KeyPoolError};
 use crate::logging::{LogComponent, LogStage};
-use crate::{ldebug, linfo};
+use crate::proxy::effective_config;
+use crate::{ldebug, linfo, lwarn};
+use dashmap::DashMap;
 use entity::user_provider_keys;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
+
+/// 候选集排序：健康密钥优先，其次限流中的密钥
+const HEALTH_RANK_SQL: &str =
+    "CASE health_status WHEN 'healthy' THEN 0 WHEN 'rate_limited' THEN 1 ELSE 2 END";
+/// 候选集排序：权重高的优先（未设置权重按 1 计）
+const WEIGHT_SQL: &str = "COALESCE(weight, 1)";
+
+/// 候选集排序：健康状态、权重相同时从轮换起点开始按 ID 排列，起点之前的密钥排在最后
+fn rotation_sql(rotation_start: i32) -> String {
+    format!("CASE WHEN id >= {rotation_start} THEN 0 ELSE 1 END")
+}
+
+/// 缓存的候选集：(服务 API ID, 锁定的服务商类型) -> 候选集
+type CandidateCacheKey = (i32, Option<i32>);
+
+/// 从数据库加载的候选密钥
+#[derive(Clone)]
+struct CandidateSet {
+    /// 服务 API 配置的密钥 ID（配置变更时缓存失效）
+    provider_key_ids: Vec<i32>,
+    keys: Arc<Vec<user_provider_keys::Model>>,
+    /// 是否因候选上限截断
+    truncated: bool,
+    loaded_at: Instant,
+}
 
 /// API 密钥池服务
 /// 职责：管理用户的 API 密钥池，根据策略选择合适的密钥，并集成健康检查与 OAuth 智能刷新
@@ -23,6 +62,20 @@ pub struct ApiKeySchedulerService {
     selectors: tokio::sync::RwLock<HashMap<SchedulingStrategy, Arc<dyn ApiKeySelector>>>,
     /// API 密钥健康检查器
     api_key_health_service: Arc<ApiKeyHealthService>,
+    /// 密钥每日请求配额（未设置时不限制）
+    daily_quota: Option<ApiKeyDailyQuota>,
+    /// 密钥响应延迟统计
+    latency_stats: Arc<ApiKeyLatencyStats>,
+    /// 成本感知调度配置
+    cost_aware: CostAwareConfig,
+    /// 候选集上限与缓存配置
+    key_selection: KeySelectionConfig,
+    /// 候选集缓存
+    candidates: DashMap<CandidateCacheKey, CandidateSet>,
+    /// 截断候选集的轮换起点：每次重新加载后移到上次候选集最后一个密钥之后
+    rotation_starts: DashMap<CandidateCacheKey, i32>,
+    /// 新密钥预热份额
+    warmup: KeyWarmup,
 }
 
 impl ApiKeySchedulerService {
@@ -36,14 +89,47 @@ impl ApiKeySchedulerService {
             db,
             selectors: tokio::sync::RwLock::new(HashMap::new()),
             api_key_health_service,
+            daily_quota: None,
+            latency_stats: Arc::new(ApiKeyLatencyStats::new()),
+            cost_aware: CostAwareConfig::default(),
+            key_selection: KeySelectionConfig::default(),
+            candidates: DashMap::new(),
+            rotation_starts: DashMap::new(),
+            warmup: KeyWarmup::new(),
         }
     }
 
+    /// 设置成本感知调度配置
+    #[must_use]
+    pub fn with_cost_aware(mut self, cost_aware: CostAwareConfig) -> Self {
+        self.cost_aware = cost_aware;
+        self
+    }
+
+    /// 设置候选集上限与缓存配置
+    #[must_use]
+    pub fn with_key_selection(mut self, key_selection: KeySelectionConfig) -> Self {
+        self.key_selection = key_selection;
+        self
+    }
+
+    /// 启用密钥每日请求配额
+    #[must_use]
+    pub fn with_daily_quota(mut self, daily_quota: ApiKeyDailyQuota) -> Self {
+        self.daily_quota = Some(daily_quota);
+        self
+    }
+
     #[must_use]
     pub const fn api_key_health_service(&self) -> &Arc<ApiKeyHealthService> {
         &self.api_key_health_service
     }
 
+    #[must_use]
+    pub const fn latency_stats(&self) -> &Arc<ApiKeyLatencyStats> {
+        &self.latency_stats
+    }
+
     /// 从用户服务API配置中获取API密钥池并选择密钥
     pub async fn select_api_key_from_service_api(
         &self,
@@ -61,13 +147,36 @@ impl ApiKeySchedulerService {
         );
 
         let provider_key_ids = Self::get_provider_key_ids(service_api, context)?;
-        let all_candidate_keys = self
-            .load_active_provider_keys(&provider_key_ids, context)
+        let candidates = self
+            .candidate_set(service_api.id, &provider_key_ids, context)
             .await?;
-        let user_keys = Self::filter_valid_keys_with_logging(&all_candidate_keys, context)?;
-        Self::log_key_limits(&user_keys);
-
-        let keys_to_use = user_keys.as_slice();
+        let key_tag = service_api.key_tag.as_deref();
+        let mut all_candidate_keys = candidates.keys;
+        let mut keys_to_use = match self
+            .usable_keys(&all_candidate_keys, key_tag, context)
+            .await
+        {
+            // 截断后的候选集没有可用密钥时回退到全部密钥
+            Err(err) if candidates.truncated => {
+                lwarn!(
+                    &context.request_id,
+                    LogStage::Scheduling,
+                    LogComponent::KeyPool,
+                    "capped_candidates_unusable",
+                    "No usable key within the capped candidate set, falling back to all keys",
+                    max_candidates = ?self.key_selection.max_candidates(),
+                    error = %err
+                );
+                all_candidate_keys = Arc::new(
+                    self.load_active_provider_keys(&provider_key_ids, None, i32::MIN, context)
+                        .await?,
+                );
+                self.usable_keys(&all_candidate_keys, key_tag, context)
+                    .await?
+            }
+            result => result?,
+        };
+        keys_to_use = self.apply_warmup(keys_to_use, context);
 
         linfo!(
             &context.request_id,
@@ -81,7 +190,268 @@ impl ApiKeySchedulerService {
         let scheduling_strategy = Self::resolve_strategy(service_api);
         let selector = self.get_selector(scheduling_strategy).await;
 
-        selector.select_key(keys_to_use, context).await
+        loop {
+            let mut result = selector.select_key(&keys_to_use, context).await?;
+            if self
+                .acquire_daily_quota(&result.selected_key, context)
+                .await?
+            {
+                if let Some(Value::Object(debug)) = result.selection_debug.as_mut() {
+                    debug.insert(
+                        "excluded".to_string(),
+                        Self::excluded_keys_debug(&all_candidate_keys, &keys_to_use),
+                    );
+                }
+                return Ok(result);
+            }
+
+            // 并发请求抢占了最后的名额，换用其他密钥
+            keys_to_use.retain(|key| key.id != result.selected_key.id);
+            if keys_to_use.is_empty() {
+                return Err(KeyPoolError::DailyQuotaExhausted {
+                    service_api_id: context.user_service_api_id,
+                }
+                .into());
+            }
+        }
+    }
+
+    /// 通过标签、认证、过期、健康状态与每日配额检查的密钥
+    async fn usable_keys(
+        &self,
+        candidate_keys: &[user_provider_keys::Model],
+        key_tag: Option<&str>,
+        context: &SelectionContext,
+    ) -> Result<Vec<user_provider_keys::Model>> {
+        let tagged_keys;
+        let candidate_keys = match key_tag {
+            Some(tag) => {
+                tagged_keys = Self::filter_by_tag(candidate_keys, tag, context)?;
+                tagged_keys.as_slice()
+            }
+            None => candidate_keys,
+        };
+        let user_keys = Self::filter_valid_keys_with_logging(candidate_keys, context)?;
+        Self::log_key_limits(&user_keys);
+        self.filter_daily_quota(user_keys, context).await
+    }
+
+    /// 预热窗口内的新密钥按份额参与本次选择；全部被跳过时保留原候选
+    fn apply_warmup(
+        &self,
+        keys: Vec<user_provider_keys::Model>,
+        context: &SelectionContext,
+    ) -> Vec<user_provider_keys::Model> {
+        let Some(window) = self.key_selection.new_key_warmup() else {
+            return keys;
+        };
+        let now = chrono::Utc::now().naive_utc();
+        let (admitted, skipped): (Vec<_>, Vec<_>) = keys
+            .into_iter()
+            .partition(|key| self.warmup.admit(key, now, window));
+        if admitted.is_empty() {
+            return skipped;
+        }
+        if !skipped.is_empty() {
+            ldebug!(
+                &context.request_id,
+                LogStage::Scheduling,
+                LogComponent::KeyPool,
+                "warming_keys_skipped",
+                "Skipped warming-up keys for this selection",
+                key_ids = ?skipped.iter().map(|key| key.id).collect::<Vec<_>>()
+            );
+        }
+        admitted
+    }
+
+    /// 只保留带有服务 API 指定标签的密钥
+    fn filter_by_tag(
+        candidate_keys: &[user_provider_keys::Model],
+        tag: &str,
+        context: &SelectionContext,
+    ) -> Result<Vec<user_provider_keys::Model>> {
+        let tagged: Vec<_> = candidate_keys
+            .iter()
+            .filter(|key| key_has_tag(key, tag))
+            .cloned()
+            .collect();
+        ldebug!(
+            &context.request_id,
+            LogStage::Scheduling,
+            LogComponent::KeyPool,
+            "filter_keys_by_tag",
+            "Filtered candidate keys by service API key tag",
+            tag = tag,
+            candidates = candidate_keys.len(),
+            matched = tagged.len()
+        );
+        if tagged.is_empty() {
+            return Err(KeyPoolError::NoKeysMatchingTag {
+                service_api_id: context.user_service_api_id,
+                tag: tag.to_string(),
+            }
+            .into());
+        }
+        Ok(tagged)
+    }
+
+    /// 获取候选集：配置了缓存时间时在有效期内复用上次加载的结果
+    async fn candidate_set(
+        &self,
+        service_api_id: i32,
+        provider_key_ids: &[i32],
+        context: &SelectionContext,
+    ) -> Result<CandidateSet> {
+        let cache_ttl = self.key_selection.candidate_cache_ttl();
+        let cache_key = (
+            service_api_id,
+            context.pin_provider.then_some(context.provider_type_id),
+        );
+        if let Some(ttl) = cache_ttl
+            && let Some(cached) = self.candidates.get(&cache_key)
+            && cached.provider_key_ids == provider_key_ids
+            && cached.loaded_at.elapsed() < ttl
+        {
+            ldebug!(
+                &context.request_id,
+                LogStage::Scheduling,
+                LogComponent::KeyPool,
+                "candidate_keys_cached",
+                "Using cached candidate keys",
+                count = cached.keys.len(),
+                truncated = cached.truncated
+            );
+            return Ok(cached.clone());
+        }
+
+        let max_candidates = self
+            .key_selection
+            .max_candidates()
+            .filter(|max| provider_key_ids.len() > *max);
+        let rotation_start = self
+            .rotation_starts
+            .get(&cache_key)
+            .map_or(i32::MIN, |start| *start);
+        let keys = self
+            .load_active_provider_keys(provider_key_ids, max_candidates, rotation_start, context)
+            .await?;
+        let truncated = max_candidates.is_some_and(|max| keys.len() >= max);
+        if truncated && let Some(last) = keys.last() {
+            self.rotation_starts
+                .insert(cache_key, last.id.saturating_add(1));
+        }
+        let candidates = CandidateSet {
+            provider_key_ids: provider_key_ids.to_vec(),
+            truncated,
+            keys: Arc::new(keys),
+            loaded_at: Instant::now(),
+        };
+        if cache_ttl.is_some() {
+            self.candidates.insert(cache_key, candidates.clone());
+        }
+        Ok(candidates)
+    }
+
+    /// 丢弃服务 API 的候选集缓存，下次选择时重新加载
+    pub fn invalidate_service_api(&self, service_api_id: i32) {
+        self.candidates.retain(|(id, _), _| *id != service_api_id);
+    }
+
+    /// 未进入选择算法的密钥（认证、过期、健康状态或每日配额不满足）
+    fn excluded_keys_debug(
+        all_keys: &[user_provider_keys::Model],
+        selectable: &[user_provider_keys::Model],
+    ) -> Value {
+        all_keys
+            .iter()
+            .filter(|key| selectable.iter().all(|candidate| candidate.id != key.id))
+            .map(|key| {
+                json!({
+                    "key_id": key.id,
+                    "health_status": key.health_status,
+                    "auth_status": key.auth_status,
+                })
+            })
+            .collect()
+    }
+
+    /// 跳过当天请求数已达 `max_requests_per_day` 的密钥
+    async fn filter_daily_quota(
+        &self,
+        keys: Vec<user_provider_keys::Model>,
+        context: &SelectionContext,
+    ) -> Result<Vec<user_provider_keys::Model>> {
+        let Some(daily_quota) = &self.daily_quota else {
+            return Ok(keys);
+        };
+
+        let now = chrono::Utc::now();
+        let mut available = Vec::with_capacity(keys.len());
+        for key in keys {
+            if daily_quota.is_exhausted(&key, now).await? {
+                ldebug!(
+                    &context.request_id,
+                    LogStage::Scheduling,
+                    LogComponent::KeyPool,
+                    "key_daily_quota_exhausted",
+                    "API key reached its daily request limit, skipping",
+                    key_id = key.id,
+                    key_name = %key.name,
+                    max_requests_per_day = ?key.max_requests_per_day,
+                );
+            } else {
+                available.push(key);
+            }
+        }
+
+        if available.is_empty() {
+            return Err(KeyPoolError::DailyQuotaExhausted {
+                service_api_id: context.user_service_api_id,
+            }
+            .into());
+        }
+        Ok(available)
+    }
+
+    /// 为选中的密钥占用一次每日配额，返回是否放行
+    ///
+    /// 用掉最后一个名额时将密钥标记为限流至下一次配额重置，由限流恢复任务在重置时恢复健康状态。
+    async fn acquire_daily_quota(
+        &self,
+        key: &user_provider_keys::Model,
+        context: &SelectionContext,
+    ) -> Result<bool> {
+        let Some(daily_quota) = &self.daily_quota else {
+            return Ok(true);
+        };
+
+        let now = chrono::Utc::now();
+        match daily_quota.acquire(key, now).await? {
+            DailyQuotaOutcome::Unlimited | DailyQuotaOutcome::Allowed { exhausted: false } => {
+                Ok(true)
+            }
+            DailyQuotaOutcome::Allowed { exhausted: true } => {
+                let resets_at = daily_quota.next_reset(now).naive_utc();
+                if let Err(err) = self
+                    .api_key_health_service
+                    .mark_key_rate_limited(key.id, Some(resets_at), "daily request quota exhausted")
+                    .await
+                {
+                    lwarn!(
+                        &context.request_id,
+                        LogStage::Scheduling,
+                        LogComponent::KeyPool,
+                        "mark_daily_quota_exhausted_failed",
+                        "密钥每日配额已用尽，标记限流状态失败",
+                        key_id = key.id,
+                        error = %err
+                    );
+                }
+                Ok(true)
+            }
+            DailyQuotaOutcome::Exceeded => Ok(false),
+        }
     }
 
     /// 获取或创建API密钥选择器
@@ -94,7 +464,15 @@ impl ApiKeySchedulerService {
         }
 
         // 创建新的选择器
-        let selector = super::algorithms::create_api_key_selector(strategy);
+        let selector: Arc<dyn ApiKeySelector> = match strategy {
+            SchedulingStrategy::CostAware => Arc::new(CostAwareApiKeySelector::new(
+                self.db.clone(),
+                self.latency_stats.clone(),
+                self.cost_aware.max_latency(),
+                self.cost_aware.price_cache_ttl(),
+            )),
+            _ => super::algorithms::create_api_key_selector(strategy),
+        };
 
         {
             let mut selectors = self.selectors.write().await;
@@ -179,6 +557,18 @@ impl ApiKeySchedulerService {
                 );
                 false
             }
+            AuthStatus::NeedsAttention => {
+                ldebug!(
+                    "system",
+                    LogStage::Scheduling,
+                    LogComponent::KeyPool,
+                    "key_needs_attention",
+                    "API key OAuth refresh exhausted retries, skipping",
+                    key_id = key.id,
+                    key_name = %key.name,
+                );
+                false
+            }
         }
     }
 
@@ -270,14 +660,32 @@ impl ApiKeySchedulerService {
         }
     }
 
+    /// 加载启用中的密钥；指定上限时按健康状态、权重排序后只取前 `max_candidates` 个，
+    /// 同层密钥从 `rotation_start` 开始轮换
     async fn load_active_provider_keys(
         &self,
         provider_key_ids: &[i32],
+        max_candidates: Option<usize>,
+        rotation_start: i32,
         context: &SelectionContext,
     ) -> Result<Vec<user_provider_keys::Model>> {
-        let keys = entity::user_provider_keys::Entity::find()
+        let mut query = entity::user_provider_keys::Entity::find()
             .filter(entity::user_provider_keys::Column::Id.is_in(provider_key_ids.to_vec()))
             .filter(entity::user_provider_keys::Column::IsActive.eq(true))
+            .filter(entity::user_provider_keys::Column::DeletedAt.is_null());
+        if context.pin_provider {
+            query = query.filter(
+                entity::user_provider_keys::Column::ProviderTypeId.eq(context.provider_type_id),
+            );
+        }
+        if let Some(max_candidates) = max_candidates {
+            query = query
+                .order_by(Expr::cust(HEALTH_RANK_SQL), Order::Asc)
+                .order_by(Expr::cust(WEIGHT_SQL), Order::Desc)
+                .order_by(Expr::cust(rotation_sql(rotation_start)), Order::Asc)
+                .limit(u64::try_from(max_candidates).unwrap_or(u64::MAX));
+        }
+        let keys = query
             .order_by_asc(entity::user_provider_keys::Column::Id)
             .all(&*self.db)
             .await
@@ -289,7 +697,9 @@ impl ApiKeySchedulerService {
             LogComponent::KeyPool,
             "candidate_keys_count",
             "Retrieved candidate keys from DB",
-            count = keys.len()
+            count = keys.len(),
+            max_candidates = ?max_candidates,
+            rotation_start = rotation_start
         );
 
         Ok(keys)
@@ -299,6 +709,18 @@ impl ApiKeySchedulerService {
         service_api: &entity::user_service_apis::Model,
         context: &SelectionContext,
     ) -> Result<Vec<i32>> {
+        if let Some(ids) = &context.key_ids {
+            ldebug!(
+                &context.request_id,
+                LogStage::Scheduling,
+                LogComponent::KeyPool,
+                "routed_keys",
+                "Using provider key IDs from matched model route",
+                key_ids = ?ids
+            );
+            return Ok(ids.clone());
+        }
+
         let ids = match &service_api.user_provider_keys_ids {
             sea_orm::prelude::Json::Array(values) => values
                 .iter()
@@ -357,11 +779,7 @@ impl ApiKeySchedulerService {
     }
 
     fn resolve_strategy(service_api: &entity::user_service_apis::Model) -> SchedulingStrategy {
-        service_api
-            .scheduling_strategy
-            .as_deref()
-            .and_then(SchedulingStrategy::parse)
-            .unwrap_or_default()
+        effective_config::scheduling_strategy(service_api).value
     }
 
     /// 记录密钥限制信息
@@ -388,6 +806,7 @@ impl ApiKeySchedulerService {
 
     /// 手动标记API密钥为不健康
     pub async fn mark_key_unhealthy(&self, key_id: i32, reason: String) -> Result<()> {
+        self.candidates.clear();
         self.api_key_health_service
             .mark_key_unhealthy(key_id, reason)
             .await
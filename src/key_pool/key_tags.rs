@@ -0,0 +1,102 @@
+//! # 密钥标签
+//!
+//! 密钥可以打上若干标签（如 `prod`、`high-quota`）用于分组，保存在 `user_provider_keys.tags`
+//! （JSON 字符串数组）。服务 API 配置 `key_tag` 后，调度只在带有该标签的密钥之间选择。
+//! 标签去除首尾空白后比较，区分大小写。
+
+use crate::error::{Result, conversion::ConversionError};
+use entity::user_provider_keys;
+use serde_json::Value;
+
+/// 单个密钥的标签数量上限
+const MAX_TAGS_PER_KEY: usize = 16;
+/// 单个标签的长度上限（字符）
+const MAX_TAG_LEN: usize = 32;
+
+/// 校验单个标签并去除首尾空白
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(ConversionError::message("标签不能为空").into());
+    }
+    if tag.chars().count() > MAX_TAG_LEN {
+        return Err(ConversionError::message(format!("标签长度不能超过 {MAX_TAG_LEN}")).into());
+    }
+    Ok(tag.to_string())
+}
+
+/// 校验标签列表：去除首尾空白、去重并保持原顺序
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = normalize_tag(tag)?;
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    if normalized.len() > MAX_TAGS_PER_KEY {
+        return Err(
+            ConversionError::message(format!("每个密钥最多 {MAX_TAGS_PER_KEY} 个标签")).into(),
+        );
+    }
+    Ok(normalized)
+}
+
+/// 校验后的标签列表转为列值，空列表存为 NULL
+pub fn tags_to_json(tags: &[String]) -> Result<Option<Value>> {
+    let tags = normalize_tags(tags)?;
+    Ok((!tags.is_empty()).then(|| Value::from(tags)))
+}
+
+/// 读取密钥的标签，忽略非字符串项
+#[must_use]
+pub fn key_tags(key: &user_provider_keys::Model) -> Vec<String> {
+    key.tags
+        .as_ref()
+        .and_then(Value::as_array)
+        .map(|tags| {
+            tags.iter()
+                .filter_map(Value::as_str)
+                .map(|tag| tag.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 密钥是否带有指定标签
+#[must_use]
+pub fn key_has_tag(key: &user_provider_keys::Model, tag: &str) -> bool {
+    let tag = tag.trim();
+    key.tags
+        .as_ref()
+        .and_then(Value::as_array)
+        .is_some_and(|tags| {
+            tags.iter()
+                .filter_map(Value::as_str)
+                .any(|candidate| candidate.trim() == tag)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn normalize_trims_and_dedups() {
+        let normalized = normalize_tags(&tags(&[" prod ", "high-quota", "prod"])).unwrap();
+        assert_eq!(normalized, tags(&["prod", "high-quota"]));
+        assert_eq!(tags_to_json(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_tags_are_rejected() {
+        assert!(normalize_tags(&tags(&["  "])).is_err());
+        assert!(normalize_tags(&["x".repeat(MAX_TAG_LEN + 1)]).is_err());
+        let too_many: Vec<String> = (0..=MAX_TAGS_PER_KEY).map(|i| format!("t{i}")).collect();
+        assert!(normalize_tags(&too_many).is_err());
+    }
+}
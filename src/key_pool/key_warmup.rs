@@ -0,0 +1,140 @@
+//! # 新密钥预热
+//!
+//! 刚添加的密钥可能尚未经过真实流量验证，直接分到全部流量风险较大。开启预热后，密钥在创建后的
+//! 预热窗口内只按一定比例参与选择：比例随创建时长线性增长，窗口结束后恢复正常。
+//! 比例按密钥累积（每次选择累加比例，满 1 时放行一次），分配稳定且不依赖随机数。
+
+use dashmap::DashMap;
+use entity::user_provider_keys;
+use sea_orm::prelude::DateTime;
+use std::time::Duration;
+
+/// 预热刚开始时参与选择的最低比例，避免新密钥完全拿不到流量
+pub const MIN_WARMUP_SHARE: f64 = 0.05;
+
+/// 密钥在预热窗口内参与选择的比例（`MIN_WARMUP_SHARE` 到 1）
+#[must_use]
+pub fn warmup_share(created_at: DateTime, now: DateTime, window: Duration) -> f64 {
+    let elapsed = now
+        .signed_duration_since(created_at)
+        .to_std()
+        .unwrap_or_default();
+    if window.is_zero() || elapsed >= window {
+        return 1.0;
+    }
+    (elapsed.as_secs_f64() / window.as_secs_f64()).max(MIN_WARMUP_SHARE)
+}
+
+/// 预热中密钥的累积份额（进程内）
+#[derive(Debug, Default)]
+pub struct KeyWarmup {
+    /// 密钥ID -> 尚未用完的份额
+    credits: DashMap<i32, f64>,
+}
+
+impl KeyWarmup {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 本次选择是否让该密钥参与：累加当前比例，满 1 时放行
+    pub fn admit(&self, key: &user_provider_keys::Model, now: DateTime, window: Duration) -> bool {
+        let share = warmup_share(key.created_at, now, window);
+        if share >= 1.0 {
+            self.credits.remove(&key.id);
+            return true;
+        }
+        let mut credit = self.credits.entry(key.id).or_insert(0.0);
+        *credit += share;
+        if *credit >= 1.0 {
+            *credit -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeDelta, Utc};
+
+    const WINDOW: Duration = Duration::from_secs(3600);
+
+    fn provider_key(id: i32, created_at: DateTime) -> user_provider_keys::Model {
+        user_provider_keys::Model {
+            id,
+            user_id: 1,
+            provider_type_id: 1,
+            api_key: format!("sk-test-{id}"),
+            auth_type: "api_key".to_string(),
+            name: format!("key-{id}"),
+            weight: Some(1),
+            max_requests_per_minute: None,
+            max_tokens_prompt_per_minute: None,
+            max_requests_per_day: None,
+            is_active: true,
+            health_status: "healthy".to_string(),
+            health_status_detail: None,
+            rate_limit_resets_at: None,
+            last_error_time: None,
+            auth_status: None,
+            expires_at: None,
+            last_auth_check: None,
+            project_id: None,
+            tags: None,
+            deleted_at: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    /// 在 `now` 时刻连续选择 1000 次，返回密钥获准参与的次数
+    fn admitted_of_1000(warmup: &KeyWarmup, key: &user_provider_keys::Model, now: DateTime) -> u32 {
+        let mut admitted = 0;
+        for _ in 0..1000 {
+            if warmup.admit(key, now, WINDOW) {
+                admitted += 1;
+            }
+        }
+        admitted
+    }
+
+    #[test]
+    fn share_grows_with_key_age() {
+        let created_at = Utc::now().naive_utc();
+        let share_at =
+            |minutes| warmup_share(created_at, created_at + TimeDelta::minutes(minutes), WINDOW);
+
+        assert!((share_at(0) - MIN_WARMUP_SHARE).abs() < f64::EPSILON);
+        assert!((share_at(30) - 0.5).abs() < f64::EPSILON);
+        assert!((share_at(60) - 1.0).abs() < f64::EPSILON);
+        assert!((share_at(120) - 1.0).abs() < f64::EPSILON);
+        // 创建时间晚于当前时间（时钟偏差）按刚创建处理
+        assert!((share_at(-5) - MIN_WARMUP_SHARE).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn new_key_gets_reduced_share_that_grows_over_time() {
+        let created_at = Utc::now().naive_utc();
+        let warmup = KeyWarmup::new();
+        let fresh = provider_key(1, created_at);
+
+        // 刚创建时只参与约 5% 的选择（浮点累加可能少放行一两次）
+        let admitted = admitted_of_1000(&warmup, &fresh, created_at);
+        assert!((45..=50).contains(&admitted), "{admitted}");
+        // 预热过半时约 50%（上一阶段剩余的份额可能多放行一次）
+        let halfway = created_at + TimeDelta::minutes(30);
+        let admitted = admitted_of_1000(&warmup, &fresh, halfway);
+        assert!((500..=501).contains(&admitted), "{admitted}");
+        // 预热结束后全部参与
+        let warmed = created_at + TimeDelta::minutes(60);
+        assert_eq!(admitted_of_1000(&warmup, &fresh, warmed), 1000);
+
+        // 早已存在的密钥不受影响
+        let existing = provider_key(2, created_at - TimeDelta::days(1));
+        assert_eq!(admitted_of_1000(&warmup, &existing, created_at), 1000);
+    }
+}
@@ -3,16 +3,26 @@
 //! 实现API密钥选择算法，从用户的多个API密钥中选择合适的密钥
 
 pub mod algorithms;
+pub mod api_key_daily_quota;
 pub mod api_key_health;
+pub mod api_key_latency;
 pub mod api_key_rate_limit_reset_task;
 pub mod api_key_scheduler_service;
+pub mod key_tags;
+pub mod key_warmup;
+pub mod provider_health_check_task;
 pub mod types;
 
 pub use algorithms::{
-    ApiKeySelectionResult, ApiKeySelector, RoundRobinApiKeySelector, SelectionContext,
-    create_api_key_selector,
+    ApiKeySelectionResult, ApiKeySelector, CostAwareApiKeySelector, RoundRobinApiKeySelector,
+    SelectionContext, create_api_key_selector,
 };
+pub use api_key_daily_quota::ApiKeyDailyQuota;
 pub use api_key_health::ApiKeyHealthService;
+pub use api_key_latency::ApiKeyLatencyStats;
 pub use api_key_rate_limit_reset_task::ApiKeyRateLimitResetTask;
 pub use api_key_scheduler_service::ApiKeySchedulerService;
-pub use types::SchedulingStrategy;
+pub use provider_health_check_task::{
+    HealthCheckTarget, ProviderHealthCheckTask, ProviderHealthProbe, UpstreamReachabilityProbe,
+};
+pub use types::{HealthUpdate, PoolStats, SchedulingStrategy};
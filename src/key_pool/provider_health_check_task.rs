@@ -0,0 +1,591 @@
+//! # 服务商健康检查任务
+//!
+//! 每个服务商类型拥有独立的检查协程与计时器：
+//! - 检查间隔按服务商配置（`health_check.providers`），未配置时使用默认间隔；
+//! - 单个服务商的检查变慢或超时只影响自身，不会阻塞其它服务商；
+//! - 检查结果计入上游熔断器：失败与代理请求的失败一起累计，达到阈值后熔断；通过则关闭熔断；
+//! - 管理端修改配置后通过 [`ProviderHealthCheckTask::reload`] 生效，运行中的计时器立即按新间隔重新排期。
+
+use crate::app::task_scheduler::TaskRunRecorder;
+use crate::config::HealthCheckConfig;
+use crate::error::{Context, Result, key_pool::KeyPoolError};
+use crate::logging::{LogComponent, LogStage};
+use crate::proxy::upstream_circuit::UpstreamCircuitBreaker;
+use crate::{ldebug, linfo, lwarn};
+use async_trait::async_trait;
+use chrono::Utc;
+use entity::provider_types;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{RwLock, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, sleep_until, timeout};
+
+/// 健康检查目标（服务商类型）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheckTarget {
+    pub provider_type_id: i32,
+    pub name: String,
+    pub base_url: String,
+}
+
+impl From<provider_types::Model> for HealthCheckTarget {
+    fn from(model: provider_types::Model) -> Self {
+        Self {
+            provider_type_id: model.id,
+            name: model.name,
+            base_url: model.base_url,
+        }
+    }
+}
+
+/// 服务商探测实现
+#[async_trait]
+pub trait ProviderHealthProbe: Send + Sync {
+    /// 对单个服务商执行一次检查，返回错误表示不健康
+    async fn check(&self, target: &HealthCheckTarget) -> Result<()>;
+}
+
+/// 上游可达性探测：能收到非 5xx 的 HTTP 响应即视为健康
+pub struct UpstreamReachabilityProbe {
+    client: reqwest::Client,
+}
+
+impl UpstreamReachabilityProbe {
+    #[must_use]
+    pub const fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl ProviderHealthProbe for UpstreamReachabilityProbe {
+    async fn check(&self, target: &HealthCheckTarget) -> Result<()> {
+        let url = if target.base_url.contains("://") {
+            target.base_url.clone()
+        } else {
+            format!("https://{}", target.base_url)
+        };
+
+        let response =
+            self.client
+                .get(&url)
+                .send()
+                .await
+                .map_err(|err| KeyPoolError::ProviderUnhealthy {
+                    provider: target.name.clone(),
+                    reason: err.to_string(),
+                })?;
+
+        if response.status().is_server_error() {
+            return Err(KeyPoolError::ProviderUnhealthy {
+                provider: target.name.clone(),
+                reason: format!("upstream returned {}", response.status()),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+struct ProviderWorker {
+    name: String,
+    interval_tx: watch::Sender<Duration>,
+    handle: JoinHandle<()>,
+}
+
+/// 服务商健康检查后台任务
+#[derive(Clone)]
+pub struct ProviderHealthCheckTask {
+    db: Arc<DatabaseConnection>,
+    probe: Arc<dyn ProviderHealthProbe>,
+    config: Arc<RwLock<HealthCheckConfig>>,
+    workers: Arc<RwLock<HashMap<i32, ProviderWorker>>>,
+    /// 检查结果写入的熔断器
+    circuit_breaker: Option<Arc<UpstreamCircuitBreaker>>,
+    recorder: Option<TaskRunRecorder>,
+}
+
+impl ProviderHealthCheckTask {
+    #[must_use]
+    pub fn new(
+        db: Arc<DatabaseConnection>,
+        probe: Arc<dyn ProviderHealthProbe>,
+        config: HealthCheckConfig,
+    ) -> Self {
+        Self {
+            db,
+            probe,
+            config: Arc::new(RwLock::new(config)),
+            workers: Arc::new(RwLock::new(HashMap::new())),
+            circuit_breaker: None,
+            recorder: None,
+        }
+    }
+
+    /// 将检查结果计入上游熔断器
+    #[must_use]
+    pub fn with_circuit_breaker(mut self, circuit_breaker: Arc<UpstreamCircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// 每次检查后记录心跳与执行历史
+    #[must_use]
+    pub fn with_run_recorder(mut self, recorder: TaskRunRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// 当前生效的配置
+    pub async fn config(&self) -> HealthCheckConfig {
+        self.config.read().await.clone()
+    }
+
+    /// 启动任务：为每个启用的服务商类型创建独立的检查协程
+    pub async fn start(&self) -> Result<()> {
+        if !self.config.read().await.enabled {
+            linfo!(
+                "system",
+                LogStage::Startup,
+                LogComponent::HealthChecker,
+                "provider_health_check_disabled",
+                "服务商健康检查未启用"
+            );
+            return Ok(());
+        }
+
+        let targets = provider_types::Entity::find()
+            .filter(provider_types::Column::IsActive.eq(true))
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to load provider types for health check")?
+            .into_iter()
+            .map(HealthCheckTarget::from)
+            .collect();
+
+        self.start_targets(targets).await;
+        Ok(())
+    }
+
+    /// 为指定目标启动检查协程；已在运行的目标保持不变
+    pub async fn start_targets(&self, targets: Vec<HealthCheckTarget>) {
+        let config = self.config.read().await.clone();
+        let mut workers = self.workers.write().await;
+
+        for target in targets {
+            if workers.contains_key(&target.provider_type_id) {
+                continue;
+            }
+            let interval = config.interval_for(&target.name);
+            let (interval_tx, interval_rx) = watch::channel(interval);
+            let provider_type_id = target.provider_type_id;
+            let name = target.name.clone();
+
+            linfo!(
+                "system",
+                LogStage::Startup,
+                LogComponent::HealthChecker,
+                "provider_health_check_scheduled",
+                "服务商健康检查已排期",
+                provider = %target.name,
+                interval_secs = interval.as_secs()
+            );
+
+            let handle = tokio::spawn(run_provider_checks(
+                target,
+                Arc::clone(&self.probe),
+                self.circuit_breaker.clone(),
+                self.recorder.clone(),
+                interval_rx,
+                config.timeout(),
+                Instant::now(),
+            ));
+            workers.insert(
+                provider_type_id,
+                ProviderWorker {
+                    name,
+                    interval_tx,
+                    handle,
+                },
+            );
+        }
+    }
+
+    /// 重新加载配置：运行中的计时器按新间隔重新排期；停用时停止所有检查，启用时为尚未检查的服务商排期
+    pub async fn reload(&self, config: HealthCheckConfig) -> Result<()> {
+        let enabled = config.enabled;
+        let workers = self.workers.read().await;
+        for worker in workers.values() {
+            let interval = config.interval_for(&worker.name);
+            worker.interval_tx.send_if_modified(|current| {
+                let changed = *current != interval;
+                *current = interval;
+                changed
+            });
+        }
+        let idle = workers.is_empty();
+        drop(workers);
+
+        *self.config.write().await = config;
+        if !enabled {
+            self.stop().await;
+        } else if idle {
+            self.start().await?;
+        }
+        Ok(())
+    }
+
+    /// 停止所有检查协程
+    pub async fn stop(&self) {
+        let workers: Vec<ProviderWorker> = {
+            let mut guard = self.workers.write().await;
+            guard.drain().map(|(_, worker)| worker).collect()
+        };
+        for worker in workers {
+            worker.handle.abort();
+            let _ = worker.handle.await;
+        }
+    }
+}
+
+/// 单个服务商的检查循环
+///
+/// 下一次检查从本次检查结束后开始计时，探测变慢时不会堆积；间隔变更时以上次检查时间为基准重新排期。
+async fn run_provider_checks(
+    target: HealthCheckTarget,
+    probe: Arc<dyn ProviderHealthProbe>,
+    circuit_breaker: Option<Arc<UpstreamCircuitBreaker>>,
+    recorder: Option<TaskRunRecorder>,
+    mut interval_rx: watch::Receiver<Duration>,
+    check_timeout: Duration,
+    scheduled_at: Instant,
+) {
+    let mut interval = *interval_rx.borrow_and_update();
+    let mut last_check = scheduled_at;
+
+    loop {
+        tokio::select! {
+            () = sleep_until(last_check + interval) => {
+                let started_at = Utc::now();
+                let timer = Instant::now();
+                let error = check_once(&target, probe.as_ref(), check_timeout).await;
+                let elapsed = timer.elapsed();
+                if let Some(circuit_breaker) = circuit_breaker.as_deref() {
+                    record_check_result(circuit_breaker, &target, error.is_none());
+                }
+                if let Some(recorder) = &recorder {
+                    recorder.record(started_at, elapsed, error).await;
+                }
+                last_check = Instant::now();
+            }
+            changed = interval_rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                interval = *interval_rx.borrow_and_update();
+                ldebug!(
+                    "system",
+                    LogStage::BackgroundTask,
+                    LogComponent::HealthChecker,
+                    "provider_health_check_rescheduled",
+                    "服务商健康检查间隔已更新",
+                    provider = %target.name,
+                    interval_secs = interval.as_secs()
+                );
+            }
+        }
+    }
+}
+
+/// 执行一次检查，返回失败原因；检查通过时为 `None`
+async fn check_once(
+    target: &HealthCheckTarget,
+    probe: &dyn ProviderHealthProbe,
+    limit: Duration,
+) -> Option<String> {
+    match timeout(limit, probe.check(target)).await {
+        Ok(Ok(())) => {
+            ldebug!(
+                "system",
+                LogStage::HealthCheck,
+                LogComponent::HealthChecker,
+                "provider_health_check_ok",
+                "服务商健康检查通过",
+                provider = %target.name
+            );
+            None
+        }
+        Ok(Err(err)) => {
+            lwarn!(
+                "system",
+                LogStage::HealthCheck,
+                LogComponent::HealthChecker,
+                "provider_health_check_failed",
+                "服务商健康检查失败",
+                provider = %target.name,
+                error = %err
+            );
+            Some(err.to_string())
+        }
+        Err(_) => {
+            lwarn!(
+                "system",
+                LogStage::HealthCheck,
+                LogComponent::HealthChecker,
+                "provider_health_check_timeout",
+                "服务商健康检查超时",
+                provider = %target.name,
+                timeout_secs = limit.as_secs()
+            );
+            Some(format!("健康检查超时（{}s）", limit.as_secs()))
+        }
+    }
+}
+
+/// 检查结果计入熔断器：失败累计到熔断阈值，通过则关闭熔断
+fn record_check_result(
+    circuit_breaker: &UpstreamCircuitBreaker,
+    target: &HealthCheckTarget,
+    healthy: bool,
+) {
+    if healthy {
+        circuit_breaker.record_success(&target.name);
+    } else if circuit_breaker.record_failure(&target.name) {
+        lwarn!(
+            "system",
+            LogStage::HealthCheck,
+            LogComponent::HealthChecker,
+            "provider_health_check_circuit_opened",
+            "服务商健康检查连续失败，上游已熔断",
+            provider = %target.name
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::task_scheduler::TaskScheduler;
+    use crate::app::tasks::TaskType;
+    use crate::config::CircuitBreakerConfig;
+    use crate::proxy::upstream_circuit::CircuitDecision;
+    use std::sync::Mutex;
+
+    /// 记录每个服务商被检查的次数；`slow` 中的服务商每次检查耗时 `slow_delay`，`failing` 中的服务商检查失败
+    #[derive(Default)]
+    struct RecordingProbe {
+        checks: Mutex<HashMap<String, usize>>,
+        slow: Vec<String>,
+        slow_delay: Duration,
+        failing: Vec<String>,
+    }
+
+    impl RecordingProbe {
+        fn count(&self, name: &str) -> usize {
+            self.checks.lock().unwrap().get(name).copied().unwrap_or(0)
+        }
+    }
+
+    #[async_trait]
+    impl ProviderHealthProbe for RecordingProbe {
+        async fn check(&self, target: &HealthCheckTarget) -> Result<()> {
+            *self
+                .checks
+                .lock()
+                .unwrap()
+                .entry(target.name.clone())
+                .or_default() += 1;
+            if self.slow.contains(&target.name) {
+                tokio::time::sleep(self.slow_delay).await;
+            }
+            if self.failing.contains(&target.name) {
+                return Err(KeyPoolError::ProviderUnhealthy {
+                    provider: target.name.clone(),
+                    reason: "upstream returned 503".to_string(),
+                }
+                .into());
+            }
+            Ok(())
+        }
+    }
+
+    fn target(id: i32, name: &str) -> HealthCheckTarget {
+        HealthCheckTarget {
+            provider_type_id: id,
+            name: name.to_string(),
+            base_url: format!("{name}.example.com"),
+        }
+    }
+
+    fn config(default_secs: u64, overrides: &[(&str, u64)]) -> HealthCheckConfig {
+        HealthCheckConfig {
+            enabled: true,
+            default_interval_secs: default_secs,
+            timeout_secs: 600,
+            providers: overrides
+                .iter()
+                .map(|(name, secs)| ((*name).to_string(), *secs))
+                .collect(),
+        }
+    }
+
+    async fn task_with(
+        probe: Arc<RecordingProbe>,
+        config: HealthCheckConfig,
+    ) -> ProviderHealthCheckTask {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        ProviderHealthCheckTask::new(Arc::new(db), probe, config)
+    }
+
+    /// 让出执行权，使被唤醒的检查协程完成本轮检查
+    async fn advance(duration: Duration) {
+        tokio::time::advance(duration).await;
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn providers_are_checked_at_their_own_cadence() {
+        let probe = Arc::new(RecordingProbe::default());
+        let task = task_with(
+            probe.clone(),
+            config(300, &[("openai", 10), ("gemini", 30)]),
+        )
+        .await;
+        task.start_targets(vec![target(1, "openai"), target(2, "gemini")])
+            .await;
+
+        for _ in 0..6 {
+            advance(Duration::from_secs(10)).await;
+        }
+
+        assert_eq!(probe.count("openai"), 6);
+        assert_eq!(probe.count("gemini"), 2);
+        task.stop().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_provider_does_not_block_others() {
+        let probe = Arc::new(RecordingProbe {
+            slow: vec!["claude".to_string()],
+            slow_delay: Duration::from_secs(45),
+            ..Default::default()
+        });
+        let task = task_with(probe.clone(), config(10, &[])).await;
+        task.start_targets(vec![target(1, "openai"), target(2, "claude")])
+            .await;
+
+        for _ in 0..6 {
+            advance(Duration::from_secs(10)).await;
+        }
+
+        // claude 在 10s 开始的检查持续到 55s，期间 openai 照常每 10s 检查一次
+        assert_eq!(probe.count("openai"), 6);
+        assert_eq!(probe.count("claude"), 1);
+        task.stop().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reload_reschedules_running_timers() {
+        let probe = Arc::new(RecordingProbe::default());
+        let task = task_with(probe.clone(), config(60, &[])).await;
+        task.start_targets(vec![target(1, "openai")]).await;
+
+        advance(Duration::from_secs(60)).await;
+        assert_eq!(probe.count("openai"), 1);
+
+        // 以上次检查（60s）为基准按新间隔重新排期：70s/80s/90s
+        task.reload(config(60, &[("openai", 10)]))
+            .await
+            .expect("reload config");
+        advance(Duration::from_secs(1)).await;
+
+        for _ in 0..3 {
+            advance(Duration::from_secs(10)).await;
+        }
+        assert_eq!(probe.count("openai"), 4);
+        task.stop().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_checks_open_the_upstream_circuit() {
+        let probe = Arc::new(RecordingProbe {
+            failing: vec!["claude".to_string()],
+            ..Default::default()
+        });
+        let breaker = Arc::new(UpstreamCircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 2,
+            open_secs: 600,
+            ..CircuitBreakerConfig::default()
+        }));
+        let task = task_with(probe.clone(), config(10, &[]))
+            .await
+            .with_circuit_breaker(breaker.clone());
+        task.start_targets(vec![target(1, "openai"), target(2, "claude")])
+            .await;
+
+        advance(Duration::from_secs(10)).await;
+        assert_eq!(breaker.check("claude"), CircuitDecision::Allow);
+        advance(Duration::from_secs(10)).await;
+
+        assert!(matches!(
+            breaker.check("claude"),
+            CircuitDecision::Reject { .. }
+        ));
+        assert_eq!(breaker.check("openai"), CircuitDecision::Allow);
+        task.stop().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reload_disabling_stops_checks() {
+        let probe = Arc::new(RecordingProbe::default());
+        let task = task_with(probe.clone(), config(10, &[])).await;
+        task.start_targets(vec![target(1, "openai")]).await;
+
+        advance(Duration::from_secs(10)).await;
+        assert_eq!(probe.count("openai"), 1);
+
+        let disabled = HealthCheckConfig {
+            enabled: false,
+            ..config(10, &[])
+        };
+        task.reload(disabled).await.expect("reload config");
+        for _ in 0..3 {
+            advance(Duration::from_secs(10)).await;
+        }
+        assert_eq!(probe.count("openai"), 1);
+        assert!(!task.config().await.enabled);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn each_check_updates_task_heartbeat() {
+        let probe = Arc::new(RecordingProbe {
+            failing: vec!["claude".to_string()],
+            ..Default::default()
+        });
+        let scheduler = TaskScheduler::new();
+        let task = task_with(probe.clone(), config(10, &[]))
+            .await
+            .with_run_recorder(scheduler.recorder(TaskType::ProviderHealthCheck));
+        task.start_targets(vec![target(1, "openai"), target(2, "claude")])
+            .await;
+
+        for _ in 0..2 {
+            advance(Duration::from_secs(10)).await;
+        }
+
+        let heartbeat = scheduler
+            .heartbeats()
+            .get(TaskType::ProviderHealthCheck)
+            .expect("heartbeat recorded");
+        assert_eq!(heartbeat.run_count, 4);
+        assert_eq!(heartbeat.error_count, 2);
+        assert!(heartbeat.last_run_at.is_some());
+        task.stop().await;
+    }
+}
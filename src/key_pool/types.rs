@@ -1,8 +1,12 @@
 //! # API密钥调度器类型定义
 
+use super::algorithms::registered_strategies;
+use crate::error::{self, conversion::ConversionError};
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
+use std::time::Duration;
 
 /// 调度策略枚举
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
@@ -12,6 +16,8 @@ pub enum SchedulingStrategy {
     RoundRobin,
     /// 权重调度
     Weighted,
+    /// 成本感知调度（优先选择定价最低的健康密钥）
+    CostAware,
 }
 
 /// API密钥健康状态枚举
@@ -25,6 +31,22 @@ pub enum ApiKeyHealthStatus {
     Unhealthy,
 }
 
+/// 根据上游响应得出的密钥健康状态变更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthUpdate {
+    /// 不改变健康状态
+    Unchanged,
+    /// 暂时降级（服务商过载、限流等），冷却结束后自动恢复
+    Degraded { cooldown: Duration, reason: String },
+    /// 上游限流（429），冷却时间取 `Retry-After`；未携带时使用配置的默认冷却，连续限流时按配置递增
+    RateLimited {
+        retry_after: Option<Duration>,
+        reason: String,
+    },
+    /// 密钥失效（无效密钥、权限被撤销等），需要人工处理
+    Unhealthy { reason: String },
+}
+
 impl<'de> serde::Deserialize<'de> for ApiKeyHealthStatus {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -100,6 +122,7 @@ impl FromStr for SchedulingStrategy {
         match s.to_lowercase().as_str() {
             "round_robin" | "roundrobin" | "rr" => Ok(Self::RoundRobin),
             "weighted" | "weight" | "w" => Ok(Self::Weighted),
+            "cost_aware" | "costaware" | "cost" => Ok(Self::CostAware),
             _ => Err(format!("Unknown scheduling strategy: {s}")),
         }
     }
@@ -112,12 +135,68 @@ impl SchedulingStrategy {
         s.parse().ok()
     }
 
+    /// 写入前校验调度策略名称，只接受选择器工厂已注册的策略，未知名称返回列出可选值的错误
+    pub fn parse_known(s: &str) -> error::Result<Self> {
+        Self::parse(s)
+            .filter(|strategy| registered_strategies().any(|registered| registered == *strategy))
+            .ok_or_else(|| {
+                let known: Vec<&str> = registered_strategies()
+                    .map(|strategy| strategy.as_str())
+                    .collect();
+                ConversionError::message(format!(
+                    "未知的调度策略: {s}，可选值: {}",
+                    known.join(", ")
+                ))
+                .into()
+            })
+    }
+
     /// 转换为字符串
     #[must_use]
     pub const fn as_str(&self) -> &'static str {
         match self {
             Self::RoundRobin => "round_robin",
             Self::Weighted => "weighted",
+            Self::CostAware => "cost_aware",
+        }
+    }
+}
+
+/// 服务商密钥池状态汇总
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PoolStats {
+    /// 未删除的密钥总数
+    pub total: u32,
+    /// 已启用的密钥数
+    pub active: u32,
+    /// 已启用且健康的密钥数
+    pub healthy: u32,
+    /// 已启用且限流中的密钥数
+    pub rate_limited: u32,
+    /// 已启用且冷却未结束（`rate_limit_resets_at` 在未来）的密钥数，冷却结束后自动恢复
+    pub cooling_down: u32,
+    /// 已启用但不健康的密钥数（包括无法识别的状态）
+    pub unhealthy: u32,
+}
+
+impl PoolStats {
+    /// 计入一个未删除的密钥
+    pub fn record(&mut self, key: &entity::user_provider_keys::Model, now: NaiveDateTime) {
+        self.total += 1;
+        if !key.is_active {
+            return;
+        }
+        self.active += 1;
+        match key.health_status.parse() {
+            Ok(ApiKeyHealthStatus::Healthy) => self.healthy += 1,
+            Ok(ApiKeyHealthStatus::RateLimited) => self.rate_limited += 1,
+            Ok(ApiKeyHealthStatus::Unhealthy) | Err(_) => self.unhealthy += 1,
+        }
+        if key
+            .rate_limit_resets_at
+            .is_some_and(|resets_at| resets_at > now)
+        {
+            self.cooling_down += 1;
         }
     }
 }
@@ -136,13 +215,36 @@ mod tests {
             SchedulingStrategy::parse("weighted"),
             Some(SchedulingStrategy::Weighted)
         );
+        assert_eq!(
+            SchedulingStrategy::parse("cost_aware"),
+            Some(SchedulingStrategy::CostAware)
+        );
         assert_eq!(SchedulingStrategy::parse("unknown"), None);
     }
 
+    #[test]
+    fn test_known_scheduling_strategies_are_accepted() {
+        for strategy in registered_strategies() {
+            assert_eq!(
+                SchedulingStrategy::parse_known(strategy.as_str()).unwrap(),
+                strategy
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_scheduling_strategy_is_rejected() {
+        let err = SchedulingStrategy::parse_known("round_robbin").unwrap_err();
+        assert_eq!(err.status_code(), http::StatusCode::BAD_REQUEST);
+        assert!(err.to_string().contains("round_robbin"));
+        assert!(err.to_string().contains("cost_aware"));
+    }
+
     #[test]
     fn test_scheduling_strategy_as_str() {
         assert_eq!(SchedulingStrategy::RoundRobin.as_str(), "round_robin");
         assert_eq!(SchedulingStrategy::Weighted.as_str(), "weighted");
+        assert_eq!(SchedulingStrategy::CostAware.as_str(), "cost_aware");
     }
 
     #[test]
@@ -4,10 +4,13 @@
 //! - 业务日志格式化（proxy模块专用）
 //! - 数据库查询日志格式化
 //! - 日志系统初始化和配置
+//! - 可选的本地滚动文件输出（见 [`file_sink`]）
+
+pub mod file_sink;
 
 use crate::{
     collect::util::decompress_for_stats,
-    error::{ErrorCategory, ProxyError},
+    error::{ErrorCategory, ProxyError, tls::TlsError},
     proxy::ProxyContext,
 };
 use flate2::read::GzDecoder;
@@ -18,8 +21,14 @@ use serde_json;
 use std::collections::BTreeMap;
 use std::env;
 use std::io::Read;
+use std::sync::OnceLock;
 use tracing_subscriber::{EnvFilter, Layer, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
+use self::file_sink::{FileSink, FileSinkConfig};
+
+/// 进程级文件日志（写盘线程随进程存活）
+static FILE_SINK: OnceLock<FileSink> = OnceLock::new();
+
 /// 附加错误日志字段的结构体
 #[derive(Debug, Clone)]
 pub struct ErrorLogField<'a> {
@@ -684,21 +693,74 @@ pub fn init_optimized_logging(log_level: Option<&String>) {
         .with_thread_names(false)
         .with_file(false)
         .with_line_number(false)
-        .with_filter(tracing_subscriber::filter::FilterFn::new(|metadata| {
-            // 过滤掉一些噪音日志
-            !metadata.target().starts_with("h2::client")
-                && !metadata.target().starts_with("hyper::")
-                && !metadata.target().starts_with("tokio::runtime")
-                && !metadata.target().starts_with("pingora::upstreams::peer")
-        }));
+        .with_filter(tracing_subscriber::filter::FilterFn::new(
+            is_relevant_target,
+        ));
+
+    // 可选：同时写入本地滚动文件（结构化事件与请求/响应体日志都经由 tracing 输出）
+    let file_sink_config = FileSinkConfig::from_env();
+    let file_sink_result = file_sink_config.clone().map(FileSink::start).transpose();
+    let file_layer = file_sink_result
+        .as_ref()
+        .ok()
+        .and_then(Option::as_ref)
+        .map(|sink| {
+            fmt::layer()
+                .with_ansi(false)
+                .with_target(true)
+                .with_level(true)
+                .with_writer(sink.make_writer())
+                .with_filter(tracing_subscriber::filter::FilterFn::new(
+                    is_relevant_target,
+                ))
+        });
 
     tracing_subscriber::registry()
         .with(env_filter)
         .with(fmt_layer)
+        .with(file_layer)
         .init();
 
     // 打印启动信息
     print_startup_info(&final_config, &log_filter);
+
+    match (file_sink_config, file_sink_result) {
+        (Some(config), Ok(Some(sink))) => {
+            let _ = FILE_SINK.set(sink);
+            linfo!(
+                "system",
+                LogStage::Startup,
+                LogComponent::Main,
+                "log_file_enabled",
+                "文件日志已启用",
+                path = %config.path.display(),
+                max_size_bytes = config.max_size_bytes,
+                rotation = ?config.rotation,
+                retention = config.retention
+            );
+        }
+        (Some(config), Err(err)) => {
+            lwarn!(
+                "system",
+                LogStage::Startup,
+                LogComponent::Main,
+                "log_file_open_failed",
+                "文件日志无法打开，仅输出到标准输出",
+                path = %config.path.display(),
+                error = %err
+            );
+        }
+        _ => {}
+    }
+}
+
+/// 过滤掉一些噪音日志
+fn is_relevant_target(metadata: &tracing::Metadata<'_>) -> bool {
+    let target = metadata.target();
+    !target.starts_with("h2::client")
+        && !target.starts_with("hyper::")
+        && !target.starts_with("tokio::runtime")
+        && !target.starts_with("pingora::upstreams::peer")
 }
 
 /// 打印启动信息
@@ -947,52 +1009,61 @@ fn analyze_error_type(
                 )
             }
         },
-        |err| match err.etype {
-            ErrorType::ConnectionClosed => (
-                "connection_failure".to_string(),
-                "连接关闭".to_string(),
-                format!("连接关闭: {err}"),
-            ),
-            ErrorType::ConnectTimedout => (
-                "connection_timeout".to_string(),
-                "连接超时".to_string(),
-                format!("连接上游服务器超时: {err}"),
-            ),
-            ErrorType::ReadTimedout => (
-                "read_timeout".to_string(),
-                "读取超时".to_string(),
-                format!("读取响应数据超时: {err}"),
-            ),
-            ErrorType::WriteTimedout => (
-                "write_timeout".to_string(),
-                "写入超时".to_string(),
-                format!("发送请求数据超时: {err}"),
-            ),
-            ErrorType::HTTPStatus(code) => {
-                if code == 0 {
-                    (
-                        "connection_error".to_string(),
-                        "连接错误".to_string(),
-                        format!("连接中断，未收到HTTP响应: {err}"),
-                    )
-                } else {
-                    (
-                        "http_error".to_string(),
-                        "HTTP错误响应".to_string(),
-                        format!("上游返回HTTP错误 {code}: {err}"),
-                    )
+        |err| {
+            if let Some(tls) = TlsError::from_pingora(err) {
+                return (
+                    "tls_error".to_string(),
+                    tls.description().to_string(),
+                    format!("{}: {err}", tls.description()),
+                );
+            }
+            match err.etype {
+                ErrorType::ConnectionClosed => (
+                    "connection_failure".to_string(),
+                    "连接关闭".to_string(),
+                    format!("连接关闭: {err}"),
+                ),
+                ErrorType::ConnectTimedout => (
+                    "connection_timeout".to_string(),
+                    "连接超时".to_string(),
+                    format!("连接上游服务器超时: {err}"),
+                ),
+                ErrorType::ReadTimedout => (
+                    "read_timeout".to_string(),
+                    "读取超时".to_string(),
+                    format!("读取响应数据超时: {err}"),
+                ),
+                ErrorType::WriteTimedout => (
+                    "write_timeout".to_string(),
+                    "写入超时".to_string(),
+                    format!("发送请求数据超时: {err}"),
+                ),
+                ErrorType::HTTPStatus(code) => {
+                    if code == 0 {
+                        (
+                            "connection_error".to_string(),
+                            "连接错误".to_string(),
+                            format!("连接中断，未收到HTTP响应: {err}"),
+                        )
+                    } else {
+                        (
+                            "http_error".to_string(),
+                            "HTTP错误响应".to_string(),
+                            format!("上游返回HTTP错误 {code}: {err}"),
+                        )
+                    }
                 }
+                ErrorType::CustomCode(_, code) => (
+                    "custom_error".to_string(),
+                    "自定义错误".to_string(),
+                    format!("自定义错误 {code}: {err}"),
+                ),
+                _ => (
+                    "unknown_error".to_string(),
+                    "未知错误".to_string(),
+                    format!("未知错误类型: {:?}", err.etype),
+                ),
             }
-            ErrorType::CustomCode(_, code) => (
-                "custom_error".to_string(),
-                "自定义错误".to_string(),
-                format!("自定义错误 {code}: {err}"),
-            ),
-            _ => (
-                "unknown_error".to_string(),
-                "未知错误".to_string(),
-                format!("未知错误类型: {:?}", err.etype),
-            ),
         },
     )
 }
@@ -1016,11 +1087,7 @@ pub fn log_proxy_failure_details(
     let context_status_code = ctx.response.details.status_code;
     let status_code_consistent = context_status_code.is_none_or(|ctx_code| ctx_code == status_code);
 
-    let should_log_response_body = ctx
-        .routing
-        .user_service_api
-        .as_ref()
-        .is_some_and(|user_api| user_api.log_mode);
+    let should_log_response_body = ctx.captures_full_detail();
 
     // 记录详细的错误信息
     let response_body = should_log_response_body.then(|| decode_response_body_for_logging(ctx));
@@ -1221,15 +1288,21 @@ pub fn log_complete_response(
 /// - 仅写入服务日志（不落库）
 /// - 不记录原始 body，只记录 JSON 结构（schema）与截断后的预览（key 保持，value 截断）
 /// - 始终输出 `*_body_schema` 字段，保证“结构”不缺失
+/// - 命中定向调试采集（`debug_capture`）时同样记录，且 value 不截断、附带耗时
 pub fn log_user_service_api_log_mode(ctx: &ProxyContext, status_code: u16) {
     const VALUE_TRUNCATE_LEN: usize = 1024;
 
     let Some(user_api) = ctx.routing.user_service_api.as_ref() else {
         return;
     };
-    if !user_api.log_mode {
+    if !ctx.captures_full_detail() {
         return;
     }
+    let value_truncate_len = if ctx.trace.debug_capture {
+        usize::MAX
+    } else {
+        VALUE_TRUNCATE_LEN
+    };
 
     let request_id = ctx.request_id.as_str();
 
@@ -1240,7 +1313,7 @@ pub fn log_user_service_api_log_mode(ctx: &ProxyContext, status_code: u16) {
             for (k, v) in &ctx.request.details.headers {
                 map.insert(
                     k.to_ascii_lowercase(),
-                    truncate_string_value(v, VALUE_TRUNCATE_LEN),
+                    truncate_string_value(v, value_truncate_len),
                 );
             }
             serde_json::to_string(&map).unwrap_or_else(|_| "{}".to_string())
@@ -1250,7 +1323,7 @@ pub fn log_user_service_api_log_mode(ctx: &ProxyContext, status_code: u16) {
             for (k, v) in headers {
                 map.insert(
                     k.to_ascii_lowercase(),
-                    truncate_string_value(v, VALUE_TRUNCATE_LEN),
+                    truncate_string_value(v, value_truncate_len),
                 );
             }
             serde_json::to_string(&map).unwrap_or_else(|_| "{}".to_string())
@@ -1259,14 +1332,14 @@ pub fn log_user_service_api_log_mode(ctx: &ProxyContext, status_code: u16) {
 
     // === 请求体预览（完整结构，value 截断） ===
     let request_body_bytes = ctx.request.body.as_ref();
-    let request_body_preview = build_body_preview(request_body_bytes, VALUE_TRUNCATE_LEN);
+    let request_body_preview = build_body_preview(request_body_bytes, value_truncate_len);
 
     // === 响应头（采集到的最终版本） ===
     let mut response_headers_map = BTreeMap::new();
     for (k, v) in &ctx.response.details.headers {
         response_headers_map.insert(
             k.to_ascii_lowercase(),
-            truncate_string_value(v, VALUE_TRUNCATE_LEN),
+            truncate_string_value(v, value_truncate_len),
         );
     }
     let response_headers_json =
@@ -1274,11 +1347,19 @@ pub fn log_user_service_api_log_mode(ctx: &ProxyContext, status_code: u16) {
 
     // === 响应体预览（完整结构，value 截断） ===
     let response_body_bytes = ctx.response.body.as_ref();
-    let response_body_preview = build_body_preview(response_body_bytes, VALUE_TRUNCATE_LEN);
+    let response_body_preview = if ctx.response.body_capture_skipped {
+        format!(
+            "[{}]",
+            crate::collect::usage_model::BODY_NOT_CAPTURED_MARKER
+        )
+    } else {
+        build_body_preview(response_body_bytes, value_truncate_len)
+    };
     let content_type = ctx.response.details.content_type.as_deref().unwrap_or("");
-    let response_sse_tail = if content_type
-        .to_ascii_lowercase()
-        .contains("text/event-stream")
+    let response_sse_tail = if !ctx.response.body_capture_skipped
+        && content_type
+            .to_ascii_lowercase()
+            .contains("text/event-stream")
     {
         extract_sse_tail_preview(
             response_body_bytes,
@@ -1322,7 +1403,9 @@ pub fn log_user_service_api_log_mode(ctx: &ProxyContext, status_code: u16) {
         status_code = status_code,
         response_sse_tail = %response_sse_tail,
         response_headers = %response_headers_json,
-        response_body_preview = %response_body_preview
+        response_body_preview = %response_body_preview,
+        debug_capture = ctx.trace.debug_capture,
+        duration_ms = ctx.start_time.elapsed().as_millis()
     );
 }
 
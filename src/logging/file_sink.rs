@@ -0,0 +1,394 @@
+//! # 滚动文件日志
+//!
+//! 无法把日志发送到外部系统的部署可以同时把日志写入本地文件。文件按大小和（可选）时间周期滚动，
+//! 只保留最近若干个历史文件：`proxy.log` 为当前文件，`proxy.log.1` 为最近一次滚动的文件，依次类推。
+//!
+//! 日志事件先进入有界队列，由独立线程写盘和滚动，请求处理线程不会被磁盘 I/O 阻塞；
+//! 队列满或写盘失败时丢弃日志并计数，写盘失败按间隔告警。
+//!
+//! 通过环境变量配置（与 `LOG_MODE` 一样在加载配置文件之前生效）：
+//! - `LOG_FILE_PATH`：日志文件路径，设置后启用
+//! - `LOG_FILE_MAX_SIZE_MB`：单个文件大小上限，默认 100
+//! - `LOG_FILE_ROTATION`：按时间滚动的周期，`hourly` / `daily` / `never`，默认 `never`
+//! - `LOG_FILE_RETENTION`：保留的历史文件个数，默认 7
+
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
+use chrono::{DateTime, Utc};
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// 写盘线程前的队列容量（条日志）
+const QUEUE_CAPACITY: usize = 8192;
+
+/// 写盘失败告警的最小间隔；告警本身也会写入文件，按间隔发出避免失败时反复告警
+const WRITE_ERROR_WARN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 队列已满或写盘失败被丢弃的日志条数
+static DROPPED_LINES: AtomicU64 = AtomicU64::new(0);
+
+/// 因写盘线程跟不上或写盘失败而丢弃的日志条数
+#[must_use]
+pub fn dropped_log_lines() -> u64 {
+    DROPPED_LINES.load(Ordering::Relaxed)
+}
+
+/// 按时间滚动的周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationInterval {
+    Never,
+    Hourly,
+    Daily,
+}
+
+impl RotationInterval {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "never" | "" => Some(Self::Never),
+            "hourly" => Some(Self::Hourly),
+            "daily" => Some(Self::Daily),
+            _ => None,
+        }
+    }
+
+    /// 时间所属的滚动周期，周期变化即触发滚动
+    fn period_key(self, now: &DateTime<Utc>) -> Option<String> {
+        match self {
+            Self::Never => None,
+            Self::Hourly => Some(now.format("%Y-%m-%d %H").to_string()),
+            Self::Daily => Some(now.format("%Y-%m-%d").to_string()),
+        }
+    }
+}
+
+/// 滚动文件配置
+#[derive(Debug, Clone)]
+pub struct FileSinkConfig {
+    pub path: PathBuf,
+    pub max_size_bytes: u64,
+    pub rotation: RotationInterval,
+    pub retention: usize,
+}
+
+impl FileSinkConfig {
+    /// 从环境变量读取配置，未设置 `LOG_FILE_PATH` 时返回 `None`
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let path = env::var("LOG_FILE_PATH")
+            .ok()
+            .filter(|path| !path.trim().is_empty())?;
+        let max_size_mb = env::var("LOG_FILE_MAX_SIZE_MB")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|size| *size > 0)
+            .unwrap_or(100);
+        let rotation = env::var("LOG_FILE_ROTATION")
+            .ok()
+            .and_then(|value| RotationInterval::parse(&value))
+            .unwrap_or(RotationInterval::Never);
+        let retention = env::var("LOG_FILE_RETENTION")
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(7);
+        Some(Self {
+            path: PathBuf::from(path),
+            max_size_bytes: max_size_mb.saturating_mul(1024 * 1024),
+            rotation,
+            retention: retention.max(1),
+        })
+    }
+}
+
+/// 同步滚动写入器，由写盘线程独占
+pub struct RotatingFileWriter {
+    config: FileSinkConfig,
+    file: File,
+    written: u64,
+    period: Option<String>,
+}
+
+impl RotatingFileWriter {
+    /// 打开（必要时创建）日志文件，追加写入
+    pub fn open(config: FileSinkConfig) -> io::Result<Self> {
+        if let Some(parent) = config.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let file = open_append(&config.path)?;
+        let written = file.metadata()?.len();
+        let period = config.rotation.period_key(&Utc::now());
+        Ok(Self {
+            config,
+            file,
+            written,
+            period,
+        })
+    }
+
+    /// 写入一条日志；超过大小上限或进入新的时间周期时先滚动
+    pub fn write_record(&mut self, record: &[u8]) -> io::Result<()> {
+        self.write_record_at(record, &Utc::now())
+    }
+
+    fn write_record_at(&mut self, record: &[u8], now: &DateTime<Utc>) -> io::Result<()> {
+        let record_len = u64::try_from(record.len()).unwrap_or(u64::MAX);
+        let period = self.config.rotation.period_key(now);
+        let over_size = self.written.saturating_add(record_len) > self.config.max_size_bytes;
+        if self.written > 0 && (over_size || period != self.period) {
+            self.rotate()?;
+        }
+        self.period = period;
+        self.file.write_all(record)?;
+        self.written = self.written.saturating_add(record_len);
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    /// 当前文件依次后移为 `.1`、`.2` …，超出保留个数的最旧文件被删除
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let retention = self.config.retention;
+        remove_if_exists(&rotated_path(&self.config.path, retention))?;
+        for index in (1..retention).rev() {
+            let from = rotated_path(&self.config.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.config.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.config.path, rotated_path(&self.config.path, 1))?;
+        self.file = open_append(&self.config.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// 第 `index` 个历史文件的路径
+#[must_use]
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = OsString::from(path.as_os_str());
+    rotated.push(format!(".{index}"));
+    PathBuf::from(rotated)
+}
+
+enum SinkMessage {
+    Record(Vec<u8>),
+    Shutdown,
+}
+
+/// 非阻塞文件日志：写入方只把日志放入队列，由后台线程写盘
+pub struct FileSink {
+    sender: SyncSender<SinkMessage>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl FileSink {
+    /// 打开日志文件并启动写盘线程
+    pub fn start(config: FileSinkConfig) -> io::Result<Self> {
+        let writer = RotatingFileWriter::open(config)?;
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let worker = std::thread::Builder::new()
+            .name("log-file-sink".to_string())
+            .spawn(move || run_worker(writer, &receiver))?;
+        Ok(Self {
+            sender,
+            worker: Some(worker),
+        })
+    }
+
+    /// 供 `tracing_subscriber::fmt` 使用的写入器
+    #[must_use]
+    pub fn make_writer(&self) -> FileSinkWriter {
+        FileSinkWriter {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl Drop for FileSink {
+    /// 写完队列中已有的日志后停止写盘线程
+    fn drop(&mut self) {
+        let _ = self.sender.send(SinkMessage::Shutdown);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run_worker(mut writer: RotatingFileWriter, receiver: &Receiver<SinkMessage>) {
+    let mut last_warned: Option<Instant> = None;
+    // 收到停止消息或所有写入端都已释放时退出
+    while let Ok(SinkMessage::Record(record)) = receiver.recv() {
+        if let Err(err) = writer.write_record(&record) {
+            let dropped = DROPPED_LINES.fetch_add(1, Ordering::Relaxed) + 1;
+            if last_warned.is_none_or(|at| at.elapsed() >= WRITE_ERROR_WARN_INTERVAL) {
+                last_warned = Some(Instant::now());
+                lwarn!(
+                    "system",
+                    LogStage::Internal,
+                    LogComponent::Main,
+                    "log_file_write_failed",
+                    "文件日志写入失败，日志已丢弃",
+                    error = %err,
+                    dropped_lines = dropped
+                );
+            }
+        }
+    }
+    let _ = writer.flush();
+}
+
+/// 单条日志的写入端，满队列时丢弃而不是等待
+#[derive(Clone)]
+pub struct FileSinkWriter {
+    sender: SyncSender<SinkMessage>,
+}
+
+impl Write for FileSinkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.sender.try_send(SinkMessage::Record(buf.to_vec())) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+            Err(TrySendError::Full(_)) => {
+                DROPPED_LINES.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for FileSinkWriter {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_log_path(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        env::temp_dir()
+            .join(format!("api-proxy-log-{}-{nanos}", std::process::id()))
+            .join(name)
+    }
+
+    fn config(path: &Path, max_size_bytes: u64, retention: usize) -> FileSinkConfig {
+        FileSinkConfig {
+            path: path.to_path_buf(),
+            max_size_bytes,
+            rotation: RotationInterval::Never,
+            retention,
+        }
+    }
+
+    #[test]
+    fn rotates_past_size_threshold_and_keeps_retention() {
+        let path = temp_log_path("proxy.log");
+        let mut writer = RotatingFileWriter::open(config(&path, 20, 2)).unwrap();
+
+        for line in [
+            "line-0000000001\n",
+            "line-0000000002\n",
+            "line-0000000003\n",
+        ] {
+            writer.write_record(line.as_bytes()).unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line-0000000003\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "line-0000000002\n"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "line-0000000001\n"
+        );
+
+        // 超过保留个数时删除最旧的文件
+        writer.write_record(b"line-0000000004\n").unwrap();
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "line-0000000002\n"
+        );
+        assert!(!rotated_path(&path, 3).exists());
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn rotates_when_time_period_changes() {
+        let path = temp_log_path("proxy.log");
+        let mut writer = RotatingFileWriter::open(FileSinkConfig {
+            rotation: RotationInterval::Daily,
+            ..config(&path, 1024, 3)
+        })
+        .unwrap();
+
+        let day_one = Utc.with_ymd_and_hms(2025, 11, 18, 23, 59, 0).unwrap();
+        let day_two = Utc.with_ymd_and_hms(2025, 11, 19, 0, 1, 0).unwrap();
+        writer.write_record_at(b"first\n", &day_one).unwrap();
+        writer.write_record_at(b"second\n", &day_one).unwrap();
+        writer.write_record_at(b"third\n", &day_two).unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "first\nsecond\n"
+        );
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn sink_writes_queued_records_in_background() {
+        let path = temp_log_path("proxy.log");
+        let sink = FileSink::start(config(&path, 1024 * 1024, 1)).unwrap();
+        let mut writer = sink.make_writer();
+        writer.write_all(b"structured event\n").unwrap();
+        writer.write_all(b"captured body\n").unwrap();
+        drop(writer);
+        drop(sink);
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "structured event\ncaptured body\n"
+        );
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
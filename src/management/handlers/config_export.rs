@@ -0,0 +1,72 @@
+//! # 配置导出/导入处理器
+
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Extension, Query, State},
+};
+use serde_json::Value;
+
+use crate::{
+    logging::{LogComponent, LogStage, log_management_error},
+    management::{
+        middleware::{RequestId, auth::AuthContext},
+        response,
+        server::ManagementState,
+        services::{ConfigBundle, ConfigExportService, ExportQuery},
+    },
+};
+
+/// 导出当前用户配置
+pub async fn export_config(
+    State(state): State<ManagementState>,
+    Query(query): Query<ExportQuery>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+) -> axum::response::Response {
+    let service = ConfigExportService::new(state.database());
+    match service.export(auth_context.user_id, &query).await {
+        Ok(bundle) => response::success(bundle),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::Database,
+                "export_config_failed",
+                "导出用户配置失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 导入配置到当前用户
+pub async fn import_config(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Json(payload): Json<Value>,
+) -> axum::response::Response {
+    let bundle = match ConfigBundle::from_value(payload) {
+        Ok(bundle) => bundle,
+        Err(err) => return response::app_error(err),
+    };
+
+    let service = ConfigExportService::new(state.database());
+    match service.import(auth_context.user_id, &bundle).await {
+        Ok(report) => response::success_with_message(report, "配置导入成功"),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::Database,
+                "import_config_failed",
+                "导入用户配置失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
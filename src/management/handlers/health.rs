@@ -1,9 +1,35 @@
 //! API密钥健康检查相关处理器
 
 use crate::logging::{LogComponent, LogStage, log_management_error};
-use crate::management::middleware::RequestId;
+use crate::management::middleware::{RequestId, auth::AuthContext};
+use crate::management::services::{PoolHealthService, PoolStatsQuery};
 use crate::management::{response, server::ManagementState};
-use axum::extract::{Extension, Path, State};
+use axum::extract::{Extension, Path, Query, State};
+use std::sync::Arc;
+
+/// 按服务商汇总密钥池状态
+pub async fn get_pool_stats(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Query(query): Query<PoolStatsQuery>,
+) -> axum::response::Response {
+    let service = PoolHealthService::new(state.database.clone());
+    match service.pool_stats(auth_context.as_ref(), &query).await {
+        Ok(pools) => response::success(pools),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::HealthCheck,
+                LogComponent::HealthChecker,
+                "get_pool_stats_fail",
+                "获取密钥池状态汇总失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
 
 /// 标记API密钥为不健康
 pub async fn mark_key_unhealthy(
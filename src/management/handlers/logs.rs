@@ -8,14 +8,23 @@ use crate::{
         middleware::{RequestId, auth::AuthContext},
         response::{self, ApiResponse},
         server::ManagementState,
-        services::logs::{LogsAnalyticsQuery, LogsListQuery, LogsService},
+        services::{
+            TraceReplayRequest, TraceReplayService,
+            logs::{LogsAnalyticsQuery, LogsListQuery, LogsService, LogsStreamQuery},
+        },
     },
+    trace::LiveTraceEvent,
     types::TimezoneContext,
 };
 use axum::{
+    Json,
     extract::{Extension, Path, Query, State},
-    response::IntoResponse,
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
+use futures::StreamExt;
 use std::sync::Arc;
 
 /// 获取日志仪表板统计数据
@@ -69,6 +78,23 @@ pub async fn get_traces_list(
     }
 }
 
+/// 实时推送追踪摘要（SSE）
+///
+/// 默认只推送已完成的请求，`include_in_progress=true` 时同时推送请求开始事件。
+pub async fn stream_live_traces(
+    State(state): State<ManagementState>,
+    Query(query): Query<LogsStreamQuery>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+) -> impl IntoResponse {
+    let filter = LogsService::live_trace_filter(auth_context.as_ref(), &query);
+    let stream = state
+        .live_traces()
+        .subscribe(filter)
+        .map(|event: LiveTraceEvent| Event::default().event("trace").json_data(event));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
 /// 获取日志详情
 pub async fn get_trace_detail(
     State(state): State<ManagementState>,
@@ -98,6 +124,36 @@ pub async fn get_trace_detail(
     }
 }
 
+/// 回放追踪记录对应的请求
+///
+/// 使用采集的请求体直连上游，结果标记为回放，不计入原用户的用量与额度。
+pub async fn replay_trace(
+    State(state): State<ManagementState>,
+    Path(trace_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Json(request): Json<TraceReplayRequest>,
+) -> impl IntoResponse {
+    let service = TraceReplayService::new(state.database(), reqwest::Client::new());
+    match service
+        .replay(auth_context.as_ref(), trace_id, &request)
+        .await
+    {
+        Ok(result) => response::success(result),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::ExternalApi,
+                LogComponent::Tracing,
+                "replay_trace_fail",
+                "回放追踪请求失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
 /// 获取日志统计分析
 pub async fn get_logs_analytics(
     State(state): State<ManagementState>,
@@ -4,13 +4,17 @@
 
 // pub mod adapters; // temporarily disabled: module file missing
 pub mod auth;
+pub mod config_export;
 pub mod health;
 pub mod logs;
 // pub mod oauth; // deprecated: replaced by oauth_v2
 pub mod oauth_v2;
+pub mod pricing;
+pub mod provider_blocks;
 pub mod provider_keys;
 pub mod provider_types;
 pub mod service_apis;
+pub mod shadow;
 pub mod statistics;
 pub mod stats_public;
 pub mod system;
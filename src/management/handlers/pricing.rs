@@ -0,0 +1,238 @@
+//! # 模型定价管理处理器
+
+use crate::logging::{LogComponent, LogStage, log_management_error};
+use crate::management::middleware::{RequestId, auth::AuthContext};
+use crate::management::services::{
+    CreateModelPricingRequest, CreatePricingTierRequest, ModelPricingQuery, PricingService,
+    PricingTierInput, UpdateModelPricingRequest,
+};
+use crate::management::{response, server::ManagementState};
+use crate::types::TimezoneContext;
+use axum::extract::{Extension, Path, Query, State};
+use axum::response::Json;
+use serde_json::json;
+use std::sync::Arc;
+
+/// 获取模型定价列表
+pub async fn list_model_pricing(
+    State(state): State<ManagementState>,
+    Query(query): Query<ModelPricingQuery>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+) -> axum::response::Response {
+    let service = PricingService::new(state.database());
+    match service.list(&query, timezone_context.timezone).await {
+        Ok(list) => response::success(json!({ "model_pricing": list })),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "list_model_pricing_failed",
+                "获取模型定价列表失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 获取单个模型定价
+pub async fn get_model_pricing(
+    State(state): State<ManagementState>,
+    Path(id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+) -> axum::response::Response {
+    let service = PricingService::new(state.database());
+    match service.get(id, timezone_context.timezone).await {
+        Ok(item) => response::success(json!({ "model_pricing": item })),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "get_model_pricing_failed",
+                "获取模型定价失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 创建模型定价
+pub async fn create_model_pricing(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Json(request): Json<CreateModelPricingRequest>,
+) -> axum::response::Response {
+    let service = PricingService::new(state.database());
+    match service
+        .create(auth_context.as_ref(), &request, timezone_context.timezone)
+        .await
+    {
+        Ok(item) => response::success_with_message(json!({ "model_pricing": item }), "创建成功"),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "create_model_pricing_failed",
+                "创建模型定价失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 更新模型定价
+pub async fn update_model_pricing(
+    State(state): State<ManagementState>,
+    Path(id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Json(request): Json<UpdateModelPricingRequest>,
+) -> axum::response::Response {
+    let service = PricingService::new(state.database());
+    match service
+        .update(
+            auth_context.as_ref(),
+            id,
+            &request,
+            timezone_context.timezone,
+        )
+        .await
+    {
+        Ok(item) => response::success_with_message(json!({ "model_pricing": item }), "更新成功"),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "update_model_pricing_failed",
+                "更新模型定价失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 删除模型定价
+pub async fn delete_model_pricing(
+    State(state): State<ManagementState>,
+    Path(id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+) -> axum::response::Response {
+    let service = PricingService::new(state.database());
+    match service.delete(auth_context.as_ref(), id).await {
+        Ok(()) => response::success(json!({ "deleted": true })),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "delete_model_pricing_failed",
+                "删除模型定价失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 新增定价阶梯
+pub async fn create_pricing_tier(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Json(request): Json<CreatePricingTierRequest>,
+) -> axum::response::Response {
+    let service = PricingService::new(state.database());
+    match service
+        .create_tier(auth_context.as_ref(), &request, timezone_context.timezone)
+        .await
+    {
+        Ok(item) => response::success_with_message(json!({ "model_pricing": item }), "创建成功"),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "create_pricing_tier_failed",
+                "新增定价阶梯失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 修改定价阶梯
+pub async fn update_pricing_tier(
+    State(state): State<ManagementState>,
+    Path(id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Json(request): Json<PricingTierInput>,
+) -> axum::response::Response {
+    let service = PricingService::new(state.database());
+    match service
+        .update_tier(
+            auth_context.as_ref(),
+            id,
+            &request,
+            timezone_context.timezone,
+        )
+        .await
+    {
+        Ok(item) => response::success_with_message(json!({ "model_pricing": item }), "更新成功"),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "update_pricing_tier_failed",
+                "修改定价阶梯失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 删除定价阶梯
+pub async fn delete_pricing_tier(
+    State(state): State<ManagementState>,
+    Path(id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+) -> axum::response::Response {
+    let service = PricingService::new(state.database());
+    match service
+        .delete_tier(auth_context.as_ref(), id, timezone_context.timezone)
+        .await
+    {
+        Ok(item) => response::success_with_message(json!({ "model_pricing": item }), "删除成功"),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "delete_pricing_tier_failed",
+                "删除定价阶梯失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
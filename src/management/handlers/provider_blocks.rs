@@ -0,0 +1,123 @@
+//! # 用户服务商封禁管理处理器
+
+use crate::logging::{LogComponent, LogStage, log_management_error};
+use crate::management::middleware::{RequestId, auth::AuthContext};
+use crate::management::services::{ProviderBlocksService, SetProviderBlocksRequest};
+use crate::management::{response, server::ManagementState};
+use axum::extract::{Extension, Path, State};
+use axum::response::Json;
+use serde_json::json;
+use std::sync::Arc;
+
+/// 获取用户被禁用的服务商
+pub async fn list_provider_blocks(
+    State(state): State<ManagementState>,
+    Path(user_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+) -> axum::response::Response {
+    let service = ProviderBlocksService::new(state.database());
+    match service.list(auth_context.as_ref(), user_id).await {
+        Ok(blocks) => response::success(json!({ "provider_blocks": blocks })),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "list_provider_blocks_failed",
+                "获取用户服务商封禁列表失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 设置用户被禁用的服务商（整体替换）
+pub async fn set_provider_blocks(
+    State(state): State<ManagementState>,
+    Path(user_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Json(request): Json<SetProviderBlocksRequest>,
+) -> axum::response::Response {
+    let service = ProviderBlocksService::new(state.database());
+    match service.set(auth_context.as_ref(), user_id, &request).await {
+        Ok(blocks) => {
+            response::success_with_message(json!({ "provider_blocks": blocks }), "设置成功")
+        }
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "set_provider_blocks_failed",
+                "设置用户服务商封禁失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 清除用户的全部服务商封禁
+pub async fn clear_provider_blocks(
+    State(state): State<ManagementState>,
+    Path(user_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+) -> axum::response::Response {
+    let service = ProviderBlocksService::new(state.database());
+    match service
+        .set(
+            auth_context.as_ref(),
+            user_id,
+            &SetProviderBlocksRequest::default(),
+        )
+        .await
+    {
+        Ok(blocks) => {
+            response::success_with_message(json!({ "provider_blocks": blocks }), "清除成功")
+        }
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "clear_provider_blocks_failed",
+                "清除用户服务商封禁失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 解除用户对单个服务商的封禁
+pub async fn remove_provider_block(
+    State(state): State<ManagementState>,
+    Path((user_id, provider_type_id)): Path<(i32, i32)>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+) -> axum::response::Response {
+    let service = ProviderBlocksService::new(state.database());
+    match service
+        .remove(auth_context.as_ref(), user_id, provider_type_id)
+        .await
+    {
+        Ok(blocks) => {
+            response::success_with_message(json!({ "provider_blocks": blocks }), "解除成功")
+        }
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Config,
+                "remove_provider_block_failed",
+                "解除用户服务商封禁失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
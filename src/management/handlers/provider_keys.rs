@@ -5,14 +5,17 @@
 use std::sync::Arc;
 
 use axum::extract::{Extension, Path, Query, State};
+use axum::http::{HeaderMap, header};
 use axum::response::{IntoResponse, Json};
 
 use crate::key_pool::types::ApiKeyHealthStatus;
 use crate::logging::{LogComponent, LogStage, log_management_error};
-use crate::management::middleware::{RequestId, auth::AuthContext};
+use crate::management::middleware::{ApiResource, ApiVersion, RequestId, auth::AuthContext};
+use crate::management::services::provider_keys::{BulkImportFormat, parse_bulk_import};
 use crate::management::services::{
-    CreateProviderKeyRequest, ProviderKeyService, ProviderKeysListQuery, ServiceResponse,
-    TrendQuery, UpdateProviderKeyRequest, UserProviderKeyQuery,
+    CreateProviderKeyRequest, PatchProviderKeyRequest, ProviderKeyService, ProviderKeysListQuery,
+    ServiceResponse, SetProviderKeyTagsRequest, TrendQuery, UpdateProviderKeyRequest,
+    UserProviderKeyQuery,
 };
 use crate::management::{response, server::ManagementState};
 use crate::types::TimezoneContext;
@@ -24,13 +27,16 @@ pub async fn get_provider_keys_list(
     Extension(request_id): Extension<RequestId>,
     Extension(auth_context): Extension<Arc<AuthContext>>,
     Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Extension(api_version): Extension<ApiVersion>,
 ) -> axum::response::Response {
     let service = ProviderKeyService::new(&state);
     match service
         .list(auth_context.user_id, &timezone_context, &query)
         .await
     {
-        Ok(ServiceResponse { data, .. }) => response::success(data),
+        Ok(ServiceResponse { data, .. }) => {
+            response::success(api_version.render(ApiResource::ProviderKeyList, &data))
+        }
         Err(err) => {
             log_management_error(
                 &request_id,
@@ -76,6 +82,47 @@ pub async fn create_provider_key(
     }
 }
 
+/// 批量导入提供商密钥
+///
+/// 请求体为 JSON 数组，或 `Content-Type: text/csv` 的带表头 CSV 文本；返回逐行结果报告。
+pub async fn bulk_import_provider_keys(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    headers: HeaderMap,
+    body: String,
+) -> axum::response::Response {
+    let format = BulkImportFormat::from_content_type(
+        headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok()),
+    );
+    let rows = match parse_bulk_import(&body, format) {
+        Ok(rows) => rows,
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::KeyPool,
+                "bulk_import_provider_keys_failed",
+                "解析批量导入内容失败",
+                &err,
+            );
+            return response::app_error(err);
+        }
+    };
+
+    let report = ProviderKeyService::new(&state)
+        .bulk_import(auth_context.user_id, &timezone_context, rows)
+        .await;
+    let msg = format!(
+        "导入完成：成功 {}，重复 {}，失败 {}",
+        report.created, report.duplicates, report.failed
+    );
+    response::success_with_message(report, &msg)
+}
+
 /// 获取提供商密钥详情
 pub async fn get_provider_key_detail(
     State(state): State<ManagementState>,
@@ -83,13 +130,16 @@ pub async fn get_provider_key_detail(
     Path(key_id): Path<i32>,
     Extension(request_id): Extension<RequestId>,
     Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(api_version): Extension<ApiVersion>,
 ) -> axum::response::Response {
     let service = ProviderKeyService::new(&state);
     match service
         .detail(auth_context.user_id, &timezone_context, key_id)
         .await
     {
-        Ok(ServiceResponse { data, .. }) => response::success(data),
+        Ok(ServiceResponse { data, .. }) => {
+            response::success(api_version.render(ApiResource::ProviderKeyDetail, &data))
+        }
         Err(err) => {
             log_management_error(
                 &request_id,
@@ -136,6 +186,70 @@ pub async fn update_provider_key(
     }
 }
 
+/// 部分更新提供商密钥
+pub async fn patch_provider_key(
+    State(state): State<ManagementState>,
+    Path(key_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Json(payload): Json<PatchProviderKeyRequest>,
+) -> axum::response::Response {
+    let service = ProviderKeyService::new(&state);
+    match service
+        .patch(key_id, auth_context.user_id, &timezone_context, &payload)
+        .await
+    {
+        Ok(ServiceResponse { data, message }) => {
+            let msg = message.unwrap_or_else(|| "更新成功".to_string());
+            response::success_with_message(data, &msg)
+        }
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::KeyPool,
+                "patch_provider_key_failed",
+                "部分更新提供商密钥失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 设置提供商密钥标签
+pub async fn set_provider_key_tags(
+    State(state): State<ManagementState>,
+    Path(key_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Json(payload): Json<SetProviderKeyTagsRequest>,
+) -> axum::response::Response {
+    let service = ProviderKeyService::new(&state);
+    match service
+        .set_tags(key_id, auth_context.user_id, &timezone_context, &payload)
+        .await
+    {
+        Ok(ServiceResponse { data, message }) => {
+            let msg = message.unwrap_or_else(|| "更新成功".to_string());
+            response::success_with_message(data, &msg)
+        }
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::KeyPool,
+                "set_provider_key_tags_failed",
+                "设置提供商密钥标签失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
 /// 删除提供商密钥
 pub async fn delete_provider_key(
     State(state): State<ManagementState>,
@@ -167,6 +281,37 @@ pub async fn delete_provider_key(
     }
 }
 
+/// 恢复已删除的提供商密钥
+pub async fn restore_provider_key(
+    State(state): State<ManagementState>,
+    Path(key_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+) -> axum::response::Response {
+    let service = ProviderKeyService::new(&state);
+    match service
+        .restore(auth_context.user_id, &timezone_context, key_id)
+        .await
+    {
+        Ok(ServiceResponse { data, message }) => {
+            let msg = message.unwrap_or_else(|| "恢复成功".to_string());
+            response::success_with_message(data, &msg)
+        }
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::KeyPool,
+                "restore_provider_key_failed",
+                "恢复提供商密钥失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
 /// 获取密钥统计信息
 pub async fn get_provider_key_stats(
     State(state): State<ManagementState>,
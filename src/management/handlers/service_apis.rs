@@ -13,19 +13,45 @@ use axum::{
 use serde_json::Value;
 
 use crate::{
+    cache::InvalidationEvent,
     logging::{LogComponent, LogStage, log_management_error},
+    lwarn,
     management::{
-        middleware::{RequestId, auth::AuthContext},
+        middleware::{ApiResource, ApiVersion, RequestId, auth::AuthContext},
         response,
         server::ManagementState,
         services::service_apis::{
-            CreateUserServiceKeyRequest, ServiceApiService, UpdateStatusRequest,
-            UpdateUserServiceKeyRequest, UsageStatsQuery, UserServiceKeyQuery,
+            CreateUserServiceKeyRequest, RotateUserServiceKeyRequest, ServiceApiService,
+            UpdateStatusRequest, UpdateUserServiceKeyRequest, UsageStatsQuery, UserServiceKeyQuery,
         },
     },
     types::TimezoneContext,
 };
 
+/// 服务 API 变更后通知所有实例丢弃相关缓存；广播失败只记录日志，不影响本次变更结果
+async fn invalidate_service_api(
+    state: &ManagementState,
+    request_id: &RequestId,
+    user_id: i32,
+    service_api_id: i32,
+) {
+    let event = InvalidationEvent::ServiceApi {
+        user_id,
+        service_api_id,
+    };
+    if let Err(err) = state.cache_invalidator().publish(event).await {
+        lwarn!(
+            request_id,
+            LogStage::Cache,
+            LogComponent::Cache,
+            "service_api_invalidation_failed",
+            "广播服务 API 缓存失效事件失败",
+            service_api_id = service_api_id,
+            error = %err
+        );
+    }
+}
+
 /// 1. 用户 API Keys 卡片展示
 pub async fn get_user_service_cards(
     State(state): State<ManagementState>,
@@ -56,13 +82,14 @@ pub async fn list_user_service_keys(
     Extension(request_id): Extension<RequestId>,
     Extension(auth_context): Extension<Arc<AuthContext>>,
     Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Extension(api_version): Extension<ApiVersion>,
 ) -> axum::response::Response {
     let service = ServiceApiService::new(&state);
     match service
         .list(auth_context.user_id, &query, &timezone_context.timezone)
         .await
     {
-        Ok(payload) => response::success(payload),
+        Ok(payload) => response::success(api_version.render(ApiResource::ServiceApiList, &payload)),
         Err(err) => {
             log_management_error(
                 &request_id,
@@ -112,13 +139,14 @@ pub async fn get_user_service_key(
     Extension(request_id): Extension<RequestId>,
     Extension(auth_context): Extension<Arc<AuthContext>>,
     Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+    Extension(api_version): Extension<ApiVersion>,
 ) -> axum::response::Response {
     let service = ServiceApiService::new(&state);
     match service
         .detail(api_id, auth_context.user_id, &timezone_context.timezone)
         .await
     {
-        Ok(detail) => response::success(detail),
+        Ok(detail) => response::success(api_version.render(ApiResource::ServiceApiDetail, &detail)),
         Err(err) => {
             log_management_error(
                 &request_id,
@@ -133,6 +161,34 @@ pub async fn get_user_service_key(
     }
 }
 
+/// 4.1 预览 API Key 的生效配置（标注每个值的来源）
+pub async fn get_user_service_key_effective_config(
+    State(state): State<ManagementState>,
+    Path(api_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+) -> axum::response::Response {
+    let service = ServiceApiService::new(&state);
+    let config = state.context_arc().config();
+    match service
+        .effective_config(api_id, auth_context.user_id, &config)
+        .await
+    {
+        Ok(effective) => response::success(effective),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::ApiKey,
+                "get_user_service_key_effective_config_failed",
+                "获取用户 API Key 生效配置失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
 /// 5. 编辑 API Key
 pub async fn update_user_service_key(
     State(state): State<ManagementState>,
@@ -143,7 +199,10 @@ pub async fn update_user_service_key(
 ) -> axum::response::Response {
     let service = ServiceApiService::new(&state);
     match service.update(api_id, auth_context.user_id, &request).await {
-        Ok(result) => response::success_with_message(result, "API Key更新成功"),
+        Ok(result) => {
+            invalidate_service_api(&state, &request_id, auth_context.user_id, api_id).await;
+            response::success_with_message(result, "API Key更新成功")
+        }
         Err(err) => {
             log_management_error(
                 &request_id,
@@ -167,7 +226,10 @@ pub async fn delete_user_service_key(
 ) -> axum::response::Response {
     let service = ServiceApiService::new(&state);
     match service.delete(api_id, auth_context.user_id).await {
-        Ok(()) => response::success_with_message(Value::Null, "API Key删除成功"),
+        Ok(()) => {
+            invalidate_service_api(&state, &request_id, auth_context.user_id, api_id).await;
+            response::success_with_message(Value::Null, "API Key删除成功")
+        }
         Err(err) => {
             log_management_error(
                 &request_id,
@@ -216,6 +278,40 @@ pub async fn get_user_service_key_usage(
     }
 }
 
+/// 7.1 API Key 响应时间目标达成统计
+pub async fn get_user_service_key_sla(
+    State(state): State<ManagementState>,
+    Path(api_id): Path<i32>,
+    Query(query): Query<UsageStatsQuery>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+) -> axum::response::Response {
+    let service = ServiceApiService::new(&state);
+    match service
+        .sla_stats(
+            api_id,
+            auth_context.user_id,
+            &query,
+            &timezone_context.timezone,
+        )
+        .await
+    {
+        Ok(summary) => response::success(summary),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::ApiKey,
+                "get_user_service_key_sla_failed",
+                "获取用户 API Key 响应时间目标统计失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
 /// 8. 重新生成 API Key
 pub async fn regenerate_user_service_key(
     State(state): State<ManagementState>,
@@ -225,7 +321,10 @@ pub async fn regenerate_user_service_key(
 ) -> axum::response::Response {
     let service = ServiceApiService::new(&state);
     match service.regenerate(api_id, auth_context.user_id).await {
-        Ok(result) => response::success_with_message(result, "API Key重新生成成功"),
+        Ok(result) => {
+            invalidate_service_api(&state, &request_id, auth_context.user_id, api_id).await;
+            response::success_with_message(result, "API Key重新生成成功")
+        }
         Err(err) => {
             log_management_error(
                 &request_id,
@@ -240,6 +339,34 @@ pub async fn regenerate_user_service_key(
     }
 }
 
+/// 8.1 轮换 API Key（可保留旧 Key 宽限期）
+pub async fn rotate_user_service_key(
+    State(state): State<ManagementState>,
+    Path(api_id): Path<i32>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Json(request): Json<RotateUserServiceKeyRequest>,
+) -> axum::response::Response {
+    let service = ServiceApiService::new(&state);
+    match service.rotate(api_id, auth_context.user_id, &request).await {
+        Ok(result) => {
+            invalidate_service_api(&state, &request_id, auth_context.user_id, api_id).await;
+            response::success_with_message(result, "API Key轮换成功")
+        }
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::ApiKey,
+                "rotate_user_service_key_failed",
+                "轮换用户 API Key 失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
 /// 9. 启用/禁用 API Key
 pub async fn update_user_service_key_status(
     State(state): State<ManagementState>,
@@ -253,7 +380,10 @@ pub async fn update_user_service_key_status(
         .update_status(api_id, auth_context.user_id, &request)
         .await
     {
-        Ok(result) => response::success_with_message(result, "API Key状态更新成功"),
+        Ok(result) => {
+            invalidate_service_api(&state, &request_id, auth_context.user_id, api_id).await;
+            response::success_with_message(result, "API Key状态更新成功")
+        }
         Err(err) => {
             log_management_error(
                 &request_id,
@@ -0,0 +1,32 @@
+//! # 影子路由差异分析处理器
+
+use crate::logging::{LogComponent, LogStage, log_management_error};
+use crate::management::middleware::{RequestId, auth::AuthContext};
+use crate::management::services::{ShadowDiffQuery, ShadowDiffService};
+use crate::management::{response, server::ManagementState};
+use axum::extract::{Extension, Query, State};
+use std::sync::Arc;
+
+/// 汇总时间范围内主请求与影子请求的差异（仅管理员）
+pub async fn get_shadow_diffs(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Query(query): Query<ShadowDiffQuery>,
+) -> axum::response::Response {
+    let service = ShadowDiffService::new(state.database());
+    match service.diffs(auth_context.as_ref(), &query).await {
+        Ok(diffs) => response::success(diffs),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Tracing,
+                "get_shadow_diffs_failed",
+                "查询影子路由差异失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
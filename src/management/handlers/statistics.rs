@@ -42,6 +42,34 @@ pub async fn get_today_dashboard_cards(
     }
 }
 
+/// 按模型拆分用量 API: /api/statistics/models
+pub async fn get_models_breakdown(
+    State(state): State<ManagementState>,
+    Query(query): Query<TimeRangeQuery>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Extension(timezone_context): Extension<Arc<TimezoneContext>>,
+) -> axum::response::Response {
+    let service = StatisticsService::new(&state);
+    match service
+        .models_breakdown(auth_context.user_id, &query, &timezone_context)
+        .await
+    {
+        Ok(data) => response::success(data),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Db,
+                LogComponent::Database,
+                "fetch_models_breakdown_fail",
+                "获取按模型拆分的用量失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
 /// 模型使用占比 API: /api/statistics/models/rate
 pub async fn get_models_usage_rate(
     State(state): State<ManagementState>,
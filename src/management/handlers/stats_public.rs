@@ -13,7 +13,6 @@ use axum::{
 use chrono::{DateTime, Duration, Utc};
 use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
 use crate::{
     error::{ProxyError, Result},
@@ -29,6 +28,7 @@ use crate::{
         },
     },
     types::{TimezoneContext, timezone_utils},
+    utils::request_id,
 };
 
 /// 默认分页页码
@@ -140,7 +140,7 @@ pub async fn get_stats_overview(
     Extension(timezone_ctx): Extension<Arc<TimezoneContext>>,
     Query(query): Query<OverviewQuery>,
 ) -> Response {
-    let request_id = Uuid::new_v4().to_string();
+    let request_id = request_id::generate();
     let timezone = timezone_ctx.timezone;
     let service = StatsService::new(state.database.as_ref());
 
@@ -201,7 +201,7 @@ pub async fn get_stats_trend(
     Extension(timezone_ctx): Extension<Arc<TimezoneContext>>,
     Query(query): Query<TrendQuery>,
 ) -> Response {
-    let request_id = Uuid::new_v4().to_string();
+    let request_id = request_id::generate();
     let timezone = timezone_ctx.timezone;
     let service = StatsService::new(state.database.as_ref());
 
@@ -256,7 +256,7 @@ pub async fn get_stats_model_share(
     Extension(timezone_ctx): Extension<Arc<TimezoneContext>>,
     Query(query): Query<ModelShareQuery>,
 ) -> Response {
-    let request_id = Uuid::new_v4().to_string();
+    let request_id = request_id::generate();
     let timezone = timezone_ctx.timezone;
     let service = StatsService::new(state.database.as_ref());
 
@@ -312,7 +312,7 @@ pub async fn get_stats_logs(
     Extension(timezone_ctx): Extension<Arc<TimezoneContext>>,
     Query(query): Query<LogsQuery>,
 ) -> Response {
-    let request_id = Uuid::new_v4().to_string();
+    let request_id = request_id::generate();
     let timezone = timezone_ctx.timezone;
     let service = StatsService::new(state.database.as_ref());
 
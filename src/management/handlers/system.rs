@@ -1,12 +1,16 @@
 //! # 系统信息处理器
 
+use crate::app::task_history::TaskRunHistoryQuery;
+use crate::config::HealthCheckConfig;
 use crate::logging::{LogComponent, LogStage, log_management_error};
-use crate::management::middleware::RequestId;
+use crate::management::middleware::{RequestId, auth::AuthContext};
 use crate::management::response;
 use crate::management::server::ManagementState;
-use crate::management::services::system;
+use crate::management::services::system::{self, SpendAnomalyQuery};
+use crate::proxy::maintenance::MaintenanceUpdate;
 use crate::types::TimezoneContext;
-use axum::extract::{Extension, State};
+use axum::Json;
+use axum::extract::{Extension, Path, Query, State};
 use std::sync::Arc;
 
 /// 初始化启动时间
@@ -41,6 +45,167 @@ pub async fn get_system_metrics(
     }
 }
 
+/// 获取后台任务心跳
+pub async fn get_system_tasks(State(state): State<ManagementState>) -> axum::response::Response {
+    response::success(state.scheduler().heartbeats().snapshot())
+}
+
+/// 查询后台任务执行历史
+pub async fn get_task_history(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Query(query): Query<TaskRunHistoryQuery>,
+) -> axum::response::Response {
+    match system::list_task_runs(&state, &query).await {
+        Ok(runs) => response::success(runs),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::Main,
+                "get_task_history_failed",
+                "查询后台任务执行历史失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 获取维护模式状态
+pub async fn get_maintenance(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+) -> axum::response::Response {
+    match state.maintenance().current().await {
+        Ok(maintenance) => response::success(maintenance),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::Main,
+                "get_maintenance_failed",
+                "获取维护模式状态失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 切换维护模式（仅管理员）
+pub async fn update_maintenance(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Json(update): Json<MaintenanceUpdate>,
+) -> axum::response::Response {
+    match system::update_maintenance(&state, auth_context.as_ref(), update).await {
+        Ok(maintenance) => response::success(maintenance),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::Main,
+                "update_maintenance_failed",
+                "切换维护模式失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 获取服务商健康检查配置
+pub async fn get_health_check(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+) -> axum::response::Response {
+    match system::health_check_config(&state).await {
+        Ok(config) => response::success(config),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::HealthChecker,
+                "get_health_check_failed",
+                "获取服务商健康检查配置失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 更新服务商健康检查配置（仅管理员）
+pub async fn update_health_check(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Json(config): Json<HealthCheckConfig>,
+) -> axum::response::Response {
+    match system::update_health_check(&state, auth_context.as_ref(), config).await {
+        Ok(config) => response::success(config),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::HealthChecker,
+                "update_health_check_failed",
+                "更新服务商健康检查配置失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 查询消费异常标记（仅管理员）
+pub async fn list_spend_anomalies(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Query(query): Query<SpendAnomalyQuery>,
+) -> axum::response::Response {
+    match system::list_spend_anomalies(&state, auth_context.as_ref(), &query).await {
+        Ok(flags) => response::success(flags),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::Main,
+                "list_spend_anomalies_failed",
+                "查询消费异常标记失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
+/// 确认消费异常标记（仅管理员）
+pub async fn acknowledge_spend_anomaly(
+    State(state): State<ManagementState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(auth_context): Extension<Arc<AuthContext>>,
+    Path(flag_id): Path<i32>,
+) -> axum::response::Response {
+    match system::acknowledge_spend_anomaly(&state, auth_context.as_ref(), flag_id).await {
+        Ok(flag) => response::success(flag),
+        Err(err) => {
+            log_management_error(
+                &request_id,
+                LogStage::Internal,
+                LogComponent::Main,
+                "acknowledge_spend_anomaly_failed",
+                "确认消费异常标记失败",
+                &err,
+            );
+            response::app_error(err)
+        }
+    }
+}
+
 /// 根路径处理器（管理API信息）
 pub async fn root_handler(
     Extension(timezone_context): Extension<Arc<TimezoneContext>>,
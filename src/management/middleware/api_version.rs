@@ -0,0 +1,284 @@
+//! # API 版本协商中间件
+//!
+//! 解析 `X-API-Version`（优先）或 `Accept-Version` 头，选择管理接口响应的 DTO 版本，
+//! 并通过 `X-API-Version` 响应头回显实际使用的版本。未指定时使用最新版本；
+//! 无法识别的版本直接返回 400，避免旧客户端静默拿到不兼容的结构。
+//!
+//! 版本化序列化只作用于响应：v1 只保留首个版本中已存在的字段，后续新增字段只在 v2 及以后返回。
+
+use axum::{
+    extract::Request,
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::management::response;
+
+/// 指定版本的请求头
+pub const API_VERSION_HEADER: &str = "X-API-Version";
+/// 兼容的版本请求头
+pub const ACCEPT_VERSION_HEADER: &str = "Accept-Version";
+
+/// 管理接口响应版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum ApiVersion {
+    V1,
+    #[default]
+    V2,
+}
+
+impl ApiVersion {
+    pub const LATEST: Self = Self::V2;
+
+    /// 解析版本号，接受 `2` / `v2` / `V2` 形式
+    #[must_use]
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let number = raw
+            .strip_prefix('v')
+            .or_else(|| raw.strip_prefix('V'))
+            .unwrap_or(raw);
+        match number {
+            "1" => Some(Self::V1),
+            "2" => Some(Self::V2),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::V1 => "1",
+            Self::V2 => "2",
+        }
+    }
+
+    /// 按版本序列化响应数据
+    #[must_use]
+    pub fn render<T: Serialize>(self, resource: ApiResource, data: &T) -> Value {
+        let mut value = serde_json::to_value(data).unwrap_or(Value::Null);
+        if self == Self::V1 {
+            match resource.list_field() {
+                Some(field) => {
+                    if let Some(items) = value.get_mut(field).and_then(Value::as_array_mut) {
+                        for item in items {
+                            retain_fields(item, resource.v1_fields());
+                        }
+                    }
+                }
+                None => retain_fields(&mut value, resource.v1_fields()),
+            }
+        }
+        value
+    }
+}
+
+/// 支持版本化序列化的响应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiResource {
+    ProviderKeyList,
+    ProviderKeyDetail,
+    ServiceApiList,
+    ServiceApiDetail,
+}
+
+/// v1 提供商密钥字段
+const PROVIDER_KEY_V1_FIELDS: &[&str] = &[
+    "id",
+    "provider",
+    "provider_type_id",
+    "name",
+    "api_key",
+    "auth_type",
+    "auth_status",
+    "expires_at",
+    "weight",
+    "max_requests_per_minute",
+    "max_tokens_prompt_per_minute",
+    "max_requests_per_day",
+    "is_active",
+    "usage",
+    "limits",
+    "status",
+    "created_at",
+    "updated_at",
+];
+
+/// v1 服务 API 列表项字段
+const SERVICE_API_V1_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "description",
+    "provider",
+    "provider_type_id",
+    "api_key",
+    "usage",
+    "is_active",
+    "last_used_at",
+    "created_at",
+    "expires_at",
+    "scheduling_strategy",
+    "retry_count",
+    "timeout_seconds",
+    "max_request_per_min",
+    "max_requests_per_day",
+    "max_tokens_per_day",
+    "max_cost_per_day",
+];
+
+/// v1 服务 API 详情字段
+const SERVICE_API_DETAIL_V1_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "description",
+    "provider_type_id",
+    "provider",
+    "api_key",
+    "user_provider_keys_ids",
+    "scheduling_strategy",
+    "retry_count",
+    "timeout_seconds",
+    "max_request_per_min",
+    "max_requests_per_day",
+    "max_tokens_per_day",
+    "max_cost_per_day",
+    "expires_at",
+    "is_active",
+    "created_at",
+    "updated_at",
+];
+
+impl ApiResource {
+    /// 列表响应中条目数组所在字段
+    const fn list_field(self) -> Option<&'static str> {
+        match self {
+            Self::ProviderKeyList => Some("provider_keys"),
+            Self::ServiceApiList => Some("service_api_keys"),
+            Self::ProviderKeyDetail | Self::ServiceApiDetail => None,
+        }
+    }
+
+    const fn v1_fields(self) -> &'static [&'static str] {
+        match self {
+            Self::ProviderKeyList | Self::ProviderKeyDetail => PROVIDER_KEY_V1_FIELDS,
+            Self::ServiceApiList => SERVICE_API_V1_FIELDS,
+            Self::ServiceApiDetail => SERVICE_API_DETAIL_V1_FIELDS,
+        }
+    }
+}
+
+fn retain_fields(value: &mut Value, fields: &[&str]) {
+    if let Value::Object(object) = value {
+        object.retain(|key, _| fields.contains(&key.as_str()));
+    }
+}
+
+/// 从请求头中解析版本：`X-API-Version` 优先，其次 `Accept-Version`；未指定或为空时取最新版本
+#[must_use]
+pub fn resolve_api_version(request: &Request) -> Option<ApiVersion> {
+    let Some(header) = [API_VERSION_HEADER, ACCEPT_VERSION_HEADER]
+        .into_iter()
+        .find_map(|name| request.headers().get(name))
+    else {
+        return Some(ApiVersion::LATEST);
+    };
+    let raw = header.to_str().ok()?.trim();
+    if raw.is_empty() {
+        return Some(ApiVersion::LATEST);
+    }
+    ApiVersion::parse(raw)
+}
+
+/// API 版本中间件
+pub async fn api_version_middleware(mut request: Request, next: Next) -> Response {
+    let Some(version) = resolve_api_version(&request) else {
+        return response::error(
+            StatusCode::BAD_REQUEST,
+            "UNSUPPORTED_API_VERSION",
+            "不支持的 API 版本，可选值：1、2",
+        );
+    };
+
+    request.extensions_mut().insert(version);
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(
+        API_VERSION_HEADER,
+        HeaderValue::from_static(version.as_str()),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request_with(headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder().uri("/api/provider-keys/keys");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(axum::body::Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn negotiates_version_from_headers() {
+        assert_eq!(
+            resolve_api_version(&request_with(&[])),
+            Some(ApiVersion::LATEST)
+        );
+        assert_eq!(
+            resolve_api_version(&request_with(&[("Accept-Version", "v1")])),
+            Some(ApiVersion::V1)
+        );
+        assert_eq!(
+            resolve_api_version(&request_with(&[
+                ("X-API-Version", "2"),
+                ("Accept-Version", "1")
+            ])),
+            Some(ApiVersion::V2)
+        );
+        assert_eq!(
+            resolve_api_version(&request_with(&[("X-API-Version", "3")])),
+            None
+        );
+    }
+
+    #[test]
+    fn v1_drops_fields_added_in_v2() {
+        let list = json!({
+            "service_api_keys": [{
+                "id": 1,
+                "name": "key",
+                "log_mode": true,
+                "max_cost_per_request": "0.5"
+            }],
+            "pagination": {"page": 1, "limit": 10, "total": 1, "pages": 1}
+        });
+
+        let v1 = ApiVersion::V1.render(ApiResource::ServiceApiList, &list);
+        assert_eq!(v1["service_api_keys"][0], json!({"id": 1, "name": "key"}));
+        assert_eq!(v1["pagination"], list["pagination"]);
+        assert_eq!(
+            ApiVersion::V2.render(ApiResource::ServiceApiList, &list),
+            list
+        );
+
+        let detail = json!({
+            "id": 7,
+            "health_status": "healthy",
+            "health_status_detail": null,
+            "project_id": "proj",
+            "status": {"health_status": "healthy"}
+        });
+        let v1 = ApiVersion::V1.render(ApiResource::ProviderKeyDetail, &detail);
+        assert_eq!(v1, json!({"id": 7, "status": {"health_status": "healthy"}}));
+        assert_eq!(
+            ApiVersion::V2.render(ApiResource::ProviderKeyDetail, &detail),
+            detail
+        );
+    }
+}
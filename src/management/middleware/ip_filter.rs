@@ -3,14 +3,19 @@
 //! 提供基于IP地址的访问控制功能
 
 use crate::{
-    error::{Result, config::ConfigError},
+    error::{
+        Result,
+        config::ConfigError,
+        reject::{REJECT_REASON_HEADER, RejectReason},
+    },
     ldebug,
     logging::{LogComponent, LogStage},
     lwarn,
+    management::response,
 };
 use axum::{
     extract::{ConnectInfo, Request},
-    http::StatusCode,
+    http::{HeaderValue, StatusCode},
     middleware::Next,
     response::Response,
 };
@@ -169,7 +174,7 @@ pub async fn ip_filter_middleware(
             "ip_denied",
             &format!("Access denied for IP: {client_ip}")
         );
-        return Err(StatusCode::FORBIDDEN);
+        return Ok(ip_denied_response());
     }
 
     ldebug!(
@@ -182,6 +187,21 @@ pub async fn ip_filter_middleware(
     Ok(next.run(request).await)
 }
 
+/// IP 被拒绝时的响应，携带 `ip_not_allowed` 原因码
+fn ip_denied_response() -> Response {
+    let reason = RejectReason::IpNotAllowed;
+    let mut response = response::error(
+        StatusCode::FORBIDDEN,
+        "IP_NOT_ALLOWED",
+        "当前 IP 不在允许访问的范围内",
+    );
+    response.headers_mut().insert(
+        REJECT_REASON_HEADER,
+        HeaderValue::from_static(reason.as_str()),
+    );
+    response
+}
+
 /// 获取真实客户端IP地址（考虑代理情况）
 pub fn get_real_client_ip(request: &Request) -> Option<IpAddr> {
     // 尝试从 X-Forwarded-For 头获取
@@ -233,4 +253,14 @@ mod tests {
         // 测试不在列表中的IP
         assert!(!config.is_allowed(Ipv4Addr::new(192, 168, 2, 1).into()));
     }
+
+    #[test]
+    fn denied_response_carries_reason_code() {
+        let response = ip_denied_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.headers().get(REJECT_REASON_HEADER).unwrap(),
+            "ip_not_allowed"
+        );
+    }
 }
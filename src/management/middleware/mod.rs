@@ -2,11 +2,13 @@
 //!
 //! 提供各种中间件功能
 
+pub mod api_version;
 pub mod auth;
 pub mod ip_filter;
 pub mod request_id;
 pub mod timezone;
 
+pub use api_version::{ApiResource, ApiVersion, api_version_middleware};
 pub use auth::{AuthContext, auth};
 pub use ip_filter::{IpFilterConfig, get_real_client_ip, ip_filter_middleware};
 pub use request_id::{RequestId, request_id_middleware};
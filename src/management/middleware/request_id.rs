@@ -1,12 +1,13 @@
 //! # Request ID 中间件
 //!
-//! 为每个请求生成唯一 `request_id`，并注入到请求扩展中。
+//! 为每个请求生成唯一 `request_id`（ULID，与代理端格式一致），并注入到请求扩展中。
 
 use axum::{extract::Request, middleware::Next, response::Response};
 use http::header::HeaderValue;
 use std::fmt;
 use std::ops::Deref;
-use uuid::Uuid;
+
+use crate::utils::request_id::{self, REQUEST_ID_HEADER};
 
 /// 请求ID类型
 #[derive(Debug, Clone)]
@@ -15,7 +16,7 @@ pub struct RequestId(String);
 impl RequestId {
     #[must_use]
     pub fn new() -> Self {
-        Self(Uuid::new_v4().to_string())
+        Self(request_id::generate())
     }
 
     #[must_use]
@@ -57,7 +58,7 @@ pub async fn request_id_middleware(mut request: Request, next: Next) -> Response
 
     let mut response = next.run(request).await;
     if let Ok(value) = HeaderValue::from_str(&request_id) {
-        response.headers_mut().insert("x-request-id", value);
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
     }
     response
 }
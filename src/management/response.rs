@@ -5,7 +5,7 @@
 use crate::error::ProxyError;
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use chrono::{DateTime, Utc};
@@ -122,19 +122,7 @@ impl<T: Serialize> IntoResponse for ApiResponse<T> {
                 };
                 (status, Json(error_response)).into_response()
             }
-            Self::AppError(error) => {
-                // 统一使用新的 as_http_parts() 映射，保持管理端响应包裹结构不变
-                let (status, code, message) = error.as_http_parts();
-                let error_response = ErrorResponse {
-                    success: false,
-                    error: ErrorInfo {
-                        code: code.to_string(),
-                        message,
-                    },
-                    timestamp: Utc::now(),
-                };
-                (status, Json(error_response)).into_response()
-            }
+            Self::AppError(error) => app_error_response(&error),
         }
     }
 }
@@ -171,7 +159,12 @@ pub fn error(status: StatusCode, code: &str, message: &str) -> axum::response::R
 #[must_use]
 #[allow(clippy::needless_pass_by_value)]
 pub fn app_error(error: ProxyError) -> axum::response::Response {
-    // 与 IntoResponse(AppError) 分支一致：用 as_http_parts() 保持包裹结构
+    app_error_response(&error)
+}
+
+/// 统一使用 `as_http_parts()` 映射，保持管理端响应包裹结构不变；
+/// 错误带有重试建议时附加 `Retry-After` 响应头
+fn app_error_response(error: &ProxyError) -> Response {
     let (status, code, message) = error.as_http_parts();
     let body = ErrorResponse {
         success: false,
@@ -181,5 +174,11 @@ pub fn app_error(error: ProxyError) -> axum::response::Response {
         },
         timestamp: Utc::now(),
     };
-    (status, Json(body)).into_response()
+    let mut response = (status, Json(body)).into_response();
+    if let Some(secs) = error.retry_after_secs() {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+    }
+    response
 }
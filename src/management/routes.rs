@@ -37,10 +37,26 @@ pub fn create_routes(state: ManagementState) -> Router {
         .nest("/provider-keys", provider_api_keys_routes())
         // Provider类型管理路由（需要认证）
         .nest("/provider-types", provider_type_routes())
+        // 模型定价管理路由（需要认证，写操作需管理员）
+        .nest("/pricing", pricing_routes())
         // 日志管理路由（需要认证）
         .nest("/logs", logs_routes())
         // OAuth认证路由（需要认证）
         .nest("/oauth", oauth_v2_routes())
+        // 影子路由差异分析（仅管理员）
+        .route(
+            "/shadow/diffs",
+            get(crate::management::handlers::shadow::get_shadow_diffs),
+        )
+        // 配置导出/导入路由（需要认证）
+        .route(
+            "/export",
+            get(crate::management::handlers::config_export::export_config),
+        )
+        .route(
+            "/import",
+            post(crate::management::handlers::config_export::import_config),
+        )
         .with_state(state.clone())
         .layer(middleware::from_fn_with_state(state.clone(), auth));
 
@@ -73,14 +89,21 @@ fn public_stats_routes() -> Router<ManagementState> {
 
 /// 健康检查路由
 fn health_routes() -> Router<ManagementState> {
-    Router::new().route(
-        "/mark-unhealthy/{key_id}",
-        post(crate::management::handlers::health::mark_key_unhealthy),
-    )
+    Router::new()
+        .route(
+            "/pools",
+            get(crate::management::handlers::health::get_pool_stats),
+        )
+        .route(
+            "/mark-unhealthy/{key_id}",
+            post(crate::management::handlers::health::mark_key_unhealthy),
+        )
 }
 
 /// 系统信息路由
 fn system_routes() -> Router<ManagementState> {
+    use axum::routing::{post, put};
+
     Router::new()
         .route(
             "/info",
@@ -90,6 +113,38 @@ fn system_routes() -> Router<ManagementState> {
             "/metrics",
             get(crate::management::handlers::system::get_system_metrics),
         )
+        .route(
+            "/tasks",
+            get(crate::management::handlers::system::get_system_tasks),
+        )
+        .route(
+            "/tasks/history",
+            get(crate::management::handlers::system::get_task_history),
+        )
+        .route(
+            "/maintenance",
+            get(crate::management::handlers::system::get_maintenance),
+        )
+        .route(
+            "/maintenance",
+            put(crate::management::handlers::system::update_maintenance),
+        )
+        .route(
+            "/health-check",
+            get(crate::management::handlers::system::get_health_check),
+        )
+        .route(
+            "/health-check",
+            put(crate::management::handlers::system::update_health_check),
+        )
+        .route(
+            "/spend-anomalies",
+            get(crate::management::handlers::system::list_spend_anomalies),
+        )
+        .route(
+            "/spend-anomalies/{id}/acknowledge",
+            post(crate::management::handlers::system::acknowledge_spend_anomaly),
+        )
 }
 
 /// 统计查询路由
@@ -113,6 +168,10 @@ fn today_stats_routes() -> Router<ManagementState> {
 /// 模型统计路由
 fn models_stats_routes() -> Router<ManagementState> {
     Router::new()
+        .route(
+            "/",
+            get(crate::management::handlers::statistics::get_models_breakdown),
+        )
         .route(
             "/rate",
             get(crate::management::handlers::statistics::get_models_usage_rate),
@@ -177,6 +236,23 @@ fn user_routes() -> Router<ManagementState> {
             "/{id}/reset-password",
             patch(crate::management::handlers::users::reset_user_password),
         )
+        // 用户服务商封禁（仅管理员）
+        .route(
+            "/{id}/provider-blocks",
+            get(crate::management::handlers::provider_blocks::list_provider_blocks),
+        )
+        .route(
+            "/{id}/provider-blocks",
+            put(crate::management::handlers::provider_blocks::set_provider_blocks),
+        )
+        .route(
+            "/{id}/provider-blocks",
+            delete(crate::management::handlers::provider_blocks::clear_provider_blocks),
+        )
+        .route(
+            "/{id}/provider-blocks/{provider_type_id}",
+            delete(crate::management::handlers::provider_blocks::remove_provider_block),
+        )
         // 用户个人资料管理
         .route(
             "/profile",
@@ -207,7 +283,7 @@ fn user_routes() -> Router<ManagementState> {
 
 /// Provider API密钥路由（内部密钥池管理）- 核心功能
 fn provider_api_keys_routes() -> Router<ManagementState> {
-    use axum::routing::{delete, post, put};
+    use axum::routing::{delete, patch, post, put};
     Router::new()
         // 获取提供商密钥卡片统计数据
         .route(
@@ -229,6 +305,11 @@ fn provider_api_keys_routes() -> Router<ManagementState> {
             "/keys",
             post(crate::management::handlers::provider_keys::create_provider_key),
         )
+        // 批量导入提供商密钥（JSON 数组或 CSV）
+        .route(
+            "/bulk-import",
+            post(crate::management::handlers::provider_keys::bulk_import_provider_keys),
+        )
         // 获取提供商密钥详情
         .route(
             "/keys/{id}",
@@ -239,11 +320,26 @@ fn provider_api_keys_routes() -> Router<ManagementState> {
             "/keys/{id}",
             put(crate::management::handlers::provider_keys::update_provider_key),
         )
+        // 部分更新提供商密钥
+        .route(
+            "/keys/{id}",
+            patch(crate::management::handlers::provider_keys::patch_provider_key),
+        )
         // 删除提供商密钥
         .route(
             "/keys/{id}",
             delete(crate::management::handlers::provider_keys::delete_provider_key),
         )
+        // 设置提供商密钥标签
+        .route(
+            "/keys/{id}/tags",
+            put(crate::management::handlers::provider_keys::set_provider_key_tags),
+        )
+        // 恢复已删除的提供商密钥
+        .route(
+            "/keys/{id}/restore",
+            post(crate::management::handlers::provider_keys::restore_provider_key),
+        )
         // 获取密钥统计信息
         .route(
             "/keys/{id}/stats",
@@ -300,6 +396,16 @@ fn user_service_routes() -> Router<ManagementState> {
             "/keys/{id}/usage",
             get(crate::management::handlers::service_apis::get_user_service_key_usage),
         )
+        // API Key响应时间目标达成统计
+        .route(
+            "/keys/{id}/sla",
+            get(crate::management::handlers::service_apis::get_user_service_key_sla),
+        )
+        // API Key生效配置预览
+        .route(
+            "/keys/{id}/effective-config",
+            get(crate::management::handlers::service_apis::get_user_service_key_effective_config),
+        )
         // API Key趋势数据
         .route(
             "/keys/{id}/trends",
@@ -310,6 +416,11 @@ fn user_service_routes() -> Router<ManagementState> {
             "/keys/{id}/regenerate",
             post(crate::management::handlers::service_apis::regenerate_user_service_key),
         )
+        // 轮换API Key（可保留旧Key宽限期）
+        .route(
+            "/keys/{id}/rotate-key",
+            post(crate::management::handlers::service_apis::rotate_user_service_key),
+        )
         // 启用/禁用API Key
         .route(
             "/keys/{id}/status",
@@ -347,6 +458,44 @@ fn provider_type_routes() -> Router<ManagementState> {
         )
 }
 
+/// 模型定价管理路由
+fn pricing_routes() -> Router<ManagementState> {
+    use axum::routing::{delete, put};
+    Router::new()
+        .route(
+            "/models",
+            get(crate::management::handlers::pricing::list_model_pricing),
+        )
+        .route(
+            "/models",
+            post(crate::management::handlers::pricing::create_model_pricing),
+        )
+        .route(
+            "/models/{id}",
+            get(crate::management::handlers::pricing::get_model_pricing),
+        )
+        .route(
+            "/models/{id}",
+            put(crate::management::handlers::pricing::update_model_pricing),
+        )
+        .route(
+            "/models/{id}",
+            delete(crate::management::handlers::pricing::delete_model_pricing),
+        )
+        .route(
+            "/tiers",
+            post(crate::management::handlers::pricing::create_pricing_tier),
+        )
+        .route(
+            "/tiers/{id}",
+            put(crate::management::handlers::pricing::update_pricing_tier),
+        )
+        .route(
+            "/tiers/{id}",
+            delete(crate::management::handlers::pricing::delete_pricing_tier),
+        )
+}
+
 /// 日志管理路由
 fn logs_routes() -> Router<ManagementState> {
     Router::new()
@@ -360,11 +509,21 @@ fn logs_routes() -> Router<ManagementState> {
             "/traces",
             get(crate::management::handlers::logs::get_traces_list),
         )
+        // 实时推送追踪摘要（SSE）
+        .route(
+            "/stream",
+            get(crate::management::handlers::logs::stream_live_traces),
+        )
         // 获取日志详情
         .route(
             "/traces/{id}",
             get(crate::management::handlers::logs::get_trace_detail),
         )
+        // 回放追踪记录对应的请求（仅管理员）
+        .route(
+            "/{trace_id}/replay",
+            post(crate::management::handlers::logs::replay_trace),
+        )
         // 获取日志统计分析
         .route(
             "/analytics",
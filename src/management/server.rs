@@ -9,7 +9,8 @@
 )]
 
 use super::middleware::{
-    IpFilterConfig, ip_filter_middleware, request_id_middleware, timezone_middleware,
+    IpFilterConfig, api_version_middleware, ip_filter_middleware, request_id_middleware,
+    timezone_middleware,
 };
 use crate::app::{context::AppContext, task_scheduler::TaskScheduler, tasks::TaskType};
 use crate::auth::api_key_oauth_refresh_service::ApiKeyOAuthRefreshService;
@@ -17,15 +18,19 @@ use crate::auth::api_key_oauth_service::ApiKeyOauthService;
 use crate::auth::api_key_oauth_state_service::ApiKeyOAuthStateService;
 use crate::auth::api_key_oauth_token_refresh_task::ApiKeyOAuthTokenRefreshTask;
 use crate::auth::service::ApiKeyAuthenticationService;
+use crate::cache::CacheInvalidator;
 use crate::config::AppConfig;
 use crate::error::{Context, Result, management::ManagementError};
 use crate::key_pool::ApiKeySchedulerService;
 use crate::logging::{LogComponent, LogStage};
+use crate::proxy::maintenance::MaintenanceService;
+use crate::trace::LiveTraceHub;
 use crate::{linfo, lwarn};
 use axum::Router;
 use axum::routing::get;
 use sea_orm::DatabaseConnection;
 use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
 use std::net::SocketAddr;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -40,6 +45,9 @@ use tower_http::trace::TraceLayer;
 pub struct ManagementConfig {
     /// 监听地址
     pub bind_address: String,
+    /// 额外监听地址，与 `bind_address` 共用端口
+    #[serde(default)]
+    pub additional_bind_addresses: Vec<String>,
     /// 监听端口
     pub port: u16,
     /// 是否启用CORS
@@ -58,6 +66,7 @@ impl Default for ManagementConfig {
     fn default() -> Self {
         Self {
             bind_address: "0.0.0.0".to_string(),
+            additional_bind_addresses: Vec::new(),
             port: 8080,
             enable_cors: true,
             cors_origins: vec!["*".to_string()],
@@ -194,6 +203,27 @@ impl ManagementState {
         self.services.api_key_oauth_refresh_service()
     }
 
+    /// 获取实时追踪广播的便捷方法
+    #[must_use]
+    pub fn live_traces(&self) -> LiveTraceHub {
+        self.context
+            .services()
+            .api_key_trace_service()
+            .live_traces()
+    }
+
+    /// 获取维护模式开关的便捷方法
+    #[must_use]
+    pub fn maintenance(&self) -> Arc<MaintenanceService> {
+        self.context.services().maintenance_service()
+    }
+
+    /// 获取缓存失效广播器的便捷方法
+    #[must_use]
+    pub fn cache_invalidator(&self) -> Arc<CacheInvalidator> {
+        self.context.services().cache_invalidator()
+    }
+
     #[must_use]
     pub fn services(&self) -> &ManagementServices {
         self.services.as_ref()
@@ -362,6 +392,9 @@ impl ManagementServer {
         // 添加时区中间件
         app = app.layer(axum::middleware::from_fn(timezone_middleware));
 
+        // 添加 API 版本协商中间件
+        app = app.layer(axum::middleware::from_fn(api_version_middleware));
+
         // 添加IP过滤中间件（如果配置了限制）
         if !config.allowed_ips.is_empty() || !config.denied_ips.is_empty() {
             app = app.layer(axum::middleware::from_fn(ip_filter_middleware));
@@ -372,31 +405,37 @@ impl ManagementServer {
         Ok(app)
     }
 
-    /// 启动服务器
+    /// 启动服务器，在全部监听地址上提供服务
     pub async fn serve(self) -> Result<()> {
-        let bind_address = self.config.bind_address.clone();
-        let ip = bind_address
-            .parse::<std::net::IpAddr>()
-            .with_context(|| format!("管理服务器绑定地址无效: {bind_address}"))?;
-        let addr = SocketAddr::new(ip, self.config.port);
-
-        linfo!(
-            "system",
-            LogStage::Startup,
-            LogComponent::ServerSetup,
-            "server_start",
-            &format!("Starting management server on {addr}")
-        );
-
-        let listener = TcpListener::bind(&addr).await?;
-
-        if let Err(err) = axum::serve(
-            listener,
-            self.router
-                .into_make_service_with_connect_info::<SocketAddr>(),
-        )
-        .await
+        let mut servers = Vec::new();
+        for bind_address in
+            std::iter::once(&self.config.bind_address).chain(&self.config.additional_bind_addresses)
         {
+            let ip = bind_address
+                .parse::<std::net::IpAddr>()
+                .with_context(|| format!("管理服务器绑定地址无效: {bind_address}"))?;
+            let addr = SocketAddr::new(ip, self.config.port);
+
+            linfo!(
+                "system",
+                LogStage::Startup,
+                LogComponent::ServerSetup,
+                "server_start",
+                &format!("Starting management server on {addr}")
+            );
+
+            let listener = bind_listener(addr)?;
+            let router = self.router.clone();
+            servers.push(async move {
+                axum::serve(
+                    listener,
+                    router.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .await
+            });
+        }
+
+        if let Err(err) = futures::future::try_join_all(servers).await {
             return Err(crate::error::network::NetworkError::BadGateway(format!(
                 "Management server error: {err}"
             ))
@@ -413,4 +452,19 @@ impl ManagementServer {
     }
 }
 
+/// 绑定监听套接字；IPv6 地址只接受 IPv6 连接，避免与同端口的 IPv4 监听冲突
+fn bind_listener(addr: SocketAddr) -> Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket
+        .bind(&addr.into())
+        .with_context(|| format!("管理服务器绑定失败: {addr}"))?;
+    socket.listen(1024)?;
+    Ok(TcpListener::from_std(socket.into())?)
+}
+
 // 根路径处理器与 Ping 已迁移至 handlers::system
@@ -0,0 +1,635 @@
+//! # 用户配置导出/导入服务
+//!
+//! 将用户的提供商密钥与服务 API 导出为带版本号的 JSON 包，用于备份与跨实例迁移。
+//!
+//! - 提供商类型按 `(name, auth_type)` 引用，而非数据库 ID，便于导入到其它实例
+//! - 默认脱敏：提供商密钥与服务 API 密钥不导出；OAuth 密钥绑定本实例会话，始终不导出
+//! - 导入时缺少密钥的提供商密钥以停用状态创建，等待用户补录或重新授权；服务 API 缺少密钥时重新生成
+
+use chrono::{DateTime, Utc};
+use entity::{provider_types, user_provider_keys, user_service_apis};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::auth::types::{AuthStatus, AuthType};
+use crate::collect::field_extractor::parse_transform_rules;
+use crate::error::{Context, ProxyError, Result};
+use crate::key_pool::SchedulingStrategy;
+use crate::key_pool::key_tags::{key_tags, normalize_tag, tags_to_json};
+use crate::management::services::service_apis::generate_service_api_key;
+use crate::proxy::model_routing::{ModelRoute, ModelRoutes};
+use crate::proxy::non_streaming::StreamPolicy;
+use crate::proxy::path_allowlist::parse_allowed_paths;
+use crate::proxy::response_transform_service::parse_custom_response_headers;
+use crate::proxy::system_prompt::parse_system_prompt;
+use crate::proxy::upstream_error::UpstreamErrorMode;
+
+/// 当前导出格式版本；结构发生不兼容变更时递增，并在 `ConfigBundle::from_value` 中补充升级逻辑
+pub const CONFIG_BUNDLE_VERSION: u32 = 1;
+
+/// 导出查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportQuery {
+    /// 是否导出密钥明文（默认脱敏）
+    #[serde(default)]
+    pub include_secrets: bool,
+}
+
+/// 配置导出包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub provider_keys: Vec<ExportedProviderKey>,
+    #[serde(default)]
+    pub service_apis: Vec<ExportedServiceApi>,
+}
+
+/// 提供商类型引用
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProviderTypeRef {
+    pub name: String,
+    pub auth_type: String,
+}
+
+/// 导出的提供商密钥
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedProviderKey {
+    /// 导出时的密钥 ID，仅用于包内服务 API 的关联
+    pub ref_id: i32,
+    pub provider_type: ProviderTypeRef,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub weight: Option<i32>,
+    #[serde(default)]
+    pub max_requests_per_minute: Option<i32>,
+    #[serde(default)]
+    pub max_tokens_prompt_per_minute: Option<i32>,
+    #[serde(default)]
+    pub max_requests_per_day: Option<i32>,
+    pub is_active: bool,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// 导出的服务 API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedServiceApi {
+    pub provider_type: ProviderTypeRef,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 关联的提供商密钥（对应 `ExportedProviderKey::ref_id`）
+    #[serde(default)]
+    pub provider_key_refs: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub scheduling_strategy: Option<String>,
+    #[serde(default)]
+    pub retry_count: Option<i32>,
+    #[serde(default)]
+    pub timeout_seconds: Option<i32>,
+    #[serde(default)]
+    pub max_response_duration_seconds: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sla_target_ms: Option<i32>,
+    #[serde(default)]
+    pub max_request_per_min: Option<i32>,
+    #[serde(default)]
+    pub max_requests_per_day: Option<i32>,
+    #[serde(default)]
+    pub max_tokens_per_day: Option<i64>,
+    #[serde(default)]
+    pub max_cost_per_day: Option<sea_orm::prelude::Decimal>,
+    #[serde(default)]
+    pub max_cost_per_request: Option<sea_orm::prelude::Decimal>,
+    #[serde(default)]
+    pub log_mode: bool,
+    #[serde(default)]
+    pub selection_debug: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_policy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_error_mode: Option<String>,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_transform_rules: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_headers: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_paths: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub model_routes: Vec<ExportedModelRoute>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub is_active: bool,
+}
+
+/// 导出的按模型路由规则，服务商与密钥均使用包内引用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedModelRoute {
+    pub pattern: String,
+    pub provider_type: ProviderTypeRef,
+    /// 规则指定的密钥（对应 `ExportedProviderKey::ref_id`），未指定时使用服务 API 的密钥池
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_key_refs: Option<Vec<i32>>,
+}
+
+/// 导入结果
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub provider_keys_created: usize,
+    /// 已停用、需要补录密钥或重新授权的提供商密钥名称
+    pub provider_keys_disabled: Vec<String>,
+    pub skipped: Vec<ImportSkip>,
+    pub service_apis: Vec<ImportedServiceApi>,
+}
+
+/// 被跳过的导入项
+#[derive(Debug, Serialize)]
+pub struct ImportSkip {
+    pub kind: &'static str,
+    pub name: String,
+    pub reason: String,
+}
+
+/// 已导入的服务 API（密钥为重新生成时需告知用户）
+#[derive(Debug, Serialize)]
+pub struct ImportedServiceApi {
+    pub id: i32,
+    pub name: Option<String>,
+    pub api_key: String,
+    pub api_key_regenerated: bool,
+}
+
+impl ConfigBundle {
+    /// 解析导入包并校验版本
+    ///
+    /// 旧版本包在此处升级为当前结构；高于当前版本的包无法安全解析，直接拒绝。
+    pub fn from_value(value: Value) -> Result<Self> {
+        let version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| business_error("导入包缺少 version 字段"))?;
+        crate::ensure!(
+            (1..=u64::from(CONFIG_BUNDLE_VERSION)).contains(&version),
+            business_error(format!(
+                "不支持的导入包版本: {version}（当前支持 1..={CONFIG_BUNDLE_VERSION}）"
+            ))
+        );
+
+        serde_json::from_value(value).map_err(|e| business_error(format!("导入包格式错误: {e}")))
+    }
+}
+
+/// 用户配置导出/导入服务
+pub struct ConfigExportService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl ConfigExportService {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// 导出用户配置
+    pub async fn export(&self, user_id: i32, query: &ExportQuery) -> Result<ConfigBundle> {
+        let provider_types: HashMap<i32, ProviderTypeRef> = provider_types::Entity::find()
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to fetch provider types")?
+            .into_iter()
+            .map(|model| {
+                (
+                    model.id,
+                    ProviderTypeRef {
+                        name: model.name,
+                        auth_type: model.auth_type,
+                    },
+                )
+            })
+            .collect();
+        let provider_type_ref = |id: i32| {
+            provider_types
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| business_error(format!("服务商类型不存在: {id}")))
+        };
+
+        let keys = user_provider_keys::Entity::find()
+            .filter(user_provider_keys::Column::UserId.eq(user_id))
+            .filter(user_provider_keys::Column::DeletedAt.is_null())
+            .order_by_asc(user_provider_keys::Column::Id)
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to fetch provider keys")?;
+        let mut provider_keys = Vec::with_capacity(keys.len());
+        for key in keys {
+            let is_oauth = key.auth_type.parse::<AuthType>()? == AuthType::OAuth;
+            let tags = key_tags(&key);
+            let api_key = (query.include_secrets && !is_oauth).then_some(key.api_key);
+            provider_keys.push(ExportedProviderKey {
+                ref_id: key.id,
+                provider_type: provider_type_ref(key.provider_type_id)?,
+                name: key.name,
+                api_key,
+                weight: key.weight,
+                max_requests_per_minute: key.max_requests_per_minute,
+                max_tokens_prompt_per_minute: key.max_tokens_prompt_per_minute,
+                max_requests_per_day: key.max_requests_per_day,
+                is_active: key.is_active,
+                project_id: key.project_id,
+                tags,
+            });
+        }
+
+        let apis = user_service_apis::Entity::find()
+            .filter(user_service_apis::Column::UserId.eq(user_id))
+            .order_by_asc(user_service_apis::Column::Id)
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to fetch user service APIs")?;
+        let mut service_apis = Vec::with_capacity(apis.len());
+        for api in apis {
+            let model_routes = ModelRoutes::of(&api)
+                .map(|routes| {
+                    routes
+                        .routes()
+                        .iter()
+                        .map(|route| {
+                            Ok(ExportedModelRoute {
+                                pattern: route.pattern.clone(),
+                                provider_type: provider_type_ref(route.provider_type_id)?,
+                                provider_key_refs: route.user_provider_keys_ids.clone(),
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+            service_apis.push(ExportedServiceApi {
+                provider_type: provider_type_ref(api.provider_type_id)?,
+                name: api.name,
+                description: api.description,
+                provider_key_refs: serde_json::from_value(api.user_provider_keys_ids)
+                    .unwrap_or_default(),
+                api_key: query.include_secrets.then_some(api.api_key),
+                scheduling_strategy: api.scheduling_strategy,
+                retry_count: api.retry_count,
+                timeout_seconds: api.timeout_seconds,
+                max_response_duration_seconds: api.max_response_duration_seconds,
+                sla_target_ms: api.sla_target_ms,
+                max_request_per_min: api.max_request_per_min,
+                max_requests_per_day: api.max_requests_per_day,
+                max_tokens_per_day: api.max_tokens_per_day,
+                max_cost_per_day: api.max_cost_per_day,
+                max_cost_per_request: api.max_cost_per_request,
+                log_mode: api.log_mode,
+                selection_debug: api.selection_debug,
+                stream_policy: Some(api.stream_policy),
+                upstream_error_mode: Some(api.upstream_error_mode),
+                priority: api.priority,
+                request_transform_rules: api.request_transform_rules,
+                response_headers: api.response_headers,
+                allowed_paths: api.allowed_paths,
+                system_prompt: api.system_prompt,
+                key_tag: api.key_tag,
+                model_routes,
+                expires_at: api.expires_at.map(|dt| dt.and_utc()),
+                is_active: api.is_active,
+            });
+        }
+
+        Ok(ConfigBundle {
+            version: CONFIG_BUNDLE_VERSION,
+            exported_at: Utc::now(),
+            provider_keys,
+            service_apis,
+        })
+    }
+
+    /// 导入配置到指定用户（单事务，任一写入失败则整体回滚）
+    pub async fn import(&self, user_id: i32, bundle: &ConfigBundle) -> Result<ImportReport> {
+        let provider_types: HashMap<ProviderTypeRef, i32> = provider_types::Entity::find()
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to fetch provider types")?
+            .into_iter()
+            .map(|model| {
+                (
+                    ProviderTypeRef {
+                        name: model.name,
+                        auth_type: model.auth_type,
+                    },
+                    model.id,
+                )
+            })
+            .collect();
+
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin import transaction")?;
+        let mut report = ImportReport::default();
+
+        // 包内 ref_id -> 导入后的密钥 ID
+        let mut key_ids: HashMap<i32, i32> = HashMap::new();
+        for key in &bundle.provider_keys {
+            let Some(&provider_type_id) = provider_types.get(&key.provider_type) else {
+                report.skipped.push(ImportSkip {
+                    kind: "provider_key",
+                    name: key.name.clone(),
+                    reason: unknown_provider_type(&key.provider_type),
+                });
+                continue;
+            };
+
+            if let Some(existing) =
+                find_provider_key(&txn, user_id, provider_type_id, &key.name).await?
+            {
+                key_ids.insert(key.ref_id, existing.id);
+                report.skipped.push(ImportSkip {
+                    kind: "provider_key",
+                    name: key.name.clone(),
+                    reason: "同名密钥已存在，服务 API 将关联到现有密钥".to_string(),
+                });
+                continue;
+            }
+
+            let inserted = insert_provider_key(&txn, user_id, provider_type_id, key).await?;
+            if !inserted.is_active && key.is_active {
+                report.provider_keys_disabled.push(key.name.clone());
+            }
+            key_ids.insert(key.ref_id, inserted.id);
+            report.provider_keys_created += 1;
+        }
+
+        for api in &bundle.service_apis {
+            let name = api.name.clone().unwrap_or_default();
+            let Some(&provider_type_id) = provider_types.get(&api.provider_type) else {
+                report.skipped.push(ImportSkip {
+                    kind: "service_api",
+                    name,
+                    reason: unknown_provider_type(&api.provider_type),
+                });
+                continue;
+            };
+
+            let model_routes =
+                match import_model_routes(&api.model_routes, &provider_types, &key_ids) {
+                    Ok(routes) => routes,
+                    Err(reason) => {
+                        report.skipped.push(ImportSkip {
+                            kind: "service_api",
+                            name,
+                            reason,
+                        });
+                        continue;
+                    }
+                };
+
+            let key_refs: Vec<i32> = api
+                .provider_key_refs
+                .iter()
+                .filter_map(|ref_id| key_ids.get(ref_id).copied())
+                .collect();
+            // 原密钥已被占用（例如导入回同一实例）时重新生成，避免与现有服务 API 冲突
+            let reusable = match &api.api_key {
+                Some(api_key) if !service_api_key_exists(&txn, api_key).await? => {
+                    Some(api_key.clone())
+                }
+                _ => None,
+            };
+            let regenerated = reusable.is_none();
+            if let Some(rules) = &api.request_transform_rules {
+                parse_transform_rules(rules)?;
+            }
+            if let Some(headers) = &api.response_headers {
+                parse_custom_response_headers(headers)?;
+            }
+            if let Some(paths) = &api.allowed_paths {
+                parse_allowed_paths(paths)?;
+            }
+            if let Some(prompt) = &api.system_prompt {
+                parse_system_prompt(prompt)?;
+            }
+            let scheduling_strategy = api
+                .scheduling_strategy
+                .as_deref()
+                .map(SchedulingStrategy::parse_known)
+                .transpose()?;
+            let key_tag = api.key_tag.as_deref().map(normalize_tag).transpose()?;
+            let stream_policy = api
+                .stream_policy
+                .as_deref()
+                .map(StreamPolicy::parse_known)
+                .transpose()?
+                .unwrap_or_default();
+            let upstream_error_mode = api
+                .upstream_error_mode
+                .as_deref()
+                .map(UpstreamErrorMode::parse_known)
+                .transpose()?
+                .unwrap_or_default();
+            let api_key = reusable.unwrap_or_else(generate_service_api_key);
+
+            let now = Utc::now().naive_utc();
+            let inserted = user_service_apis::ActiveModel {
+                user_id: Set(user_id),
+                provider_type_id: Set(provider_type_id),
+                api_key: Set(api_key),
+                name: Set(api.name.clone()),
+                description: Set(api.description.clone()),
+                user_provider_keys_ids: Set(serde_json::to_value(&key_refs)
+                    .context("Failed to serialize user provider key ids")?),
+                log_mode: Set(api.log_mode),
+                selection_debug: Set(api.selection_debug),
+                stream_policy: Set(stream_policy.as_str().to_string()),
+                upstream_error_mode: Set(upstream_error_mode.as_str().to_string()),
+                priority: Set(api.priority),
+                request_transform_rules: Set(api.request_transform_rules.clone()),
+                response_headers: Set(api.response_headers.clone()),
+                allowed_paths: Set(api.allowed_paths.clone()),
+                system_prompt: Set(api.system_prompt.clone()),
+                key_tag: Set(key_tag),
+                model_routes: Set(model_routes),
+                scheduling_strategy: Set(scheduling_strategy.map(|s| s.as_str().to_string())),
+                retry_count: Set(api.retry_count),
+                timeout_seconds: Set(api.timeout_seconds),
+                max_response_duration_seconds: Set(api.max_response_duration_seconds),
+                sla_target_ms: Set(api.sla_target_ms.filter(|ms| *ms > 0)),
+                max_request_per_min: Set(api.max_request_per_min),
+                max_requests_per_day: Set(api.max_requests_per_day),
+                max_tokens_per_day: Set(api.max_tokens_per_day),
+                max_cost_per_day: Set(api.max_cost_per_day),
+                max_cost_per_request: Set(api.max_cost_per_request),
+                expires_at: Set(api.expires_at.map(|dt| dt.naive_utc())),
+                is_active: Set(api.is_active),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await
+            .context("Failed to insert user service API")?;
+
+            report.service_apis.push(ImportedServiceApi {
+                id: inserted.id,
+                name: inserted.name,
+                api_key: inserted.api_key,
+                api_key_regenerated: regenerated,
+            });
+        }
+
+        txn.commit()
+            .await
+            .context("Failed to commit import transaction")?;
+        Ok(report)
+    }
+}
+
+/// 将包内的按模型路由规则映射到本实例的服务商与密钥 ID
+///
+/// 规则引用了本实例不存在的服务商，或指定的密钥均未导入时返回原因，整个服务 API 跳过导入，
+/// 避免导入后请求被路由到与导出时不同的服务商。
+fn import_model_routes(
+    routes: &[ExportedModelRoute],
+    provider_types: &HashMap<ProviderTypeRef, i32>,
+    key_ids: &HashMap<i32, i32>,
+) -> std::result::Result<Option<Value>, String> {
+    if routes.is_empty() {
+        return Ok(None);
+    }
+
+    let mut mapped = Vec::with_capacity(routes.len());
+    for route in routes {
+        let provider_type_id = *provider_types.get(&route.provider_type).ok_or_else(|| {
+            format!(
+                "按模型路由规则 {}: {}",
+                route.pattern,
+                unknown_provider_type(&route.provider_type)
+            )
+        })?;
+        let user_provider_keys_ids = match &route.provider_key_refs {
+            Some(refs) => {
+                let ids: Vec<i32> = refs
+                    .iter()
+                    .filter_map(|ref_id| key_ids.get(ref_id).copied())
+                    .collect();
+                if ids.is_empty() {
+                    return Err(format!(
+                        "按模型路由规则 {} 指定的密钥均未导入",
+                        route.pattern
+                    ));
+                }
+                Some(ids)
+            }
+            None => None,
+        };
+        mapped.push(ModelRoute {
+            pattern: route.pattern.clone(),
+            provider_type_id,
+            user_provider_keys_ids,
+        });
+    }
+
+    let value = serde_json::to_value(mapped).map_err(|e| e.to_string())?;
+    ModelRoutes::parse(&value).map_err(|e| e.to_string())?;
+    Ok(Some(value))
+}
+
+async fn find_provider_key(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    provider_type_id: i32,
+    name: &str,
+) -> Result<Option<user_provider_keys::Model>> {
+    user_provider_keys::Entity::find()
+        .filter(user_provider_keys::Column::UserId.eq(user_id))
+        .filter(user_provider_keys::Column::ProviderTypeId.eq(provider_type_id))
+        .filter(user_provider_keys::Column::Name.eq(name))
+        .one(txn)
+        .await
+        .context("Failed to check existing provider key")
+}
+
+async fn insert_provider_key(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    provider_type_id: i32,
+    key: &ExportedProviderKey,
+) -> Result<user_provider_keys::Model> {
+    // OAuth 会话无法跨实例迁移，需要重新授权；API Key 脱敏时需要用户补录
+    let auth_type = key.provider_type.auth_type.parse::<AuthType>()?;
+    let is_oauth = auth_type == AuthType::OAuth;
+    let api_key = key.api_key.clone().filter(|_| !is_oauth);
+    let auth_status = if is_oauth {
+        AuthStatus::Pending
+    } else {
+        AuthStatus::Authorized
+    };
+    let now = Utc::now().naive_utc();
+
+    user_provider_keys::ActiveModel {
+        user_id: Set(user_id),
+        provider_type_id: Set(provider_type_id),
+        name: Set(key.name.clone()),
+        is_active: Set(key.is_active && api_key.is_some()),
+        api_key: Set(api_key.unwrap_or_default()),
+        auth_type: Set(auth_type.to_string()),
+        auth_status: Set(Some(auth_status.to_string())),
+        weight: Set(key.weight),
+        max_requests_per_minute: Set(key.max_requests_per_minute),
+        max_tokens_prompt_per_minute: Set(key.max_tokens_prompt_per_minute),
+        max_requests_per_day: Set(key.max_requests_per_day),
+        project_id: Set(key.project_id.clone()),
+        tags: Set(tags_to_json(&key.tags)?),
+        health_status: Set("healthy".to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(txn)
+    .await
+    .context("Failed to insert provider key")
+}
+
+async fn service_api_key_exists(txn: &DatabaseTransaction, api_key: &str) -> Result<bool> {
+    Ok(user_service_apis::Entity::find()
+        .filter(user_service_apis::Column::ApiKey.eq(api_key))
+        .one(txn)
+        .await
+        .context("Failed to check existing service API key")?
+        .is_some())
+}
+
+fn unknown_provider_type(provider_type: &ProviderTypeRef) -> String {
+    format!(
+        "服务商类型不存在: {}（{}）",
+        provider_type.name, provider_type.auth_type
+    )
+}
+
+fn business_error(message: impl Into<String>) -> ProxyError {
+    crate::error::auth::AuthError::Message(message.into()).into()
+}
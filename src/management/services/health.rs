@@ -0,0 +1,108 @@
+//! # 密钥池健康汇总服务
+//!
+//! 按服务商汇总 `user_provider_keys` 的启用、健康、限流与冷却状态，供运维快速查看各密钥池是否可用。
+//! 普通用户只能查看自己的密钥；管理员默认汇总所有用户，也可按用户过滤。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::ensure;
+use crate::error::{Context, Result, auth::AuthError};
+use crate::key_pool::PoolStats;
+use crate::management::middleware::AuthContext;
+
+use chrono::Utc;
+use entity::{provider_types, user_provider_keys};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+
+/// 密钥池汇总查询参数
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PoolStatsQuery {
+    /// 只汇总指定用户的密钥（普通用户只能查询自己）
+    pub user_id: Option<i32>,
+}
+
+/// 单个服务商的密钥池汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderPoolStats {
+    pub provider_type_id: i32,
+    pub provider_name: String,
+    pub display_name: String,
+    #[serde(flatten)]
+    pub stats: PoolStats,
+}
+
+#[derive(Clone)]
+pub struct PoolHealthService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl PoolHealthService {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// 按服务商汇总密钥池状态，按服务商 ID 排序
+    pub async fn pool_stats(
+        &self,
+        auth: &AuthContext,
+        query: &PoolStatsQuery,
+    ) -> Result<Vec<ProviderPoolStats>> {
+        let user_id = if auth.is_admin {
+            query.user_id
+        } else {
+            ensure!(
+                query.user_id.is_none_or(|user_id| user_id == auth.user_id),
+                AuthError::PermissionDenied {
+                    required: "admin".to_string(),
+                    actual: "user".to_string(),
+                }
+            );
+            Some(auth.user_id)
+        };
+
+        let mut select = user_provider_keys::Entity::find()
+            .filter(user_provider_keys::Column::DeletedAt.is_null());
+        if let Some(user_id) = user_id {
+            select = select.filter(user_provider_keys::Column::UserId.eq(user_id));
+        }
+        let keys = select
+            .all(self.db.as_ref())
+            .await
+            .context("获取服务商密钥失败")?;
+
+        let now = Utc::now().naive_utc();
+        let mut pools: BTreeMap<i32, PoolStats> = BTreeMap::new();
+        for key in &keys {
+            pools
+                .entry(key.provider_type_id)
+                .or_default()
+                .record(key, now);
+        }
+
+        let providers = provider_types::Entity::find()
+            .filter(provider_types::Column::Id.is_in(pools.keys().copied()))
+            .all(self.db.as_ref())
+            .await
+            .context("获取服务商类型失败")?;
+        let providers: BTreeMap<i32, provider_types::Model> = providers
+            .into_iter()
+            .map(|provider| (provider.id, provider))
+            .collect();
+
+        Ok(pools
+            .into_iter()
+            .map(|(provider_type_id, stats)| {
+                let provider = providers.get(&provider_type_id);
+                ProviderPoolStats {
+                    provider_type_id,
+                    provider_name: provider.map(|p| p.name.clone()).unwrap_or_default(),
+                    display_name: provider.map(|p| p.display_name.clone()).unwrap_or_default(),
+                    stats,
+                }
+            })
+            .collect())
+    }
+}
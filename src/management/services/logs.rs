@@ -7,6 +7,7 @@ use crate::{
     linfo,
     logging::{LogComponent, LogStage},
     management::{middleware::auth::AuthContext, server::ManagementState},
+    trace::LiveTraceFilter,
     types::{ConvertToUtc, ProviderTypeId, TimezoneContext, timezone_utils},
 };
 use chrono::{DateTime, Utc};
@@ -48,13 +49,18 @@ pub struct ProxyTraceListEntry {
     pub tokens_prompt: i32,
     pub tokens_completion: i32,
     pub tokens_total: i32,
+    pub tokens_estimated: bool,
     pub token_efficiency_ratio: Option<f64>,
     pub cache_create_tokens: i32,
     pub cache_read_tokens: i32,
+    pub request_bytes: Option<i64>,
+    pub response_bytes: Option<i64>,
     pub cost: Option<f64>,
     pub cost_currency: String,
     pub model_used: Option<String>,
     pub client_ip: Option<String>,
+    pub client_country: Option<String>,
+    pub client_asn: Option<i64>,
     pub user_agent: Option<String>,
     pub error_type: Option<String>,
     pub error_message: Option<String>,
@@ -63,6 +69,7 @@ pub struct ProxyTraceListEntry {
     pub start_time: Option<String>,
     pub end_time: Option<String>,
     pub duration_ms: Option<i64>,
+    pub sla_breached: bool,
     pub is_success: bool,
     pub created_at: String,
     pub provider_name: Option<String>,
@@ -84,13 +91,18 @@ pub struct ProxyTraceEntry {
     pub tokens_prompt: i32,
     pub tokens_completion: i32,
     pub tokens_total: i32,
+    pub tokens_estimated: bool,
     pub token_efficiency_ratio: Option<f64>,
     pub cache_create_tokens: i32,
     pub cache_read_tokens: i32,
+    pub request_bytes: Option<i64>,
+    pub response_bytes: Option<i64>,
     pub cost: Option<f64>,
     pub cost_currency: String,
     pub model_used: Option<String>,
     pub client_ip: Option<String>,
+    pub client_country: Option<String>,
+    pub client_asn: Option<i64>,
     pub user_agent: Option<String>,
     pub error_type: Option<String>,
     pub error_message: Option<String>,
@@ -99,6 +111,7 @@ pub struct ProxyTraceEntry {
     pub start_time: Option<String>,
     pub end_time: Option<String>,
     pub duration_ms: Option<i64>,
+    pub sla_breached: bool,
     pub is_success: bool,
     pub created_at: String,
     pub provider_name: Option<String>,
@@ -175,6 +188,16 @@ pub struct LogsListQuery {
     pub end_time: Option<chrono::NaiveDateTime>,
 }
 
+/// 实时日志订阅参数
+#[derive(Debug, Default, Deserialize)]
+pub struct LogsStreamQuery {
+    pub user_id: Option<i32>,
+    pub provider_type_id: Option<ProviderTypeId>,
+    /// 是否同时推送进行中的请求
+    #[serde(default)]
+    pub include_in_progress: bool,
+}
+
 /// 日志分析查询参数
 #[derive(Debug, Deserialize)]
 pub struct LogsAnalyticsQuery {
@@ -215,6 +238,20 @@ impl<'a> LogsService<'a> {
         self.fetch_traces_list(auth, timezone, query, params).await
     }
 
+    /// 构造实时日志订阅过滤条件，非管理员只能订阅自己的请求
+    #[must_use]
+    pub fn live_trace_filter(auth: &AuthContext, query: &LogsStreamQuery) -> LiveTraceFilter {
+        LiveTraceFilter {
+            user_id: if auth.is_admin {
+                query.user_id
+            } else {
+                Some(auth.user_id)
+            },
+            provider_type_id: query.provider_type_id,
+            include_in_progress: query.include_in_progress,
+        }
+    }
+
     /// 获取日志详情
     pub async fn trace_detail(
         &self,
@@ -778,15 +815,20 @@ impl<'a> LogsService<'a> {
                 tokens_prompt: trace_model.tokens_prompt.unwrap_or(0),
                 tokens_completion: trace_model.tokens_completion.unwrap_or(0),
                 tokens_total: trace_model.tokens_total.unwrap_or(0),
+                tokens_estimated: trace_model.tokens_estimated,
                 token_efficiency_ratio: trace_model.token_efficiency_ratio,
                 cache_create_tokens: trace_model.cache_create_tokens.unwrap_or(0),
                 cache_read_tokens: trace_model.cache_read_tokens.unwrap_or(0),
+                request_bytes: trace_model.request_bytes,
+                response_bytes: trace_model.response_bytes,
                 cost: trace_model.cost,
                 cost_currency: trace_model
                     .cost_currency
                     .unwrap_or_else(|| "USD".to_string()),
                 model_used: trace_model.model_used,
                 client_ip: trace_model.client_ip,
+                client_country: trace_model.client_country,
+                client_asn: trace_model.client_asn,
                 user_agent: trace_model.user_agent,
                 error_type: trace_model.error_type,
                 error_message: trace_model.error_message,
@@ -801,6 +843,7 @@ impl<'a> LogsService<'a> {
                     &timezone.timezone,
                 ),
                 duration_ms: trace_model.duration_ms,
+                sla_breached: trace_model.sla_breached,
                 is_success: trace_model.is_success,
                 created_at: timezone_utils::format_naive_utc_for_response(
                     &trace_model.created_at,
@@ -903,9 +946,12 @@ fn build_trace_entries(
             tokens_prompt: record.trace.tokens_prompt.unwrap_or(0),
             tokens_completion: record.trace.tokens_completion.unwrap_or(0),
             tokens_total: record.trace.tokens_total.unwrap_or(0),
+            tokens_estimated: record.trace.tokens_estimated,
             token_efficiency_ratio: record.trace.token_efficiency_ratio,
             cache_create_tokens: record.trace.cache_create_tokens.unwrap_or(0),
             cache_read_tokens: record.trace.cache_read_tokens.unwrap_or(0),
+            request_bytes: record.trace.request_bytes,
+            response_bytes: record.trace.response_bytes,
             cost: record.trace.cost,
             cost_currency: record
                 .trace
@@ -914,6 +960,8 @@ fn build_trace_entries(
                 .unwrap_or_else(|| "USD".to_string()),
             model_used: record.trace.model_used.clone(),
             client_ip: record.trace.client_ip.clone(),
+            client_country: record.trace.client_country.clone(),
+            client_asn: record.trace.client_asn,
             user_agent: record.trace.user_agent.clone(),
             error_type: record.trace.error_type.clone(),
             error_message: record.trace.error_message.clone(),
@@ -928,6 +976,7 @@ fn build_trace_entries(
                 &timezone.timezone,
             ),
             duration_ms: record.trace.duration_ms,
+            sla_breached: record.trace.sla_breached,
             is_success: record.trace.is_success,
             created_at: timezone_utils::format_naive_utc_for_response(
                 &record.trace.created_at,
@@ -4,34 +4,49 @@
 //! 此模块不仅暴露各领域 service，还统一导出常用的共享工具，便于调用方组合使用。
 
 pub mod auth;
+pub mod config_export;
+pub mod health;
 pub mod logs;
 pub mod oauth_v2;
+pub mod pricing;
+pub mod provider_blocks;
 pub mod provider_keys;
 pub mod provider_types;
 pub mod service_apis;
+pub mod shadow;
 pub mod shared;
 pub mod statistics;
 pub mod stats_public;
 pub mod system;
+pub mod trace_replay;
 pub mod users;
 
 pub use auth::AuthManagementService;
+pub use config_export::{ConfigBundle, ConfigExportService, ExportQuery, ImportReport};
+pub use health::{PoolHealthService, PoolStatsQuery, ProviderPoolStats};
 pub use logs::LogsService;
 pub use oauth_v2::{
     OAuthProviderSummary, OAuthSessionInfoWithTimezone, OAuthV2AuthorizeRequest,
     OAuthV2ExchangeRequest, OAuthV2PollQuery, OAuthV2Service,
 };
+pub use pricing::{
+    CreateModelPricingRequest, CreatePricingTierRequest, ModelPricingQuery, PricingService,
+    PricingTierInput, UpdateModelPricingRequest,
+};
+pub use provider_blocks::{ProviderBlockItem, ProviderBlocksService, SetProviderBlocksRequest};
 pub use provider_keys::ProviderKeyService;
 pub use provider_keys::{
-    CreateProviderKeyRequest, ProviderKeysListQuery, TrendQuery, UpdateProviderKeyRequest,
-    UserProviderKeyQuery,
+    CreateProviderKeyRequest, PatchProviderKeyRequest, ProviderKeysListQuery,
+    SetProviderKeyTagsRequest, TrendQuery, UpdateProviderKeyRequest, UserProviderKeyQuery,
 };
 pub use provider_types::{
     CreateProviderTypeRequest, ProviderTypesCrudService, UpdateProviderTypeRequest,
 };
 pub use service_apis::ServiceApiService;
+pub use shadow::{ShadowDiffQuery, ShadowDiffService, ShadowDiffsResponse};
 pub use statistics::StatisticsService;
 pub use stats_public::StatsService;
+pub use trace_replay::{TraceReplayRequest, TraceReplayResponse, TraceReplayService};
 pub use users::UsersService;
 
 pub use shared::{
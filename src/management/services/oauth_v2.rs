@@ -26,6 +26,9 @@ pub struct OAuthV2AuthorizeRequest {
     pub name: String,
     /// 会话描述
     pub description: Option<String>,
+    /// 回调地址（可选，须为提供商配置中登记的地址之一）
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
 }
 
 /// OAuth v2轮询查询参数
@@ -92,6 +95,7 @@ impl<'a> OAuthV2Service<'a> {
                 &request.provider_name,
                 &request.name,
                 request.description.as_deref(),
+                request.redirect_uri.as_deref(),
             )
             .await
         {
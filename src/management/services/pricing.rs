@@ -0,0 +1,540 @@
+//! # 模型定价管理服务
+//!
+//! 管理端查看与编辑模型定价及其阶梯配置。写操作会校验阶梯连续性，并使进程内的定价缓存失效。
+
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::ensure;
+use crate::error::{Context, ProxyError, Result, auth::AuthError};
+use crate::management::middleware::AuthContext;
+use crate::pricing::{invalidate_pricing_cache, validate_tier_ranges};
+use crate::types::timezone_utils;
+
+use entity::{
+    model_pricing::{self, Entity as ModelPricing},
+    model_pricing_tiers::{self, Entity as ModelPricingTiers},
+    provider_types::Entity as ProviderTypes,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+
+/// 支持的 token 类型
+const TOKEN_TYPES: &[&str] = &["prompt", "completion", "cache_create", "cache_read"];
+
+// =========================
+// 数据结构定义 (DTOs)
+// =========================
+
+/// 定价列表查询参数
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelPricingQuery {
+    #[serde(default)]
+    pub provider_type_id: Option<i32>,
+    /// 按模型名称模糊匹配
+    #[serde(default)]
+    pub model_name: Option<String>,
+}
+
+/// 阶梯定价输入
+///
+/// 阈值为闭区间 `[min_tokens, max_tokens]`，`max_tokens` 为空表示无上限。
+#[derive(Debug, Clone, Deserialize)]
+pub struct PricingTierInput {
+    pub token_type: String,
+    pub min_tokens: i32,
+    #[serde(default)]
+    pub max_tokens: Option<i32>,
+    pub price_per_token: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateModelPricingRequest {
+    pub provider_type_id: i32,
+    pub model_name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 默认 `USD`
+    #[serde(default)]
+    pub cost_currency: Option<String>,
+    #[serde(default)]
+    pub tiers: Vec<PricingTierInput>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateModelPricingRequest {
+    #[serde(default)]
+    pub model_name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub cost_currency: Option<String>,
+    /// 传入时整体替换该模型的全部阶梯
+    #[serde(default)]
+    pub tiers: Option<Vec<PricingTierInput>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatePricingTierRequest {
+    pub model_pricing_id: i32,
+    pub token_type: String,
+    pub min_tokens: i32,
+    #[serde(default)]
+    pub max_tokens: Option<i32>,
+    pub price_per_token: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PricingTierItem {
+    pub id: i32,
+    pub token_type: String,
+    pub min_tokens: i32,
+    pub max_tokens: Option<i32>,
+    pub price_per_token: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelPricingItem {
+    pub id: i32,
+    pub provider_type_id: i32,
+    pub model_name: String,
+    pub description: Option<String>,
+    pub cost_currency: String,
+    pub tiers: Vec<PricingTierItem>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// =========================
+// 服务实现
+// =========================
+
+#[derive(Clone)]
+pub struct PricingService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl PricingService {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// 列出模型定价（含阶梯）
+    pub async fn list(
+        &self,
+        query: &ModelPricingQuery,
+        timezone: Tz,
+    ) -> Result<Vec<ModelPricingItem>> {
+        let mut select = ModelPricing::find()
+            .order_by_asc(model_pricing::Column::ProviderTypeId)
+            .order_by_asc(model_pricing::Column::ModelName);
+        if let Some(provider_type_id) = query.provider_type_id {
+            select = select.filter(model_pricing::Column::ProviderTypeId.eq(provider_type_id));
+        }
+        if let Some(model_name) = query.model_name.as_deref().map(str::trim)
+            && !model_name.is_empty()
+        {
+            select = select.filter(model_pricing::Column::ModelName.contains(model_name));
+        }
+
+        let rows = select
+            .find_with_related(ModelPricingTiers)
+            .all(self.db.as_ref())
+            .await
+            .context("获取模型定价列表失败")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(pricing, tiers)| convert_to_item(pricing, tiers, timezone))
+            .collect())
+    }
+
+    /// 获取单个模型定价（含阶梯）
+    pub async fn get(&self, id: i32, timezone: Tz) -> Result<ModelPricingItem> {
+        let pricing = load_pricing(self.db.as_ref(), id).await?;
+        let tiers = load_tiers(self.db.as_ref(), id).await?;
+        Ok(convert_to_item(pricing, tiers, timezone))
+    }
+
+    /// 创建模型定价及其阶梯
+    pub async fn create(
+        &self,
+        auth: &AuthContext,
+        request: &CreateModelPricingRequest,
+        timezone: Tz,
+    ) -> Result<ModelPricingItem> {
+        ensure_admin(auth)?;
+        let model_name = validate_model_name(&request.model_name)?;
+        let cost_currency = validate_currency(request.cost_currency.as_deref().unwrap_or("USD"))?;
+        validate_tiers(&request.tiers)?;
+
+        ProviderTypes::find_by_id(request.provider_type_id)
+            .one(self.db.as_ref())
+            .await
+            .context("获取服务商类型失败")?
+            .ok_or_else(|| AuthError::Message("服务商类型不存在".to_string()))?;
+        ensure_unique_model(
+            self.db.as_ref(),
+            request.provider_type_id,
+            &model_name,
+            None,
+        )
+        .await?;
+
+        let txn = self.db.begin().await.context("开启模型定价事务失败")?;
+        let now = chrono::Utc::now().naive_utc();
+        let pricing = model_pricing::ActiveModel {
+            provider_type_id: Set(request.provider_type_id),
+            model_name: Set(model_name),
+            description: Set(request.description.clone()),
+            cost_currency: Set(cost_currency),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .context("创建模型定价失败")?;
+        insert_tiers(&txn, pricing.id, &request.tiers).await?;
+        txn.commit().await.context("提交模型定价事务失败")?;
+
+        invalidate_pricing_cache();
+        self.get(pricing.id, timezone).await
+    }
+
+    /// 更新模型定价；传入 `tiers` 时整体替换阶梯
+    pub async fn update(
+        &self,
+        auth: &AuthContext,
+        id: i32,
+        request: &UpdateModelPricingRequest,
+        timezone: Tz,
+    ) -> Result<ModelPricingItem> {
+        ensure_admin(auth)?;
+        let existing = load_pricing(self.db.as_ref(), id).await?;
+        if let Some(tiers) = &request.tiers {
+            validate_tiers(tiers)?;
+        }
+
+        let provider_type_id = existing.provider_type_id;
+        let mut active: model_pricing::ActiveModel = existing.into();
+        if let Some(model_name) = &request.model_name {
+            let model_name = validate_model_name(model_name)?;
+            ensure_unique_model(self.db.as_ref(), provider_type_id, &model_name, Some(id)).await?;
+            active.model_name = Set(model_name);
+        }
+        if let Some(description) = &request.description {
+            active.description = Set(Some(description.clone()));
+        }
+        if let Some(cost_currency) = &request.cost_currency {
+            active.cost_currency = Set(validate_currency(cost_currency)?);
+        }
+        active.updated_at = Set(chrono::Utc::now().naive_utc());
+
+        let txn = self.db.begin().await.context("开启模型定价事务失败")?;
+        active.update(&txn).await.context("更新模型定价失败")?;
+        if let Some(tiers) = &request.tiers {
+            delete_tiers(&txn, id).await?;
+            insert_tiers(&txn, id, tiers).await?;
+        }
+        txn.commit().await.context("提交模型定价事务失败")?;
+
+        invalidate_pricing_cache();
+        self.get(id, timezone).await
+    }
+
+    /// 删除模型定价及其阶梯
+    pub async fn delete(&self, auth: &AuthContext, id: i32) -> Result<()> {
+        ensure_admin(auth)?;
+        load_pricing(self.db.as_ref(), id).await?;
+
+        let txn = self.db.begin().await.context("开启模型定价事务失败")?;
+        delete_tiers(&txn, id).await?;
+        ModelPricing::delete_by_id(id)
+            .exec(&txn)
+            .await
+            .context("删除模型定价失败")?;
+        txn.commit().await.context("提交模型定价事务失败")?;
+
+        invalidate_pricing_cache();
+        Ok(())
+    }
+
+    /// 为模型新增一个阶梯，返回更新后的模型定价
+    pub async fn create_tier(
+        &self,
+        auth: &AuthContext,
+        request: &CreatePricingTierRequest,
+        timezone: Tz,
+    ) -> Result<ModelPricingItem> {
+        ensure_admin(auth)?;
+        let pricing_id = request.model_pricing_id;
+        load_pricing(self.db.as_ref(), pricing_id).await?;
+
+        let tier = PricingTierInput {
+            token_type: request.token_type.clone(),
+            min_tokens: request.min_tokens,
+            max_tokens: request.max_tokens,
+            price_per_token: request.price_per_token,
+        };
+        let mut tiers = existing_tier_inputs(self.db.as_ref(), pricing_id, None).await?;
+        tiers.push(tier.clone());
+        validate_tiers(&tiers)?;
+
+        insert_tiers(self.db.as_ref(), pricing_id, std::slice::from_ref(&tier)).await?;
+        invalidate_pricing_cache();
+        self.get(pricing_id, timezone).await
+    }
+
+    /// 修改一个阶梯，返回更新后的模型定价
+    pub async fn update_tier(
+        &self,
+        auth: &AuthContext,
+        tier_id: i32,
+        request: &PricingTierInput,
+        timezone: Tz,
+    ) -> Result<ModelPricingItem> {
+        ensure_admin(auth)?;
+        let existing = load_tier(self.db.as_ref(), tier_id).await?;
+        let pricing_id = existing.model_pricing_id;
+
+        let mut tiers = existing_tier_inputs(self.db.as_ref(), pricing_id, Some(tier_id)).await?;
+        tiers.push(request.clone());
+        validate_tiers(&tiers)?;
+
+        let mut active: model_pricing_tiers::ActiveModel = existing.into();
+        active.token_type = Set(request.token_type.trim().to_string());
+        active.min_tokens = Set(request.min_tokens);
+        active.max_tokens = Set(request.max_tokens);
+        active.price_per_token = Set(request.price_per_token);
+        active.updated_at = Set(chrono::Utc::now().naive_utc());
+        active
+            .update(self.db.as_ref())
+            .await
+            .context("更新定价阶梯失败")?;
+
+        invalidate_pricing_cache();
+        self.get(pricing_id, timezone).await
+    }
+
+    /// 删除一个阶梯，返回更新后的模型定价
+    pub async fn delete_tier(
+        &self,
+        auth: &AuthContext,
+        tier_id: i32,
+        timezone: Tz,
+    ) -> Result<ModelPricingItem> {
+        ensure_admin(auth)?;
+        let existing = load_tier(self.db.as_ref(), tier_id).await?;
+        let pricing_id = existing.model_pricing_id;
+
+        let tiers = existing_tier_inputs(self.db.as_ref(), pricing_id, Some(tier_id)).await?;
+        validate_tiers(&tiers)?;
+
+        ModelPricingTiers::delete_by_id(tier_id)
+            .exec(self.db.as_ref())
+            .await
+            .context("删除定价阶梯失败")?;
+
+        invalidate_pricing_cache();
+        self.get(pricing_id, timezone).await
+    }
+}
+
+/// 校验阶梯输入：token 类型与单价合法，且每种 token 类型的阶梯连续（见 [`validate_tier_ranges`]）
+pub fn validate_tiers(tiers: &[PricingTierInput]) -> Result<()> {
+    for tier in tiers {
+        let token_type = tier.token_type.trim();
+        ensure!(
+            TOKEN_TYPES.contains(&token_type),
+            AuthError::Message(format!(
+                "不支持的 token_type: {token_type}（可选: {}）",
+                TOKEN_TYPES.join(", ")
+            ))
+        );
+        ensure!(
+            tier.price_per_token.is_finite() && tier.price_per_token >= 0.0,
+            AuthError::Message(format!("{token_type} 阶梯单价必须为非负数"))
+        );
+    }
+    validate_tier_ranges(
+        tiers
+            .iter()
+            .map(|tier| (tier.token_type.trim(), tier.min_tokens, tier.max_tokens)),
+    )
+}
+
+// =========================
+// 私有辅助函数
+// =========================
+
+fn ensure_admin(auth: &AuthContext) -> Result<()> {
+    ensure!(
+        auth.is_admin,
+        AuthError::PermissionDenied {
+            required: "admin".to_string(),
+            actual: "user".to_string(),
+        }
+    );
+    Ok(())
+}
+
+fn validate_model_name(model_name: &str) -> Result<String> {
+    let model_name = model_name.trim();
+    ensure!(
+        !model_name.is_empty() && model_name.len() <= 100,
+        AuthError::Message("model_name 不能为空且长度不超过100".to_string())
+    );
+    Ok(model_name.to_string())
+}
+
+fn validate_currency(cost_currency: &str) -> Result<String> {
+    let cost_currency = cost_currency.trim().to_uppercase();
+    ensure!(
+        !cost_currency.is_empty() && cost_currency.len() <= 10,
+        AuthError::Message("cost_currency 不能为空且长度不超过10".to_string())
+    );
+    Ok(cost_currency)
+}
+
+async fn ensure_unique_model(
+    db: &DatabaseConnection,
+    provider_type_id: i32,
+    model_name: &str,
+    exclude_id: Option<i32>,
+) -> Result<()> {
+    let mut select = ModelPricing::find()
+        .filter(model_pricing::Column::ProviderTypeId.eq(provider_type_id))
+        .filter(model_pricing::Column::ModelName.eq(model_name));
+    if let Some(exclude_id) = exclude_id {
+        select = select.filter(model_pricing::Column::Id.ne(exclude_id));
+    }
+    let existing = select.one(db).await.context("检查模型定价是否重复失败")?;
+    ensure!(
+        existing.is_none(),
+        AuthError::Message(format!("ModelPricing conflict: {model_name}"))
+    );
+    Ok(())
+}
+
+async fn load_pricing(db: &DatabaseConnection, id: i32) -> Result<model_pricing::Model> {
+    ModelPricing::find_by_id(id)
+        .one(db)
+        .await
+        .context("获取模型定价失败")?
+        .ok_or_else(|| {
+            ProxyError::Authentication(AuthError::Message(format!("ModelPricing not found: {id}")))
+        })
+}
+
+async fn load_tier(db: &DatabaseConnection, tier_id: i32) -> Result<model_pricing_tiers::Model> {
+    ModelPricingTiers::find_by_id(tier_id)
+        .one(db)
+        .await
+        .context("获取定价阶梯失败")?
+        .ok_or_else(|| {
+            ProxyError::Authentication(AuthError::Message(format!(
+                "PricingTier not found: {tier_id}"
+            )))
+        })
+}
+
+async fn load_tiers(
+    db: &DatabaseConnection,
+    pricing_id: i32,
+) -> Result<Vec<model_pricing_tiers::Model>> {
+    ModelPricingTiers::find()
+        .filter(model_pricing_tiers::Column::ModelPricingId.eq(pricing_id))
+        .all(db)
+        .await
+        .context("获取定价阶梯失败")
+}
+
+/// 读取模型现有阶梯（可排除指定阶梯），用于校验修改后的完整阶梯集合
+async fn existing_tier_inputs(
+    db: &DatabaseConnection,
+    pricing_id: i32,
+    exclude_tier_id: Option<i32>,
+) -> Result<Vec<PricingTierInput>> {
+    Ok(load_tiers(db, pricing_id)
+        .await?
+        .into_iter()
+        .filter(|tier| Some(tier.id) != exclude_tier_id)
+        .map(|tier| PricingTierInput {
+            token_type: tier.token_type,
+            min_tokens: tier.min_tokens,
+            max_tokens: tier.max_tokens,
+            price_per_token: tier.price_per_token,
+        })
+        .collect())
+}
+
+async fn insert_tiers<C: ConnectionTrait>(
+    db: &C,
+    pricing_id: i32,
+    tiers: &[PricingTierInput],
+) -> Result<()> {
+    if tiers.is_empty() {
+        return Ok(());
+    }
+    let now = chrono::Utc::now().naive_utc();
+    let models = tiers.iter().map(|tier| model_pricing_tiers::ActiveModel {
+        model_pricing_id: Set(pricing_id),
+        token_type: Set(tier.token_type.trim().to_string()),
+        min_tokens: Set(tier.min_tokens),
+        max_tokens: Set(tier.max_tokens),
+        price_per_token: Set(tier.price_per_token),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    });
+    ModelPricingTiers::insert_many(models)
+        .exec(db)
+        .await
+        .context("创建定价阶梯失败")?;
+    Ok(())
+}
+
+async fn delete_tiers<C: ConnectionTrait>(db: &C, pricing_id: i32) -> Result<()> {
+    ModelPricingTiers::delete_many()
+        .filter(model_pricing_tiers::Column::ModelPricingId.eq(pricing_id))
+        .exec(db)
+        .await
+        .context("删除定价阶梯失败")?;
+    Ok(())
+}
+
+fn convert_to_item(
+    pricing: model_pricing::Model,
+    mut tiers: Vec<model_pricing_tiers::Model>,
+    timezone: Tz,
+) -> ModelPricingItem {
+    tiers.sort_by(|a, b| {
+        a.token_type
+            .cmp(&b.token_type)
+            .then(a.min_tokens.cmp(&b.min_tokens))
+    });
+    ModelPricingItem {
+        id: pricing.id,
+        provider_type_id: pricing.provider_type_id,
+        model_name: pricing.model_name,
+        description: pricing.description,
+        cost_currency: pricing.cost_currency,
+        tiers: tiers
+            .into_iter()
+            .map(|tier| PricingTierItem {
+                id: tier.id,
+                token_type: tier.token_type,
+                min_tokens: tier.min_tokens,
+                max_tokens: tier.max_tokens,
+                price_per_token: tier.price_per_token,
+            })
+            .collect(),
+        created_at: timezone_utils::format_naive_utc_for_response(&pricing.created_at, &timezone),
+        updated_at: timezone_utils::format_naive_utc_for_response(&pricing.updated_at, &timezone),
+    }
+}
@@ -0,0 +1,179 @@
+//! # 用户服务商封禁管理服务
+//!
+//! 管理员按用户禁用指定服务商。代理端在确定服务商后查询封禁表，命中即以 403 拒绝。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::ensure;
+use crate::error::{Context, ProxyError, Result, auth::AuthError};
+use crate::management::middleware::AuthContext;
+
+use entity::{
+    provider_types::{self, Entity as ProviderTypes},
+    user_provider_blocks::{self, Entity as UserProviderBlocks},
+    users::Entity as Users,
+};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+
+// =========================
+// 数据结构定义 (DTOs)
+// =========================
+
+/// 设置用户封禁的服务商（整体替换）
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SetProviderBlocksRequest {
+    #[serde(default)]
+    pub provider_type_ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderBlockItem {
+    pub provider_type_id: i32,
+    pub provider_name: String,
+    pub display_name: String,
+}
+
+// =========================
+// 服务实现
+// =========================
+
+#[derive(Clone)]
+pub struct ProviderBlocksService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl ProviderBlocksService {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// 列出用户被禁用的服务商
+    pub async fn list(&self, auth: &AuthContext, user_id: i32) -> Result<Vec<ProviderBlockItem>> {
+        ensure_admin(auth)?;
+        ensure_user_exists(self.db.as_ref(), user_id).await?;
+
+        let rows = UserProviderBlocks::find()
+            .filter(user_provider_blocks::Column::UserId.eq(user_id))
+            .order_by_asc(user_provider_blocks::Column::ProviderTypeId)
+            .find_also_related(ProviderTypes)
+            .all(self.db.as_ref())
+            .await
+            .context("获取用户服务商封禁列表失败")?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(block, provider)| {
+                provider.map(|provider| ProviderBlockItem {
+                    provider_type_id: block.provider_type_id,
+                    provider_name: provider.name,
+                    display_name: provider.display_name,
+                })
+            })
+            .collect())
+    }
+
+    /// 整体替换用户被禁用的服务商；传入空列表即解除全部封禁
+    pub async fn set(
+        &self,
+        auth: &AuthContext,
+        user_id: i32,
+        request: &SetProviderBlocksRequest,
+    ) -> Result<Vec<ProviderBlockItem>> {
+        ensure_admin(auth)?;
+        ensure_user_exists(self.db.as_ref(), user_id).await?;
+
+        let provider_type_ids: BTreeSet<i32> = request.provider_type_ids.iter().copied().collect();
+        if !provider_type_ids.is_empty() {
+            let found = ProviderTypes::find()
+                .filter(provider_types::Column::Id.is_in(provider_type_ids.iter().copied()))
+                .count(self.db.as_ref())
+                .await
+                .context("获取服务商类型失败")?;
+            ensure!(
+                usize::try_from(found).is_ok_and(|found| found == provider_type_ids.len()),
+                AuthError::Message("存在不存在的服务商类型".to_string())
+            );
+        }
+
+        let txn = self.db.begin().await.context("开启服务商封禁事务失败")?;
+        UserProviderBlocks::delete_many()
+            .filter(user_provider_blocks::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await
+            .context("清除用户服务商封禁失败")?;
+        if !provider_type_ids.is_empty() {
+            let now = chrono::Utc::now().naive_utc();
+            let models = provider_type_ids.iter().map(|&provider_type_id| {
+                user_provider_blocks::ActiveModel {
+                    user_id: Set(user_id),
+                    provider_type_id: Set(provider_type_id),
+                    created_at: Set(now),
+                    ..Default::default()
+                }
+            });
+            UserProviderBlocks::insert_many(models)
+                .exec(&txn)
+                .await
+                .context("创建用户服务商封禁失败")?;
+        }
+        txn.commit().await.context("提交服务商封禁事务失败")?;
+
+        self.list(auth, user_id).await
+    }
+
+    /// 解除用户对单个服务商的封禁
+    pub async fn remove(
+        &self,
+        auth: &AuthContext,
+        user_id: i32,
+        provider_type_id: i32,
+    ) -> Result<Vec<ProviderBlockItem>> {
+        ensure_admin(auth)?;
+        ensure_user_exists(self.db.as_ref(), user_id).await?;
+
+        let deleted = UserProviderBlocks::delete_many()
+            .filter(user_provider_blocks::Column::UserId.eq(user_id))
+            .filter(user_provider_blocks::Column::ProviderTypeId.eq(provider_type_id))
+            .exec(self.db.as_ref())
+            .await
+            .context("解除用户服务商封禁失败")?;
+        ensure!(
+            deleted.rows_affected > 0,
+            AuthError::Message(format!("ProviderBlock not found: {provider_type_id}"))
+        );
+
+        self.list(auth, user_id).await
+    }
+}
+
+// =========================
+// 私有辅助函数
+// =========================
+
+fn ensure_admin(auth: &AuthContext) -> Result<()> {
+    ensure!(
+        auth.is_admin,
+        AuthError::PermissionDenied {
+            required: "admin".to_string(),
+            actual: "user".to_string(),
+        }
+    );
+    Ok(())
+}
+
+async fn ensure_user_exists(db: &DatabaseConnection, user_id: i32) -> Result<()> {
+    Users::find_by_id(user_id)
+        .one(db)
+        .await
+        .context("获取用户失败")?
+        .ok_or_else(|| {
+            ProxyError::Authentication(AuthError::Message(format!("User not found: {user_id}")))
+        })?;
+    Ok(())
+}
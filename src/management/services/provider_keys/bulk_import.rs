@@ -0,0 +1,247 @@
+//! # 提供商密钥批量导入
+//!
+//! 支持 JSON 数组或带表头的 CSV 文本，逐行校验并创建，返回逐行结果报告：
+//! 名称重复（与已有密钥或同批次前面的行）与校验失败只影响所在行，不中断整个批次。
+
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    ProxyError,
+    error::{Result, auth::AuthError},
+};
+
+use super::models::CreateProviderKeyRequest;
+
+/// 单批次最多导入的行数
+pub const MAX_BULK_IMPORT_ROWS: usize = 500;
+
+/// CSV 中按整数解析的列
+const CSV_INTEGER_COLUMNS: &[&str] = &[
+    "provider_type_id",
+    "weight",
+    "max_requests_per_minute",
+    "max_tokens_prompt_per_minute",
+    "max_requests_per_day",
+];
+
+/// CSV 中按布尔值解析的列
+const CSV_BOOLEAN_COLUMNS: &[&str] = &["is_active"];
+
+/// 导入数据格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkImportFormat {
+    Json,
+    Csv,
+}
+
+impl BulkImportFormat {
+    /// 按 `Content-Type` 判断格式，未指定或无法识别时按 JSON 处理
+    #[must_use]
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        match content_type {
+            Some(value) if value.trim().to_ascii_lowercase().starts_with("text/csv") => Self::Csv,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// 单行导入结果状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkImportRowStatus {
+    Created,
+    Duplicate,
+    Failed,
+}
+
+/// 单行导入结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkImportRowResult {
+    /// 数据行序号（从 1 开始，不含 CSV 表头）
+    pub row: usize,
+    pub name: Option<String>,
+    pub status: BulkImportRowStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 批量导入报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BulkImportReport {
+    pub total: usize,
+    pub created: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    pub results: Vec<BulkImportRowResult>,
+}
+
+impl BulkImportReport {
+    pub(super) fn push(&mut self, result: BulkImportRowResult) {
+        self.total += 1;
+        match result.status {
+            BulkImportRowStatus::Created => self.created += 1,
+            BulkImportRowStatus::Duplicate => self.duplicates += 1,
+            BulkImportRowStatus::Failed => self.failed += 1,
+        }
+        self.results.push(result);
+    }
+}
+
+/// 待导入的一行：解析失败时保留错误信息，由报告逐行返回
+#[derive(Debug)]
+pub struct BulkImportRow {
+    pub name: Option<String>,
+    pub payload: std::result::Result<CreateProviderKeyRequest, String>,
+}
+
+impl BulkImportRow {
+    fn from_value(value: Value) -> Self {
+        let name = value
+            .get("name")
+            .and_then(Value::as_str)
+            .map(ToString::to_string);
+        let payload = serde_json::from_value(value).map_err(|err| format!("字段格式错误: {err}"));
+        Self { name, payload }
+    }
+}
+
+/// 解析导入内容为逐行数据；整体格式错误（非数组、缺少表头、超出行数上限）直接返回错误
+pub fn parse_bulk_import(body: &str, format: BulkImportFormat) -> Result<Vec<BulkImportRow>> {
+    let rows = match format {
+        BulkImportFormat::Json => parse_json_rows(body)?,
+        BulkImportFormat::Csv => parse_csv_rows(body)?,
+    };
+    if rows.is_empty() {
+        return Err(import_error("导入内容为空".to_string()));
+    }
+    if rows.len() > MAX_BULK_IMPORT_ROWS {
+        return Err(import_error(format!(
+            "单次最多导入 {MAX_BULK_IMPORT_ROWS} 条，当前 {} 条",
+            rows.len()
+        )));
+    }
+    Ok(rows.into_iter().map(BulkImportRow::from_value).collect())
+}
+
+fn parse_json_rows(body: &str) -> Result<Vec<Value>> {
+    match serde_json::from_str::<Value>(body) {
+        Ok(Value::Array(rows)) => Ok(rows),
+        Ok(_) => Err(import_error("JSON 导入内容必须是数组".to_string())),
+        Err(err) => Err(import_error(format!("JSON 解析失败: {err}"))),
+    }
+}
+
+fn parse_csv_rows(body: &str) -> Result<Vec<Value>> {
+    let mut lines = body
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty());
+    let header = lines
+        .next()
+        .map(split_csv_line)
+        .ok_or_else(|| import_error("CSV 缺少表头".to_string()))?;
+    let header: Vec<String> = header
+        .into_iter()
+        .map(|column| column.trim().to_ascii_lowercase())
+        .collect();
+    if !header.iter().any(|column| column == "name") {
+        return Err(import_error("CSV 表头缺少 name 列".to_string()));
+    }
+
+    Ok(lines
+        .map(|line| {
+            let fields = header
+                .iter()
+                .zip(split_csv_line(line))
+                .filter_map(|(column, cell)| {
+                    let cell = cell.trim();
+                    (!cell.is_empty()).then(|| (column.clone(), csv_cell_value(column, cell)))
+                })
+                .collect::<Map<String, Value>>();
+            Value::Object(fields)
+        })
+        .collect())
+}
+
+/// 按列类型转换单元格，无法转换时保留原文，由逐行反序列化报告错误
+fn csv_cell_value(column: &str, cell: &str) -> Value {
+    if CSV_INTEGER_COLUMNS.contains(&column)
+        && let Ok(number) = cell.parse::<i64>()
+    {
+        return Value::Number(Number::from(number));
+    }
+    if CSV_BOOLEAN_COLUMNS.contains(&column) {
+        match cell.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" => return Value::Bool(true),
+            "false" | "0" | "no" => return Value::Bool(false),
+            _ => {}
+        }
+    }
+    Value::String(cell.to_string())
+}
+
+/// 拆分一行 CSV，支持双引号包裹（含转义的 `""`）
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current)),
+            _ => current.push(ch),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+fn import_error(message: String) -> ProxyError {
+    ProxyError::Authentication(AuthError::Message(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_are_typed_and_keep_bad_rows_for_reporting() {
+        let csv = "provider_type_id,name,api_key,auth_type,weight,is_active\r\n\
+                   1,\"Key, primary\",sk-1,api_key,3,true\n\
+                   \n\
+                   1,Key two,sk-2,api_key,heavy,\n";
+        let rows = parse_bulk_import(csv, BulkImportFormat::Csv).expect("parse csv");
+        assert_eq!(rows.len(), 2);
+
+        let first = rows[0].payload.as_ref().expect("first row valid");
+        assert_eq!(first.name, "Key, primary");
+        assert_eq!(first.weight, Some(3));
+        assert_eq!(first.is_active, Some(true));
+
+        assert_eq!(rows[1].name.as_deref(), Some("Key two"));
+        assert!(rows[1].payload.is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_batches() {
+        assert_eq!(
+            BulkImportFormat::from_content_type(Some("text/csv; charset=utf-8")),
+            BulkImportFormat::Csv
+        );
+        assert_eq!(
+            BulkImportFormat::from_content_type(None),
+            BulkImportFormat::Json
+        );
+        assert!(parse_bulk_import("{\"name\": \"x\"}", BulkImportFormat::Json).is_err());
+        assert!(parse_bulk_import("[]", BulkImportFormat::Json).is_err());
+        assert!(parse_bulk_import("api_key\nsk-1", BulkImportFormat::Csv).is_err());
+    }
+}
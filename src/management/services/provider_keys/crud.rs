@@ -11,8 +11,9 @@ use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, Qu
 
 use crate::{
     ProxyError,
-    auth::types::AuthStatus,
+    auth::types::{AuthStatus, AuthType},
     error::{Context, Result, auth::AuthError},
+    key_pool::key_tags::tags_to_json,
 };
 
 use super::models::{CreateProviderKeyRequest, UpdateProviderKeyRequest};
@@ -68,6 +69,23 @@ pub async fn ensure_unique_provider_key(
     }
 }
 
+/// 检查同一服务商下是否已存在同名密钥（含已软删除的密钥，与创建时的唯一性校验一致）
+pub async fn provider_key_name_taken(
+    db: &DatabaseConnection,
+    user_id: i32,
+    provider_type_id: i32,
+    name: &str,
+) -> Result<bool> {
+    let existing = UserProviderKey::find()
+        .filter(user_provider_keys::Column::UserId.eq(user_id))
+        .filter(user_provider_keys::Column::Name.eq(name))
+        .filter(user_provider_keys::Column::ProviderTypeId.eq(provider_type_id))
+        .one(db)
+        .await
+        .context("Failed to check existing provider key")?;
+    Ok(existing.is_some())
+}
+
 /// 插入提供商密钥记录
 pub async fn insert_provider_key_record(
     db: &DatabaseConnection,
@@ -75,7 +93,7 @@ pub async fn insert_provider_key_record(
     payload: &CreateProviderKeyRequest,
     final_project_id: Option<String>,
     health_status: String,
-    auth_type: &str,
+    auth_type: AuthType,
 ) -> Result<user_provider_keys::Model> {
     let new_provider_key = user_provider_keys::ActiveModel {
         user_id: Set(user_id),
@@ -90,6 +108,7 @@ pub async fn insert_provider_key_record(
         max_requests_per_day: Set(payload.max_requests_per_day),
         is_active: Set(payload.is_active.unwrap_or(true)),
         project_id: Set(final_project_id),
+        tags: Set(tags_to_json(payload.tags.as_deref().unwrap_or_default())?),
         health_status: Set(health_status),
         created_at: Set(Utc::now().naive_utc()),
         updated_at: Set(Utc::now().naive_utc()),
@@ -102,7 +121,7 @@ pub async fn insert_provider_key_record(
         .context("Failed to create provider key")
 }
 
-/// 加载现有密钥
+/// 加载现有密钥（不含已软删除的密钥）
 pub async fn load_existing_key(
     db: &DatabaseConnection,
     key_id: i32,
@@ -111,6 +130,7 @@ pub async fn load_existing_key(
     UserProviderKey::find()
         .filter(user_provider_keys::Column::Id.eq(key_id))
         .filter(user_provider_keys::Column::UserId.eq(user_id))
+        .filter(user_provider_keys::Column::DeletedAt.is_null())
         .one(db)
         .await
         .context("Failed to find provider key")?
@@ -130,6 +150,7 @@ pub async fn load_key_with_provider(
     UserProviderKey::find()
         .filter(user_provider_keys::Column::Id.eq(key_id))
         .filter(user_provider_keys::Column::UserId.eq(user_id))
+        .filter(user_provider_keys::Column::DeletedAt.is_null())
         .find_also_related(ProviderType)
         .one(db)
         .await
@@ -146,7 +167,7 @@ pub async fn persist_updated_key(
     db: &DatabaseConnection,
     existing_key: user_provider_keys::Model,
     payload: &UpdateProviderKeyRequest,
-    auth_type: &str,
+    auth_type: AuthType,
 ) -> Result<user_provider_keys::Model> {
     let mut active_model: user_provider_keys::ActiveModel = existing_key.into();
     active_model.provider_type_id = Set(payload.provider_type_id);
@@ -159,6 +180,7 @@ pub async fn persist_updated_key(
     active_model.max_requests_per_day = Set(payload.max_requests_per_day);
     active_model.is_active = Set(payload.is_active.unwrap_or(true));
     active_model.project_id = Set(payload.project_id.clone());
+    active_model.tags = Set(tags_to_json(payload.tags.as_deref().unwrap_or_default())?);
     active_model.updated_at = Set(Utc::now().naive_utc());
 
     active_model
@@ -167,12 +189,51 @@ pub async fn persist_updated_key(
         .context("Failed to update provider key")
 }
 
-/// 删除密钥
-pub async fn delete_key(db: &DatabaseConnection, key: user_provider_keys::Model) -> Result<()> {
-    let active_model: user_provider_keys::ActiveModel = key.into();
+/// 加载已软删除的密钥
+pub async fn load_deleted_key(
+    db: &DatabaseConnection,
+    key_id: i32,
+    user_id: i32,
+) -> Result<user_provider_keys::Model> {
+    UserProviderKey::find()
+        .filter(user_provider_keys::Column::Id.eq(key_id))
+        .filter(user_provider_keys::Column::UserId.eq(user_id))
+        .filter(user_provider_keys::Column::DeletedAt.is_not_null())
+        .one(db)
+        .await
+        .context("Failed to find deleted provider key")?
+        .ok_or_else(|| {
+            ProxyError::Authentication(AuthError::Message(format!(
+                "Deleted ProviderKey not found: {key_id}"
+            )))
+        })
+}
+
+/// 软删除密钥：记录删除时间，保留行以维持追踪记录的关联
+pub async fn soft_delete_key(
+    db: &DatabaseConnection,
+    key: user_provider_keys::Model,
+) -> Result<user_provider_keys::Model> {
+    let now = Utc::now().naive_utc();
+    let mut active_model: user_provider_keys::ActiveModel = key.into();
+    active_model.deleted_at = Set(Some(now));
+    active_model.updated_at = Set(now);
     active_model
-        .delete(db)
+        .update(db)
+        .await
+        .context("Failed to delete provider key")
+}
+
+/// 恢复已软删除的密钥
+pub async fn restore_key(
+    db: &DatabaseConnection,
+    key: user_provider_keys::Model,
+) -> Result<user_provider_keys::Model> {
+    let mut active_model: user_provider_keys::ActiveModel = key.into();
+    active_model.deleted_at = Set(None);
+    active_model.updated_at = Set(Utc::now().naive_utc());
+    active_model
+        .update(db)
         .await
-        .context("Failed to delete provider key")?;
-    Ok(())
+        .context("Failed to restore provider key")
 }
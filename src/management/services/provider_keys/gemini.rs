@@ -6,7 +6,10 @@ use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, Qu
 use tokio::spawn;
 
 use crate::{
-    auth::{gemini_code_assist_client::GeminiCodeAssistClient, types::AuthStatus},
+    auth::{
+        gemini_code_assist_client::GeminiCodeAssistClient,
+        types::{AuthStatus, AuthType},
+    },
     error::{Context, Result},
     key_pool::types::ApiKeyHealthStatus,
     lerror, linfo,
@@ -22,7 +25,6 @@ use entity::{
 use super::models::PrepareGeminiContext;
 
 const GEMINI_PROVIDER_NAME: &str = "gemini";
-const OAUTH_AUTH_TYPE: &str = "oauth";
 
 /// 准备 Gemini 上下文
 pub async fn prepare_gemini_context(
@@ -31,6 +33,7 @@ pub async fn prepare_gemini_context(
     api_key: Option<&String>,
     project_id: Option<String>,
     provider_type_name: &str,
+    auth_type: AuthType,
 ) -> Result<PrepareGeminiContext> {
     let mut context = PrepareGeminiContext {
         final_project_id: project_id,
@@ -38,7 +41,7 @@ pub async fn prepare_gemini_context(
         needs_auto_get_project_id_async: false,
     };
 
-    if !is_gemini_oauth_flow(OAUTH_AUTH_TYPE, provider_type_name) {
+    if !is_gemini_oauth_flow(auth_type, provider_type_name) {
         return Ok(context);
     }
 
@@ -74,8 +77,8 @@ pub async fn prepare_gemini_context(
 }
 
 /// 检查是否为 Gemini OAuth 流程
-fn is_gemini_oauth_flow(auth_type: &str, provider_type_name: &str) -> bool {
-    auth_type == OAUTH_AUTH_TYPE && provider_type_name == GEMINI_PROVIDER_NAME
+fn is_gemini_oauth_flow(auth_type: AuthType, provider_type_name: &str) -> bool {
+    auth_type == AuthType::OAuth && provider_type_name == GEMINI_PROVIDER_NAME
 }
 
 /// 获取已授权的会话
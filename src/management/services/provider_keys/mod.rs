@@ -8,7 +8,9 @@
 //! - `gemini`: Gemini 特定逻辑
 //! - `statistics`: 统计查询
 //! - `service`: 核心服务编排
+//! - `bulk_import`: 批量导入解析与报告
 
+mod bulk_import;
 mod crud;
 mod gemini;
 mod models;
@@ -19,9 +21,13 @@ mod validation;
 
 // 重新导出公共接口
 pub use models::{
-    CreateProviderKeyRequest, DailyStats, PrepareGeminiContext, ProviderKeyUsageStats,
-    ProviderKeysListQuery, TrendData, TrendDataPoint, TrendQuery, UpdateProviderKeyRequest,
-    UserProviderKeyQuery,
+    CreateProviderKeyRequest, DailyStats, PatchProviderKeyRequest, PrepareGeminiContext,
+    ProviderKeyUsageStats, ProviderKeysListQuery, SetProviderKeyTagsRequest, TrendData,
+    TrendDataPoint, TrendQuery, UpdateProviderKeyRequest, UserProviderKeyQuery,
 };
 
+pub use bulk_import::{
+    BulkImportFormat, BulkImportReport, BulkImportRow, BulkImportRowResult, BulkImportRowStatus,
+    MAX_BULK_IMPORT_ROWS, parse_bulk_import,
+};
 pub use service::ProviderKeyService;
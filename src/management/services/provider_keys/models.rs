@@ -2,9 +2,14 @@
 //!
 //! 定义提供商密钥相关的请求和响应数据结构。
 
+use entity::user_provider_keys;
 use serde::{Deserialize, Serialize};
 
-use crate::{key_pool::types::ApiKeyHealthStatus, types::ProviderTypeId};
+use crate::{
+    key_pool::{key_tags::key_tags, types::ApiKeyHealthStatus},
+    management::services::service_apis::NullableField,
+    types::ProviderTypeId,
+};
 
 /// 提供商密钥列表查询参数
 #[derive(Debug, Deserialize)]
@@ -29,6 +34,9 @@ pub struct CreateProviderKeyRequest {
     pub max_requests_per_day: Option<i32>,
     pub is_active: Option<bool>,
     pub project_id: Option<String>,
+    /// 密钥标签（如 `prod`、`high-quota`）
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
 }
 
 /// 更新提供商密钥请求
@@ -44,6 +52,69 @@ pub struct UpdateProviderKeyRequest {
     pub max_requests_per_day: Option<i32>,
     pub is_active: Option<bool>,
     pub project_id: Option<String>,
+    /// 密钥标签（如 `prod`、`high-quota`）
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+/// 部分更新提供商密钥请求（PATCH）
+///
+/// 仅修改请求中出现的字段；可空字段显式传 `null` 表示清空。
+#[derive(Debug, Default, Deserialize)]
+pub struct PatchProviderKeyRequest {
+    pub provider_type_id: Option<ProviderTypeId>,
+    pub name: Option<String>,
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub weight: NullableField<i32>,
+    #[serde(default)]
+    pub max_requests_per_minute: NullableField<i32>,
+    #[serde(default)]
+    pub max_tokens_prompt_per_minute: NullableField<i32>,
+    #[serde(default)]
+    pub max_requests_per_day: NullableField<i32>,
+    pub is_active: Option<bool>,
+    #[serde(default)]
+    pub project_id: NullableField<String>,
+    /// 密钥标签，`null` 表示清空
+    #[serde(default)]
+    pub tags: NullableField<Vec<String>>,
+}
+
+/// 设置密钥标签请求（整体替换，空数组表示清空）
+#[derive(Debug, Deserialize)]
+pub struct SetProviderKeyTagsRequest {
+    pub tags: Vec<String>,
+}
+
+impl PatchProviderKeyRequest {
+    /// 与现有密钥合并为完整的更新请求，未提供的字段沿用现值
+    #[must_use]
+    pub fn merge_into(&self, existing: &user_provider_keys::Model) -> UpdateProviderKeyRequest {
+        UpdateProviderKeyRequest {
+            provider_type_id: self.provider_type_id.unwrap_or(existing.provider_type_id),
+            name: self.name.clone().unwrap_or_else(|| existing.name.clone()),
+            api_key: Some(
+                self.api_key
+                    .clone()
+                    .unwrap_or_else(|| existing.api_key.clone()),
+            ),
+            auth_type: existing.auth_type.clone(),
+            weight: self.weight.resolve(existing.weight),
+            max_requests_per_minute: self
+                .max_requests_per_minute
+                .resolve(existing.max_requests_per_minute),
+            max_tokens_prompt_per_minute: self
+                .max_tokens_prompt_per_minute
+                .resolve(existing.max_tokens_prompt_per_minute),
+            max_requests_per_day: self
+                .max_requests_per_day
+                .resolve(existing.max_requests_per_day),
+            is_active: Some(self.is_active.unwrap_or(existing.is_active)),
+            project_id: self.project_id.resolve(existing.project_id.clone()),
+            tags: self.tags.resolve(Some(key_tags(existing))),
+        }
+    }
 }
 
 /// 密钥使用统计
@@ -120,3 +191,86 @@ pub struct PrepareGeminiContext {
     /// 是否需要异步获取 `project_id`
     pub needs_auto_get_project_id_async: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn existing_key() -> user_provider_keys::Model {
+        let now = Utc::now().naive_utc();
+        user_provider_keys::Model {
+            id: 7,
+            user_id: 1,
+            provider_type_id: 2,
+            api_key: "sk-existing-secret".to_string(),
+            auth_type: "api_key".to_string(),
+            name: "primary".to_string(),
+            weight: Some(1),
+            max_requests_per_minute: Some(60),
+            max_tokens_prompt_per_minute: None,
+            max_requests_per_day: Some(1000),
+            is_active: false,
+            health_status: "healthy".to_string(),
+            health_status_detail: None,
+            rate_limit_resets_at: None,
+            last_error_time: None,
+            auth_status: None,
+            expires_at: None,
+            last_auth_check: None,
+            project_id: Some("project-a".to_string()),
+            tags: None,
+            deleted_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn patch_weight_only_keeps_other_fields() {
+        let patch: PatchProviderKeyRequest = serde_json::from_str(r#"{"weight": 5}"#).unwrap();
+        let merged = patch.merge_into(&existing_key());
+
+        assert_eq!(merged.weight, Some(5));
+        assert_eq!(merged.name, "primary");
+        assert_eq!(merged.api_key.as_deref(), Some("sk-existing-secret"));
+        assert_eq!(merged.provider_type_id, 2);
+        assert_eq!(merged.max_requests_per_minute, Some(60));
+        assert_eq!(merged.max_requests_per_day, Some(1000));
+        assert_eq!(merged.is_active, Some(false));
+        assert_eq!(merged.project_id.as_deref(), Some("project-a"));
+    }
+
+    #[test]
+    fn patch_null_clears_nullable_field() {
+        let patch: PatchProviderKeyRequest =
+            serde_json::from_str(r#"{"max_requests_per_day": null, "name": "renamed"}"#).unwrap();
+        let merged = patch.merge_into(&existing_key());
+
+        assert_eq!(merged.max_requests_per_day, None);
+        assert_eq!(merged.name, "renamed");
+        assert_eq!(merged.weight, Some(1));
+    }
+
+    #[test]
+    fn patch_tags_replaces_or_keeps_tags() {
+        let mut existing = existing_key();
+        existing.tags = Some(serde_json::json!(["prod"]));
+
+        let untouched: PatchProviderKeyRequest = serde_json::from_str(r#"{"weight": 2}"#).unwrap();
+        assert_eq!(
+            untouched.merge_into(&existing).tags,
+            Some(vec!["prod".to_string()])
+        );
+
+        let replaced: PatchProviderKeyRequest =
+            serde_json::from_str(r#"{"tags": ["high-quota"]}"#).unwrap();
+        assert_eq!(
+            replaced.merge_into(&existing).tags,
+            Some(vec!["high-quota".to_string()])
+        );
+
+        let cleared: PatchProviderKeyRequest = serde_json::from_str(r#"{"tags": null}"#).unwrap();
+        assert_eq!(cleared.merge_into(&existing).tags, None);
+    }
+}
@@ -10,7 +10,8 @@ use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use crate::{
     auth::{
         api_key_oauth_state_service::ScheduledTokenRefresh,
-        api_key_oauth_token_refresh_task::ApiKeyOAuthTokenRefreshTask, types::AuthStatus,
+        api_key_oauth_token_refresh_task::ApiKeyOAuthTokenRefreshTask,
+        types::{AuthStatus, AuthType},
     },
     ensure_context,
     error::{Context, Result, management::ManagementError},
@@ -21,8 +22,6 @@ use crate::{
 
 use std::sync::Arc;
 
-const OAUTH_AUTH_TYPE: &str = "oauth";
-
 /// OAuth 辅助器
 pub struct OAuthHelper {
     pub db: DatabaseConnection,
@@ -30,16 +29,51 @@ pub struct OAuthHelper {
 }
 
 impl OAuthHelper {
-    /// 准备 OAuth 调度
+    /// 准备 OAuth 调度；会话已被其他密钥引用时其刷新计划已在队列中，不再重复调度
     pub async fn prepare_schedule(
         &self,
         session_id: Option<&String>,
         user_id: i32,
         key_id: Option<i32>,
     ) -> Result<Option<ScheduledTokenRefresh>> {
+        if let Some(session_id) = session_id.filter(|id| !id.is_empty())
+            && self.session_used_by_other_keys(session_id, key_id).await?
+        {
+            ldebug!(
+                "system",
+                LogStage::Scheduling,
+                LogComponent::OAuth,
+                "session_already_scheduled",
+                "OAuth session is shared with another key, skip scheduling",
+                user_id = user_id,
+                key_id = key_id,
+                session_id = session_id.as_str(),
+            );
+            return Ok(None);
+        }
         prepare_oauth_schedule(self.refresh_task.as_deref(), session_id, user_id, key_id).await
     }
 
+    /// 会话是否仍被其他未删除的密钥引用（不含 `key_id` 本身）
+    pub async fn session_used_by_other_keys(
+        &self,
+        session_id: &str,
+        key_id: Option<i32>,
+    ) -> Result<bool> {
+        let mut query = user_provider_keys::Entity::find()
+            .filter(user_provider_keys::Column::ApiKey.eq(session_id))
+            .filter(user_provider_keys::Column::AuthType.eq(AuthType::OAuth.as_str()))
+            .filter(user_provider_keys::Column::DeletedAt.is_null());
+        if let Some(key_id) = key_id {
+            query = query.filter(user_provider_keys::Column::Id.ne(key_id));
+        }
+        let other = query
+            .one(&self.db)
+            .await
+            .context("Failed to check OAuth session usage")?;
+        Ok(other.is_some())
+    }
+
     /// 入队 OAuth 调度
     pub async fn enqueue_schedule(
         &self,
@@ -65,29 +99,19 @@ impl OAuthHelper {
         user_id: i32,
         key_id: i32,
     ) {
-        cleanup_obsolete_session_internal(
-            self.refresh_task.as_deref(),
-            old_session_id,
-            updated_key,
-            user_id,
-            key_id,
-        )
-        .await;
+        cleanup_obsolete_session_internal(self, old_session_id, updated_key, user_id, key_id).await;
     }
 
-    /// 提取 OAuth 会话 ID
-    pub fn extract_session_id(key: &user_provider_keys::Model) -> Option<String> {
-        if key.auth_type == OAUTH_AUTH_TYPE && !key.api_key.is_empty() {
-            Some(key.api_key.clone())
-        } else {
-            None
-        }
+    /// 提取 OAuth 会话 ID；认证类型无法识别时返回错误
+    pub fn extract_session_id(key: &user_provider_keys::Model) -> Result<Option<String>> {
+        extract_oauth_session_id(key)
     }
 }
 
 /// 检查是否需要 OAuth 调度
-pub fn needs_oauth_schedule(auth_type: &str) -> bool {
-    auth_type == OAUTH_AUTH_TYPE
+#[must_use]
+pub const fn needs_oauth_schedule(auth_type: AuthType) -> bool {
+    matches!(auth_type, AuthType::OAuth)
 }
 
 /// 准备 OAuth 调度
@@ -195,7 +219,7 @@ async fn enqueue_oauth_schedule(
 
 /// 清理过时的 OAuth 会话
 async fn cleanup_obsolete_session_internal(
-    refresh_task: Option<&ApiKeyOAuthTokenRefreshTask>,
+    helper: &OAuthHelper,
     old_session_id: Option<String>,
     updated_key: &user_provider_keys::Model,
     user_id: i32,
@@ -205,15 +229,25 @@ async fn cleanup_obsolete_session_internal(
         return;
     };
 
-    let Some(task) = refresh_task else {
+    let Some(task) = helper.refresh_task.as_deref() else {
         return;
     };
 
-    let updated_session_id = extract_oauth_session_id(updated_key);
+    // 更新后的认证类型已在写入前校验，解析失败时按无会话处理
+    let updated_session_id = extract_oauth_session_id(updated_key).ok().flatten();
     if updated_session_id.as_deref() == Some(old_id.as_str()) {
         return;
     }
 
+    // 共享会话仍被其他密钥使用时保留刷新计划；查询失败时同样保留，避免误删
+    if helper
+        .session_used_by_other_keys(&old_id, Some(key_id))
+        .await
+        .unwrap_or(true)
+    {
+        return;
+    }
+
     if let Err(err) = task.remove_session(&old_id).await {
         lwarn!(
             "system",
@@ -229,12 +263,9 @@ async fn cleanup_obsolete_session_internal(
 }
 
 /// 提取 OAuth 会话 ID
-fn extract_oauth_session_id(key: &user_provider_keys::Model) -> Option<String> {
-    if key.auth_type == OAUTH_AUTH_TYPE && !key.api_key.is_empty() {
-        Some(key.api_key.clone())
-    } else {
-        None
-    }
+fn extract_oauth_session_id(key: &user_provider_keys::Model) -> Result<Option<String>> {
+    let auth_type = key.auth_type.parse::<AuthType>()?;
+    Ok((auth_type == AuthType::OAuth && !key.api_key.is_empty()).then(|| key.api_key.clone()))
 }
 
 /// 获取 OAuth 密钥的 access token
@@ -255,10 +286,10 @@ pub async fn get_access_token_for_key(
         })?;
 
     ensure_context!(
-        key_record.auth_type == OAUTH_AUTH_TYPE,
+        key_record.auth_type.parse::<AuthType>()? == AuthType::OAuth,
         ManagementError::InvalidKeyAuthType {
             key_id,
-            expected: OAUTH_AUTH_TYPE.to_string(),
+            expected: AuthType::OAuth.to_string(),
             actual: key_record.auth_type.clone(),
         },
         format!("自动获取 project_id 前校验 key 类型失败: key_id={key_id}, user_id={user_id}")
@@ -2,6 +2,9 @@
 //!
 //! 核心服务编排逻辑，协调各个子模块完成业务功能。
 
+use std::collections::HashSet;
+use std::sync::Arc;
+
 use chrono::{Duration, Utc};
 use entity::{user_provider_keys, user_service_apis, user_service_apis::Entity as UserServiceApi};
 use sea_orm::{
@@ -12,23 +15,29 @@ use serde_json::{Value, json};
 
 use crate::{
     ProxyError,
+    auth::{api_key_oauth_token_refresh_task::ApiKeyOAuthTokenRefreshTask, types::AuthType},
+    config::OAuthSessionPolicy,
     error::{Context, Result, auth::AuthError},
+    key_pool::key_tags::key_tags,
     lerror, linfo,
     logging::{LogComponent, LogStage},
     lwarn,
-    management::server::ManagementState,
+    management::{server::ManagementState, services::service_apis::NullableField},
     types::{TimezoneContext, timezone_utils},
 };
 
 use super::{
+    bulk_import::{BulkImportReport, BulkImportRow, BulkImportRowResult, BulkImportRowStatus},
     crud::{
-        delete_key, ensure_unique_provider_key, insert_provider_key_record, load_existing_key,
-        load_key_with_provider, load_provider_type_or_error, persist_updated_key,
+        ensure_unique_provider_key, insert_provider_key_record, load_deleted_key,
+        load_existing_key, load_key_with_provider, load_provider_type_or_error,
+        persist_updated_key, provider_key_name_taken, restore_key, soft_delete_key,
     },
     gemini::{prepare_gemini_context, spawn_gemini_project_task},
     models::{
-        CreateProviderKeyRequest, PrepareGeminiContext, ProviderKeysListQuery, TrendQuery,
-        UpdateProviderKeyRequest, UserProviderKeyQuery,
+        CreateProviderKeyRequest, PatchProviderKeyRequest, PrepareGeminiContext,
+        ProviderKeysListQuery, SetProviderKeyTagsRequest, TrendQuery, UpdateProviderKeyRequest,
+        UserProviderKeyQuery,
     },
     oauth::{OAuthHelper, needs_oauth_schedule},
     statistics::{
@@ -36,20 +45,20 @@ use super::{
         fetch_provider_keys_usage_stats, mask_api_key, rate_limit_remaining_seconds,
     },
     validation::{
-        ensure_unique_name, validate_create_payload, validate_oauth_session_for_creation,
-        validate_oauth_session_for_update, validate_update_requirements,
+        ensure_oauth_session_free_for_restore, ensure_unique_name, validate_create_payload,
+        validate_oauth_session_for_creation, validate_oauth_session_for_update,
+        validate_update_requirements,
     },
 };
 
 use crate::management::services::shared::ServiceResponse;
 
-const OAUTH_AUTH_TYPE: &str = "oauth";
-
 /// 提供商密钥服务入口
 pub struct ProviderKeyService<'a> {
-    state: &'a ManagementState,
     db: &'a DatabaseConnection,
     oauth_helper: OAuthHelper,
+    /// 同一 OAuth 会话能否被多个密钥引用
+    session_policy: OAuthSessionPolicy,
 }
 
 impl<'a> ProviderKeyService<'a> {
@@ -58,15 +67,42 @@ impl<'a> ProviderKeyService<'a> {
         let db = state.database.as_ref();
         let refresh_task = Some(state.oauth_token_refresh_task());
         Self {
-            state,
             db,
             oauth_helper: OAuthHelper {
                 db: db.clone(),
                 refresh_task,
             },
+            session_policy: state.config.oauth_session.sharing,
         }
     }
 
+    /// 直接基于数据库连接创建，不调度 OAuth 刷新（供任务与测试使用）
+    #[must_use]
+    pub fn from_db(db: &'a DatabaseConnection) -> Self {
+        Self {
+            db,
+            oauth_helper: OAuthHelper {
+                db: db.clone(),
+                refresh_task: None,
+            },
+            session_policy: OAuthSessionPolicy::default(),
+        }
+    }
+
+    /// 使用指定的 OAuth 刷新任务调度会话
+    #[must_use]
+    pub fn with_refresh_task(mut self, refresh_task: Arc<ApiKeyOAuthTokenRefreshTask>) -> Self {
+        self.oauth_helper.refresh_task = Some(refresh_task);
+        self
+    }
+
+    /// 指定 OAuth 会话共享策略（默认 `strict`）
+    #[must_use]
+    pub const fn with_session_policy(mut self, policy: OAuthSessionPolicy) -> Self {
+        self.session_policy = policy;
+        self
+    }
+
     #[must_use]
     const fn db(&self) -> &'a DatabaseConnection {
         self.db
@@ -80,7 +116,8 @@ impl<'a> ProviderKeyService<'a> {
         query: &ProviderKeysListQuery,
     ) -> Result<ServiceResponse<Value>> {
         let mut select = entity::user_provider_keys::Entity::find()
-            .filter(entity::user_provider_keys::Column::UserId.eq(user_id));
+            .filter(entity::user_provider_keys::Column::UserId.eq(user_id))
+            .filter(entity::user_provider_keys::Column::DeletedAt.is_null());
 
         if let Some(search) = query.search.as_ref().filter(|s| !s.is_empty()) {
             select = select.filter(entity::user_provider_keys::Column::Name.contains(search));
@@ -150,28 +187,21 @@ impl<'a> ProviderKeyService<'a> {
     ) -> Result<ServiceResponse<Value>> {
         let provider_type =
             load_provider_type_or_error(self.db(), payload.provider_type_id).await?;
-        let effective_auth_type = provider_type.auth_type.clone();
-        if payload.auth_type != effective_auth_type {
-            lwarn!(
-                "system",
-                LogStage::Authentication,
-                LogComponent::Auth,
-                "provider_key_auth_type_mismatch",
-                "Payload auth_type does not match provider type auth_type, using provider type value",
-                user_id = user_id,
-                provider_type_id = payload.provider_type_id,
-                payload_auth_type = payload.auth_type.as_str(),
-                provider_auth_type = effective_auth_type.as_str(),
-            );
-        }
+        let effective_auth_type = resolve_auth_type(
+            user_id,
+            payload.provider_type_id,
+            &payload.auth_type,
+            &provider_type.auth_type,
+        )?;
 
         ensure_unique_provider_key(self.db(), user_id, payload).await?;
-        validate_create_payload(payload, effective_auth_type.as_str())?;
+        validate_create_payload(payload, effective_auth_type)?;
         validate_oauth_session_for_creation(
             self.db(),
             user_id,
             payload,
-            effective_auth_type.as_str(),
+            effective_auth_type,
+            self.session_policy,
         )
         .await?;
 
@@ -185,10 +215,11 @@ impl<'a> ProviderKeyService<'a> {
             payload.api_key.as_ref(),
             payload.project_id.clone(),
             provider_type.name.as_str(),
+            effective_auth_type,
         )
         .await?;
 
-        let pending_schedule = if needs_oauth_schedule(effective_auth_type.as_str()) {
+        let pending_schedule = if needs_oauth_schedule(effective_auth_type) {
             self.oauth_helper
                 .prepare_schedule(payload.api_key.as_ref(), user_id, None)
                 .await?
@@ -202,7 +233,7 @@ impl<'a> ProviderKeyService<'a> {
             payload,
             final_project_id,
             health_status,
-            effective_auth_type.as_str(),
+            effective_auth_type,
         )
         .await?;
 
@@ -232,6 +263,7 @@ impl<'a> ProviderKeyService<'a> {
             "auth_status": record.auth_status,
             "health_status": record.health_status,
             "project_id": record.project_id,
+            "tags": key_tags(&record),
             "has_background_tasks": needs_auto_get_project_id_async,
             "background_tasks": {
                 "auto_get_project_id_pending": needs_auto_get_project_id_async
@@ -245,6 +277,89 @@ impl<'a> ProviderKeyService<'a> {
         Ok(ServiceResponse::with_message(data, message))
     }
 
+    /// 批量导入提供商密钥
+    ///
+    /// 逐行创建，每行独立成功或失败：重名（已有密钥或同批次前面的行）记为 `duplicate`，
+    /// 校验或创建失败记为 `failed`，均不影响其它行。创建成功的密钥与单个创建一样直接进入调度密钥池。
+    pub async fn bulk_import(
+        &self,
+        user_id: i32,
+        timezone_context: &TimezoneContext,
+        rows: Vec<BulkImportRow>,
+    ) -> BulkImportReport {
+        let mut report = BulkImportReport::default();
+        let mut seen = HashSet::new();
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let BulkImportRow { name, payload } = row;
+            let outcome = match payload {
+                Ok(payload) => {
+                    self.import_row(user_id, timezone_context, &payload, &mut seen)
+                        .await
+                }
+                Err(error) => Err((BulkImportRowStatus::Failed, error)),
+            };
+            let (status, id, error) = match outcome {
+                Ok(id) => (BulkImportRowStatus::Created, id, None),
+                Err((status, error)) => (status, None, Some(error)),
+            };
+            report.push(BulkImportRowResult {
+                row: index + 1,
+                name,
+                status,
+                id,
+                error,
+            });
+        }
+
+        linfo!(
+            "system",
+            LogStage::Internal,
+            LogComponent::KeyPool,
+            "provider_keys_bulk_imported",
+            "提供商密钥批量导入完成",
+            user_id = user_id,
+            total = report.total,
+            created = report.created,
+            duplicates = report.duplicates,
+            failed = report.failed
+        );
+
+        report
+    }
+
+    /// 导入单行：成功返回新密钥 ID，失败返回状态与原因
+    async fn import_row(
+        &self,
+        user_id: i32,
+        timezone_context: &TimezoneContext,
+        payload: &CreateProviderKeyRequest,
+        seen: &mut HashSet<(i32, String)>,
+    ) -> std::result::Result<Option<i32>, (BulkImportRowStatus, String)> {
+        let key = (payload.provider_type_id, payload.name.clone());
+        let taken = seen.contains(&key)
+            || provider_key_name_taken(self.db(), user_id, payload.provider_type_id, &payload.name)
+                .await
+                .map_err(|err| (BulkImportRowStatus::Failed, err.to_string()))?;
+        if taken {
+            return Err((
+                BulkImportRowStatus::Duplicate,
+                format!("密钥名称已存在: {}", payload.name),
+            ));
+        }
+        seen.insert(key);
+
+        let response = self
+            .create(user_id, timezone_context, payload)
+            .await
+            .map_err(|err| (BulkImportRowStatus::Failed, err.to_string()))?;
+        Ok(response
+            .data
+            .get("id")
+            .and_then(Value::as_i64)
+            .and_then(|id| i32::try_from(id).ok()))
+    }
+
     /// 更新提供商密钥
     pub async fn update(
         &self,
@@ -254,37 +369,70 @@ impl<'a> ProviderKeyService<'a> {
         payload: &UpdateProviderKeyRequest,
     ) -> Result<ServiceResponse<Value>> {
         let existing_key = load_existing_key(self.db(), key_id, user_id).await?;
+        self.apply_update(key_id, user_id, timezone_context, existing_key, payload)
+            .await
+    }
+
+    /// 部分更新提供商密钥：仅修改请求中出现的字段
+    pub async fn patch(
+        &self,
+        key_id: i32,
+        user_id: i32,
+        timezone_context: &TimezoneContext,
+        payload: &PatchProviderKeyRequest,
+    ) -> Result<ServiceResponse<Value>> {
+        let existing_key = load_existing_key(self.db(), key_id, user_id).await?;
+        let merged = payload.merge_into(&existing_key);
+        self.apply_update(key_id, user_id, timezone_context, existing_key, &merged)
+            .await
+    }
+
+    /// 整体替换密钥标签，其他字段保持不变
+    pub async fn set_tags(
+        &self,
+        key_id: i32,
+        user_id: i32,
+        timezone_context: &TimezoneContext,
+        payload: &SetProviderKeyTagsRequest,
+    ) -> Result<ServiceResponse<Value>> {
+        let patch = PatchProviderKeyRequest {
+            tags: NullableField::Value(payload.tags.clone()),
+            ..Default::default()
+        };
+        self.patch(key_id, user_id, timezone_context, &patch).await
+    }
+
+    /// 更新流程：唯一性与 OAuth 会话校验、持久化、刷新调度与旧会话清理
+    async fn apply_update(
+        &self,
+        key_id: i32,
+        user_id: i32,
+        timezone_context: &TimezoneContext,
+        existing_key: user_provider_keys::Model,
+        payload: &UpdateProviderKeyRequest,
+    ) -> Result<ServiceResponse<Value>> {
         let provider_type =
             load_provider_type_or_error(self.db(), payload.provider_type_id).await?;
-        let effective_auth_type = provider_type.auth_type.clone();
-        if payload.auth_type != effective_auth_type {
-            lwarn!(
-                "system",
-                LogStage::Authentication,
-                LogComponent::Auth,
-                "provider_key_auth_type_mismatch",
-                "Payload auth_type does not match provider type auth_type, using provider type value",
-                user_id = user_id,
-                provider_type_id = payload.provider_type_id,
-                payload_auth_type = payload.auth_type.as_str(),
-                provider_auth_type = effective_auth_type.as_str(),
-            );
-        }
+        let effective_auth_type = resolve_auth_type(
+            user_id,
+            payload.provider_type_id,
+            &payload.auth_type,
+            &provider_type.auth_type,
+        )?;
 
         ensure_unique_name(self.db(), user_id, key_id, &existing_key, payload).await?;
-        validate_update_requirements(payload, effective_auth_type.as_str())?;
-        if effective_auth_type == OAUTH_AUTH_TYPE {
-            validate_oauth_session_for_update(
-                self.db(),
-                user_id,
-                key_id,
-                payload,
-                effective_auth_type.as_str(),
-            )
-            .await?;
-        }
+        validate_update_requirements(payload, effective_auth_type)?;
+        validate_oauth_session_for_update(
+            self.db(),
+            user_id,
+            key_id,
+            payload,
+            effective_auth_type,
+            self.session_policy,
+        )
+        .await?;
 
-        let pending_schedule = if needs_oauth_schedule(effective_auth_type.as_str()) {
+        let pending_schedule = if needs_oauth_schedule(effective_auth_type) {
             self.oauth_helper
                 .prepare_schedule(payload.api_key.as_ref(), user_id, Some(key_id))
                 .await?
@@ -293,25 +441,15 @@ impl<'a> ProviderKeyService<'a> {
         };
 
         let original_key = existing_key.clone();
-        let old_session_id =
-            crate::management::services::provider_keys::oauth::OAuthHelper::extract_session_id(
-                &existing_key,
-            );
-        let updated_key = persist_updated_key(
-            self.db(),
-            existing_key,
-            payload,
-            effective_auth_type.as_str(),
-        )
-        .await?;
+        let old_session_id = OAuthHelper::extract_session_id(&existing_key)?;
+        let updated_key =
+            persist_updated_key(self.db(), existing_key, payload, effective_auth_type).await?;
 
-        if let Some(schedule) = pending_schedule {
-            if let Err(err) = self
-                .state
-                .oauth_token_refresh_task()
-                .enqueue_schedule(schedule)
-                .await
-            {
+        // 只有刷新任务可用时才会生成待入队的调度
+        if let (Some(schedule), Some(task)) =
+            (pending_schedule, self.oauth_helper.refresh_task.as_deref())
+        {
+            if let Err(err) = task.enqueue_schedule(schedule).await {
                 let revert_model: user_provider_keys::ActiveModel = original_key.into();
                 if let Err(revert_err) = revert_model.update(self.db()).await {
                     lerror!(
@@ -330,7 +468,7 @@ impl<'a> ProviderKeyService<'a> {
                 return Err(err);
             }
 
-            if updated_key.auth_type == OAUTH_AUTH_TYPE && !updated_key.api_key.is_empty() {
+            if needs_oauth_schedule(effective_auth_type) && !updated_key.api_key.is_empty() {
                 linfo!(
                     "system",
                     LogStage::Scheduling,
@@ -364,7 +502,7 @@ impl<'a> ProviderKeyService<'a> {
         let usage_stats =
             fetch_provider_keys_usage_stats(&[key.id], timezone_context, self.db()).await;
         let key_stats = usage_stats.get(&key.id).cloned().unwrap_or_default();
-        let api_key_value = if key.auth_type == "api_key" {
+        let api_key_value = if key.auth_type.parse::<AuthType>()? == AuthType::ApiKey {
             mask_api_key(&key)
         } else {
             key.api_key.clone()
@@ -391,6 +529,7 @@ impl<'a> ProviderKeyService<'a> {
             "max_requests_per_day": key.max_requests_per_day,
             "is_active": key.is_active,
             "project_id": key.project_id,
+            "tags": key_tags(&key),
             "usage": {
                 "total_requests": key_stats.total_requests,
                 "successful_requests": key_stats.successful_requests,
@@ -424,7 +563,7 @@ impl<'a> ProviderKeyService<'a> {
         Ok(ServiceResponse::new(data))
     }
 
-    /// 删除提供商密钥
+    /// 删除提供商密钥（软删除）：记录删除时间，移出密钥池与 OAuth 刷新队列
     pub async fn delete(
         &self,
         user_id: i32,
@@ -433,19 +572,36 @@ impl<'a> ProviderKeyService<'a> {
     ) -> Result<ServiceResponse<Value>> {
         let existing_key = load_existing_key(self.db(), key_id, user_id).await?;
 
-        let session_to_remove =
-            if existing_key.auth_type == OAUTH_AUTH_TYPE && !existing_key.api_key.is_empty() {
-                Some(existing_key.api_key.clone())
-            } else {
+        // 认证类型无法识别的密钥仍允许删除，便于清理异常数据
+        let session_to_remove = match OAuthHelper::extract_session_id(&existing_key) {
+            Ok(session_id) => session_id,
+            Err(err) => {
+                lwarn!(
+                    "system",
+                    LogStage::Db,
+                    LogComponent::Database,
+                    "delete_key_unknown_auth_type",
+                    "密钥认证类型无法识别，跳过 OAuth 会话清理",
+                    user_id = user_id,
+                    key_id = key_id,
+                    error = %err
+                );
                 None
-            };
+            }
+        };
 
-        delete_key(self.db(), existing_key).await?;
+        let deleted_key = soft_delete_key(self.db(), existing_key).await?;
 
+        // 共享会话仍被其他密钥使用时保留刷新计划；查询失败时同样保留，避免误删
         if let (Some(session_id), Some(task)) = (
             session_to_remove.as_ref(),
             self.oauth_helper.refresh_task.as_deref(),
-        ) && let Err(err) = task.remove_session(session_id.as_str()).await
+        ) && !self
+            .oauth_helper
+            .session_used_by_other_keys(session_id, Some(key_id))
+            .await
+            .unwrap_or(true)
+            && let Err(err) = task.remove_session(session_id.as_str()).await
         {
             lwarn!(
                 "system",
@@ -461,15 +617,90 @@ impl<'a> ProviderKeyService<'a> {
 
         let data = json!({
             "id": key_id,
-            "deleted_at": timezone_utils::format_utc_for_response(
-                &Utc::now(),
-                &timezone_context.timezone
+            "deleted_at": deleted_key.deleted_at.map(|dt|
+                timezone_utils::format_naive_utc_for_response(&dt, &timezone_context.timezone)
             )
         });
 
         Ok(ServiceResponse::with_message(data, "删除成功"))
     }
 
+    /// 恢复已软删除的提供商密钥，重新加入密钥池；OAuth 密钥重新加入刷新队列
+    pub async fn restore(
+        &self,
+        user_id: i32,
+        timezone_context: &TimezoneContext,
+        key_id: i32,
+    ) -> Result<ServiceResponse<Value>> {
+        let deleted_key = load_deleted_key(self.db(), key_id, user_id).await?;
+        ensure_oauth_session_free_for_restore(self.db(), &deleted_key, self.session_policy).await?;
+
+        let session_id = match OAuthHelper::extract_session_id(&deleted_key) {
+            Ok(session_id) => session_id,
+            Err(err) => {
+                lwarn!(
+                    "system",
+                    LogStage::Db,
+                    LogComponent::Database,
+                    "restore_key_unknown_auth_type",
+                    "密钥认证类型无法识别，跳过 OAuth 刷新调度",
+                    user_id = user_id,
+                    key_id = key_id,
+                    error = %err
+                );
+                None
+            }
+        };
+        let pending_schedule = self
+            .oauth_helper
+            .prepare_schedule(session_id.as_ref(), user_id, Some(key_id))
+            .await?;
+
+        let restored_key = restore_key(self.db(), deleted_key).await?;
+        if let (Some(schedule), Some(task)) =
+            (pending_schedule, self.oauth_helper.refresh_task.as_deref())
+            && let Err(err) = task.enqueue_schedule(schedule).await
+        {
+            // 入队失败时重新标记为删除，避免恢复出无法刷新的 OAuth 密钥
+            if let Err(revert_err) = soft_delete_key(self.db(), restored_key).await {
+                lerror!(
+                    "system",
+                    LogStage::Db,
+                    LogComponent::Database,
+                    "rollback_key_restore_fail",
+                    &format!(
+                        "Failed to rollback provider key restore after enqueue error: {revert_err}"
+                    ),
+                    user_id = user_id,
+                    key_id = key_id,
+                );
+            }
+            return Err(err);
+        }
+
+        linfo!(
+            "system",
+            LogStage::Db,
+            LogComponent::KeyPool,
+            "provider_key_restored",
+            "提供商密钥已恢复",
+            user_id = user_id,
+            key_id = key_id,
+        );
+
+        let data = json!({
+            "id": restored_key.id,
+            "name": restored_key.name,
+            "is_active": restored_key.is_active,
+            "restored_at": timezone_utils::format_naive_utc_for_response(
+                &restored_key.updated_at,
+                &timezone_context.timezone
+            )
+        });
+
+        Ok(ServiceResponse::with_message(data, "恢复成功"))
+    }
+
     /// 获取密钥统计信息
     pub async fn stats(
         &self,
@@ -538,6 +769,7 @@ impl<'a> ProviderKeyService<'a> {
     pub async fn dashboard(&self, user_id: i32) -> Result<ServiceResponse<Value>> {
         let total_keys = entity::user_provider_keys::Entity::find()
             .filter(entity::user_provider_keys::Column::UserId.eq(user_id))
+            .filter(entity::user_provider_keys::Column::DeletedAt.is_null())
             .count(self.db())
             .await
             .context("Failed to count total keys")?;
@@ -545,12 +777,14 @@ impl<'a> ProviderKeyService<'a> {
         let active_keys = entity::user_provider_keys::Entity::find()
             .filter(entity::user_provider_keys::Column::UserId.eq(user_id))
             .filter(entity::user_provider_keys::Column::IsActive.eq(true))
+            .filter(entity::user_provider_keys::Column::DeletedAt.is_null())
             .count(self.db())
             .await
             .context("Failed to count active keys")?;
 
         let user_provider_key_ids: Vec<i32> = entity::user_provider_keys::Entity::find()
             .filter(entity::user_provider_keys::Column::UserId.eq(user_id))
+            .filter(entity::user_provider_keys::Column::DeletedAt.is_null())
             .all(self.db())
             .await
             .context("Failed to fetch user provider keys")?
@@ -593,7 +827,8 @@ impl<'a> ProviderKeyService<'a> {
         query: &UserProviderKeyQuery,
     ) -> Result<ServiceResponse<Value>> {
         let mut select = entity::user_provider_keys::Entity::find()
-            .filter(entity::user_provider_keys::Column::UserId.eq(user_id));
+            .filter(entity::user_provider_keys::Column::UserId.eq(user_id))
+            .filter(entity::user_provider_keys::Column::DeletedAt.is_null());
 
         if let Some(provider_type_id) = query.provider_type_id {
             select = select
@@ -780,3 +1015,62 @@ impl<'a> ProviderKeyService<'a> {
         Ok(ServiceResponse::new(data))
     }
 }
+
+/// 解析密钥的认证类型：以服务商类型为准，请求中的值不一致时记录告警
+///
+/// 请求或服务商类型中出现无法识别的认证类型时直接返回错误，而不是按非 OAuth 处理。
+fn resolve_auth_type(
+    user_id: i32,
+    provider_type_id: i32,
+    requested: &str,
+    provider_auth_type: &str,
+) -> Result<AuthType> {
+    let requested = requested.parse::<AuthType>()?;
+    let effective = provider_auth_type.parse::<AuthType>()?;
+    if requested != effective {
+        lwarn!(
+            "system",
+            LogStage::Authentication,
+            LogComponent::Auth,
+            "provider_key_auth_type_mismatch",
+            "Payload auth_type does not match provider type auth_type, using provider type value",
+            user_id = user_id,
+            provider_type_id = provider_type_id,
+            payload_auth_type = requested.as_str(),
+            provider_auth_type = effective.as_str(),
+        );
+    }
+    Ok(effective)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_auth_type_wins_over_payload() {
+        assert_eq!(
+            resolve_auth_type(1, 2, "api_key", "oauth").unwrap(),
+            AuthType::OAuth
+        );
+        assert_eq!(
+            resolve_auth_type(1, 2, "aws_sigv4", "aws_sigv4").unwrap(),
+            AuthType::AwsSigV4
+        );
+    }
+
+    #[test]
+    fn unknown_auth_types_are_rejected() {
+        for (requested, provider) in [("oauth2", "oauth"), ("oauth", "service_account")] {
+            let err = resolve_auth_type(1, 2, requested, provider).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    ProxyError::Authentication(AuthError::UnsupportedAuthType(_))
+                ),
+                "{err:?}"
+            );
+            assert_eq!(err.status_code(), http::StatusCode::BAD_REQUEST);
+        }
+    }
+}
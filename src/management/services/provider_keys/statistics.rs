@@ -11,6 +11,7 @@ use sea_orm::{
 };
 use serde_json::Value;
 
+use crate::key_pool::key_tags::key_tags;
 use crate::types::{TimezoneContext, ratio_as_percentage, timezone_utils};
 
 use super::models::{DailyStats, TrendData, TrendDataPoint};
@@ -295,6 +296,7 @@ pub fn build_provider_key_json(
         "max_requests_per_day": provider_key.max_requests_per_day,
         "is_active": provider_key.is_active,
         "project_id": provider_key.project_id,
+        "tags": key_tags(provider_key),
         "usage": {
             "total_requests": stats.total_requests,
             "successful_requests": stats.successful_requests,
@@ -422,6 +424,7 @@ pub fn build_update_response(
         "name": updated_key.name,
         "auth_type": updated_key.auth_type,
         "auth_status": updated_key.auth_status,
+        "tags": key_tags(updated_key),
         "updated_at": timezone_utils::format_naive_utc_for_response(
             &updated_key.updated_at,
             &timezone_context.timezone
@@ -10,57 +10,75 @@ use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 
 use crate::{
     ProxyError,
-    auth::types::AuthStatus,
+    auth::types::{AuthStatus, AuthType},
+    config::OAuthSessionPolicy,
     error::{Context, Result, auth::AuthError},
+    key_pool::key_tags::normalize_tags,
+    proxy::aws_sigv4::AwsCredentials,
 };
 
 use super::models::{CreateProviderKeyRequest, UpdateProviderKeyRequest};
 
 /// 验证创建请求的 payload
-pub fn validate_create_payload(payload: &CreateProviderKeyRequest, auth_type: &str) -> Result<()> {
-    if auth_type == "api_key" && payload.api_key.is_none() {
-        return Err(ProxyError::Authentication(AuthError::Message(
-            "API Key认证类型需要提供api_key字段 (field: api_key)".to_string(),
-        )));
-    }
-
-    if auth_type == "oauth" && payload.api_key.is_none() {
-        return Err(ProxyError::Authentication(AuthError::Message(
-            "OAuth认证类型需要通过api_key字段提供session_id (field: api_key)".to_string(),
-        )));
-    }
-
-    Ok(())
+pub fn validate_create_payload(
+    payload: &CreateProviderKeyRequest,
+    auth_type: AuthType,
+) -> Result<()> {
+    validate_credential(payload.api_key.as_deref(), auth_type)?;
+    validate_tags(payload.tags.as_deref())
 }
 
 /// 验证更新请求的要求
 pub fn validate_update_requirements(
     payload: &UpdateProviderKeyRequest,
-    auth_type: &str,
+    auth_type: AuthType,
 ) -> Result<()> {
-    if auth_type == "api_key" && payload.api_key.is_none() {
-        return Err(ProxyError::Authentication(AuthError::Message(
-            "API Key认证类型需要提供api_key字段 (field: api_key)".to_string(),
-        )));
+    validate_credential(payload.api_key.as_deref(), auth_type)?;
+    validate_tags(payload.tags.as_deref())
+}
+
+/// 校验密钥标签（数量、长度、非空）
+fn validate_tags(tags: Option<&[String]>) -> Result<()> {
+    if let Some(tags) = tags {
+        normalize_tags(tags)?;
     }
+    Ok(())
+}
 
-    if auth_type == "oauth" && payload.api_key.is_none() {
+/// 按认证类型校验 `api_key` 字段：
+/// - API Key：密钥本身；
+/// - OAuth：已授权会话的 `session_id`；
+/// - AWS SigV4：`ACCESS_KEY_ID:SECRET_ACCESS_KEY[:SESSION_TOKEN]`；
+/// - HMAC 签名：签名密钥
+fn validate_credential(api_key: Option<&str>, auth_type: AuthType) -> Result<()> {
+    let missing_message = match auth_type {
+        AuthType::ApiKey => "API Key认证类型需要提供api_key字段 (field: api_key)",
+        AuthType::OAuth => "OAuth认证类型需要通过api_key字段提供session_id (field: api_key)",
+        AuthType::AwsSigV4 => "AWS SigV4认证类型需要通过api_key字段提供AWS凭证 (field: api_key)",
+        AuthType::HmacSignature => {
+            "HMAC签名认证类型需要通过api_key字段提供签名密钥 (field: api_key)"
+        }
+    };
+    let Some(api_key) = api_key else {
         return Err(ProxyError::Authentication(AuthError::Message(
-            "OAuth认证类型需要通过api_key字段提供session_id (field: api_key)".to_string(),
+            missing_message.to_string(),
         )));
+    };
+    if auth_type == AuthType::AwsSigV4 {
+        AwsCredentials::parse(api_key)?;
     }
-
     Ok(())
 }
 
-/// 验证创建时的 OAuth 会话
+/// 验证创建时的 OAuth 会话；`strict` 策略下会话不能已被其他有效密钥使用
 pub async fn validate_oauth_session_for_creation(
     db: &DatabaseConnection,
     user_id: i32,
     payload: &CreateProviderKeyRequest,
-    auth_type: &str,
+    auth_type: AuthType,
+    policy: OAuthSessionPolicy,
 ) -> Result<()> {
-    if auth_type != "oauth" {
+    if auth_type != AuthType::OAuth {
         return Ok(());
     }
 
@@ -75,11 +93,13 @@ pub async fn validate_oauth_session_for_creation(
         .one(db)
         .await
     {
+        Ok(Some(_)) if policy.allows_sharing() => Ok(()),
         Ok(Some(_)) => {
             let existing_usage = UserProviderKey::find()
                 .filter(user_provider_keys::Column::ApiKey.eq(session_id))
-                .filter(user_provider_keys::Column::AuthType.eq("oauth"))
+                .filter(user_provider_keys::Column::AuthType.eq(AuthType::OAuth.as_str()))
                 .filter(user_provider_keys::Column::IsActive.eq(true))
+                .filter(user_provider_keys::Column::DeletedAt.is_null())
                 .one(db)
                 .await;
 
@@ -126,15 +146,16 @@ pub async fn validate_oauth_session_for_creation(
     }
 }
 
-/// 验证更新时的 OAuth 会话
+/// 验证更新时的 OAuth 会话；`strict` 策略下会话不能已被其他有效密钥使用
 pub async fn validate_oauth_session_for_update(
     db: &DatabaseConnection,
     user_id: i32,
     key_id: i32,
     payload: &UpdateProviderKeyRequest,
-    auth_type: &str,
+    auth_type: AuthType,
+    policy: OAuthSessionPolicy,
 ) -> Result<()> {
-    if auth_type != "oauth" {
+    if auth_type != AuthType::OAuth {
         return Ok(());
     }
 
@@ -157,10 +178,15 @@ pub async fn validate_oauth_session_for_update(
         )));
     }
 
+    if policy.allows_sharing() {
+        return Ok(());
+    }
+
     let existing_usage = UserProviderKey::find()
         .filter(user_provider_keys::Column::ApiKey.eq(session_id))
-        .filter(user_provider_keys::Column::AuthType.eq("oauth"))
+        .filter(user_provider_keys::Column::AuthType.eq(AuthType::OAuth.as_str()))
         .filter(user_provider_keys::Column::IsActive.eq(true))
+        .filter(user_provider_keys::Column::DeletedAt.is_null())
         .filter(user_provider_keys::Column::Id.ne(key_id))
         .one(db)
         .await
@@ -205,3 +231,35 @@ pub async fn ensure_unique_name(
 
     Ok(())
 }
+
+/// 恢复前确认 OAuth 会话未被其他密钥占用（删除期间会话可能已被新密钥使用）；`shared` 策略下不检查
+pub async fn ensure_oauth_session_free_for_restore(
+    db: &DatabaseConnection,
+    key: &user_provider_keys::Model,
+    policy: OAuthSessionPolicy,
+) -> Result<()> {
+    if policy.allows_sharing()
+        || key.auth_type != AuthType::OAuth.as_str()
+        || key.api_key.is_empty()
+    {
+        return Ok(());
+    }
+
+    let existing_usage = UserProviderKey::find()
+        .filter(user_provider_keys::Column::ApiKey.eq(&key.api_key))
+        .filter(user_provider_keys::Column::AuthType.eq(AuthType::OAuth.as_str()))
+        .filter(user_provider_keys::Column::IsActive.eq(true))
+        .filter(user_provider_keys::Column::DeletedAt.is_null())
+        .filter(user_provider_keys::Column::Id.ne(key.id))
+        .one(db)
+        .await
+        .context("Failed to check OAuth session usage")?;
+
+    if existing_usage.is_some() {
+        return Err(ProxyError::Authentication(AuthError::Message(
+            "密钥的OAuth会话已被其他provider key使用，无法恢复".to_string(),
+        )));
+    }
+
+    Ok(())
+}
@@ -6,11 +6,15 @@ use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use crate::auth::types::AuthType;
 use crate::collect::usage_model;
 use crate::error::{Context, Result};
 use crate::key_pool::types::SchedulingStrategy;
 use crate::management::middleware::AuthContext;
 use crate::management::server::ManagementState;
+use crate::proxy::model_rate_limit::ModelRateLimits;
+use crate::proxy::parameter_policy::MaxTokensCeiling;
+use crate::proxy::request_transform_service::parse_default_request_headers;
 use crate::types::timezone_utils;
 use crate::{ensure, error};
 
@@ -175,6 +179,12 @@ const STRATEGIES: &[(SchedulingStrategy, &str, &str, bool)] = &[
         "根据权重比例分配请求到上游服务器",
         false,
     ),
+    (
+        SchedulingStrategy::CostAware,
+        "成本优先调度",
+        "按模型定价优先选择最便宜的健康密钥，可配置延迟上限",
+        false,
+    ),
 ];
 
 /// 获取调度策略枚举。
@@ -242,17 +252,15 @@ impl ProviderTypeService {
             )
         );
 
-        let auth_type = request.auth_type.trim();
-        ensure!(
-            auth_type == "api_key" || auth_type == "oauth",
-            crate::error::auth::AuthError::Message("auth_type 仅支持 api_key / oauth".to_string())
-        );
+        let auth_type = request.auth_type.parse::<AuthType>()?;
 
         ensure!(
             !request.base_url.trim().is_empty(),
             crate::error::auth::AuthError::Message("base_url 不能为空".to_string())
         );
 
+        validate_config_json(request.config_json.as_ref())?;
+
         let now = chrono::Utc::now().naive_utc();
         let active = provider_types::ActiveModel {
             name: Set(name.to_string()),
@@ -311,16 +319,10 @@ impl ProviderTypeService {
         }
 
         if let Some(auth_type) = &request.auth_type {
-            let auth_type = auth_type.trim();
-            ensure!(
-                auth_type == "api_key" || auth_type == "oauth",
-                crate::error::auth::AuthError::Message(
-                    "auth_type 仅支持 api_key / oauth".to_string()
-                )
-            );
+            let auth_type = auth_type.parse::<AuthType>()?;
             // auth_type 是分行粒度的“身份字段”，修改会引入歧义与唯一约束冲突
             ensure!(
-                auth_type == existing_auth_type,
+                auth_type.as_str() == existing_auth_type,
                 error::conversion::ConversionError::message(
                     "不允许修改 auth_type；如需切换认证类型，请新建一条 provider_types 记录",
                 )
@@ -340,6 +342,7 @@ impl ProviderTypeService {
         }
 
         if request.config_json.is_some() {
+            validate_config_json(request.config_json.as_ref())?;
             active.config_json = Set(serialize_option_json(request.config_json.as_ref())?);
         }
         if request.token_mappings_json.is_some() {
@@ -420,3 +423,17 @@ fn serialize_option_json(value: Option<&serde_json::Value>) -> Result<Option<Str
         .map(|v| serde_json::to_string(v).context("序列化 JSON 失败"))
         .transpose()
 }
+
+/// 校验 `config_json` 中由代理解析的字段（`default_headers`、`max_tokens_ceiling`、`model_rate_limits`）
+fn validate_config_json(value: Option<&serde_json::Value>) -> Result<()> {
+    if let Some(headers) = value.and_then(|config| config.get("default_headers")) {
+        parse_default_request_headers(headers)?;
+    }
+    if let Some(ceiling) = value.and_then(|config| config.get("max_tokens_ceiling")) {
+        MaxTokensCeiling::parse(ceiling)?;
+    }
+    if let Some(limits) = value.and_then(|config| config.get("model_rate_limits")) {
+        ModelRateLimits::parse(limits)?;
+    }
+    Ok(())
+}
@@ -2,13 +2,15 @@
 //!
 //! 聚合用户服务 API 相关的业务逻辑，供管理端 Handler 复用。
 
+use std::collections::HashSet;
 use std::ops::Range;
 
 use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
 use chrono_tz::Tz;
 use entity::{
     provider_types::Entity as ProviderTypes, proxy_tracing, proxy_tracing::Entity as ProxyTracing,
-    user_service_apis, user_service_apis::Entity as UserServiceApis,
+    user_provider_keys, user_provider_keys::Entity as UserProviderKeys, user_service_apis,
+    user_service_apis::Entity as UserServiceApis,
 };
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, Order, PaginatorTrait,
@@ -19,9 +21,19 @@ use serde_json::Value;
 use uuid::Uuid;
 
 use crate::{
+    collect::field_extractor::parse_transform_rules,
+    config::AppConfig,
     error::{Context, ProxyError, Result},
+    key_pool::{SchedulingStrategy, key_tags::normalize_tag},
     management::response::Pagination,
     management::server::ManagementState,
+    proxy::effective_config::EffectiveConfig,
+    proxy::model_routing::ModelRoutes,
+    proxy::non_streaming::StreamPolicy,
+    proxy::path_allowlist::parse_allowed_paths,
+    proxy::response_transform_service::parse_custom_response_headers,
+    proxy::system_prompt::parse_system_prompt,
+    proxy::upstream_error::UpstreamErrorMode,
     types::{ProviderTypeId, timezone_utils},
 };
 
@@ -30,6 +42,9 @@ use super::shared::{
     pagination::{PaginationParams, build_page},
 };
 
+/// 轮换 API Key 时旧 Key 宽限期上限（7 天）
+const MAX_ROTATION_GRACE_PERIOD_SECONDS: i64 = 7 * 24 * 60 * 60;
+
 /// 用户服务 API 查询参数
 #[derive(Debug, Deserialize)]
 pub struct UserServiceKeyQuery {
@@ -50,13 +65,36 @@ pub struct CreateUserServiceKeyRequest {
     pub user_provider_keys_ids: Vec<i32>,
     /// 是否开启日志模式（记录完整请求/响应内容到服务日志）
     pub log_mode: Option<bool>,
+    /// 是否在追踪记录中保存密钥选择依据
+    pub selection_debug: Option<bool>,
+    /// 流式策略（allow/deny/force-off）
+    pub stream_policy: Option<String>,
+    /// 上游错误返回方式（sanitized/verbatim）
+    pub upstream_error_mode: Option<String>,
+    /// 全局并发准入优先级，越高越先获得空位
+    pub priority: Option<i32>,
+    /// 请求体改写规则（见 `collect::field_extractor::TransformRule`）
+    pub request_transform_rules: Option<Value>,
+    /// 自定义响应头（JSON 对象：头部名称 -> 值）
+    pub response_headers: Option<Value>,
+    /// 允许访问的请求路径模式（JSON 数组，支持前缀与 `*` 通配符）
+    pub allowed_paths: Option<Value>,
+    /// 强制注入的系统提示词（`{"content": "...", "mode": "prepend"|"merge"}`）
+    pub system_prompt: Option<Value>,
+    /// 只在带有该标签的密钥之间选择
+    pub key_tag: Option<String>,
+    /// 按模型路由到不同服务商的规则（JSON 数组，见 `proxy::model_routing`）
+    pub model_routes: Option<Value>,
     pub scheduling_strategy: Option<String>,
     pub retry_count: Option<i32>,
     pub timeout_seconds: Option<i32>,
+    pub max_response_duration_seconds: Option<i32>,
+    pub sla_target_ms: Option<i32>,
     pub max_request_per_min: Option<i32>,
     pub max_requests_per_day: Option<i32>,
     pub max_tokens_per_day: Option<i64>,
     pub max_cost_per_day: Option<sea_orm::prelude::Decimal>,
+    pub max_cost_per_request: Option<sea_orm::prelude::Decimal>,
     pub expires_at: Option<String>,
     pub is_active: Option<bool>,
 }
@@ -69,6 +107,18 @@ pub enum NullableField<T> {
     Value(T),
 }
 
+impl<T: Clone> NullableField<T> {
+    /// 应用到当前值：未出现时保留，`null` 时清空
+    #[must_use]
+    pub fn resolve(&self, current: Option<T>) -> Option<T> {
+        match self {
+            Self::Missing => current,
+            Self::Null => None,
+            Self::Value(value) => Some(value.clone()),
+        }
+    }
+}
+
 impl<'de, T> Deserialize<'de> for NullableField<T>
 where
     T: Deserialize<'de>,
@@ -90,15 +140,44 @@ pub struct UpdateUserServiceKeyRequest {
     pub user_provider_keys_ids: Option<Vec<i32>>,
     /// 是否开启日志模式（记录完整请求/响应内容到服务日志）
     pub log_mode: Option<bool>,
+    /// 是否在追踪记录中保存密钥选择依据
+    pub selection_debug: Option<bool>,
+    /// 流式策略（allow/deny/force-off）
+    pub stream_policy: Option<String>,
+    /// 上游错误返回方式（sanitized/verbatim）
+    pub upstream_error_mode: Option<String>,
+    /// 全局并发准入优先级，越高越先获得空位
+    pub priority: Option<i32>,
     pub scheduling_strategy: Option<String>,
     pub retry_count: Option<i32>,
     pub timeout_seconds: Option<i32>,
+    pub max_response_duration_seconds: Option<i32>,
+    pub sla_target_ms: Option<i32>,
     pub max_request_per_min: Option<i32>,
     pub max_requests_per_day: Option<i32>,
     pub max_tokens_per_day: Option<i64>,
     pub max_cost_per_day: Option<sea_orm::prelude::Decimal>,
+    pub max_cost_per_request: Option<sea_orm::prelude::Decimal>,
     #[serde(default)]
     pub expires_at: NullableField<String>,
+    /// 请求体改写规则，`null` 表示清空
+    #[serde(default)]
+    pub request_transform_rules: NullableField<Value>,
+    /// 自定义响应头，`null` 表示清空
+    #[serde(default)]
+    pub response_headers: NullableField<Value>,
+    /// 请求路径白名单，`null` 表示不限制
+    #[serde(default)]
+    pub allowed_paths: NullableField<Value>,
+    /// 强制注入的系统提示词，`null` 表示不注入
+    #[serde(default)]
+    pub system_prompt: NullableField<Value>,
+    /// 限定参与选择的密钥标签，`null` 表示不限定
+    #[serde(default)]
+    pub key_tag: NullableField<String>,
+    /// 按模型路由的规则，`null` 表示清空
+    #[serde(default)]
+    pub model_routes: NullableField<Value>,
 }
 
 /// 使用统计查询
@@ -115,6 +194,14 @@ pub struct UpdateStatusRequest {
     pub is_active: bool,
 }
 
+/// 轮换 API Key 请求
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateUserServiceKeyRequest {
+    /// 旧 Key 继续可用的秒数，为空或 0 时立即失效
+    #[serde(default)]
+    pub grace_period_seconds: Option<i64>,
+}
+
 /// 卡片指标
 #[derive(Debug, Serialize)]
 pub struct UserServiceCardsResponse {
@@ -141,10 +228,13 @@ pub struct UserServiceKeyResponse {
     pub scheduling_strategy: Option<String>,
     pub retry_count: Option<i32>,
     pub timeout_seconds: Option<i32>,
+    pub max_response_duration_seconds: Option<i32>,
+    pub sla_target_ms: Option<i32>,
     pub max_request_per_min: Option<i32>,
     pub max_requests_per_day: Option<i32>,
     pub max_tokens_per_day: Option<i64>,
     pub max_cost_per_day: Option<sea_orm::prelude::Decimal>,
+    pub max_cost_per_request: Option<sea_orm::prelude::Decimal>,
 }
 
 /// 列表响应
@@ -167,13 +257,26 @@ pub struct UserServiceKeyDetailResponse {
     pub scheduling_strategy: Option<String>,
     pub retry_count: Option<i32>,
     pub timeout_seconds: Option<i32>,
+    pub max_response_duration_seconds: Option<i32>,
+    pub sla_target_ms: Option<i32>,
     pub max_request_per_min: Option<i32>,
     pub max_requests_per_day: Option<i32>,
     pub max_tokens_per_day: Option<i64>,
     pub max_cost_per_day: Option<sea_orm::prelude::Decimal>,
+    pub max_cost_per_request: Option<sea_orm::prelude::Decimal>,
     pub expires_at: Option<String>,
     pub is_active: bool,
     pub log_mode: bool,
+    pub selection_debug: bool,
+    pub stream_policy: String,
+    pub upstream_error_mode: String,
+    pub priority: i32,
+    pub request_transform_rules: Option<Value>,
+    pub response_headers: Option<Value>,
+    pub allowed_paths: Option<Value>,
+    pub system_prompt: Option<Value>,
+    pub key_tag: Option<String>,
+    pub model_routes: Option<Value>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -218,6 +321,19 @@ pub struct UserServiceKeyUsageResponse {
     pub usage_trend: Vec<Value>,
 }
 
+/// 响应时间目标达成统计
+#[derive(Debug, Serialize)]
+pub struct UserServiceKeySlaResponse {
+    /// 当前配置的响应时间目标（毫秒）
+    pub sla_target_ms: Option<i32>,
+    /// 统计区间内已完成的请求数
+    pub total_requests: u64,
+    /// 其中超过响应时间目标的请求数
+    pub breached_requests: u64,
+    /// 超时率（百分比）
+    pub breach_rate: f64,
+}
+
 /// 重新生成响应
 #[derive(Debug, Serialize)]
 pub struct RegenerateUserServiceKeyResponse {
@@ -226,6 +342,16 @@ pub struct RegenerateUserServiceKeyResponse {
     pub regenerated_at: String,
 }
 
+/// 轮换响应（新 Key 只在此返回一次）
+#[derive(Debug, Serialize)]
+pub struct RotateUserServiceKeyResponse {
+    pub id: i32,
+    pub api_key: String,
+    pub rotated_at: String,
+    /// 旧 Key 失效时间，未设置宽限期时为空
+    pub previous_key_expires_at: Option<String>,
+}
+
 /// 状态更新响应
 #[derive(Debug, Serialize)]
 pub struct UpdateUserServiceKeyStatusResponse {
@@ -242,9 +368,13 @@ pub struct ServiceApiService<'a> {
 impl<'a> ServiceApiService<'a> {
     #[must_use]
     pub fn new(state: &'a ManagementState) -> Self {
-        Self {
-            db: state.database.as_ref(),
-        }
+        Self::from_db(state.database.as_ref())
+    }
+
+    /// 直接基于数据库连接创建（供测试使用）
+    #[must_use]
+    pub const fn from_db(db: &'a DatabaseConnection) -> Self {
+        Self { db }
     }
 
     /// 获取卡片指标
@@ -336,8 +466,28 @@ impl<'a> ServiceApiService<'a> {
         request: &CreateUserServiceKeyRequest,
         timezone: &Tz,
     ) -> Result<CreateUserServiceKeyResponse> {
-        let api_key = format!("sk-usr-{}", Uuid::new_v4().to_string().replace('-', ""));
+        let api_key = generate_service_api_key();
         let expires_at = parse_optional_rfc3339(request.expires_at.as_deref())?;
+        let request_transform_rules =
+            normalize_transform_rules(request.request_transform_rules.as_ref())?;
+        let response_headers = normalize_response_headers(request.response_headers.as_ref())?;
+        let allowed_paths = normalize_allowed_paths(request.allowed_paths.as_ref())?;
+        let system_prompt = normalize_system_prompt(request.system_prompt.as_ref())?;
+        let key_tag = request.key_tag.as_deref().map(normalize_tag).transpose()?;
+        let scheduling_strategy =
+            normalize_scheduling_strategy(request.scheduling_strategy.as_deref())?;
+        let stream_policy = normalize_stream_policy(request.stream_policy.as_deref())?;
+        let upstream_error_mode =
+            normalize_upstream_error_mode(request.upstream_error_mode.as_deref())?;
+        let sla_target_ms = normalize_sla_target_ms(request.sla_target_ms)?;
+        let model_routes = self
+            .normalize_model_routes(
+                user_id,
+                request.provider_type_id,
+                &request.user_provider_keys_ids,
+                request.model_routes.as_ref(),
+            )
+            .await?;
         let now = Utc::now().naive_utc();
 
         let user_provider_keys_ids = serde_json::to_value(&request.user_provider_keys_ids)
@@ -351,13 +501,26 @@ impl<'a> ServiceApiService<'a> {
             description: Set(request.description.clone()),
             user_provider_keys_ids: Set(user_provider_keys_ids),
             log_mode: Set(request.log_mode.unwrap_or(false)),
-            scheduling_strategy: Set(request.scheduling_strategy.clone()),
+            selection_debug: Set(request.selection_debug.unwrap_or(false)),
+            stream_policy: Set(stream_policy.unwrap_or_default().as_str().to_string()),
+            upstream_error_mode: Set(upstream_error_mode.unwrap_or_default().as_str().to_string()),
+            priority: Set(request.priority.unwrap_or(0)),
+            request_transform_rules: Set(request_transform_rules),
+            response_headers: Set(response_headers),
+            allowed_paths: Set(allowed_paths),
+            system_prompt: Set(system_prompt),
+            key_tag: Set(key_tag),
+            model_routes: Set(model_routes),
+            scheduling_strategy: Set(scheduling_strategy),
             retry_count: Set(request.retry_count),
             timeout_seconds: Set(request.timeout_seconds),
+            max_response_duration_seconds: Set(request.max_response_duration_seconds),
+            sla_target_ms: Set(sla_target_ms),
             max_request_per_min: Set(request.max_request_per_min),
             max_requests_per_day: Set(request.max_requests_per_day),
             max_tokens_per_day: Set(request.max_tokens_per_day),
             max_cost_per_day: Set(request.max_cost_per_day),
+            max_cost_per_request: Set(request.max_cost_per_request),
             expires_at: Set(expires_at),
             is_active: Set(request.is_active.unwrap_or(true)),
             created_at: Set(now),
@@ -414,18 +577,52 @@ impl<'a> ServiceApiService<'a> {
             scheduling_strategy: api.scheduling_strategy,
             retry_count: api.retry_count,
             timeout_seconds: api.timeout_seconds,
+            max_response_duration_seconds: api.max_response_duration_seconds,
+            sla_target_ms: api.sla_target_ms,
             max_request_per_min: api.max_request_per_min,
             max_requests_per_day: api.max_requests_per_day,
             max_tokens_per_day: api.max_tokens_per_day,
             max_cost_per_day: api.max_cost_per_day,
+            max_cost_per_request: api.max_cost_per_request,
             expires_at: api.expires_at.map(|dt| format_naive_utc(&dt, *timezone)),
             is_active: api.is_active,
             log_mode: api.log_mode,
+            selection_debug: api.selection_debug,
+            stream_policy: api.stream_policy,
+            upstream_error_mode: api.upstream_error_mode,
+            priority: api.priority,
+            request_transform_rules: api.request_transform_rules,
+            response_headers: api.response_headers,
+            allowed_paths: api.allowed_paths,
+            system_prompt: api.system_prompt,
+            key_tag: api.key_tag,
+            model_routes: api.model_routes,
             created_at: format_naive_utc(&api.created_at, *timezone),
             updated_at: format_naive_utc(&api.updated_at, *timezone),
         })
     }
 
+    /// 生效配置预览：按请求链路的优先级合并各层配置，并标注每个值的来源
+    pub async fn effective_config(
+        &self,
+        api_id: i32,
+        user_id: i32,
+        config: &AppConfig,
+    ) -> Result<EffectiveConfig> {
+        ensure_positive(api_id)?;
+        let api = self.find_user_api(api_id, user_id).await?;
+
+        let provider = ProviderTypes::find_by_id(api.provider_type_id)
+            .one(self.db)
+            .await
+            .context("Failed to fetch provider type")?
+            .ok_or_else(|| {
+                business_error(format!("Provider type not found: {}", api.provider_type_id))
+            })?;
+
+        Ok(EffectiveConfig::resolve(config, &api, &provider))
+    }
+
     /// 更新
     pub async fn update(
         &self,
@@ -441,6 +638,44 @@ impl<'a> ServiceApiService<'a> {
             NullableField::Null => None,
             NullableField::Value(value) => Some(parse_rfc3339(value)?),
         };
+        let request_transform_rules = match &request.request_transform_rules {
+            NullableField::Missing => existing.request_transform_rules,
+            NullableField::Null => None,
+            NullableField::Value(value) => normalize_transform_rules(Some(value))?,
+        };
+        let response_headers = match &request.response_headers {
+            NullableField::Missing => existing.response_headers,
+            NullableField::Null => None,
+            NullableField::Value(value) => normalize_response_headers(Some(value))?,
+        };
+        let allowed_paths = match &request.allowed_paths {
+            NullableField::Missing => existing.allowed_paths,
+            NullableField::Null => None,
+            NullableField::Value(value) => normalize_allowed_paths(Some(value))?,
+        };
+        let system_prompt = match &request.system_prompt {
+            NullableField::Missing => existing.system_prompt,
+            NullableField::Null => None,
+            NullableField::Value(value) => normalize_system_prompt(Some(value))?,
+        };
+        let key_tag = match &request.key_tag {
+            NullableField::Missing => existing.key_tag,
+            NullableField::Null => None,
+            NullableField::Value(tag) => Some(normalize_tag(tag)?),
+        };
+        let model_routes = match &request.model_routes {
+            NullableField::Missing => existing.model_routes,
+            NullableField::Null => None,
+            NullableField::Value(value) => {
+                let pool = match &request.user_provider_keys_ids {
+                    Some(ids) => ids.clone(),
+                    None => serde_json::from_value(existing.user_provider_keys_ids.clone())
+                        .unwrap_or_default(),
+                };
+                self.normalize_model_routes(user_id, existing.provider_type_id, &pool, Some(value))
+                    .await?
+            }
+        };
 
         let mut model = user_service_apis::ActiveModel {
             id: Set(api_id),
@@ -459,19 +694,42 @@ impl<'a> ServiceApiService<'a> {
                 serde_json::to_value(user_provider_keys_ids).unwrap_or(Value::Array(vec![]));
             model.user_provider_keys_ids = Set(value);
         }
-        if let Some(strategy) = &request.scheduling_strategy {
-            model.scheduling_strategy = Set(Some(strategy.clone()));
+        if let Some(strategy) =
+            normalize_scheduling_strategy(request.scheduling_strategy.as_deref())?
+        {
+            model.scheduling_strategy = Set(Some(strategy));
         }
         if let Some(log_mode) = request.log_mode {
             model.log_mode = Set(log_mode);
         }
+        if let Some(selection_debug) = request.selection_debug {
+            model.selection_debug = Set(selection_debug);
+        }
+        if let Some(policy) = normalize_stream_policy(request.stream_policy.as_deref())? {
+            model.stream_policy = Set(policy.as_str().to_string());
+        }
+        if let Some(mode) = normalize_upstream_error_mode(request.upstream_error_mode.as_deref())? {
+            model.upstream_error_mode = Set(mode.as_str().to_string());
+        }
+        if let Some(priority) = request.priority {
+            model.priority = Set(priority);
+        }
         model.retry_count = Set(request.retry_count);
         model.timeout_seconds = Set(request.timeout_seconds);
+        model.max_response_duration_seconds = Set(request.max_response_duration_seconds);
+        model.sla_target_ms = Set(normalize_sla_target_ms(request.sla_target_ms)?);
         model.max_request_per_min = Set(request.max_request_per_min);
         model.max_requests_per_day = Set(request.max_requests_per_day);
         model.max_tokens_per_day = Set(request.max_tokens_per_day);
         model.max_cost_per_day = Set(request.max_cost_per_day);
+        model.max_cost_per_request = Set(request.max_cost_per_request);
         model.expires_at = Set(expires_at);
+        model.request_transform_rules = Set(request_transform_rules);
+        model.response_headers = Set(response_headers);
+        model.allowed_paths = Set(allowed_paths);
+        model.system_prompt = Set(system_prompt);
+        model.key_tag = Set(key_tag);
+        model.model_routes = Set(model_routes);
 
         let updated = model
             .update(self.db)
@@ -582,21 +840,90 @@ impl<'a> ServiceApiService<'a> {
         })
     }
 
+    /// 响应时间目标达成统计：按追踪记录完成时写入的 `sla_breached` 汇总
+    pub async fn sla_stats(
+        &self,
+        api_id: i32,
+        user_id: i32,
+        query: &UsageStatsQuery,
+        timezone: &Tz,
+    ) -> Result<UserServiceKeySlaResponse> {
+        ensure_positive(api_id)?;
+        let api = self.find_user_api(api_id, user_id).await?;
+
+        let range = resolve_usage_range(query, *timezone)?;
+        let completed = || {
+            ProxyTracing::find()
+                .filter(proxy_tracing::Column::UserServiceApiId.eq(api_id))
+                .filter(proxy_tracing::Column::CreatedAt.gte(range.start.naive_utc()))
+                .filter(proxy_tracing::Column::CreatedAt.lt(range.end.naive_utc()))
+                .filter(proxy_tracing::Column::EndTime.is_not_null())
+        };
+        let total_requests = completed()
+            .count(self.db)
+            .await
+            .context("Failed to count proxy tracings for SLA statistics")?;
+        let breached_requests = completed()
+            .filter(proxy_tracing::Column::SlaBreached.eq(true))
+            .count(self.db)
+            .await
+            .context("Failed to count SLA breaches")?;
+
+        Ok(UserServiceKeySlaResponse {
+            sla_target_ms: api.sla_target_ms,
+            total_requests,
+            breached_requests,
+            breach_rate: ratio_as_percentage(breached_requests, total_requests),
+        })
+    }
+
     /// 重新生成
     pub async fn regenerate(
         &self,
         api_id: i32,
         user_id: i32,
     ) -> Result<RegenerateUserServiceKeyResponse> {
+        let rotated = self
+            .rotate(api_id, user_id, &RotateUserServiceKeyRequest::default())
+            .await?;
+
+        Ok(RegenerateUserServiceKeyResponse {
+            id: rotated.id,
+            api_key: rotated.api_key,
+            regenerated_at: rotated.rotated_at,
+        })
+    }
+
+    /// 轮换 API Key：生成新 Key 并保留服务 API 的配置与历史
+    ///
+    /// 代理端认证直接查询数据库，旧 Key 在轮换后立即失效；指定宽限期时旧 Key 在宽限期内仍可认证。
+    /// 再次轮换会覆盖上一次保留的旧 Key。
+    pub async fn rotate(
+        &self,
+        api_id: i32,
+        user_id: i32,
+        request: &RotateUserServiceKeyRequest,
+    ) -> Result<RotateUserServiceKeyResponse> {
         ensure_positive(api_id)?;
-        self.find_user_api(api_id, user_id).await?;
+        let existing = self.find_user_api(api_id, user_id).await?;
+
+        let grace_period_seconds = request.grace_period_seconds.unwrap_or(0);
+        if !(0..=MAX_ROTATION_GRACE_PERIOD_SECONDS).contains(&grace_period_seconds) {
+            return Err(business_error(format!(
+                "grace_period_seconds must be between 0 and {MAX_ROTATION_GRACE_PERIOD_SECONDS}"
+            )));
+        }
 
-        let new_api_key = format!("sk-usr-{}", Uuid::new_v4().to_string().replace('-', ""));
+        let new_api_key = generate_service_api_key();
         let now = Utc::now().naive_utc();
+        let previous_key_expires_at =
+            (grace_period_seconds > 0).then(|| now + Duration::seconds(grace_period_seconds));
 
         let model = user_service_apis::ActiveModel {
             id: Set(api_id),
             api_key: Set(new_api_key.clone()),
+            previous_api_key: Set(previous_key_expires_at.map(|_| existing.api_key)),
+            previous_api_key_expires_at: Set(previous_key_expires_at),
             updated_at: Set(now),
             ..Default::default()
         };
@@ -604,13 +931,16 @@ impl<'a> ServiceApiService<'a> {
         let updated = model
             .update(self.db)
             .await
-            .context("Failed to regenerate API key")?;
+            .context("Failed to rotate API key")?;
 
-        Ok(RegenerateUserServiceKeyResponse {
+        Ok(RotateUserServiceKeyResponse {
             id: updated.id,
             api_key: new_api_key,
-            regenerated_at: DateTime::<Utc>::from_naive_utc_and_offset(updated.updated_at, Utc)
+            rotated_at: DateTime::<Utc>::from_naive_utc_and_offset(updated.updated_at, Utc)
                 .to_rfc3339(),
+            previous_key_expires_at: updated
+                .previous_api_key_expires_at
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc).to_rfc3339()),
         })
     }
 
@@ -712,10 +1042,13 @@ impl<'a> ServiceApiService<'a> {
             scheduling_strategy: api.scheduling_strategy,
             retry_count: api.retry_count,
             timeout_seconds: api.timeout_seconds,
+            max_response_duration_seconds: api.max_response_duration_seconds,
+            sla_target_ms: api.sla_target_ms,
             max_request_per_min: api.max_request_per_min,
             max_requests_per_day: api.max_requests_per_day,
             max_tokens_per_day: api.max_tokens_per_day,
             max_cost_per_day: api.max_cost_per_day,
+            max_cost_per_request: api.max_cost_per_request,
         })
     }
 
@@ -731,6 +1064,84 @@ impl<'a> ServiceApiService<'a> {
             .context("Failed to count user service APIs")
     }
 
+    /// 校验模型路由规则：服务商存在且已启用；规则指定的密钥属于该用户且属于规则的服务商，
+    /// 未指定密钥时服务 API 的密钥池中需要有该服务商的密钥。空数组视为未配置。
+    async fn normalize_model_routes(
+        &self,
+        user_id: i32,
+        provider_type_id: ProviderTypeId,
+        pool: &[i32],
+        value: Option<&Value>,
+    ) -> Result<Option<Value>> {
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let routes = ModelRoutes::parse(value)?;
+        if routes.routes().is_empty() {
+            return Ok(None);
+        }
+
+        let pool_providers: HashSet<ProviderTypeId> = UserProviderKeys::find()
+            .filter(user_provider_keys::Column::Id.is_in(pool.to_vec()))
+            .filter(user_provider_keys::Column::UserId.eq(user_id))
+            .filter(user_provider_keys::Column::DeletedAt.is_null())
+            .all(self.db)
+            .await
+            .context("Failed to fetch provider keys")?
+            .into_iter()
+            .map(|key| key.provider_type_id)
+            .collect();
+
+        for (index, route) in routes.routes().iter().enumerate() {
+            let provider = ProviderTypes::find_by_id(route.provider_type_id)
+                .one(self.db)
+                .await
+                .context("Failed to fetch provider type")?;
+            if !provider.is_some_and(|provider| provider.is_active) {
+                return Err(business_error(format!(
+                    "model_routes[{index}]: 服务商不存在或已停用: {}",
+                    route.provider_type_id
+                )));
+            }
+
+            match &route.user_provider_keys_ids {
+                Some(key_ids) => {
+                    let keys = UserProviderKeys::find()
+                        .filter(user_provider_keys::Column::Id.is_in(key_ids.clone()))
+                        .filter(user_provider_keys::Column::UserId.eq(user_id))
+                        .filter(user_provider_keys::Column::DeletedAt.is_null())
+                        .all(self.db)
+                        .await
+                        .context("Failed to fetch provider keys")?;
+                    let found: HashSet<i32> = keys
+                        .iter()
+                        .filter(|key| key.provider_type_id == route.provider_type_id)
+                        .map(|key| key.id)
+                        .collect();
+                    if let Some(missing) = key_ids.iter().find(|id| !found.contains(id)) {
+                        return Err(business_error(format!(
+                            "model_routes[{index}]: 密钥 {missing} 不存在或不属于服务商 {}",
+                            route.provider_type_id
+                        )));
+                    }
+                }
+                None if route.provider_type_id != provider_type_id
+                    && !pool_providers.contains(&route.provider_type_id) =>
+                {
+                    return Err(business_error(format!(
+                        "model_routes[{index}]: 密钥池中没有服务商 {} 的密钥，请为规则指定 user_provider_keys_ids",
+                        route.provider_type_id
+                    )));
+                }
+                None => {}
+            }
+        }
+
+        serde_json::to_value(routes.routes())
+            .map(Some)
+            .context("Failed to serialize model routes")
+    }
+
     async fn find_user_api(&self, api_id: i32, user_id: i32) -> Result<user_service_apis::Model> {
         UserServiceApis::find_by_id(api_id)
             .filter(user_service_apis::Column::UserId.eq(user_id))
@@ -741,10 +1152,74 @@ impl<'a> ServiceApiService<'a> {
     }
 }
 
+/// 生成新的用户服务 API 密钥
+pub(crate) fn generate_service_api_key() -> String {
+    format!("sk-usr-{}", Uuid::new_v4().to_string().replace('-', ""))
+}
+
 fn business_error(message: impl Into<String>) -> ProxyError {
     crate::error::auth::AuthError::Message(message.into()).into()
 }
 
+/// 校验请求体改写规则；空数组视为未配置
+fn normalize_transform_rules(value: Option<&Value>) -> Result<Option<Value>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let rules = parse_transform_rules(value)?;
+    Ok((!rules.is_empty()).then(|| value.clone()))
+}
+
+fn normalize_response_headers(value: Option<&Value>) -> Result<Option<Value>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let headers = parse_custom_response_headers(value)?;
+    Ok((!headers.is_empty()).then(|| value.clone()))
+}
+
+/// 校验请求路径白名单并去除模式两端空白；空数组视为不限制
+fn normalize_allowed_paths(value: Option<&Value>) -> Result<Option<Value>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let patterns = parse_allowed_paths(value)?;
+    Ok((!patterns.is_empty()).then(|| Value::from(patterns)))
+}
+
+/// 校验系统提示词配置并补全缺省的注入方式
+fn normalize_system_prompt(value: Option<&Value>) -> Result<Option<Value>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let config = parse_system_prompt(value)?;
+    serde_json::to_value(config)
+        .map(Some)
+        .context("Failed to serialize system prompt")
+}
+
+/// 校验调度策略并统一为规范名称（如 `rr` 存为 `round_robin`）
+fn normalize_scheduling_strategy(value: Option<&str>) -> Result<Option<String>> {
+    value
+        .map(|name| SchedulingStrategy::parse_known(name).map(|s| s.as_str().to_string()))
+        .transpose()
+}
+
+fn normalize_stream_policy(value: Option<&str>) -> Result<Option<StreamPolicy>> {
+    value.map(StreamPolicy::parse_known).transpose()
+}
+
+fn normalize_upstream_error_mode(value: Option<&str>) -> Result<Option<UpstreamErrorMode>> {
+    value.map(UpstreamErrorMode::parse_known).transpose()
+}
+
+fn normalize_sla_target_ms(value: Option<i32>) -> Result<Option<i32>> {
+    if value.is_some_and(|ms| ms <= 0) {
+        return Err(business_error("sla_target_ms 必须为正数"));
+    }
+    Ok(value)
+}
+
 fn ensure_positive(id: i32) -> Result<()> {
     if id <= 0 {
         return Err(business_error("Invalid API ID"));
@@ -0,0 +1,95 @@
+//! # 影子路由差异分析服务
+//!
+//! 按时间范围汇总主请求与影子请求的对比记录（仅管理员），并返回逐请求差异供排查。
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::ensure;
+use crate::error::{Result, auth::AuthError, conversion::ConversionError};
+use crate::management::middleware::AuthContext;
+use crate::trace::{ShadowComparisonStore, ShadowDiffSummary, ShadowRequestDiff};
+use sea_orm::DatabaseConnection;
+
+/// 未指定起止时间时的默认范围（最近 24 小时）
+const DEFAULT_RANGE_HOURS: i64 = 24;
+const DEFAULT_DIFF_LIMIT: usize = 100;
+const MAX_DIFF_LIMIT: usize = 1000;
+
+/// 差异查询参数
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShadowDiffQuery {
+    /// 起始时间（含），缺省为结束时间前 24 小时
+    pub start: Option<DateTime<Utc>>,
+    /// 结束时间（不含），缺省为当前时间
+    pub end: Option<DateTime<Utc>>,
+    /// 只返回状态码不一致或影子请求失败的逐请求差异
+    #[serde(default)]
+    pub mismatches_only: bool,
+    /// 逐请求差异条数上限，汇总指标不受影响
+    pub limit: Option<usize>,
+}
+
+/// 差异分析结果
+#[derive(Debug, Serialize)]
+pub struct ShadowDiffsResponse {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub summary: ShadowDiffSummary,
+    pub diffs: Vec<ShadowRequestDiff>,
+}
+
+#[derive(Clone)]
+pub struct ShadowDiffService {
+    store: ShadowComparisonStore,
+}
+
+impl ShadowDiffService {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self {
+            store: ShadowComparisonStore::new(db),
+        }
+    }
+
+    /// 汇总时间范围内的对比记录，逐请求差异按时间倒序
+    pub async fn diffs(
+        &self,
+        auth: &AuthContext,
+        query: &ShadowDiffQuery,
+    ) -> Result<ShadowDiffsResponse> {
+        ensure!(
+            auth.is_admin,
+            AuthError::PermissionDenied {
+                required: "admin".to_string(),
+                actual: "user".to_string(),
+            }
+        );
+        let end = query.end.unwrap_or_else(Utc::now);
+        let start = query
+            .start
+            .unwrap_or_else(|| end - Duration::hours(DEFAULT_RANGE_HOURS));
+        ensure!(start < end, ConversionError::message("start 必须早于 end"));
+
+        let rows = self.store.list(start.naive_utc(), end.naive_utc()).await?;
+        let summary = ShadowDiffSummary::from_rows(&rows);
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_DIFF_LIMIT)
+            .clamp(1, MAX_DIFF_LIMIT);
+        let diffs = rows
+            .iter()
+            .map(ShadowRequestDiff::from)
+            .filter(|diff| !query.mismatches_only || diff.is_mismatch())
+            .take(limit)
+            .collect();
+
+        Ok(ShadowDiffsResponse {
+            start,
+            end,
+            summary,
+            diffs,
+        })
+    }
+}
@@ -78,6 +78,34 @@ pub struct ModelsStatisticsResponse {
     pub model_usage: Vec<ModelStatistics>,
 }
 
+/// 未记录模型的追踪归入的分组名称
+pub const UNKNOWN_MODEL: &str = "unknown";
+
+/// 单个模型的用量汇总
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelBreakdown {
+    pub model: String,
+    pub requests: i64,
+    pub successful_requests: i64,
+    pub failed_requests: i64,
+    pub tokens_prompt: i64,
+    pub tokens_completion: i64,
+    pub tokens_total: i64,
+    pub cache_create_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cost: f64,
+}
+
+/// 按模型拆分的用量响应
+#[derive(Debug, Serialize)]
+pub struct ModelsBreakdownResponse {
+    /// 统计区间起点（调用方时区）
+    pub start: String,
+    /// 统计区间终点（调用方时区，不含）
+    pub end: String,
+    pub models: Vec<ModelBreakdown>,
+}
+
 /// Token 使用趋势数据点
 #[derive(Debug, Serialize)]
 pub struct TokenTrendPoint {
@@ -138,9 +166,13 @@ pub struct StatisticsService<'a> {
 impl<'a> StatisticsService<'a> {
     #[must_use]
     pub fn new(state: &'a ManagementState) -> Self {
-        Self {
-            db: state.database.as_ref(),
-        }
+        Self::from_db(state.database.as_ref())
+    }
+
+    /// 直接基于数据库连接创建（供任务与测试使用）
+    #[must_use]
+    pub const fn from_db(db: &'a DatabaseConnection) -> Self {
+        Self { db }
     }
 
     const fn db(&self) -> &'a DatabaseConnection {
@@ -315,6 +347,38 @@ impl<'a> StatisticsService<'a> {
         Ok(ModelsStatisticsResponse { model_usage })
     }
 
+    /// 按模型拆分的请求数、Token 与费用
+    ///
+    /// 同时提供 `start` 与 `end` 时按调用方时区解析为自定义区间，否则沿用 `range` 的预设区间。
+    /// 包含失败请求，未识别模型的请求归入 `unknown`。
+    pub async fn models_breakdown(
+        &self,
+        user_id: i32,
+        query: &TimeRangeQuery,
+        timezone: &TimezoneContext,
+    ) -> Result<ModelsBreakdownResponse> {
+        let custom_range;
+        let query = if query.range.is_none() && query.start.is_some() && query.end.is_some() {
+            custom_range = TimeRangeQuery {
+                range: Some("custom".to_string()),
+                start: query.start.clone(),
+                end: query.end.clone(),
+            };
+            &custom_range
+        } else {
+            query
+        };
+        let (start_time, end_time) = parse_time_range(query, timezone)
+            .context("Failed to parse time range for models breakdown")?;
+
+        let traces = self.fetch_traces(user_id, start_time, end_time).await?;
+        Ok(ModelsBreakdownResponse {
+            start: start_time.with_timezone(&timezone.timezone).to_rfc3339(),
+            end: end_time.with_timezone(&timezone.timezone).to_rfc3339(),
+            models: aggregate_models_breakdown(&traces),
+        })
+    }
+
     /// Token 使用趋势
     pub async fn tokens_trend(
         &self,
@@ -616,6 +680,51 @@ fn aggregate_model_usage(
     stats
 }
 
+/// 按 `model_used` 汇总追踪记录，按请求数降序
+fn aggregate_models_breakdown(traces: &[proxy_tracing::Model]) -> Vec<ModelBreakdown> {
+    let mut stats: HashMap<&str, ModelBreakdown> = HashMap::new();
+    for trace in traces {
+        let model = trace
+            .model_used
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(UNKNOWN_MODEL);
+        let entry = stats.entry(model).or_insert_with(|| ModelBreakdown {
+            model: model.to_string(),
+            requests: 0,
+            successful_requests: 0,
+            failed_requests: 0,
+            tokens_prompt: 0,
+            tokens_completion: 0,
+            tokens_total: 0,
+            cache_create_tokens: 0,
+            cache_read_tokens: 0,
+            cost: 0.0,
+        });
+        entry.requests += 1;
+        if trace.is_success {
+            entry.successful_requests += 1;
+        } else {
+            entry.failed_requests += 1;
+        }
+        entry.tokens_prompt += i64::from(trace.tokens_prompt.unwrap_or(0));
+        entry.tokens_completion += i64::from(trace.tokens_completion.unwrap_or(0));
+        entry.tokens_total += i64::from(trace.tokens_total.unwrap_or(0));
+        entry.cache_create_tokens += i64::from(trace.cache_create_tokens.unwrap_or(0));
+        entry.cache_read_tokens += i64::from(trace.cache_read_tokens.unwrap_or(0));
+        entry.cost += trace.cost.unwrap_or(0.0);
+    }
+
+    let mut models: Vec<ModelBreakdown> = stats.into_values().collect();
+    models.sort_by(|a, b| {
+        b.requests
+            .cmp(&a.requests)
+            .then_with(|| a.model.cmp(&b.model))
+    });
+    models
+}
+
 fn build_model_usage_list(mut stats: HashMap<String, ModelUsageAggregate>) -> Vec<ModelUsage> {
     let mut sorted: Vec<(String, ModelUsageAggregate)> = stats.drain().collect();
     sorted.sort_by(|a, b| b.1.usage.cmp(&a.1.usage));
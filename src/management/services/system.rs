@@ -3,21 +3,40 @@
 //! 提供管理端系统信息、运行指标等业务逻辑，供 handler 复用。
 
 use std::{
-    sync::{Mutex, OnceLock},
+    sync::{Arc, Mutex, OnceLock},
     time::Instant,
 };
 
 use chrono::Utc;
 use chrono_tz::Tz;
-use serde::Serialize;
+use entity::{spend_anomaly_flags, task_run_history};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
 use sysinfo::{Disks, System};
 use tokio::task;
 
-use crate::error::{Result, management::ManagementError};
-use crate::logging::{LogComponent, LogStage};
-use crate::lwarn;
+use crate::app::task_history::{TaskRunHistory, TaskRunHistoryQuery};
+use crate::app::tasks::TaskType;
+use crate::cache;
+use crate::config::HealthCheckConfig;
+use crate::ensure;
+use crate::error::{
+    Result,
+    auth::AuthError,
+    database::{self, DatabaseError},
+    management::ManagementError,
+};
+use crate::key_pool::ProviderHealthCheckTask;
+use crate::logging::{LogComponent, LogStage, file_sink};
+use crate::management::middleware::auth::AuthContext;
 use crate::management::server::ManagementState;
+use crate::proxy::maintenance::{MaintenanceState, MaintenanceUpdate};
+use crate::proxy::upstream_service::{UpstreamConnectionStats, upstream_connection_stats};
+use crate::trace;
 use crate::types::timezone_utils;
+use crate::{linfo, lwarn};
 
 use super::shared::metrics::ratio_as_percentage;
 
@@ -60,6 +79,11 @@ pub struct SystemMetrics {
     pub cpu_usage: f32,
     pub memory: MemoryMetrics,
     pub disk: DiskMetrics,
+    pub database: DatabaseMetrics,
+    pub cache: CacheMetrics,
+    pub logging: LoggingMetrics,
+    /// 上游连接复用统计
+    pub upstream_connections: UpstreamConnectionStats,
     pub uptime: String,
 }
 
@@ -77,6 +101,26 @@ pub struct DiskMetrics {
     pub usage_percentage: f64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct DatabaseMetrics {
+    /// 启动以来获取数据库连接超时（连接池耗尽）的次数
+    pub pool_exhausted_total: u64,
+    /// 启动以来因追踪写入队列写满丢弃的追踪记录数
+    pub trace_writes_dropped_total: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheMetrics {
+    /// 启动以来因缓存不可用而放行的限流与配额检查次数
+    pub fail_open_total: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoggingMetrics {
+    /// 启动以来因写盘线程跟不上或写盘失败而丢弃的文件日志条数
+    pub file_lines_dropped_total: u64,
+}
+
 /// 初始化启动时间缓存。
 pub fn init_start_time() {
     START_TIME.set(Instant::now()).ok();
@@ -152,6 +196,17 @@ pub async fn collect_system_metrics() -> Result<SystemMetrics> {
             cpu_usage,
             memory,
             disk,
+            database: DatabaseMetrics {
+                pool_exhausted_total: database::pool_exhausted_total(),
+                trace_writes_dropped_total: trace::writer::dropped_total(),
+            },
+            cache: CacheMetrics {
+                fail_open_total: cache::fail_open_total(),
+            },
+            logging: LoggingMetrics {
+                file_lines_dropped_total: file_sink::dropped_log_lines(),
+            },
+            upstream_connections: upstream_connection_stats(),
             uptime: format_uptime(uptime_seconds()),
         }
     })
@@ -173,6 +228,166 @@ pub async fn collect_system_metrics() -> Result<SystemMetrics> {
     Ok(metrics)
 }
 
+/// 切换维护模式（仅管理员）
+pub async fn update_maintenance(
+    state: &ManagementState,
+    auth: &AuthContext,
+    update: MaintenanceUpdate,
+) -> Result<MaintenanceState> {
+    ensure_admin(auth)?;
+
+    let maintenance = state.maintenance().update(update).await?;
+    linfo!(
+        "system",
+        LogStage::Internal,
+        LogComponent::Main,
+        "maintenance_mode_updated",
+        "维护模式已切换",
+        enabled = maintenance.enabled,
+        retry_after_secs = maintenance.retry_after_secs,
+        user_id = auth.user_id
+    );
+    Ok(maintenance)
+}
+
+fn provider_health_check_task(state: &ManagementState) -> Result<Arc<ProviderHealthCheckTask>> {
+    state
+        .tasks()
+        .get_task::<ProviderHealthCheckTask>(TaskType::ProviderHealthCheck)
+        .ok_or_else(|| {
+            ManagementError::MissingTask {
+                task: "ProviderHealthCheck",
+            }
+            .into()
+        })
+}
+
+/// 获取服务商健康检查当前配置
+pub async fn health_check_config(state: &ManagementState) -> Result<HealthCheckConfig> {
+    Ok(provider_health_check_task(state)?.config().await)
+}
+
+/// 更新服务商健康检查配置（仅管理员），运行中的检查按新配置重新排期
+pub async fn update_health_check(
+    state: &ManagementState,
+    auth: &AuthContext,
+    config: HealthCheckConfig,
+) -> Result<HealthCheckConfig> {
+    ensure_admin(auth)?;
+    config.validate()?;
+
+    provider_health_check_task(state)?
+        .reload(config.clone())
+        .await?;
+    linfo!(
+        "system",
+        LogStage::Internal,
+        LogComponent::HealthChecker,
+        "health_check_config_updated",
+        "服务商健康检查配置已更新",
+        enabled = config.enabled,
+        default_interval_secs = config.default_interval_secs,
+        provider_overrides = config.providers.len(),
+        user_id = auth.user_id
+    );
+    Ok(config)
+}
+
+/// 查询后台任务执行历史，按开始时间倒序
+pub async fn list_task_runs(
+    state: &ManagementState,
+    query: &TaskRunHistoryQuery,
+) -> Result<Vec<task_run_history::Model>> {
+    TaskRunHistory::new(state.database.clone())
+        .list(query)
+        .await
+}
+
+/// 消费异常标记查询参数
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SpendAnomalyQuery {
+    pub user_id: Option<i32>,
+    /// 只返回未确认的标记
+    #[serde(default)]
+    pub unacknowledged: bool,
+    pub limit: Option<u64>,
+}
+
+const DEFAULT_SPEND_ANOMALY_LIMIT: u64 = 50;
+const MAX_SPEND_ANOMALY_LIMIT: u64 = 500;
+
+/// 查询消费异常标记（仅管理员），按标记时间倒序
+pub async fn list_spend_anomalies(
+    state: &ManagementState,
+    auth: &AuthContext,
+    query: &SpendAnomalyQuery,
+) -> Result<Vec<spend_anomaly_flags::Model>> {
+    ensure_admin(auth)?;
+
+    let mut select = spend_anomaly_flags::Entity::find();
+    if let Some(user_id) = query.user_id {
+        select = select.filter(spend_anomaly_flags::Column::UserId.eq(user_id));
+    }
+    if query.unacknowledged {
+        select = select.filter(spend_anomaly_flags::Column::AcknowledgedAt.is_null());
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SPEND_ANOMALY_LIMIT)
+        .clamp(1, MAX_SPEND_ANOMALY_LIMIT);
+
+    Ok(select
+        .order_by_desc(spend_anomaly_flags::Column::CreatedAt)
+        .order_by_desc(spend_anomaly_flags::Column::Id)
+        .limit(limit)
+        .all(state.database.as_ref())
+        .await?)
+}
+
+/// 确认消费异常标记（仅管理员）；重复确认保留首次确认时间
+pub async fn acknowledge_spend_anomaly(
+    state: &ManagementState,
+    auth: &AuthContext,
+    flag_id: i32,
+) -> Result<spend_anomaly_flags::Model> {
+    ensure_admin(auth)?;
+
+    let flag = spend_anomaly_flags::Entity::find_by_id(flag_id)
+        .one(state.database.as_ref())
+        .await?
+        .ok_or_else(|| {
+            DatabaseError::NotFound(format!("Spend anomaly flag not found: {flag_id}"))
+        })?;
+    if flag.acknowledged_at.is_some() {
+        return Ok(flag);
+    }
+
+    let mut active: spend_anomaly_flags::ActiveModel = flag.into();
+    active.acknowledged_at = Set(Some(Utc::now().naive_utc()));
+    let flag = active.update(state.database.as_ref()).await?;
+    linfo!(
+        "system",
+        LogStage::Internal,
+        LogComponent::Main,
+        "spend_anomaly_acknowledged",
+        "消费异常标记已确认",
+        flag_id = flag.id,
+        user_id = auth.user_id
+    );
+    Ok(flag)
+}
+
+fn ensure_admin(auth: &AuthContext) -> Result<()> {
+    ensure!(
+        auth.is_admin,
+        AuthError::PermissionDenied {
+            required: "admin".to_string(),
+            actual: "user".to_string(),
+        }
+    );
+    Ok(())
+}
+
 /// 构建管理根信息。
 #[must_use]
 pub fn build_root_metadata(timezone: &Tz) -> serde_json::Value {
@@ -0,0 +1,229 @@
+//! # 追踪请求回放
+//!
+//! 按追踪记录中采集的请求体（`trace.capture_request_body`）重建请求，使用指定的服务商密钥
+//! 直连上游并返回响应，供排查问题使用。回放不经过代理管道：不写追踪记录、不计入用户的
+//! 限流与用量，本次回放的用量在响应中单独给出。
+
+use crate::auth::types::AuthType;
+use crate::collect::stream_usage::SseUsageTracker;
+use crate::collect::types::TokenUsageMetrics;
+use crate::collect::usage_model::{extract_tokens_from_json, normalize};
+use crate::ensure;
+use crate::error::{Result, auth::AuthError, database::DatabaseError, management::ManagementError};
+use crate::linfo;
+use crate::logging::{LogComponent, LogStage};
+use crate::management::middleware::auth::AuthContext;
+use crate::proxy::provider_strategy::{ProviderType, direct_base_url};
+use entity::{provider_types, proxy_tracing, user_provider_keys};
+use reqwest::header::CONTENT_TYPE;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 回放请求的超时
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 回放参数
+#[derive(Debug, Default, Deserialize)]
+pub struct TraceReplayRequest {
+    /// 回放使用的服务商密钥，缺省时使用原请求的密钥
+    #[serde(default)]
+    pub provider_key_id: Option<i32>,
+}
+
+/// 回放结果
+#[derive(Debug, Serialize)]
+pub struct TraceReplayResponse {
+    /// 固定为 `true`，标记该结果来自回放
+    pub replay: bool,
+    pub trace_id: i32,
+    /// 原请求的 `request_id`
+    pub original_request_id: String,
+    pub provider_key_id: i32,
+    pub provider: String,
+    pub method: String,
+    pub path: String,
+    pub status_code: u16,
+    pub duration_ms: u64,
+    /// 本次回放的用量（单独统计，不写入追踪记录、不计入用户额度）
+    pub usage: TokenUsageMetrics,
+    /// 上游响应体：JSON 响应原样返回，其它内容（如 SSE）以字符串返回
+    pub response_body: Value,
+}
+
+/// 追踪请求回放服务
+pub struct TraceReplayService {
+    db: Arc<DatabaseConnection>,
+    client: reqwest::Client,
+}
+
+impl TraceReplayService {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>, client: reqwest::Client) -> Self {
+        Self { db, client }
+    }
+
+    /// 回放追踪记录对应的请求（仅管理员）
+    pub async fn replay(
+        &self,
+        auth: &AuthContext,
+        trace_id: i32,
+        request: &TraceReplayRequest,
+    ) -> Result<TraceReplayResponse> {
+        ensure!(
+            auth.is_admin,
+            AuthError::PermissionDenied {
+                required: "admin".to_string(),
+                actual: "user".to_string(),
+            }
+        );
+
+        let trace = proxy_tracing::Entity::find_by_id(trace_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| DatabaseError::NotFound(format!("Trace not found: {trace_id}")))?;
+        let body = trace.request_body.clone().ok_or_else(|| {
+            not_replayable(
+                trace_id,
+                "未采集请求体（需开启 trace.capture_request_body）",
+            )
+        })?;
+        let path = trace
+            .path
+            .clone()
+            .ok_or_else(|| not_replayable(trace_id, "缺少请求路径"))?;
+        let method = reqwest::Method::from_bytes(trace.method.as_bytes())
+            .map_err(|_| not_replayable(trace_id, format!("无效的请求方法: {}", trace.method)))?;
+
+        let (key, provider) = self.load_key(&trace, request).await?;
+        let provider_type = ProviderType::from_str(&provider.name).ok_or_else(|| {
+            not_replayable(trace_id, format!("不支持回放的服务商: {}", provider.name))
+        })?;
+
+        let url = format!("{}{path}", direct_base_url(&provider.base_url));
+        let started = Instant::now();
+        let response = provider_type
+            .authorize_direct_request(self.client.request(method, url), &key.api_key)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .timeout(REPLAY_TIMEOUT)
+            .send()
+            .await?;
+        let status_code = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_ascii_lowercase);
+        let bytes = response.bytes().await?;
+        let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+        let (usage, response_body) =
+            parse_replay_response(Some(&provider), content_type.as_deref(), &bytes);
+
+        linfo!(
+            &trace.request_id,
+            LogStage::ExternalApi,
+            LogComponent::Tracing,
+            "trace_replayed",
+            "追踪请求回放完成",
+            trace_id = trace_id,
+            provider_key_id = key.id,
+            status_code = status_code,
+            duration_ms = duration_ms,
+            operator_user_id = auth.user_id
+        );
+
+        Ok(TraceReplayResponse {
+            replay: true,
+            trace_id,
+            original_request_id: trace.request_id,
+            provider_key_id: key.id,
+            provider: provider.name,
+            method: trace.method,
+            path,
+            status_code,
+            duration_ms,
+            usage,
+            response_body,
+        })
+    }
+
+    /// 加载回放使用的密钥：必须是启用中的 API Key，且与原请求属于同一服务商
+    async fn load_key(
+        &self,
+        trace: &proxy_tracing::Model,
+        request: &TraceReplayRequest,
+    ) -> Result<(user_provider_keys::Model, provider_types::Model)> {
+        let trace_id = trace.id;
+        let key_id = request
+            .provider_key_id
+            .or(trace.user_provider_key_id)
+            .ok_or_else(|| not_replayable(trace_id, "未指定服务商密钥"))?;
+        let key = user_provider_keys::Entity::find_by_id(key_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| not_replayable(trace_id, format!("服务商密钥 {key_id} 不存在")))?;
+
+        ensure!(
+            key.is_active,
+            not_replayable(trace_id, format!("服务商密钥 {key_id} 已禁用"))
+        );
+        ensure!(
+            key.auth_type.parse::<AuthType>()? == AuthType::ApiKey,
+            not_replayable(trace_id, "仅支持使用 API Key 类型的密钥回放")
+        );
+        ensure!(
+            trace
+                .provider_type_id
+                .is_none_or(|provider_type_id| provider_type_id == key.provider_type_id),
+            not_replayable(
+                trace_id,
+                format!("服务商密钥 {key_id} 与原请求的服务商不一致")
+            )
+        );
+
+        let provider = provider_types::Entity::find_by_id(key.provider_type_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| {
+                not_replayable(
+                    trace_id,
+                    format!("服务商类型 {} 不存在", key.provider_type_id),
+                )
+            })?;
+        Ok((key, provider))
+    }
+}
+
+fn not_replayable(trace_id: i32, reason: impl Into<String>) -> ManagementError {
+    ManagementError::TraceNotReplayable {
+        trace_id,
+        reason: reason.into(),
+    }
+}
+
+/// 解析回放响应并提取用量：SSE 按事件汇总，JSON 整体提取，其它内容用量置零
+fn parse_replay_response(
+    provider: Option<&provider_types::Model>,
+    content_type: Option<&str>,
+    bytes: &[u8],
+) -> (TokenUsageMetrics, Value) {
+    let text = || Value::String(String::from_utf8_lossy(bytes).into_owned());
+
+    if content_type.is_some_and(|ct| ct.contains("text/event-stream")) {
+        let mut tracker = SseUsageTracker::new(provider);
+        tracker.observe_chunk(bytes, provider);
+        return (tracker.finish(provider).usage, text());
+    }
+
+    if let Ok(json) = serde_json::from_slice::<Value>(bytes) {
+        return (extract_tokens_from_json(provider, &json), json);
+    }
+
+    let mut usage = TokenUsageMetrics::default();
+    normalize(&mut usage);
+    (usage, text())
+}
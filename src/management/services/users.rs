@@ -43,6 +43,15 @@ pub struct CreateUserRequest {
     pub email: String,
     pub password: String,
     pub is_admin: Option<bool>,
+    /// 每月免费 token 额度
+    #[serde(default)]
+    pub included_tokens_per_month: Option<i64>,
+    /// 每月免费请求数额度
+    #[serde(default)]
+    pub included_requests_per_month: Option<i64>,
+    /// 积分余额，为空表示不启用积分额度
+    #[serde(default)]
+    pub credits_balance: Option<i64>,
 }
 
 /// 更新用户请求
@@ -53,6 +62,18 @@ pub struct UpdateUserRequest {
     pub password: Option<String>,
     pub is_active: Option<bool>,
     pub is_admin: Option<bool>,
+    /// 每月免费 token 额度，传 0 表示取消
+    #[serde(default)]
+    pub included_tokens_per_month: Option<i64>,
+    /// 每月免费请求数额度，传 0 表示取消
+    #[serde(default)]
+    pub included_requests_per_month: Option<i64>,
+    /// 设置积分余额（启用积分额度）
+    #[serde(default)]
+    pub credits_balance: Option<i64>,
+    /// 传 true 表示停用积分额度
+    #[serde(default)]
+    pub disable_credits: Option<bool>,
 }
 
 /// 批量删除请求
@@ -88,6 +109,10 @@ pub struct UserResponse {
     pub email: String,
     pub is_active: bool,
     pub is_admin: bool,
+    pub included_tokens_per_month: Option<i64>,
+    pub included_requests_per_month: Option<i64>,
+    pub credits_balance: Option<i64>,
+    pub credits_reserved: i64,
     pub created_at: String,
     pub updated_at: String,
     pub last_login: Option<String>,
@@ -104,6 +129,10 @@ impl UserResponse {
             email: user.email,
             is_active: user.is_active,
             is_admin: user.is_admin,
+            included_tokens_per_month: user.included_tokens_per_month,
+            included_requests_per_month: user.included_requests_per_month,
+            credits_balance: user.credits_balance,
+            credits_reserved: user.credits_reserved,
             created_at: timezone_utils::format_utc_for_response(
                 &user.created_at.and_utc(),
                 &timezone.timezone,
@@ -407,6 +436,11 @@ impl<'a> UsersService<'a> {
             is_active: Set(true),
             is_admin: Set(is_admin),
             last_login: Set(None),
+            included_tokens_per_month: Set(request.included_tokens_per_month.filter(|v| *v > 0)),
+            included_requests_per_month: Set(request
+                .included_requests_per_month
+                .filter(|v| *v > 0)),
+            credits_balance: Set(request.credits_balance),
             created_at: Set(now),
             updated_at: Set(now),
             ..Default::default()
@@ -611,6 +645,17 @@ impl<'a> UsersService<'a> {
         if let Some(is_admin) = request.is_admin {
             active_model.is_admin = Set(is_admin);
         }
+        if let Some(tokens) = request.included_tokens_per_month {
+            active_model.included_tokens_per_month = Set((tokens > 0).then_some(tokens));
+        }
+        if let Some(requests) = request.included_requests_per_month {
+            active_model.included_requests_per_month = Set((requests > 0).then_some(requests));
+        }
+        if request.disable_credits == Some(true) {
+            active_model.credits_balance = Set(None);
+        } else if let Some(balance) = request.credits_balance {
+            active_model.credits_balance = Set(Some(balance));
+        }
         if let Some(password) = &request.password {
             ensure_password_strength(password)?;
             active_model.password_hash = Set(hash_password(password)?);
@@ -865,6 +910,11 @@ fn validate_new_user_input(request: &CreateUserRequest) -> Result<()> {
     ensure_username(&request.username)?;
     validate_email(&request.email)?;
     ensure_password_strength(&request.password)?;
+    ensure_included_quota(
+        request.included_tokens_per_month,
+        request.included_requests_per_month,
+    )?;
+    ensure_credits_balance(request.credits_balance)?;
     Ok(())
 }
 
@@ -878,9 +928,30 @@ fn validate_update_input(request: &UpdateUserRequest) -> Result<()> {
     if let Some(password) = &request.password {
         ensure_password_strength(password)?;
     }
+    ensure_included_quota(
+        request.included_tokens_per_month,
+        request.included_requests_per_month,
+    )?;
+    ensure_credits_balance(request.credits_balance)?;
     Ok(())
 }
 
+fn ensure_credits_balance(balance: Option<i64>) -> Result<()> {
+    if balance.is_some_and(i64::is_negative) {
+        Err(business_error("积分余额不能为负数"))
+    } else {
+        Ok(())
+    }
+}
+
+fn ensure_included_quota(tokens: Option<i64>, requests: Option<i64>) -> Result<()> {
+    if tokens.is_some_and(i64::is_negative) || requests.is_some_and(i64::is_negative) {
+        Err(business_error("免费额度不能为负数"))
+    } else {
+        Ok(())
+    }
+}
+
 fn ensure_username(username: &str) -> Result<()> {
     if (3..=50).contains(&username.len()) {
         Ok(())
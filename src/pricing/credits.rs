@@ -0,0 +1,167 @@
+//! # 积分额度
+//!
+//! 用户配置了积分余额（`users.credits_balance`）时，转发前先在 `users.credits_reserved`
+//! 中预留积分：可用积分（余额减去已预留）不足时直接拒绝请求。请求成功后按模型权重折算实际
+//! 消耗从余额扣减并释放预留；请求失败只释放预留，不扣减积分。实际消耗可能超过预留，
+//! 余额因此变为负数时，后续请求会被拒绝直到管理员充值。
+
+use entity::users;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::Arc;
+
+use super::TokenUsage;
+use crate::auth::api_key_usage_limit_service::ApiKeyUsageLimitService;
+use crate::config::CreditsConfig;
+use crate::error::auth::UsageLimitKind;
+use crate::error::{Context, Result};
+use crate::logging::{LogComponent, LogStage};
+use crate::{ldebug, linfo};
+
+/// 转发前为请求预留的积分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreditReservation {
+    pub user_id: i32,
+    pub amount: i64,
+}
+
+/// 积分预留、结算与释放
+#[derive(Debug, Clone)]
+pub struct CreditsService {
+    db: Arc<DatabaseConnection>,
+    config: CreditsConfig,
+}
+
+impl CreditsService {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>, config: CreditsConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// 为请求预留积分；用户未启用积分额度时返回 `None`
+    ///
+    /// 可用积分不足时返回 `UsageLimitExceeded`（`UsageLimitKind::Credits`）。
+    pub async fn reserve(
+        &self,
+        user_id: i32,
+        request_id: &str,
+    ) -> Result<Option<CreditReservation>> {
+        let Some(user) = users::Entity::find_by_id(user_id)
+            .one(&*self.db)
+            .await
+            .context("Failed to load user credits")?
+        else {
+            return Ok(None);
+        };
+        let Some(balance) = user.credits_balance else {
+            return Ok(None);
+        };
+
+        let amount = self.config.reserve_amount();
+        // 条件更新保证并发请求不会预留超过余额的积分
+        let result = users::Entity::update_many()
+            .col_expr(
+                users::Column::CreditsReserved,
+                Expr::col(users::Column::CreditsReserved).add(amount),
+            )
+            .filter(users::Column::Id.eq(user_id))
+            .filter(users::Column::CreditsBalance.is_not_null())
+            .filter(
+                Expr::expr(
+                    Expr::col(users::Column::CreditsBalance)
+                        .sub(Expr::col(users::Column::CreditsReserved)),
+                )
+                .gte(amount),
+            )
+            .exec(&*self.db)
+            .await
+            .context("Failed to reserve credits")?;
+
+        if result.rows_affected == 0 {
+            let available = balance.saturating_sub(user.credits_reserved);
+            return Err(ApiKeyUsageLimitService::rate_limit_error(
+                UsageLimitKind::Credits,
+                Some(to_f64(amount)),
+                Some(to_f64(available)),
+                None,
+            ));
+        }
+
+        ldebug!(
+            request_id,
+            LogStage::Authentication,
+            LogComponent::Statistics,
+            "credits_reserved",
+            "Reserved credits before forwarding",
+            user_id = user_id,
+            amount = amount
+        );
+        Ok(Some(CreditReservation { user_id, amount }))
+    }
+
+    /// 请求成功：按实际用量扣减余额并释放预留，返回扣减的积分
+    pub async fn settle(
+        &self,
+        reservation: CreditReservation,
+        model: Option<&str>,
+        usage: &TokenUsage,
+        request_id: &str,
+    ) -> Result<i64> {
+        let credits = self.config.credits_for(model, usage);
+        users::Entity::update_many()
+            .col_expr(
+                users::Column::CreditsBalance,
+                Expr::col(users::Column::CreditsBalance).sub(credits),
+            )
+            .col_expr(
+                users::Column::CreditsReserved,
+                Expr::col(users::Column::CreditsReserved).sub(reservation.amount),
+            )
+            .filter(users::Column::Id.eq(reservation.user_id))
+            .exec(&*self.db)
+            .await
+            .context("Failed to deduct credits")?;
+
+        linfo!(
+            request_id,
+            LogStage::Internal,
+            LogComponent::Statistics,
+            "credits_deducted",
+            "Deducted credits for completed request",
+            user_id = reservation.user_id,
+            model = model,
+            credits = credits,
+            reserved = reservation.amount
+        );
+        Ok(credits)
+    }
+
+    /// 请求失败：只释放预留，不扣减余额
+    pub async fn release(&self, reservation: CreditReservation, request_id: &str) -> Result<()> {
+        users::Entity::update_many()
+            .col_expr(
+                users::Column::CreditsReserved,
+                Expr::col(users::Column::CreditsReserved).sub(reservation.amount),
+            )
+            .filter(users::Column::Id.eq(reservation.user_id))
+            .exec(&*self.db)
+            .await
+            .context("Failed to release credits reservation")?;
+
+        ldebug!(
+            request_id,
+            LogStage::Internal,
+            LogComponent::Statistics,
+            "credits_released",
+            "Released credits reservation for failed request",
+            user_id = reservation.user_id,
+            amount = reservation.amount
+        );
+        Ok(())
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn to_f64(value: i64) -> f64 {
+    value as f64
+}
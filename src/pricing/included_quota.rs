@@ -0,0 +1,164 @@
+//! # 套餐内免费额度
+//!
+//! 用户配置了每月免费额度（`users.included_requests_per_month` /
+//! `users.included_tokens_per_month`）时，先用免费额度抵扣请求费用，剩余部分才计费。
+//! 请求数额度优先：当月前 N 个计费请求全额免费；请求数额度用完后再按 token 额度
+//! 按比例抵扣。已消耗的额度按 UTC 自然月累计在 `user_included_quota_usage` 表中。
+
+use chrono::Utc;
+use entity::{user_included_quota_usage, users};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+
+use super::{CostCalculationResult, PricingCalculatorService, TokenUsage};
+use crate::error::{Context, Result};
+use crate::linfo;
+use crate::logging::{LogComponent, LogStage};
+use crate::types::{TokenCount, ratio_as_f64};
+
+/// 单次请求从免费额度中抵扣的部分
+struct QuotaConsumption {
+    requests: i64,
+    tokens: TokenCount,
+    /// 被抵扣的费用占比
+    ratio: f64,
+}
+
+impl PricingCalculatorService {
+    /// 用当月免费额度抵扣费用，返回抵扣后的应付费用
+    ///
+    /// 未找到定价（fallback）或费用为 0 的请求不消耗额度。
+    pub async fn apply_included_quota(
+        &self,
+        user_id: i32,
+        token_usage: &TokenUsage,
+        result: CostCalculationResult,
+        request_id: &str,
+    ) -> Result<CostCalculationResult> {
+        if result.used_fallback || result.total_cost <= 0.0 {
+            return Ok(result);
+        }
+        let Some(user) = users::Entity::find_by_id(user_id)
+            .one(&*self.db)
+            .await
+            .context("Failed to load user included quota")?
+        else {
+            return Ok(result);
+        };
+        let request_limit = user.included_requests_per_month.unwrap_or(0);
+        let token_limit = user.included_tokens_per_month.unwrap_or(0);
+        if request_limit <= 0 && token_limit <= 0 {
+            return Ok(result);
+        }
+
+        let period = Utc::now().format("%Y-%m").to_string();
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin included quota transaction")?;
+        let usage = user_included_quota_usage::Entity::find()
+            .filter(user_included_quota_usage::Column::UserId.eq(user_id))
+            .filter(user_included_quota_usage::Column::Period.eq(&period))
+            .one(&txn)
+            .await
+            .context("Failed to load included quota usage")?;
+        let (used_requests, used_tokens) = usage
+            .as_ref()
+            .map_or((0, 0), |row| (row.used_requests, row.used_tokens));
+
+        let Some(consumption) = consume(
+            request_limit.saturating_sub(used_requests),
+            token_limit.saturating_sub(used_tokens),
+            billable_tokens(token_usage),
+        ) else {
+            return Ok(result);
+        };
+
+        let consumed_tokens = i64::try_from(consumption.tokens).unwrap_or(i64::MAX);
+        let now = Utc::now().naive_utc();
+        if let Some(row) = usage {
+            let mut active: user_included_quota_usage::ActiveModel = row.into();
+            active.used_requests = Set(used_requests.saturating_add(consumption.requests));
+            active.used_tokens = Set(used_tokens.saturating_add(consumed_tokens));
+            active.updated_at = Set(now);
+            active
+                .update(&txn)
+                .await
+                .context("Failed to update included quota usage")?;
+        } else {
+            user_included_quota_usage::ActiveModel {
+                user_id: Set(user_id),
+                period: Set(period.clone()),
+                used_requests: Set(consumption.requests),
+                used_tokens: Set(consumed_tokens),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await
+            .context("Failed to insert included quota usage")?;
+        }
+        txn.commit()
+            .await
+            .context("Failed to commit included quota usage")?;
+
+        let covered_cost = result.total_cost * consumption.ratio;
+        linfo!(
+            request_id,
+            LogStage::Internal,
+            LogComponent::Statistics,
+            "included_quota_applied",
+            "Applied included quota before billing",
+            user_id = user_id,
+            period = %period,
+            covered_requests = consumption.requests,
+            covered_tokens = consumption.tokens,
+            covered_cost = covered_cost,
+        );
+
+        Ok(CostCalculationResult {
+            total_cost: result.total_cost - covered_cost,
+            quota_covered_cost: covered_cost,
+            ..result
+        })
+    }
+}
+
+/// 计费 token 总数
+fn billable_tokens(usage: &TokenUsage) -> TokenCount {
+    [
+        usage.prompt_tokens,
+        usage.completion_tokens,
+        usage.cache_create_tokens,
+        usage.cache_read_tokens,
+    ]
+    .into_iter()
+    .flatten()
+    .fold(0, TokenCount::saturating_add)
+}
+
+/// 按剩余额度计算本次抵扣；没有可抵扣的额度时返回 None
+fn consume(
+    remaining_requests: i64,
+    remaining_tokens: i64,
+    total_tokens: TokenCount,
+) -> Option<QuotaConsumption> {
+    if remaining_requests > 0 {
+        return Some(QuotaConsumption {
+            requests: 1,
+            tokens: 0,
+            ratio: 1.0,
+        });
+    }
+    let remaining_tokens = TokenCount::try_from(remaining_tokens).unwrap_or(0);
+    let tokens = remaining_tokens.min(total_tokens);
+    if tokens == 0 {
+        return None;
+    }
+    Some(QuotaConsumption {
+        requests: 0,
+        tokens,
+        ratio: ratio_as_f64(tokens, total_tokens).unwrap_or(0.0),
+    })
+}
@@ -2,19 +2,111 @@
 //!
 //! 基于模型定价和阶梯定价配置，计算AI请求的token使用费用
 
-use crate::error::Result;
+mod credits;
+mod included_quota;
+
+pub use credits::{CreditReservation, CreditsService};
+
+use crate::ensure;
+use crate::error::{Result, conversion::ConversionError};
 use crate::logging::{LogComponent, LogStage};
 use crate::types::{CostValue, ProviderTypeId, TokenCount};
 use crate::{ldebug, lerror, linfo, lwarn};
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use entity::{
     model_pricing::{self, Entity as ModelPricing},
     model_pricing_tiers::{self, Entity as ModelPricingTiers},
 };
 
+/// 定价缓存版本号：定价数据被修改后递增，持有定价缓存的组件据此丢弃旧数据
+static PRICING_CACHE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 使进程内的定价缓存失效（管理端修改定价后调用）
+pub fn invalidate_pricing_cache() {
+    PRICING_CACHE_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 当前定价缓存版本号
+#[must_use]
+pub fn pricing_cache_generation() -> u64 {
+    PRICING_CACHE_GENERATION.load(Ordering::Relaxed)
+}
+
+/// 阶梯区间：(token 类型, `min_tokens`, `max_tokens`)
+pub type TierRange<'a> = (&'a str, i32, Option<i32>);
+
+/// 校验模型的阶梯定价是否构成完整划分
+pub fn validate_tiers(tiers: &[model_pricing_tiers::Model]) -> Result<()> {
+    validate_tier_ranges(
+        tiers
+            .iter()
+            .map(|tier| (tier.token_type.as_str(), tier.min_tokens, tier.max_tokens)),
+    )
+}
+
+/// 校验阶梯区间的连续性
+///
+/// 区间为闭区间 `[min_tokens, max_tokens]`。按 token 类型分组后，阶梯需从 0 开始首尾相接
+/// （下一阶的 `min_tokens` 等于上一阶的 `max_tokens + 1`），且最后一阶无上限，
+/// 保证任意 token 数都恰好落在一个阶梯内。
+pub fn validate_tier_ranges<'a>(ranges: impl IntoIterator<Item = TierRange<'a>>) -> Result<()> {
+    let mut grouped: BTreeMap<&str, Vec<(i32, Option<i32>)>> = BTreeMap::new();
+    for (token_type, min_tokens, max_tokens) in ranges {
+        ensure!(
+            min_tokens >= 0,
+            ConversionError::message(format!("{token_type} 阶梯 min_tokens 不能为负数"))
+        );
+        if let Some(max_tokens) = max_tokens {
+            ensure!(
+                max_tokens >= min_tokens,
+                ConversionError::message(format!(
+                    "{token_type} 阶梯 max_tokens({max_tokens}) 小于 min_tokens({min_tokens})"
+                ))
+            );
+        }
+        grouped
+            .entry(token_type)
+            .or_default()
+            .push((min_tokens, max_tokens));
+    }
+
+    for (token_type, mut group) in grouped {
+        group.sort_by_key(|&(min_tokens, _)| min_tokens);
+        let mut expected_min = Some(0);
+        for (min_tokens, max_tokens) in group {
+            let Some(expected) = expected_min else {
+                return Err(ConversionError::message(format!(
+                    "{token_type} 阶梯重叠：无上限阶梯之后不能再有阶梯（min_tokens={min_tokens}）"
+                ))
+                .into());
+            };
+            ensure!(
+                min_tokens <= expected,
+                ConversionError::message(format!(
+                    "{token_type} 阶梯不连续：{expected} 至 {} 之间没有定价",
+                    min_tokens - 1
+                ))
+            );
+            ensure!(
+                min_tokens == expected,
+                ConversionError::message(format!(
+                    "{token_type} 阶梯重叠：min_tokens={min_tokens} 落在上一阶梯范围内"
+                ))
+            );
+            expected_min = max_tokens.map(|max_tokens| max_tokens.saturating_add(1));
+        }
+        ensure!(
+            expected_min.is_none(),
+            ConversionError::message(format!("{token_type} 最后一个阶梯的 max_tokens 必须为空"))
+        );
+    }
+    Ok(())
+}
+
 /// 费用计算服务
 #[derive(Debug, Clone)]
 pub struct PricingCalculatorService {
@@ -46,6 +138,45 @@ pub struct CostCalculationResult {
     pub cost_breakdown: HashMap<String, CostValue>,
     /// 是否使用了fallback定价
     pub used_fallback: bool,
+    /// 已由套餐内免费额度抵扣的费用（`total_cost` 为抵扣后的应付费用，明细为抵扣前）
+    pub quota_covered_cost: CostValue,
+}
+
+/// 预先加载的模型阶梯定价，可在同步上下文（如流式响应分块回调）中反复估算费用
+#[derive(Debug, Clone)]
+pub struct ModelPriceSheet {
+    tiers: Vec<model_pricing_tiers::Model>,
+    /// 货币单位
+    pub currency: String,
+}
+
+impl ModelPriceSheet {
+    #[must_use]
+    pub const fn new(tiers: Vec<model_pricing_tiers::Model>, currency: String) -> Self {
+        Self { tiers, currency }
+    }
+
+    /// 按阶梯定价计算用量的费用（不抵扣套餐内免费额度）
+    #[must_use]
+    pub fn cost(&self, token_usage: &TokenUsage) -> CostValue {
+        [
+            ("prompt", token_usage.prompt_tokens),
+            ("completion", token_usage.completion_tokens),
+            ("cache_create", token_usage.cache_create_tokens),
+            ("cache_read", token_usage.cache_read_tokens),
+        ]
+        .into_iter()
+        .filter_map(|(token_type, tokens)| Some((token_type, tokens?)))
+        .map(|(token_type, tokens)| {
+            let tokens = i32::try_from(tokens).unwrap_or(i32::MAX);
+            self.tiers
+                .iter()
+                .filter(|tier| tier.token_type == token_type)
+                .map(|tier| tier.calculate_cost(tier.calculate_tokens_in_tier(tokens)))
+                .sum::<CostValue>()
+        })
+        .sum()
+    }
 }
 
 impl PricingCalculatorService {
@@ -55,6 +186,22 @@ impl PricingCalculatorService {
         Self { db }
     }
 
+    /// 加载模型的阶梯定价；未配置定价或阶梯时返回 `None`
+    pub async fn load_price_sheet(
+        &self,
+        model_used: &str,
+        provider_type_id: ProviderTypeId,
+    ) -> Result<Option<ModelPriceSheet>> {
+        let Some(model_pricing) = self
+            .find_model_pricing(model_used, provider_type_id)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let tiers = self.get_pricing_tiers(model_pricing.id).await?;
+        Ok((!tiers.is_empty()).then(|| ModelPriceSheet::new(tiers, model_pricing.cost_currency)))
+    }
+
     /// 计算请求费用
     ///
     /// # 参数
@@ -115,6 +262,20 @@ impl PricingCalculatorService {
             return Ok(Self::create_fallback_result());
         }
 
+        // 阶梯配置异常时仍按现有阶梯计费，但提示可能计费错误
+        if let Err(err) = validate_tiers(&pricing_tiers) {
+            lwarn!(
+                request_id,
+                LogStage::Internal,
+                LogComponent::Statistics,
+                "invalid_pricing_tiers",
+                "Pricing tiers do not form a contiguous partition, cost may be inaccurate",
+                model = %model_used,
+                pricing_id = model_pricing.id,
+                error = %err,
+            );
+        }
+
         // 计算各类型token的费用
         let mut cost_breakdown: HashMap<String, CostValue> = HashMap::new();
         let mut total_cost: CostValue = 0.0;
@@ -180,6 +341,7 @@ impl PricingCalculatorService {
             currency: model_pricing.cost_currency,
             cost_breakdown,
             used_fallback: false,
+            quota_covered_cost: 0.0,
         })
     }
 
@@ -340,6 +502,7 @@ impl PricingCalculatorService {
             currency: "USD".to_string(),
             cost_breakdown: HashMap::new(),
             used_fallback: true,
+            quota_covered_cost: 0.0,
         }
     }
 }
@@ -571,4 +734,57 @@ mod tests {
             result.total_cost
         );
     }
+
+    fn tier(
+        token_type: &str,
+        min_tokens: i32,
+        max_tokens: Option<i32>,
+    ) -> model_pricing_tiers::Model {
+        model_pricing_tiers::Model {
+            id: 0,
+            model_pricing_id: 1,
+            token_type: token_type.to_string(),
+            min_tokens,
+            max_tokens,
+            price_per_token: 0.000_001,
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn test_validate_tiers_accepts_contiguous_tiers() {
+        let tiers = vec![
+            tier("prompt", 200_000, None),
+            tier("prompt", 0, Some(199_999)),
+            tier("completion", 0, None),
+        ];
+        assert!(validate_tiers(&tiers).is_ok());
+    }
+
+    #[test]
+    fn test_validate_tiers_rejects_gap() {
+        let tiers = vec![
+            tier("prompt", 0, Some(99_999)),
+            tier("prompt", 200_000, None),
+        ];
+        let err = validate_tiers(&tiers).expect_err("gap should be rejected");
+        assert!(err.to_string().contains("不连续"));
+
+        // 不从 0 开始同样视为空档
+        assert!(validate_tiers(&[tier("completion", 1, None)]).is_err());
+    }
+
+    #[test]
+    fn test_validate_tiers_rejects_overlap() {
+        let tiers = vec![
+            tier("prompt", 0, Some(199_999)),
+            tier("prompt", 100_000, None),
+        ];
+        let err = validate_tiers(&tiers).expect_err("overlap should be rejected");
+        assert!(err.to_string().contains("重叠"));
+
+        let after_unbounded = vec![tier("prompt", 0, None), tier("prompt", 100, None)];
+        assert!(validate_tiers(&after_unbounded).is_err());
+    }
 }
@@ -13,6 +13,8 @@ use super::template::{build_oauth_template_context, render_json_value};
 /// 说明：
 /// - 授权 URL 的 `query` 参数完全由数据库配置驱动（包含基础参数与 PKCE 参数）。
 /// - 业务侧不再根据 OpenAI/Gemini/Anthropic 等做分支判断。
+/// - 携带 `code_challenge` 时，`code_challenge_method` 以提供商配置的 PKCE 方法为准。
+/// - 会话选定了回调地址时，`{{redirect_uri}}` 渲染为该地址。
 pub fn build_authorize_url(
     config: &OAuthProviderConfig,
     session: &oauth_client_sessions::Model,
@@ -41,6 +43,13 @@ pub fn build_authorize_url(
         }
     }
 
+    if params.contains_key("code_challenge") {
+        params.insert(
+            "code_challenge_method".to_string(),
+            config.pkce_method.as_str().to_string(),
+        );
+    }
+
     // 基础参数必须存在，否则无法完成授权流程
     for required in [
         "client_id",
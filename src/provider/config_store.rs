@@ -1,5 +1,6 @@
+use crate::auth::pkce::ChallengeMethod;
 use crate::auth::types::{OAuthAuthorizeConfig, OAuthProviderConfig, OAuthTokenConfig};
-use crate::error::{ProxyError, Result, auth::OAuthError};
+use crate::error::{ProxyError, Result, auth::OAuthError, config::ConfigError};
 use crate::ldebug;
 use crate::logging::{LogComponent, LogStage};
 use entity::{ProviderTypes, provider_types};
@@ -35,7 +36,7 @@ impl ApiKeyProviderConfig {
             let oauth_types = model.get_oauth_types();
             for oauth_type in oauth_types {
                 if let Ok(Some(oauth_config)) = model.get_oauth_config(&oauth_type) {
-                    let config = Self::oauth_model_to_config(&model, &oauth_type, oauth_config);
+                    let config = Self::oauth_model_to_config(&model, &oauth_type, oauth_config)?;
                     configs.push(config);
                 }
             }
@@ -75,11 +76,7 @@ impl ApiKeyProviderConfig {
         match model {
             Some(model) => {
                 if let Ok(Some(oauth_config)) = model.get_oauth_config(oauth_type) {
-                    return Ok(Self::oauth_model_to_config(
-                        &model,
-                        oauth_type,
-                        oauth_config,
-                    ));
+                    return Self::oauth_model_to_config(&model, oauth_type, oauth_config);
                 }
 
                 Err(OAuthError::ProviderNotFound(format!(
@@ -95,7 +92,8 @@ impl ApiKeyProviderConfig {
         model: &provider_types::Model,
         oauth_type: &str,
         oauth_config: entity::provider_types::OAuthConfig,
-    ) -> OAuthProviderConfig {
+    ) -> Result<OAuthProviderConfig> {
+        let pkce_method = Self::parse_pkce_method(model, oauth_config.pkce_method.as_deref())?;
         let authorize = OAuthAuthorizeConfig {
             url: oauth_config.authorize.url,
             method: oauth_config.authorize.method,
@@ -126,17 +124,36 @@ impl ApiKeyProviderConfig {
             )
         );
 
-        OAuthProviderConfig {
+        Ok(OAuthProviderConfig {
             provider_name: format!("{}:{}", model.name, oauth_type),
             client_id: oauth_config.client_id,
             client_secret: oauth_config.client_secret,
             redirect_uri: oauth_config.redirect_uri.unwrap_or_default(),
+            redirect_uris: oauth_config.redirect_uris,
             pkce_required: oauth_config.pkce_required,
+            pkce_method,
             scopes: oauth_config.scopes,
             authorize,
             exchange,
             refresh,
             extra: oauth_config.extra,
+        })
+    }
+
+    /// 解析 PKCE challenge 方法：缺省为 `S256`，只接受 `plain`/`S256`（不区分大小写）
+    fn parse_pkce_method(
+        model: &provider_types::Model,
+        method: Option<&str>,
+    ) -> Result<ChallengeMethod> {
+        match method.map(str::trim) {
+            None => Ok(ChallengeMethod::S256),
+            Some(method) if method.eq_ignore_ascii_case("S256") => Ok(ChallengeMethod::S256),
+            Some(method) if method.eq_ignore_ascii_case("plain") => Ok(ChallengeMethod::Plain),
+            Some(method) => Err(ConfigError::Load(format!(
+                "provider {} 的 pkce_method 无效: {method}（仅支持 plain 或 S256）",
+                model.name
+            ))
+            .into()),
         }
     }
 }
@@ -197,8 +214,10 @@ impl ProviderConfigBuilder {
                 client_id: String::new(),
                 client_secret: None,
                 redirect_uri: String::new(),
+                redirect_uris: Vec::new(),
                 scopes: String::new(),
                 pkce_required: true,
+                pkce_method: ChallengeMethod::S256,
                 authorize: OAuthAuthorizeConfig {
                     url: String::new(),
                     method: "GET".to_string(),
@@ -253,6 +272,18 @@ impl ProviderConfigBuilder {
         self
     }
 
+    #[must_use]
+    pub fn redirect_uris(mut self, redirect_uris: &[&str]) -> Self {
+        self.config.redirect_uris = redirect_uris.iter().map(|uri| (*uri).to_string()).collect();
+        self
+    }
+
+    #[must_use]
+    pub const fn pkce_method(mut self, method: ChallengeMethod) -> Self {
+        self.config.pkce_method = method;
+        self
+    }
+
     #[must_use]
     pub fn scopes(mut self, scopes: &[&str]) -> Self {
         self.config.scopes = scopes.join(" ");
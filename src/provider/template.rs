@@ -105,6 +105,14 @@ pub fn build_oauth_template_context(
     root.remove("session");
     root.remove("request");
 
+    // 会话记录了发起授权时选定的回调地址，交换令牌时必须使用同一地址
+    if let Some(redirect_uri) = &session.redirect_uri {
+        root.insert(
+            "redirect_uri".to_string(),
+            Value::String(redirect_uri.clone()),
+        );
+    }
+
     let mut session_obj: serde_json::Map<String, Value> = serde_json::Map::new();
     // 仅注入白名单字段：`session.*` 与数据库表字段绑定，避免配置方意外获得更多会话字段。
     session_obj.insert("state".to_string(), Value::String(session.state.clone()));
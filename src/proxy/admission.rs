@@ -0,0 +1,175 @@
+//! # 优先级准入
+//!
+//! 全局并发限制达到上限后，新请求按服务 API 的 `priority` 排队：释放的空位直接交给优先级最高、
+//! 同优先级中最早到达的等待者。队列已满时挤出优先级更低的最后一个等待者，否则拒绝本请求；
+//! 排队超时或被挤出的请求返回 503 与 `Retry-After`。
+
+use crate::config::ConcurrencyConfig;
+use crate::error::{ProxyError, Result};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::oneshot;
+
+/// 等待队列排序键：优先级高者在前，同优先级按到达顺序
+type WaiterKey = (Reverse<i32>, u64);
+
+#[derive(Default)]
+struct AdmissionState {
+    in_flight: usize,
+    waiters: BTreeMap<WaiterKey, oneshot::Sender<()>>,
+    next_seq: u64,
+}
+
+/// 全局并发准入控制器
+pub struct AdmissionController {
+    config: ConcurrencyConfig,
+    state: Mutex<AdmissionState>,
+}
+
+/// 已占用的并发空位，释放时交给下一个等待者
+pub struct AdmissionPermit {
+    controller: Arc<AdmissionController>,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.controller.release();
+    }
+}
+
+/// 排队中的请求；未拿到结果就被取消时从队列移除，已交接的空位归还
+struct QueuedWaiter {
+    controller: Arc<AdmissionController>,
+    key: WaiterKey,
+    receiver: oneshot::Receiver<()>,
+    settled: bool,
+}
+
+impl QueuedWaiter {
+    /// 放弃排队，返回放弃前是否已经拿到空位
+    fn abandon(&mut self) -> bool {
+        self.settled = true;
+        let mut state = self.controller.lock_state();
+        if state.waiters.remove(&self.key).is_some() {
+            return false;
+        }
+        // 不在队列中说明已被交接或挤出；交接在锁内完成，此时结果一定已写入通道
+        self.receiver.try_recv().is_ok()
+    }
+}
+
+impl Drop for QueuedWaiter {
+    fn drop(&mut self) {
+        if !self.settled && self.abandon() {
+            self.controller.release();
+        }
+    }
+}
+
+impl AdmissionController {
+    #[must_use]
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(AdmissionState::default()),
+        }
+    }
+
+    /// 是否启用全局并发限制
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 当前占用的并发空位数
+    #[must_use]
+    pub fn in_flight(&self) -> usize {
+        self.lock_state().in_flight
+    }
+
+    /// 当前排队的请求数
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.lock_state().waiters.len()
+    }
+
+    /// 按优先级申请并发空位，有空位时立即返回，否则排队直到获得空位、超时或被挤出
+    pub async fn acquire(self: &Arc<Self>, priority: i32) -> Result<AdmissionPermit> {
+        let (key, receiver) = {
+            let mut state = self.lock_state();
+            if state.in_flight < self.config.max_concurrent_requests {
+                state.in_flight += 1;
+                return Ok(self.permit());
+            }
+
+            state.waiters.retain(|_, sender| !sender.is_closed());
+            if state.waiters.len() >= self.config.max_queue_size {
+                let lowest = state
+                    .waiters
+                    .last_key_value()
+                    .map(|(key, _)| *key)
+                    .filter(|(Reverse(lowest), _)| *lowest < priority);
+                let Some(lowest) = lowest else {
+                    return Err(self.rejection("并发已达上限且准入队列已满"));
+                };
+                // 丢弃发送端即通知被挤出的等待者
+                state.waiters.remove(&lowest);
+            }
+
+            let key = (Reverse(priority), state.next_seq);
+            state.next_seq += 1;
+            let (sender, receiver) = oneshot::channel();
+            state.waiters.insert(key, sender);
+            (key, receiver)
+        };
+
+        let mut waiter = QueuedWaiter {
+            controller: Arc::clone(self),
+            key,
+            receiver,
+            settled: false,
+        };
+        match tokio::time::timeout(self.config.queue_timeout(), &mut waiter.receiver).await {
+            Ok(Ok(())) => {
+                waiter.settled = true;
+                Ok(self.permit())
+            }
+            Ok(Err(_)) => {
+                waiter.settled = true;
+                Err(self.rejection("请求被更高优先级的请求挤出准入队列"))
+            }
+            Err(_) => {
+                if waiter.abandon() {
+                    return Ok(self.permit());
+                }
+                Err(self.rejection("等待并发空位超时"))
+            }
+        }
+    }
+
+    fn permit(self: &Arc<Self>) -> AdmissionPermit {
+        AdmissionPermit {
+            controller: Arc::clone(self),
+        }
+    }
+
+    /// 释放一个空位：优先交给仍在等待的最高优先级请求，没有等待者时归还
+    fn release(&self) {
+        let mut state = self.lock_state();
+        while let Some((_, sender)) = state.waiters.pop_first() {
+            if sender.send(()).is_ok() {
+                return;
+            }
+        }
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+
+    fn rejection(&self, message: &str) -> ProxyError {
+        ProxyError::service_unavailable(message, self.config.retry_after_secs)
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, AdmissionState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
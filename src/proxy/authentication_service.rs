@@ -13,24 +13,35 @@ use crate::error::{
     auth::{AuthError, OAuthError, UsageLimitInfo, UsageLimitKind},
     config::ConfigError,
 };
-use crate::key_pool::{ApiKeySchedulerService, SelectionContext};
+use crate::key_pool::{ApiKeySchedulerService, ApiKeySelectionResult, SelectionContext};
 use crate::logging::{LogComponent, LogStage};
+use crate::proxy::aws_sigv4::AwsCredentials;
 use crate::proxy::context::{ProxyContext, ResolvedCredential};
+use crate::proxy::model_routing::{self, ModelRoute, ModelRoutes};
+use crate::proxy::parameter_policy;
+use crate::proxy::path_allowlist;
+use crate::proxy::provider_strategy::ProviderType;
 use crate::proxy::response::format_rate_limit_message;
+use crate::proxy::websocket;
 use crate::types::ProviderTypeId;
 use crate::{ldebug, linfo, lwarn};
 use entity::{
     oauth_client_sessions::{self, Entity as OAuthClientSessions},
     provider_types::{self, Entity as ProviderTypes},
+    user_provider_blocks::{self, Entity as UserProviderBlocks},
     user_provider_keys,
     user_service_apis::{self},
 };
 use pingora_proxy::Session;
 use sea_orm::prelude::Decimal;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 
+/// 按请求指定服务商的请求头，值为 `provider_types.name`
+pub const PROVIDER_OVERRIDE_HEADER: &str = "x-provider";
+
 /// 认证信息来源类型
 #[derive(Debug, Clone)]
 pub enum AuthSource {
@@ -102,18 +113,64 @@ impl AuthenticationService {
         let user_api = self
             .authenticate_entry_api(session, &ctx.request_id)
             .await?;
+        path_allowlist::ensure_path_allowed(
+            user_api.allowed_paths.as_ref(),
+            session.req_header().uri.path(),
+            &ctx.request_id,
+        )?;
+
+        // 2. 获取提供商配置：`X-Provider` 可在服务 API 允许的范围内覆盖，非法覆盖不占用限流额度；
+        //    未指定时按 `model_routes` 匹配请求模型，未命中使用默认服务商
+        let requested_provider = Self::requested_provider(session);
+        let provider_override = self
+            .resolve_provider_override(requested_provider.as_deref(), &user_api, &ctx.request_id)
+            .await?;
+        let mut pinned_provider = provider_override.is_some();
+        let mut route_key_ids = None;
+        let provider_type = match provider_override {
+            Some(provider_type) => provider_type,
+            None => {
+                let default_provider = self.get_provider_type(user_api.provider_type_id).await?;
+                match self
+                    .resolve_model_route(session, ctx, &user_api, &default_provider)
+                    .await?
+                {
+                    Some((provider_type, route)) => {
+                        pinned_provider = true;
+                        route_key_ids = route.user_provider_keys_ids;
+                        provider_type
+                    }
+                    None => default_provider,
+                }
+            }
+        };
+        self.ensure_provider_not_blocked(user_api.user_id, &provider_type, &ctx.request_id)
+            .await?;
 
-        // 2. 检查速率限制和配额
-        self.check_limits(&user_api, &ctx.request_id).await?;
-
-        // 3. 获取提供商配置
-        let provider_type = self.get_provider_type(user_api.provider_type_id).await?;
+        // 3. 检查速率限制和配额
+        self.check_limits(&user_api, provider_type.id, &ctx.request_id)
+            .await?;
 
-        // 4. 选择后端密钥
+        // 4. 选择后端密钥（此时尚未读取请求体，模型只能从路径或查询参数得知）
         let route_group = session.req_header().uri.path().to_string();
-        let selected_backend = self
-            .select_api_key(&user_api, &ctx.request_id, route_group)
-            .await?;
+        let model = ProviderType::from_str(&provider_type.name)
+            .and_then(|provider| {
+                parameter_policy::requested_model(provider, &route_group, &serde_json::Value::Null)
+            })
+            .or_else(|| websocket::model_from_query(session.req_header()));
+        let context = SelectionContext::new(
+            ctx.request_id.clone(),
+            user_api.user_id,
+            user_api.id,
+            provider_type.id,
+            route_group,
+        )
+        .with_model(model)
+        .with_pinned_provider(pinned_provider)
+        .with_key_ids(route_key_ids)
+        .with_selection_debug(user_api.selection_debug);
+        let selection = self.select_api_key(&user_api, &context).await?;
+        let selected_backend = selection.selected_key;
 
         // 5. 解析最终凭证
         let resolved_credential = self
@@ -125,6 +182,7 @@ impl AuthenticationService {
         ctx.routing.provider_type = Some(provider_type);
         ctx.routing.selected_backend = Some(selected_backend);
         ctx.routing.resolved_credential = Some(resolved_credential);
+        ctx.trace.selection_debug = selection.selection_debug;
 
         Ok(())
     }
@@ -212,10 +270,196 @@ impl AuthenticationService {
         Err(AuthError::ApiKeyMissing.into())
     }
 
-    /// 2. 检查所有限制 (Redis for freq, DB for usage)
+    /// 2. 解析 `X-Provider` 覆盖
+    ///
+    /// 只允许服务 API 自身的服务商以及其密钥池中密钥所属的服务商；未知服务商与未授权服务商
+    /// 返回同一种错误，避免借此探测系统中存在哪些服务商。
+    pub async fn resolve_provider_override(
+        &self,
+        requested: Option<&str>,
+        user_api: &user_service_apis::Model,
+        request_id: &str,
+    ) -> Result<Option<provider_types::Model>> {
+        let Some(requested) = requested else {
+            return Ok(None);
+        };
+        let requested = requested.trim().to_ascii_lowercase();
+
+        let provider_type = if requested.is_empty() {
+            None
+        } else {
+            ProviderTypes::find()
+                .filter(provider_types::Column::Name.eq(requested.as_str()))
+                .filter(provider_types::Column::IsActive.eq(true))
+                .one(&*self.db)
+                .await
+                .context("Failed to fetch provider type")?
+        };
+        let allowed = self.allowed_provider_ids(user_api).await?;
+        match provider_type {
+            Some(provider_type) if allowed.contains(&provider_type.id) => {
+                linfo!(
+                    request_id,
+                    LogStage::Authentication,
+                    LogComponent::Auth,
+                    "provider_override",
+                    "请求通过 X-Provider 指定服务商",
+                    provider = %provider_type.name,
+                    provider_type_id = provider_type.id,
+                    default_provider_type_id = user_api.provider_type_id
+                );
+                Ok(Some(provider_type))
+            }
+            _ => {
+                lwarn!(
+                    request_id,
+                    LogStage::Authentication,
+                    LogComponent::Auth,
+                    "provider_override_rejected",
+                    "X-Provider 指定的服务商不在允许范围内",
+                    provider = %requested,
+                    user_service_api_id = user_api.id
+                );
+                Err(AuthError::ProviderNotAllowed(requested).into())
+            }
+        }
+    }
+
+    /// 2. 按 `model_routes` 匹配请求模型，返回命中规则的服务商
+    ///
+    /// 模型优先从路径或查询参数得知，否则预读请求体；无法得知模型或没有规则命中时返回 `None`。
+    async fn resolve_model_route(
+        &self,
+        session: &mut Session,
+        ctx: &mut ProxyContext,
+        user_api: &user_service_apis::Model,
+        default_provider: &provider_types::Model,
+    ) -> Result<Option<(provider_types::Model, ModelRoute)>> {
+        let Some(routes) = ModelRoutes::of(user_api) else {
+            return Ok(None);
+        };
+
+        let path = session.req_header().uri.path().to_string();
+        let mut model = ProviderType::from_str(&default_provider.name)
+            .and_then(|provider| {
+                parameter_policy::requested_model(provider, &path, &serde_json::Value::Null)
+            })
+            .or_else(|| websocket::model_from_query(session.req_header()));
+        if model.is_none()
+            && let Some(body) = model_routing::read_routing_body(session).await?
+        {
+            model = model_routing::body_model(&body);
+            ctx.request.routing_body = Some(body);
+        }
+
+        let Some(route) = model
+            .as_deref()
+            .and_then(|model| routes.route_for(model))
+            .cloned()
+        else {
+            ldebug!(
+                &ctx.request_id,
+                LogStage::Authentication,
+                LogComponent::Auth,
+                "model_route_default",
+                "未命中模型路由规则，使用默认服务商",
+                model = model.as_deref(),
+                provider_type_id = default_provider.id
+            );
+            return Ok(None);
+        };
+
+        let provider_type = if route.provider_type_id == default_provider.id {
+            default_provider.clone()
+        } else {
+            self.get_provider_type(route.provider_type_id).await?
+        };
+        linfo!(
+            &ctx.request_id,
+            LogStage::Authentication,
+            LogComponent::Auth,
+            "model_routed",
+            "请求按模型路由到服务商",
+            model = model.as_deref(),
+            pattern = %route.pattern,
+            provider = %provider_type.name,
+            provider_type_id = provider_type.id,
+            default_provider_type_id = user_api.provider_type_id
+        );
+        Ok(Some((provider_type, route)))
+    }
+
+    /// 拒绝管理员为该用户禁用的服务商（无论是默认服务商还是 `X-Provider` 指定的服务商）
+    pub async fn ensure_provider_not_blocked(
+        &self,
+        user_id: i32,
+        provider_type: &provider_types::Model,
+        request_id: &str,
+    ) -> Result<()> {
+        let blocked = UserProviderBlocks::find()
+            .filter(user_provider_blocks::Column::UserId.eq(user_id))
+            .filter(user_provider_blocks::Column::ProviderTypeId.eq(provider_type.id))
+            .one(&*self.db)
+            .await
+            .context("Failed to fetch user provider blocks")?;
+        if blocked.is_some() {
+            lwarn!(
+                request_id,
+                LogStage::Authentication,
+                LogComponent::Auth,
+                "provider_blocked",
+                "该用户已被禁止使用此服务商",
+                user_id = user_id,
+                provider = %provider_type.name,
+                provider_type_id = provider_type.id
+            );
+            return Err(AuthError::ProviderNotAllowed(provider_type.name.clone()).into());
+        }
+        Ok(())
+    }
+
+    /// 读取 `X-Provider` 请求头；非 ASCII 的值按空值处理，由校验统一拒绝
+    fn requested_provider(session: &Session) -> Option<String> {
+        session
+            .req_header()
+            .headers
+            .get(PROVIDER_OVERRIDE_HEADER)
+            .map(|value| value.to_str().unwrap_or_default().to_string())
+    }
+
+    /// 服务 API 允许使用的服务商：自身配置的服务商 + 密钥池中密钥所属的服务商
+    async fn allowed_provider_ids(
+        &self,
+        user_api: &user_service_apis::Model,
+    ) -> Result<HashSet<ProviderTypeId>> {
+        let key_ids: Vec<i32> = user_api
+            .user_provider_keys_ids
+            .as_array()
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| id.as_i64().and_then(|id| i32::try_from(id).ok()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut allowed = HashSet::from([user_api.provider_type_id]);
+        if !key_ids.is_empty() {
+            let keys = user_provider_keys::Entity::find()
+                .filter(user_provider_keys::Column::Id.is_in(key_ids))
+                .filter(user_provider_keys::Column::UserId.eq(user_api.user_id))
+                .all(&*self.db)
+                .await
+                .context("Failed to fetch provider keys")?;
+            allowed.extend(keys.iter().map(|key| key.provider_type_id));
+        }
+        Ok(allowed)
+    }
+
+    /// 3. 检查所有限制 (Redis for freq, DB for usage)
     async fn check_limits(
         &self,
         user_api: &user_service_apis::Model,
+        provider_type_id: ProviderTypeId,
         request_id: &str,
     ) -> Result<()> {
         if let Some(expires_at) = &user_api.expires_at
@@ -234,7 +478,7 @@ impl AuthenticationService {
         {
             let outcome = self
                 .rate_limiter
-                .check_per_minute(user_api.user_id, &endpoint_key, i64::from(rate_limit))
+                .acquire_per_minute(user_api.user_id, &endpoint_key, i64::from(rate_limit))
                 .await?;
             if !outcome.allowed {
                 let resets = u64::try_from(outcome.ttl_seconds)
@@ -324,6 +568,31 @@ impl AuthenticationService {
             return Err(err);
         }
 
+        // 5. 提供商级全局 RPM（所有用户共享），放在用户级检查之后，避免被拒请求占用全局名额
+        if let Some(outcome) = self
+            .rate_limiter
+            .check_provider_per_minute(provider_type_id)
+            .await?
+            && !outcome.allowed
+        {
+            let resets = u64::try_from(outcome.ttl_seconds)
+                .ok()
+                .map(Duration::from_secs);
+            Self::log_rate_limit_hit(
+                request_id,
+                UsageLimitKind::ProviderPerMinute,
+                Some(Self::to_f64(outcome.limit)),
+                Some(Self::to_f64(outcome.current)),
+                resets,
+            );
+            return Err(ApiKeyUsageLimitService::rate_limit_error(
+                UsageLimitKind::ProviderPerMinute,
+                Some(Self::to_f64(outcome.limit)),
+                Some(Self::to_f64(outcome.current)),
+                resets,
+            ));
+        }
+
         Ok(())
     }
 
@@ -339,6 +608,9 @@ impl AuthenticationService {
             UsageLimitKind::DailyRequests => "每日请求次数",
             UsageLimitKind::DailyTokens => "每日 Token 用量",
             UsageLimitKind::DailyCost => "每日成本",
+            UsageLimitKind::ProviderPerMinute => "提供商每分钟请求",
+            UsageLimitKind::ModelPerMinute => "模型每分钟请求",
+            UsageLimitKind::Credits => "积分余额",
         };
         let info = UsageLimitInfo {
             kind,
@@ -367,7 +639,7 @@ impl AuthenticationService {
         value as f64
     }
 
-    /// 3. 获取提供商类型配置
+    /// 获取提供商类型配置
     async fn get_provider_type(
         &self,
         provider_type_id: ProviderTypeId,
@@ -400,22 +672,14 @@ impl AuthenticationService {
     async fn select_api_key(
         &self,
         user_service_api: &user_service_apis::Model,
-        request_id: &str,
-        route_group: String,
-    ) -> Result<user_provider_keys::Model> {
-        let context = SelectionContext::new(
-            request_id.to_string(),
-            user_service_api.user_id,
-            user_service_api.id,
-            user_service_api.provider_type_id,
-            route_group,
-        );
+        context: &SelectionContext,
+    ) -> Result<ApiKeySelectionResult> {
         let result = self
             .api_key_scheduler_service
-            .select_api_key_from_service_api(user_service_api, &context)
+            .select_api_key_from_service_api(user_service_api, context)
             .await?;
         ldebug!(
-            request_id,
+            &context.request_id,
             LogStage::Authentication,
             LogComponent::Auth,
             "api_key_selected",
@@ -423,7 +687,7 @@ impl AuthenticationService {
             selected_key_id = result.selected_key.id,
             strategy = result.strategy.as_str()
         );
-        Ok(result.selected_key)
+        Ok(result)
     }
 
     /// 5. 解析最终凭证
@@ -432,21 +696,20 @@ impl AuthenticationService {
         selected_backend: &user_provider_keys::Model,
         request_id: &str,
     ) -> Result<ResolvedCredential> {
-        match AuthType::from(selected_backend.auth_type.as_str()) {
-            Some(AuthType::ApiKey) => {
-                Ok(ResolvedCredential::ApiKey(selected_backend.api_key.clone()))
-            }
-            Some(AuthType::OAuth) => {
+        match selected_backend.auth_type.parse::<AuthType>()? {
+            AuthType::ApiKey => Ok(ResolvedCredential::ApiKey(selected_backend.api_key.clone())),
+            AuthType::OAuth => {
                 let token = self
                     .resolve_oauth_access_token(&selected_backend.api_key, request_id)
                     .await?;
                 Ok(ResolvedCredential::OAuthAccessToken(token))
             }
-            _ => Err(AuthError::Message(format!(
-                "Unsupported auth type: {}",
-                selected_backend.auth_type
-            ))
-            .into()),
+            AuthType::AwsSigV4 => Ok(ResolvedCredential::AwsSigV4(AwsCredentials::parse(
+                &selected_backend.api_key,
+            )?)),
+            AuthType::HmacSignature => Ok(ResolvedCredential::HmacSignature(
+                selected_backend.api_key.clone(),
+            )),
         }
     }
 
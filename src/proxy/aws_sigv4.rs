@@ -0,0 +1,530 @@
+//! # AWS SigV4 请求签名
+//!
+//! Amazon Bedrock 等 AWS 服务不接受请求头中的静态密钥，每个请求都需要按 SigV4 签名：
+//! 签名覆盖方法、路径、查询参数、部分请求头以及请求体的 SHA-256，因此必须在请求头发出前拿到最终请求体。
+//!
+//! - 凭证保存在服务商密钥的 `api_key` 字段：`ACCESS_KEY_ID:SECRET_ACCESS_KEY[:SESSION_TOKEN]`；
+//! - 区域与服务名来自服务商 `config_json.aws_sigv4`，区域缺省时从 `base_url`（如
+//!   `bedrock-runtime.us-east-1.amazonaws.com`）推断。
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+
+use chrono::{DateTime, Utc};
+use entity::provider_types;
+use pingora_http::RequestHeader;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::ensure;
+use crate::error::{Context, Result, auth::AuthError};
+use crate::proxy::upstream_url::parse_base_url;
+
+/// 签名算法标识
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+/// HMAC-SHA256 分组长度
+const HMAC_BLOCK_SIZE: usize = 64;
+
+/// AWS 访问凭证
+#[derive(Clone, PartialEq, Eq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"***")
+            .field("session_token", &self.session_token.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl AwsCredentials {
+    /// 解析 `ACCESS_KEY_ID:SECRET_ACCESS_KEY[:SESSION_TOKEN]`
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut parts = raw.trim().splitn(3, ':');
+        let access_key_id = parts.next().unwrap_or_default().trim();
+        let secret_access_key = parts.next().unwrap_or_default().trim();
+        let session_token = parts
+            .next()
+            .map(str::trim)
+            .filter(|token| !token.is_empty());
+        ensure!(
+            !access_key_id.is_empty() && !secret_access_key.is_empty(),
+            AuthError::Message(
+                "AWS 凭证格式应为 ACCESS_KEY_ID:SECRET_ACCESS_KEY[:SESSION_TOKEN]".to_string()
+            )
+        );
+        Ok(Self {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            session_token: session_token.map(str::to_string),
+        })
+    }
+}
+
+/// 签名作用域（区域 + 服务名）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SigV4Scope {
+    #[serde(default)]
+    pub region: String,
+    #[serde(default = "default_service")]
+    pub service: String,
+}
+
+fn default_service() -> String {
+    "bedrock".to_string()
+}
+
+#[derive(Deserialize)]
+struct ProviderSigV4Config {
+    aws_sigv4: Option<SigV4Scope>,
+}
+
+impl SigV4Scope {
+    /// 从服务商配置读取签名作用域
+    pub fn from_provider(provider: &provider_types::Model) -> Result<Self> {
+        let configured = provider
+            .config_json
+            .as_deref()
+            .map(serde_json::from_str::<ProviderSigV4Config>)
+            .transpose()
+            .context("解析服务商 config_json.aws_sigv4 失败")?
+            .and_then(|config| config.aws_sigv4);
+        let mut scope = configured.unwrap_or_else(|| Self {
+            region: String::new(),
+            service: default_service(),
+        });
+        if scope.region.is_empty() {
+            scope.region = region_from_base_url(&provider.base_url).unwrap_or_default();
+        }
+        ensure!(
+            !scope.region.is_empty() && !scope.service.is_empty(),
+            AuthError::Message(format!(
+                "服务商 {} 未配置 SigV4 区域（config_json.aws_sigv4.region）",
+                provider.name
+            ))
+        );
+        Ok(scope)
+    }
+}
+
+/// 从 `<service>.<region>.amazonaws.com` 形式的地址推断区域
+fn region_from_base_url(base_url: &str) -> Option<String> {
+    let parsed = parse_base_url(base_url).ok()?;
+    let host = parsed.host_header.split(':').next()?.to_ascii_lowercase();
+    let (_, region) = host.strip_suffix(".amazonaws.com")?.rsplit_once('.')?;
+    region.contains('-').then(|| region.to_string())
+}
+
+/// 规范请求及其签名头列表
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalRequest {
+    pub text: String,
+    pub signed_headers: String,
+}
+
+/// 构建规范请求
+///
+/// `path`/`query` 为线上传输的原始形式；路径按非 S3 服务的规则再编码一次，
+/// 请求头名称转小写、值去除首尾空白并合并连续空格。
+#[must_use]
+pub fn canonical_request(
+    method: &str,
+    path: &str,
+    query: &str,
+    headers: &[(String, String)],
+    payload_hash: &str,
+) -> CanonicalRequest {
+    let canonical_path = if path.is_empty() {
+        "/".to_string()
+    } else {
+        uri_encode(path, false)
+    };
+
+    let mut query_pairs: Vec<(String, String)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (
+                uri_encode(&percent_decode(name), true),
+                uri_encode(&percent_decode(value), true),
+            )
+        })
+        .collect();
+    query_pairs.sort();
+    let canonical_query = query_pairs
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut canonical_headers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, value) in headers {
+        canonical_headers
+            .entry(name.to_ascii_lowercase())
+            .or_default()
+            .push(value.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+    let signed_headers = canonical_headers
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(";");
+    let header_block = canonical_headers
+        .iter()
+        .fold(String::new(), |mut out, (name, values)| {
+            let _ = writeln!(out, "{name}:{}", values.join(","));
+            out
+        });
+
+    CanonicalRequest {
+        text: format!(
+            "{method}\n{canonical_path}\n{canonical_query}\n{header_block}\n{signed_headers}\n{payload_hash}"
+        ),
+        signed_headers,
+    }
+}
+
+/// SigV4 签名器
+pub struct SigV4Signer<'a> {
+    credentials: &'a AwsCredentials,
+    scope: &'a SigV4Scope,
+}
+
+impl<'a> SigV4Signer<'a> {
+    #[must_use]
+    pub const fn new(credentials: &'a AwsCredentials, scope: &'a SigV4Scope) -> Self {
+        Self { credentials, scope }
+    }
+
+    /// 对上游请求签名：写入 `x-amz-date`、`x-amz-content-sha256`、`x-amz-security-token` 与 `authorization`
+    ///
+    /// 参与签名的请求头为 `host`、`content-type` 与全部 `x-amz-*`，调用前这些头必须已是最终值。
+    pub fn sign(
+        &self,
+        request: &mut RequestHeader,
+        payload: &[u8],
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex_encode(&Sha256::digest(payload));
+
+        request.remove_header("authorization");
+        request
+            .insert_header("x-amz-date", &amz_date)
+            .context("Failed to set x-amz-date header")?;
+        request
+            .insert_header("x-amz-content-sha256", &payload_hash)
+            .context("Failed to set x-amz-content-sha256 header")?;
+        request.remove_header("x-amz-security-token");
+        if let Some(token) = &self.credentials.session_token {
+            request
+                .insert_header("x-amz-security-token", token)
+                .context("Failed to set x-amz-security-token header")?;
+        }
+
+        let headers: Vec<(String, String)> = request
+            .headers
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.as_str();
+                name == "host" || name == "content-type" || name.starts_with("x-amz-")
+            })
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_string(), value.to_string()))
+            })
+            .collect();
+        let canonical = canonical_request(
+            request.method.as_str(),
+            request.uri.path(),
+            request.uri.query().unwrap_or_default(),
+            &headers,
+            &payload_hash,
+        );
+
+        request
+            .insert_header("authorization", self.authorization(&canonical, &amz_date))
+            .context("Failed to set SigV4 authorization header")?;
+        Ok(())
+    }
+
+    /// 生成 `Authorization` 头
+    #[must_use]
+    pub fn authorization(&self, canonical: &CanonicalRequest, amz_date: &str) -> String {
+        format!(
+            "{ALGORITHM} Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.credentials.access_key_id,
+            self.credential_scope(amz_date),
+            canonical.signed_headers,
+            self.signature(&canonical.text, amz_date)
+        )
+    }
+
+    /// 计算规范请求的签名（十六进制）
+    #[must_use]
+    pub fn signature(&self, canonical_request: &str, amz_date: &str) -> String {
+        let date = amz_date.get(..8).unwrap_or(amz_date);
+        let string_to_sign = format!(
+            "{ALGORITHM}\n{amz_date}\n{}\n{}",
+            self.credential_scope(amz_date),
+            hex_encode(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let secret = format!("AWS4{}", self.credentials.secret_access_key);
+        let date_key = hmac_sha256(secret.as_bytes(), date.as_bytes());
+        let region_key = hmac_sha256(&date_key, self.scope.region.as_bytes());
+        let service_key = hmac_sha256(&region_key, self.scope.service.as_bytes());
+        let signing_key = hmac_sha256(&service_key, b"aws4_request");
+        hex_encode(&hmac_sha256(&signing_key, string_to_sign.as_bytes()))
+    }
+
+    fn credential_scope(&self, amz_date: &str) -> String {
+        let date = amz_date.get(..8).unwrap_or(amz_date);
+        format!(
+            "{date}/{}/{}/aws4_request",
+            self.scope.region, self.scope.service
+        )
+    }
+}
+
+/// HMAC-SHA256
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; HMAC_BLOCK_SIZE];
+    if key.len() > HMAC_BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block.map(|byte| byte ^ 0x36));
+    inner.update(data);
+    let inner_hash = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block.map(|byte| byte ^ 0x5c));
+    outer.update(inner_hash);
+    outer.finalize().into()
+}
+
+/// 十六进制小写编码
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut out, byte| {
+            let _ = write!(out, "{byte:02x}");
+            out
+        })
+}
+
+/// 按 RFC 3986 编码，仅保留非保留字符（路径中可保留 `/`）
+fn uri_encode(input: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || matches!(byte, b'-' | b'_' | b'.' | b'~')
+            || (byte == b'/' && !encode_slash)
+        {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+    out
+}
+
+fn percent_decode(input: &str) -> String {
+    urlencoding::decode(input).map_or_else(|_| input.to_string(), |decoded| decoded.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // AWS SigV4 官方测试套件使用的凭证与时间
+    const TEST_DATE: &str = "20150830T123600Z";
+    const EMPTY_PAYLOAD_HASH: &str =
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn test_credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        }
+    }
+
+    fn scope(service: &str) -> SigV4Scope {
+        SigV4Scope {
+            region: "us-east-1".to_string(),
+            service: service.to_string(),
+        }
+    }
+
+    fn vanilla_headers() -> Vec<(String, String)> {
+        vec![
+            ("Host".to_string(), "example.amazonaws.com".to_string()),
+            ("X-Amz-Date".to_string(), TEST_DATE.to_string()),
+        ]
+    }
+
+    #[test]
+    fn matches_aws_test_suite_vectors() {
+        let credentials = test_credentials();
+        let scope = scope("service");
+        let signer = SigV4Signer::new(&credentials, &scope);
+        let form_payload_hash = hex_encode(&Sha256::digest(b"Param1=value1"));
+        let mut form_headers = vanilla_headers();
+        form_headers.push((
+            "Content-Type".to_string(),
+            "application/x-www-form-urlencoded".to_string(),
+        ));
+
+        // (用例, 方法, 查询参数, 请求头, 请求体哈希, 规范查询串, 期望签名)
+        let cases = [
+            (
+                "get-vanilla",
+                "GET",
+                "",
+                vanilla_headers(),
+                EMPTY_PAYLOAD_HASH.to_string(),
+                "",
+                "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31",
+            ),
+            (
+                "post-vanilla",
+                "POST",
+                "",
+                vanilla_headers(),
+                EMPTY_PAYLOAD_HASH.to_string(),
+                "",
+                "5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b",
+            ),
+            (
+                "get-vanilla-query-order-key-case",
+                "GET",
+                "Param2=value2&Param1=value1",
+                vanilla_headers(),
+                EMPTY_PAYLOAD_HASH.to_string(),
+                "Param1=value1&Param2=value2",
+                "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500",
+            ),
+            (
+                "post-x-www-form-urlencoded",
+                "POST",
+                "",
+                form_headers,
+                form_payload_hash,
+                "",
+                "ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a",
+            ),
+        ];
+
+        for (name, method, query, headers, payload_hash, canonical_query, expected) in cases {
+            let canonical = canonical_request(method, "/", query, &headers, &payload_hash);
+            let mut expected_headers = String::new();
+            if headers.len() == 3 {
+                expected_headers.push_str("content-type:application/x-www-form-urlencoded\n");
+            }
+            let _ = write!(
+                expected_headers,
+                "host:example.amazonaws.com\nx-amz-date:{TEST_DATE}\n"
+            );
+            assert_eq!(
+                canonical.text,
+                format!(
+                    "{method}\n/\n{canonical_query}\n{expected_headers}\n{}\n{payload_hash}",
+                    canonical.signed_headers
+                ),
+                "{name}"
+            );
+            assert_eq!(
+                signer.signature(&canonical.text, TEST_DATE),
+                expected,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn signs_bedrock_request_over_final_body() {
+        let credentials = test_credentials();
+        let scope = scope("bedrock");
+        let mut request =
+            RequestHeader::build("POST", b"/model/anthropic.claude-v2%3A1/invoke", None).unwrap();
+        request
+            .insert_header("host", "bedrock-runtime.us-east-1.amazonaws.com")
+            .unwrap();
+        request
+            .insert_header("content-type", "application/json")
+            .unwrap();
+        request.insert_header("accept", "*/*").unwrap();
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+
+        SigV4Signer::new(&credentials, &scope)
+            .sign(&mut request, br#"{"prompt":"hi"}"#, now)
+            .unwrap();
+
+        let header = |name: &str| request.headers.get(name).unwrap().to_str().unwrap();
+        assert_eq!(header("x-amz-date"), TEST_DATE);
+        assert_eq!(
+            header("x-amz-content-sha256"),
+            "14479f4e87d340fe0ca0d522d87a5b3a028ebb1af24fbb8d3ef4553044fc6db6"
+        );
+        assert_eq!(
+            header("authorization"),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/bedrock/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-content-sha256;x-amz-date, \
+             Signature=67701982a650730335b02b7debbb7b498342c8056faf62fb81ed88439f44b1f4"
+        );
+    }
+
+    #[test]
+    fn parses_credentials_and_scope() {
+        let credentials = AwsCredentials::parse("AKID:secret:token").unwrap();
+        assert_eq!(credentials.session_token.as_deref(), Some("token"));
+        assert!(!format!("{credentials:?}").contains("secret"));
+        assert!(AwsCredentials::parse("AKID").is_err());
+
+        let now = Utc::now().naive_utc();
+        let mut provider = provider_types::Model {
+            id: 9401,
+            name: "bedrock".to_string(),
+            display_name: "Amazon Bedrock".to_string(),
+            auth_type: "aws_sigv4".to_string(),
+            base_url: "bedrock-runtime.eu-west-1.amazonaws.com".to_string(),
+            is_active: true,
+            config_json: None,
+            token_mappings_json: None,
+            model_extraction_json: None,
+            auth_configs_json: None,
+            created_at: now,
+            updated_at: now,
+        };
+        assert_eq!(
+            SigV4Scope::from_provider(&provider).unwrap(),
+            SigV4Scope {
+                region: "eu-west-1".to_string(),
+                service: "bedrock".to_string(),
+            }
+        );
+
+        provider.base_url = "bedrock.example.com".to_string();
+        assert!(SigV4Scope::from_provider(&provider).is_err());
+        provider.config_json = Some(r#"{"aws_sigv4":{"region":"us-west-2"}}"#.to_string());
+        assert_eq!(
+            SigV4Scope::from_provider(&provider).unwrap().region,
+            "us-west-2"
+        );
+    }
+}
@@ -2,13 +2,26 @@
 //!
 //! 包含代理请求处理过程中使用的上下文类型定义
 
+use crate::config::NonIdempotentRetry;
+use crate::pricing::CreditReservation;
+use crate::proxy::admission::AdmissionPermit;
+use crate::proxy::aws_sigv4::AwsCredentials;
+use crate::proxy::cost_ceiling::CostCeiling;
+use crate::proxy::model_alias::ModelAliasRewriter;
+use crate::proxy::parameter_policy::ParameterAdjustment;
 use crate::proxy::provider_strategy::ProviderStrategy;
+use crate::proxy::request_body_buffer::RequestBodyBuffer;
+use crate::proxy::retry_budget::RetryBudget;
+use crate::proxy::sse_event_flush::SseEventFlusher;
+use crate::proxy::upstream_timing::UpstreamTiming;
+use crate::proxy::websocket::WebSocketSession;
 use crate::{ldebug, logging::LogComponent, logging::LogStage};
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use rand::Rng;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use crate::collect::stream_usage::SseUsageTracker;
 use crate::collect::types::TokenUsageMetrics;
 use crate::collect::types::{RequestDetails, ResponseDetails};
 use entity::{provider_types, user_provider_keys, user_service_apis};
@@ -21,6 +34,10 @@ pub enum ResolvedCredential {
     ApiKey(String),
     /// OAuth 访问令牌
     OAuthAccessToken(String),
+    /// AWS 访问凭证（请求按 SigV4 签名）
+    AwsSigV4(AwsCredentials),
+    /// HMAC 签名密钥（请求按 HMAC-SHA256 签名）
+    HmacSignature(String),
 }
 
 /// 重试相关的运行时状态
@@ -40,6 +57,30 @@ pub struct RetryState {
     pub last_retry_status_code: Option<u16>,
     /// 上游建议的 Retry-After（毫秒），仅在解析到对应响应头时设置
     pub retry_after_ms: Option<u64>,
+    /// 非幂等请求的重试限制；幂等请求为 `None`，不受限制
+    pub idempotency_guard: Option<NonIdempotentRetry>,
+    /// 服务商重试预算；未启用时为 `None`，重试只受服务 API 的 `retry_count` 限制
+    pub provider_budget: Option<Arc<RetryBudget>>,
+}
+
+/// 解析 Retry-After 头（毫秒）
+///
+/// 支持 `<delay-seconds>` 与 HTTP-date（常见：Sun, 06 Nov 1994 08:49:37 GMT）；
+/// 按 RFC 9110，时间已过视为立即可重试。
+pub fn parse_retry_after_ms(header_value: &str) -> Result<Option<u64>, chrono::ParseError> {
+    let trimmed = header_value.trim();
+    if let Ok(seconds) = trimmed.parse::<u64>() {
+        return Ok(Some(seconds.saturating_mul(1000)));
+    }
+    let target = chrono::DateTime::parse_from_rfc2822(trimmed)?.with_timezone(&chrono::Utc);
+    let ms = target
+        .signed_duration_since(chrono::Utc::now())
+        .num_milliseconds();
+    Ok(if ms <= 0 {
+        Some(0)
+    } else {
+        u64::try_from(ms).ok()
+    })
 }
 
 impl RetryState {
@@ -66,27 +107,8 @@ impl RetryState {
 
     pub fn set_retry_after_from_header_value(&mut self, request_id: &str, header_value: &str) {
         let trimmed = header_value.trim();
-
-        // Retry-After: <delay-seconds> 或 HTTP-date（常见：Sun, 06 Nov 1994 08:49:37 GMT）
-        if let Ok(seconds) = trimmed.parse::<u64>() {
-            self.retry_after_ms = Some(seconds.saturating_mul(1000));
-            return;
-        }
-
-        match chrono::DateTime::parse_from_rfc2822(trimmed) {
-            Ok(dt) => {
-                let now = chrono::Utc::now();
-                let target = dt.with_timezone(&chrono::Utc);
-                let delta = target.signed_duration_since(now);
-                let ms = delta.num_milliseconds();
-
-                // RFC 9110: 若时间已过，视为“立即可重试”
-                self.retry_after_ms = if ms <= 0 {
-                    Some(0)
-                } else {
-                    u64::try_from(ms).ok()
-                };
-            }
+        match parse_retry_after_ms(trimmed) {
+            Ok(ms) => self.retry_after_ms = ms,
             Err(e) => {
                 ldebug!(
                     request_id,
@@ -169,8 +191,27 @@ pub struct ProxyRequestContext {
     pub body_truncated: bool,
     /// 是否计划修改请求体（供上游头部处理决策使用）
     pub will_modify_body: bool,
+    /// 计划修改请求体时累积分块，流结束后交出完整请求体
+    pub body_buffer: RequestBodyBuffer,
     /// 用户请求的模型名称
     pub requested_model: Option<String>,
+    /// 参数策略对请求体所做的调整（注入缺省值/钳制上限）
+    pub parameter_adjustments: Vec<ParameterAdjustment>,
+    /// 是否为 WebSocket 升级请求
+    pub is_websocket: bool,
+    /// 是否为流式请求；仅在开启 SSE keepalive 时于请求阶段判定，决定上游连接是否经 keepalive 中转
+    pub is_streaming: bool,
+    /// 是否为探活/就绪探针请求（不计入代理流量）
+    pub is_probe: bool,
+    /// 签名（SigV4、HMAC）覆盖的最终请求体（签名前已完整读取并改写，重试时复用）
+    pub signed_body: Option<Bytes>,
+    /// 本次尝试是否已发送 `signed_body`
+    pub signed_body_sent: bool,
+    /// 按模型路由或判定流式请求时预读的请求体；普通请求由重放缓冲照常驱动 `request_body_filter`，
+    /// 签名请求无法再从下游读取，签名前从这里取回
+    pub routing_body: Option<Bytes>,
+    /// 是否已计入模型级限流（重试重放请求体时不再重复计数）
+    pub model_rate_limit_counted: bool,
 }
 
 /// 响应相关上下文
@@ -185,10 +226,28 @@ pub struct ProxyResponseContext {
     pub body_truncated: bool,
     /// 是否为 SSE 响应（在 `response_filter` 时缓存）
     pub is_sse: bool,
+    /// 是否为非 JSON 响应（`text/plain`、图片等），跳过 JSON 解析相关处理
+    pub is_opaque_body: bool,
+    /// 流式响应体是否按配置跳过缓存（只统计字节数，用量与元数据逐帧提取）
+    pub body_capture_skipped: bool,
     /// SSE 首包心跳是否已注入（用于保持下游连接活跃）
     pub sse_keepalive_sent: bool,
     /// 最终使用量（统一出口）
     pub usage_final: Option<TokenUsageMetrics>,
+    /// WebSocket 会话统计（上游返回 101 后创建）
+    pub websocket: Option<WebSocketSession>,
+    /// 未压缩 SSE 响应的用量跟踪（分块到达时解析事件，不受响应体缓存上限影响）
+    pub stream_usage: Option<SseUsageTracker>,
+    /// 白标模式下改写返回客户端的响应体模型名（在 `response_filter` 时创建）
+    pub model_alias: Option<ModelAliasRewriter>,
+    /// 开启 `streaming.flush_per_event` 时按事件边界发送 SSE 响应（在 `response_filter` 时创建）
+    pub sse_event_flush: Option<SseEventFlusher>,
+    /// 服务 API 配置单请求费用上限时的流式费用估算器（在 `response_filter` 时创建）
+    pub cost_ceiling: Option<CostCeiling>,
+    /// 是否因估算费用超过单请求费用上限而中断了响应
+    pub cost_limit_reached: bool,
+    /// 上游错误响应按服务 API 配置规范化时的响应体改写器（在 `response_filter` 时创建）
+    pub upstream_error: Option<UpstreamErrorSanitizer>,
 }
 
 /// 路由与认证相关上下文
@@ -205,6 +264,8 @@ pub struct ProxyRoutingContext {
     pub provider_type: Option<provider_types::Model>,
     /// 选定的服务商策略
     pub strategy: Option<Arc<dyn ProviderStrategy>>,
+    /// 本次尝试计入熔断统计的服务商名称（未启用熔断或使用备用地址时为空）
+    pub circuit_provider: Option<String>,
 }
 
 /// 请求控制相关上下文
@@ -213,6 +274,12 @@ pub struct ProxyControlContext {
     pub retry: RetryState,
     /// 连接超时时间(秒)
     pub timeout_seconds: Option<i32>,
+    /// 请求总时长上限（从 `start_time` 起算），`None` 表示不限制
+    pub total_timeout: Option<Duration>,
+    /// 全局并发空位，请求结束随上下文释放
+    pub admission: Option<AdmissionPermit>,
+    /// 转发前预留的积分，请求结束时结算或释放
+    pub credit_reservation: Option<CreditReservation>,
 }
 
 /// 追踪与日志相关上下文
@@ -223,6 +290,12 @@ pub struct ProxyTraceContext {
     pub upstream_request_headers: Option<BTreeMap<String, String>>,
     /// 最终上游请求 URI（可能被策略改写）
     pub upstream_request_uri: Option<String>,
+    /// 密钥选择依据（服务 API 开启 `selection_debug` 时记录）
+    pub selection_debug: Option<serde_json::Value>,
+    /// 是否命中定向调试采集（`debug_capture`），请求结束时按最终模型确定
+    pub debug_capture: bool,
+    /// 当前上游尝试的 DNS、建连、TLS 与首字节耗时
+    pub upstream_timing: UpstreamTiming,
 }
 
 /// 请求上下文
@@ -252,6 +325,9 @@ impl Default for ProxyContext {
             control: ProxyControlContext {
                 retry: RetryState::default(),
                 timeout_seconds: None,
+                total_timeout: None,
+                admission: None,
+                credit_reservation: None,
             },
             request: ProxyRequestContext {
                 details: RequestDetails::default(),
@@ -259,7 +335,15 @@ impl Default for ProxyContext {
                 body_received_size: 0,
                 body_truncated: false,
                 will_modify_body: false,
+                body_buffer: RequestBodyBuffer::default(),
                 requested_model: None,
+                parameter_adjustments: Vec::new(),
+                is_websocket: false,
+                is_streaming: false,
+                is_probe: false,
+                signed_body: None,
+                signed_body_sent: false,
+                model_rate_limit_counted: false,
             },
             response: ProxyResponseContext {
                 details: ResponseDetails::default(),
@@ -267,8 +351,17 @@ impl Default for ProxyContext {
                 body_received_size: 0,
                 body_truncated: false,
                 is_sse: false,
+                is_opaque_body: false,
+                body_capture_skipped: false,
                 sse_keepalive_sent: false,
                 usage_final: None,
+                websocket: None,
+                stream_usage: None,
+                model_alias: None,
+                sse_event_flush: None,
+                cost_ceiling: None,
+                cost_limit_reached: false,
+                upstream_error: None,
             },
             routing: ProxyRoutingContext {
                 resolved_credential: None,
@@ -277,17 +370,31 @@ impl Default for ProxyContext {
                 selected_backend: None,
                 provider_type: None,
                 strategy: None,
+                circuit_provider: None,
             },
             trace: ProxyTraceContext {
                 trace_started: false,
                 upstream_request_headers: None,
                 upstream_request_uri: None,
+                selection_debug: None,
+                debug_capture: false,
+                upstream_timing: UpstreamTiming::default(),
             },
         }
     }
 }
 
-impl ProxyContext {}
+impl ProxyContext {
+    /// 为新的下游请求创建上下文：生成 ULID 请求 ID 并开始计时
+    #[must_use]
+    pub fn new_request() -> Self {
+        Self {
+            request_id: crate::utils::request_id::generate(),
+            start_time: Instant::now(),
+            ..Default::default()
+        }
+    }
+}
 
 impl ProxyContext {
     /// 标记追踪已成功启动
@@ -300,6 +407,51 @@ impl ProxyContext {
     pub const fn is_trace_started(&self) -> bool {
         self.trace.trace_started
     }
+
+    /// 是否记录完整请求/响应内容：服务 API 开启 `log_mode` 或命中定向调试采集
+    #[must_use]
+    pub fn captures_full_detail(&self) -> bool {
+        self.trace.debug_capture
+            || self
+                .routing
+                .user_service_api
+                .as_ref()
+                .is_some_and(|api| api.log_mode)
+    }
+
+    /// 客户端发送的请求体字节数（请求体缓存截断或被改写都不影响计数）
+    #[must_use]
+    pub fn request_bytes(&self) -> u64 {
+        u64::try_from(self.request.body_received_size).unwrap_or(u64::MAX)
+    }
+
+    /// 上游返回的响应体字节数：流式响应为全部分块之和，WebSocket 会话为上游下发的全部字节
+    #[must_use]
+    pub fn response_bytes(&self) -> u64 {
+        let size = self
+            .response
+            .websocket
+            .as_ref()
+            .map_or(self.response.body_received_size, |session| {
+                session.bytes_received
+            });
+        u64::try_from(size).unwrap_or(u64::MAX)
+    }
+
+    /// 距请求总时长上限的剩余时间，未配置上限时为 `None`
+    #[must_use]
+    pub fn total_timeout_remaining(&self) -> Option<Duration> {
+        self.control
+            .total_timeout
+            .map(|limit| limit.saturating_sub(self.start_time.elapsed()))
+    }
+
+    /// 请求是否已超过总时长上限
+    #[must_use]
+    pub fn total_timeout_exceeded(&self) -> bool {
+        self.total_timeout_remaining()
+            .is_some_and(|remaining| remaining.is_zero())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,96 @@
+//! # 单请求费用上限
+//!
+//! 服务 API 配置 `max_cost_per_request` 后，流式响应在下发过程中按已出现的 Token 估算累计费用，
+//! 超过上限时中断上游流，追踪记录以 `cost_limit_reached` 状态收尾。
+//!
+//! 估算方式：
+//! - 上游已上报的用量（Anthropic `message_start`、Gemini 每个分块的累计值）优先；
+//! - 输出 Token 取上报值与已下发文本估算值中的较大者：能识别分词器的模型按分词器计数，
+//!   其它模型按约 4 字符 / Token 粗略估算；
+//! - 未上报输入用量时，对能识别分词器的模型按请求体估算输入 Token。
+//!
+//! 定价在响应头阶段一次加载，分块回调中只做同步计算；未配置定价的模型不做限制。
+
+use serde_json::Value;
+use tiktoken_rs::CoreBPE;
+
+use crate::collect::token_estimator::{count_tokens, estimate_prompt_tokens, tokenizer_for_model};
+use crate::collect::types::TokenUsageMetrics;
+use crate::pricing::{ModelPriceSheet, TokenUsage};
+use crate::types::{CostValue, TokenCount};
+
+/// 无分词器时每个 Token 对应的字符数
+const CHARS_PER_TOKEN: usize = 4;
+
+/// 单请求费用上限的估算器
+#[derive(Clone)]
+pub struct CostCeiling {
+    max_cost: CostValue,
+    prices: ModelPriceSheet,
+    tokenizer: Option<&'static CoreBPE>,
+    /// 按请求体估算的输入 Token（上游未上报输入用量时使用）
+    estimated_prompt_tokens: Option<TokenCount>,
+}
+
+impl CostCeiling {
+    #[must_use]
+    pub fn new(max_cost: CostValue, prices: ModelPriceSheet, model: &str) -> Self {
+        Self {
+            max_cost,
+            prices,
+            tokenizer: tokenizer_for_model(model),
+            estimated_prompt_tokens: None,
+        }
+    }
+
+    /// 按请求体估算输入 Token（仅能识别分词器的模型）
+    #[must_use]
+    pub fn with_request_body(mut self, request: Option<&Value>) -> Self {
+        self.estimated_prompt_tokens = self
+            .tokenizer
+            .zip(request)
+            .map(|(bpe, request)| estimate_prompt_tokens(bpe, request));
+        self
+    }
+
+    #[must_use]
+    pub const fn max_cost(&self) -> CostValue {
+        self.max_cost
+    }
+
+    /// 按截至目前的上报用量与已下发文本估算累计费用
+    #[must_use]
+    pub fn accrued_cost(
+        &self,
+        reported: &TokenUsageMetrics,
+        completion_text: Option<&str>,
+    ) -> CostValue {
+        let estimated_completion = completion_text.map(|text| match self.tokenizer {
+            Some(bpe) => count_tokens(bpe, text),
+            None => TokenCount::try_from(text.chars().count().div_ceil(CHARS_PER_TOKEN))
+                .unwrap_or(TokenCount::MAX),
+        });
+        let completion_tokens = match (reported.completion_tokens, estimated_completion) {
+            (Some(reported), Some(estimated)) => Some(reported.max(estimated)),
+            (reported, estimated) => reported.or(estimated),
+        };
+
+        self.prices.cost(&TokenUsage {
+            prompt_tokens: reported.prompt_tokens.or(self.estimated_prompt_tokens),
+            completion_tokens,
+            cache_create_tokens: reported.cache_create_tokens,
+            cache_read_tokens: reported.cache_read_tokens,
+        })
+    }
+
+    /// 累计费用超过上限时返回估算的费用
+    #[must_use]
+    pub fn exceeded(
+        &self,
+        reported: &TokenUsageMetrics,
+        completion_text: Option<&str>,
+    ) -> Option<CostValue> {
+        let accrued = self.accrued_cost(reported, completion_text);
+        (accrued > self.max_cost).then_some(accrued)
+    }
+}
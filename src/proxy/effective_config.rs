@@ -0,0 +1,236 @@
+//! # 生效配置
+//!
+//! 服务 API 的运行参数来自多层配置：服务 API 自身字段、服务商 `config_json`、配置文件（可热更新）以及内置默认值。
+//! 这里集中实现各参数的取值优先级，代理请求链路与管理端的生效配置预览共用同一套逻辑，
+//! 预览结果为每个值标注来源。
+
+use crate::config::{AppConfig, TimeoutBoundsConfig, TotalTimeoutConfig};
+use crate::key_pool::types::SchedulingStrategy;
+use crate::proxy::model_rate_limit::ModelRateLimits;
+use crate::proxy::non_streaming::StreamPolicy;
+use crate::proxy::parameter_policy::MaxTokensCeiling;
+use crate::proxy::upstream_error::UpstreamErrorMode;
+use entity::{provider_types, user_service_apis};
+use sea_orm::prelude::Decimal;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// 服务 API 未配置超时时使用的默认上游超时（秒）
+pub const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+/// 配置值的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    /// 服务 API 自身的字段
+    ServiceApi,
+    /// 服务商 `config_json`
+    Provider,
+    /// 配置文件（含按服务商覆盖的配置项）
+    Global,
+    /// 内置默认值
+    Default,
+}
+
+/// 带来源标注的生效值
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Resolved<T> {
+    pub value: T,
+    pub source: ConfigSource,
+}
+
+impl<T> Resolved<T> {
+    const fn new(value: T, source: ConfigSource) -> Self {
+        Self { value, source }
+    }
+}
+
+/// 服务 API 请求的上游超时（秒）：未配置或非正数时回退默认值，尚未按全局范围收敛
+#[must_use]
+pub fn requested_timeout_seconds(configured: Option<i32>) -> Resolved<u64> {
+    configured
+        .and_then(|secs| u64::try_from(secs).ok())
+        .filter(|secs| *secs > 0)
+        .map_or_else(
+            || Resolved::new(DEFAULT_TIMEOUT_SECS, ConfigSource::Default),
+            |secs| Resolved::new(secs, ConfigSource::ServiceApi),
+        )
+}
+
+/// 生效的上游超时（秒）：超出配置文件 `[timeout_bounds]` 范围时收敛，来源记为配置文件
+#[must_use]
+pub fn timeout_seconds(bounds: &TimeoutBoundsConfig, configured: Option<i32>) -> Resolved<u64> {
+    let requested = requested_timeout_seconds(configured);
+    let clamped = bounds.clamp(requested.value);
+    if clamped == requested.value {
+        requested
+    } else {
+        Resolved::new(clamped, ConfigSource::Global)
+    }
+}
+
+/// 生效的请求总时长上限（秒），0 表示不限制
+///
+/// 优先级与 `TotalTimeoutConfig::resolve` 一致：服务 API > 配置文件按服务商覆盖 > 配置文件默认值。
+#[must_use]
+pub fn total_timeout_seconds(
+    config: &TotalTimeoutConfig,
+    provider_name: &str,
+    service_api_secs: Option<i32>,
+) -> Resolved<u64> {
+    let source = if service_api_secs.is_some() {
+        ConfigSource::ServiceApi
+    } else {
+        ConfigSource::Global
+    };
+    let secs = config
+        .resolve(provider_name, service_api_secs)
+        .map_or(0, |duration| duration.as_secs());
+    Resolved::new(secs, source)
+}
+
+/// 重试预算（额外重试次数）：服务 API 未配置时不重试
+#[must_use]
+pub fn retry_budget(api: &user_service_apis::Model) -> Resolved<u32> {
+    api.retry_count.map_or_else(
+        || Resolved::new(0, ConfigSource::Default),
+        |count| {
+            Resolved::new(
+                u32::try_from(count.max(0)).unwrap_or(u32::MAX),
+                ConfigSource::ServiceApi,
+            )
+        },
+    )
+}
+
+/// 调度策略：服务 API 未配置或无法识别时使用默认策略
+#[must_use]
+pub fn scheduling_strategy(api: &user_service_apis::Model) -> Resolved<SchedulingStrategy> {
+    api.scheduling_strategy
+        .as_deref()
+        .and_then(SchedulingStrategy::parse)
+        .map_or_else(
+            || Resolved::new(SchedulingStrategy::default(), ConfigSource::Default),
+            |strategy| Resolved::new(strategy, ConfigSource::ServiceApi),
+        )
+}
+
+/// 服务 API 的可选配置：未配置时为 `null`（不限制）
+fn optional_value<T: Serialize>(value: Option<T>) -> Resolved<Value> {
+    value.map_or_else(
+        || Resolved::new(Value::Null, ConfigSource::Default),
+        |value| Resolved::new(json!(value), ConfigSource::ServiceApi),
+    )
+}
+
+/// 服务 API 的生效配置，每个值标注来源
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub service_api_id: i32,
+    pub provider_type_id: i32,
+    pub provider: String,
+    pub timeout_seconds: Resolved<u64>,
+    pub total_timeout_seconds: Resolved<u64>,
+    pub sla_target_ms: Resolved<Value>,
+    pub retry_count: Resolved<u32>,
+    pub scheduling_strategy: Resolved<&'static str>,
+    pub stream_policy: Resolved<&'static str>,
+    pub upstream_error_mode: Resolved<&'static str>,
+    pub key_tag: Resolved<Value>,
+    pub max_request_per_min: Resolved<Value>,
+    pub max_requests_per_day: Resolved<Value>,
+    pub max_tokens_per_day: Resolved<Value>,
+    pub max_cost_per_day: Resolved<Value>,
+    pub max_cost_per_request: Resolved<Value>,
+    pub provider_max_requests_per_min: Resolved<Value>,
+    pub max_tokens_ceiling: Resolved<Value>,
+    pub model_rate_limits: Resolved<Value>,
+}
+
+impl EffectiveConfig {
+    /// 按请求链路的优先级合并服务 API、服务商配置、配置文件与默认值
+    #[must_use]
+    pub fn resolve(
+        config: &AppConfig,
+        api: &user_service_apis::Model,
+        provider: &provider_types::Model,
+    ) -> Self {
+        let scheduling = scheduling_strategy(api);
+        let stream_policy = StreamPolicy::of(api);
+        let stream_policy_source = if stream_policy == StreamPolicy::default() {
+            ConfigSource::Default
+        } else {
+            ConfigSource::ServiceApi
+        };
+        let upstream_error_mode = UpstreamErrorMode::of(api);
+        let upstream_error_mode_source = if upstream_error_mode == UpstreamErrorMode::default() {
+            ConfigSource::Default
+        } else {
+            ConfigSource::ServiceApi
+        };
+        let provider_rpm = config.rate_limit.provider_limit(provider.id).map_or_else(
+            || Resolved::new(Value::Null, ConfigSource::Default),
+            |limit| Resolved::new(json!(limit), ConfigSource::Global),
+        );
+        let max_tokens_ceiling = MaxTokensCeiling::from_provider(provider).map_or_else(
+            || Resolved::new(Value::Null, ConfigSource::Default),
+            |ceiling| {
+                Resolved::new(
+                    json!({"max": ceiling.max, "inject_when_absent": ceiling.inject_when_absent}),
+                    ConfigSource::Provider,
+                )
+            },
+        );
+        let model_rate_limits = ModelRateLimits::from_provider(provider)
+            .and_then(|_| provider_config_field(provider, "model_rate_limits"))
+            .map_or_else(
+                || Resolved::new(Value::Null, ConfigSource::Default),
+                |limits| Resolved::new(limits, ConfigSource::Provider),
+            );
+
+        Self {
+            service_api_id: api.id,
+            provider_type_id: provider.id,
+            provider: provider.name.clone(),
+            timeout_seconds: timeout_seconds(&config.timeout_bounds, api.timeout_seconds),
+            total_timeout_seconds: total_timeout_seconds(
+                &config.total_timeout,
+                &provider.name,
+                api.max_response_duration_seconds,
+            ),
+            sla_target_ms: optional_value(api.sla_target_ms.filter(|ms| *ms > 0)),
+            retry_count: retry_budget(api),
+            scheduling_strategy: Resolved::new(scheduling.value.as_str(), scheduling.source),
+            stream_policy: Resolved::new(stream_policy.as_str(), stream_policy_source),
+            upstream_error_mode: Resolved::new(
+                upstream_error_mode.as_str(),
+                upstream_error_mode_source,
+            ),
+            key_tag: optional_value(api.key_tag.clone()),
+            max_request_per_min: optional_value(api.max_request_per_min.filter(|rpm| *rpm > 0)),
+            max_requests_per_day: optional_value(api.max_requests_per_day.filter(|n| *n > 0)),
+            max_tokens_per_day: optional_value(api.max_tokens_per_day.filter(|n| *n > 0)),
+            max_cost_per_day: optional_value(
+                api.max_cost_per_day
+                    .filter(|cost| *cost > Decimal::ZERO)
+                    .map(|cost| cost.to_string()),
+            ),
+            max_cost_per_request: optional_value(
+                api.max_cost_per_request
+                    .filter(|cost| *cost > Decimal::ZERO)
+                    .map(|cost| cost.to_string()),
+            ),
+            provider_max_requests_per_min: provider_rpm,
+            max_tokens_ceiling,
+            model_rate_limits,
+        }
+    }
+}
+
+fn provider_config_field(provider: &provider_types::Model, field: &str) -> Option<Value> {
+    provider
+        .config_json
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+        .and_then(|mut config| config.get_mut(field).map(Value::take))
+}
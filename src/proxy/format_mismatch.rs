@@ -0,0 +1,141 @@
+//! # 请求体格式不匹配检测
+//!
+//! 客户端误把一种接口格式的请求体发给另一家服务商时（最常见的是把 `OpenAI` Chat Completions 请求体
+//! 发到 Anthropic `/v1/messages`），上游只会返回含义模糊的 400。各服务商策略按目标接口检测请求体中
+//! 只属于其他格式的字段，命中时代理直接返回 400 说明不匹配原因，不转发上游。
+//!
+//! 只有请求体已完整缓存（计划改写）时才能在转发前检测；当前不做格式自动转换。
+
+use serde_json::Value;
+
+/// 只出现在 `OpenAI` Chat Completions 请求体中的顶层字段
+pub const OPENAI_ONLY_FIELDS: &[&str] = &[
+    "n",
+    "frequency_penalty",
+    "presence_penalty",
+    "logit_bias",
+    "logprobs",
+    "top_logprobs",
+    "response_format",
+    "max_completion_tokens",
+    "seed",
+    "stream_options",
+    "parallel_tool_calls",
+    "functions",
+    "function_call",
+];
+
+/// 只出现在 Anthropic Messages 请求体中的顶层字段
+pub const ANTHROPIC_ONLY_FIELDS: &[&str] = &["stop_sequences", "anthropic_version"];
+
+/// 只出现在 Gemini `generateContent` 请求体中的顶层字段
+pub const GEMINI_ONLY_FIELDS: &[&str] = &[
+    "contents",
+    "generationConfig",
+    "safetySettings",
+    "systemInstruction",
+];
+
+/// 检测到的格式不匹配
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatMismatch {
+    /// 请求体看起来属于的格式
+    pub detected: &'static str,
+    /// 目标接口期望的格式
+    pub expected: &'static str,
+    /// 暴露不匹配的字段（按请求体中的路径表示）
+    pub fields: Vec<String>,
+}
+
+impl FormatMismatch {
+    /// 命中字段非空时构造不匹配结果
+    #[must_use]
+    pub fn from_fields(
+        detected: &'static str,
+        expected: &'static str,
+        fields: Vec<String>,
+    ) -> Option<Self> {
+        (!fields.is_empty()).then_some(Self {
+            detected,
+            expected,
+            fields,
+        })
+    }
+
+    /// 返回给客户端的说明
+    #[must_use]
+    pub fn message(&self) -> String {
+        format!(
+            "请求体看起来是 {} 格式（包含字段 {}），但目标接口需要 {} 格式，请改用对应格式的请求体或接口路径",
+            self.detected,
+            self.fields.join(", "),
+            self.expected
+        )
+    }
+}
+
+/// 请求体中出现的顶层字段（保持列表顺序）
+#[must_use]
+pub fn present_fields(body: &Value, fields: &[&str]) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|field| body.get(**field).is_some())
+        .map(|field| (*field).to_string())
+        .collect()
+}
+
+/// `messages` 中使用 `system` 角色的消息下标（`OpenAI` 把系统提示放在消息列表中）
+#[must_use]
+pub fn system_role_messages(body: &Value) -> Vec<String> {
+    body.get("messages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, message)| message.get("role").and_then(Value::as_str) == Some("system"))
+        .map(|(index, _)| format!("messages[{index}].role=system"))
+        .collect()
+}
+
+/// `tools` 是否使用 `OpenAI` 的 `{"type": "function", "function": {...}}` 结构
+#[must_use]
+pub fn has_openai_function_tools(body: &Value) -> bool {
+    body.get("tools")
+        .and_then(Value::as_array)
+        .is_some_and(|tools| tools.iter().any(|tool| tool.get("function").is_some()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detects_openai_only_fields_and_system_messages() {
+        let body = json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "system", "content": "be brief"},
+                {"role": "user", "content": "hi"}
+            ],
+            "n": 1,
+            "response_format": {"type": "json_object"},
+            "tools": [{"type": "function", "function": {"name": "lookup"}}]
+        });
+
+        assert_eq!(
+            present_fields(&body, OPENAI_ONLY_FIELDS),
+            ["n", "response_format"]
+        );
+        assert_eq!(system_role_messages(&body), ["messages[0].role=system"]);
+        assert!(has_openai_function_tools(&body));
+        assert!(
+            FormatMismatch::from_fields(
+                "openai",
+                "anthropic",
+                present_fields(&json!({"model": "x"}), OPENAI_ONLY_FIELDS)
+            )
+            .is_none()
+        );
+    }
+}
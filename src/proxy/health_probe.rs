@@ -0,0 +1,247 @@
+//! # 探活与就绪探针
+//!
+//! 代理端口上的 `/healthz` 与 `/readyz` 供负载均衡器探测，不经过认证，也不计入代理流量：
+//! - `/healthz`：进程存活即返回 200；
+//! - `/readyz`：数据库、缓存可达且至少有一个启用中的健康密钥时返回 200，否则返回 503 并列出各项结果。
+//!
+//! Redis 不可达时缓存层降级放行请求，`/readyz` 在结果中报告 `cache: "degraded"` 但仍返回 200：
+//! 共享的 Redis 故障会同时影响所有实例，若因此全部报告未就绪，负载均衡器会摘除整个集群。
+
+use crate::cache::CacheManager;
+use crate::key_pool::types::ApiKeyHealthStatus;
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
+use entity::user_provider_keys;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// 存活探针路径
+pub const HEALTHZ_PATH: &str = "/healthz";
+/// 就绪探针路径
+pub const READYZ_PATH: &str = "/readyz";
+
+/// 缓存可达性检查使用的键，只读取不写入
+const CACHE_PROBE_KEY: &str = "health:readyz";
+
+/// 探针类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeKind {
+    /// 存活探针
+    Liveness,
+    /// 就绪探针
+    Readiness,
+}
+
+impl ProbeKind {
+    /// 按请求路径识别探针，非探针路径返回 `None`
+    #[must_use]
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path {
+            HEALTHZ_PATH => Some(Self::Liveness),
+            READYZ_PATH => Some(Self::Readiness),
+            _ => None,
+        }
+    }
+}
+
+/// 缓存检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheCheck {
+    /// 缓存可用
+    Ok,
+    /// Redis 不可达，缓存层处于降级状态；不影响就绪
+    Degraded,
+    /// 缓存读取失败
+    Unreachable,
+}
+
+/// 就绪检查结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadinessReport {
+    pub database: bool,
+    pub cache: CacheCheck,
+    /// 启用中且健康的服务商密钥数量
+    pub healthy_keys: u64,
+}
+
+impl ReadinessReport {
+    /// 所有依赖均可用
+    #[must_use]
+    pub const fn is_ready(&self) -> bool {
+        self.database && !matches!(self.cache, CacheCheck::Unreachable) && self.healthy_keys > 0
+    }
+
+    /// 探针响应状态码
+    #[must_use]
+    pub const fn status_code(&self) -> u16 {
+        if self.is_ready() { 200 } else { 503 }
+    }
+
+    /// 探针响应体
+    #[must_use]
+    pub fn payload(&self) -> Value {
+        json!({
+            "status": if self.is_ready() { "ready" } else { "not_ready" },
+            "checks": self,
+        })
+    }
+}
+
+/// 探针服务
+pub struct HealthProbeService {
+    db: Arc<DatabaseConnection>,
+    cache: Arc<CacheManager>,
+}
+
+impl HealthProbeService {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>, cache: Arc<CacheManager>) -> Self {
+        Self { db, cache }
+    }
+
+    /// 存活探针响应
+    #[must_use]
+    pub fn liveness() -> (u16, Value) {
+        (200, json!({ "status": "ok" }))
+    }
+
+    /// 按探针类型生成响应状态码与响应体
+    pub async fn respond(&self, kind: ProbeKind) -> (u16, Value) {
+        match kind {
+            ProbeKind::Liveness => Self::liveness(),
+            ProbeKind::Readiness => {
+                let report = self.readiness().await;
+                (report.status_code(), report.payload())
+            }
+        }
+    }
+
+    /// 实际检查数据库、缓存与密钥池
+    pub async fn readiness(&self) -> ReadinessReport {
+        let database = match self.db.ping().await {
+            Ok(()) => true,
+            Err(err) => {
+                lwarn!(
+                    "system",
+                    LogStage::HealthCheck,
+                    LogComponent::HealthChecker,
+                    "readyz_database_unreachable",
+                    "就绪检查：数据库不可达",
+                    error = %err
+                );
+                false
+            }
+        };
+
+        let cache = match self.cache.exists(CACHE_PROBE_KEY).await {
+            // 降级期间读取被跳过并视为未命中，需单独检查降级状态
+            Ok(_) if self.cache.is_degraded() => {
+                lwarn!(
+                    "system",
+                    LogStage::HealthCheck,
+                    LogComponent::HealthChecker,
+                    "readyz_cache_degraded",
+                    "就绪检查：缓存处于降级状态"
+                );
+                CacheCheck::Degraded
+            }
+            Ok(_) => CacheCheck::Ok,
+            Err(err) => {
+                lwarn!(
+                    "system",
+                    LogStage::HealthCheck,
+                    LogComponent::HealthChecker,
+                    "readyz_cache_unreachable",
+                    "就绪检查：缓存不可达",
+                    error = %err
+                );
+                CacheCheck::Unreachable
+            }
+        };
+
+        let healthy_keys = if database {
+            self.count_healthy_keys().await
+        } else {
+            0
+        };
+
+        ReadinessReport {
+            database,
+            cache,
+            healthy_keys,
+        }
+    }
+
+    async fn count_healthy_keys(&self) -> u64 {
+        match user_provider_keys::Entity::find()
+            .filter(user_provider_keys::Column::IsActive.eq(true))
+            .filter(user_provider_keys::Column::DeletedAt.is_null())
+            .filter(
+                user_provider_keys::Column::HealthStatus
+                    .eq(ApiKeyHealthStatus::Healthy.to_string()),
+            )
+            .count(self.db.as_ref())
+            .await
+        {
+            Ok(count) => count,
+            Err(err) => {
+                lwarn!(
+                    "system",
+                    LogStage::HealthCheck,
+                    LogComponent::HealthChecker,
+                    "readyz_key_count_failed",
+                    "就绪检查：统计健康密钥失败",
+                    error = %err
+                );
+                0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_paths_are_exact() {
+        assert_eq!(ProbeKind::from_path("/healthz"), Some(ProbeKind::Liveness));
+        assert_eq!(ProbeKind::from_path("/readyz"), Some(ProbeKind::Readiness));
+        assert_eq!(ProbeKind::from_path("/readyz/extra"), None);
+        assert_eq!(ProbeKind::from_path("/v1/chat/completions"), None);
+    }
+
+    #[test]
+    fn readiness_requires_every_dependency() {
+        let ready = ReadinessReport {
+            database: true,
+            cache: CacheCheck::Ok,
+            healthy_keys: 1,
+        };
+        assert_eq!(ready.status_code(), 200);
+        assert_eq!(ready.payload()["status"], "ready");
+
+        let no_keys = ReadinessReport {
+            healthy_keys: 0,
+            ..ready.clone()
+        };
+        assert_eq!(no_keys.status_code(), 503);
+
+        let degraded = ReadinessReport {
+            cache: CacheCheck::Degraded,
+            ..ready.clone()
+        };
+        assert_eq!(degraded.status_code(), 200);
+        assert_eq!(degraded.payload()["checks"]["cache"], "degraded");
+
+        let no_cache = ReadinessReport {
+            cache: CacheCheck::Unreachable,
+            ..ready
+        };
+        assert_eq!(no_cache.status_code(), 503);
+        assert_eq!(no_cache.payload()["checks"]["cache"], "unreachable");
+    }
+}
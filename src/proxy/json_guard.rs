@@ -0,0 +1,156 @@
+//! # 请求体 JSON 复杂度检查
+//!
+//! 在解析请求体之前按字节扫描一遍：跟踪对象与数组的嵌套深度，统计数组元素与对象成员总数，
+//! 超过 `request_body` 配置的上限立即停止并拒绝请求，不必先构造完整的 `serde_json::Value`。
+//! 扫描只识别结构字符并跳过字符串内容，不校验 JSON 语法，语法错误留给后续解析处理。
+
+use crate::config::RequestBodyConfig;
+
+/// 超过的复杂度上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonComplexityViolation {
+    /// 嵌套深度超过上限
+    Depth { limit: usize },
+    /// 元素总数超过上限
+    Elements { limit: usize },
+}
+
+impl JsonComplexityViolation {
+    /// 返回给客户端的提示
+    #[must_use]
+    pub fn message(self) -> String {
+        match self {
+            Self::Depth { limit } => format!("请求体 JSON 嵌套层数超过上限 {limit}"),
+            Self::Elements { limit } => format!("请求体 JSON 元素数量超过上限 {limit}"),
+        }
+    }
+}
+
+/// 扫描请求体，超过任一上限时返回对应的违规项
+pub fn check_complexity(
+    body: &[u8],
+    config: &RequestBodyConfig,
+) -> Result<(), JsonComplexityViolation> {
+    let max_depth = config.max_depth();
+    let max_elements = config.max_elements();
+    if max_depth.is_none() && max_elements.is_none() {
+        return Ok(());
+    }
+
+    let mut depth = 0_usize;
+    let mut elements = 0_usize;
+    // 刚进入容器、尚未遇到第一个元素
+    let mut awaiting_first = false;
+    let mut bytes = body.iter();
+
+    while let Some(&byte) = bytes.next() {
+        if byte.is_ascii_whitespace() {
+            continue;
+        }
+        let starts_element = match byte {
+            b']' | b'}' => {
+                depth = depth.saturating_sub(1);
+                awaiting_first = false;
+                false
+            }
+            b',' => true,
+            b':' => false,
+            _ => std::mem::take(&mut awaiting_first),
+        };
+        if starts_element {
+            elements += 1;
+            if let Some(limit) = max_elements
+                && elements > limit
+            {
+                return Err(JsonComplexityViolation::Elements { limit });
+            }
+        }
+        match byte {
+            b'[' | b'{' => {
+                depth += 1;
+                if let Some(limit) = max_depth
+                    && depth > limit
+                {
+                    return Err(JsonComplexityViolation::Depth { limit });
+                }
+                awaiting_first = true;
+            }
+            b'"' => skip_string(&mut bytes),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 跳过字符串剩余内容（含转义字符），停在结束引号之后
+fn skip_string<'a>(bytes: &mut impl Iterator<Item = &'a u8>) {
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            b'\\' => {
+                bytes.next();
+            }
+            b'"' => return,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn limits(max_json_depth: usize, max_json_elements: usize) -> RequestBodyConfig {
+        RequestBodyConfig {
+            max_json_depth,
+            max_json_elements,
+            validate_tools: true,
+        }
+    }
+
+    #[test]
+    fn normal_bodies_pass() {
+        let body = br#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi [{,}]"}],"stream":true}"#;
+        assert_eq!(check_complexity(body, &limits(4, 10)), Ok(()));
+        assert_eq!(check_complexity(b"{}", &limits(1, 1)), Ok(()));
+        assert_eq!(check_complexity(b"[[]]", &limits(2, 1)), Ok(()));
+    }
+
+    #[test]
+    fn overly_deep_json_is_rejected() {
+        let body = format!("{}{}", "[".repeat(65), "]".repeat(65));
+        assert_eq!(
+            check_complexity(body.as_bytes(), &RequestBodyConfig::default()),
+            Err(JsonComplexityViolation::Depth { limit: 64 })
+        );
+        // 字符串中的括号不计入嵌套
+        let quoted = format!(r#"{{"text":"{}"}}"#, "[".repeat(100));
+        assert_eq!(
+            check_complexity(quoted.as_bytes(), &RequestBodyConfig::default()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn overly_large_array_is_rejected() {
+        let items = vec!["1"; 11].join(",");
+        let body = format!(r#"{{"input":[{items}]}}"#);
+        // 对象成员 1 个加数组元素 11 个
+        assert_eq!(
+            check_complexity(body.as_bytes(), &limits(0, 10)),
+            Err(JsonComplexityViolation::Elements { limit: 10 })
+        );
+        assert_eq!(check_complexity(body.as_bytes(), &limits(0, 12)), Ok(()));
+    }
+
+    #[test]
+    fn escaped_quotes_do_not_end_strings() {
+        let body = br#"{"text":"say \"[[[\" loudly","n":1}"#;
+        assert_eq!(check_complexity(body, &limits(1, 2)), Ok(()));
+    }
+
+    #[test]
+    fn zero_limits_disable_checks() {
+        let body = format!("{}{}", "[".repeat(500), "]".repeat(500));
+        assert_eq!(check_complexity(body.as_bytes(), &limits(0, 0)), Ok(()));
+    }
+}
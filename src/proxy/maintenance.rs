@@ -0,0 +1,177 @@
+//! # 维护模式
+//!
+//! 维护模式开启时代理端口对所有请求返回 503 与 `Retry-After`，管理端口照常服务。
+//! 开关状态保存在缓存中，管理端切换后代理端从下一个请求开始生效；缓存中没有状态时使用配置的初始值。
+
+use crate::cache::CacheManager;
+use crate::config::MaintenanceConfig;
+use crate::ensure;
+use crate::error::{Result, conversion::ConversionError};
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+const MAINTENANCE_STATE_KEY: &str = "config:maintenance";
+/// 缓存中的开关状态保留 30 天，过期后回到配置的初始状态
+const MAINTENANCE_STATE_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// 当前维护模式状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    pub enabled: bool,
+    pub message: String,
+    pub retry_after_secs: u64,
+    /// 最近一次通过管理接口切换的时间，使用配置初始值时为空
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// 切换维护模式的请求；未提供的字段沿用当前值
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MaintenanceUpdate {
+    pub enabled: bool,
+    pub message: Option<String>,
+    pub retry_after_secs: Option<u64>,
+}
+
+/// 维护模式开关
+pub struct MaintenanceService {
+    cache: Arc<CacheManager>,
+    defaults: MaintenanceConfig,
+}
+
+impl MaintenanceService {
+    #[must_use]
+    pub const fn new(cache: Arc<CacheManager>, defaults: MaintenanceConfig) -> Self {
+        Self { cache, defaults }
+    }
+
+    /// 读取当前状态
+    pub async fn current(&self) -> Result<MaintenanceState> {
+        Ok(self
+            .cache
+            .get::<MaintenanceState>(MAINTENANCE_STATE_KEY)
+            .await?
+            .unwrap_or_else(|| self.initial_state()))
+    }
+
+    /// 代理端使用：处于维护模式时返回当前状态
+    ///
+    /// 缓存读取失败时回退到配置的初始状态，避免缓存故障导致代理整体不可用。
+    pub async fn active(&self) -> Option<MaintenanceState> {
+        let state = match self.current().await {
+            Ok(state) => state,
+            Err(err) => {
+                lwarn!(
+                    "system",
+                    LogStage::Cache,
+                    LogComponent::Cache,
+                    "maintenance_state_read_failed",
+                    "读取维护模式状态失败，使用配置初始值",
+                    error = %err
+                );
+                self.initial_state()
+            }
+        };
+        state.enabled.then_some(state)
+    }
+
+    /// 切换维护模式并保存到缓存
+    pub async fn update(&self, update: MaintenanceUpdate) -> Result<MaintenanceState> {
+        let current = self.current().await?;
+        let message = update
+            .message
+            .map_or(current.message, |message| message.trim().to_string());
+        ensure!(
+            !message.is_empty(),
+            ConversionError::message("message 不能为空")
+        );
+        let retry_after_secs = update.retry_after_secs.unwrap_or(current.retry_after_secs);
+        ensure!(
+            retry_after_secs > 0,
+            ConversionError::message("retry_after_secs 必须为正数")
+        );
+
+        let state = MaintenanceState {
+            enabled: update.enabled,
+            message,
+            retry_after_secs,
+            updated_at: Some(Utc::now()),
+        };
+        self.cache
+            .set(MAINTENANCE_STATE_KEY, &state, Some(MAINTENANCE_STATE_TTL))
+            .await?;
+        Ok(state)
+    }
+
+    fn initial_state(&self) -> MaintenanceState {
+        MaintenanceState {
+            enabled: self.defaults.enabled,
+            message: self.defaults.message.clone(),
+            retry_after_secs: self.defaults.retry_after_secs,
+            updated_at: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(defaults: MaintenanceConfig) -> MaintenanceService {
+        MaintenanceService::new(Arc::new(CacheManager::memory_only()), defaults)
+    }
+
+    #[tokio::test]
+    async fn toggle_overrides_configured_initial_state() {
+        let service = service(MaintenanceConfig::default());
+        assert!(service.active().await.is_none());
+
+        let enabled = service
+            .update(MaintenanceUpdate {
+                enabled: true,
+                message: Some("  升级数据库  ".to_string()),
+                retry_after_secs: Some(120),
+            })
+            .await
+            .unwrap();
+        assert_eq!(enabled.message, "升级数据库");
+        assert_eq!(service.active().await, Some(enabled));
+
+        let disabled = service
+            .update(MaintenanceUpdate {
+                enabled: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        // 未提供的字段沿用上一次的设置
+        assert_eq!(disabled.message, "升级数据库");
+        assert_eq!(disabled.retry_after_secs, 120);
+        assert!(service.active().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn configured_initial_state_applies_until_toggled() {
+        let service = service(MaintenanceConfig {
+            enabled: true,
+            ..MaintenanceConfig::default()
+        });
+        let state = service.active().await.expect("maintenance enabled");
+        assert_eq!(state.retry_after_secs, 300);
+        assert!(state.updated_at.is_none());
+
+        let err = service
+            .update(MaintenanceUpdate {
+                enabled: false,
+                message: Some("   ".to_string()),
+                retry_after_secs: None,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), http::StatusCode::BAD_REQUEST);
+        assert!(service.active().await.is_some());
+    }
+}
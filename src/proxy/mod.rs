@@ -18,13 +18,48 @@
 //!   选择后端密钥池、解析最终上游凭证（API Key/OAuth）、以及执行速率限制和配额检查。
 //!
 //! - **`upstream_service.rs`**: **上游管理中心**。负责根据服务商策略选择正确的上游主机地址，
-//!   并配置连接参数（如超时、TLS、HTTP/2）。
+//!   并配置连接参数（如超时、TLS、HTTP/2）；服务商上游熔断时快速失败或改走备用地址（见 `upstream_circuit.rs`）。
 //!
 //! - **`request_transform_service.rs`**: **请求转换器**。负责在请求发往上游前对其进行修改，
 //!   包括：注入正确的认证头、根据 `ProviderStrategy` 改写路径或请求体、清理代理痕迹。
 //!
+//! - **`request_body_buffer.rs`**: **请求体缓冲**。改写请求体前累积分块，只在流结束时交出完整请求体，超过上限则放弃改写原样转发。
+//!
+//! - **`aws_sigv4.rs`**: **AWS 请求签名**。为 Amazon Bedrock 等要求 SigV4 的服务商按最终请求体计算签名。
+//!
+//! - **`health_probe.rs`**: **探针**。代理端口上的 `/healthz` 与 `/readyz`，绕过认证且不计入代理流量，
+//!   就绪探针实际检查数据库、缓存与健康密钥。
+//!
+//! - **`admission.rs`**: **优先级准入**。全局并发达到上限后按服务 API 的 `priority` 排队，高优先级请求先获得空位。
+//!
+//! - **`retry_budget.rs`**: **重试预算**。按服务商的令牌桶限制全局重试速率，预算耗尽后失败请求直接返回、不再重试。
+//!
+//! - **`maintenance.rs`**: **维护模式**。开关保存在缓存中，开启后代理端口直接返回 503，管理端口不受影响。
+//!
+//! - **`path_allowlist.rs`**: **路径白名单**。服务 API 可限制允许访问的请求路径（前缀或通配符），未命中时返回 403。
+//!
+//! - **`format_mismatch.rs`**: **格式不匹配检测**。请求体属于其他服务商格式（如 `OpenAI` 请求体发往 Anthropic）时返回说明原因的 400。
+//!
+//! - **`tool_validation.rs`**: **工具定义校验**。按服务商格式校验 `tools` / `functionDeclarations`，定义无效时返回指出字段的 400。
+//!
+//! - **`model_availability.rs`**: **模型预检**。按服务商密钥缓存 `/models` 列表，转发前拒绝不存在的模型并给出相近模型。
+//!
+//! - **`effective_config.rs`**: **生效配置**。集中实现服务 API 各参数的取值优先级，请求链路与管理端生效配置预览共用。
+//!
+//! - **`model_routing.rs`**: **按模型路由**。服务 API 的 `model_routes` 按请求模型选择服务商与密钥池，未命中时使用默认服务商。
+//!
+//! - **`model_rate_limit.rs`**: **模型级限流**。服务商 `config_json.model_rate_limits` 为单个模型设置每分钟请求上限，转发前检查。
+//!
 //! - **`response_transform_service.rs`**: **响应转换器**。负责修改从上游返回的响应头，
-//!   例如添加CORS头、移除敏感信息。
+//!   例如添加CORS头、移除敏感信息；白标模式下移除服务商标识头部。
+//!
+//! - **`upstream_error.rs`**: **上游错误返回方式**。服务 API 可选择规范化并脱敏上游错误响应（默认），或原样透传便于排查。
+//!
+//! - **`model_alias.rs`**: **响应模型别名**。白标模式下把返回客户端的响应体中的模型名改写为别名。
+//!
+//! - **`sse_event_flush.rs`**: **SSE 按事件刷新**。开启后 SSE 响应按事件边界发送，完整事件立即发出。
+//!
+//! - **`cost_ceiling.rs`**: **单请求费用上限**。流式响应按已输出的 Token 估算费用，超过服务 API 的上限时中断上游流。
 //!
 //! - **`collect/`**: **采集层**。负责从请求和响应中提取模型、用量等统计信息，并计算费用。
 //! - **`trace/`**: **记录层**。负责写入追踪记录、限流缓存与审计信息。
@@ -40,6 +75,7 @@
 //! - **`ProviderStrategy` Trait**: 定义了服务商特有的行为接口（如 `modify_request`, `build_auth_headers`）。
 //! - **具体实现 (e.g., `GeminiStrategy`, `OpenAIStrategy`)**: 封装了针对特定服务商（如Google Gemini, `OpenAI`）
 //!   的定制化逻辑，例如请求体注入、特殊错误处理等。这使得核心代理逻辑保持通用，易于扩展以支持新的AI服务。
+//! - **`StandardPassthroughStrategy`**: 没有专用策略、但 `config_json.api_format` 为 `openai` 的兼容服务商的回退策略。
 //!
 //! ## 数据流
 //!
@@ -55,13 +91,39 @@ pub mod state;
 pub mod types;
 
 // 专有服务
+pub mod admission;
 pub mod authentication_service;
+pub mod aws_sigv4;
+pub mod cost_ceiling;
+pub mod effective_config;
+pub mod format_mismatch;
+pub mod health_probe;
+pub mod hmac_signing;
+pub mod json_guard;
+pub mod maintenance;
+pub mod model_alias;
+pub mod model_availability;
+pub mod model_rate_limit;
+pub mod model_routing;
+pub mod non_streaming;
+pub mod parameter_policy;
+pub mod path_allowlist;
 pub mod pingora_proxy;
 pub mod provider_strategy;
+pub mod request_body_buffer;
 pub mod request_transform_service;
 pub mod response_transform_service;
+pub mod retry_budget;
+pub mod sse_event_flush;
+pub mod sse_keepalive;
+pub mod system_prompt;
+pub mod tool_validation;
+pub mod upstream_circuit;
+pub mod upstream_error;
 pub mod upstream_service;
+pub mod upstream_timing;
 pub mod upstream_url;
+pub mod websocket;
 
 // 统一导出
 pub use crate::collect::service::CollectService;
@@ -79,7 +141,9 @@ pub use upstream_service::UpstreamService;
 pub mod prelude {
     pub use super::authentication_service::AuthenticationService;
     pub use super::context::ProxyContext;
-    pub use super::provider_strategy::{ProviderRegistry, ProviderStrategy, make_strategy};
+    pub use super::provider_strategy::{
+        ProviderRegistry, ProviderStrategy, make_strategy, make_strategy_for_provider,
+    };
     pub use super::types::{ForwardingContext, ForwardingResult, ProviderId};
     pub use super::{
         PingoraProxyServer, ProxyService, RequestTransformService, ResponseTransformService,
@@ -0,0 +1,234 @@
+//! # 响应模型别名
+//!
+//! 白标模式配置了 `model_alias` 时，把返回客户端的响应体中的模型名改写为别名，避免暴露上游模型：
+//! - JSON 响应缓存到流结束后整体改写；
+//! - SSE 响应按行改写 `data:` 事件，不完整的行留到下一个分块。
+//!
+//! 追踪与计费使用改写前的原始响应体，不受影响。超过缓存上限时放弃改写、原样透传。
+
+use bytes::{Bytes, BytesMut};
+use serde_json::Value;
+
+/// 响应体中直接携带模型名的字段（`OpenAI`/Anthropic 的 `model`、Gemini 的 `modelVersion`）
+const MODEL_FIELDS: &[&str] = &["model", "modelVersion"];
+
+/// 模型名嵌套在子对象中的事件：Anthropic `message_start` 的 `message`、`OpenAI` Responses 的 `response`
+const NESTED_MODEL_CONTAINERS: &[&str] = &["message", "response"];
+
+/// 把 JSON 中的模型名改写为别名，返回是否修改
+pub fn apply_model_alias(value: &mut Value, alias: &str) -> bool {
+    let Some(object) = value.as_object_mut() else {
+        return false;
+    };
+
+    let mut modified = false;
+    for field in MODEL_FIELDS {
+        if let Some(model) = object.get_mut(*field)
+            && model.is_string()
+            && model.as_str() != Some(alias)
+        {
+            *model = Value::String(alias.to_string());
+            modified = true;
+        }
+    }
+    for container in NESTED_MODEL_CONTAINERS {
+        if let Some(model) = object
+            .get_mut(*container)
+            .and_then(Value::as_object_mut)
+            .and_then(|nested| nested.get_mut("model"))
+            && model.is_string()
+            && model.as_str() != Some(alias)
+        {
+            *model = Value::String(alias.to_string());
+            modified = true;
+        }
+    }
+    modified
+}
+
+/// 逐块改写响应体中的模型名
+#[derive(Debug)]
+pub struct ModelAliasRewriter {
+    alias: String,
+    is_sse: bool,
+    pending: BytesMut,
+    limit: usize,
+    overflowed: bool,
+}
+
+impl ModelAliasRewriter {
+    /// 默认缓存上限：非流式 JSON 响应需要完整缓存，SSE 只缓存未结束的一行
+    pub const DEFAULT_LIMIT: usize = 32 * 1024 * 1024;
+
+    #[must_use]
+    pub fn new(alias: impl Into<String>, is_sse: bool) -> Self {
+        Self {
+            alias: alias.into(),
+            is_sse,
+            pending: BytesMut::new(),
+            limit: Self::DEFAULT_LIMIT,
+            overflowed: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// 推入一个分块（可能为空），返回本次应发送给客户端的内容；`None` 表示暂不发送
+    pub fn push(&mut self, chunk: Option<&Bytes>, end_of_stream: bool) -> Option<Bytes> {
+        if self.overflowed {
+            return chunk.cloned();
+        }
+        if let Some(chunk) = chunk {
+            self.pending.extend_from_slice(chunk);
+        }
+
+        let ready = if end_of_stream {
+            self.pending.split()
+        } else if self.is_sse
+            && let Some(last_newline) = self.pending.iter().rposition(|b| *b == b'\n')
+        {
+            self.pending.split_to(last_newline + 1)
+        } else if self.pending.len() > self.limit {
+            // 超过上限：已缓存的内容原样发出，后续分块透传
+            self.overflowed = true;
+            return Some(self.pending.split().freeze());
+        } else {
+            return None;
+        };
+
+        if ready.is_empty() {
+            return None;
+        }
+        let rewritten = if self.is_sse {
+            self.rewrite_sse_lines(&ready)
+        } else {
+            self.rewrite_json(&ready)
+        };
+        Some(rewritten.unwrap_or_else(|| ready.freeze()))
+    }
+
+    /// 改写完整的 JSON 响应体，不是 JSON 或无需改写时返回 `None`
+    fn rewrite_json(&self, body: &[u8]) -> Option<Bytes> {
+        let mut value: Value = serde_json::from_slice(body).ok()?;
+        if !apply_model_alias(&mut value, &self.alias) {
+            return None;
+        }
+        serde_json::to_vec(&value).ok().map(Bytes::from)
+    }
+
+    /// 逐行改写 SSE 的 `data:` 事件，没有任何行被改写时返回 `None`
+    fn rewrite_sse_lines(&self, lines: &[u8]) -> Option<Bytes> {
+        let mut out = BytesMut::with_capacity(lines.len());
+        let mut modified = false;
+        for line in lines.split_inclusive(|b| *b == b'\n') {
+            match self.rewrite_sse_line(line) {
+                Some(rewritten) => {
+                    out.extend_from_slice(&rewritten);
+                    modified = true;
+                }
+                None => out.extend_from_slice(line),
+            }
+        }
+        modified.then(|| out.freeze())
+    }
+
+    fn rewrite_sse_line(&self, line: &[u8]) -> Option<Vec<u8>> {
+        let content_len = line
+            .iter()
+            .rposition(|b| *b != b'\n' && *b != b'\r')
+            .map_or(0, |pos| pos + 1);
+        let (content, terminator) = line.split_at(content_len);
+        let payload = content.strip_prefix(b"data:")?;
+        let mut value: Value = serde_json::from_slice(payload.trim_ascii_start()).ok()?;
+        if !apply_model_alias(&mut value, &self.alias) {
+            return None;
+        }
+
+        let mut rewritten = b"data: ".to_vec();
+        serde_json::to_writer(&mut rewritten, &value).ok()?;
+        rewritten.extend_from_slice(terminator);
+        Some(rewritten)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn feed(rewriter: &mut ModelAliasRewriter, chunks: &[&'static str]) -> String {
+        let last = chunks.len() - 1;
+        let mut out = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            if let Some(bytes) =
+                rewriter.push(Some(&Bytes::from_static(chunk.as_bytes())), i == last)
+            {
+                out.extend_from_slice(&bytes);
+            }
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn json_response_model_is_replaced_with_alias() {
+        let mut rewriter = ModelAliasRewriter::new("acme-chat", false);
+        let body = feed(
+            &mut rewriter,
+            &[
+                r#"{"id":"chatcmpl-1","model":"gpt-4o-2024"#,
+                r#"-08-06","choices":[]}"#,
+            ],
+        );
+
+        let value: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value["model"], "acme-chat");
+        assert_eq!(value["id"], "chatcmpl-1");
+    }
+
+    #[test]
+    fn sse_events_are_rewritten_across_chunk_boundaries() {
+        let mut rewriter = ModelAliasRewriter::new("acme-chat", true);
+        let body = feed(
+            &mut rewriter,
+            &[
+                "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"model\":\"claude-",
+                "3-5-sonnet\"}}\n\ndata: {\"model\":\"claude-3-5-sonnet\",\"delta\":\"hi\"}\r\n\r\n",
+                "data: [DONE]\n\n",
+            ],
+        );
+
+        let lines: Vec<&str> = body.split('\n').collect();
+        assert_eq!(lines[0], "event: message_start");
+        let start: Value = serde_json::from_str(lines[1].strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(start["message"]["model"], "acme-chat");
+        let delta = lines[3].strip_prefix("data: ").unwrap();
+        assert!(delta.ends_with('\r'));
+        let delta: Value = serde_json::from_str(delta.trim_end()).unwrap();
+        assert_eq!(delta["model"], "acme-chat");
+        assert_eq!(delta["delta"], "hi");
+        assert!(!body.contains("claude"));
+        assert!(body.ends_with("\r\n\r\ndata: [DONE]\n\n"));
+    }
+
+    #[test]
+    fn non_json_and_oversized_bodies_pass_through() {
+        let mut rewriter = ModelAliasRewriter::new("acme-chat", false);
+        assert_eq!(feed(&mut rewriter, &["plain ", "text"]), "plain text");
+
+        let mut rewriter = ModelAliasRewriter::new("acme-chat", false).with_limit(8);
+        let body = feed(&mut rewriter, &[r#"{"model":"#, r#""gpt-4o"}"#]);
+        assert_eq!(body, r#"{"model":"gpt-4o"}"#);
+    }
+
+    #[test]
+    fn gemini_model_version_is_rewritten() {
+        let mut value = json!({"candidates": [], "modelVersion": "gemini-1.5-pro-002"});
+        assert!(apply_model_alias(&mut value, "acme-chat"));
+        assert_eq!(value["modelVersion"], "acme-chat");
+        assert!(!apply_model_alias(&mut json!({"usage": {}}), "acme-chat"));
+    }
+}
@@ -0,0 +1,431 @@
+//! # 模型可用性预检
+//!
+//! 按服务商密钥缓存 `/models` 返回的模型列表（同一服务商的不同密钥可能属于不同账户、
+//! 开通的模型不同），转发前用本次选中密钥的列表校验请求的模型是否存在：
+//! - 模型不存在时由调用方直接返回 404，并附带名称相近的模型；
+//! - 列表过期后先继续使用旧列表，同时在后台刷新，不阻塞请求；
+//! - 拉取失败或列表为空时放行请求（无法确认的模型交给上游判断）。
+
+use crate::config::ModelCheckConfig;
+use crate::error::{Result, provider::ProviderError};
+use crate::logging::{LogComponent, LogStage};
+use crate::proxy::context::{ProxyContext, ResolvedCredential};
+use crate::proxy::provider_strategy::{ProviderType, direct_base_url};
+use crate::{linfo, lwarn};
+use async_trait::async_trait;
+use dashmap::{DashMap, DashSet};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// 拉取模型列表所需的服务商信息
+#[derive(Debug, Clone)]
+pub struct ModelListTarget {
+    /// 本次请求选中的服务商密钥，模型列表按密钥缓存
+    pub provider_key_id: i32,
+    pub provider: ProviderType,
+    pub name: String,
+    pub base_url: String,
+    pub api_key: String,
+}
+
+impl ModelListTarget {
+    /// 从请求上下文构造；OAuth 凭证的模型范围由账户决定，不做预检
+    #[must_use]
+    pub fn from_context(ctx: &ProxyContext) -> Option<Self> {
+        let provider_type = ctx.routing.provider_type.as_ref()?;
+        let provider_key = ctx.routing.selected_backend.as_ref()?;
+        let Some(ResolvedCredential::ApiKey(api_key)) = &ctx.routing.resolved_credential else {
+            return None;
+        };
+        Some(Self {
+            provider_key_id: provider_key.id,
+            provider: ProviderType::from_str(&provider_type.name)?,
+            name: provider_type.name.clone(),
+            base_url: provider_type.base_url.clone(),
+            api_key: api_key.clone(),
+        })
+    }
+}
+
+/// 模型列表拉取实现
+#[async_trait]
+pub trait ModelListFetcher: Send + Sync {
+    /// 拉取服务商当前可用的模型名称
+    async fn fetch(&self, target: &ModelListTarget) -> Result<Vec<String>>;
+}
+
+/// 通过服务商 `/models` 接口拉取模型列表
+pub struct HttpModelListFetcher {
+    client: reqwest::Client,
+    config: ModelCheckConfig,
+}
+
+impl HttpModelListFetcher {
+    #[must_use]
+    pub const fn new(client: reqwest::Client, config: ModelCheckConfig) -> Self {
+        Self { client, config }
+    }
+}
+
+#[async_trait]
+impl ModelListFetcher for HttpModelListFetcher {
+    async fn fetch(&self, target: &ModelListTarget) -> Result<Vec<String>> {
+        let base = direct_base_url(&target.base_url);
+        let url = match target.provider {
+            ProviderType::OpenAI => format!("{base}/v1/models"),
+            ProviderType::Anthropic => format!("{base}/v1/models?limit=1000"),
+            ProviderType::Gemini => format!("{base}/v1beta/models?pageSize=1000"),
+        };
+        let request = target
+            .provider
+            .authorize_direct_request(self.client.get(url), &target.api_key);
+
+        let response = request.timeout(self.config.timeout()).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ProviderError::ApiError {
+                provider: target.name.clone(),
+                status: status.as_u16(),
+                message: "failed to list models".to_string(),
+            }
+            .into());
+        }
+
+        let body: Value = response.json().await?;
+        Ok(parse_model_list(target.provider, &body))
+    }
+}
+
+/// 解析 `/models` 响应：OpenAI/Anthropic 为 `data[].id`，Gemini 为 `models[].name`
+fn parse_model_list(provider: ProviderType, body: &Value) -> Vec<String> {
+    let (items, field) = match provider {
+        ProviderType::OpenAI | ProviderType::Anthropic => (body.get("data"), "id"),
+        ProviderType::Gemini => (body.get("models"), "name"),
+    };
+    items
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.get(field).and_then(Value::as_str))
+                .map(|name| normalize_model(name).to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Gemini 模型名可能带 `models/` 前缀，比较前统一去掉
+fn normalize_model(name: &str) -> &str {
+    name.strip_prefix("models/").unwrap_or(name)
+}
+
+/// 预检结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelCheckOutcome {
+    /// 模型在服务商列表中
+    Available,
+    /// 模型不在服务商列表中，附带名称相近的模型
+    Unknown { suggestions: Vec<String> },
+    /// 未启用或无法获取模型列表，放行请求
+    Unverified,
+}
+
+struct CachedModels {
+    /// 最近一次拉取结果；`None` 表示拉取失败
+    models: Option<Arc<Vec<String>>>,
+    fetched_at: Instant,
+}
+
+/// 模型可用性预检服务
+pub struct ModelAvailabilityService {
+    config: ModelCheckConfig,
+    fetcher: Arc<dyn ModelListFetcher>,
+    entries: Arc<DashMap<i32, CachedModels>>,
+    refreshing: Arc<DashSet<i32>>,
+}
+
+impl ModelAvailabilityService {
+    #[must_use]
+    pub fn new(config: ModelCheckConfig, fetcher: Arc<dyn ModelListFetcher>) -> Self {
+        Self {
+            config,
+            fetcher,
+            entries: Arc::new(DashMap::new()),
+            refreshing: Arc::new(DashSet::new()),
+        }
+    }
+
+    /// 是否启用预检
+    #[must_use]
+    pub const fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 校验请求的模型是否在选中密钥可用的模型列表中
+    pub async fn check(&self, target: &ModelListTarget, model: &str) -> ModelCheckOutcome {
+        if !self.config.enabled {
+            return ModelCheckOutcome::Unverified;
+        }
+
+        let cached = self.entries.get(&target.provider_key_id).map(|entry| {
+            (
+                entry.models.clone(),
+                entry.fetched_at.elapsed() < self.config.refresh_interval(),
+            )
+        });
+        let models = match cached {
+            Some((models, fresh)) => {
+                if !fresh {
+                    self.spawn_refresh(target.clone());
+                }
+                models
+            }
+            None => Self::refresh(&self.fetcher, &self.entries, target).await,
+        };
+
+        match models {
+            Some(models) if !models.is_empty() => {
+                Self::evaluate(&models, model, self.config.max_suggestions)
+            }
+            _ => ModelCheckOutcome::Unverified,
+        }
+    }
+
+    /// 在已知模型列表中查找请求的模型，不存在时给出相近模型
+    #[must_use]
+    pub fn evaluate(models: &[String], model: &str, max_suggestions: usize) -> ModelCheckOutcome {
+        let requested = normalize_model(model);
+        if models.iter().any(|known| known == requested) {
+            return ModelCheckOutcome::Available;
+        }
+
+        let requested_lower = requested.to_ascii_lowercase();
+        let mut scored: Vec<(f64, &String)> = models
+            .iter()
+            .filter_map(|known| {
+                let known_lower = known.to_ascii_lowercase();
+                let score = strsim::jaro_winkler(&requested_lower, &known_lower);
+                let related = known_lower.contains(&requested_lower)
+                    || requested_lower.contains(&known_lower);
+                (related || score >= 0.8).then_some((score, known))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+        ModelCheckOutcome::Unknown {
+            suggestions: scored
+                .into_iter()
+                .take(max_suggestions)
+                .map(|(_, known)| known.clone())
+                .collect(),
+        }
+    }
+
+    /// 列表过期时在后台刷新；同一密钥同时只有一个刷新任务
+    fn spawn_refresh(&self, target: ModelListTarget) {
+        if !self.refreshing.insert(target.provider_key_id) {
+            return;
+        }
+        let fetcher = Arc::clone(&self.fetcher);
+        let entries = Arc::clone(&self.entries);
+        let refreshing = Arc::clone(&self.refreshing);
+        tokio::spawn(async move {
+            Self::refresh(&fetcher, &entries, &target).await;
+            refreshing.remove(&target.provider_key_id);
+        });
+    }
+
+    /// 拉取并缓存模型列表；失败时保留旧列表，并在下一个刷新周期重试
+    async fn refresh(
+        fetcher: &Arc<dyn ModelListFetcher>,
+        entries: &DashMap<i32, CachedModels>,
+        target: &ModelListTarget,
+    ) -> Option<Arc<Vec<String>>> {
+        let models = match fetcher.fetch(target).await {
+            Ok(models) => {
+                linfo!(
+                    "system",
+                    LogStage::ExternalApi,
+                    LogComponent::Upstream,
+                    "model_list_refreshed",
+                    "服务商模型列表已刷新",
+                    provider = %target.name,
+                    provider_key_id = target.provider_key_id,
+                    model_count = models.len()
+                );
+                Some(Arc::new(models))
+            }
+            Err(err) => {
+                lwarn!(
+                    "system",
+                    LogStage::ExternalApi,
+                    LogComponent::Upstream,
+                    "model_list_refresh_failed",
+                    "拉取服务商模型列表失败，暂不校验该密钥的模型",
+                    provider = %target.name,
+                    provider_key_id = target.provider_key_id,
+                    error = %err
+                );
+                entries
+                    .get(&target.provider_key_id)
+                    .and_then(|entry| entry.models.clone())
+            }
+        };
+
+        entries.insert(
+            target.provider_key_id,
+            CachedModels {
+                models: models.clone(),
+                fetched_at: Instant::now(),
+            },
+        );
+        models
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct StaticFetcher {
+        models: Vec<String>,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ModelListFetcher for StaticFetcher {
+        async fn fetch(&self, _target: &ModelListTarget) -> Result<Vec<String>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.models.clone())
+        }
+    }
+
+    /// 按密钥返回不同的模型列表
+    struct PerKeyFetcher;
+
+    #[async_trait]
+    impl ModelListFetcher for PerKeyFetcher {
+        async fn fetch(&self, target: &ModelListTarget) -> Result<Vec<String>> {
+            Ok(match target.api_key.as_str() {
+                "sk-premium" => vec!["gpt-4o".to_string(), "o3".to_string()],
+                _ => vec!["gpt-4o".to_string()],
+            })
+        }
+    }
+
+    fn service(models: &[&str]) -> (ModelAvailabilityService, Arc<StaticFetcher>) {
+        let fetcher = Arc::new(StaticFetcher {
+            models: models.iter().map(ToString::to_string).collect(),
+            calls: AtomicUsize::new(0),
+        });
+        let config = ModelCheckConfig {
+            enabled: true,
+            ..ModelCheckConfig::default()
+        };
+        (
+            ModelAvailabilityService::new(config, fetcher.clone()),
+            fetcher,
+        )
+    }
+
+    fn target(provider: ProviderType) -> ModelListTarget {
+        ModelListTarget {
+            provider_key_id: 1,
+            provider,
+            name: "openai".to_string(),
+            base_url: "api.openai.com".to_string(),
+            api_key: "sk-test".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn known_model_passes() {
+        let (service, fetcher) = service(&["gpt-4o", "gpt-4o-mini", "o3-mini"]);
+        let target = target(ProviderType::OpenAI);
+
+        assert_eq!(
+            service.check(&target, "gpt-4o-mini").await,
+            ModelCheckOutcome::Available
+        );
+        assert_eq!(
+            service.check(&target, "gpt-4o").await,
+            ModelCheckOutcome::Available
+        );
+        // 列表在刷新间隔内复用缓存
+        assert_eq!(fetcher.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_model_rejected_with_suggestions() {
+        let (service, _) = service(&["gpt-4o", "gpt-4o-mini", "o3-mini", "text-embedding-3-small"]);
+
+        let ModelCheckOutcome::Unknown { suggestions } = service
+            .check(&target(ProviderType::OpenAI), "gpt-4o-mni")
+            .await
+        else {
+            panic!("typo model should be rejected");
+        };
+        assert_eq!(suggestions.first().map(String::as_str), Some("gpt-4o-mini"));
+        assert!(!suggestions.contains(&"text-embedding-3-small".to_string()));
+    }
+
+    #[tokio::test]
+    async fn model_lists_are_cached_per_provider_key() {
+        let config = ModelCheckConfig {
+            enabled: true,
+            ..ModelCheckConfig::default()
+        };
+        let service = ModelAvailabilityService::new(config, Arc::new(PerKeyFetcher));
+        let premium = ModelListTarget {
+            provider_key_id: 2,
+            api_key: "sk-premium".to_string(),
+            ..target(ProviderType::OpenAI)
+        };
+
+        assert_eq!(
+            service.check(&premium, "o3").await,
+            ModelCheckOutcome::Available
+        );
+        // 同一服务商的另一个密钥不会复用前一个密钥的列表
+        assert!(matches!(
+            service.check(&target(ProviderType::OpenAI), "o3").await,
+            ModelCheckOutcome::Unknown { .. }
+        ));
+        assert_eq!(
+            service.check(&premium, "o3").await,
+            ModelCheckOutcome::Available
+        );
+    }
+
+    #[test]
+    fn gemini_model_names_are_normalized() {
+        let body = serde_json::json!({
+            "models": [{"name": "models/gemini-2.0-flash"}, {"name": "models/gemini-1.5-pro"}]
+        });
+        let models = parse_model_list(ProviderType::Gemini, &body);
+        assert_eq!(models, vec!["gemini-2.0-flash", "gemini-1.5-pro"]);
+        assert_eq!(
+            ModelAvailabilityService::evaluate(&models, "models/gemini-2.0-flash", 5),
+            ModelCheckOutcome::Available
+        );
+    }
+
+    #[tokio::test]
+    async fn disabled_check_is_unverified() {
+        let fetcher = Arc::new(StaticFetcher {
+            models: vec!["gpt-4o".to_string()],
+            calls: AtomicUsize::new(0),
+        });
+        let service = ModelAvailabilityService::new(ModelCheckConfig::default(), fetcher.clone());
+
+        assert_eq!(
+            service
+                .check(&target(ProviderType::OpenAI), "unknown")
+                .await,
+            ModelCheckOutcome::Unverified
+        );
+        assert_eq!(fetcher.calls.load(Ordering::SeqCst), 0);
+    }
+}
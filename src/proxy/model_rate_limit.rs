@@ -0,0 +1,120 @@
+//! # 模型级速率限制
+//!
+//! 服务商通过 `config_json.model_rate_limits` 为单个模型（或以 `*` 结尾的模型前缀）设置每分钟请求上限，
+//! 计数按“服务商 + 模型”维度共享，独立于用户级、密钥级与提供商全局限流。
+//! 模型名需要从请求体解析，因此在请求体阶段、转发上游之前检查。
+
+use crate::error::Result;
+use crate::error::conversion::ConversionError;
+use entity::provider_types;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// 单个模型的限流规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelRateLimit {
+    /// 每分钟请求上限
+    pub requests_per_minute: u32,
+}
+
+/// 服务商的模型级限流配置（`config_json.model_rate_limits`），键为模型名或模型前缀
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct ModelRateLimits {
+    rules: BTreeMap<String, ModelRateLimit>,
+}
+
+#[derive(Deserialize)]
+struct ProviderModelRateLimitConfig {
+    model_rate_limits: Option<ModelRateLimits>,
+}
+
+impl ModelRateLimits {
+    /// 严格解析服务商 `config_json.model_rate_limits`
+    pub fn parse(value: &Value) -> Result<Self> {
+        let limits: Self = serde_json::from_value(value.clone()).map_err(|err| {
+            ConversionError::message(format!(
+                "model_rate_limits 必须是 {{\"模型名\": {{\"requests_per_minute\": 正整数}}}}: {err}"
+            ))
+        })?;
+        for (pattern, limit) in &limits.rules {
+            if pattern
+                .strip_suffix('*')
+                .unwrap_or(pattern)
+                .trim()
+                .is_empty()
+            {
+                return Err(ConversionError::message("model_rate_limits 的模型名不能为空").into());
+            }
+            if limit.requests_per_minute == 0 {
+                return Err(ConversionError::message(format!(
+                    "model_rate_limits.{pattern}.requests_per_minute 必须大于 0"
+                ))
+                .into());
+            }
+        }
+        Ok(limits)
+    }
+
+    /// 读取服务商配置；未配置、配置为空或无法解析时返回 `None`
+    #[must_use]
+    pub fn from_provider(provider: &provider_types::Model) -> Option<Self> {
+        provider
+            .config_json
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<ProviderModelRateLimitConfig>(raw).ok())
+            .and_then(|config| config.model_rate_limits)
+            .filter(|limits| !limits.rules.is_empty())
+    }
+
+    /// 查找模型适用的每分钟上限：精确匹配优先，其次取最长的前缀匹配；上限为 0 的规则视为未配置
+    #[must_use]
+    pub fn limit_for(&self, model: &str) -> Option<u32> {
+        if let Some(limit) = self.rules.get(model) {
+            return Some(limit.requests_per_minute).filter(|rpm| *rpm > 0);
+        }
+        self.rules
+            .iter()
+            .filter_map(|(pattern, limit)| {
+                let prefix = pattern.strip_suffix('*')?;
+                model.starts_with(prefix).then_some((prefix.len(), limit))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, limit)| limit.requests_per_minute)
+            .filter(|rpm| *rpm > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn limits(value: &Value) -> ModelRateLimits {
+        ModelRateLimits::parse(value).expect("valid model_rate_limits")
+    }
+
+    #[test]
+    fn exact_match_wins_over_longest_prefix() {
+        let limits = limits(&json!({
+            "gpt-4*": {"requests_per_minute": 20},
+            "gpt-4o*": {"requests_per_minute": 10},
+            "gpt-4o": {"requests_per_minute": 60}
+        }));
+        assert_eq!(limits.limit_for("gpt-4o"), Some(60));
+        assert_eq!(limits.limit_for("gpt-4o-mini"), Some(10));
+        assert_eq!(limits.limit_for("gpt-4-turbo"), Some(20));
+        assert_eq!(limits.limit_for("gpt-3.5-turbo"), None);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_limits() {
+        assert!(ModelRateLimits::parse(&json!({"gpt-4o": {"requests_per_minute": 0}})).is_err());
+        assert!(ModelRateLimits::parse(&json!({"gpt-4o": {"requests_per_minute": -1}})).is_err());
+        assert!(ModelRateLimits::parse(&json!({"*": {"requests_per_minute": 5}})).is_err());
+        assert!(ModelRateLimits::parse(&json!({"gpt-4o": 5})).is_err());
+        assert!(ModelRateLimits::parse(&json!([])).is_err());
+    }
+}
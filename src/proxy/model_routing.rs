@@ -0,0 +1,205 @@
+//! # 按模型路由
+//!
+//! 服务 API 可配置 `model_routes`（JSON 数组），按请求的模型把请求路由到不同的服务商与密钥池，
+//! 例如 `claude-*` 走 Anthropic、`gpt-*` 走 `OpenAI`，客户端只需一个服务 API 密钥。
+//! 规则按顺序匹配，第一条命中的规则生效；模式为完整模型名或以 `*` 结尾的前缀。没有规则命中、
+//! 或无法得知请求的模型时使用服务 API 默认的 `provider_type_id`。
+//!
+//! 路由在认证阶段选择密钥前完成。模型不在路径或查询参数中时需要预读请求体：只预读声明了
+//! `Content-Length` 且不超过 Pingora 重放缓冲上限的请求体，预读后由重放缓冲照常转发；
+//! 更大或分块传输的请求体不预读，按未命中处理。
+
+use crate::error::Result;
+use crate::error::conversion::ConversionError;
+use crate::types::ProviderTypeId;
+use bytes::{Bytes, BytesMut};
+use entity::user_service_apis;
+use pingora_proxy::Session;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 单个服务 API 允许配置的路由规则上限
+const MAX_MODEL_ROUTES: usize = 32;
+
+/// 预读请求体的上限，与 Pingora 重放缓冲上限一致（超过后预读的内容无法重放给上游）
+pub const MAX_ROUTING_BODY_BYTES: usize = 64 * 1024;
+
+/// 单条路由规则
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelRoute {
+    /// 模型名，或以 `*` 结尾的模型前缀
+    pub pattern: String,
+    /// 命中后使用的服务商
+    pub provider_type_id: ProviderTypeId,
+    /// 命中后参与调度的密钥；未配置时使用服务 API 密钥池中属于该服务商的密钥
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_provider_keys_ids: Option<Vec<i32>>,
+}
+
+impl ModelRoute {
+    /// 模式是否匹配模型名
+    #[must_use]
+    pub fn matches(&self, model: &str) -> bool {
+        self.pattern
+            .strip_suffix('*')
+            .map_or(self.pattern == model, |prefix| model.starts_with(prefix))
+    }
+}
+
+/// 服务 API 的路由规则（按配置顺序匹配）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelRoutes {
+    routes: Vec<ModelRoute>,
+}
+
+impl ModelRoutes {
+    /// 严格解析服务 API 的 `model_routes`，去除模式两端空白
+    pub fn parse(value: &Value) -> Result<Self> {
+        let entries = value
+            .as_array()
+            .ok_or_else(|| ConversionError::message("model_routes 必须是数组"))?;
+        if entries.len() > MAX_MODEL_ROUTES {
+            return Err(ConversionError::message(format!(
+                "model_routes 最多 {MAX_MODEL_ROUTES} 条"
+            ))
+            .into());
+        }
+
+        let mut routes = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            let mut route: ModelRoute = serde_json::from_value(entry.clone()).map_err(|err| {
+                ConversionError::message(format!(
+                    "model_routes[{index}] 必须是 {{\"pattern\": 模型名, \"provider_type_id\": 服务商 ID, \"user_provider_keys_ids\": [密钥 ID]}}: {err}"
+                ))
+            })?;
+            route.pattern = route.pattern.trim().to_string();
+            let prefix = route.pattern.strip_suffix('*').unwrap_or(&route.pattern);
+            if prefix.is_empty() || prefix.contains('*') {
+                return Err(ConversionError::message(format!(
+                    "model_routes[{index}].pattern 必须是模型名或以 * 结尾的非空前缀"
+                ))
+                .into());
+            }
+            if route.provider_type_id <= 0 {
+                return Err(ConversionError::message(format!(
+                    "model_routes[{index}].provider_type_id 必须为正数"
+                ))
+                .into());
+            }
+            if route
+                .user_provider_keys_ids
+                .as_ref()
+                .is_some_and(Vec::is_empty)
+            {
+                return Err(ConversionError::message(format!(
+                    "model_routes[{index}].user_provider_keys_ids 不能为空数组，使用默认密钥池时省略该字段"
+                ))
+                .into());
+            }
+            routes.push(route);
+        }
+        Ok(Self { routes })
+    }
+
+    /// 读取服务 API 的路由规则；未配置、为空或无法解析时返回 `None`
+    #[must_use]
+    pub fn of(api: &user_service_apis::Model) -> Option<Self> {
+        api.model_routes
+            .as_ref()
+            .and_then(|value| Self::parse(value).ok())
+            .filter(|routes| !routes.routes.is_empty())
+    }
+
+    /// 第一条匹配模型的规则
+    #[must_use]
+    pub fn route_for(&self, model: &str) -> Option<&ModelRoute> {
+        self.routes.iter().find(|route| route.matches(model))
+    }
+
+    #[must_use]
+    pub fn routes(&self) -> &[ModelRoute] {
+        &self.routes
+    }
+}
+
+/// 预读请求体用于确定模型（SSE keepalive 判定流式请求时同样使用）
+///
+/// 只在声明了不超过 [`MAX_ROUTING_BODY_BYTES`] 的 `Content-Length` 时预读，否则返回 `None`、不消费请求体。
+/// 预读前开启重放缓冲，上游请求照常发送完整请求体。
+pub async fn read_routing_body(session: &mut Session) -> Result<Option<Bytes>> {
+    let declared = session
+        .req_header()
+        .headers
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<usize>().ok());
+    let Some(declared) = declared.filter(|len| (1..=MAX_ROUTING_BODY_BYTES).contains(len)) else {
+        return Ok(None);
+    };
+
+    session.enable_retry_buffering();
+    let mut body = BytesMut::with_capacity(declared);
+    while let Some(chunk) = session.read_request_body().await? {
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Some(body.freeze()))
+}
+
+/// 从 JSON 请求体中取出 `model`
+#[must_use]
+pub fn body_model(body: &[u8]) -> Option<String> {
+    serde_json::from_slice::<Value>(body)
+        .ok()?
+        .get("model")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|model| !model.is_empty())
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn first_matching_route_wins() {
+        let routes = ModelRoutes::parse(&json!([
+            {"pattern": "claude-3-opus", "provider_type_id": 3, "user_provider_keys_ids": [7]},
+            {"pattern": "claude-*", "provider_type_id": 2},
+            {"pattern": "gpt-*", "provider_type_id": 1}
+        ]))
+        .expect("valid model_routes");
+
+        let route = |model: &str| routes.route_for(model).map(|route| route.provider_type_id);
+        assert_eq!(route("claude-3-opus"), Some(3));
+        assert_eq!(route("claude-3-5-sonnet"), Some(2));
+        assert_eq!(route("gpt-4o"), Some(1));
+        assert_eq!(route("gemini-1.5-pro"), None);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_routes() {
+        for invalid in [
+            json!({"pattern": "gpt-*", "provider_type_id": 1}),
+            json!([{"pattern": "*", "provider_type_id": 1}]),
+            json!([{"pattern": "gpt-*-mini", "provider_type_id": 1}]),
+            json!([{"pattern": "gpt-4o", "provider_type_id": 0}]),
+            json!([{"pattern": "gpt-4o", "provider_type_id": 1, "user_provider_keys_ids": []}]),
+            json!([{"pattern": "gpt-4o", "provider": "openai"}]),
+        ] {
+            assert!(ModelRoutes::parse(&invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn extracts_model_from_body() {
+        assert_eq!(
+            body_model(br#"{"model": "gpt-4o", "messages": []}"#).as_deref(),
+            Some("gpt-4o")
+        );
+        assert_eq!(body_model(br#"{"messages": []}"#), None);
+        assert_eq!(body_model(b"not json"), None);
+    }
+}
@@ -0,0 +1,219 @@
+//! # 流式策略
+//!
+//! 服务 API 通过 `stream_policy` 控制是否允许流式请求：
+//! - `allow`：原样转发（默认）；
+//! - `deny`：客户端请求流式时直接返回 400；
+//! - `force-off`：无论客户端是否请求流式，转发上游前都改为非流式，便于排查问题和按完整响应准确计费。
+//!
+//! 流式请求的识别与改写：
+//! - 请求体中的 `stream: true` 改为 `false`，并移除仅流式可用的参数（如 `OpenAI` 的 `stream_options`）；
+//! - Gemini 通过路径区分流式，`:streamGenerateContent` 改写为 `:generateContent`，并去掉查询参数 `alt=sse`。
+
+use crate::error::{self, conversion::ConversionError};
+use crate::proxy::ProxyContext;
+use entity::user_service_apis;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// 仅在流式请求中有效、关闭流式后需要移除的请求体参数
+const STREAMING_ONLY_PARAMS: &[&str] = &["stream_options"];
+
+/// Gemini 流式生成动作
+const GEMINI_STREAM_ACTION: &str = ":streamGenerateContent";
+
+/// Gemini 非流式生成动作
+const GEMINI_GENERATE_ACTION: &str = ":generateContent";
+
+/// 服务 API 的流式策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamPolicy {
+    /// 允许流式请求
+    #[default]
+    Allow,
+    /// 拒绝流式请求
+    Deny,
+    /// 强制改为非流式
+    ForceOff,
+}
+
+impl FromStr for StreamPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "allow" => Ok(Self::Allow),
+            "deny" => Ok(Self::Deny),
+            "force-off" | "force_off" => Ok(Self::ForceOff),
+            _ => Err(format!("Unknown stream policy: {s}")),
+        }
+    }
+}
+
+impl StreamPolicy {
+    /// 所有流式策略
+    pub const ALL: [Self; 3] = [Self::Allow, Self::Deny, Self::ForceOff];
+
+    /// 写入前校验策略名称，未知名称返回列出可选值的错误
+    pub fn parse_known(s: &str) -> error::Result<Self> {
+        s.parse().map_err(|_| {
+            let known: Vec<&str> = Self::ALL.into_iter().map(Self::as_str).collect();
+            ConversionError::message(format!("未知的流式策略: {s}，可选值: {}", known.join(", ")))
+                .into()
+        })
+    }
+
+    /// 转换为字符串
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+            Self::ForceOff => "force-off",
+        }
+    }
+
+    /// 服务 API 生效的流式策略，无法识别的取值按 `allow` 处理
+    #[must_use]
+    pub fn of(api: &user_service_apis::Model) -> Self {
+        api.stream_policy.parse().unwrap_or_default()
+    }
+
+    /// 当前请求所属服务 API 的流式策略
+    #[must_use]
+    pub fn from_context(ctx: &ProxyContext) -> Self {
+        ctx.routing
+            .user_service_api
+            .as_ref()
+            .map_or(Self::Allow, Self::of)
+    }
+}
+
+/// 当前请求所属的服务 API 是否强制非流式
+#[must_use]
+pub fn is_forced(ctx: &ProxyContext) -> bool {
+    StreamPolicy::from_context(ctx) == StreamPolicy::ForceOff
+}
+
+/// 当前请求所属的服务 API 是否拒绝流式请求
+#[must_use]
+pub fn is_denied(ctx: &ProxyContext) -> bool {
+    StreamPolicy::from_context(ctx) == StreamPolicy::Deny
+}
+
+/// 请求是否为流式：请求体 `stream: true`，或 Gemini 流式生成路径
+#[must_use]
+pub fn requests_streaming(path: &str, body: &Value) -> bool {
+    path.ends_with(GEMINI_STREAM_ACTION) || body.get("stream") == Some(&Value::Bool(true))
+}
+
+/// 关闭请求体中的流式开关并移除仅流式可用的参数，返回是否修改了请求体
+pub fn disable_body_streaming(json_value: &mut Value) -> bool {
+    let Some(body) = json_value.as_object_mut() else {
+        return false;
+    };
+
+    let mut modified = false;
+    if let Some(stream) = body.get_mut("stream")
+        && *stream != Value::Bool(false)
+    {
+        *stream = Value::Bool(false);
+        modified = true;
+    }
+    for param in STREAMING_ONLY_PARAMS {
+        modified |= body.remove(*param).is_some();
+    }
+    modified
+}
+
+/// 将 Gemini 流式路径改写为非流式路径（含查询参数），不是流式路径时返回 `None`
+#[must_use]
+pub fn non_streaming_gemini_path(path_and_query: &str) -> Option<String> {
+    let (path, query) = path_and_query
+        .split_once('?')
+        .map_or((path_and_query, None), |(path, query)| (path, Some(query)));
+    let action_base = path.strip_suffix(GEMINI_STREAM_ACTION)?;
+
+    let mut rewritten = format!("{action_base}{GEMINI_GENERATE_ACTION}");
+    let remaining: Vec<&str> = query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter(|param| !param.is_empty() && !param.eq_ignore_ascii_case("alt=sse"))
+        .collect();
+    if !remaining.is_empty() {
+        rewritten.push('?');
+        rewritten.push_str(&remaining.join("&"));
+    }
+    Some(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn openai_body_streaming_is_forced_off() {
+        let mut body = json!({
+            "model": "gpt-4o",
+            "stream": true,
+            "stream_options": {"include_usage": true},
+            "messages": [{"role": "user", "content": "hi"}]
+        });
+        assert!(disable_body_streaming(&mut body));
+        assert_eq!(body["stream"], false);
+        assert!(body.get("stream_options").is_none());
+        assert_eq!(body["model"], "gpt-4o");
+
+        // 已是非流式时不再修改
+        assert!(!disable_body_streaming(&mut body));
+        let mut no_stream = json!({"model": "gpt-4o"});
+        assert!(!disable_body_streaming(&mut no_stream));
+        assert!(no_stream.get("stream").is_none());
+    }
+
+    #[test]
+    fn stream_policy_parses_known_values() {
+        for policy in StreamPolicy::ALL {
+            assert_eq!(policy.as_str().parse::<StreamPolicy>(), Ok(policy));
+        }
+        assert_eq!("FORCE_OFF".parse(), Ok(StreamPolicy::ForceOff));
+        assert!(StreamPolicy::parse_known("never").is_err());
+    }
+
+    #[test]
+    fn streaming_requests_are_detected() {
+        let path = "/v1/chat/completions";
+        assert!(requests_streaming(path, &json!({"stream": true})));
+        assert!(!requests_streaming(path, &json!({"stream": false})));
+        assert!(!requests_streaming(path, &json!({"model": "gpt-4o"})));
+        assert!(requests_streaming(
+            "/v1beta/models/gemini-2.5-pro:streamGenerateContent",
+            &Value::Null
+        ));
+        assert!(!requests_streaming(
+            "/v1beta/models/gemini-2.5-pro:generateContent",
+            &json!({"contents": []})
+        ));
+    }
+
+    #[test]
+    fn gemini_stream_path_is_rewritten() {
+        assert_eq!(
+            non_streaming_gemini_path(
+                "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+            )
+            .as_deref(),
+            Some("/v1beta/models/gemini-2.5-pro:generateContent")
+        );
+        assert_eq!(
+            non_streaming_gemini_path("/v1internal:streamGenerateContent?key=abc&alt=SSE")
+                .as_deref(),
+            Some("/v1internal:generateContent?key=abc")
+        );
+        assert_eq!(
+            non_streaming_gemini_path("/v1beta/models/gemini-2.5-pro:generateContent"),
+            None
+        );
+        assert_eq!(non_streaming_gemini_path("/v1/chat/completions"), None);
+    }
+}
@@ -0,0 +1,494 @@
+//! # 请求参数策略
+//!
+//! 按 `ParameterPolicyRule` 为请求体注入缺省参数、钳制超限参数。
+//! 规则使用通用参数名，写入时映射为各提供商的实际字段：
+//! - `OpenAI`：`temperature`、`top_p`、`max_tokens`（已使用 `max_completion_tokens` 时沿用之，Responses API 为 `max_output_tokens`）
+//! - Anthropic：`temperature`、`top_p`、`max_tokens`
+//! - Gemini：`generationConfig.temperature`、`generationConfig.topP`、`generationConfig.maxOutputTokens`
+//!
+//! 服务商还可通过 `config_json.max_tokens_ceiling` 设置输出 Token 上限，作用于该服务商的所有生成请求，
+//! 在参数策略之后执行，调整同样记录到追踪记录。
+
+use crate::config::{ParameterPolicyRule, ParameterValues};
+use crate::error::Result;
+use crate::error::conversion::ConversionError;
+use crate::proxy::provider_strategy::ProviderType;
+use entity::provider_types;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 参数调整动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterAction {
+    /// 请求未携带，注入缺省值
+    Injected,
+    /// 请求值超过上限，钳制为上限
+    Clamped,
+}
+
+/// 单个参数的调整记录，随追踪记录落库
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParameterAdjustment {
+    /// 请求体中的实际字段路径（以 `.` 分隔）
+    pub field: String,
+    pub action: ParameterAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original: Option<Value>,
+    pub applied: Value,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Parameter {
+    Temperature,
+    TopP,
+    MaxTokens,
+}
+
+impl Parameter {
+    const ALL: [Self; 3] = [Self::Temperature, Self::TopP, Self::MaxTokens];
+
+    fn value(self, values: &ParameterValues) -> Option<Value> {
+        match self {
+            Self::Temperature => values.temperature.map(Value::from),
+            Self::TopP => values.top_p.map(Value::from),
+            Self::MaxTokens => values.max_tokens.map(Value::from),
+        }
+    }
+
+    /// 映射为提供商请求体中的字段路径
+    fn field_path(self, provider: ProviderType, path: &str, body: &Value) -> Vec<&'static str> {
+        match provider {
+            ProviderType::Gemini => {
+                let name = match self {
+                    Self::Temperature => "temperature",
+                    Self::TopP => "topP",
+                    Self::MaxTokens => "maxOutputTokens",
+                };
+                // Code Assist 接口将原始请求包裹在 `request` 字段中
+                if body.get("request").is_some_and(Value::is_object) {
+                    vec!["request", "generationConfig", name]
+                } else {
+                    vec!["generationConfig", name]
+                }
+            }
+            ProviderType::Anthropic => match self {
+                Self::Temperature => vec!["temperature"],
+                Self::TopP => vec!["top_p"],
+                Self::MaxTokens => vec!["max_tokens"],
+            },
+            ProviderType::OpenAI => match self {
+                Self::Temperature => vec!["temperature"],
+                Self::TopP => vec!["top_p"],
+                Self::MaxTokens if path.contains("/responses") => vec!["max_output_tokens"],
+                Self::MaxTokens if body.get("max_completion_tokens").is_some() => {
+                    vec!["max_completion_tokens"]
+                }
+                Self::MaxTokens => vec!["max_tokens"],
+            },
+        }
+    }
+}
+
+/// 服务商级输出 Token 上限（`config_json.max_tokens_ceiling`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct MaxTokensCeiling {
+    /// 输出 Token 上限，超出时钳制
+    pub max: u64,
+    /// 请求未携带时是否注入上限值
+    #[serde(default)]
+    pub inject_when_absent: bool,
+}
+
+#[derive(Deserialize)]
+struct ProviderCeilingConfig {
+    max_tokens_ceiling: Option<MaxTokensCeiling>,
+}
+
+impl MaxTokensCeiling {
+    /// 严格解析服务商 `config_json.max_tokens_ceiling`
+    pub fn parse(value: &Value) -> Result<Self> {
+        let ceiling: Self = serde_json::from_value(value.clone()).map_err(|err| {
+            ConversionError::message(format!(
+                "max_tokens_ceiling 必须是 {{\"max\": 正整数, \"inject_when_absent\": bool}}: {err}"
+            ))
+        })?;
+        if ceiling.max == 0 {
+            return Err(ConversionError::message("max_tokens_ceiling.max 必须大于 0").into());
+        }
+        Ok(ceiling)
+    }
+
+    /// 读取服务商配置；未配置、上限为 0 或配置无法解析时返回 `None`
+    #[must_use]
+    pub fn from_provider(provider: &provider_types::Model) -> Option<Self> {
+        provider
+            .config_json
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<ProviderCeilingConfig>(raw).ok())
+            .and_then(|config| config.max_tokens_ceiling)
+            .filter(|ceiling| ceiling.max > 0)
+    }
+
+    /// 转换为只约束 `max_tokens` 的参数策略规则
+    #[must_use]
+    pub fn rule(self) -> ParameterPolicyRule {
+        ParameterPolicyRule {
+            model: None,
+            service_api_id: None,
+            defaults: ParameterValues {
+                max_tokens: self.inject_when_absent.then_some(self.max),
+                ..Default::default()
+            },
+            max: ParameterValues {
+                max_tokens: Some(self.max),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// 将上限应用到生成请求；Embeddings、计数等其他接口不受影响
+    pub fn apply(
+        self,
+        provider: ProviderType,
+        path: &str,
+        body: &mut Value,
+    ) -> Vec<ParameterAdjustment> {
+        if !is_generation_path(provider, path) {
+            return Vec::new();
+        }
+        apply_parameter_policy(&self.rule(), provider, path, body)
+    }
+}
+
+/// 是否为会产生输出 Token 的生成接口
+fn is_generation_path(provider: ProviderType, path: &str) -> bool {
+    match provider {
+        ProviderType::OpenAI => path.ends_with("/completions") || path.ends_with("/responses"),
+        ProviderType::Anthropic => path.ends_with("/messages"),
+        ProviderType::Gemini => {
+            path.ends_with(":generateContent") || path.ends_with(":streamGenerateContent")
+        }
+    }
+}
+
+/// 解析请求的模型名：优先请求体 `model` 字段，Gemini 回退到路径 `/models/{model}:action`
+#[must_use]
+pub fn requested_model(provider: ProviderType, path: &str, body: &Value) -> Option<String> {
+    if let Some(model) = body.get("model").and_then(Value::as_str) {
+        return Some(model.to_string());
+    }
+    if provider != ProviderType::Gemini {
+        return None;
+    }
+    path.split("/models/")
+        .nth(1)
+        .and_then(|rest| rest.split(':').next())
+        .filter(|model| !model.is_empty())
+        .map(ToString::to_string)
+}
+
+/// 将规则应用到请求体，返回所有实际发生的调整
+pub fn apply_parameter_policy(
+    rule: &ParameterPolicyRule,
+    provider: ProviderType,
+    path: &str,
+    body: &mut Value,
+) -> Vec<ParameterAdjustment> {
+    let mut adjustments = Vec::new();
+    if !body.is_object() {
+        return adjustments;
+    }
+
+    for parameter in Parameter::ALL {
+        let field = parameter.field_path(provider, path, body);
+        let current = get_field(body, &field)
+            .filter(|value| !value.is_null())
+            .cloned();
+
+        let adjustment = match current {
+            None => parameter
+                .value(&rule.defaults)
+                .map(|default| (ParameterAction::Injected, None, default)),
+            Some(current) => parameter
+                .value(&rule.max)
+                .filter(|max| exceeds(&current, max))
+                .map(|max| (ParameterAction::Clamped, Some(current), max)),
+        };
+
+        if let Some((action, original, applied)) = adjustment
+            && set_field(body, &field, applied.clone())
+        {
+            adjustments.push(ParameterAdjustment {
+                field: field.join("."),
+                action,
+                original,
+                applied,
+            });
+        }
+    }
+
+    adjustments
+}
+
+fn exceeds(current: &Value, max: &Value) -> bool {
+    match (current.as_f64(), max.as_f64()) {
+        (Some(current), Some(max)) => current > max,
+        _ => false,
+    }
+}
+
+fn get_field<'a>(body: &'a Value, field: &[&str]) -> Option<&'a Value> {
+    field.iter().try_fold(body, |value, key| value.get(*key))
+}
+
+fn set_field(body: &mut Value, field: &[&str], value: Value) -> bool {
+    let Some((last, parents)) = field.split_last() else {
+        return false;
+    };
+    let mut current = body;
+    for key in parents {
+        let Some(object) = current.as_object_mut() else {
+            return false;
+        };
+        current = object
+            .entry((*key).to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    let Some(object) = current.as_object_mut() else {
+        return false;
+    };
+    object.insert((*last).to_string(), value);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(defaults: ParameterValues, max: ParameterValues) -> ParameterPolicyRule {
+        ParameterPolicyRule {
+            model: None,
+            service_api_id: None,
+            defaults,
+            max,
+        }
+    }
+
+    #[test]
+    fn injects_missing_default() {
+        let rule = rule(
+            ParameterValues {
+                temperature: Some(0.3),
+                ..Default::default()
+            },
+            ParameterValues::default(),
+        );
+        let mut body = json!({"model": "gpt-4o-mini", "messages": []});
+
+        let adjustments = apply_parameter_policy(
+            &rule,
+            ProviderType::OpenAI,
+            "/v1/chat/completions",
+            &mut body,
+        );
+
+        assert_eq!(body["temperature"], json!(0.3));
+        assert_eq!(adjustments.len(), 1);
+        assert_eq!(adjustments[0].field, "temperature");
+        assert_eq!(adjustments[0].action, ParameterAction::Injected);
+        assert_eq!(adjustments[0].original, None);
+    }
+
+    #[test]
+    fn clamps_over_limit_value() {
+        let rule = rule(
+            ParameterValues::default(),
+            ParameterValues {
+                max_tokens: Some(1024),
+                ..Default::default()
+            },
+        );
+        let mut body = json!({"model": "gpt-4o", "max_completion_tokens": 8192});
+
+        let adjustments = apply_parameter_policy(
+            &rule,
+            ProviderType::OpenAI,
+            "/v1/chat/completions",
+            &mut body,
+        );
+
+        assert_eq!(body["max_completion_tokens"], json!(1024));
+        assert!(body.get("max_tokens").is_none());
+        assert_eq!(
+            adjustments,
+            vec![ParameterAdjustment {
+                field: "max_completion_tokens".to_string(),
+                action: ParameterAction::Clamped,
+                original: Some(json!(8192)),
+                applied: json!(1024),
+            }]
+        );
+    }
+
+    #[test]
+    fn maps_gemini_generation_config_fields() {
+        let rule = rule(
+            ParameterValues {
+                max_tokens: Some(512),
+                ..Default::default()
+            },
+            ParameterValues {
+                temperature: Some(1.0),
+                ..Default::default()
+            },
+        );
+        let path = "/v1beta/models/gemini-1.5-pro:generateContent";
+        let mut body = json!({"contents": [], "generationConfig": {"temperature": 1.5}});
+
+        assert_eq!(
+            requested_model(ProviderType::Gemini, path, &body).as_deref(),
+            Some("gemini-1.5-pro")
+        );
+        let adjustments = apply_parameter_policy(&rule, ProviderType::Gemini, path, &mut body);
+
+        assert_eq!(body["generationConfig"]["temperature"], json!(1.0));
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], json!(512));
+        assert_eq!(adjustments.len(), 2);
+    }
+
+    const CEILING: MaxTokensCeiling = MaxTokensCeiling {
+        max: 4096,
+        inject_when_absent: true,
+    };
+
+    #[test]
+    fn ceiling_clamps_over_limit_values_across_formats() {
+        let cases = [
+            (
+                ProviderType::OpenAI,
+                "/v1/chat/completions",
+                json!({"model": "gpt-4o", "max_tokens": 16_000}),
+                "max_tokens",
+            ),
+            (
+                ProviderType::OpenAI,
+                "/v1/responses",
+                json!({"model": "gpt-4o", "max_output_tokens": 16_000}),
+                "max_output_tokens",
+            ),
+            (
+                ProviderType::Anthropic,
+                "/v1/messages",
+                json!({"model": "claude-sonnet-4", "max_tokens": 16_000}),
+                "max_tokens",
+            ),
+            (
+                ProviderType::Gemini,
+                "/v1beta/models/gemini-2.5-pro:streamGenerateContent",
+                json!({"contents": [], "generationConfig": {"maxOutputTokens": 16_000}}),
+                "generationConfig.maxOutputTokens",
+            ),
+        ];
+
+        for (provider, path, mut body, field) in cases {
+            let adjustments = CEILING.apply(provider, path, &mut body);
+            assert_eq!(
+                adjustments,
+                vec![ParameterAdjustment {
+                    field: field.to_string(),
+                    action: ParameterAction::Clamped,
+                    original: Some(json!(16_000)),
+                    applied: json!(4096),
+                }],
+                "{path}"
+            );
+        }
+
+        // 未超过上限时保持不变
+        let mut body = json!({"model": "claude-sonnet-4", "max_tokens": 1024});
+        assert!(
+            CEILING
+                .apply(ProviderType::Anthropic, "/v1/messages", &mut body)
+                .is_empty()
+        );
+        assert_eq!(body["max_tokens"], json!(1024));
+    }
+
+    #[test]
+    fn ceiling_injects_when_absent_only_if_enabled() {
+        let mut body = json!({"model": "claude-sonnet-4", "messages": []});
+        let adjustments = CEILING.apply(ProviderType::Anthropic, "/v1/messages", &mut body);
+        assert_eq!(body["max_tokens"], json!(4096));
+        assert_eq!(adjustments[0].action, ParameterAction::Injected);
+
+        let mut body =
+            json!({"model": "gemini-2.5-pro", "project": "demo", "request": {"contents": []}});
+        CEILING.apply(
+            ProviderType::Gemini,
+            "/v1internal:generateContent",
+            &mut body,
+        );
+        assert_eq!(
+            body["request"]["generationConfig"]["maxOutputTokens"],
+            json!(4096)
+        );
+
+        let clamp_only = MaxTokensCeiling {
+            inject_when_absent: false,
+            ..CEILING
+        };
+        let mut body = json!({"model": "gpt-4o", "messages": []});
+        assert!(
+            clamp_only
+                .apply(ProviderType::OpenAI, "/v1/chat/completions", &mut body)
+                .is_empty()
+        );
+        assert!(body.get("max_tokens").is_none());
+
+        // 非生成接口不注入
+        let mut body = json!({"model": "text-embedding-3-small", "input": "hi"});
+        assert!(
+            CEILING
+                .apply(ProviderType::OpenAI, "/v1/embeddings", &mut body)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn ceiling_reads_provider_config() {
+        let now = chrono::Utc::now().naive_utc();
+        let mut provider = provider_types::Model {
+            id: 1,
+            name: "anthropic".to_string(),
+            display_name: "Anthropic".to_string(),
+            auth_type: "api_key".to_string(),
+            base_url: "https://api.anthropic.com".to_string(),
+            is_active: true,
+            config_json: Some(r#"{"max_tokens_ceiling": {"max": 4096}}"#.to_string()),
+            token_mappings_json: None,
+            model_extraction_json: None,
+            auth_configs_json: None,
+            created_at: now,
+            updated_at: now,
+        };
+        assert_eq!(
+            MaxTokensCeiling::from_provider(&provider),
+            Some(MaxTokensCeiling {
+                max: 4096,
+                inject_when_absent: false,
+            })
+        );
+
+        provider.config_json = Some(r#"{"max_tokens_ceiling": {"max": 0}}"#.to_string());
+        assert_eq!(MaxTokensCeiling::from_provider(&provider), None);
+        provider.config_json = None;
+        assert_eq!(MaxTokensCeiling::from_provider(&provider), None);
+
+        assert!(MaxTokensCeiling::parse(&json!({"max": 1024, "inject_when_absent": true})).is_ok());
+        assert!(MaxTokensCeiling::parse(&json!({"max": 0})).is_err());
+        assert!(MaxTokensCeiling::parse(&json!({"max": -1})).is_err());
+        assert!(MaxTokensCeiling::parse(&json!(4096)).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! # 请求路径白名单
+//!
+//! 服务 API 可配置允许访问的请求路径模式（JSON 字符串数组），转发上游前检查，未命中时返回 403。
+//! 模式含 `*` 时按通配符匹配（`*` 匹配任意字符序列，包括 `/`）；否则按完整路径或以 `/` 为边界的前缀匹配，
+//! 例如 `/v1/chat` 允许 `/v1/chat/completions`，但不允许 `/v1/chatbot`。未配置时不限制路径。
+
+use crate::error::Result;
+use crate::error::auth::AuthError;
+use crate::error::conversion::ConversionError;
+use crate::logging::{LogComponent, LogStage};
+use crate::lwarn;
+use serde_json::Value;
+
+/// 单个服务 API 允许配置的路径模式上限
+const MAX_ALLOWED_PATHS: usize = 32;
+
+/// 严格解析服务 API 的路径白名单（JSON 数组：以 `/` 或 `*` 开头的非空字符串）
+pub fn parse_allowed_paths(value: &Value) -> Result<Vec<String>> {
+    let entries = value
+        .as_array()
+        .ok_or_else(|| ConversionError::message("allowed_paths 必须是数组"))?;
+    if entries.len() > MAX_ALLOWED_PATHS {
+        return Err(
+            ConversionError::message(format!("allowed_paths 最多 {MAX_ALLOWED_PATHS} 项")).into(),
+        );
+    }
+
+    let mut patterns = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let pattern = entry
+            .as_str()
+            .map(str::trim)
+            .filter(|pattern| pattern.starts_with('/') || pattern.starts_with('*'))
+            .ok_or_else(|| {
+                ConversionError::message(format!(
+                    "allowed_paths[{index}]: 必须是以 / 或 * 开头的字符串"
+                ))
+            })?;
+        patterns.push(pattern.to_string());
+    }
+    Ok(patterns)
+}
+
+/// 检查请求路径是否在服务 API 的白名单内
+///
+/// 未配置白名单时放行；已保存的配置无法解析时拒绝请求，避免配置损坏时放开限制。
+pub fn ensure_path_allowed(
+    allowed_paths: Option<&Value>,
+    path: &str,
+    request_id: &str,
+) -> Result<()> {
+    let Some(value) = allowed_paths else {
+        return Ok(());
+    };
+    let patterns = match parse_allowed_paths(value) {
+        Ok(patterns) => patterns,
+        Err(err) => {
+            lwarn!(
+                request_id,
+                LogStage::Authentication,
+                LogComponent::Auth,
+                "invalid_allowed_paths",
+                "服务 API 的路径白名单配置无效，拒绝请求",
+                error = %err
+            );
+            return Err(AuthError::PathNotAllowed(path.to_string()).into());
+        }
+    };
+
+    if patterns.iter().any(|pattern| path_matches(pattern, path)) {
+        Ok(())
+    } else {
+        Err(AuthError::PathNotAllowed(path.to_string()).into())
+    }
+}
+
+/// 单个模式是否匹配请求路径
+fn path_matches(pattern: &str, path: &str) -> bool {
+    if pattern.contains('*') {
+        return glob_matches(pattern.as_bytes(), path.as_bytes());
+    }
+    let prefix = pattern.trim_end_matches('/');
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// 通配符匹配：`*` 匹配任意字符序列，遇到不匹配时回溯到上一个 `*`
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|b| *b == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ProxyError;
+    use serde_json::json;
+
+    fn check(allowed: &Value, path: &str) -> Result<()> {
+        ensure_path_allowed(Some(allowed), path, "test")
+    }
+
+    #[test]
+    fn allowed_chat_completions_passes_and_files_is_rejected() {
+        let allowed = json!(["/v1/chat/completions", "/v1/models"]);
+
+        assert!(check(&allowed, "/v1/chat/completions").is_ok());
+
+        let err = check(&allowed, "/v1/files").unwrap_err();
+        assert!(matches!(
+            err,
+            ProxyError::Authentication(AuthError::PathNotAllowed(ref path)) if path == "/v1/files"
+        ));
+        assert_eq!(err.status_code(), http::StatusCode::FORBIDDEN);
+        assert_eq!(err.error_code(), "PATH_NOT_ALLOWED");
+    }
+
+    #[test]
+    fn prefix_and_glob_patterns() {
+        let allowed = json!([
+            "/v1/chat",
+            "*/embeddings",
+            "/v1beta/models/*:generateContent"
+        ]);
+
+        assert!(check(&allowed, "/v1/chat").is_ok());
+        assert!(check(&allowed, "/v1/chat/completions").is_ok());
+        assert!(check(&allowed, "/v1/chatbot").is_err());
+        assert!(check(&allowed, "/v1/embeddings").is_ok());
+        assert!(check(&allowed, "/openai/v1/embeddings").is_ok());
+        assert!(check(&allowed, "/v1beta/models/gemini-pro:generateContent").is_ok());
+        assert!(check(&allowed, "/v1beta/models/gemini-pro:streamGenerateContent").is_err());
+        assert!(check(&allowed, "/chat/completions").is_err());
+    }
+
+    #[test]
+    fn missing_allowlist_allows_everything_and_invalid_config_fails_closed() {
+        assert!(ensure_path_allowed(None, "/v1/files", "test").is_ok());
+        assert!(check(&json!({"path": "/v1"}), "/v1/files").is_err());
+        assert!(check(&json!([]), "/v1/chat/completions").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_malformed_patterns() {
+        assert!(parse_allowed_paths(&json!(["v1/chat"])).is_err());
+        assert!(parse_allowed_paths(&json!([""])).is_err());
+        assert!(parse_allowed_paths(&json!([1])).is_err());
+        assert_eq!(
+            parse_allowed_paths(&json!([" /v1/chat "])).unwrap(),
+            ["/v1/chat"]
+        );
+    }
+}
@@ -6,8 +6,10 @@ use crate::error::{Result, network::NetworkError};
 use crate::linfo;
 use crate::logging::{LogComponent, LogStage};
 use crate::proxy::state::ProxyState;
+use pingora_core::listeners::TcpSocketOptions;
 use pingora_core::server::{Server, configuration::Opt};
 use pingora_proxy::http_proxy_service;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 /// Pingora 代理服务器
@@ -39,16 +41,18 @@ impl PingoraProxyServer {
         }
     }
 
-    /// 获取代理服务器监听地址
-    #[must_use]
-    pub fn get_server_address(&self) -> String {
+    /// 获取代理服务器全部监听地址
+    pub fn get_server_addresses(&self) -> Result<Vec<SocketAddr>> {
         let config = self.state.context().config();
-        let proxy_port = config.get_proxy_port();
-        let host = config
-            .dual_port
-            .as_ref()
-            .map_or_else(|| "0.0.0.0".to_string(), |d| d.proxy.http.host.clone());
-        format!("{host}:{proxy_port}")
+        config.dual_port.as_ref().map_or_else(
+            || {
+                Ok(vec![SocketAddr::from((
+                    [0, 0, 0, 0],
+                    config.get_proxy_port(),
+                ))])
+            },
+            |d| d.proxy.http.bind_addresses(),
+        )
     }
 
     /// 启动服务器
@@ -65,12 +69,19 @@ impl PingoraProxyServer {
             }
         };
 
+        // 上游空闲连接池容量；引导前配置尚未被共享，可以直接修改
+        let pool_size = self.state.context().config().upstream_pool.pool_size;
+        if let Some(conf) = Arc::get_mut(&mut server.configuration) {
+            conf.upstream_keepalive_pool_size = pool_size;
+        }
+
         linfo!(
             "system",
             LogStage::Startup,
             LogComponent::ServerSetup,
             "bootstrapping_server",
-            "启动Pingora服务器引导"
+            "启动Pingora服务器引导",
+            upstream_keepalive_pool_size = server.configuration.upstream_keepalive_pool_size
         );
         server.bootstrap();
 
@@ -86,8 +97,19 @@ impl PingoraProxyServer {
 
         let mut http_service = http_proxy_service(&server.configuration, proxy_service);
 
-        let server_address = self.get_server_address();
-        http_service.add_tcp(&server_address);
+        let server_addresses = self.get_server_addresses()?;
+        for addr in &server_addresses {
+            if addr.is_ipv6() {
+                // IPv6 监听只接受 IPv6 连接，避免与同端口的 IPv4 监听冲突
+                let options = TcpSocketOptions {
+                    ipv6_only: Some(true),
+                    ..Default::default()
+                };
+                http_service.add_tcp_with_settings(&addr.to_string(), options);
+            } else {
+                http_service.add_tcp(&addr.to_string());
+            }
+        }
 
         server.add_service(http_service);
 
@@ -97,7 +119,7 @@ impl PingoraProxyServer {
             LogComponent::ServerSetup,
             "starting_server",
             "启动Pingora代理服务器",
-            address = &server_address
+            addresses = ?server_addresses
         );
 
         let handle = tokio::task::spawn_blocking(move || {
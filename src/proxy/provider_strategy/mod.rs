@@ -6,19 +6,28 @@
 use self::provider_strategy_claude::ClaudeStrategy;
 use self::provider_strategy_gemini::GeminiStrategy;
 use self::provider_strategy_openai::OpenAIStrategy;
+use self::provider_strategy_passthrough::StandardPassthroughStrategy;
 use std::sync::Arc;
 
 pub mod provider_strategy_claude;
 pub mod provider_strategy_gemini;
 pub mod provider_strategy_openai;
+pub mod provider_strategy_passthrough;
 
-use crate::key_pool::ApiKeyHealthService;
+use crate::auth::types::AuthType;
+use crate::key_pool::{ApiKeyHealthService, HealthUpdate};
 use pingora_http::RequestHeader;
 use pingora_proxy::Session;
 
 use crate::error::Result;
 use crate::proxy::ProxyContext;
-use entity::user_provider_keys;
+use crate::proxy::format_mismatch::FormatMismatch;
+use crate::proxy::tool_validation::ToolValidationError;
+use entity::{provider_types, user_provider_keys};
+use serde::Deserialize;
+
+/// `config_json.api_format` 中表示 `OpenAI` 兼容接口的取值
+pub const OPENAI_API_FORMAT: &str = "openai";
 
 /// 提供商类型枚举
 ///
@@ -73,6 +82,33 @@ impl ProviderType {
             Self::Anthropic => "anthropic", // 与数据库一致
         }
     }
+
+    /// 为绕过代理管道、直连上游的请求（模型列表、请求回放等）附加 API Key 认证头
+    #[must_use]
+    pub fn authorize_direct_request(
+        self,
+        request: reqwest::RequestBuilder,
+        api_key: &str,
+    ) -> reqwest::RequestBuilder {
+        match self {
+            Self::OpenAI => request.bearer_auth(api_key),
+            Self::Anthropic => request
+                .header("x-api-key", api_key)
+                .header("anthropic-version", "2023-06-01"),
+            Self::Gemini => request.header("x-goog-api-key", api_key),
+        }
+    }
+}
+
+/// 服务商 `base_url` 可能不带协议，直连时补全为 https 并去掉末尾 `/`
+#[must_use]
+pub fn direct_base_url(base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    if base_url.contains("://") {
+        base_url.to_string()
+    } else {
+        format!("https://{base_url}")
+    }
 }
 
 #[async_trait::async_trait]
@@ -105,6 +141,24 @@ pub trait ProviderStrategy: Send + Sync {
         Ok(false)
     }
 
+    /// 可选：检测请求体是否为其他接口的格式（如把 `OpenAI` 请求体发给 Anthropic），命中时代理直接返回 400
+    fn detect_format_mismatch(
+        &self,
+        _path: &str,
+        _body: &serde_json::Value,
+    ) -> Option<FormatMismatch> {
+        None
+    }
+
+    /// 可选：按服务商格式校验请求体中的工具（函数调用）定义，定义无效时代理直接返回 400
+    fn validate_tools(
+        &self,
+        _path: &str,
+        _body: &serde_json::Value,
+    ) -> Option<ToolValidationError> {
+        None
+    }
+
     /// 可选：处理响应体，包括错误处理和状态更新
     async fn handle_response_body(
         &self,
@@ -116,6 +170,19 @@ pub trait ProviderStrategy: Send + Sync {
         Ok(())
     }
 
+    /// 可选：根据上游状态码与响应体判定所用密钥的健康状态变更，由响应路径统一应用
+    ///
+    /// 默认只把 API Key 的认证失败（401/403）视为密钥失效；服务商可覆盖以识别自身的错误结构，
+    /// 例如区分过载（暂时降级）与无效密钥（失效）。
+    fn classify_and_update_health(
+        &self,
+        key: &user_provider_keys::Model,
+        status_code: u16,
+        _body: &[u8],
+    ) -> HealthUpdate {
+        default_health_update(key, status_code)
+    }
+
     /// 可选：检查密钥是否应该重试使用
     async fn should_retry_key(&self, _key: &user_provider_keys::Model) -> Result<bool> {
         Ok(true)
@@ -125,6 +192,31 @@ pub trait ProviderStrategy: Send + Sync {
     fn build_auth_headers(&self, api_key: &str) -> Vec<(String, String)>;
 }
 
+/// 通用健康判定：API Key 认证失败（401/403）视为密钥失效，429 视为限流，其余情况不改变状态
+///
+/// `OAuth` 凭证的 401 通常只是访问令牌过期，由刷新流程处理，不标记密钥。
+#[must_use]
+pub fn default_health_update(key: &user_provider_keys::Model, status_code: u16) -> HealthUpdate {
+    if matches!(status_code, 401 | 403) && matches!(key.auth_type.parse(), Ok(AuthType::ApiKey)) {
+        return HealthUpdate::Unhealthy {
+            reason: format!("上游认证失败 (HTTP {status_code})"),
+        };
+    }
+    if status_code == 429 {
+        return HealthUpdate::RateLimited {
+            retry_after: None,
+            reason: "上游限流 (HTTP 429)".to_string(),
+        };
+    }
+    HealthUpdate::Unchanged
+}
+
+/// 读取上游错误响应体中的 `error` 对象（`OpenAI`、Anthropic、Gemini 均使用该字段）
+fn upstream_error(body: &[u8]) -> Option<serde_json::Value> {
+    let mut value = serde_json::from_slice::<serde_json::Value>(body).ok()?;
+    Some(value.get_mut("error")?.take())
+}
+
 /// 简单注册表（进程内静态）
 pub struct ProviderRegistry;
 
@@ -138,6 +230,36 @@ impl ProviderRegistry {
     }
 }
 
+#[derive(Deserialize)]
+struct ProviderApiFormatConfig {
+    api_format: Option<String>,
+}
+
+/// 读取服务商 `config_json.api_format`（小写）；未配置或配置无法解析时返回 `None`
+#[must_use]
+pub fn provider_api_format(provider: &provider_types::Model) -> Option<String> {
+    provider
+        .config_json
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<ProviderApiFormatConfig>(raw).ok())
+        .and_then(|config| config.api_format)
+        .map(|format| format.trim().to_ascii_lowercase())
+}
+
+/// 按服务商选择策略：名称匹配的专用策略优先；
+/// 无专用策略但 `api_format` 为 `openai` 时回退到通用透传策略
+#[must_use]
+pub fn make_strategy_for_provider(
+    provider: &provider_types::Model,
+    health_checker: Option<Arc<ApiKeyHealthService>>,
+) -> Option<Arc<dyn ProviderStrategy>> {
+    if let Some(name) = ProviderRegistry::match_name(&provider.name) {
+        return make_strategy(name, health_checker);
+    }
+    (provider_api_format(provider).as_deref() == Some(OPENAI_API_FORMAT))
+        .then(|| Arc::new(StandardPassthroughStrategy::new()) as Arc<dyn ProviderStrategy>)
+}
+
 // 预留：将来可切换为动态注册（HashMap<&'static str, Arc<dyn ProviderStrategy>>）
 // 这里先提供一个工厂方法，避免无谓的全局可变状态。
 #[must_use]
@@ -167,6 +289,172 @@ pub fn make_strategy(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::{Value, json};
+
+    fn dummy_key(auth_type: &str) -> user_provider_keys::Model {
+        let now = chrono::Utc::now().naive_utc();
+        user_provider_keys::Model {
+            id: 1,
+            user_id: 1,
+            provider_type_id: 1,
+            api_key: "sk-test".to_string(),
+            auth_type: auth_type.to_string(),
+            name: "key1".to_string(),
+            weight: Some(1),
+            max_requests_per_minute: None,
+            max_tokens_prompt_per_minute: None,
+            max_requests_per_day: None,
+            is_active: true,
+            health_status: "healthy".to_string(),
+            health_status_detail: None,
+            rate_limit_resets_at: None,
+            last_error_time: None,
+            auth_status: None,
+            expires_at: None,
+            last_auth_check: None,
+            project_id: None,
+            tags: None,
+            deleted_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn classify(
+        provider: &str,
+        key: &user_provider_keys::Model,
+        status: u16,
+        body: &Value,
+    ) -> HealthUpdate {
+        make_strategy(provider, None)
+            .unwrap()
+            .classify_and_update_health(key, status, body.to_string().as_bytes())
+    }
+
+    #[test]
+    fn anthropic_overloaded_is_degraded_not_dead() {
+        let key = dummy_key("api_key");
+        let overloaded = json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}});
+        assert!(matches!(
+            classify("anthropic", &key, 529, &overloaded),
+            HealthUpdate::Degraded { .. }
+        ));
+        // 529 即使响应体不可解析也是过载
+        assert!(matches!(
+            make_strategy("anthropic", None)
+                .unwrap()
+                .classify_and_update_health(&key, 529, b""),
+            HealthUpdate::Degraded { .. }
+        ));
+
+        let invalid_key = json!({"type": "error", "error": {"type": "authentication_error", "message": "invalid x-api-key"}});
+        assert!(matches!(
+            classify("anthropic", &key, 401, &invalid_key),
+            HealthUpdate::Unhealthy { .. }
+        ));
+
+        let rate_limited = json!({"type": "error", "error": {"type": "rate_limit_error", "message": "Rate limited"}});
+        assert!(matches!(
+            classify("anthropic", &key, 429, &rate_limited),
+            HealthUpdate::RateLimited { .. }
+        ));
+
+        let bad_request = json!({"type": "error", "error": {"type": "invalid_request_error", "message": "max_tokens"}});
+        assert_eq!(
+            classify("anthropic", &key, 400, &bad_request),
+            HealthUpdate::Unchanged
+        );
+    }
+
+    #[test]
+    fn gemini_invalid_key_and_exhausted_quota_are_distinguished() {
+        let key = dummy_key("api_key");
+        let invalid_key = json!({"error": {
+            "code": 400,
+            "message": "API key not valid. Please pass a valid API key.",
+            "status": "INVALID_ARGUMENT",
+            "details": [{"@type": "type.googleapis.com/google.rpc.ErrorInfo", "reason": "API_KEY_INVALID"}]
+        }});
+        assert!(matches!(
+            classify("gemini", &key, 400, &invalid_key),
+            HealthUpdate::Unhealthy { .. }
+        ));
+
+        let exhausted = json!({"error": {"code": 429, "message": "Quota exceeded", "status": "RESOURCE_EXHAUSTED"}});
+        assert!(matches!(
+            classify("gemini", &key, 429, &exhausted),
+            HealthUpdate::RateLimited {
+                retry_after: None,
+                ..
+            }
+        ));
+
+        let overloaded = json!({"error": {"code": 503, "message": "The model is overloaded.", "status": "UNAVAILABLE"}});
+        assert!(matches!(
+            classify("gemini", &key, 503, &overloaded),
+            HealthUpdate::Degraded { .. }
+        ));
+
+        let bad_argument = json!({"error": {"code": 400, "message": "Invalid JSON payload", "status": "INVALID_ARGUMENT"}});
+        assert_eq!(
+            classify("gemini", &key, 400, &bad_argument),
+            HealthUpdate::Unchanged
+        );
+    }
+
+    #[test]
+    fn openai_quota_exhausted_is_unhealthy_but_rate_limit_is_left_to_handler() {
+        let key = dummy_key("api_key");
+        let no_quota = json!({"error": {"type": "insufficient_quota", "code": "insufficient_quota", "message": "You exceeded your current quota"}});
+        assert!(matches!(
+            classify("openai", &key, 429, &no_quota),
+            HealthUpdate::Unhealthy { .. }
+        ));
+
+        let rate_limited = json!({"error": {"type": "usage_limit_reached", "message": "limit", "resets_in_seconds": 60}});
+        assert_eq!(
+            classify("openai", &key, 429, &rate_limited),
+            HealthUpdate::Unchanged
+        );
+
+        // 没有重置时间的 429 交给默认冷却处理
+        let no_reset = json!({"error": {"type": "requests", "code": "rate_limit_exceeded", "message": "Rate limit reached"}});
+        assert!(matches!(
+            classify("openai", &key, 429, &no_reset),
+            HealthUpdate::RateLimited {
+                retry_after: None,
+                ..
+            }
+        ));
+
+        let invalid_key = json!({"error": {"type": "invalid_request_error", "code": "invalid_api_key", "message": "Incorrect API key provided"}});
+        assert!(matches!(
+            classify("openai", &key, 401, &invalid_key),
+            HealthUpdate::Unhealthy { .. }
+        ));
+    }
+
+    #[test]
+    fn oauth_auth_failure_does_not_mark_key() {
+        let key = dummy_key("oauth");
+        assert_eq!(default_health_update(&key, 401), HealthUpdate::Unchanged);
+        assert_eq!(
+            classify("openai", &key, 401, &json!({})),
+            HealthUpdate::Unchanged
+        );
+        assert!(matches!(
+            default_health_update(&dummy_key("api_key"), 403),
+            HealthUpdate::Unhealthy { .. }
+        ));
+        assert_eq!(
+            default_health_update(&dummy_key("api_key"), 500),
+            HealthUpdate::Unchanged
+        );
+        assert!(matches!(
+            default_health_update(&key, 429),
+            HealthUpdate::RateLimited { .. }
+        ));
+    }
 
     #[test]
     fn test_provider_registry_match_name() {
@@ -218,6 +506,46 @@ mod tests {
         assert!(unknown_strategy.is_none());
     }
 
+    fn provider(name: &str, config_json: Option<&str>) -> provider_types::Model {
+        let now = chrono::Utc::now().naive_utc();
+        provider_types::Model {
+            id: 1,
+            name: name.to_string(),
+            display_name: name.to_string(),
+            auth_type: "api_key".to_string(),
+            base_url: "api.compatible.test".to_string(),
+            is_active: true,
+            config_json: config_json.map(ToString::to_string),
+            token_mappings_json: None,
+            model_extraction_json: None,
+            auth_configs_json: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn unknown_openai_format_provider_gets_passthrough_strategy() {
+        let compatible = provider("deepseek", Some(r#"{"api_format": "OpenAI"}"#));
+        assert_eq!(provider_api_format(&compatible).as_deref(), Some("openai"));
+        let strategy = make_strategy_for_provider(&compatible, None).expect("fallback strategy");
+        assert_eq!(strategy.name(), "openai_compatible");
+
+        // 未声明格式或声明为其他格式时不回退
+        assert!(make_strategy_for_provider(&provider("deepseek", None), None).is_none());
+        let other = provider("deepseek", Some(r#"{"api_format": "custom"}"#));
+        assert!(make_strategy_for_provider(&other, None).is_none());
+        let invalid = provider("deepseek", Some("not json"));
+        assert!(make_strategy_for_provider(&invalid, None).is_none());
+
+        // 名称匹配的专用策略优先
+        let claude = provider("claude", Some(r#"{"api_format": "openai"}"#));
+        assert_eq!(
+            make_strategy_for_provider(&claude, None).unwrap().name(),
+            "anthropic"
+        );
+    }
+
     #[test]
     fn test_provider_type_from_str() {
         // 测试 OpenAI 及其别名
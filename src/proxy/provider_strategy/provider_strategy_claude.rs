@@ -1,21 +1,36 @@
 //! Claude 提供商策略
 //!
-//! 处理 Claude API 特有的逻辑，包括 client ID 替换以保护隐私
+//! 处理 Claude API 特有的逻辑，包括 client ID 替换以保护隐私、按配置自动插入 `cache_control`
 
-use super::ProviderStrategy;
+use super::{ProviderStrategy, ProviderType, default_health_update, upstream_error};
 use crate::error::{Context, Result, config::ConfigError};
-use crate::key_pool::ApiKeyHealthService;
+use crate::key_pool::{ApiKeyHealthService, HealthUpdate};
 use crate::proxy::ProxyContext;
+use crate::proxy::format_mismatch::{
+    FormatMismatch, OPENAI_ONLY_FIELDS, has_openai_function_tools, present_fields,
+    system_role_messages,
+};
+use crate::proxy::system_prompt;
+use crate::proxy::tool_validation::{ToolValidationError, validate_anthropic_tools};
 use crate::proxy::upstream_url::parse_base_url;
 use crate::{
     ldebug, linfo,
     logging::{LogComponent, LogStage},
     lwarn,
 };
+use entity::user_provider_keys;
 use pingora_http::RequestHeader;
 use pingora_proxy::Session;
 use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Value, json};
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Anthropic 过载（HTTP 529 / `overloaded_error`）后密钥的冷却时间
+const OVERLOADED_COOLDOWN: Duration = Duration::from_secs(30);
+/// Anthropic 单个请求最多允许的 `cache_control` 断点数
+const MAX_CACHE_BREAKPOINTS: usize = 4;
 
 /// Claude 策略实现
 ///
@@ -23,6 +38,7 @@ use std::sync::Arc;
 /// 1. 从数据库配置动态获取上游地址
 /// 2. 替换 `metadata.user_id` 中的 client ID 以保护隐私
 /// 3. 设置 Claude 特定的请求头
+/// 4. 服务商开启 `config_json.auto_cache_control` 时为大段 system / 消息内容插入 `cache_control`
 pub struct ClaudeStrategy {
     health_checker: Option<Arc<ApiKeyHealthService>>,
     unified_client_id: String,
@@ -122,9 +138,11 @@ impl ProviderStrategy for ClaudeStrategy {
         ctx: &ProxyContext,
         json_value: &mut serde_json::Value,
     ) -> Result<bool> {
-        let modified = replace_client_id(json_value, &self.unified_client_id);
+        // 先注入系统提示词，使其同样参与自动 cache_control 标记
+        let prompt_injected = system_prompt::apply(ctx, ProviderType::Anthropic, json_value);
+        let client_id_replaced = replace_client_id(json_value, &self.unified_client_id);
 
-        if modified {
+        if client_id_replaced {
             linfo!(
                 &ctx.request_id,
                 LogStage::RequestModify,
@@ -143,7 +161,70 @@ impl ProviderStrategy for ClaudeStrategy {
             );
         }
 
-        Ok(modified)
+        let cache_marked = ctx
+            .routing
+            .provider_type
+            .as_ref()
+            .and_then(AutoCacheControlConfig::from_provider)
+            .map_or(0, |config| {
+                insert_cache_control(json_value, config.min_chars)
+            });
+        if cache_marked > 0 {
+            linfo!(
+                &ctx.request_id,
+                LogStage::RequestModify,
+                LogComponent::ClaudeStrategy,
+                "cache_control_inserted",
+                "已为大段内容自动插入 cache_control",
+                blocks = cache_marked
+            );
+        }
+
+        Ok(prompt_injected || client_id_replaced || cache_marked > 0)
+    }
+
+    /// Anthropic 错误体为 `{"type":"error","error":{"type":"..."}}`：过载与限流只是暂时降级，
+    /// 认证与权限错误才视为密钥失效
+    fn classify_and_update_health(
+        &self,
+        key: &user_provider_keys::Model,
+        status_code: u16,
+        body: &[u8],
+    ) -> HealthUpdate {
+        let error_type =
+            upstream_error(body).and_then(|error| error.get("type")?.as_str().map(str::to_string));
+        match (status_code, error_type.as_deref()) {
+            (529, _) | (_, Some("overloaded_error")) => HealthUpdate::Degraded {
+                cooldown: OVERLOADED_COOLDOWN,
+                reason: "Anthropic overloaded_error".to_string(),
+            },
+            (_, Some("rate_limit_error")) => HealthUpdate::RateLimited {
+                retry_after: None,
+                reason: "Anthropic rate_limit_error".to_string(),
+            },
+            _ => default_health_update(key, status_code),
+        }
+    }
+
+    /// Messages 接口收到 `OpenAI` Chat Completions 请求体：`OpenAI` 独有字段、`system` 角色消息或 `function` 工具结构。
+    /// Anthropic 的 `OpenAI` 兼容接口（`/v1/chat/completions`）本就接受该格式，不做检测
+    fn detect_format_mismatch(&self, path: &str, body: &Value) -> Option<FormatMismatch> {
+        if !path.contains("/v1/messages") {
+            return None;
+        }
+        let mut fields = present_fields(body, OPENAI_ONLY_FIELDS);
+        fields.extend(system_role_messages(body));
+        if has_openai_function_tools(body) {
+            fields.push("tools[].function".to_string());
+        }
+        FormatMismatch::from_fields("openai", "anthropic", fields)
+    }
+
+    fn validate_tools(&self, path: &str, body: &Value) -> Option<ToolValidationError> {
+        if !path.contains("/v1/messages") {
+            return None;
+        }
+        validate_anthropic_tools(body).err()
     }
 
     fn build_auth_headers(&self, api_key: &str) -> Vec<(String, String)> {
@@ -178,6 +259,124 @@ fn replace_client_id(json_value: &mut serde_json::Value, unified_client_id: &str
     false
 }
 
+/// 服务商 `config_json.auto_cache_control` 配置（默认关闭）
+#[derive(Debug, Clone, Deserialize)]
+struct AutoCacheControlConfig {
+    #[serde(default)]
+    enabled: bool,
+    /// 文本长度（字符数）达到该值的内容块才插入 `cache_control`
+    #[serde(default = "default_cache_min_chars")]
+    min_chars: usize,
+}
+
+/// 约 1024 token，低于 Anthropic 的最小可缓存长度时插入断点没有意义
+const fn default_cache_min_chars() -> usize {
+    4096
+}
+
+#[derive(Deserialize)]
+struct ProviderCacheConfig {
+    auto_cache_control: Option<AutoCacheControlConfig>,
+}
+
+impl AutoCacheControlConfig {
+    /// 读取服务商配置；未开启或配置无法解析时返回 `None`
+    fn from_provider(provider: &entity::provider_types::Model) -> Option<Self> {
+        provider
+            .config_json
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<ProviderCacheConfig>(raw).ok())
+            .and_then(|config| config.auto_cache_control)
+            .filter(|config| config.enabled)
+    }
+}
+
+/// 为达到长度阈值的 system 与消息文本块插入 `cache_control`，返回新插入的断点数
+///
+/// 已带 `cache_control` 的块保持不变；连同已有断点在内不超过 Anthropic 的断点上限。
+fn insert_cache_control(json_value: &mut Value, min_chars: usize) -> usize {
+    let mut budget = MAX_CACHE_BREAKPOINTS.saturating_sub(count_cache_breakpoints(json_value));
+    let mut inserted = 0;
+
+    if let Some(system) = json_value.get_mut("system") {
+        inserted += mark_content(system, min_chars, &mut budget);
+    }
+    if let Some(messages) = json_value.get_mut("messages").and_then(Value::as_array_mut) {
+        for message in messages {
+            if let Some(content) = message.get_mut("content") {
+                inserted += mark_content(content, min_chars, &mut budget);
+            }
+        }
+    }
+    inserted
+}
+
+/// 统计请求中已有的 `cache_control` 断点
+fn count_cache_breakpoints(json_value: &Value) -> usize {
+    let blocks = |content: Option<&Value>| {
+        content.and_then(Value::as_array).map_or(0, |blocks| {
+            blocks
+                .iter()
+                .filter(|block| block.get("cache_control").is_some())
+                .count()
+        })
+    };
+    let messages = json_value
+        .get("messages")
+        .and_then(Value::as_array)
+        .map_or(0, |messages| {
+            messages
+                .iter()
+                .map(|message| blocks(message.get("content")))
+                .sum()
+        });
+    blocks(json_value.get("system")) + messages
+}
+
+/// 标记一段内容（字符串或内容块数组）中的大文本块
+fn mark_content(content: &mut Value, min_chars: usize, budget: &mut usize) -> usize {
+    if *budget == 0 {
+        return 0;
+    }
+    // 字符串形式的大段内容改写为带 cache_control 的文本块
+    if let Some(text) = content.as_str() {
+        if text.chars().count() < min_chars {
+            return 0;
+        }
+        *content = json!([{
+            "type": "text",
+            "text": text,
+            "cache_control": { "type": "ephemeral" },
+        }]);
+        *budget -= 1;
+        return 1;
+    }
+
+    let Some(blocks) = content.as_array_mut() else {
+        return 0;
+    };
+    let mut inserted = 0;
+    for block in blocks {
+        if *budget == 0 {
+            break;
+        }
+        let Some(object) = block.as_object_mut() else {
+            continue;
+        };
+        let is_large_text = object.get("type").and_then(Value::as_str) == Some("text")
+            && object
+                .get("text")
+                .and_then(Value::as_str)
+                .is_some_and(|text| text.chars().count() >= min_chars);
+        if is_large_text && !object.contains_key("cache_control") {
+            object.insert("cache_control".to_string(), json!({ "type": "ephemeral" }));
+            *budget -= 1;
+            inserted += 1;
+        }
+    }
+    inserted
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -452,4 +651,152 @@ mod tests {
             "a1b2c3d4e5f6789012345678901234567890abcdef1234567890abcdef123456"
         );
     }
+
+    // ==================== cache_control 自动插入 ====================
+
+    #[test]
+    fn test_insert_cache_control_on_large_system_prompt() {
+        let large = "x".repeat(5000);
+        let mut body = json!({
+            "system": large,
+            "messages": [{ "role": "user", "content": "Hi" }]
+        });
+
+        assert_eq!(insert_cache_control(&mut body, 4096), 1);
+        assert_eq!(body["system"][0]["type"], "text");
+        assert_eq!(body["system"][0]["text"].as_str().map(str::len), Some(5000));
+        assert_eq!(body["system"][0]["cache_control"]["type"], "ephemeral");
+        // 短消息保持原样
+        assert_eq!(body["messages"][0]["content"], "Hi");
+    }
+
+    #[test]
+    fn test_insert_cache_control_skips_marked_blocks() {
+        let large = "y".repeat(5000);
+        let mut body = json!({
+            "system": [{
+                "type": "text",
+                "text": large,
+                "cache_control": { "type": "ephemeral", "ttl": "1h" }
+            }],
+            "messages": [{
+                "role": "user",
+                "content": [{ "type": "text", "text": large }]
+            }]
+        });
+
+        assert_eq!(insert_cache_control(&mut body, 4096), 1);
+        assert_eq!(body["system"][0]["cache_control"]["ttl"], "1h");
+        assert_eq!(
+            body["messages"][0]["content"][0]["cache_control"]["type"],
+            "ephemeral"
+        );
+
+        // 再次处理不会重复插入
+        let snapshot = body.clone();
+        assert_eq!(insert_cache_control(&mut body, 4096), 0);
+        assert_eq!(body, snapshot);
+    }
+
+    #[test]
+    fn test_insert_cache_control_respects_breakpoint_limit() {
+        let large = "z".repeat(5000);
+        let messages: Vec<Value> = (0..6)
+            .map(|_| json!({ "role": "user", "content": large }))
+            .collect();
+        let mut body = json!({ "messages": messages });
+
+        assert_eq!(insert_cache_control(&mut body, 4096), MAX_CACHE_BREAKPOINTS);
+        assert_eq!(count_cache_breakpoints(&body), MAX_CACHE_BREAKPOINTS);
+    }
+
+    #[test]
+    fn test_auto_cache_control_is_opt_in() {
+        let mut provider = dummy_claude_provider();
+        assert!(AutoCacheControlConfig::from_provider(&provider).is_none());
+
+        provider.config_json = Some(r#"{"auto_cache_control":{"enabled":false}}"#.to_string());
+        assert!(AutoCacheControlConfig::from_provider(&provider).is_none());
+
+        provider.config_json = Some(r#"{"auto_cache_control":{"enabled":true}}"#.to_string());
+        let config = AutoCacheControlConfig::from_provider(&provider).expect("enabled");
+        assert_eq!(config.min_chars, 4096);
+    }
+
+    #[test]
+    fn test_openai_body_sent_to_messages_is_detected() {
+        let strategy = ClaudeStrategy::new(None);
+        let openai_body = json!({
+            "model": "claude-3-5-sonnet",
+            "messages": [
+                { "role": "system", "content": "You are helpful" },
+                { "role": "user", "content": "hi" }
+            ],
+            "max_completion_tokens": 256,
+            "response_format": { "type": "json_object" }
+        });
+
+        let mismatch = strategy
+            .detect_format_mismatch("/v1/messages", &openai_body)
+            .expect("mismatch detected");
+        assert_eq!(mismatch.detected, "openai");
+        assert_eq!(mismatch.expected, "anthropic");
+        assert_eq!(
+            mismatch.fields,
+            [
+                "response_format",
+                "max_completion_tokens",
+                "messages[0].role=system"
+            ]
+        );
+
+        let rejection =
+            crate::proxy::response::build_format_mismatch_response(&mismatch, "anthropic");
+        assert_eq!(rejection.status, 400);
+        assert_eq!(rejection.payload["error"]["reason_code"], "format_mismatch");
+        assert_eq!(rejection.payload["error"]["detected_format"], "openai");
+        assert!(rejection.message.contains("max_completion_tokens"));
+
+        // OpenAI 兼容接口与正常的 Messages 请求体不受影响
+        assert!(
+            strategy
+                .detect_format_mismatch("/v1/chat/completions", &openai_body)
+                .is_none()
+        );
+        let anthropic_body = json!({
+            "model": "claude-3-5-sonnet",
+            "system": "You are helpful",
+            "max_tokens": 256,
+            "messages": [{ "role": "user", "content": "hi" }],
+            "tools": [{ "name": "lookup", "input_schema": { "type": "object" } }]
+        });
+        assert!(
+            strategy
+                .detect_format_mismatch("/v1/messages", &anthropic_body)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_anthropic_tool_without_input_schema_is_rejected() {
+        let strategy = ClaudeStrategy::new(None);
+        let body = json!({
+            "model": "claude-3-5-sonnet",
+            "max_tokens": 256,
+            "messages": [{ "role": "user", "content": "hi" }],
+            "tools": [
+                { "type": "web_search_20250305", "name": "web_search" },
+                { "name": "get_weather", "description": "查询天气" }
+            ]
+        });
+
+        let error = strategy
+            .validate_tools("/v1/messages", &body)
+            .expect("invalid tool detected");
+        assert_eq!(error.field, "tools[1].input_schema");
+        assert!(error.message().contains("tools[1].input_schema"));
+
+        // 非 Messages 接口不校验
+        assert!(strategy.validate_tools("/v1/models", &body).is_none());
+    }
 }
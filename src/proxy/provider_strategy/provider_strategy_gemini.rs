@@ -3,9 +3,15 @@
 //! 说明：当前仅做最小无害改写示例（如补充少量兼容性 Header），
 //! 实际的路径/JSON 注入逻辑仍留在 RequestHandler，后续再迁移。
 
-use super::ProviderStrategy;
+use super::{ProviderStrategy, ProviderType, default_health_update, upstream_error};
+use crate::auth::types::AuthType;
 use crate::error::{Context, Result};
 use crate::proxy::ProxyContext;
+use crate::proxy::format_mismatch::{
+    ANTHROPIC_ONLY_FIELDS, FormatMismatch, OPENAI_ONLY_FIELDS, present_fields,
+};
+use crate::proxy::system_prompt;
+use crate::proxy::tool_validation::{ToolValidationError, validate_gemini_tools};
 use crate::proxy::upstream_url::parse_base_url;
 use crate::{
     ldebug, linfo,
@@ -14,8 +20,13 @@ use crate::{
 use pingora_http::RequestHeader;
 use pingora_proxy::Session;
 
-use crate::key_pool::ApiKeyHealthService;
+use crate::key_pool::{ApiKeyHealthService, HealthUpdate};
+use entity::user_provider_keys;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Gemini 服务过载（`UNAVAILABLE`）后密钥的冷却时间
+const UNAVAILABLE_COOLDOWN: Duration = Duration::from_secs(30);
 
 #[derive(Default)]
 pub struct GeminiStrategy {
@@ -73,7 +84,7 @@ impl ProviderStrategy for GeminiStrategy {
 
         // 判断是否需要后续 JSON 注入（在 body filter 里执行）
         if let Some(backend) = &ctx.routing.selected_backend
-            && backend.auth_type.as_str() == "oauth"
+            && matches!(backend.auth_type.parse(), Ok(AuthType::OAuth))
             && let Some(pid) = &backend.project_id
             && !pid.is_empty()
         {
@@ -92,13 +103,15 @@ impl ProviderStrategy for GeminiStrategy {
         ctx: &ProxyContext,
         json_value: &mut serde_json::Value,
     ) -> Result<bool> {
+        let prompt_injected = system_prompt::apply(ctx, ProviderType::Gemini, json_value);
+
         let Some(backend) = &ctx.routing.selected_backend else {
-            return Ok(false);
+            return Ok(prompt_injected);
         };
 
-        // 仅处理 OAuth 认证
-        if backend.auth_type.as_str() != "oauth" {
-            return Ok(false);
+        // 以下仅处理 OAuth 认证
+        if !matches!(backend.auth_type.parse(), Ok(AuthType::OAuth)) {
+            return Ok(prompt_injected);
         }
 
         let request_path = session.req_header().uri.path();
@@ -121,7 +134,7 @@ impl ProviderStrategy for GeminiStrategy {
                 );
             }
 
-            return Ok(modified);
+            return Ok(modified || prompt_injected);
         }
 
         // 使用 will_modify_body 判断是否需要注入，仅当存在真实的project_id时才执行注入
@@ -145,7 +158,72 @@ impl ProviderStrategy for GeminiStrategy {
             );
         }
 
-        Ok(modified)
+        Ok(modified || prompt_injected)
+    }
+
+    /// Gemini 错误体为 Google API 格式：`error.status` 表示错误类别，
+    /// 无效密钥以 400 返回，需要从 `error.details[].reason` 识别 `API_KEY_INVALID`
+    fn classify_and_update_health(
+        &self,
+        key: &user_provider_keys::Model,
+        status_code: u16,
+        body: &[u8],
+    ) -> HealthUpdate {
+        let Some(error) = upstream_error(body) else {
+            return default_health_update(key, status_code);
+        };
+        let key_invalid = error
+            .get("details")
+            .and_then(serde_json::Value::as_array)
+            .is_some_and(|details| {
+                details.iter().any(|detail| {
+                    detail.get("reason").and_then(serde_json::Value::as_str)
+                        == Some("API_KEY_INVALID")
+                })
+            });
+        if key_invalid {
+            return HealthUpdate::Unhealthy {
+                reason: "Gemini API_KEY_INVALID".to_string(),
+            };
+        }
+        match error.get("status").and_then(serde_json::Value::as_str) {
+            Some("RESOURCE_EXHAUSTED") => HealthUpdate::RateLimited {
+                retry_after: None,
+                reason: "Gemini RESOURCE_EXHAUSTED".to_string(),
+            },
+            Some("UNAVAILABLE") => HealthUpdate::Degraded {
+                cooldown: UNAVAILABLE_COOLDOWN,
+                reason: "Gemini UNAVAILABLE".to_string(),
+            },
+            _ => default_health_update(key, status_code),
+        }
+    }
+
+    /// `generateContent` 系列接口收到带 `messages` 而没有 `contents` 的请求体（`OpenAI` 或 Anthropic 格式）
+    fn detect_format_mismatch(
+        &self,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Option<FormatMismatch> {
+        let generate = path.contains("generateContent") || path.contains("countTokens");
+        if !generate || body.get("contents").is_some() || body.get("messages").is_none() {
+            return None;
+        }
+        let anthropic = present_fields(body, ANTHROPIC_ONLY_FIELDS);
+        let (detected, mut fields) = if anthropic.is_empty() && body.get("system").is_none() {
+            ("openai", present_fields(body, OPENAI_ONLY_FIELDS))
+        } else {
+            ("anthropic", anthropic)
+        };
+        fields.insert(0, "messages".to_string());
+        FormatMismatch::from_fields(detected, "gemini", fields)
+    }
+
+    fn validate_tools(&self, path: &str, body: &serde_json::Value) -> Option<ToolValidationError> {
+        if !path.contains("generateContent") && !path.contains("countTokens") {
+            return None;
+        }
+        validate_gemini_tools(body).err()
     }
 
     fn build_auth_headers(&self, api_key: &str) -> Vec<(String, String)> {
@@ -256,6 +334,8 @@ mod tests {
             expires_at: None,
             last_auth_check: Some(now),
             project_id: project_id.map(std::string::ToString::to_string),
+            tags: None,
+            deleted_at: None,
             created_at: now,
             updated_at: now,
         }
@@ -554,4 +634,29 @@ mod tests {
         let effective_id = backend_no_project.project_id.as_deref().unwrap_or("");
         assert_eq!(effective_id, "");
     }
+
+    #[test]
+    fn test_gemini_function_declaration_with_invalid_type_is_rejected() {
+        let strategy = GeminiStrategy::new(None);
+        let body = serde_json::json!({
+            "contents": [{ "role": "user", "parts": [{ "text": "hi" }] }],
+            "tools": [{
+                "functionDeclarations": [{
+                    "name": "get_weather",
+                    "parameters": {
+                        "type": "OBJECT",
+                        "properties": { "city": { "type": "text" } }
+                    }
+                }]
+            }]
+        });
+
+        let error = strategy
+            .validate_tools("/v1beta/models/gemini-2.0-flash:generateContent", &body)
+            .expect("invalid tool detected");
+        assert_eq!(
+            error.field,
+            "tools[0].functionDeclarations[0].parameters.properties.city.type"
+        );
+    }
 }
@@ -3,12 +3,19 @@
 //! 处理OpenAI特有的逻辑，包括429错误处理、JWT解析等
 
 use crate::auth::openai::OpenAI;
+use crate::auth::types::AuthType;
 use crate::error::{Context, Result};
-use crate::key_pool::ApiKeyHealthService;
+use crate::key_pool::{ApiKeyHealthService, HealthUpdate};
 use crate::logging::{LogComponent, LogStage};
 use crate::proxy::ProxyContext;
 use crate::proxy::context::ResolvedCredential;
+use crate::proxy::format_mismatch::{
+    ANTHROPIC_ONLY_FIELDS, FormatMismatch, GEMINI_ONLY_FIELDS, present_fields,
+};
 use crate::proxy::prelude::ProviderStrategy;
+use crate::proxy::provider_strategy::{ProviderType, default_health_update, upstream_error};
+use crate::proxy::system_prompt;
+use crate::proxy::tool_validation::{ToolValidationError, validate_openai_tools};
 use crate::{linfo, lwarn};
 use chrono::Utc;
 use entity::user_provider_keys;
@@ -55,6 +62,8 @@ impl OpenAIStrategy {
     }
 
     /// 异步处理429限流错误
+    ///
+    /// 仅处理携带 `resets_in_seconds` 的限流；其余 429 由响应路径按 `Retry-After` 或默认冷却统一处理。
     async fn handle_rate_limit(&self, ctx: &ProxyContext, body: &[u8]) -> Result<()> {
         let Some(health_checker) = self.health_checker.as_ref() else {
             return Ok(());
@@ -64,6 +73,9 @@ impl OpenAIStrategy {
         };
 
         if let Ok(error_info) = serde_json::from_slice::<OpenAI429Error>(body) {
+            let Some(seconds) = error_info.error.resets_in_seconds else {
+                return Ok(());
+            };
             linfo!(
                 &ctx.request_id,
                 LogStage::Internal,
@@ -72,13 +84,10 @@ impl OpenAIStrategy {
                 "成功解析OpenAI 429错误，准备更新密钥状态",
                 error_type = %error_info.error.r#type
             );
-            let resets_at = error_info
-                .error
-                .resets_in_seconds
-                .map(|seconds| (Utc::now() + chrono::Duration::seconds(seconds)).naive_utc());
+            let resets_at = (Utc::now() + chrono::Duration::seconds(seconds)).naive_utc();
             let details = serde_json::to_string(&error_info.error).unwrap_or_default();
             health_checker
-                .mark_key_rate_limited(key_id, resets_at, &details)
+                .mark_key_rate_limited(key_id, Some(resets_at), &details)
                 .await?;
         } else {
             lwarn!(
@@ -189,6 +198,52 @@ impl OpenAIStrategy {
             .find(|(k, _)| k.eq_ignore_ascii_case(name))
             .map(|(_, v)| v.as_str())
     }
+
+    /// Codex Responses 请求未携带 instructions 时，使用服务 API 描述补充
+    fn inject_codex_instructions(
+        session: &Session,
+        ctx: &ProxyContext,
+        json_value: &mut Value,
+    ) -> bool {
+        let path = session.req_header().uri.path();
+        if !is_codex_responses_path(path) {
+            return false;
+        }
+
+        let Some(object) = json_value.as_object_mut() else {
+            return false;
+        };
+
+        if object.contains_key("instructions") {
+            return false;
+        }
+
+        let Some(description) = ctx
+            .routing
+            .user_service_api
+            .as_ref()
+            .and_then(|api| api.description.as_deref())
+            .filter(|value| !value.trim().is_empty())
+        else {
+            return false;
+        };
+
+        object.insert(
+            "instructions".to_string(),
+            Value::String(description.to_string()),
+        );
+
+        linfo!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::OpenAIStrategy,
+            "inject_instructions",
+            "OpenAI请求补充instructions字段",
+            route_path = path
+        );
+
+        true
+    }
 }
 
 #[async_trait::async_trait]
@@ -216,7 +271,7 @@ impl ProviderStrategy for OpenAIStrategy {
             );
         }
         if let Some(backend) = &ctx.routing.selected_backend
-            && backend.auth_type == "oauth"
+            && matches!(backend.auth_type.parse(), Ok(AuthType::OAuth))
         {
             upstream_request
                 .insert_header("host", "chatgpt.com")
@@ -247,44 +302,9 @@ impl ProviderStrategy for OpenAIStrategy {
         ctx: &ProxyContext,
         json_value: &mut Value,
     ) -> Result<bool> {
-        let path = session.req_header().uri.path();
-        if !is_codex_responses_path(path) {
-            return Ok(false);
-        }
-
-        let Some(object) = json_value.as_object_mut() else {
-            return Ok(false);
-        };
-
-        if object.contains_key("instructions") {
-            return Ok(false);
-        }
-
-        let Some(description) = ctx
-            .routing
-            .user_service_api
-            .as_ref()
-            .and_then(|api| api.description.as_deref())
-            .filter(|value| !value.trim().is_empty())
-        else {
-            return Ok(false);
-        };
-
-        object.insert(
-            "instructions".to_string(),
-            Value::String(description.to_string()),
-        );
-
-        linfo!(
-            &ctx.request_id,
-            LogStage::RequestModify,
-            LogComponent::OpenAIStrategy,
-            "inject_instructions",
-            "OpenAI请求补充instructions字段",
-            route_path = path
-        );
-
-        Ok(true)
+        let instructions = Self::inject_codex_instructions(session, ctx, json_value);
+        let prompt_injected = system_prompt::apply(ctx, ProviderType::OpenAI, json_value);
+        Ok(instructions || prompt_injected)
     }
 
     async fn handle_response_body(
@@ -337,6 +357,47 @@ impl ProviderStrategy for OpenAIStrategy {
         Ok(key.is_active && key.health_status == "healthy")
     }
 
+    /// 携带 `resets_in_seconds` 的 429 由 `handle_response_body` 按重置时间标记限流；
+    /// `insufficient_quota` 表示账户额度用尽，等待不会恢复，视为密钥失效
+    fn classify_and_update_health(
+        &self,
+        key: &user_provider_keys::Model,
+        status_code: u16,
+        body: &[u8],
+    ) -> HealthUpdate {
+        if status_code == 429
+            && let Some(error) = upstream_error(body)
+        {
+            if error.get("code").and_then(Value::as_str) == Some("insufficient_quota") {
+                return HealthUpdate::Unhealthy {
+                    reason: "OpenAI insufficient_quota".to_string(),
+                };
+            }
+            if error.get("resets_in_seconds").is_some_and(Value::is_i64) {
+                return HealthUpdate::Unchanged;
+            }
+        }
+        default_health_update(key, status_code)
+    }
+
+    /// Chat Completions 接口收到 Gemini（`contents` 等）或 Anthropic（顶层 `system`、`stop_sequences` 等）格式的请求体
+    fn detect_format_mismatch(&self, path: &str, body: &Value) -> Option<FormatMismatch> {
+        if !path.ends_with("/chat/completions") {
+            return None;
+        }
+        let gemini = present_fields(body, GEMINI_ONLY_FIELDS);
+        if !gemini.is_empty() {
+            return FormatMismatch::from_fields("gemini", "openai", gemini);
+        }
+        let mut anthropic = present_fields(body, &["system"]);
+        anthropic.extend(present_fields(body, ANTHROPIC_ONLY_FIELDS));
+        FormatMismatch::from_fields("anthropic", "openai", anthropic)
+    }
+
+    fn validate_tools(&self, path: &str, body: &Value) -> Option<ToolValidationError> {
+        validate_openai_tools(path, body).err()
+    }
+
     fn build_auth_headers(&self, api_key: &str) -> Vec<(String, String)> {
         vec![("Authorization".to_string(), format!("Bearer {api_key}"))]
     }
@@ -402,16 +463,31 @@ mod tests {
             provider_type_id: 1,
             user_provider_keys_ids: serde_json::json!([]),
             api_key: "test-api-key".to_string(),
+            previous_api_key: None,
+            previous_api_key_expires_at: None,
             name: None,
             description,
             scheduling_strategy: None,
             retry_count: None,
             timeout_seconds: None,
+            max_response_duration_seconds: None,
+            sla_target_ms: None,
             max_request_per_min: None,
             max_requests_per_day: None,
             max_tokens_per_day: None,
             max_cost_per_day: None,
+            max_cost_per_request: None,
             log_mode: false,
+            selection_debug: false,
+            stream_policy: "allow".to_string(),
+            upstream_error_mode: "sanitized".to_string(),
+            priority: 0,
+            request_transform_rules: None,
+            response_headers: None,
+            allowed_paths: None,
+            system_prompt: None,
+            key_tag: None,
+            model_routes: None,
             expires_at: None,
             is_active: true,
             created_at: now,
@@ -477,4 +553,41 @@ mod tests {
         assert!(!modified);
         assert!(json_value.get("instructions").is_none());
     }
+
+    #[test]
+    fn test_openai_malformed_tool_is_rejected() {
+        let strategy = OpenAIStrategy::new(None);
+        let body = json!({
+            "model": "gpt-4o",
+            "messages": [{ "role": "user", "content": "hi" }],
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "parameters": {
+                        "type": "object",
+                        "properties": { "city": { "type": "string" } },
+                        "required": ["city", "unit"]
+                    }
+                }
+            }]
+        });
+
+        let error = strategy
+            .validate_tools("/v1/chat/completions", &body)
+            .expect("invalid tool detected");
+        assert_eq!(error.field, "tools[0].function.parameters.required[1]");
+
+        let rejection = crate::proxy::response::build_invalid_tool_response(&error, "openai");
+        assert_eq!(rejection.status, 400);
+        assert_eq!(
+            rejection.payload["error"]["reason_code"],
+            "invalid_tool_definition"
+        );
+        assert_eq!(
+            rejection.payload["error"]["field"],
+            "tools[0].function.parameters.required[1]"
+        );
+        assert!(rejection.message.contains("unit"));
+    }
 }
@@ -0,0 +1,50 @@
+//! `OpenAI` 兼容服务商的通用透传策略
+//!
+//! 没有专门策略、但在 `config_json.api_format` 中声明为 `openai` 的服务商使用本策略：
+//! 请求与响应原样透传，只负责 Bearer 认证头，新接入的兼容服务商无需改代码。
+
+use super::ProviderStrategy;
+
+/// 通用透传策略
+pub struct StandardPassthroughStrategy;
+
+impl StandardPassthroughStrategy {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for StandardPassthroughStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl ProviderStrategy for StandardPassthroughStrategy {
+    fn name(&self) -> &'static str {
+        "openai_compatible"
+    }
+
+    fn build_auth_headers(&self, api_key: &str) -> Vec<(String, String)> {
+        vec![("Authorization".to_string(), format!("Bearer {api_key}"))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_auth_headers_uses_bearer_token() {
+        let headers = StandardPassthroughStrategy::new().build_auth_headers("sk-compatible");
+        assert_eq!(
+            headers,
+            vec![(
+                "Authorization".to_string(),
+                "Bearer sk-compatible".to_string()
+            )]
+        );
+    }
+}
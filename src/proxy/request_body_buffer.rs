@@ -0,0 +1,153 @@
+//! # 请求体缓冲
+//!
+//! 改写请求体必须等到 `end_of_stream` 拿到完整内容后进行，提前改写会把半截 JSON
+//! 当成完整请求体处理。`RequestBodyBuffer` 统一负责累积分块、只在流结束时交出完整请求体，
+//! 并限制缓存大小：超过上限后放弃改写，把已缓存的内容原样交还转发。
+
+use bytes::{Bytes, BytesMut};
+
+/// 推入一个分块后的处理结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum BufferedBody {
+    /// 分块已缓存，本次不向上游发送
+    Pending,
+    /// 流结束，返回完整请求体；每个请求体只返回一次，调用方在此时进行改写
+    Complete(Bytes),
+    /// 超过缓存上限，放弃改写；返回已缓存的全部内容（含当前分块）原样转发
+    Overflow(Bytes),
+    /// 溢出后的后续分块，原样透传
+    PassThrough,
+}
+
+/// 累积请求体分块，只在流结束时交出完整请求体
+#[derive(Debug)]
+pub struct RequestBodyBuffer {
+    buffer: BytesMut,
+    limit: usize,
+    overflowed: bool,
+}
+
+impl RequestBodyBuffer {
+    /// 默认缓存上限：多模态请求携带 base64 图片时请求体可达数十 MB
+    pub const DEFAULT_LIMIT: usize = 32 * 1024 * 1024;
+
+    #[must_use]
+    pub const fn new(limit: usize) -> Self {
+        Self {
+            buffer: BytesMut::new(),
+            limit,
+            overflowed: false,
+        }
+    }
+
+    /// 是否已因超过上限放弃改写
+    #[must_use]
+    pub const fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    /// 推入一个分块（可能为空），返回本次应如何处理
+    ///
+    /// 返回 `Complete` 后缓冲区清空，Pingora 重试时重放的请求体会重新累积。
+    pub fn push(&mut self, chunk: Option<&Bytes>, end_of_stream: bool) -> BufferedBody {
+        if self.overflowed {
+            if end_of_stream {
+                self.overflowed = false;
+            }
+            return BufferedBody::PassThrough;
+        }
+
+        if let Some(chunk) = chunk {
+            self.buffer.extend_from_slice(chunk);
+        }
+        if self.buffer.len() > self.limit {
+            self.overflowed = !end_of_stream;
+            return BufferedBody::Overflow(self.buffer.split().freeze());
+        }
+        if end_of_stream {
+            return BufferedBody::Complete(self.buffer.split().freeze());
+        }
+        BufferedBody::Pending
+    }
+}
+
+impl Default for RequestBodyBuffer {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(buffer: &mut RequestBodyBuffer, chunks: &[&'static str]) -> Vec<BufferedBody> {
+        let last = chunks.len() - 1;
+        chunks
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                buffer.push(Some(&Bytes::from_static((*chunk).as_bytes())), i == last)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn yields_complete_body_once_at_end_of_stream() {
+        let mut buffer = RequestBodyBuffer::default();
+        let events = feed(
+            &mut buffer,
+            &[r#"{"model":"#, r#""gpt-4o","#, r#""stream":true}"#],
+        );
+
+        let completed: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                BufferedBody::Complete(body) => Some(body.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(events[..2], [BufferedBody::Pending, BufferedBody::Pending]);
+        assert_eq!(completed.len(), 1);
+        assert_eq!(
+            completed[0].as_ref(),
+            br#"{"model":"gpt-4o","stream":true}"#
+        );
+    }
+
+    #[test]
+    fn trailing_empty_chunk_completes_body() {
+        let mut buffer = RequestBodyBuffer::default();
+        assert_eq!(
+            buffer.push(Some(&Bytes::from_static(b"{}")), false),
+            BufferedBody::Pending
+        );
+        assert_eq!(
+            buffer.push(None, true),
+            BufferedBody::Complete(Bytes::from_static(b"{}"))
+        );
+
+        // 重试重放时重新累积
+        assert_eq!(
+            buffer.push(Some(&Bytes::from_static(b"{}")), true),
+            BufferedBody::Complete(Bytes::from_static(b"{}"))
+        );
+    }
+
+    #[test]
+    fn overflow_releases_buffered_bytes_and_passes_through() {
+        let mut buffer = RequestBodyBuffer::new(8);
+        let events = feed(&mut buffer, &["abcd", "efgh", "ijkl", "mnop"]);
+
+        assert_eq!(
+            events,
+            [
+                BufferedBody::Pending,
+                BufferedBody::Pending,
+                BufferedBody::Overflow(Bytes::from_static(b"abcdefghijkl")),
+                BufferedBody::PassThrough,
+            ]
+        );
+        assert!(!buffer.overflowed());
+    }
+}
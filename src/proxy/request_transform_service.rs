@@ -1,27 +1,126 @@
 //! # 请求转换服务
 //!
 //! 负责在请求发往上游前对其进行修改，包括注入认证头、改写路径/请求体、清理代理痕迹等。
+//!
+//! 服务商可在 `config_json.default_headers` 中配置默认请求头（如 `anthropic-beta`）：
+//! 仅在客户端未携带同名头部时补充，客户端发送的值始终优先，不会被覆盖。
 
-use crate::error::{Context, Result, auth::AuthError};
-use crate::linfo;
+use crate::collect::field_extractor;
+use crate::collect::util::content_type_is_json;
+use crate::config::{AppConfig, UpstreamHeadersConfig};
+use crate::error::{Context, ProxyError, Result, auth::AuthError, conversion::ConversionError};
 use crate::logging::{LogComponent, LogStage};
+use crate::proxy::authentication_service::PROVIDER_OVERRIDE_HEADER;
+use crate::proxy::aws_sigv4::{SigV4Scope, SigV4Signer};
 use crate::proxy::context::{ProxyContext, ResolvedCredential};
+use crate::proxy::hmac_signing::{HmacSignatureConfig, HmacSigner};
+use crate::proxy::model_availability::ModelListTarget;
+use crate::proxy::model_rate_limit::ModelRateLimits;
+use crate::proxy::non_streaming;
+use crate::proxy::parameter_policy::{self, MaxTokensCeiling};
+use crate::proxy::provider_strategy::ProviderType;
+use crate::proxy::system_prompt;
 use crate::proxy::upstream_url::parse_base_url;
+use crate::{ldebug, linfo, lwarn};
+use bytes::Bytes;
+use chrono::Utc;
+use entity::provider_types;
+use http::{HeaderName, HeaderValue};
 use pingora_http::RequestHeader;
 use pingora_proxy::Session;
 use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use serde_json::Value;
 use std::sync::Arc;
 
+/// 服务商默认请求头的最大数量
+const MAX_DEFAULT_REQUEST_HEADERS: usize = 32;
+
+/// 由代理管理、不允许作为默认请求头配置的头部
+const PROTECTED_REQUEST_HEADERS: &[&str] = &[
+    "host",
+    "authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "api-key",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+    "upgrade",
+];
+
+#[derive(Deserialize)]
+struct ProviderDefaultHeadersConfig {
+    default_headers: Option<Value>,
+}
+
+/// 严格解析服务商 `config_json.default_headers`（JSON 对象：头部名称 -> 字符串值）
+///
+/// 头部名称统一转为小写；名称或值不合法、命中由代理管理的头部时返回错误。
+pub fn parse_default_request_headers(value: &Value) -> Result<Vec<(HeaderName, HeaderValue)>> {
+    let entries = value
+        .as_object()
+        .ok_or_else(|| ConversionError::message("default_headers 必须是对象"))?;
+    if entries.len() > MAX_DEFAULT_REQUEST_HEADERS {
+        return Err(ConversionError::message(format!(
+            "default_headers 最多 {MAX_DEFAULT_REQUEST_HEADERS} 项"
+        ))
+        .into());
+    }
+
+    let mut headers = Vec::with_capacity(entries.len());
+    for (name, value) in entries {
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+            ConversionError::message(format!("default_headers: 非法的头部名称: {name}"))
+        })?;
+        if PROTECTED_REQUEST_HEADERS.contains(&header_name.as_str()) {
+            return Err(ConversionError::message(format!(
+                "default_headers: 不允许配置由代理管理的头部: {name}"
+            ))
+            .into());
+        }
+        let header_value = value
+            .as_str()
+            .and_then(|value| HeaderValue::from_str(value).ok())
+            .ok_or_else(|| {
+                ConversionError::message(format!("default_headers.{name}: 值必须是合法的字符串"))
+            })?;
+        headers.push((header_name, header_value));
+    }
+    Ok(headers)
+}
+
+/// 读取服务商默认请求头；未配置或配置无法解析时返回空列表（写入时已严格校验）
+fn provider_default_headers(provider: &provider_types::Model) -> Vec<(HeaderName, HeaderValue)> {
+    provider
+        .config_json
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<ProviderDefaultHeadersConfig>(raw).ok())
+        .and_then(|config| config.default_headers)
+        .and_then(|value| parse_default_request_headers(&value).ok())
+        .unwrap_or_default()
+}
+
+/// 发往上游的请求体长度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyLength {
+    /// 请求体已完整确定（含空请求体），以 `content-length` 发送
+    Known(usize),
+    /// 请求头发出时请求体尚未改写完成，以分块方式发送
+    Chunked,
+}
+
 /// 请求转换服务
 pub struct RequestTransformService {
     db: Arc<DatabaseConnection>,
+    config: Arc<AppConfig>,
 }
 
 impl RequestTransformService {
     /// 创建新的请求转换服务
     #[must_use]
-    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
-        Self { db }
+    pub const fn new(db: Arc<DatabaseConnection>, config: Arc<AppConfig>) -> Self {
+        Self { db, config }
     }
 
     /// 过滤并转换上游请求
@@ -31,6 +130,19 @@ impl RequestTransformService {
         upstream_request: &mut RequestHeader,
         ctx: &mut ProxyContext,
     ) -> Result<()> {
+        // 0. 按白名单过滤客户端请求头（策略与认证注入的头在之后设置，不受影响）
+        let dropped = Self::retain_allowed_headers(&self.config.upstream_headers, upstream_request);
+        if !dropped.is_empty() {
+            ldebug!(
+                &ctx.request_id,
+                LogStage::RequestModify,
+                LogComponent::RequestTransform,
+                "headers_not_allowlisted",
+                "丢弃白名单外的客户端请求头",
+                dropped = ?dropped
+            );
+        }
+
         // 1. 应用 ProviderStrategy 进行早期修改
         if let Some(strategy) = ctx.routing.strategy.clone() {
             strategy
@@ -38,6 +150,46 @@ impl RequestTransformService {
                 .await?;
         }
 
+        // 1.0 补充服务商默认请求头（客户端已携带的同名头部优先）
+        if let Some(provider) = ctx.routing.provider_type.as_ref() {
+            let applied =
+                Self::apply_default_headers(upstream_request, provider_default_headers(provider));
+            if !applied.is_empty() {
+                ldebug!(
+                    &ctx.request_id,
+                    LogStage::RequestModify,
+                    LogComponent::RequestTransform,
+                    "default_headers_applied",
+                    "补充服务商默认请求头",
+                    headers = ?applied
+                );
+            }
+        }
+
+        // 1.1 参数策略或服务 API 改写规则可能作用于本次请求时，需在请求体阶段改写 JSON；
+        //     模型预检、模型级限流与拒绝流式同样需要先缓存完整请求体，确认后再发往上游；
+        //     配置了系统提示词时由提供商策略在请求体阶段注入
+        //     WebSocket 升级后传输的是数据帧，不做请求体改写
+        if ctx.request.is_websocket {
+            // 禁止协商压缩扩展，保证会话事件可被旁路解析用于计费
+            upstream_request.remove_header("sec-websocket-extensions");
+        } else if self.parameter_policy_may_apply(session, ctx)
+            || Self::max_tokens_ceiling_may_apply(session, ctx)
+            || Self::has_transform_rules(ctx)
+            || self.model_check_may_apply(session, ctx)
+            || Self::model_rate_limit_may_apply(session, ctx)
+            || non_streaming::is_forced(ctx)
+            || non_streaming::is_denied(ctx)
+            || system_prompt::is_configured(ctx)
+        {
+            ctx.request.will_modify_body = true;
+        }
+
+        // 1.2 强制非流式：Gemini 通过路径区分流式，需要改写生成动作
+        if non_streaming::is_forced(ctx) && !ctx.request.is_websocket {
+            Self::force_non_streaming_path(upstream_request, ctx)?;
+        }
+
         // 2. 覆盖 Host 头为上游地址（避免下游 Host 影响上游路由）
         Self::ensure_host_header(upstream_request, ctx)?;
 
@@ -53,6 +205,17 @@ impl RequestTransformService {
         // 6. 处理 Content-Length
         Self::handle_content_length(session, upstream_request, ctx);
 
+        // 7. 改写请求体时补全缺失的 Content-Type
+        if Self::ensure_json_content_type(upstream_request, ctx) {
+            ldebug!(
+                &ctx.request_id,
+                LogStage::RequestModify,
+                LogComponent::RequestTransform,
+                "content_type_defaulted",
+                "客户端未声明 Content-Type，按 JSON 请求体补全"
+            );
+        }
+
         linfo!(
             &ctx.request_id,
             LogStage::UpstreamRequest,
@@ -66,6 +229,239 @@ impl RequestTransformService {
         Ok(())
     }
 
+    /// 在完整请求体上应用通用改写策略（策略级改写之后执行）
+    ///
+    /// 先执行服务 API 的改写规则，再执行参数策略与服务商输出 Token 上限，保证约束最终生效。
+    /// 返回 `true` 表示请求体已被修改。
+    pub fn apply_body_policies(
+        &self,
+        session: &Session,
+        ctx: &mut ProxyContext,
+        json_value: &mut Value,
+    ) -> bool {
+        let transformed = Self::apply_transform_rules(ctx, json_value);
+        let adjusted = self.apply_parameter_policy(session, ctx, json_value);
+        let capped = Self::apply_max_tokens_ceiling(session, ctx, json_value);
+        let non_streaming = Self::force_non_streaming_body(ctx, json_value);
+        transformed || adjusted || capped || non_streaming
+    }
+
+    /// 强制非流式时关闭请求体中的流式开关
+    fn force_non_streaming_body(ctx: &ProxyContext, json_value: &mut Value) -> bool {
+        if !non_streaming::is_forced(ctx) || !non_streaming::disable_body_streaming(json_value) {
+            return false;
+        }
+        linfo!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::RequestTransform,
+            "streaming_forced_off",
+            "服务 API 开启强制非流式，已关闭请求体中的流式参数"
+        );
+        true
+    }
+
+    /// 强制非流式时将 Gemini 流式路径改写为非流式路径
+    fn force_non_streaming_path(
+        upstream_request: &mut RequestHeader,
+        ctx: &ProxyContext,
+    ) -> Result<()> {
+        let Some(path_and_query) = upstream_request.uri.path_and_query().map(|pq| pq.as_str())
+        else {
+            return Ok(());
+        };
+        let Some(rewritten) = non_streaming::non_streaming_gemini_path(path_and_query) else {
+            return Ok(());
+        };
+
+        let mut parts = upstream_request.uri.clone().into_parts();
+        parts.path_and_query =
+            Some(rewritten.parse().map_err(|err| {
+                ProxyError::from(format!("改写非流式路径失败: {rewritten}: {err}"))
+            })?);
+        let uri = http::Uri::from_parts(parts)
+            .map_err(|err| ProxyError::from(format!("构建非流式上游 URI 失败: {err}")))?;
+        linfo!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::RequestTransform,
+            "streaming_path_forced_off",
+            "服务 API 开启强制非流式，已改写流式路径",
+            from = path_and_query,
+            to = %uri
+        );
+        upstream_request.set_uri(uri);
+        Ok(())
+    }
+
+    fn has_transform_rules(ctx: &ProxyContext) -> bool {
+        ctx.routing
+            .user_service_api
+            .as_ref()
+            .and_then(|api| api.request_transform_rules.as_ref())
+            .and_then(Value::as_array)
+            .is_some_and(|rules| !rules.is_empty())
+    }
+
+    fn apply_transform_rules(ctx: &ProxyContext, json_value: &mut Value) -> bool {
+        let Some(raw_rules) = ctx
+            .routing
+            .user_service_api
+            .as_ref()
+            .and_then(|api| api.request_transform_rules.as_ref())
+        else {
+            return false;
+        };
+
+        // 规则已在管理端写入时严格校验，这里解析失败仅告警并跳过，不影响请求转发
+        let rules = match field_extractor::parse_transform_rules(raw_rules) {
+            Ok(rules) => rules,
+            Err(err) => {
+                lwarn!(
+                    &ctx.request_id,
+                    LogStage::RequestModify,
+                    LogComponent::RequestTransform,
+                    "transform_rules_invalid",
+                    &format!("请求体改写规则无效，已跳过: {err}")
+                );
+                return false;
+            }
+        };
+
+        let modified = field_extractor::apply_transform_rules(&rules, json_value);
+        if modified {
+            linfo!(
+                &ctx.request_id,
+                LogStage::RequestModify,
+                LogComponent::RequestTransform,
+                "transform_rules_applied",
+                "已按服务 API 改写规则调整请求体",
+                rule_count = rules.len()
+            );
+        }
+        modified
+    }
+
+    fn parameter_policy_may_apply(&self, session: &Session, ctx: &ProxyContext) -> bool {
+        session.req_header().method == http::Method::POST
+            && Self::provider_type(ctx).is_some()
+            && ctx
+                .routing
+                .user_service_api
+                .as_ref()
+                .is_some_and(|api| self.config.parameter_policy.may_apply_to(api.id))
+    }
+
+    fn model_check_may_apply(&self, session: &Session, ctx: &ProxyContext) -> bool {
+        self.config.model_check.enabled
+            && session.req_header().method == http::Method::POST
+            && ModelListTarget::from_context(ctx).is_some()
+    }
+
+    fn model_rate_limit_may_apply(session: &Session, ctx: &ProxyContext) -> bool {
+        session.req_header().method == http::Method::POST
+            && ctx
+                .routing
+                .provider_type
+                .as_ref()
+                .and_then(ModelRateLimits::from_provider)
+                .is_some()
+    }
+
+    fn apply_parameter_policy(
+        &self,
+        session: &Session,
+        ctx: &mut ProxyContext,
+        json_value: &mut Value,
+    ) -> bool {
+        let (Some(api_id), Some(provider)) = (
+            ctx.routing.user_service_api.as_ref().map(|api| api.id),
+            Self::provider_type(ctx),
+        ) else {
+            return false;
+        };
+
+        let path = session.req_header().uri.path();
+        let model = parameter_policy::requested_model(provider, path, json_value);
+        let Some(rule) = self
+            .config
+            .parameter_policy
+            .find_rule(api_id, model.as_deref())
+        else {
+            return false;
+        };
+
+        let adjustments =
+            parameter_policy::apply_parameter_policy(rule, provider, path, json_value);
+        if adjustments.is_empty() {
+            return false;
+        }
+
+        linfo!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::RequestTransform,
+            "parameter_policy_applied",
+            "已按参数策略调整请求参数",
+            model = ?model,
+            adjustments = ?adjustments
+        );
+        ctx.request.parameter_adjustments.extend(adjustments);
+        true
+    }
+
+    fn max_tokens_ceiling_may_apply(session: &Session, ctx: &ProxyContext) -> bool {
+        session.req_header().method == http::Method::POST
+            && Self::provider_type(ctx).is_some()
+            && ctx
+                .routing
+                .provider_type
+                .as_ref()
+                .and_then(MaxTokensCeiling::from_provider)
+                .is_some()
+    }
+
+    /// 按服务商配置的输出 Token 上限钳制（或注入）请求参数
+    fn apply_max_tokens_ceiling(
+        session: &Session,
+        ctx: &mut ProxyContext,
+        json_value: &mut Value,
+    ) -> bool {
+        let (Some(ceiling), Some(provider)) = (
+            ctx.routing
+                .provider_type
+                .as_ref()
+                .and_then(MaxTokensCeiling::from_provider),
+            Self::provider_type(ctx),
+        ) else {
+            return false;
+        };
+
+        let adjustments = ceiling.apply(provider, session.req_header().uri.path(), json_value);
+        if adjustments.is_empty() {
+            return false;
+        }
+
+        linfo!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::RequestTransform,
+            "max_tokens_ceiling_applied",
+            "已按服务商输出 Token 上限调整请求参数",
+            max_tokens = ceiling.max,
+            adjustments = ?adjustments
+        );
+        ctx.request.parameter_adjustments.extend(adjustments);
+        true
+    }
+
+    fn provider_type(ctx: &ProxyContext) -> Option<ProviderType> {
+        ctx.routing
+            .provider_type
+            .as_ref()
+            .and_then(|provider| ProviderType::from_str(&provider.name))
+    }
+
     /// 确保 Host 头为上游地址，避免下游虚拟主机路由错误
     fn ensure_host_header(upstream_request: &mut RequestHeader, ctx: &ProxyContext) -> Result<()> {
         let Some(provider) = &ctx.routing.provider_type else {
@@ -135,11 +531,82 @@ impl RequestTransformService {
                     .insert_header("Authorization", format!("Bearer {token}"))
                     .context("Failed to set OAuth header")?;
             }
+            // 签名需覆盖最终请求体，在请求体就绪后由 `sign_aws_request` / `sign_hmac_request` 写入
+            ResolvedCredential::AwsSigV4(_) | ResolvedCredential::HmacSignature(_) => {}
         }
 
         Ok(())
     }
 
+    /// 按 SigV4 对上游请求签名
+    ///
+    /// `payload` 必须是最终发往上游的完整请求体；签名后请求体长度固定，改用 `content-length` 发送。
+    pub fn sign_aws_request(
+        upstream_request: &mut RequestHeader,
+        ctx: &ProxyContext,
+        payload: &[u8],
+    ) -> Result<()> {
+        let Some(ResolvedCredential::AwsSigV4(credentials)) = &ctx.routing.resolved_credential
+        else {
+            return Ok(());
+        };
+        let provider = ctx
+            .routing
+            .provider_type
+            .as_ref()
+            .ok_or(AuthError::NotAuthenticated)?;
+        let scope = SigV4Scope::from_provider(provider)?;
+
+        Self::apply_body_length(upstream_request, BodyLength::Known(payload.len()))?;
+        SigV4Signer::new(credentials, &scope).sign(upstream_request, payload, Utc::now())?;
+
+        ldebug!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::RequestTransform,
+            "aws_sigv4_signed",
+            "已按 SigV4 对上游请求签名",
+            region = scope.region.as_str(),
+            service = scope.service.as_str(),
+            body_size = payload.len()
+        );
+        Ok(())
+    }
+
+    /// 按 HMAC-SHA256 对上游请求签名，写入签名头与 UTC 时间戳头
+    ///
+    /// 与 SigV4 相同，`payload` 必须是最终发往上游的完整请求体。
+    pub fn sign_hmac_request(
+        upstream_request: &mut RequestHeader,
+        ctx: &ProxyContext,
+        payload: &[u8],
+    ) -> Result<()> {
+        let Some(ResolvedCredential::HmacSignature(secret)) = &ctx.routing.resolved_credential
+        else {
+            return Ok(());
+        };
+        let provider = ctx
+            .routing
+            .provider_type
+            .as_ref()
+            .ok_or(AuthError::NotAuthenticated)?;
+        let config = HmacSignatureConfig::from_provider(provider)?;
+
+        Self::apply_body_length(upstream_request, BodyLength::Known(payload.len()))?;
+        HmacSigner::new(secret, &config).sign(upstream_request, payload, Utc::now())?;
+
+        ldebug!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::RequestTransform,
+            "hmac_signed",
+            "已按 HMAC-SHA256 对上游请求签名",
+            signature_header = config.signature_header.as_str(),
+            body_size = payload.len()
+        );
+        Ok(())
+    }
+
     /// 清理所有可能的认证头
     fn clear_auth_headers(upstream_request: &mut RequestHeader) {
         upstream_request.remove_header("authorization");
@@ -148,7 +615,41 @@ impl RequestTransformService {
         upstream_request.remove_header("api-key");
     }
 
-    /// 清理代理相关的头部
+    /// 按白名单过滤请求头，返回被丢弃的请求头名称
+    /// 补充客户端未携带的默认请求头，返回实际补充的头部名称
+    fn apply_default_headers(
+        upstream_request: &mut RequestHeader,
+        defaults: Vec<(HeaderName, HeaderValue)>,
+    ) -> Vec<String> {
+        let mut applied = Vec::new();
+        for (name, value) in defaults {
+            if upstream_request.headers.contains_key(&name) {
+                continue;
+            }
+            applied.push(name.to_string());
+            let _ = upstream_request.insert_header(name, value);
+        }
+        applied
+    }
+
+    fn retain_allowed_headers(
+        config: &UpstreamHeadersConfig,
+        upstream_request: &mut RequestHeader,
+    ) -> Vec<String> {
+        let dropped: Vec<String> = upstream_request
+            .headers
+            .keys()
+            .map(http::HeaderName::as_str)
+            .filter(|name| !config.is_allowed(name))
+            .map(str::to_string)
+            .collect();
+        for name in &dropped {
+            upstream_request.remove_header(name.as_str());
+        }
+        dropped
+    }
+
+    /// 清理代理相关的头部（白名单之外的额外拒绝层）
     fn cleanup_headers(upstream_request: &mut RequestHeader) {
         let headers_to_remove = [
             "x-forwarded-for",
@@ -158,6 +659,7 @@ impl RequestTransformService {
             "forwarded",
             "proxy-authorization",
             "via",
+            PROVIDER_OVERRIDE_HEADER,
         ];
         for header in &headers_to_remove {
             upstream_request.remove_header(*header);
@@ -181,6 +683,40 @@ impl RequestTransformService {
         }
     }
 
+    /// 改写请求体时补全缺失的 Content-Type，返回是否补全
+    ///
+    /// 请求体改写只作用于 JSON，但请求头先于请求体发出，此时无法确认请求体内容，
+    /// 因此只补全缺失的头；显式声明的类型保持不变，由请求体阶段按实际内容决定是否改写。
+    fn ensure_json_content_type(upstream_request: &mut RequestHeader, ctx: &ProxyContext) -> bool {
+        if !ctx.request.will_modify_body || upstream_request.headers.get("content-type").is_some() {
+            return false;
+        }
+        if !matches!(upstream_request.method.as_str(), "POST" | "PUT" | "PATCH") {
+            return false;
+        }
+        upstream_request
+            .insert_header("content-type", "application/json")
+            .is_ok()
+    }
+
+    /// 已读取完整请求体时按实际内容校正 Content-Type，返回是否校正
+    ///
+    /// 请求体能解析为 JSON 而声明的类型缺失或不是 JSON 时改为 `application/json`；
+    /// 非 JSON 请求体保持原样。
+    pub fn correct_json_content_type(upstream_request: &mut RequestHeader, body: &[u8]) -> bool {
+        let declared_json = upstream_request
+            .headers
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(content_type_is_json);
+        if declared_json || serde_json::from_slice::<Value>(body).is_err() {
+            return false;
+        }
+        upstream_request
+            .insert_header("content-type", "application/json")
+            .is_ok()
+    }
+
     /// 处理 Content-Length
     fn handle_content_length(
         session: &Session,
@@ -190,15 +726,241 @@ impl RequestTransformService {
         let is_sse = session.req_header().uri.path().contains("stream"); // Simplified check
 
         if ctx.request.will_modify_body || is_sse {
-            upstream_request.remove_header("content-length");
+            let _ = Self::apply_body_length(upstream_request, BodyLength::Chunked);
         } else {
             let method = upstream_request.method.as_str();
             if (method == "POST" || method == "PUT" || method == "PATCH")
                 && upstream_request.headers.get("content-length").is_none()
                 && upstream_request.headers.get("transfer-encoding").is_none()
             {
-                let _ = upstream_request.insert_header("content-length", "0");
+                let _ = Self::apply_body_length(upstream_request, BodyLength::Known(0));
+            }
+        }
+    }
+
+    /// 按最终请求体长度设置 Content-Length / Transfer-Encoding
+    ///
+    /// 请求体被改写后原始的长度头不再可信，所有改写请求体的路径都应经由此处更新长度头：
+    /// 长度已知（含空请求体）时写入 `content-length` 并移除 `transfer-encoding`；
+    /// 请求头先于改写完成发出时移除 `content-length`，由 Pingora 按分块发送。
+    pub fn apply_body_length(
+        upstream_request: &mut RequestHeader,
+        length: BodyLength,
+    ) -> Result<()> {
+        match length {
+            BodyLength::Known(len) => {
+                upstream_request.remove_header("transfer-encoding");
+                upstream_request
+                    .insert_header("content-length", len.to_string())
+                    .context("Failed to set content-length header")?;
+            }
+            BodyLength::Chunked => {
+                upstream_request.remove_header("content-length");
             }
         }
+        Ok(())
+    }
+
+    /// 将改写后的 JSON 请求体重新序列化
+    pub fn encode_json_body(json_value: &Value) -> Result<Bytes> {
+        serde_json::to_vec(json_value)
+            .map(Bytes::from)
+            .context("Failed to serialize request body")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upstream_request(headers: &[(&'static str, &'static str)]) -> RequestHeader {
+        let mut req = RequestHeader::build("POST", b"/v1/chat/completions", None).unwrap();
+        for (name, value) in headers {
+            req.append_header(*name, *value).unwrap();
+        }
+        req
+    }
+
+    #[test]
+    fn drops_headers_outside_allowlist() {
+        let mut req = upstream_request(&[
+            ("content-type", "application/json"),
+            ("anthropic-version", "2023-06-01"),
+            ("x-stainless-lang", "python"),
+            ("content-length", "42"),
+            ("x-internal-user", "alice"),
+            ("cookie", "session=secret"),
+        ]);
+
+        let mut dropped = RequestTransformService::retain_allowed_headers(
+            &UpstreamHeadersConfig::default(),
+            &mut req,
+        );
+        dropped.sort();
+
+        assert_eq!(dropped, vec!["cookie", "x-internal-user"]);
+        for kept in [
+            "content-type",
+            "anthropic-version",
+            "x-stainless-lang",
+            "content-length",
+        ] {
+            assert!(
+                req.headers.get(kept).is_some(),
+                "{kept} should be forwarded"
+            );
+        }
+        assert!(req.headers.get("x-internal-user").is_none());
+    }
+
+    #[test]
+    fn configured_allowlist_replaces_defaults() {
+        let config = UpstreamHeadersConfig {
+            allowlist_enabled: true,
+            allowed: vec!["x-tenant-*".to_string()],
+        };
+        let mut req =
+            upstream_request(&[("x-tenant-id", "t-1"), ("content-type", "application/json")]);
+
+        let dropped = RequestTransformService::retain_allowed_headers(&config, &mut req);
+
+        assert_eq!(dropped, vec!["content-type"]);
+        assert!(req.headers.get("x-tenant-id").is_some());
+    }
+
+    #[test]
+    fn client_header_wins_over_provider_default() {
+        let defaults = parse_default_request_headers(&serde_json::json!({
+            "anthropic-beta": "prompt-caching-2024-07-31",
+            "X-Region": "us"
+        }))
+        .unwrap();
+        let mut req = upstream_request(&[("anthropic-beta", "tools-2024-04-04")]);
+
+        let applied = RequestTransformService::apply_default_headers(&mut req, defaults);
+
+        assert_eq!(applied, vec!["x-region"]);
+        assert_eq!(
+            req.headers.get("anthropic-beta").unwrap(),
+            "tools-2024-04-04"
+        );
+        assert_eq!(req.headers.get("x-region").unwrap(), "us");
+    }
+
+    #[test]
+    fn provider_default_applied_when_client_header_absent() {
+        let now = Utc::now().naive_utc();
+        let mut provider = provider_types::Model {
+            id: 1,
+            name: "anthropic".to_string(),
+            display_name: "Anthropic".to_string(),
+            auth_type: "api_key".to_string(),
+            base_url: "api.anthropic.com".to_string(),
+            is_active: true,
+            config_json: Some(
+                r#"{"default_headers":{"anthropic-beta":"prompt-caching-2024-07-31"}}"#.to_string(),
+            ),
+            token_mappings_json: None,
+            model_extraction_json: None,
+            auth_configs_json: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let mut req = upstream_request(&[("content-type", "application/json")]);
+
+        RequestTransformService::apply_default_headers(
+            &mut req,
+            provider_default_headers(&provider),
+        );
+        assert_eq!(
+            req.headers.get("anthropic-beta").unwrap(),
+            "prompt-caching-2024-07-31"
+        );
+
+        provider.config_json = None;
+        assert!(provider_default_headers(&provider).is_empty());
+        assert!(
+            parse_default_request_headers(&serde_json::json!({"authorization": "Bearer x"}))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn json_body_without_content_type_gets_json_header() {
+        let mut ctx = ProxyContext::default();
+        ctx.request.will_modify_body = true;
+        let mut req = upstream_request(&[]);
+
+        assert!(RequestTransformService::ensure_json_content_type(
+            &mut req, &ctx
+        ));
+        assert_eq!(req.headers.get("content-type").unwrap(), "application/json");
+
+        // 请求体读取后确认是 JSON，错误声明的类型被校正
+        let mut req = upstream_request(&[("content-type", "text/plain")]);
+        assert!(RequestTransformService::correct_json_content_type(
+            &mut req,
+            br#"{"model":"gpt-4o"}"#
+        ));
+        assert_eq!(req.headers.get("content-type").unwrap(), "application/json");
+    }
+
+    #[test]
+    fn non_json_body_keeps_declared_content_type() {
+        let mut ctx = ProxyContext::default();
+        ctx.request.will_modify_body = true;
+        let mut req = upstream_request(&[("content-type", "multipart/form-data; boundary=x")]);
+
+        assert!(!RequestTransformService::ensure_json_content_type(
+            &mut req, &ctx
+        ));
+        assert!(!RequestTransformService::correct_json_content_type(
+            &mut req,
+            b"--x\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\nhello\r\n--x--"
+        ));
+        assert_eq!(
+            req.headers.get("content-type").unwrap(),
+            "multipart/form-data; boundary=x"
+        );
+
+        // 不改写请求体时不补全
+        ctx.request.will_modify_body = false;
+        let mut req = upstream_request(&[]);
+        assert!(!RequestTransformService::ensure_json_content_type(
+            &mut req, &ctx
+        ));
+        assert!(req.headers.get("content-type").is_none());
+    }
+
+    #[test]
+    fn content_length_matches_rewritten_body() {
+        let mut req =
+            upstream_request(&[("content-length", "17"), ("transfer-encoding", "chunked")]);
+        let mut body = serde_json::json!({"model": "gpt-4o", "messages": []});
+        body["stream"] = Value::Bool(false);
+        body["max_tokens"] = Value::from(1024);
+
+        let encoded = RequestTransformService::encode_json_body(&body).unwrap();
+        RequestTransformService::apply_body_length(&mut req, BodyLength::Known(encoded.len()))
+            .unwrap();
+
+        assert_eq!(
+            req.headers.get("content-length").unwrap(),
+            encoded.len().to_string().as_str()
+        );
+        assert!(req.headers.get("transfer-encoding").is_none());
+        assert_eq!(serde_json::from_slice::<Value>(&encoded).unwrap(), body);
+    }
+
+    #[test]
+    fn body_length_handles_empty_and_chunked_bodies() {
+        let mut req = upstream_request(&[("transfer-encoding", "chunked")]);
+        RequestTransformService::apply_body_length(&mut req, BodyLength::Known(0)).unwrap();
+        assert_eq!(req.headers.get("content-length").unwrap(), "0");
+        assert!(req.headers.get("transfer-encoding").is_none());
+
+        let mut req = upstream_request(&[("content-length", "42")]);
+        RequestTransformService::apply_body_length(&mut req, BodyLength::Chunked).unwrap();
+        assert!(req.headers.get("content-length").is_none());
     }
 }
@@ -1,4 +1,12 @@
+use crate::error::ProxyError;
 use crate::error::auth::{AuthError, UsageLimitInfo, UsageLimitKind};
+use crate::error::key_pool::KeyPoolError;
+use crate::error::reject::{REJECT_REASON_HEADER, RejectReason};
+use crate::proxy::format_mismatch::FormatMismatch;
+use crate::proxy::json_guard::JsonComplexityViolation;
+use crate::proxy::maintenance::MaintenanceState;
+use crate::proxy::tool_validation::ToolValidationError;
+use crate::utils::request_id::REQUEST_ID_HEADER;
 use bytes::Bytes;
 use pingora_core::{Error as PingoraError, ErrorType, Result as PingoraResult};
 use pingora_http::ResponseHeader;
@@ -9,17 +17,26 @@ use std::fmt::Write;
 /// 统一的 JSON 错误响应结构
 pub struct JsonError {
     pub status: u16,
+    pub reason: RejectReason,
     pub payload: Value,
     pub message: String,
+    /// 需要时附带 `Retry-After` 响应头（秒）
+    pub retry_after_secs: Option<u64>,
 }
 
 #[must_use]
 pub fn format_rate_limit_message(info: &UsageLimitInfo) -> String {
+    if info.kind == UsageLimitKind::Credits {
+        return format_credits_message(info);
+    }
     let kind_label = match info.kind {
         UsageLimitKind::PerMinute => "每分钟请求",
         UsageLimitKind::DailyRequests => "每日请求次数",
         UsageLimitKind::DailyTokens => "每日 Token 用量",
         UsageLimitKind::DailyCost => "每日成本",
+        UsageLimitKind::ProviderPerMinute => "提供商每分钟请求",
+        UsageLimitKind::ModelPerMinute => "模型每分钟请求",
+        UsageLimitKind::Credits => "积分余额",
     };
 
     let mut message = format!("已达到{kind_label}上限");
@@ -52,22 +69,58 @@ pub fn format_rate_limit_message(info: &UsageLimitInfo) -> String {
     message
 }
 
+/// 积分余额不足的提示：给出需要预留的积分与当前可用积分
+fn format_credits_message(info: &UsageLimitInfo) -> String {
+    let mut message = "积分余额不足".to_string();
+    if let (Some(required), Some(available)) = (info.limit, info.current) {
+        let _ = write!(
+            message,
+            "（需要预留 {}，可用 {}）",
+            format_quantity(required),
+            format_quantity(available)
+        );
+    }
+    message.push('。');
+    message
+}
+
 #[must_use]
 pub fn build_auth_error_response(err: &AuthError) -> JsonError {
+    let reason = RejectReason::from_auth_error(err);
     match err {
         AuthError::UsageLimitExceeded(info) => {
             let message = format_rate_limit_message(info);
             let payload = json!({
                 "error": {
                     "type": "usage_limit_reached",
+                    "reason_code": reason,
                     "message": message,
                     "plan_type": info.plan_type
                 }
             });
             JsonError {
                 status: 429,
+                reason,
+                payload,
+                message,
+                retry_after_secs: None,
+            }
+        }
+        AuthError::ProviderNotAllowed(_) | AuthError::PathNotAllowed(_) => {
+            let message = build_auth_failure_message(err);
+            let payload = json!({
+                "error": {
+                    "type": "permission_denied",
+                    "reason_code": reason,
+                    "message": message
+                }
+            });
+            JsonError {
+                status: 403,
+                reason,
                 payload,
                 message,
+                retry_after_secs: None,
             }
         }
         other => {
@@ -75,23 +128,292 @@ pub fn build_auth_error_response(err: &AuthError) -> JsonError {
             let payload = json!({
                 "error": {
                     "type": "authentication_failed",
+                    "reason_code": reason,
                     "message": message
                 }
             });
             JsonError {
                 status: 401,
+                reason,
                 payload,
                 message,
+                retry_after_secs: None,
             }
         }
     }
 }
 
+/// 将拒绝类错误（认证、限流、预算、密钥配额、暂时不可用）转换为结构化 JSON 响应；其它错误返回 `None`
+#[must_use]
+pub fn build_rejection_response(err: &ProxyError) -> Option<JsonError> {
+    match err {
+        ProxyError::Authentication(auth_err) => Some(build_auth_error_response(auth_err)),
+        ProxyError::KeyPool(key_err @ KeyPoolError::DailyQuotaExhausted { .. }) => {
+            let reason = RejectReason::KeyQuotaExhausted;
+            let message = "当前服务的上游密钥均已达到每日请求上限，请明日再试".to_string();
+            Some(JsonError {
+                status: 429,
+                reason,
+                payload: json!({
+                    "error": {
+                        "type": "usage_limit_reached",
+                        "reason_code": reason,
+                        "message": message,
+                        "detail": key_err.to_string()
+                    }
+                }),
+                message,
+                retry_after_secs: None,
+            })
+        }
+        ProxyError::ServiceUnavailable {
+            message,
+            retry_after_secs,
+        } => {
+            let reason = RejectReason::ServiceUnavailable;
+            Some(JsonError {
+                status: 503,
+                reason,
+                payload: json!({
+                    "error": {
+                        "type": "service_unavailable",
+                        "reason_code": reason,
+                        "message": message,
+                        "retry_after": retry_after_secs
+                    }
+                }),
+                message: message.clone(),
+                retry_after_secs: Some(*retry_after_secs),
+            })
+        }
+        ProxyError::Context { source, .. } => build_rejection_response(source),
+        _ => None,
+    }
+}
+
+/// 请求的模型不在服务商模型列表中
+#[must_use]
+pub fn build_model_not_found_response(
+    model: &str,
+    provider: &str,
+    suggestions: &[String],
+) -> JsonError {
+    let reason = RejectReason::ModelNotFound;
+    let message = if suggestions.is_empty() {
+        format!("模型 {model} 在服务商 {provider} 中不存在")
+    } else {
+        format!(
+            "模型 {model} 在服务商 {provider} 中不存在，相近的模型：{}",
+            suggestions.join(", ")
+        )
+    };
+    let payload = json!({
+        "error": {
+            "type": "model_not_found",
+            "reason_code": reason,
+            "message": message,
+            "model": model,
+            "suggestions": suggestions
+        }
+    });
+    JsonError {
+        status: 404,
+        reason,
+        payload,
+        message,
+        retry_after_secs: None,
+    }
+}
+
+/// 请求体格式与目标服务商接口不匹配
+#[must_use]
+pub fn build_format_mismatch_response(mismatch: &FormatMismatch, provider: &str) -> JsonError {
+    let reason = RejectReason::FormatMismatch;
+    let message = format!("{}（服务商 {provider}）", mismatch.message());
+    let payload = json!({
+        "error": {
+            "type": "invalid_request_error",
+            "reason_code": reason,
+            "message": message,
+            "detected_format": mismatch.detected,
+            "expected_format": mismatch.expected,
+            "fields": mismatch.fields
+        }
+    });
+    JsonError {
+        status: 400,
+        reason,
+        payload,
+        message,
+        retry_after_secs: None,
+    }
+}
+
+/// 工具（函数调用）定义无效
+#[must_use]
+pub fn build_invalid_tool_response(error: &ToolValidationError, provider: &str) -> JsonError {
+    let reason = RejectReason::InvalidToolDefinition;
+    let message = format!("{}（服务商 {provider}）", error.message());
+    let payload = json!({
+        "error": {
+            "type": "invalid_request_error",
+            "reason_code": reason,
+            "message": message,
+            "field": error.field
+        }
+    });
+    JsonError {
+        status: 400,
+        reason,
+        payload,
+        message,
+        retry_after_secs: None,
+    }
+}
+
+/// 请求体 JSON 超过复杂度上限（嵌套深度、元素总数）
+#[must_use]
+pub fn build_json_too_complex_response(violation: JsonComplexityViolation) -> JsonError {
+    let reason = RejectReason::JsonTooComplex;
+    let message = violation.message();
+    let payload = json!({
+        "error": {
+            "type": "invalid_request_error",
+            "reason_code": reason,
+            "message": message
+        }
+    });
+    JsonError {
+        status: 400,
+        reason,
+        payload,
+        message,
+        retry_after_secs: None,
+    }
+}
+
+/// 服务 API 的流式策略为 `deny` 时拒绝流式请求
+#[must_use]
+pub fn build_streaming_denied_response() -> JsonError {
+    let reason = RejectReason::StreamingNotAllowed;
+    let message = "当前服务 API 不允许流式请求，请关闭 stream 后重试".to_string();
+    let payload = json!({
+        "error": {
+            "type": "invalid_request_error",
+            "reason_code": reason,
+            "message": message
+        }
+    });
+    JsonError {
+        status: 400,
+        reason,
+        payload,
+        message,
+        retry_after_secs: None,
+    }
+}
+
+/// 维护模式下拒绝代理请求
+#[must_use]
+pub fn build_maintenance_response(state: &MaintenanceState) -> JsonError {
+    let reason = RejectReason::Maintenance;
+    let payload = json!({
+        "error": {
+            "type": "service_unavailable",
+            "reason_code": reason,
+            "message": state.message,
+            "retry_after": state.retry_after_secs
+        }
+    });
+    JsonError {
+        status: 503,
+        reason,
+        payload,
+        message: state.message.clone(),
+        retry_after_secs: Some(state.retry_after_secs),
+    }
+}
+
+/// 在错误响应体的 `error` 对象中附加 `request_id`
+#[must_use]
+pub fn payload_with_request_id(payload: &Value, request_id: &str) -> Value {
+    let mut payload = payload.clone();
+    if let Some(error) = payload.get_mut("error").and_then(Value::as_object_mut) {
+        error.insert("request_id".to_string(), json!(request_id));
+    }
+    payload
+}
+
+/// 代理失败（非业务拒绝）时的错误响应体
+#[must_use]
+pub fn build_proxy_failure_payload(status: u16) -> Value {
+    let message = match status {
+        400 => "请求无效",
+        502 => "上游服务不可用或响应异常",
+        504 => "上游服务响应超时",
+        _ => "代理处理请求失败",
+    };
+    json!({
+        "error": {
+            "type": "proxy_error",
+            "code": status,
+            "message": message
+        }
+    })
+}
+
 pub async fn write_json_error(
+    session: &mut Session,
+    request_id: &str,
+    error: &JsonError,
+) -> PingoraResult<()> {
+    write_json_payload(
+        session,
+        error.status,
+        Some(error.reason),
+        error.retry_after_secs,
+        request_id,
+        &error.payload,
+    )
+    .await
+}
+
+/// 以 JSON 形式返回代理失败响应（携带 `request_id`）
+pub async fn write_proxy_failure(
+    session: &mut Session,
+    request_id: &str,
+    status: u16,
+) -> PingoraResult<()> {
+    write_json_payload(
+        session,
+        status,
+        None,
+        None,
+        request_id,
+        &build_proxy_failure_payload(status),
+    )
+    .await
+}
+
+/// 以 JSON 形式返回探针响应（携带 `request_id`）
+pub async fn write_probe_response(
+    session: &mut Session,
+    request_id: &str,
+    status: u16,
+    payload: &Value,
+) -> PingoraResult<()> {
+    write_json_payload(session, status, None, None, request_id, payload).await
+}
+
+async fn write_json_payload(
     session: &mut Session,
     status: u16,
-    payload: Value,
+    reason: Option<RejectReason>,
+    retry_after_secs: Option<u64>,
+    request_id: &str,
+    payload: &Value,
 ) -> PingoraResult<()> {
+    let payload = payload_with_request_id(payload, request_id);
     let body = match serde_json::to_vec(&payload) {
         Ok(bytes) => bytes,
         Err(err) => {
@@ -102,7 +424,7 @@ pub async fn write_json_error(
         }
     };
 
-    let mut resp = match ResponseHeader::build(status, Some(4)) {
+    let mut resp = match ResponseHeader::build(status, Some(6)) {
         Ok(header) => header,
         Err(err) => {
             return Err(PingoraError::explain(
@@ -124,6 +446,28 @@ pub async fn write_json_error(
             format!("Failed to set cache-control header: {err}"),
         ));
     }
+    if let Some(reason) = reason
+        && let Err(err) = resp.insert_header(REJECT_REASON_HEADER, reason.as_str())
+    {
+        return Err(PingoraError::explain(
+            ErrorType::InternalError,
+            format!("Failed to set reject reason header: {err}"),
+        ));
+    }
+    if let Some(secs) = retry_after_secs
+        && let Err(err) = resp.insert_header("retry-after", secs.to_string())
+    {
+        return Err(PingoraError::explain(
+            ErrorType::InternalError,
+            format!("Failed to set retry-after header: {err}"),
+        ));
+    }
+    if let Err(err) = resp.insert_header(REQUEST_ID_HEADER, request_id) {
+        return Err(PingoraError::explain(
+            ErrorType::InternalError,
+            format!("Failed to set request id header: {err}"),
+        ));
+    }
     if let Err(err) = resp.set_content_length(body.len()) {
         return Err(PingoraError::explain(
             ErrorType::InternalError,
@@ -164,6 +508,11 @@ fn build_auth_failure_message(err: &AuthError) -> String {
         AuthError::PermissionDenied { required, actual } => {
             format!("权限不足，操作需要权限 {required}，当前权限 {actual}")
         }
+        AuthError::ProviderNotAllowed(provider) => {
+            format!("当前 API Key 不允许使用服务商 {provider}")
+        }
+        AuthError::PathNotAllowed(path) => format!("当前 API Key 不允许访问路径 {path}"),
+        AuthError::UnsupportedAuthType(auth_type) => format!("不支持的认证类型：{auth_type}"),
         AuthError::HeaderParse(e) => format!("认证头解析失败：{e}"),
         AuthError::OAuth(e) => format!("OAuth 流程发生异常：{e}"),
         AuthError::Pkce(e) => format!("PKCE 验证失败：{e}"),
@@ -174,3 +523,135 @@ fn build_auth_failure_message(err: &AuthError) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Context;
+
+    fn usage_limit(kind: UsageLimitKind) -> ProxyError {
+        AuthError::UsageLimitExceeded(UsageLimitInfo {
+            kind,
+            limit: Some(10.0),
+            current: Some(10.0),
+            resets_in: None,
+            plan_type: "basic".to_string(),
+        })
+        .into()
+    }
+
+    fn assert_rejection(err: &ProxyError, status: u16, code: &str) {
+        let rejection = build_rejection_response(err).expect("rejection response");
+        assert_eq!(rejection.status, status);
+        assert_eq!(rejection.reason.as_str(), code);
+        assert_eq!(rejection.payload["error"]["reason_code"], code);
+    }
+
+    #[test]
+    fn auth_rejections_carry_reason_codes() {
+        let cases = [
+            (AuthError::ApiKeyMissing, "auth_missing"),
+            (AuthError::ApiKeyInvalid(String::new()), "auth_invalid"),
+            (AuthError::ApiKeyMalformed, "auth_malformed"),
+            (AuthError::ApiKeyInactive, "auth_inactive"),
+            (
+                AuthError::PermissionDenied {
+                    required: "admin".to_string(),
+                    actual: "user".to_string(),
+                },
+                "auth_permission_denied",
+            ),
+            (AuthError::Message("expired".to_string()), "auth_failed"),
+        ];
+        for (err, code) in cases {
+            assert_rejection(&err.into(), 401, code);
+        }
+    }
+
+    #[test]
+    fn rate_limit_and_budget_rejections_carry_reason_codes() {
+        let cases = [
+            (UsageLimitKind::PerMinute, "rate_limit_per_minute"),
+            (UsageLimitKind::DailyRequests, "rate_limit_daily_requests"),
+            (
+                UsageLimitKind::ProviderPerMinute,
+                "rate_limit_provider_per_minute",
+            ),
+            (
+                UsageLimitKind::ModelPerMinute,
+                "rate_limit_model_per_minute",
+            ),
+            (UsageLimitKind::DailyTokens, "budget_daily_tokens"),
+            (UsageLimitKind::DailyCost, "budget_daily_cost"),
+            (UsageLimitKind::Credits, "credits_insufficient"),
+        ];
+        for (kind, code) in cases {
+            assert_rejection(&usage_limit(kind), 429, code);
+        }
+    }
+
+    #[test]
+    fn key_quota_rejection_unwraps_context() {
+        let err = Err::<(), _>(KeyPoolError::DailyQuotaExhausted { service_api_id: 7 })
+            .context("选择密钥失败")
+            .unwrap_err();
+        assert_rejection(&err, 429, "key_quota_exhausted");
+    }
+
+    #[test]
+    fn model_not_found_carries_reason_code() {
+        let rejection =
+            build_model_not_found_response("gpt-4o-mni", "openai", &["gpt-4o-mini".to_string()]);
+        assert_eq!(rejection.status, 404);
+        assert_eq!(rejection.reason, RejectReason::ModelNotFound);
+        assert_eq!(rejection.payload["error"]["reason_code"], "model_not_found");
+        assert_eq!(rejection.payload["error"]["suggestions"][0], "gpt-4o-mini");
+    }
+
+    #[test]
+    fn error_payloads_carry_request_id() {
+        let request_id = "01ARYZ6S41TSV4RRFFQ69G5FAV";
+        let rejection =
+            build_model_not_found_response("gpt-4o-mni", "openai", &["gpt-4o-mini".to_string()]);
+        let payload = payload_with_request_id(&rejection.payload, request_id);
+        assert_eq!(payload["error"]["request_id"], request_id);
+        assert_eq!(payload["error"]["reason_code"], "model_not_found");
+
+        let failure = payload_with_request_id(&build_proxy_failure_payload(502), request_id);
+        assert_eq!(failure["error"]["type"], "proxy_error");
+        assert_eq!(failure["error"]["code"], 502);
+        assert_eq!(failure["error"]["request_id"], request_id);
+    }
+
+    #[test]
+    fn maintenance_rejection_carries_retry_after() {
+        let state = MaintenanceState {
+            enabled: true,
+            message: "升级数据库".to_string(),
+            retry_after_secs: 120,
+            updated_at: None,
+        };
+        let rejection = build_maintenance_response(&state);
+        assert_eq!(rejection.status, 503);
+        assert_eq!(rejection.reason, RejectReason::Maintenance);
+        assert_eq!(rejection.retry_after_secs, Some(120));
+        assert_eq!(rejection.payload["error"]["reason_code"], "maintenance");
+        assert_eq!(rejection.payload["error"]["message"], "升级数据库");
+    }
+
+    #[test]
+    fn service_unavailable_rejection_unwraps_context() {
+        let err = Err::<(), _>(ProxyError::service_unavailable("数据库连接池繁忙", 5))
+            .context("查询服务 API 失败")
+            .unwrap_err();
+        assert_rejection(&err, 503, "service_unavailable");
+        let rejection = build_rejection_response(&err).expect("rejection response");
+        assert_eq!(rejection.retry_after_secs, Some(5));
+    }
+
+    #[test]
+    fn non_rejection_errors_are_ignored() {
+        let err: ProxyError = KeyPoolError::NoAvailableKeys.into();
+        assert!(build_rejection_response(&err).is_none());
+    }
+}
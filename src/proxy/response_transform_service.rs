@@ -1,28 +1,111 @@
 //! # 响应转换服务
 //!
 //! 负责修改从上游返回的响应头，例如添加CORS头、移除敏感信息等。
+//! 服务 API 可配置静态的自定义响应头（如 `X-Powered-By`），但不能覆盖传输层与代理自身依赖的头部。
+//! 白标模式下额外移除暴露上游服务商的头部，并按配置改写响应体中的模型名（见 `model_alias.rs`）。
 
+use crate::config::{AppConfig, ResponseHeadersConfig, WhiteLabelConfig};
+use crate::error::conversion::ConversionError;
+use crate::error::reject::REJECT_REASON_HEADER;
 use crate::error::{Context, Result};
-use crate::linfo;
 use crate::logging::{LogComponent, LogStage};
 use crate::proxy::context::ProxyContext;
+use crate::proxy::model_alias::ModelAliasRewriter;
+use crate::proxy::upstream_error::{UpstreamErrorMode, UpstreamErrorSanitizer};
+use crate::utils::request_id::REQUEST_ID_HEADER;
+use crate::{ldebug, linfo, lwarn};
+use http::{HeaderName, HeaderValue};
 use pingora_http::ResponseHeader;
 use pingora_proxy::Session;
+use serde_json::Value;
+use std::sync::Arc;
 
-/// 响应转换服务
-pub struct ResponseTransformService;
+/// 上游自带请求 ID 的保留头
+const UPSTREAM_REQUEST_ID_HEADER: &str = "x-upstream-request-id";
+
+/// 单个服务 API 允许配置的自定义响应头上限
+const MAX_CUSTOM_RESPONSE_HEADERS: usize = 32;
+
+/// 自定义响应头不能覆盖的头部：传输层、缓存与流式控制，以及代理自身写入的头部
+const PROTECTED_RESPONSE_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+    "content-length",
+    "content-type",
+    "content-encoding",
+    "cache-control",
+    "x-accel-buffering",
+    "retry-after",
+    "set-cookie",
+    REQUEST_ID_HEADER,
+    UPSTREAM_REQUEST_ID_HEADER,
+    REJECT_REASON_HEADER,
+];
+
+/// 按前缀保护的头部（CORS、逐跳代理头、WebSocket 握手）
+const PROTECTED_RESPONSE_HEADER_PREFIXES: &[&str] =
+    &["access-control-", "proxy-", "sec-websocket-"];
 
-impl Default for ResponseTransformService {
-    fn default() -> Self {
-        Self::new()
+/// 是否为自定义响应头不能覆盖的头部
+#[must_use]
+pub fn is_protected_response_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    PROTECTED_RESPONSE_HEADERS.contains(&name.as_str())
+        || PROTECTED_RESPONSE_HEADER_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// 严格解析服务 API 的自定义响应头（JSON 对象：头部名称 -> 字符串值）
+///
+/// 头部名称统一转为小写；名称或值不合法、命中受保护头部时返回错误。
+pub fn parse_custom_response_headers(value: &Value) -> Result<Vec<(HeaderName, HeaderValue)>> {
+    let entries = value
+        .as_object()
+        .ok_or_else(|| ConversionError::message("response_headers 必须是对象"))?;
+    if entries.len() > MAX_CUSTOM_RESPONSE_HEADERS {
+        return Err(ConversionError::message(format!(
+            "response_headers 最多 {MAX_CUSTOM_RESPONSE_HEADERS} 项"
+        ))
+        .into());
     }
+
+    let mut headers = Vec::with_capacity(entries.len());
+    for (name, value) in entries {
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+            ConversionError::message(format!("response_headers: 非法的头部名称: {name}"))
+        })?;
+        if is_protected_response_header(header_name.as_str()) {
+            return Err(ConversionError::message(format!(
+                "response_headers: 不允许覆盖受保护的头部: {name}"
+            ))
+            .into());
+        }
+        let header_value = value
+            .as_str()
+            .and_then(|value| HeaderValue::from_str(value).ok())
+            .ok_or_else(|| {
+                ConversionError::message(format!("response_headers.{name}: 值必须是合法的字符串"))
+            })?;
+        headers.push((header_name, header_value));
+    }
+    Ok(headers)
+}
+
+/// 响应转换服务
+pub struct ResponseTransformService {
+    config: Arc<AppConfig>,
 }
 
 impl ResponseTransformService {
     /// 创建新的响应转换服务
     #[must_use]
-    pub const fn new() -> Self {
-        Self
+    pub const fn new(config: Arc<AppConfig>) -> Self {
+        Self { config }
     }
 
     /// 过滤并转换上游响应
@@ -61,8 +144,40 @@ impl ResponseTransformService {
         // 2. 添加CORS头部，实现跨域支持
         Self::add_cors_headers(upstream_response)?;
 
-        // 3. 清理可能暴露服务器信息的头部
-        Self::cleanup_headers(upstream_response);
+        // 3. 上游错误响应：按服务 API 配置规范化，或原样透传（跳过下面的头部清理与响应体改写）
+        let verbatim_error = Self::prepare_upstream_error(upstream_response, ctx)?;
+
+        // 3.1 按配置清理可能暴露服务器或上游账号信息的头部
+        if !verbatim_error {
+            let provider = ctx.routing.provider_type.as_ref().map(|p| p.name.as_str());
+            let mut stripped =
+                Self::cleanup_headers(&self.config.response_headers, provider, upstream_response);
+            stripped.extend(Self::strip_provider_headers(
+                &self.config.white_label,
+                upstream_response,
+            ));
+            if !stripped.is_empty() {
+                ldebug!(
+                    &ctx.request_id,
+                    LogStage::Response,
+                    LogComponent::ResponseTransform,
+                    "response_headers_stripped",
+                    "已移除上游响应头",
+                    headers = stripped.join(",")
+                );
+            }
+        }
+
+        // 3.2 白标模式：响应体中的模型名改写为别名（规范化的错误响应体不含模型名）
+        if !verbatim_error && ctx.response.upstream_error.is_none() {
+            Self::prepare_model_alias(&self.config.white_label, upstream_response, ctx);
+        }
+
+        // 4. 写入服务 API 配置的自定义响应头
+        Self::apply_service_api_headers(upstream_response, ctx)?;
+
+        // 5. 返回本次请求的 request_id，便于客户端反馈问题时关联追踪记录
+        Self::apply_request_id_header(upstream_response, ctx)?;
 
         linfo!(
             &ctx.request_id,
@@ -173,9 +288,350 @@ impl ResponseTransformService {
             .any(|item| item == directive)
     }
 
-    /// 清理敏感或不必要的响应头
-    fn cleanup_headers(upstream_response: &mut ResponseHeader) {
-        upstream_response.remove_header("x-powered-by");
-        upstream_response.remove_header("server"); // 也可以选择保留或替换
+    /// 处理上游错误响应（状态码 >= 400），返回是否按 `verbatim` 原样透传
+    ///
+    /// `sanitized` 模式下创建响应体改写器：改写后的响应体为未压缩的 JSON，长度随之改变，
+    /// 需要移除 `content-length`、`content-encoding` 并改写 `content-type`。
+    pub fn prepare_upstream_error(
+        upstream_response: &mut ResponseHeader,
+        ctx: &mut ProxyContext,
+    ) -> Result<bool> {
+        let status = upstream_response.status.as_u16();
+        if status < 400 || ctx.request.is_websocket {
+            return Ok(false);
+        }
+        let mode = UpstreamErrorMode::from_context(ctx);
+        if mode == UpstreamErrorMode::Verbatim {
+            return Ok(true);
+        }
+
+        upstream_response.remove_header("content-length");
+        upstream_response.remove_header("content-encoding");
+        upstream_response
+            .insert_header("content-type", "application/json; charset=utf-8")
+            .context("Failed to set upstream error content-type")?;
+        ctx.response.upstream_error = Some(UpstreamErrorSanitizer::new(status, &ctx.request_id));
+        ldebug!(
+            &ctx.request_id,
+            LogStage::Response,
+            LogComponent::ResponseTransform,
+            "upstream_error_sanitized",
+            "上游错误响应将按统一格式返回",
+            status = status,
+            mode = mode.as_str()
+        );
+        Ok(false)
+    }
+
+    /// 写入 `x-request-id`；上游自带的请求 ID 改名为 `x-upstream-request-id` 保留
+    pub fn apply_request_id_header(
+        upstream_response: &mut ResponseHeader,
+        ctx: &ProxyContext,
+    ) -> Result<()> {
+        if let Some(upstream_id) = upstream_response
+            .headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+        {
+            upstream_response
+                .insert_header(UPSTREAM_REQUEST_ID_HEADER, upstream_id)
+                .context("Failed to set upstream request id header")?;
+        }
+        upstream_response
+            .insert_header(REQUEST_ID_HEADER, &ctx.request_id)
+            .context("Failed to set request id header")
+    }
+
+    /// 写入服务 API 的自定义响应头；配置无效时跳过并记录日志，不影响响应
+    fn apply_service_api_headers(
+        upstream_response: &mut ResponseHeader,
+        ctx: &ProxyContext,
+    ) -> Result<()> {
+        let Some(value) = ctx
+            .routing
+            .user_service_api
+            .as_ref()
+            .and_then(|api| api.response_headers.as_ref())
+        else {
+            return Ok(());
+        };
+
+        match parse_custom_response_headers(value) {
+            Ok(headers) => Self::apply_custom_headers(upstream_response, headers),
+            Err(err) => {
+                lwarn!(
+                    &ctx.request_id,
+                    LogStage::Response,
+                    LogComponent::ResponseTransform,
+                    "custom_response_headers_invalid",
+                    "服务 API 自定义响应头配置无效，已跳过",
+                    error = %err
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// 写入已校验的自定义响应头，覆盖上游同名头部
+    pub fn apply_custom_headers(
+        upstream_response: &mut ResponseHeader,
+        headers: Vec<(HeaderName, HeaderValue)>,
+    ) -> Result<()> {
+        for (name, value) in headers {
+            upstream_response
+                .insert_header(name, value)
+                .context("Failed to set custom response header")?;
+        }
+        Ok(())
+    }
+
+    /// 白标模式下移除暴露上游服务商的响应头，返回被移除的头部名称
+    fn strip_provider_headers(
+        config: &WhiteLabelConfig,
+        upstream_response: &mut ResponseHeader,
+    ) -> Vec<String> {
+        let stripped: Vec<String> = upstream_response
+            .headers
+            .keys()
+            .map(|name| name.as_str().to_string())
+            .filter(|name| config.should_strip(name))
+            .collect();
+
+        for name in &stripped {
+            upstream_response.remove_header(name);
+        }
+        stripped
+    }
+
+    /// 配置了模型别名时为 JSON 与 SSE 响应创建响应体改写器
+    ///
+    /// 压缩的响应体无法逐块改写，原样返回；改写会改变响应体长度，需要移除 `content-length`。
+    fn prepare_model_alias(
+        config: &WhiteLabelConfig,
+        upstream_response: &mut ResponseHeader,
+        ctx: &mut ProxyContext,
+    ) {
+        let Some(alias) = config.model_alias() else {
+            return;
+        };
+        if ctx.request.is_websocket
+            || ctx
+                .response
+                .details
+                .content_encoding
+                .as_deref()
+                .is_some_and(|encoding| !encoding.eq_ignore_ascii_case("identity"))
+        {
+            return;
+        }
+        let Some(content_type) = ctx.response.details.content_type.as_deref() else {
+            return;
+        };
+        let is_sse = Self::is_sse_content_type(content_type);
+        if !is_sse && !content_type.to_ascii_lowercase().contains("json") {
+            return;
+        }
+
+        upstream_response.remove_header("content-length");
+        ctx.response.model_alias = Some(ModelAliasRewriter::new(alias, is_sse));
+    }
+
+    /// 按配置清理敏感或不必要的响应头，返回被移除的头部名称
+    fn cleanup_headers(
+        config: &ResponseHeadersConfig,
+        provider: Option<&str>,
+        upstream_response: &mut ResponseHeader,
+    ) -> Vec<String> {
+        let stripped: Vec<String> = upstream_response
+            .headers
+            .keys()
+            .map(|name| name.as_str().to_string())
+            .filter(|name| config.should_strip(provider, name))
+            .collect();
+
+        for name in &stripped {
+            upstream_response.remove_header(name);
+        }
+        stripped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ResponseHeaderRules;
+
+    fn upstream_response() -> ResponseHeader {
+        let mut resp = ResponseHeader::build(200, None).unwrap();
+        for (name, value) in [
+            ("content-type", "application/json"),
+            ("server", "cloudflare"),
+            ("x-ratelimit-remaining-requests", "99"),
+            ("x-ratelimit-remaining", "42"),
+            ("x-request-id", "req_123"),
+        ] {
+            resp.append_header(name, value).unwrap();
+        }
+        resp
+    }
+
+    #[test]
+    fn default_config_strips_rate_limit_headers() {
+        let mut resp = upstream_response();
+
+        let mut stripped = ResponseTransformService::cleanup_headers(
+            &ResponseHeadersConfig::default(),
+            Some("openai"),
+            &mut resp,
+        );
+        stripped.sort();
+
+        assert_eq!(
+            stripped,
+            vec![
+                "server",
+                "x-ratelimit-remaining",
+                "x-ratelimit-remaining-requests"
+            ]
+        );
+        assert!(resp.headers.get("x-ratelimit-remaining").is_none());
+        assert!(resp.headers.get("content-type").is_some());
+        assert!(resp.headers.get("x-request-id").is_some());
+    }
+
+    #[test]
+    fn provider_preserve_list_keeps_rate_limit_headers() {
+        let mut config = ResponseHeadersConfig::default();
+        config.providers.insert(
+            "openai".to_string(),
+            ResponseHeaderRules {
+                strip: vec!["x-request-id".to_string()],
+                preserve: vec!["x-ratelimit-remaining".to_string()],
+            },
+        );
+
+        let mut resp = upstream_response();
+        ResponseTransformService::cleanup_headers(&config, Some("openai"), &mut resp);
+        assert_eq!(
+            resp.headers
+                .get("x-ratelimit-remaining")
+                .and_then(|v| v.to_str().ok()),
+            Some("42")
+        );
+        assert!(resp.headers.get("x-ratelimit-remaining-requests").is_none());
+        assert!(resp.headers.get("x-request-id").is_none());
+        assert!(resp.headers.get("server").is_none());
+
+        // 其他服务商仍使用默认规则
+        let mut resp = upstream_response();
+        ResponseTransformService::cleanup_headers(&config, Some("gemini"), &mut resp);
+        assert!(resp.headers.get("x-ratelimit-remaining").is_none());
+        assert!(resp.headers.get("x-request-id").is_some());
+    }
+
+    #[test]
+    fn request_id_header_replaces_upstream_id() {
+        let ctx = ProxyContext::new_request();
+        let mut resp = upstream_response();
+
+        ResponseTransformService::apply_request_id_header(&mut resp, &ctx).unwrap();
+
+        let header = |name: &str| resp.headers.get(name).and_then(|v| v.to_str().ok());
+        assert_eq!(header("x-request-id"), Some(ctx.request_id.as_str()));
+        assert_eq!(header("x-upstream-request-id"), Some("req_123"));
+    }
+
+    fn white_label(model_alias: Option<&str>) -> WhiteLabelConfig {
+        WhiteLabelConfig {
+            enabled: true,
+            model_alias: model_alias.map(str::to_string),
+            ..WhiteLabelConfig::default()
+        }
+    }
+
+    #[test]
+    fn white_label_strips_provider_headers() {
+        let ctx = ProxyContext::new_request();
+        let mut resp = upstream_response();
+        for (name, value) in [
+            ("anthropic-organization-id", "org_1"),
+            ("openai-processing-ms", "120"),
+            ("openai-version", "2020-10-01"),
+            ("cf-ray", "8a1b2c3d"),
+        ] {
+            resp.append_header(name, value).unwrap();
+        }
+
+        assert!(
+            ResponseTransformService::strip_provider_headers(
+                &WhiteLabelConfig::default(),
+                &mut resp
+            )
+            .is_empty()
+        );
+
+        let mut stripped =
+            ResponseTransformService::strip_provider_headers(&white_label(None), &mut resp);
+        stripped.sort();
+        assert_eq!(
+            stripped,
+            vec![
+                "anthropic-organization-id",
+                "cf-ray",
+                "openai-processing-ms",
+                "openai-version",
+                "x-request-id"
+            ]
+        );
+        assert!(resp.headers.get("content-type").is_some());
+
+        // 上游请求 ID 已移除，不再保留为 x-upstream-request-id
+        ResponseTransformService::apply_request_id_header(&mut resp, &ctx).unwrap();
+        assert!(resp.headers.get("x-upstream-request-id").is_none());
+    }
+
+    #[test]
+    fn model_alias_applies_only_when_configured() {
+        let mut ctx = ProxyContext::new_request();
+        ctx.response.details.content_type = Some("application/json".to_string());
+        let mut resp = upstream_response();
+        resp.insert_header("content-length", "42").unwrap();
+
+        ResponseTransformService::prepare_model_alias(&white_label(None), &mut resp, &mut ctx);
+        assert!(ctx.response.model_alias.is_none());
+        assert!(resp.headers.get("content-length").is_some());
+
+        ResponseTransformService::prepare_model_alias(
+            &white_label(Some("acme-chat")),
+            &mut resp,
+            &mut ctx,
+        );
+        assert!(resp.headers.get("content-length").is_none());
+        let rewritten = ctx
+            .response
+            .model_alias
+            .as_mut()
+            .unwrap()
+            .push(
+                Some(&bytes::Bytes::from_static(
+                    br#"{"model":"gpt-4o","choices":[]}"#,
+                )),
+                true,
+            )
+            .unwrap();
+        let value: Value = serde_json::from_slice(&rewritten).unwrap();
+        assert_eq!(value["model"], "acme-chat");
+
+        // 压缩的响应体不改写
+        let mut ctx = ProxyContext::new_request();
+        ctx.response.details.content_type = Some("application/json".to_string());
+        ctx.response.details.content_encoding = Some("gzip".to_string());
+        ResponseTransformService::prepare_model_alias(
+            &white_label(Some("acme-chat")),
+            &mut upstream_response(),
+            &mut ctx,
+        );
+        assert!(ctx.response.model_alias.is_none());
     }
 }
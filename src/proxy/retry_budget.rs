@@ -0,0 +1,113 @@
+//! # 重试预算
+//!
+//! 按服务商类型维护重试令牌桶：每次重试消耗一个令牌，令牌按配置速率补充，桶满后不再累积。
+//! 令牌耗尽说明该服务商近期失败重试过多，此时失败的请求直接返回，避免重试风暴继续放大上游压力。
+
+use crate::config::RetryBudgetConfig;
+use dashmap::DashMap;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// 按服务商的重试预算
+#[derive(Debug)]
+pub struct RetryBudget {
+    config: RetryBudgetConfig,
+    buckets: DashMap<String, Bucket>,
+}
+
+impl RetryBudget {
+    #[must_use]
+    pub fn new(config: RetryBudgetConfig) -> Self {
+        Self {
+            config,
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// 是否启用重试预算
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 为服务商的一次重试申请令牌；未启用时总是允许
+    #[must_use]
+    pub fn try_acquire(&self, provider: &str) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+
+        let now = Instant::now();
+        let capacity = f64::from(self.config.capacity);
+        let mut bucket = self.buckets.entry(provider.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = elapsed
+            .mul_add(self.config.refill_per_sec, bucket.tokens)
+            .min(capacity);
+        bucket.refilled_at = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn budget(capacity: u32, refill_per_sec: f64) -> RetryBudget {
+        RetryBudget::new(RetryBudgetConfig {
+            enabled: true,
+            capacity,
+            refill_per_sec,
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_are_suppressed_once_exhausted_and_recover_after_refill() {
+        let budget = budget(3, 1.0);
+
+        for _ in 0..3 {
+            assert!(budget.try_acquire("openai"));
+        }
+        assert!(!budget.try_acquire("openai"));
+
+        // 其他服务商的预算互不影响
+        assert!(budget.try_acquire("claude"));
+
+        tokio::time::advance(Duration::from_millis(1500)).await;
+        assert!(budget.try_acquire("openai"));
+        assert!(!budget.try_acquire("openai"));
+
+        // 长时间空闲后最多恢复到桶容量
+        tokio::time::advance(Duration::from_secs(60)).await;
+        for _ in 0..3 {
+            assert!(budget.try_acquire("openai"));
+        }
+        assert!(!budget.try_acquire("openai"));
+    }
+
+    #[test]
+    fn disabled_budget_always_allows() {
+        let budget = RetryBudget::new(RetryBudgetConfig {
+            capacity: 1,
+            ..RetryBudgetConfig::default()
+        });
+        for _ in 0..10 {
+            assert!(budget.try_acquire("openai"));
+        }
+    }
+}
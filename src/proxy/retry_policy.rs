@@ -6,7 +6,12 @@ use crate::logging::{LogComponent, LogStage};
 use crate::{ldebug, linfo};
 use pingora_proxy::Session;
 
+use crate::config::NonIdempotentRetry;
 use crate::proxy::context::ProxyContext;
+use crate::proxy::effective_config;
+
+/// 连接上游失败的重试原因（请求尚未发出）
+pub const CONNECT_FAILURE: &str = "connect_failure";
 
 /// 重试决策结果
 #[derive(Debug, Clone, Copy)]
@@ -30,6 +35,10 @@ pub enum RetryReason {
     PartialResponse,
     /// 请求体不可重放
     NotSafeToRetry,
+    /// 非幂等请求可能已被上游执行
+    NotIdempotent,
+    /// 服务商重试预算已耗尽
+    ProviderBudgetExhausted,
     /// 退避计算为 0
     ZeroBackoff,
     /// 可以重试
@@ -136,7 +145,16 @@ pub fn apply_retry_policy(
     let mut evaluator =
         RetryPolicyEvaluator::new(session, max_retry_budget, ctx.control.retry.retry_count);
 
-    let decision = evaluator.evaluate();
+    let mut decision = evaluator.evaluate();
+    if decision.should_retry
+        && !idempotency_allows_retry(ctx.control.retry.idempotency_guard, reason, status_code)
+    {
+        decision = RetryDecision::no_retry(RetryReason::NotIdempotent);
+    }
+    // 服务商重试预算放在最后检查，只有确实要重试时才消耗令牌
+    if decision.should_retry && !provider_budget_allows_retry(ctx) {
+        decision = RetryDecision::no_retry(RetryReason::ProviderBudgetExhausted);
+    }
 
     // 根据决策处理
     if !decision.should_retry {
@@ -223,6 +241,13 @@ fn log_retry_skipped(
         RetryReason::NoRetryBudget => ("no_budget", "未触发重试（未配置重试预算）"),
         RetryReason::MaxRetryExceeded => ("max_exceeded", "未触发重试（已达重试上限）"),
         RetryReason::NotSafeToRetry => ("not_safe", "未触发重试（请求体不可重放）"),
+        RetryReason::NotIdempotent => {
+            ("not_idempotent", "未触发重试（非幂等请求可能已被上游执行）")
+        }
+        RetryReason::ProviderBudgetExhausted => (
+            "provider_budget_exhausted",
+            "未触发重试（服务商重试预算已耗尽）",
+        ),
         RetryReason::ZeroBackoff => ("zero_backoff", "未触发重试（退避计算为 0）"),
         RetryReason::PartialResponse => ("partial_response", "未触发重试（已收到部分响应）"),
         RetryReason::Retryable => unreachable!(),
@@ -242,18 +267,59 @@ fn log_retry_skipped(
     );
 }
 
+/// 按请求幂等性判断本次失败是否允许重试
+///
+/// 幂等请求不受限制；非幂等请求按配置的策略，`NotExecuted` 下只重试确定未被上游执行的失败：
+/// 连接失败，或上游以 429/503 拒绝。
+/// 502 可能出现在上游已处理请求之后（如网关读取响应失败），不视为未执行。
+#[must_use]
+pub fn idempotency_allows_retry(
+    guard: Option<NonIdempotentRetry>,
+    reason: &str,
+    status_code: Option<u16>,
+) -> bool {
+    match guard {
+        None | Some(NonIdempotentRetry::Always) => true,
+        Some(NonIdempotentRetry::Never) => false,
+        Some(NonIdempotentRetry::NotExecuted) => {
+            reason == CONNECT_FAILURE || matches!(status_code, Some(429 | 503))
+        }
+    }
+}
+
+/// 向服务商重试预算申请一次重试；未启用预算或尚未确定服务商时不限制
+fn provider_budget_allows_retry(ctx: &ProxyContext) -> bool {
+    match (
+        ctx.control.retry.provider_budget.as_ref(),
+        ctx.routing.provider_type.as_ref(),
+    ) {
+        (Some(budget), Some(provider)) => budget.try_acquire(&provider.name),
+        _ => true,
+    }
+}
+
 /// 计算最大重试预算
 ///
-/// 从用户服务 API 配置中获取重试次数限制，确保返回非负值
+/// 从用户服务 API 配置中获取重试次数限制（取值规则见 `effective_config::retry_budget`）
 fn calculate_max_retry_budget(ctx: &ProxyContext) -> u32 {
-    let retry_count = ctx
-        .routing
+    ctx.routing
         .user_service_api
         .as_ref()
-        .and_then(|api| api.retry_count)
-        .unwrap_or(0);
+        .map_or(0, |api| effective_config::retry_budget(api).value)
+}
 
-    // 确保非负并安全转换为 u32
-    let retry_count = retry_count.max(0);
-    u32::try_from(retry_count).unwrap_or(u32::MAX)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_executed_only_retries_unexecuted_failures() {
+        let guard = Some(NonIdempotentRetry::NotExecuted);
+        assert!(idempotency_allows_retry(guard, CONNECT_FAILURE, None));
+        assert!(idempotency_allows_retry(guard, "status", Some(429)));
+        assert!(idempotency_allows_retry(guard, "status", Some(503)));
+        assert!(!idempotency_allows_retry(guard, "status", Some(502)));
+        assert!(!idempotency_allows_retry(guard, "status", Some(500)));
+        assert!(!idempotency_allows_retry(guard, "timeout", None));
+    }
 }
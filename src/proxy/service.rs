@@ -3,26 +3,54 @@
 //! 实现了 Pingora 的 `ProxyHttp` trait，作为核心编排器，调用各个专有服务来处理请求。
 
 use crate::error::ProxyError;
+use crate::error::auth::UsageLimitKind;
+use crate::key_pool::HealthUpdate;
 use crate::logging::{self, ErrorLogField, LogComponent, LogStage, log_proxy_error};
 use crate::{ldebug, lerror, linfo, lwarn};
 use async_trait::async_trait;
 use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
 use pingora_core::prelude::*;
+use pingora_core::protocols::Digest;
 use pingora_core::{Error as PingoraError, ErrorType};
 use pingora_http::{RequestHeader, ResponseHeader};
-use pingora_proxy::{ProxyHttp, Session};
+use pingora_proxy::{FailToProxy, ProxyHttp, Session};
 use serde_json::{Value, json};
 use std::convert::TryFrom;
 use std::sync::Arc;
-use std::time::Instant;
 use tokio::time::Duration;
-use uuid::Uuid;
 
-use crate::proxy::context::ProxyContext;
-use crate::proxy::provider_strategy;
-use crate::proxy::response::{JsonError, build_auth_error_response, write_json_error};
+use crate::collect::stream_usage::SseUsageTracker;
+use crate::collect::types::CollectedMetrics;
+use crate::collect::util::content_type_is_json;
+use crate::config::{DebugCaptureConfig, DebugCaptureTarget, TimeoutBoundsConfig};
+use crate::pricing::TokenUsage;
+use crate::proxy::context::{ProxyContext, ResolvedCredential, parse_retry_after_ms};
+use crate::proxy::cost_ceiling::CostCeiling;
+use crate::proxy::effective_config;
+use crate::proxy::health_probe::ProbeKind;
+use crate::proxy::json_guard;
+use crate::proxy::model_availability::{ModelCheckOutcome, ModelListTarget};
+use crate::proxy::model_rate_limit::ModelRateLimits;
+use crate::proxy::non_streaming;
+use crate::proxy::parameter_policy;
+use crate::proxy::provider_strategy::{self, ProviderType};
+use crate::proxy::request_body_buffer::{BufferedBody, RequestBodyBuffer};
+use crate::proxy::request_transform_service::RequestTransformService;
+use crate::proxy::response::{
+    build_format_mismatch_response, build_invalid_tool_response, build_json_too_complex_response,
+    build_maintenance_response, build_model_not_found_response, build_rejection_response,
+    build_streaming_denied_response, write_json_error, write_probe_response, write_proxy_failure,
+};
 use crate::proxy::retry_policy;
+use crate::proxy::sse_event_flush::SseEventFlusher;
+use crate::proxy::sse_keepalive;
 use crate::proxy::state::ProxyState;
+use crate::proxy::upstream_service;
+use crate::proxy::upstream_timing::UpstreamTiming;
+use crate::proxy::websocket::{self, WebSocketSession};
+use crate::trace::StreamAbortKind;
+use crate::utils::request_id::REQUEST_ID_HEADER;
 
 /// 核心AI代理服务 - 作为编排器
 pub struct ProxyService {
@@ -53,6 +81,185 @@ impl ProxyService {
         })
     }
 
+    /// 代理失败时返回给客户端的状态码（与 Pingora 默认规则一致，0 表示不再响应）
+    fn failure_status_code(error: &Error) -> u16 {
+        match error.etype {
+            ErrorType::HTTPStatus(code) | ErrorType::CustomCode(_, code) => code,
+            _ => match error.esource {
+                pingora_core::ErrorSource::Upstream => 502,
+                pingora_core::ErrorSource::Downstream => match error.etype {
+                    ErrorType::ReadError | ErrorType::WriteError | ErrorType::ConnectionClosed => 0,
+                    _ => 400,
+                },
+                pingora_core::ErrorSource::Internal | pingora_core::ErrorSource::Unset => 500,
+            },
+        }
+    }
+
+    /// 请求超过总时长上限时返回中断错误，由 `logging` 阶段按 `total_timeout` 结束追踪
+    fn enforce_total_timeout(ctx: &ProxyContext) -> pingora_core::Result<()> {
+        let Some(limit) = ctx.control.total_timeout else {
+            return Ok(());
+        };
+        if !ctx.total_timeout_exceeded() {
+            return Ok(());
+        }
+        lwarn!(
+            &ctx.request_id,
+            LogStage::ResponseFailure,
+            LogComponent::Proxy,
+            "total_timeout_exceeded",
+            "请求超过总时长上限，已中断",
+            total_timeout_ms = limit.as_millis(),
+            elapsed_ms = ctx.start_time.elapsed().as_millis(),
+            response_body_size = ctx.response.body_received_size
+        );
+        Err(PingoraError::explain(
+            ErrorType::CustomCode("total_timeout", 504),
+            format!("request exceeded total timeout of {}s", limit.as_secs_f64()),
+        ))
+    }
+
+    /// 流式响应估算费用超过单请求费用上限时返回中断错误，由 `logging` 阶段按 `cost_limit_reached` 结束追踪
+    fn enforce_cost_ceiling(ctx: &mut ProxyContext) -> pingora_core::Result<()> {
+        let (Some(ceiling), Some(tracker)) = (
+            ctx.response.cost_ceiling.as_ref(),
+            ctx.response.stream_usage.as_ref(),
+        ) else {
+            return Ok(());
+        };
+        let Some(accrued_cost) =
+            ceiling.exceeded(tracker.usage_so_far(), tracker.completion_text())
+        else {
+            return Ok(());
+        };
+        let max_cost = ceiling.max_cost();
+        ctx.response.cost_limit_reached = true;
+        lwarn!(
+            &ctx.request_id,
+            LogStage::ResponseFailure,
+            LogComponent::Proxy,
+            "cost_limit_reached",
+            "流式响应估算费用超过单请求费用上限，已中断",
+            max_cost = max_cost,
+            accrued_cost = accrued_cost,
+            response_body_size = ctx.response.body_received_size
+        );
+        Err(PingoraError::explain(
+            ErrorType::CustomCode(
+                StreamAbortKind::CostLimitReached.as_str(),
+                StreamAbortKind::CostLimitReached.status_code(),
+            ),
+            format!("estimated cost {accrued_cost:.6} exceeded per-request limit {max_cost}"),
+        ))
+    }
+
+    /// 服务 API 配置了单请求费用上限时加载模型定价，创建流式费用估算器
+    ///
+    /// 无法确定模型或模型未配置定价时不做限制。
+    async fn init_cost_ceiling(
+        &self,
+        session: &Session,
+        ctx: &ProxyContext,
+    ) -> Option<CostCeiling> {
+        let max_cost = ctx
+            .routing
+            .user_service_api
+            .as_ref()?
+            .max_cost_per_request?
+            .to_string()
+            .parse::<f64>()
+            .ok()
+            .filter(|max_cost| *max_cost > 0.0)?;
+        let provider = ctx.routing.provider_type.as_ref()?;
+        let request = (!ctx.request.body_truncated)
+            .then(|| serde_json::from_slice::<Value>(&ctx.request.body).ok())
+            .flatten();
+        let model = ctx.request.requested_model.clone().or_else(|| {
+            parameter_policy::requested_model(
+                ProviderType::from_str(&provider.name)?,
+                session.req_header().uri.path(),
+                request.as_ref()?,
+            )
+        })?;
+
+        match self
+            .state
+            .collect_service
+            .pricing()
+            .load_price_sheet(&model, provider.id)
+            .await
+        {
+            Ok(Some(prices)) => {
+                Some(CostCeiling::new(max_cost, prices, &model).with_request_body(request.as_ref()))
+            }
+            Ok(None) => {
+                ldebug!(
+                    &ctx.request_id,
+                    LogStage::Response,
+                    LogComponent::Proxy,
+                    "cost_ceiling_no_pricing",
+                    "模型未配置定价，不限制单请求费用",
+                    model = %model
+                );
+                None
+            }
+            Err(err) => {
+                lwarn!(
+                    &ctx.request_id,
+                    LogStage::Response,
+                    LogComponent::Proxy,
+                    "cost_ceiling_pricing_failed",
+                    "加载模型定价失败，不限制单请求费用",
+                    model = %model,
+                    error = %err
+                );
+                None
+            }
+        }
+    }
+
+    /// 构造定向调试采集的匹配信息；`model` 为 `None` 时只能按身份条件匹配
+    fn debug_capture_target<'a>(
+        ctx: &ProxyContext,
+        model: Option<&'a str>,
+    ) -> DebugCaptureTarget<'a> {
+        let user_api = ctx.routing.user_service_api.as_ref();
+        DebugCaptureTarget {
+            user_id: user_api.map(|api| api.user_id),
+            user_service_api_id: user_api.map(|api| api.id),
+            provider_key_id: ctx.routing.selected_backend.as_ref().map(|key| key.id),
+            model,
+        }
+    }
+
+    /// SSE 响应按配置开启事件边界刷新：完整事件立即发出，数据块末尾未结束的事件暂存到补全后再发
+    fn init_sse_event_flush(&self, ctx: &mut ProxyContext) {
+        if ctx.response.is_sse && self.state.context().config().streaming.flush_per_event {
+            ctx.response.sse_event_flush = Some(SseEventFlusher::new());
+        }
+    }
+
+    /// 请求早期（模型尚未确定）是否可能命中定向调试采集，命中时提前保留上游请求头与流式响应体
+    fn may_debug_capture(&self, ctx: &ProxyContext) -> bool {
+        self.state
+            .context()
+            .config()
+            .debug_capture
+            .matches_identity(&Self::debug_capture_target(ctx, None), Utc::now())
+    }
+
+    /// 按最终模型确定是否命中定向调试采集
+    fn resolve_debug_capture(
+        config: &DebugCaptureConfig,
+        ctx: &ProxyContext,
+        model: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let model = model.or(ctx.request.requested_model.as_deref());
+        config.matches(&Self::debug_capture_target(ctx, model), now)
+    }
+
     /// 检测是否为部分响应错误（已收到响应体数据）
     const fn is_partial_response_error(ctx: &ProxyContext) -> bool {
         ctx.response.body_received_size > 0
@@ -120,6 +327,242 @@ impl ProxyService {
         true
     }
 
+    /// 缓存响应分块供采集使用；非 JSON 的成功响应（text/plain、图片等）只统计字节数，
+    /// 分块本身原样下发。错误响应仍然缓存，便于追踪记录错误内容
+    fn buffer_response_chunk(ctx: &mut ProxyContext, chunk: &[u8]) -> bool {
+        if (ctx.response.is_opaque_body || ctx.response.body_capture_skipped)
+            && ctx
+                .response
+                .details
+                .status_code
+                .is_none_or(|status| status < 400)
+        {
+            ctx.response.body_received_size =
+                ctx.response.body_received_size.saturating_add(chunk.len());
+            return false;
+        }
+
+        Self::append_body_with_limit(
+            &mut ctx.response.body,
+            &mut ctx.response.body_received_size,
+            &mut ctx.response.body_truncated,
+            chunk,
+            Self::MAX_BODY_BUFFER_BYTES,
+        )
+    }
+
+    /// 采集一个响应分块：用量跟踪与响应体缓存都只保留有界状态，分块本身随后原样交给下游
+    ///
+    /// 这里不暂存分块：Pingora 通过容量有限的通道在上下游之间传递分块，下游写入变慢时
+    /// 上游读取随之暂停，慢速客户端不会让代理缓存整个流。
+    fn observe_response_chunk(ctx: &mut ProxyContext, chunk: &[u8]) -> bool {
+        if let Some(tracker) = ctx.response.stream_usage.as_mut() {
+            tracker.observe_chunk(chunk, ctx.routing.provider_type.as_ref());
+        }
+        Self::buffer_response_chunk(ctx, chunk)
+    }
+
+    /// 为签名凭证（SigV4、HMAC）准备签名请求：在发送请求头前读取完整请求体、完成改写并签名
+    ///
+    /// 下游请求体读取后由 Pingora 的重放缓冲驱动 `request_body_filter`，再替换为签名时的请求体；
+    /// 请求体超过重放缓冲上限时无法保证发送内容与签名一致，直接拒绝。
+    async fn prepare_signed_request(
+        &self,
+        session: &mut Session,
+        upstream_request: &mut RequestHeader,
+        ctx: &mut ProxyContext,
+    ) -> pingora_core::Result<()> {
+        // 重试时下游请求体已读完，复用首次签名的请求体重新签名
+        if ctx.request.signed_body.is_none() {
+            session.enable_retry_buffering();
+            // 按模型路由时请求体已在认证阶段预读
+            if let Some(body) = ctx.request.routing_body.take() {
+                ctx.request.body_received_size =
+                    ctx.request.body_received_size.saturating_add(body.len());
+                ctx.request.body.extend_from_slice(&body);
+            }
+            while let Some(chunk) = session.read_request_body().await? {
+                ctx.request.body_received_size =
+                    ctx.request.body_received_size.saturating_add(chunk.len());
+                ctx.request.body.extend_from_slice(&chunk);
+            }
+            if session.retry_buffer_truncated() {
+                lwarn!(
+                    &ctx.request_id,
+                    LogStage::RequestModify,
+                    LogComponent::Proxy,
+                    "signed_body_too_large",
+                    "签名请求体超过缓冲上限",
+                    body_size = ctx.request.body_received_size
+                );
+                return Err(PingoraError::explain(
+                    ErrorType::HTTPStatus(413),
+                    "Signed request body exceeds buffer limit",
+                ));
+            }
+
+            self.reject_complex_json(session, ctx).await?;
+            Self::reject_denied_streaming(session, ctx).await?;
+            self.reject_unknown_model(session, ctx).await?;
+            self.reject_model_rate_limited(session, ctx).await?;
+            let rewritten = self.rewrite_request_body(session, ctx).await;
+            ctx.request.signed_body =
+                Some(rewritten.unwrap_or_else(|| Bytes::copy_from_slice(&ctx.request.body)));
+        }
+
+        let payload = ctx.request.signed_body.clone().unwrap_or_default();
+        // 请求体已完整读取，Content-Type 参与签名，需在签名前按实际内容校正
+        if RequestTransformService::correct_json_content_type(upstream_request, &payload) {
+            ldebug!(
+                &ctx.request_id,
+                LogStage::RequestModify,
+                LogComponent::Proxy,
+                "content_type_corrected",
+                "请求体为 JSON 但 Content-Type 缺失或不匹配，已改为 application/json"
+            );
+        }
+        RequestTransformService::sign_aws_request(upstream_request, ctx, &payload)?;
+        RequestTransformService::sign_hmac_request(upstream_request, ctx, &payload)?;
+        Ok(())
+    }
+
+    /// 客户端是否声明请求体为 JSON（未声明 Content-Type 时按 JSON 对待）
+    fn declares_json_body(session: &Session) -> bool {
+        session
+            .req_header()
+            .headers
+            .get("content-type")
+            .is_none_or(|value| value.to_str().is_ok_and(content_type_is_json))
+    }
+
+    fn log_request_body_eom(ctx: &ProxyContext) {
+        linfo!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::Proxy,
+            "request_body_eom",
+            "请求体接收完成，准备处理",
+            body_size = ctx.request.body_received_size,
+            body_truncated = ctx.request.body_truncated,
+            has_strategy = ctx.routing.strategy.is_some(),
+            will_modify = ctx.request.will_modify_body
+        );
+    }
+
+    /// 在完整请求体上应用策略与通用改写，返回改写后的请求体（未改写时为 `None`）
+    async fn rewrite_request_body(
+        &self,
+        session: &Session,
+        ctx: &mut ProxyContext,
+    ) -> Option<Bytes> {
+        // 确保有完整的 body 数据才进行 JSON 修改
+        let mut rewritten = None;
+        if !ctx.request.body.is_empty() && ctx.request.will_modify_body {
+            match serde_json::from_slice::<Value>(&ctx.request.body) {
+                Ok(mut json_value) => {
+                    ldebug!(
+                        &ctx.request_id,
+                        LogStage::RequestModify,
+                        LogComponent::Proxy,
+                        "request_body_parse_ok",
+                        "请求体 JSON 解析成功，尝试应用策略修改",
+                        body = json_value.to_string()
+                    );
+                    let mut modified = false;
+                    if let Some(strategy) = ctx.routing.strategy.clone() {
+                        match strategy
+                            .modify_request_body_json(session, ctx, &mut json_value)
+                            .await
+                        {
+                            Ok(true) => modified = true,
+                            Ok(false) => {
+                                linfo!(
+                                    &ctx.request_id,
+                                    LogStage::RequestModify,
+                                    LogComponent::Proxy,
+                                    "request_body_not_modified",
+                                    "策略选择不修改请求体"
+                                );
+                            }
+                            Err(e) => {
+                                lerror!(
+                                    &ctx.request_id,
+                                    LogStage::RequestModify,
+                                    LogComponent::Proxy,
+                                    "request_body_modify_fail",
+                                    &format!("执行请求体修改策略失败: {e}")
+                                );
+                            }
+                        }
+                    }
+                    if self.state.req_transform_service.apply_body_policies(
+                        session,
+                        ctx,
+                        &mut json_value,
+                    ) {
+                        modified = true;
+                    }
+
+                    if modified {
+                        ldebug!(
+                            &ctx.request_id,
+                            LogStage::RequestModify,
+                            LogComponent::Proxy,
+                            "request_body_modified",
+                            "请求体已被修改，正在序列化回字节",
+                            body = json_value.to_string()
+                        );
+                        match RequestTransformService::encode_json_body(&json_value) {
+                            Ok(serialized) => {
+                                ctx.request.body = BytesMut::from(&serialized[..]);
+                                rewritten = Some(serialized);
+                            }
+                            Err(e) => {
+                                lerror!(
+                                    &ctx.request_id,
+                                    LogStage::RequestModify,
+                                    LogComponent::Proxy,
+                                    "request_body_serialize_fail",
+                                    &format!("序列化修改后的 JSON 失败: {e}")
+                                );
+                            }
+                        }
+                    }
+                }
+                // 客户端声明了非 JSON 类型（如 multipart 上传）时按原样转发，不视为错误
+                Err(_) if !Self::declares_json_body(session) => {
+                    ldebug!(
+                        &ctx.request_id,
+                        LogStage::RequestModify,
+                        LogComponent::Proxy,
+                        "request_body_not_json",
+                        "请求体不是 JSON，跳过请求体改写",
+                        content_type = ?session.req_header().headers.get("content-type")
+                    );
+                }
+                Err(e) => {
+                    lerror!(
+                        &ctx.request_id,
+                        LogStage::RequestModify,
+                        LogComponent::Proxy,
+                        "request_body_parse_fail",
+                        &format!("解析请求体 JSON 失败: {e}"),
+                        body_preview = %String::from_utf8_lossy(&ctx.request.body[..std::cmp::min(500, ctx.request.body.len())])
+                    );
+                }
+            }
+        } else if ctx.request.body.is_empty() && ctx.request.will_modify_body {
+            lwarn!(
+                &ctx.request_id,
+                LogStage::RequestModify,
+                LogComponent::Proxy,
+                "request_body_empty_for_modify",
+                "策略期望修改请求体，但请求体为空"
+            );
+        }
+        rewritten
+    }
+
     /// 判断是否应对上游状态码进行重试（仅基于状态码维度）
     const fn should_retry_upstream_status(status_code: u16) -> bool {
         // 最佳实践：仅对常见“临时性”错误码重试，避免对不可能成功的请求浪费资源与引入重复计费风险。
@@ -185,16 +628,30 @@ impl ProxyService {
         ctx.response.body_received_size = 0;
         ctx.response.body_truncated = false;
         ctx.response.is_sse = false;
+        ctx.response.is_opaque_body = false;
+        ctx.response.body_capture_skipped = false;
         ctx.response.sse_keepalive_sent = false;
+        ctx.response.stream_usage = None;
+        ctx.response.model_alias = None;
+        ctx.response.sse_event_flush = None;
+        ctx.response.cost_ceiling = None;
+        ctx.response.cost_limit_reached = false;
+        ctx.response.upstream_error = None;
         // 注意：重试时 Pingora 会从内部 retry buffer 重放请求体，并再次调用 `request_body_filter`。
         // 这里清空 `ctx.request.body` 仅影响本地缓存/日志与“基于完整 body 的改写逻辑”，不会导致上游请求体丢失。
         ctx.request.body = BytesMut::new();
         ctx.request.body_received_size = 0;
         ctx.request.body_truncated = false;
+        ctx.request.body_buffer = RequestBodyBuffer::default();
+        ctx.request.signed_body_sent = false;
         ctx.trace.upstream_request_headers = None;
         ctx.trace.upstream_request_uri = None;
+        ctx.trace.upstream_timing = UpstreamTiming::default();
+        ctx.routing.circuit_provider = None;
+        ctx.response.websocket = None;
         ctx.response.usage_final = None;
         ctx.request.requested_model = None;
+        ctx.request.parameter_adjustments.clear();
         ctx.control.retry.reset_for_new_attempt();
     }
 
@@ -236,34 +693,65 @@ impl ProxyService {
         }
     }
 
+    /// 选取上游超时（秒）：未配置或非正数时使用默认值，再收敛到全局上下限，发生收敛时记录日志
+    fn select_timeout_seconds(
+        bounds: &TimeoutBoundsConfig,
+        configured: Option<i32>,
+        request_id: &str,
+    ) -> u64 {
+        let requested = effective_config::requested_timeout_seconds(configured).value;
+        let timeout = effective_config::timeout_seconds(bounds, configured).value;
+        if timeout != requested {
+            lwarn!(
+                request_id,
+                LogStage::RequestStart,
+                LogComponent::Proxy,
+                "timeout_clamped",
+                "服务 API 超时超出全局范围，已收敛",
+                configured_secs = requested,
+                timeout_secs = timeout,
+                min_secs = bounds.min_secs,
+                max_secs = bounds.max_secs
+            );
+        }
+        timeout
+    }
+
     fn configure_timeouts_and_strategy(&self, session: &mut Session, ctx: &mut ProxyContext) {
         if let (Some(user_api), Some(provider_type)) = (
             ctx.routing.user_service_api.as_ref(),
             ctx.routing.provider_type.as_ref(),
         ) {
-            // 允许显式配置较小超时；非正数回退默认值
-            let configured = user_api.timeout_seconds.unwrap_or(120);
-            let timeout = if configured <= 0 { 120 } else { configured };
-
-            ctx.control.timeout_seconds = Some(timeout);
-
-            let timeout_u64 = u64::try_from(timeout).unwrap_or(120);
-            let timeout_duration = std::time::Duration::from_secs(timeout_u64 * 2);
+            let config = self.state.context().config();
+            let timeout = Self::select_timeout_seconds(
+                &config.timeout_bounds,
+                user_api.timeout_seconds,
+                &ctx.request_id,
+            );
+            ctx.control.timeout_seconds = Some(i32::try_from(timeout).unwrap_or(i32::MAX));
+            ctx.control.total_timeout = config
+                .total_timeout
+                .resolve(&provider_type.name, user_api.max_response_duration_seconds);
+            ctx.control.retry.idempotency_guard = config.retry.guard_for(session.req_header());
+            ctx.control.retry.provider_budget = self
+                .state
+                .retry_budget
+                .is_enabled()
+                .then(|| Arc::clone(&self.state.retry_budget));
+
+            let timeout_duration = std::time::Duration::from_secs(timeout * 2);
             session.set_read_timeout(Some(timeout_duration));
             session.set_write_timeout(Some(timeout_duration));
 
-            if let Some(name) = provider_strategy::ProviderRegistry::match_name(&provider_type.name)
-            {
-                ctx.routing.strategy = provider_strategy::make_strategy(
-                    name,
-                    Some(
-                        self.state
-                            .key_scheduler_service
-                            .api_key_health_service()
-                            .clone(),
-                    ),
-                );
-            }
+            ctx.routing.strategy = provider_strategy::make_strategy_for_provider(
+                provider_type,
+                Some(
+                    self.state
+                        .key_scheduler_service
+                        .api_key_health_service()
+                        .clone(),
+                ),
+            );
         }
     }
 
@@ -303,41 +791,499 @@ impl ProxyService {
         }
     }
 
-    async fn send_auth_error_response(
+    /// 格式检测：请求体属于其他服务商接口的格式时直接返回 400，避免上游返回含义模糊的错误
+    async fn reject_format_mismatch(
         &self,
         session: &mut Session,
-        request_id: &str,
-        error: &ProxyError,
-    ) -> pingora_core::Result<Option<u16>> {
-        if let ProxyError::Authentication(auth_err) = error {
-            let JsonError {
-                status,
-                payload,
-                message,
-            } = build_auth_error_response(auth_err);
-            if status == 401 {
+        ctx: &ProxyContext,
+    ) -> pingora_core::Result<()> {
+        let (Some(strategy), Some(provider)) = (&ctx.routing.strategy, &ctx.routing.provider_type)
+        else {
+            return Ok(());
+        };
+        if !ctx.request.will_modify_body || ctx.request.body.is_empty() {
+            return Ok(());
+        }
+        let Ok(body) = serde_json::from_slice::<Value>(&ctx.request.body) else {
+            return Ok(());
+        };
+        let Some(mismatch) =
+            strategy.detect_format_mismatch(session.req_header().uri.path(), &body)
+        else {
+            return Ok(());
+        };
+
+        lwarn!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::Proxy,
+            "format_mismatch",
+            "请求体格式与目标服务商接口不匹配，拒绝转发",
+            detected = mismatch.detected,
+            expected = mismatch.expected,
+            fields = ?mismatch.fields,
+            provider = %provider.name
+        );
+        let rejection = build_format_mismatch_response(&mismatch, &provider.name);
+        write_json_error(session, &ctx.request_id, &rejection).await?;
+        Err(PingoraError::explain(
+            ErrorType::HTTPStatus(rejection.status),
+            format!("{}:{}", rejection.reason.as_str(), mismatch.detected),
+        ))
+    }
+
+    /// 工具（函数调用）定义不符合服务商格式时直接返回 400，避免上游返回含义模糊的错误
+    async fn reject_invalid_tools(
+        &self,
+        session: &mut Session,
+        ctx: &ProxyContext,
+    ) -> pingora_core::Result<()> {
+        let (Some(strategy), Some(provider)) = (&ctx.routing.strategy, &ctx.routing.provider_type)
+        else {
+            return Ok(());
+        };
+        if !self.state.context().config().request_body.validate_tools
+            || !ctx.request.will_modify_body
+            || ctx.request.body.is_empty()
+        {
+            return Ok(());
+        }
+        let Ok(body) = serde_json::from_slice::<Value>(&ctx.request.body) else {
+            return Ok(());
+        };
+        let Some(error) = strategy.validate_tools(session.req_header().uri.path(), &body) else {
+            return Ok(());
+        };
+
+        lwarn!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::Proxy,
+            "invalid_tool_definition",
+            "工具定义无效，拒绝转发",
+            field = %error.field,
+            reason = %error.reason,
+            provider = %provider.name
+        );
+        let rejection = build_invalid_tool_response(&error, &provider.name);
+        write_json_error(session, &ctx.request_id, &rejection).await?;
+        Err(PingoraError::explain(
+            ErrorType::HTTPStatus(rejection.status),
+            format!("{}:{}", rejection.reason.as_str(), error.field),
+        ))
+    }
+
+    /// 流式策略为 `deny` 时拒绝流式请求（请求体 `stream: true` 或 Gemini 流式路径），直接返回 400
+    async fn reject_denied_streaming(
+        session: &mut Session,
+        ctx: &ProxyContext,
+    ) -> pingora_core::Result<()> {
+        if !non_streaming::is_denied(ctx) {
+            return Ok(());
+        }
+        let body = serde_json::from_slice::<Value>(&ctx.request.body).unwrap_or(Value::Null);
+        if !non_streaming::requests_streaming(session.req_header().uri.path(), &body) {
+            return Ok(());
+        }
+
+        lwarn!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::Proxy,
+            "streaming_denied",
+            "服务 API 不允许流式请求，拒绝转发",
+            path = %session.req_header().uri.path()
+        );
+        let rejection = build_streaming_denied_response();
+        write_json_error(session, &ctx.request_id, &rejection).await?;
+        Err(PingoraError::explain(
+            ErrorType::HTTPStatus(rejection.status),
+            rejection.reason.as_str(),
+        ))
+    }
+
+    /// 请求体 JSON 嵌套过深或元素过多时在解析前返回 400
+    async fn reject_complex_json(
+        &self,
+        session: &mut Session,
+        ctx: &ProxyContext,
+    ) -> pingora_core::Result<()> {
+        if !ctx.request.will_modify_body || ctx.request.body.is_empty() {
+            return Ok(());
+        }
+        let Err(violation) = json_guard::check_complexity(
+            &ctx.request.body,
+            &self.state.context().config().request_body,
+        ) else {
+            return Ok(());
+        };
+
+        lwarn!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::Proxy,
+            "json_too_complex",
+            "请求体 JSON 超过复杂度上限，拒绝转发",
+            violation = ?violation,
+            body_size = ctx.request.body_received_size
+        );
+        let rejection = build_json_too_complex_response(violation);
+        write_json_error(session, &ctx.request_id, &rejection).await?;
+        Err(PingoraError::explain(
+            ErrorType::HTTPStatus(rejection.status),
+            format!("{}:{}", rejection.reason.as_str(), rejection.message),
+        ))
+    }
+
+    /// 模型预检：请求的模型不在服务商模型列表中时直接返回 404，并列出相近模型
+    async fn reject_unknown_model(
+        &self,
+        session: &mut Session,
+        ctx: &ProxyContext,
+    ) -> pingora_core::Result<()> {
+        // 只有缓存了完整请求体（尚未发往上游）时才能拒绝
+        if !self.state.model_availability.enabled()
+            || !ctx.request.will_modify_body
+            || ctx.request.body.is_empty()
+        {
+            return Ok(());
+        }
+        let Some(target) = ModelListTarget::from_context(ctx) else {
+            return Ok(());
+        };
+        let Ok(body) = serde_json::from_slice::<Value>(&ctx.request.body) else {
+            return Ok(());
+        };
+        let path = session.req_header().uri.path().to_string();
+        let Some(model) = parameter_policy::requested_model(target.provider, &path, &body) else {
+            return Ok(());
+        };
+
+        let ModelCheckOutcome::Unknown { suggestions } =
+            self.state.model_availability.check(&target, &model).await
+        else {
+            return Ok(());
+        };
+
+        lwarn!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::Proxy,
+            "model_not_found",
+            "请求的模型不在服务商模型列表中，拒绝转发",
+            model = %model,
+            provider = %target.name,
+            suggestions = ?suggestions
+        );
+        let rejection = build_model_not_found_response(&model, &target.name, &suggestions);
+        write_json_error(session, &ctx.request_id, &rejection).await?;
+        Err(PingoraError::explain(
+            ErrorType::HTTPStatus(rejection.status),
+            format!("{}:{model}", rejection.reason.as_str()),
+        ))
+    }
+
+    /// 模型级限流：服务商为请求的模型配置了每分钟上限且已超出时返回 429
+    async fn reject_model_rate_limited(
+        &self,
+        session: &mut Session,
+        ctx: &mut ProxyContext,
+    ) -> pingora_core::Result<()> {
+        // 只有缓存了完整请求体时才能解析模型；重试重放请求体时不重复计数
+        if ctx.request.model_rate_limit_counted
+            || !ctx.request.will_modify_body
+            || ctx.request.body.is_empty()
+        {
+            return Ok(());
+        }
+        let Some(provider_type) = ctx.routing.provider_type.as_ref() else {
+            return Ok(());
+        };
+        let Some(limits) = ModelRateLimits::from_provider(provider_type) else {
+            return Ok(());
+        };
+        let Ok(body) = serde_json::from_slice::<Value>(&ctx.request.body) else {
+            return Ok(());
+        };
+        let path = session.req_header().uri.path().to_string();
+        let model = ProviderType::from_str(&provider_type.name).map_or_else(
+            || {
+                body.get("model")
+                    .and_then(Value::as_str)
+                    .map(ToString::to_string)
+            },
+            |provider| parameter_policy::requested_model(provider, &path, &body),
+        );
+        let Some((model, limit)) = model.and_then(|model| {
+            let limit = limits.limit_for(&model)?;
+            Some((model, limit))
+        }) else {
+            return Ok(());
+        };
+        let provider_type_id = provider_type.id;
+        ctx.request.model_rate_limit_counted = true;
+
+        let outcome = match self
+            .state
+            .rate_limiter
+            .check_model_per_minute(provider_type_id, &model, i64::from(limit))
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(err) => {
                 lwarn!(
-                    request_id,
-                    LogStage::Authentication,
-                    LogComponent::Auth,
-                    "authentication_failed",
-                    "认证失败",
-                    error = message
+                    &ctx.request_id,
+                    LogStage::RequestModify,
+                    LogComponent::Proxy,
+                    "model_rate_limit_check_failed",
+                    "模型级限流检查失败，放行请求",
+                    model = %model,
+                    error = %err
                 );
-            } else {
+                return Ok(());
+            }
+        };
+        if outcome.allowed {
+            return Ok(());
+        }
+
+        lwarn!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::Proxy,
+            "model_rate_limited",
+            "模型每分钟请求数超过服务商配置的上限，拒绝转发",
+            model = %model,
+            provider_type_id = provider_type_id,
+            limit = outcome.limit,
+            current = outcome.current
+        );
+        let err = outcome.rate_limit_error(UsageLimitKind::ModelPerMinute);
+        let Some(rejection) = build_rejection_response(&err) else {
+            return Err(err.into());
+        };
+        write_json_error(session, &ctx.request_id, &rejection).await?;
+        Err(PingoraError::explain(
+            ErrorType::HTTPStatus(rejection.status),
+            format!("{}:{model}", rejection.reason.as_str()),
+        ))
+    }
+
+    /// 按服务商策略对上游响应的判定更新所用密钥的健康状态
+    async fn apply_key_health_update(&self, ctx: &ProxyContext, status_code: u16) {
+        let (Some(strategy), Some(key)) = (&ctx.routing.strategy, &ctx.routing.selected_backend)
+        else {
+            return;
+        };
+        // 费用上限由代理主动中断，与密钥健康无关
+        if ctx.response.cost_limit_reached {
+            return;
+        }
+        let mut update = strategy.classify_and_update_health(key, status_code, &ctx.response.body);
+        if update == HealthUpdate::Unchanged {
+            return;
+        }
+        // 上游给出 Retry-After 时按其冷却，否则使用配置的默认冷却
+        if let HealthUpdate::RateLimited { retry_after, .. } = &mut update {
+            *retry_after = ctx
+                .response
+                .details
+                .headers
+                .get("retry-after")
+                .and_then(|value| parse_retry_after_ms(value).ok().flatten())
+                .map(Duration::from_millis);
+        }
+        lwarn!(
+            &ctx.request_id,
+            LogStage::Response,
+            LogComponent::KeyPool,
+            "key_health_update",
+            "根据上游响应更新密钥健康状态",
+            key_id = key.id,
+            status_code = status_code,
+            update = ?update
+        );
+        if let Err(e) = self
+            .state
+            .key_scheduler_service
+            .api_key_health_service()
+            .apply_health_update(key.id, &update)
+            .await
+        {
+            lwarn!(
+                &ctx.request_id,
+                LogStage::Response,
+                LogComponent::KeyPool,
+                "key_health_update_fail",
+                "更新密钥健康状态失败",
+                key_id = key.id,
+                error = %e
+            );
+        }
+    }
+
+    /// 全局并发准入：获得的空位保存在上下文中，请求结束时释放
+    async fn admit(
+        &self,
+        session: &mut Session,
+        ctx: &mut ProxyContext,
+    ) -> pingora_core::Result<()> {
+        if !self.state.admission.is_enabled() {
+            return Ok(());
+        }
+        let priority = ctx
+            .routing
+            .user_service_api
+            .as_ref()
+            .map_or(0, |api| api.priority);
+        match self.state.admission.acquire(priority).await {
+            Ok(permit) => {
+                ctx.control.admission = Some(permit);
+                Ok(())
+            }
+            Err(e) => {
                 lwarn!(
-                    request_id,
-                    LogStage::Authentication,
-                    LogComponent::Auth,
-                    "usage_limit_reached",
-                    "速率限制触发，返回结构化错误",
-                    error = message
+                    &ctx.request_id,
+                    LogStage::RequestStart,
+                    LogComponent::Proxy,
+                    "admission_reject",
+                    "全局并发已达上限，拒绝请求",
+                    priority = priority,
+                    in_flight = self.state.admission.in_flight(),
+                    queued = self.state.admission.queued(),
+                    error = %e
                 );
+                let status = self
+                    .send_rejection_response(session, &ctx.request_id, &e)
+                    .await?
+                    .unwrap_or(503);
+                Err(PingoraError::explain(
+                    ErrorType::HTTPStatus(status),
+                    format!("{}:{}", e.error_code(), e),
+                ))
             }
-            write_json_error(session, status, payload).await?;
-            return Ok(Some(status));
         }
-        Ok(None)
+    }
+
+    /// 积分额度：转发前预留积分，余额不足时拒绝请求
+    async fn reserve_credits(
+        &self,
+        session: &mut Session,
+        ctx: &mut ProxyContext,
+    ) -> pingora_core::Result<()> {
+        let Some(user_id) = ctx.routing.user_service_api.as_ref().map(|api| api.user_id) else {
+            return Ok(());
+        };
+        match self.state.credits.reserve(user_id, &ctx.request_id).await {
+            Ok(reservation) => {
+                ctx.control.credit_reservation = reservation;
+                Ok(())
+            }
+            Err(e) => {
+                let status = self
+                    .send_rejection_response(session, &ctx.request_id, &e)
+                    .await?;
+                let Some(status) = status else {
+                    log_proxy_error(
+                        &ctx.request_id,
+                        LogStage::Authentication,
+                        LogComponent::Statistics,
+                        "credits_reserve_fail",
+                        "预留积分失败",
+                        &e,
+                        &[ErrorLogField::new("user_id", json!(user_id))],
+                    );
+                    return Err(e.into());
+                };
+                Err(PingoraError::explain(
+                    ErrorType::HTTPStatus(status),
+                    format!("{}:{}", e.error_code(), e),
+                ))
+            }
+        }
+    }
+
+    /// 请求结束：成功时按实际用量扣减积分，失败时只释放预留
+    async fn settle_credits(&self, ctx: &mut ProxyContext, metrics: &CollectedMetrics) {
+        let Some(reservation) = ctx.control.credit_reservation.take() else {
+            return;
+        };
+        let result = if metrics.status_code < 400 {
+            let usage = TokenUsage {
+                prompt_tokens: metrics.usage.prompt_tokens,
+                completion_tokens: metrics.usage.completion_tokens,
+                cache_create_tokens: metrics.usage.cache_create_tokens,
+                cache_read_tokens: metrics.usage.cache_read_tokens,
+            };
+            self.state
+                .credits
+                .settle(
+                    reservation,
+                    metrics.model.as_deref(),
+                    &usage,
+                    &ctx.request_id,
+                )
+                .await
+                .map(|_| ())
+        } else {
+            self.state
+                .credits
+                .release(reservation, &ctx.request_id)
+                .await
+        };
+        if let Err(e) = result {
+            lwarn!(
+                &ctx.request_id,
+                LogStage::Response,
+                LogComponent::Statistics,
+                "credits_settle_fail",
+                "结算积分失败",
+                user_id = reservation.user_id,
+                error = %e
+            );
+        }
+    }
+
+    async fn send_rejection_response(
+        &self,
+        session: &mut Session,
+        request_id: &str,
+        error: &ProxyError,
+    ) -> pingora_core::Result<Option<u16>> {
+        let Some(rejection) = build_rejection_response(error) else {
+            return Ok(None);
+        };
+        match rejection.status {
+            429 => lwarn!(
+                request_id,
+                LogStage::Authentication,
+                LogComponent::Auth,
+                "usage_limit_reached",
+                "速率限制触发，返回结构化错误",
+                error = rejection.message,
+                reason_code = rejection.reason.as_str()
+            ),
+            503 => lwarn!(
+                request_id,
+                LogStage::Authentication,
+                LogComponent::Auth,
+                "service_unavailable",
+                "依赖资源暂时不可用，返回 503",
+                error = rejection.message,
+                retry_after_secs = rejection.retry_after_secs
+            ),
+            _ => lwarn!(
+                request_id,
+                LogStage::Authentication,
+                LogComponent::Auth,
+                "authentication_failed",
+                "认证失败",
+                error = rejection.message,
+                reason_code = rejection.reason.as_str()
+            ),
+        }
+        write_json_error(session, request_id, &rejection).await?;
+        Ok(Some(rejection.status))
     }
 }
 
@@ -346,11 +1292,7 @@ impl ProxyHttp for ProxyService {
     type CTX = ProxyContext;
 
     fn new_ctx(&self) -> Self::CTX {
-        ProxyContext {
-            request_id: Uuid::new_v4().to_string(),
-            start_time: Instant::now(),
-            ..Default::default()
-        }
+        ProxyContext::new_request()
     }
 
     async fn early_request_filter(
@@ -358,6 +1300,17 @@ impl ProxyHttp for ProxyService {
         session: &mut Session,
         ctx: &mut Self::CTX,
     ) -> pingora_core::Result<()> {
+        // 探针：绕过维护模式与认证直接响应，不记录请求日志
+        if let Some(kind) = ProbeKind::from_path(session.req_header().uri.path()) {
+            ctx.request.is_probe = true;
+            let (status, payload) = self.state.health_probe.respond(kind).await;
+            write_probe_response(session, &ctx.request_id, status, &payload).await?;
+            return Err(PingoraError::explain(
+                ErrorType::HTTPStatus(status),
+                "Probe handled by proxy".to_string(),
+            ));
+        }
+
         linfo!(
             &ctx.request_id,
             LogStage::RequestStart,
@@ -369,8 +1322,9 @@ impl ProxyHttp for ProxyService {
         );
 
         if session.req_header().method == "OPTIONS" {
-            let mut resp = ResponseHeader::build(204, Some(4))
+            let mut resp = ResponseHeader::build(204, Some(5))
                 .map_err(|err| PingoraError::explain(ErrorType::InternalError, err.to_string()))?;
+            let _ = resp.insert_header(REQUEST_ID_HEADER, ctx.request_id.as_str());
             let _ = resp.insert_header("access-control-allow-origin", "*");
             let _ = resp.insert_header(
                 "access-control-allow-methods",
@@ -388,6 +1342,25 @@ impl ProxyHttp for ProxyService {
             ));
         }
 
+        // 维护模式：在认证之前直接拒绝，管理端口不受影响
+        if let Some(state) = self.state.maintenance.active().await {
+            let rejection = build_maintenance_response(&state);
+            lwarn!(
+                &ctx.request_id,
+                LogStage::RequestStart,
+                LogComponent::Proxy,
+                "maintenance_reject",
+                "服务维护中，拒绝代理请求",
+                reason_code = rejection.reason.as_str(),
+                retry_after_secs = state.retry_after_secs
+            );
+            write_json_error(session, &ctx.request_id, &rejection).await?;
+            return Err(PingoraError::explain(
+                ErrorType::HTTPStatus(rejection.status),
+                rejection.reason.as_str().to_string(),
+            ));
+        }
+
         // 1. 执行完整的认证和授权流程
         if let Err(e) = self
             .state
@@ -408,7 +1381,7 @@ impl ProxyHttp for ProxyService {
                 ],
             );
             if let Some(status) = self
-                .send_auth_error_response(session, &ctx.request_id, &e)
+                .send_rejection_response(session, &ctx.request_id, &e)
                 .await?
             {
                 let context = format!("{}:{}", e.error_code(), e);
@@ -429,9 +1402,36 @@ impl ProxyHttp for ProxyService {
             user_service_api_id = ctx.routing.user_service_api.as_ref().map(|u| u.id)
         );
 
+        // 2. 全局并发准入：达到上限时按服务 API 优先级排队
+        self.admit(session, ctx).await?;
+
+        // 3. 积分额度：余额不足时在转发前拒绝
+        self.reserve_credits(session, ctx).await?;
+
         self.configure_timeouts_and_strategy(session, ctx);
         self.collect_request_metadata(session, ctx).await;
 
+        if websocket::is_websocket_upgrade(session.req_header()) {
+            ctx.request.is_websocket = true;
+            if ctx.request.requested_model.is_none() {
+                ctx.request.requested_model = websocket::model_from_query(session.req_header());
+            }
+            linfo!(
+                &ctx.request_id,
+                LogStage::RequestStart,
+                LogComponent::Proxy,
+                "websocket_upgrade_request",
+                "收到 WebSocket 升级请求",
+                path = session.req_header().uri.path(),
+                model = ctx.request.requested_model.as_deref()
+            );
+        }
+
+        // SSE keepalive 只作用于流式请求，选择上游前需得知请求是否为流式
+        if self.state.upstream_service.sse_keepalive_enabled() {
+            ctx.request.is_streaming = sse_keepalive::is_streaming_request(session, ctx).await?;
+        }
+
         Ok(())
     }
 
@@ -461,10 +1461,40 @@ impl ProxyHttp for ProxyService {
         if ctx.control.retry.retry_count > 0 {
             Self::reset_ctx_for_retry(ctx);
         }
+        Self::enforce_total_timeout(ctx)?;
         let peer = self.state.upstream_service.select_peer(ctx).await?;
         Ok(peer)
     }
 
+    async fn connected_to_upstream(
+        &self,
+        _session: &mut Session,
+        reused: bool,
+        peer: &HttpPeer,
+        #[cfg(unix)] _fd: std::os::unix::io::RawFd,
+        #[cfg(windows)] _sock: std::os::windows::io::RawSocket,
+        digest: Option<&Digest>,
+        ctx: &mut Self::CTX,
+    ) -> pingora_core::Result<()> {
+        upstream_service::record_upstream_connection(reused);
+        ctx.trace.upstream_timing.record_connected(reused, digest);
+        ldebug!(
+            &ctx.request_id,
+            LogStage::UpstreamRequest,
+            LogComponent::Upstream,
+            "upstream_connected",
+            "已连接上游",
+            sni = %peer.sni,
+            reused = reused,
+            connect_ms = ?ctx
+                .trace
+                .upstream_timing
+                .phase("connect")
+                .and_then(|phase| phase.duration_ms)
+        );
+        Ok(())
+    }
+
     async fn upstream_request_filter(
         &self,
         session: &mut Session,
@@ -475,13 +1505,15 @@ impl ProxyHttp for ProxyService {
             .req_transform_service
             .filter_request(session, upstream_request, ctx)
             .await?;
+        if matches!(
+            ctx.routing.resolved_credential,
+            Some(ResolvedCredential::AwsSigV4(_) | ResolvedCredential::HmacSignature(_))
+        ) {
+            self.prepare_signed_request(session, upstream_request, ctx)
+                .await?;
+        }
 
-        if ctx
-            .routing
-            .user_service_api
-            .as_ref()
-            .is_some_and(|api| api.log_mode)
-        {
+        if ctx.captures_full_detail() || self.may_debug_capture(ctx) {
             ctx.trace.upstream_request_headers =
                 Some(logging::headers_json_map_request(upstream_request));
             ctx.trace.upstream_request_uri = Some(upstream_request.uri.to_string());
@@ -497,148 +1529,100 @@ impl ProxyHttp for ProxyService {
         end_of_stream: bool,
         ctx: &mut Self::CTX,
     ) -> pingora_core::Result<()> {
-        // 处理当前分块数据（如果有）
-        if let Some(chunk) = body_chunk.as_ref() {
-            if ctx.request.will_modify_body {
+        // WebSocket 升级后的数据帧原样透传，只统计字节数
+        if ctx.request.is_websocket {
+            if let Some(chunk) = body_chunk.as_ref() {
                 ctx.request.body_received_size =
                     ctx.request.body_received_size.saturating_add(chunk.len());
-                ctx.request.body.extend_from_slice(chunk);
-            } else {
-                let newly_truncated = Self::append_body_with_limit(
-                    &mut ctx.request.body,
-                    &mut ctx.request.body_received_size,
-                    &mut ctx.request.body_truncated,
-                    chunk,
-                    Self::MAX_BODY_BUFFER_BYTES,
-                );
-                if newly_truncated {
+            }
+            return Ok(());
+        }
+
+        // SigV4 请求体已在签名前完整读取并改写，只发送签名覆盖的内容（重放的原始分块丢弃）
+        if let Some(signed_body) = ctx.request.signed_body.as_ref() {
+            *body_chunk = (!ctx.request.signed_body_sent).then(|| signed_body.clone());
+            ctx.request.signed_body_sent = true;
+            return Ok(());
+        }
+
+        // 计划改写请求体：分块交由缓冲累积，流结束时拿到完整请求体再改写
+        if ctx.request.will_modify_body {
+            if let Some(chunk) = body_chunk.as_ref() {
+                ctx.request.body_received_size =
+                    ctx.request.body_received_size.saturating_add(chunk.len());
+            }
+            match ctx
+                .request
+                .body_buffer
+                .push(body_chunk.as_ref(), end_of_stream)
+            {
+                // 按照 Pingora 官方示例清空分块，避免原始与改写后的内容混合发送
+                BufferedBody::Pending | BufferedBody::PassThrough => {
+                    if let Some(chunk) = body_chunk {
+                        chunk.clear();
+                    }
+                }
+                BufferedBody::Complete(body) => {
+                    ctx.request.body = BytesMut::from(body.as_ref());
+                    Self::log_request_body_eom(ctx);
+                    self.reject_complex_json(session, ctx).await?;
+                    self.reject_format_mismatch(session, ctx).await?;
+                    self.reject_invalid_tools(session, ctx).await?;
+                    Self::reject_denied_streaming(session, ctx).await?;
+                    self.reject_unknown_model(session, ctx).await?;
+                    self.reject_model_rate_limited(session, ctx).await?;
+                    // 未能改写时原样发送已缓存的请求体
+                    let rewritten = self.rewrite_request_body(session, ctx).await;
+                    *body_chunk = Some(rewritten.unwrap_or(body));
+                }
+                BufferedBody::Overflow(buffered) => {
                     lwarn!(
                         &ctx.request_id,
                         LogStage::RequestModify,
                         LogComponent::Proxy,
-                        "request_body_truncated",
-                        "请求体缓存超过上限，已截断",
-                        buffer_limit_bytes = Self::MAX_BODY_BUFFER_BYTES,
+                        "request_body_modify_overflow",
+                        "请求体超过改写缓存上限，放弃改写并原样转发",
+                        buffer_limit_bytes = RequestBodyBuffer::DEFAULT_LIMIT,
                         received_bytes = ctx.request.body_received_size
                     );
+                    // 后续分块按普通请求原样转发，日志只保留请求体前缀
+                    ctx.request.will_modify_body = false;
+                    ctx.request.body_truncated = true;
+                    ctx.request.body.extend_from_slice(
+                        &buffered[..buffered.len().min(Self::MAX_BODY_BUFFER_BYTES)],
+                    );
+                    *body_chunk = Some(buffered);
+                    if end_of_stream {
+                        Self::log_request_body_eom(ctx);
+                    }
                 }
             }
-            // 如果需要修改请求体且不是流结束，按照 Pingora 官方示例清空分块
-            // 保持 HTTP 流式语义，避免原始与改写后的内容混合发送
-            if ctx.request.will_modify_body
-                && !end_of_stream
-                && let Some(chunk) = body_chunk
-            {
-                chunk.clear();
-            }
+            return Ok(());
         }
 
-        // 流结束处理：处理完整的请求体
-        if end_of_stream {
-            linfo!(
-                &ctx.request_id,
-                LogStage::RequestModify,
-                LogComponent::Proxy,
-                "request_body_eom",
-                "请求体接收完成，准备处理",
-                body_size = ctx.request.body_received_size,
-                body_truncated = ctx.request.body_truncated,
-                has_strategy = ctx.routing.strategy.is_some(),
-                will_modify = ctx.request.will_modify_body
+        // 仅记录请求体（有上限），分块原样转发
+        if let Some(chunk) = body_chunk.as_ref() {
+            let newly_truncated = Self::append_body_with_limit(
+                &mut ctx.request.body,
+                &mut ctx.request.body_received_size,
+                &mut ctx.request.body_truncated,
+                chunk,
+                Self::MAX_BODY_BUFFER_BYTES,
             );
-
-            // 确保有完整的 body 数据才进行 JSON 修改
-            let mut chunk_replaced = false;
-            if !ctx.request.body.is_empty() && ctx.request.will_modify_body {
-                if let Some(strategy) = &ctx.routing.strategy {
-                    match serde_json::from_slice::<Value>(&ctx.request.body) {
-                        Ok(mut json_value) => {
-                            ldebug!(
-                                &ctx.request_id,
-                                LogStage::RequestModify,
-                                LogComponent::Proxy,
-                                "request_body_parse_ok",
-                                "请求体 JSON 解析成功，尝试应用策略修改",
-                                body = json_value.to_string()
-                            );
-                            match strategy
-                                .modify_request_body_json(session, ctx, &mut json_value)
-                                .await
-                            {
-                                Ok(true) => {
-                                    ldebug!(
-                                        &ctx.request_id,
-                                        LogStage::RequestModify,
-                                        LogComponent::Proxy,
-                                        "request_body_modified",
-                                        "策略选择修改请求体，正在序列化回字节",
-                                        body = json_value.to_string()
-                                    );
-                                    match serde_json::to_vec(&json_value) {
-                                        Ok(serialized) => {
-                                            // 更新 body 并重新设置到 chunk
-                                            ctx.request.body = BytesMut::from(&serialized[..]);
-                                            *body_chunk = Some(Bytes::from(serialized));
-                                            chunk_replaced = true;
-                                        }
-                                        Err(e) => {
-                                            lerror!(
-                                                &ctx.request_id,
-                                                LogStage::RequestModify,
-                                                LogComponent::Proxy,
-                                                "request_body_serialize_fail",
-                                                &format!("序列化修改后的 JSON 失败: {e}")
-                                            );
-                                        }
-                                    }
-                                }
-                                Ok(false) => {
-                                    linfo!(
-                                        &ctx.request_id,
-                                        LogStage::RequestModify,
-                                        LogComponent::Proxy,
-                                        "request_body_not_modified",
-                                        "策略选择不修改请求体"
-                                    );
-                                }
-                                Err(e) => {
-                                    lerror!(
-                                        &ctx.request_id,
-                                        LogStage::RequestModify,
-                                        LogComponent::Proxy,
-                                        "request_body_modify_fail",
-                                        &format!("执行请求体修改策略失败: {e}")
-                                    );
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            lerror!(
-                                &ctx.request_id,
-                                LogStage::RequestModify,
-                                LogComponent::Proxy,
-                                "request_body_parse_fail",
-                                &format!("解析请求体 JSON 失败: {e}"),
-                                body_preview = %String::from_utf8_lossy(&ctx.request.body[..std::cmp::min(500, ctx.request.body.len())])
-                            );
-                        }
-                    }
-                }
-            } else if ctx.request.body.is_empty() && ctx.request.will_modify_body {
+            if newly_truncated {
                 lwarn!(
                     &ctx.request_id,
                     LogStage::RequestModify,
                     LogComponent::Proxy,
-                    "request_body_empty_for_modify",
-                    "策略期望修改请求体，但请求体为空"
+                    "request_body_truncated",
+                    "请求体缓存超过上限，已截断",
+                    buffer_limit_bytes = Self::MAX_BODY_BUFFER_BYTES,
+                    received_bytes = ctx.request.body_received_size
                 );
             }
-
-            // 如果提前吞掉了分块但未能改写，确保把原始数据再发送出去
-            if ctx.request.will_modify_body && !chunk_replaced {
-                let original_body = Bytes::copy_from_slice(ctx.request.body.as_ref());
-                *body_chunk = Some(original_body);
-            }
+        }
+        if end_of_stream {
+            Self::log_request_body_eom(ctx);
         }
 
         Ok(())
@@ -650,6 +1634,7 @@ impl ProxyHttp for ProxyService {
         upstream_response: &mut ResponseHeader,
         ctx: &mut Self::CTX,
     ) -> pingora_core::Result<()> {
+        ctx.trace.upstream_timing.record_first_byte();
         self.state
             .resp_transform_service
             .filter_response(session, upstream_response, ctx)?;
@@ -659,8 +1644,49 @@ impl ProxyHttp for ProxyService {
             .collect_service
             .collect_response_details(upstream_response, ctx);
         ctx.response.details.headers = resp_stats.headers;
+        // 成功响应的响应头耗时计入密钥延迟统计，供成本感知调度避开过慢的密钥
+        if upstream_response.status.as_u16() < 400
+            && let Some(backend) = ctx.routing.selected_backend.as_ref()
+        {
+            self.state
+                .key_scheduler_service
+                .latency_stats()
+                .record(backend.id, ctx.start_time.elapsed());
+        }
         ctx.response.is_sse =
             Self::is_sse_content_type(ctx.response.details.content_type.as_deref());
+        if ctx.response.is_sse && ctx.response.details.content_encoding.is_none() {
+            let cost_ceiling = if upstream_response.status.as_u16() < 400 {
+                self.init_cost_ceiling(session, ctx).await
+            } else {
+                None
+            };
+            let collect_service = &self.state.collect_service;
+            ctx.response.stream_usage = Some(
+                SseUsageTracker::new(ctx.routing.provider_type.as_ref())
+                    .with_metadata_fields(collect_service.response_metadata_fields())
+                    .with_completion_text(
+                        collect_service.estimates_tokens() || cost_ceiling.is_some(),
+                    ),
+            );
+            ctx.response.cost_ceiling = cost_ceiling;
+            // 用量与元数据已逐帧提取，成功的流式响应可按配置不缓存响应体
+            ctx.response.body_capture_skipped = upstream_response.status.as_u16() < 400
+                && !collect_service.captures_streaming_body()
+                && !self.may_debug_capture(ctx);
+        }
+        self.init_sse_event_flush(ctx);
+
+        if ctx.request.is_websocket && upstream_response.status.as_u16() == 101 {
+            ctx.response.websocket = Some(WebSocketSession::new());
+            linfo!(
+                &ctx.request_id,
+                LogStage::Response,
+                LogComponent::Proxy,
+                "websocket_upgraded",
+                "上游已完成 WebSocket 升级，开始双向透传"
+            );
+        }
 
         Ok(())
     }
@@ -672,6 +1698,10 @@ impl ProxyHttp for ProxyService {
         ctx: &mut Self::CTX,
     ) -> pingora_core::Result<()> {
         let status_code = upstream_response.status.as_u16();
+        // 上游已响应：5xx 计入熔断失败，其余状态视为上游可用
+        self.state
+            .upstream_service
+            .record_upstream_result(ctx, status_code < 500);
 
         if !Self::should_retry_upstream_status(status_code) {
             return Ok(());
@@ -715,8 +1745,17 @@ impl ProxyHttp for ProxyService {
         e: Box<Error>,
     ) -> Box<Error> {
         let mut err = e.more_context(format!("Peer: {peer}"));
+        self.state
+            .upstream_service
+            .record_upstream_result(ctx, false);
         // 连接建立阶段失败：通常属于可重试范畴，交由预算控制。
-        Self::apply_retry_policy(session, ctx, err.as_mut(), "connect_failure", None);
+        Self::apply_retry_policy(
+            session,
+            ctx,
+            err.as_mut(),
+            retry_policy::CONNECT_FAILURE,
+            None,
+        );
         err
     }
 
@@ -814,14 +1853,16 @@ impl ProxyHttp for ProxyService {
         end_of_stream: bool,
         ctx: &mut Self::CTX,
     ) -> pingora_core::Result<Option<std::time::Duration>> {
+        // WebSocket 会话：不缓存原始帧，只旁路解析会话事件
+        if let Some(session) = ctx.response.websocket.as_mut() {
+            if let Some(chunk) = body.as_ref() {
+                session.observe_upstream(chunk, ctx.routing.provider_type.as_ref());
+            }
+            return Ok(None);
+        }
+
         if let Some(chunk) = body.as_ref() {
-            let newly_truncated = Self::append_body_with_limit(
-                &mut ctx.response.body,
-                &mut ctx.response.body_received_size,
-                &mut ctx.response.body_truncated,
-                chunk,
-                Self::MAX_BODY_BUFFER_BYTES,
-            );
+            let newly_truncated = Self::observe_response_chunk(ctx, chunk);
             if newly_truncated {
                 lwarn!(
                     &ctx.request_id,
@@ -834,6 +1875,23 @@ impl ProxyHttp for ProxyService {
                 );
             }
         }
+        // 总时长按墙钟计算：慢速持续输出的上游即使一直有数据块到达也会被截断
+        Self::enforce_total_timeout(ctx)?;
+        // 单请求费用上限：按已观察到的用量与输出文本估算，超过后中断上游流
+        Self::enforce_cost_ceiling(ctx)?;
+        // 上游错误规范化：流结束时一次性输出统一格式的错误响应体
+        if let Some(sanitizer) = ctx.response.upstream_error.as_mut() {
+            *body = sanitizer.push(body.as_ref(), end_of_stream);
+        }
+        // 白标模型别名：上面缓存的是原始响应体，改写只影响返回客户端的内容
+        if let Some(rewriter) = ctx.response.model_alias.as_mut() {
+            *body = rewriter.push(body.as_ref(), end_of_stream);
+        }
+        // 按事件刷新：完整事件立即发出，不等待后续事件，末尾的半个事件暂存到补全后再发；
+        // 未开启时数据块到达即原样转发，客户端可能先收到半个事件
+        if let Some(flusher) = ctx.response.sse_event_flush.as_mut() {
+            *body = flusher.push(body.as_ref(), end_of_stream);
+        }
         if !ctx.response.sse_keepalive_sent
             && ctx.response.is_sse
             && ctx.response.details.status_code == Some(200)
@@ -869,7 +1927,62 @@ impl ProxyHttp for ProxyService {
         Ok(None)
     }
 
+    /// 记录上游尾部响应头（HTTP/2 trailer），部分服务商在其中上报最终用量，供统计时核对
+    async fn response_trailer_filter(
+        &self,
+        _session: &mut Session,
+        upstream_trailers: &mut http::HeaderMap,
+        ctx: &mut Self::CTX,
+    ) -> pingora_core::Result<Option<Bytes>> {
+        for (name, value) in &*upstream_trailers {
+            if let Ok(value) = value.to_str() {
+                ctx.response
+                    .details
+                    .headers
+                    .insert(name.as_str().to_string(), value.to_string());
+            }
+        }
+        Ok(None)
+    }
+
+    fn suppress_error_log(&self, _session: &Session, ctx: &Self::CTX, _error: &Error) -> bool {
+        // 探针以错误短路返回，不应出现在错误日志中
+        ctx.request.is_probe
+    }
+
+    async fn fail_to_proxy(
+        &self,
+        session: &mut Session,
+        e: &Error,
+        ctx: &mut Self::CTX,
+    ) -> FailToProxy {
+        let error_code = Self::failure_status_code(e);
+        // 已发送响应头（如拒绝响应或流式中断）时不能再写错误体
+        if error_code > 0
+            && session.response_written().is_none()
+            && let Err(err) = write_proxy_failure(session, &ctx.request_id, error_code).await
+        {
+            lwarn!(
+                &ctx.request_id,
+                LogStage::ResponseFailure,
+                LogComponent::Proxy,
+                "write_error_response_failed",
+                "写入代理错误响应失败",
+                status = error_code,
+                error = %err
+            );
+        }
+        FailToProxy {
+            error_code,
+            can_reuse_downstream: false,
+        }
+    }
+
     async fn logging(&self, session: &mut Session, e: Option<&Error>, ctx: &mut Self::CTX) {
+        // 探针请求不计入代理流量统计
+        if ctx.request.is_probe {
+            return;
+        }
         let status_code = Self::resolve_status_code(ctx, e);
 
         if let Some(strategy) = &ctx.routing.strategy
@@ -885,12 +1998,20 @@ impl ProxyHttp for ProxyService {
                 &format!("Provider strategy handle_response_body failed: {e}")
             );
         }
+        self.apply_key_health_update(ctx, status_code).await;
 
         let metrics = self
             .state
             .collect_service
             .finalize_metrics(ctx, status_code)
             .await;
+        self.settle_credits(ctx, &metrics).await;
+        ctx.trace.debug_capture = Self::resolve_debug_capture(
+            &self.state.context().config().debug_capture,
+            ctx,
+            metrics.model.as_deref(),
+            Utc::now(),
+        );
 
         if ctx.is_trace_started() {
             self.state
@@ -904,7 +2025,13 @@ impl ProxyHttp for ProxyService {
                 .await;
         }
 
-        if status_code < 400 {
+        if let Some(abort_kind) = StreamAbortKind::detect(ctx, e) {
+            // 响应已开始下发后连接中断：按中断状态收尾，保留已解析的部分用量
+            self.state
+                .trace_manager
+                .record_aborted(&metrics, abort_kind, e, ctx)
+                .await;
+        } else if status_code < 400 {
             if let Err(err) = self.state.trace_manager.record_success(&metrics, ctx).await {
                 lwarn!(
                     &ctx.request_id,
@@ -921,9 +2048,23 @@ impl ProxyHttp for ProxyService {
                 .await;
         }
 
-        // 根据 user_service_api.log_mode 输出完整请求/响应日志（包含 body schema，内容可截断）
+        // 根据 user_service_api.log_mode 或定向调试采集输出完整请求/响应日志（包含 body schema）
         logging::log_user_service_api_log_mode(ctx, status_code);
 
+        if let Some(session) = &ctx.response.websocket {
+            linfo!(
+                &ctx.request_id,
+                LogStage::Response,
+                LogComponent::Proxy,
+                "websocket_session_closed",
+                "WebSocket 会话结束",
+                bytes_sent = ctx.request.body_received_size,
+                bytes_received = session.bytes_received,
+                messages_received = session.messages_received,
+                duration_ms = ctx.start_time.elapsed().as_millis()
+            );
+        }
+
         linfo!(
             &ctx.request_id,
             LogStage::Response,
@@ -942,6 +2083,7 @@ mod tests {
 
     use entity::user_service_apis;
     use pingora_core::protocols::l4::stream::Stream;
+    use std::time::Instant;
     use tokio::io::AsyncWriteExt;
     use tokio::net::{TcpListener, TcpStream};
 
@@ -1005,16 +2147,31 @@ mod tests {
             provider_type_id: 1,
             user_provider_keys_ids: serde_json::json!([]),
             api_key: "test-api-key".to_string(),
+            previous_api_key: None,
+            previous_api_key_expires_at: None,
             name: None,
             description: None,
             scheduling_strategy: None,
             retry_count: Some(retry_count),
             timeout_seconds: None,
+            max_response_duration_seconds: None,
+            sla_target_ms: None,
             max_request_per_min: None,
             max_requests_per_day: None,
             max_tokens_per_day: None,
             max_cost_per_day: None,
+            max_cost_per_request: None,
             log_mode: false,
+            selection_debug: false,
+            stream_policy: "allow".to_string(),
+            upstream_error_mode: "sanitized".to_string(),
+            priority: 0,
+            request_transform_rules: None,
+            response_headers: None,
+            allowed_paths: None,
+            system_prompt: None,
+            key_tag: None,
+            model_routes: None,
             expires_at: None,
             is_active: true,
             created_at: now,
@@ -1022,6 +2179,32 @@ mod tests {
         }
     }
 
+    /// 测试用提供商：按 `OpenAI` 格式的 `usage` 字段提取 token
+    fn make_test_provider(name: &str) -> entity::provider_types::Model {
+        let now = chrono::Utc::now().naive_utc();
+        entity::provider_types::Model {
+            id: 9300,
+            name: name.to_string(),
+            display_name: name.to_string(),
+            auth_type: "api_key".to_string(),
+            base_url: "api.openai.com".to_string(),
+            is_active: true,
+            config_json: None,
+            token_mappings_json: Some(
+                json!({
+                    "tokens_prompt": {"type": "direct", "path": "usage.prompt_tokens"},
+                    "tokens_completion": {"type": "direct", "path": "usage.completion_tokens"},
+                    "tokens_total": {"type": "direct", "path": "usage.total_tokens"}
+                })
+                .to_string(),
+            ),
+            model_extraction_json: None,
+            auth_configs_json: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
     fn reset_retry_policy_state(ctx: &mut ProxyContext) {
         ctx.control.retry.reset_for_new_attempt();
     }
@@ -1108,6 +2291,105 @@ mod tests {
         assert_eq!(ctx.control.retry.retry_count, 1);
     }
 
+    #[tokio::test]
+    async fn test_non_idempotent_post_is_not_retried_after_proxy_error() {
+        let config = crate::config::RetryConfig::default();
+        let retry_ctx = |session: &Session| {
+            let mut ctx = ProxyContext {
+                request_id: "test-request".to_string(),
+                start_time: Instant::now(),
+                ..Default::default()
+            };
+            ctx.routing.user_service_api = Some(make_test_user_service_api(2));
+            ctx.control.retry.idempotency_guard = config.guard_for(session.req_header());
+            ctx
+        };
+
+        // POST 在连接中途失败时可能已被上游执行，不重试
+        let mut session = make_test_session(
+            "POST /v1/chat/completions HTTP/1.1\r\nHost: example.com\r\nContent-Length: 0\r\n\r\n",
+        )
+        .await;
+        let mut ctx = retry_ctx(&session);
+        let mut err = PingoraError::new_up(ErrorType::ConnectionClosed);
+        err.set_retry(true);
+        ProxyService::apply_retry_policy(&mut session, &mut ctx, err.as_mut(), "proxy_error", None);
+        assert!(!err.retry());
+        assert_eq!(ctx.control.retry.retry_count, 0);
+        reset_retry_policy_state(&mut ctx);
+
+        // 上游以 503 拒绝时请求未被执行，POST 仍可重试
+        let mut err = PingoraError::new_up(ErrorType::HTTPStatus(503));
+        ProxyService::apply_retry_policy(
+            &mut session,
+            &mut ctx,
+            err.as_mut(),
+            "upstream_5xx",
+            Some(503),
+        );
+        assert!(err.retry());
+        assert_eq!(ctx.control.retry.retry_count, 1);
+
+        // GET 为幂等请求，照常重试
+        let mut session =
+            make_test_session("GET /v1/models HTTP/1.1\r\nHost: example.com\r\n\r\n").await;
+        let mut ctx = retry_ctx(&session);
+        let mut err = PingoraError::new_up(ErrorType::ConnectionClosed);
+        err.set_retry(true);
+        ProxyService::apply_retry_policy(&mut session, &mut ctx, err.as_mut(), "proxy_error", None);
+        assert!(err.retry());
+        assert_eq!(ctx.control.retry.retry_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_provider_retry_budget_suppresses_retries_once_exhausted() {
+        let budget = Arc::new(crate::proxy::retry_budget::RetryBudget::new(
+            crate::config::RetryBudgetConfig {
+                enabled: true,
+                capacity: 1,
+                refill_per_sec: 0.001,
+            },
+        ));
+        let budget_ctx = || {
+            let mut ctx = ProxyContext {
+                request_id: "test-request".to_string(),
+                start_time: Instant::now(),
+                ..Default::default()
+            };
+            ctx.routing.user_service_api = Some(make_test_user_service_api(2));
+            ctx.routing.provider_type = Some(make_test_provider("openai"));
+            ctx.control.retry.provider_budget = Some(Arc::clone(&budget));
+            ctx
+        };
+
+        // 预算内照常重试
+        let mut session = make_test_session("GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").await;
+        let mut ctx = budget_ctx();
+        let mut err = PingoraError::new_up(ErrorType::HTTPStatus(502));
+        ProxyService::apply_retry_policy(
+            &mut session,
+            &mut ctx,
+            err.as_mut(),
+            "upstream_5xx",
+            Some(502),
+        );
+        assert!(err.retry());
+        assert_eq!(ctx.control.retry.retry_count, 1);
+
+        // 同一服务商的预算已耗尽：其他请求即使还有 retry_count 也直接失败
+        let mut ctx = budget_ctx();
+        let mut err = PingoraError::new_up(ErrorType::HTTPStatus(502));
+        ProxyService::apply_retry_policy(
+            &mut session,
+            &mut ctx,
+            err.as_mut(),
+            "upstream_5xx",
+            Some(502),
+        );
+        assert!(!err.retry());
+        assert_eq!(ctx.control.retry.retry_count, 0);
+    }
+
     #[tokio::test]
     async fn test_retry_after_is_capped_by_db_timeout_seconds() {
         let mut session = make_test_session("GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").await;
@@ -1213,4 +2495,606 @@ mod tests {
         assert!(!err.retry());
         assert_eq!(ctx.control.retry.retry_count, 0);
     }
+
+    #[test]
+    fn test_service_api_timeout_is_clamped_into_global_bounds() {
+        let bounds = TimeoutBoundsConfig {
+            min_secs: 10,
+            max_secs: 300,
+        };
+
+        assert_eq!(
+            ProxyService::select_timeout_seconds(&bounds, Some(1), "test"),
+            10
+        );
+        assert_eq!(
+            ProxyService::select_timeout_seconds(&bounds, Some(86_400), "test"),
+            300
+        );
+        assert_eq!(
+            ProxyService::select_timeout_seconds(&bounds, Some(60), "test"),
+            60
+        );
+        // 未配置或非正数时先回退默认值
+        assert_eq!(
+            ProxyService::select_timeout_seconds(&bounds, Some(0), "test"),
+            120
+        );
+        assert_eq!(
+            ProxyService::select_timeout_seconds(&bounds, None, "test"),
+            120
+        );
+    }
+
+    #[test]
+    fn test_streamed_response_bytes_sum_all_chunks_past_buffer_limit() {
+        let mut ctx = ProxyContext::default();
+        ctx.response.is_sse = true;
+        let chunks: [&[u8]; 4] = [
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+            b"data: {\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2}}\n\n",
+            b"data: [DONE]\n\n",
+        ];
+
+        // 缓存上限远小于响应体：缓存被截断，但字节数仍按全部分块累计
+        for chunk in chunks {
+            ProxyService::append_body_with_limit(
+                &mut ctx.response.body,
+                &mut ctx.response.body_received_size,
+                &mut ctx.response.body_truncated,
+                chunk,
+                64,
+            );
+        }
+
+        let expected: usize = chunks.iter().map(|chunk| chunk.len()).sum();
+        assert!(ctx.response.body_truncated);
+        assert_eq!(ctx.response.body.len(), 64);
+        assert_eq!(ctx.response_bytes(), expected as u64);
+
+        ctx.request.body_received_size = 128;
+        assert_eq!(ctx.request_bytes(), 128);
+    }
+
+    #[test]
+    fn test_streamed_usage_survives_buffer_truncation() {
+        let provider = make_test_provider("openai");
+        let mut ctx = ProxyContext::default();
+        ctx.request.requested_model = Some("gpt-4o".to_string());
+        ctx.response.is_sse = true;
+        ctx.response.details.content_type = Some("text/event-stream".to_string());
+        ctx.response.stream_usage = Some(SseUsageTracker::new(Some(&provider)));
+        ctx.routing.provider_type = Some(provider);
+
+        let chunks: [&[u8]; 4] = [
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}],\"usage\":null}\n\n",
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}],\"usage\":null}\n\n",
+            b"data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}}\n\n",
+            b"data: [DONE]\n\n",
+        ];
+        // 缓存只保留前 64 字节，末尾的用量事件只能由分块跟踪得到
+        for chunk in chunks {
+            if let Some(tracker) = ctx.response.stream_usage.as_mut() {
+                tracker.observe_chunk(chunk, ctx.routing.provider_type.as_ref());
+            }
+            ProxyService::append_body_with_limit(
+                &mut ctx.response.body,
+                &mut ctx.response.body_received_size,
+                &mut ctx.response.body_truncated,
+                chunk,
+                64,
+            );
+        }
+        assert!(ctx.response.body_truncated);
+
+        let stats = crate::collect::usage_model::finalize_eos(&mut ctx);
+        assert_eq!(stats.usage.prompt_tokens, Some(3));
+        assert_eq!(stats.usage.completion_tokens, Some(2));
+        assert_eq!(stats.usage.total_tokens, Some(5));
+        assert_eq!(stats.model_name.as_deref(), Some("gpt-4o"));
+        assert!(ctx.response.stream_usage.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_large_stream_with_slow_consumer_keeps_memory_bounded() {
+        use crate::collect::stream_usage::MAX_PENDING_EVENT_BYTES;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        const CHANNEL_CAPACITY: usize = 4;
+        const EVENTS: usize = 4096;
+
+        let provider = make_test_provider("openai");
+        let mut ctx = ProxyContext::default();
+        ctx.request.requested_model = Some("gpt-4o".to_string());
+        ctx.response.is_sse = true;
+        ctx.response.details.content_type = Some("text/event-stream".to_string());
+        ctx.response.stream_usage = Some(SseUsageTracker::new(Some(&provider)));
+        ctx.routing.provider_type = Some(provider);
+
+        // 快速上游：约 16 MiB 的增量事件、一个超过事件缓存上限的超长事件，最后是用量事件
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Bytes>(CHANNEL_CAPACITY);
+        let sent = Arc::new(AtomicUsize::new(0));
+        let producer_sent = Arc::clone(&sent);
+        let producer = tokio::spawn(async move {
+            let delta = format!(
+                "data: {{\"choices\":[{{\"delta\":{{\"content\":\"{}\"}}}}],\"usage\":null}}\n\n",
+                "x".repeat(4096)
+            );
+            let oversized = format!(
+                "data: {{\"choices\":[{{\"delta\":{{\"content\":\"{}\"}}}}]}}\n\n",
+                "y".repeat(2 * MAX_PENDING_EVENT_BYTES)
+            );
+            let usage = "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":11,\"completion_tokens\":4096,\"total_tokens\":4107}}\n\ndata: [DONE]\n\n";
+            let chunks = std::iter::repeat_n(Bytes::from(delta), EVENTS)
+                .chain(
+                    Bytes::from(oversized)
+                        .chunks(64 * 1024)
+                        .map(Bytes::copy_from_slice)
+                        .collect::<Vec<_>>(),
+                )
+                .chain(std::iter::once(Bytes::from_static(usage.as_bytes())));
+            for chunk in chunks {
+                if tx.send(chunk).await.is_err() {
+                    break;
+                }
+                producer_sent.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        // 慢速下游：每个分块处理后等待，检查上游最多领先通道容量
+        let mut received = 0;
+        let mut peak_body = 0;
+        let mut peak_pending = 0;
+        while let Some(chunk) = rx.recv().await {
+            received += 1;
+            assert!(sent.load(Ordering::SeqCst) <= received + CHANNEL_CAPACITY);
+            ProxyService::observe_response_chunk(&mut ctx, &chunk);
+            peak_body = peak_body.max(ctx.response.body.len());
+            if let Some(tracker) = ctx.response.stream_usage.as_ref() {
+                peak_pending = peak_pending.max(tracker.pending_bytes());
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        producer.await.unwrap();
+
+        assert!(ctx.response.body_received_size > 16 * 1024 * 1024);
+        assert!(ctx.response.body_truncated);
+        assert!(peak_body <= ProxyService::MAX_BODY_BUFFER_BYTES);
+        assert!(peak_pending <= MAX_PENDING_EVENT_BYTES);
+        assert_eq!(
+            ctx.response
+                .stream_usage
+                .as_ref()
+                .map(SseUsageTracker::discarded_events),
+            Some(1)
+        );
+
+        let stats = crate::collect::usage_model::finalize_eos(&mut ctx);
+        assert_eq!(stats.usage.prompt_tokens, Some(11));
+        assert_eq!(stats.usage.completion_tokens, Some(4096));
+        assert_eq!(stats.usage.total_tokens, Some(4107));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_body_not_captured_keeps_usage_and_metadata() {
+        use crate::collect::service::CollectService;
+        use crate::collect::usage_model::{BODY_CAPTURE_KEY, BODY_NOT_CAPTURED_MARKER};
+        use crate::config::ResponseBodyConfig;
+        use crate::pricing::PricingCalculatorService;
+        use migration::{Migrator, MigratorTrait};
+
+        let db = sea_orm::Database::connect("sqlite::memory:")
+            .await
+            .expect("connect test db");
+        Migrator::up(&db, None).await.expect("run migrations");
+        let collect_service = CollectService::new(
+            Arc::new(PricingCalculatorService::new(Arc::new(db))),
+            vec!["id".to_string()],
+        )
+        .with_response_body_config(ResponseBodyConfig {
+            capture_streaming_body: false,
+            ..ResponseBodyConfig::default()
+        });
+
+        let provider = make_test_provider("openai");
+        let mut ctx = ProxyContext::default();
+        ctx.response.is_sse = true;
+        ctx.response.details.status_code = Some(200);
+        ctx.response.details.content_type = Some("text/event-stream".to_string());
+        ctx.response.stream_usage = Some(
+            SseUsageTracker::new(Some(&provider))
+                .with_metadata_fields(collect_service.response_metadata_fields()),
+        );
+        ctx.response.body_capture_skipped = !collect_service.captures_streaming_body();
+        ctx.routing.provider_type = Some(provider);
+
+        let chunks: [&[u8]; 3] = [
+            b"data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[{\"delta\":{\"content\":\"Hi\"}}],\"usage\":null}\n\n",
+            b"data: {\"id\":\"chatcmpl-1\",\"model\":\"gpt-4o\",\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":1,\"total_tokens\":4}}\n\n",
+            b"data: [DONE]\n\n",
+        ];
+        for chunk in chunks {
+            if let Some(tracker) = ctx.response.stream_usage.as_mut() {
+                tracker.observe_chunk(chunk, ctx.routing.provider_type.as_ref());
+            }
+            ProxyService::buffer_response_chunk(&mut ctx, chunk);
+        }
+        assert!(ctx.response.body.is_empty());
+        assert_eq!(
+            ctx.response.body_received_size,
+            chunks.iter().map(|chunk| chunk.len()).sum::<usize>()
+        );
+
+        let metrics = collect_service.finalize_metrics(&mut ctx, 200).await;
+        assert_eq!(metrics.usage.prompt_tokens, Some(3));
+        assert_eq!(metrics.usage.completion_tokens, Some(1));
+        assert_eq!(metrics.usage.total_tokens, Some(4));
+        assert_eq!(metrics.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(
+            metrics.response_metadata,
+            Some(json!({"id": "chatcmpl-1", BODY_CAPTURE_KEY: BODY_NOT_CAPTURED_MARKER}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_stream_usage_is_estimated_when_enabled() {
+        use crate::collect::service::CollectService;
+        use crate::config::{ResponseBodyConfig, TokenEstimationConfig};
+        use crate::pricing::PricingCalculatorService;
+        use migration::{Migrator, MigratorTrait};
+
+        let db = sea_orm::Database::connect("sqlite::memory:")
+            .await
+            .expect("connect test db");
+        Migrator::up(&db, None).await.expect("run migrations");
+        let collect_service = CollectService::new(
+            Arc::new(PricingCalculatorService::new(Arc::new(db))),
+            vec![],
+        )
+        .with_response_body_config(ResponseBodyConfig {
+            capture_streaming_body: false,
+            ..ResponseBodyConfig::default()
+        })
+        .with_token_estimation(TokenEstimationConfig { enabled: true });
+
+        let provider = make_test_provider("openai_compatible_gateway");
+        let mut ctx = ProxyContext::default();
+        ctx.request.requested_model = Some("gpt-4".to_string());
+        ctx.request.body.extend_from_slice(
+            json!({
+                "model": "gpt-4",
+                "stream": true,
+                "messages": [
+                    {"role": "system", "content": "You are a helpful assistant."},
+                    {"role": "user", "content": "Hello!"}
+                ]
+            })
+            .to_string()
+            .as_bytes(),
+        );
+        ctx.response.is_sse = true;
+        ctx.response.details.status_code = Some(200);
+        ctx.response.details.content_type = Some("text/event-stream".to_string());
+        ctx.response.stream_usage = Some(
+            SseUsageTracker::new(Some(&provider))
+                .with_completion_text(collect_service.estimates_tokens()),
+        );
+        ctx.response.body_capture_skipped = !collect_service.captures_streaming_body();
+        ctx.routing.provider_type = Some(provider);
+
+        // 网关没有返回任何用量事件
+        let chunks: [&[u8]; 3] = [
+            b"data: {\"model\":\"gpt-4\",\"choices\":[{\"delta\":{\"content\":\"The quick brown fox \"}}]}\n\n",
+            b"data: {\"model\":\"gpt-4\",\"choices\":[{\"delta\":{\"content\":\"jumps over the lazy dog.\"}}]}\n\n",
+            b"data: [DONE]\n\n",
+        ];
+        for chunk in chunks {
+            if let Some(tracker) = ctx.response.stream_usage.as_mut() {
+                tracker.observe_chunk(chunk, ctx.routing.provider_type.as_ref());
+            }
+            ProxyService::buffer_response_chunk(&mut ctx, chunk);
+        }
+
+        let metrics = collect_service.finalize_metrics(&mut ctx, 200).await;
+        assert!(metrics.usage.estimated);
+        let prompt = metrics.usage.prompt_tokens.expect("prompt estimated");
+        let completion = metrics
+            .usage
+            .completion_tokens
+            .expect("completion estimated");
+        assert!(prompt.abs_diff(19) <= 2, "prompt tokens {prompt}");
+        assert!(
+            completion.abs_diff(10) <= 2,
+            "completion tokens {completion}"
+        );
+        assert_eq!(metrics.usage.total_tokens, Some(prompt + completion));
+    }
+
+    #[tokio::test]
+    async fn test_drip_feeding_upstream_is_cut_off_at_total_timeout() {
+        let mut ctx = ProxyContext::default();
+        ctx.control.total_timeout = Some(Duration::from_millis(200));
+        ctx.response.is_sse = true;
+        ctx.response.details.status_code = Some(200);
+
+        // 上游每 50ms 吐一个数据块，单次读取都不会超时，总共需要 1s
+        let mut delivered = 0;
+        let mut error = None;
+        for _ in 0..20 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            ProxyService::buffer_response_chunk(&mut ctx, b"data: {\"choices\":[]}\n\n");
+            if let Err(err) = ProxyService::enforce_total_timeout(&ctx) {
+                error = Some(err);
+                break;
+            }
+            delivered += 1;
+        }
+
+        let err = error.expect("drip feed should hit the total timeout");
+        assert!(delivered < 20);
+        assert!(ctx.start_time.elapsed() < Duration::from_millis(600));
+        assert_eq!(
+            StreamAbortKind::detect(&ctx, Some(&err)),
+            Some(StreamAbortKind::TotalTimeout)
+        );
+        assert_eq!(StreamAbortKind::TotalTimeout.as_str(), "total_timeout");
+        assert_eq!(ProxyService::resolve_status_code(&ctx, Some(&err)), 504);
+    }
+
+    #[test]
+    fn test_streaming_is_cut_off_once_estimated_cost_crosses_ceiling() {
+        let now = chrono::Utc::now().naive_utc();
+        let completion_tier = entity::model_pricing_tiers::Model {
+            id: 1,
+            model_pricing_id: 1,
+            token_type: "completion".to_string(),
+            min_tokens: 0,
+            max_tokens: None,
+            price_per_token: 0.001,
+            created_at: now,
+            updated_at: now,
+        };
+        let prices = crate::pricing::ModelPriceSheet::new(vec![completion_tier], "USD".to_string());
+        let mut ctx = ProxyContext::default();
+        ctx.response.is_sse = true;
+        ctx.response.details.status_code = Some(200);
+        ctx.response.stream_usage = Some(SseUsageTracker::new(None).with_completion_text(true));
+        // 无分词器的模型按约 4 字符 / Token 估算：每个分块 20 字符 ≈ 5 Token ≈ 0.005
+        ctx.response.cost_ceiling = Some(CostCeiling::new(0.01, prices, "claude-sonnet-4"));
+
+        let chunk = format!(
+            "data: {}\n\n",
+            json!({"choices": [{"delta": {"content": "x".repeat(20)}}]})
+        );
+        let mut delivered = 0;
+        let mut error = None;
+        for _ in 0..10 {
+            ProxyService::observe_response_chunk(&mut ctx, chunk.as_bytes());
+            if let Err(err) = ProxyService::enforce_cost_ceiling(&mut ctx) {
+                error = Some(err);
+                break;
+            }
+            delivered += 1;
+        }
+
+        let err = error.expect("stream should be cut off at the cost ceiling");
+        assert_eq!(delivered, 2);
+        assert!(ctx.response.cost_limit_reached);
+        assert_eq!(
+            StreamAbortKind::detect(&ctx, Some(&err)),
+            Some(StreamAbortKind::CostLimitReached)
+        );
+        assert_eq!(
+            StreamAbortKind::CostLimitReached.as_str(),
+            "cost_limit_reached"
+        );
+        assert_eq!(ProxyService::resolve_status_code(&ctx, Some(&err)), 402);
+    }
+
+    #[test]
+    fn test_debug_capture_only_applies_to_targeted_requests_until_expiry() {
+        let now = chrono::Utc::now();
+        let config = crate::config::DebugCaptureConfig {
+            enabled: true,
+            expires_at: Some(now + chrono::Duration::minutes(10)),
+            user_service_api_ids: vec![1],
+            models: vec!["gpt-4o".to_string()],
+            ..Default::default()
+        };
+
+        let mut targeted = ProxyContext::default();
+        targeted.routing.user_service_api = Some(make_test_user_service_api(0));
+        targeted.request.requested_model = Some("gpt-4o".to_string());
+
+        let mut other = ProxyContext::default();
+        other.routing.user_service_api = Some(user_service_apis::Model {
+            id: 2,
+            ..make_test_user_service_api(0)
+        });
+        other.request.requested_model = Some("gpt-4o".to_string());
+
+        assert!(ProxyService::resolve_debug_capture(
+            &config, &targeted, None, now
+        ));
+        assert!(!ProxyService::resolve_debug_capture(
+            &config,
+            &targeted,
+            Some("gpt-4o-mini"),
+            now
+        ));
+        assert!(!ProxyService::resolve_debug_capture(
+            &config, &other, None, now
+        ));
+        assert!(!ProxyService::resolve_debug_capture(
+            &config,
+            &targeted,
+            None,
+            now + chrono::Duration::minutes(11)
+        ));
+
+        // 未开启 log_mode 的服务 API 只有命中调试采集时才记录完整内容
+        assert!(!targeted.captures_full_detail());
+        targeted.trace.debug_capture = true;
+        assert!(targeted.captures_full_detail());
+        assert!(!other.captures_full_detail());
+    }
+
+    fn opaque_response_ctx(content_type: &str, status: u16) -> ProxyContext {
+        let mut ctx = ProxyContext::default();
+        ctx.request.requested_model = Some("tts-1".to_string());
+        ctx.response.details.content_type = Some(content_type.to_string());
+        ctx.response.details.status_code = Some(status);
+        ctx.response.is_opaque_body =
+            !crate::config::ResponseBodyConfig::default().is_json(Some(content_type));
+        ctx
+    }
+
+    #[test]
+    fn test_text_plain_response_skips_json_extraction() {
+        let mut ctx = opaque_response_ctx("text/plain; charset=utf-8", 200);
+        assert!(ctx.response.is_opaque_body);
+
+        // 文本中恰好包含 JSON 片段，也不应被当作用量解析
+        let chunks: [&[u8]; 2] = [b"{\"usage\":{\"prompt_tokens\":99}} is ", b"just text"];
+        for chunk in chunks {
+            assert!(!ProxyService::buffer_response_chunk(&mut ctx, chunk));
+        }
+
+        let expected: usize = chunks.iter().map(|chunk| chunk.len()).sum();
+        assert!(ctx.response.body.is_empty());
+        assert_eq!(ctx.response_bytes(), expected as u64);
+
+        let stats = crate::collect::usage_model::finalize_eos(&mut ctx);
+        assert_eq!(stats.usage.prompt_tokens, Some(0));
+        assert_eq!(stats.usage.total_tokens, Some(0));
+        assert_eq!(stats.model_name.as_deref(), Some("tts-1"));
+        assert!(
+            crate::collect::usage_model::extract_response_metadata(
+                &ctx,
+                &["usage.prompt_tokens".to_string()]
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_image_response_counts_bytes_without_buffering() {
+        let mut ctx = opaque_response_ctx("image/png", 200);
+        let image: Vec<u8> = b"\x89PNG\r\n\x1a\n"
+            .iter()
+            .copied()
+            .chain((0..=255u8).cycle().take(4096))
+            .collect();
+
+        for chunk in image.chunks(1000) {
+            assert!(!ProxyService::buffer_response_chunk(&mut ctx, chunk));
+        }
+
+        assert!(ctx.response.body.is_empty());
+        assert!(!ctx.response.body_truncated);
+        assert_eq!(ctx.response_bytes(), image.len() as u64);
+
+        let stats = crate::collect::usage_model::finalize_eos(&mut ctx);
+        assert_eq!(stats.usage.total_tokens, Some(0));
+        assert_eq!(stats.model_name.as_deref(), Some("tts-1"));
+    }
+
+    #[test]
+    fn test_opaque_error_response_is_still_buffered() {
+        let mut ctx = opaque_response_ctx("text/plain", 502);
+        ProxyService::buffer_response_chunk(&mut ctx, b"upstream connect error");
+
+        assert_eq!(ctx.response.body.as_ref(), b"upstream connect error");
+        assert_eq!(ctx.response_bytes(), 22);
+    }
+
+    async fn read_client_until(client: &mut TcpStream, received: &mut Vec<u8>, needle: &[u8]) {
+        use tokio::io::AsyncReadExt;
+
+        let mut chunk = [0u8; 1024];
+        while !received
+            .windows(needle.len())
+            .any(|window| window == needle)
+        {
+            let n = tokio::time::timeout(Duration::from_secs(2), client.read(&mut chunk))
+                .await
+                .expect("chunk should reach client without waiting for later chunks")
+                .expect("read response");
+            assert!(n > 0, "downstream closed early");
+            received.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// 测试用代理服务：内存数据库 + 默认配置，仅调整是否按事件刷新
+    async fn make_test_service(flush_per_event: bool) -> ProxyService {
+        use migration::{Migrator, MigratorTrait};
+
+        let db = sea_orm::Database::connect("sqlite::memory:")
+            .await
+            .expect("connect test db");
+        Migrator::up(&db, None).await.expect("run migrations");
+        let mut config = crate::config::AppConfig::default();
+        config.streaming.flush_per_event = flush_per_event;
+        let context = crate::app::context::AppContext::bootstrap(Arc::new(config), Arc::new(db))
+            .await
+            .expect("bootstrap app context");
+        ProxyService::new(crate::dual_port_setup::build_proxy_state(&context))
+            .expect("create proxy service")
+    }
+
+    #[tokio::test]
+    async fn test_sse_event_reaches_client_before_next_chunk() {
+        for flush_per_event in [true, false] {
+            let service = make_test_service(flush_per_event).await;
+            let (mut session, mut client) =
+                make_test_session_with_client("GET /v1/chat HTTP/1.1\r\nHost: example.com\r\n\r\n")
+                    .await;
+            let mut header = ResponseHeader::build(200, None).expect("build header");
+            header
+                .insert_header("content-type", "text/event-stream")
+                .expect("content-type");
+            let mut ctx = ProxyContext {
+                request_id: "test-sse-flush".to_string(),
+                start_time: Instant::now(),
+                ..Default::default()
+            };
+            ctx.response.is_sse = true;
+            service.init_sse_event_flush(&mut ctx);
+            assert_eq!(ctx.response.sse_event_flush.is_some(), flush_per_event);
+            session
+                .write_response_header(Box::new(header), false)
+                .await
+                .expect("write header");
+
+            let mut first = Some(Bytes::from_static(b"data: a\n\ndata: b"));
+            service
+                .response_body_filter(&mut session, &mut first, false, &mut ctx)
+                .expect("filter first chunk");
+            session
+                .write_response_body(first, false)
+                .await
+                .expect("write first chunk");
+
+            let mut received = Vec::new();
+            read_client_until(&mut client, &mut received, b"data: a\n\n").await;
+            if flush_per_event {
+                // 末尾未结束的事件被暂存，不会先于事件边界到达客户端
+                assert!(!received.windows(7).any(|window| window == b"data: b"));
+            } else {
+                // 未开启时数据块原样转发，半个事件随之到达
+                read_client_until(&mut client, &mut received, b"data: b").await;
+            }
+
+            let mut second = Some(Bytes::from_static(b"\n\n"));
+            service
+                .response_body_filter(&mut session, &mut second, false, &mut ctx)
+                .expect("filter second chunk");
+            session
+                .write_response_body(second, false)
+                .await
+                .expect("write second chunk");
+            read_client_until(&mut client, &mut received, b"data: b\n\n").await;
+        }
+    }
 }
@@ -0,0 +1,88 @@
+//! # SSE 按事件刷新
+//!
+//! 默认情况下上游数据块到达后立即原样交给下游，代理不会为了合并而等待后续数据块。
+//! 开启 `streaming.flush_per_event` 后，SSE 响应改为按事件边界发送：数据块中已完整的事件立即发出，
+//! 末尾未结束的事件留到补全后再发，保证客户端每次收到的都是完整事件，且任何事件都不会等待下一个事件。
+//! 被暂存的半个事件只在单事件超过缓存上限或流结束时提前发出。
+
+use crate::collect::stream_usage::MAX_PENDING_EVENT_BYTES;
+use bytes::{Bytes, BytesMut};
+
+/// 按事件边界切分 SSE 数据块
+#[derive(Debug, Default)]
+pub struct SseEventFlusher {
+    pending: BytesMut,
+}
+
+impl SseEventFlusher {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 推入一个分块（可能为空），返回本次应发送给客户端的完整事件；`None` 表示暂不发送
+    ///
+    /// 未结束的事件超过 [`MAX_PENDING_EVENT_BYTES`] 时不再等待，已缓存内容直接发出。
+    pub fn push(&mut self, chunk: Option<&Bytes>, end_of_stream: bool) -> Option<Bytes> {
+        if let Some(chunk) = chunk {
+            self.pending.extend_from_slice(chunk);
+        }
+
+        let ready = if end_of_stream || self.pending.len() > MAX_PENDING_EVENT_BYTES {
+            self.pending.split()
+        } else {
+            let boundary = last_event_boundary(&self.pending)?;
+            self.pending.split_to(boundary)
+        };
+        (!ready.is_empty()).then(|| ready.freeze())
+    }
+}
+
+/// 最后一个事件结束位置（空行之后），支持 `\n` 与 `\r\n` 换行
+fn last_event_boundary(buf: &[u8]) -> Option<usize> {
+    (1..buf.len()).rev().find_map(|i| {
+        let blank_line = buf[i] == b'\n'
+            && (buf[i - 1] == b'\n' || (i >= 2 && buf[i - 1] == b'\r' && buf[i - 2] == b'\n'));
+        blank_line.then_some(i + 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(flusher: &mut SseEventFlusher, chunk: &'static str) -> Option<String> {
+        flusher
+            .push(Some(&Bytes::from_static(chunk.as_bytes())), false)
+            .map(|bytes| String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn complete_events_are_flushed_and_partial_event_is_held() {
+        let mut flusher = SseEventFlusher::new();
+
+        assert_eq!(
+            push(&mut flusher, "data: a\n\ndata: b").as_deref(),
+            Some("data: a\n\n")
+        );
+        assert_eq!(push(&mut flusher, "\n"), None);
+        assert_eq!(
+            push(&mut flusher, "\ndata: c\r\n\r\n").as_deref(),
+            Some("data: b\n\ndata: c\r\n\r\n")
+        );
+        assert_eq!(
+            flusher.push(Some(&Bytes::from_static(b"data: [DONE]")), true),
+            Some(Bytes::from_static(b"data: [DONE]"))
+        );
+    }
+
+    #[test]
+    fn oversized_pending_event_is_not_held() {
+        let mut flusher = SseEventFlusher::new();
+        let huge = Bytes::from(vec![b'x'; MAX_PENDING_EVENT_BYTES + 1]);
+
+        let flushed = flusher.push(Some(&huge), false).expect("flushed");
+        assert_eq!(flushed.len(), MAX_PENDING_EVENT_BYTES + 1);
+        assert_eq!(flusher.push(None, true), None);
+    }
+}
@@ -0,0 +1,719 @@
+//! # SSE keepalive
+//!
+//! 上游返回 SSE 响应头后长时间没有输出（如模型思考阶段）时，按间隔向客户端发送 `: ping` 注释帧，
+//! 首个数据块到达后不再发送。
+//!
+//! Pingora 只在收到上游数据时回调响应过滤器，没有可用于定时写出的钩子，因此 keepalive 放在上游连接一侧：
+//! 开启后，经 [`is_streaming_request`] 判定为流式的请求由 [`SseKeepaliveConnector`] 建立上游连接，真实的 TCP/TLS 连接由中转任务持有，
+//! Pingora 经本地 Unix 套接字对以明文 HTTP/1.1 与中转任务通信。中转任务跟踪每个响应的分帧，
+//! 只在 SSE 响应头之后、首个数据块之前写入注释帧（分块编码时作为独立分块写入），因此不会截断上游事件。
+//!
+//! 注释帧以 `:` 开头，按 SSE 规范会被客户端忽略，`EventStreamData` 解析用量时同样跳过。
+//! 带 `Content-Length` 的响应长度固定，不插入注释帧。
+//!
+//! 非流式请求（以及 WebSocket、HEAD、强制非流式的请求）不经中转，仍由 Pingora 直连上游，
+//! 保留 HTTP/2、Pingora 的 TLS 处理、上游连接池与连接阶段计时。
+
+use crate::error::{self, config::ConfigError};
+use crate::ldebug;
+use crate::logging::{LogComponent, LogStage};
+use crate::proxy::{ProxyContext, model_routing, non_streaming};
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt, stream};
+use pingora_core::connectors::L4Connect;
+use pingora_core::protocols::l4::socket::SocketAddr;
+use pingora_core::protocols::l4::stream::Stream as L4Stream;
+use pingora_core::tls::ssl::{SslConnector, SslMethod};
+use pingora_core::tls::tokio_ssl::SslStream;
+use pingora_core::{Error as PingoraError, ErrorType};
+use pingora_http::RequestHeader;
+use pingora_proxy::Session;
+use serde_json::Value;
+use std::io;
+use std::pin::{Pin, pin};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+use tokio::time::{Instant, MissedTickBehavior, interval_at};
+use tokio_util::codec::{BytesCodec, FramedRead};
+
+/// keepalive 注释帧
+pub const SSE_PING_FRAME: &[u8] = b": ping\n\n";
+
+/// 分块编码下的注释帧（`: ping\n\n` 共 8 字节）
+const SSE_PING_CHUNK: &[u8] = b"8\r\n: ping\n\n\r\n";
+
+/// 响应头与分块长度行的长度上限，超过后停止分帧跟踪、原样透传
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+
+/// keepalive 包装后的数据项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeepaliveItem {
+    /// 等待期间按间隔产生，由调用方按所在分帧写出注释帧
+    Ping,
+    /// 上游数据
+    Data(Bytes),
+}
+
+/// 为上游数据流附加 keepalive
+///
+/// 首个非空数据块之前，每隔 `interval` 产生一次 [`KeepaliveItem::Ping`]；之后原样透传上游数据。
+pub fn with_keepalive<S, E>(
+    upstream: S,
+    interval: Duration,
+) -> impl Stream<Item = Result<KeepaliveItem, E>>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    let mut ticker = interval_at(Instant::now() + interval, interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    stream::unfold(
+        (upstream, Some(ticker)),
+        |(mut upstream, mut ticker)| async move {
+            let item = match ticker.as_mut() {
+                Some(pending) => {
+                    let next = tokio::select! {
+                        biased;
+                        item = upstream.next() => Some(item?),
+                        _ = pending.tick() => None,
+                    };
+                    match next {
+                        Some(item) => item,
+                        None => return Some((Ok(KeepaliveItem::Ping), (upstream, ticker))),
+                    }
+                }
+                None => upstream.next().await?,
+            };
+            // 上游数据开始流动后停止插入
+            if matches!(&item, Ok(chunk) if !chunk.is_empty()) {
+                ticker = None;
+            }
+            Some((item.map(KeepaliveItem::Data), (upstream, ticker)))
+        },
+    )
+}
+
+/// SSE keepalive 中转：开启 `streaming.sse_keepalive_interval_ms` 时为上游节点创建连接器
+#[derive(Debug, Clone)]
+pub struct SseKeepaliveRelay {
+    interval: Duration,
+    tls: SslConnector,
+}
+
+impl SseKeepaliveRelay {
+    /// 创建中转；TLS 上游按系统默认证书校验，只协商 HTTP/1.1
+    pub fn new(interval: Duration) -> error::Result<Self> {
+        let tls_error =
+            |err| ConfigError::Load(format!("初始化 SSE keepalive TLS 连接器失败: {err}"));
+        let mut builder = SslConnector::builder(SslMethod::tls()).map_err(tls_error)?;
+        builder
+            .set_alpn_protos(b"\x08http/1.1")
+            .map_err(tls_error)?;
+        Ok(Self {
+            interval,
+            tls: builder.build(),
+        })
+    }
+
+    /// 为上游节点创建连接器；`sni` 为 `None` 表示明文上游
+    ///
+    /// `idle_limit` 为 SSE 响应头之后等待首个数据块的上限，超过后断开，与直连时的读超时一致。
+    #[must_use]
+    pub fn connector(
+        &self,
+        sni: Option<String>,
+        connect_timeout: Duration,
+        idle_limit: Duration,
+    ) -> SseKeepaliveConnector {
+        SseKeepaliveConnector {
+            interval: self.interval,
+            tls: sni.map(|sni| (self.tls.clone(), sni)),
+            connect_timeout,
+            idle_limit,
+        }
+    }
+}
+
+/// 判断请求是否期望流式响应，只有流式请求经 keepalive 中转
+///
+/// WebSocket、HEAD 与强制非流式的请求直接返回 `false`；请求头与路径无法判定时预读请求体查看 `stream: true`，
+/// 预读的请求体放入 `ctx.request.routing_body`（已由模型路由预读时直接复用）。
+pub async fn is_streaming_request(
+    session: &mut Session,
+    ctx: &mut ProxyContext,
+) -> error::Result<bool> {
+    if ctx.request.is_websocket
+        || session.req_header().method == http::Method::HEAD
+        || non_streaming::is_forced(ctx)
+    {
+        return Ok(false);
+    }
+    if streaming_hint(session.req_header()) {
+        return Ok(true);
+    }
+
+    if ctx.request.routing_body.is_none() {
+        ctx.request.routing_body = model_routing::read_routing_body(session).await?;
+    }
+    let streaming = ctx
+        .request
+        .routing_body
+        .as_deref()
+        .and_then(|body| serde_json::from_slice::<Value>(body).ok())
+        .is_some_and(|body| {
+            non_streaming::requests_streaming(session.req_header().uri.path(), &body)
+        });
+    Ok(streaming)
+}
+
+/// 仅凭请求头与路径判断是否为流式：`Accept: text/event-stream`、`alt=sse` 或 Gemini 流式生成路径
+fn streaming_hint(header: &RequestHeader) -> bool {
+    let accepts_sse = header
+        .headers
+        .get(http::header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("text/event-stream"));
+    let sse_query = header
+        .uri
+        .query()
+        .is_some_and(|query| query.split('&').any(|pair| pair == "alt=sse"));
+    accepts_sse || sse_query || non_streaming::requests_streaming(header.uri.path(), &Value::Null)
+}
+
+/// 经中转任务建立上游连接的 L4 连接器
+#[derive(Debug)]
+pub struct SseKeepaliveConnector {
+    interval: Duration,
+    /// TLS 连接器与 SNI，明文上游为 `None`
+    tls: Option<(SslConnector, String)>,
+    connect_timeout: Duration,
+    idle_limit: Duration,
+}
+
+#[async_trait]
+impl L4Connect for SseKeepaliveConnector {
+    async fn connect(&self, addr: &SocketAddr) -> pingora_core::Result<L4Stream> {
+        let SocketAddr::Inet(addr) = addr else {
+            return Err(PingoraError::explain(
+                ErrorType::ConnectError,
+                "SSE keepalive 中转仅支持 TCP 上游",
+            ));
+        };
+        let upstream = tokio::time::timeout(self.connect_timeout, TcpStream::connect(*addr))
+            .await
+            .map_err(|_| {
+                PingoraError::explain(ErrorType::ConnectTimedout, format!("连接上游超时: {addr}"))
+            })?
+            .map_err(|err| {
+                PingoraError::because(
+                    ErrorType::ConnectError,
+                    format!("连接上游失败: {addr}"),
+                    err,
+                )
+            })?;
+        let _ = upstream.set_nodelay(true);
+        let (local, remote) = UnixStream::pair().map_err(|err| {
+            PingoraError::because(
+                ErrorType::SocketError,
+                "创建 SSE keepalive 中转套接字失败",
+                err,
+            )
+        })?;
+
+        match &self.tls {
+            Some((connector, sni)) => {
+                let upstream = tls_handshake(connector, sni, upstream).await?;
+                tokio::spawn(relay(remote, upstream, self.interval, self.idle_limit));
+            }
+            None => {
+                tokio::spawn(relay(remote, upstream, self.interval, self.idle_limit));
+            }
+        }
+        Ok(L4Stream::from(local))
+    }
+}
+
+/// 与上游完成 TLS 握手
+async fn tls_handshake(
+    connector: &SslConnector,
+    sni: &str,
+    upstream: TcpStream,
+) -> pingora_core::Result<SslStream<TcpStream>> {
+    let config_error =
+        |err| PingoraError::because(ErrorType::TLSHandshakeFailure, "配置上游 TLS 失败", err);
+    let ssl = connector
+        .configure()
+        .and_then(|config| config.into_ssl(sni))
+        .map_err(config_error)?;
+    let mut stream = SslStream::new(ssl, upstream).map_err(config_error)?;
+    Pin::new(&mut stream).connect().await.map_err(|err| {
+        PingoraError::because(
+            ErrorType::TLSHandshakeFailure,
+            format!("上游 TLS 握手失败: {sni}"),
+            err,
+        )
+    })?;
+    Ok(stream)
+}
+
+/// 中转一条上游连接：请求方向原样转发，响应方向按分帧插入注释帧；任一方向结束后关闭两端
+async fn relay<U>(pingora: UnixStream, upstream: U, interval: Duration, idle_limit: Duration)
+where
+    U: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let (mut pingora_read, pingora_write) = pingora.into_split();
+    let (upstream_read, mut upstream_write) = tokio::io::split(upstream);
+    let result = tokio::select! {
+        result = tokio::io::copy(&mut pingora_read, &mut upstream_write) => result.map(drop),
+        result = relay_responses(upstream_read, pingora_write, interval, idle_limit) => result,
+    };
+    if let Err(err) = result {
+        ldebug!(
+            "system",
+            LogStage::UpstreamRequest,
+            LogComponent::Upstream,
+            "sse_keepalive_relay_closed",
+            "SSE keepalive 中转连接异常结束",
+            error = %err
+        );
+    }
+}
+
+/// 转发上游响应；SSE 响应头之后、首个数据块之前按间隔写入注释帧
+async fn relay_responses<R, W>(
+    upstream: R,
+    mut pingora: W,
+    interval: Duration,
+    idle_limit: Duration,
+) -> io::Result<()>
+where
+    R: AsyncRead + Send + Unpin,
+    W: AsyncWrite + Send + Unpin,
+{
+    let mut reads =
+        FramedRead::new(upstream, BytesCodec::new()).map(|chunk| chunk.map(BytesMut::freeze));
+    let mut framing = ResponseFraming::default();
+    while let Some(chunk) = reads.next().await {
+        let mut chunk = chunk?;
+        while !chunk.is_empty() {
+            let (consumed, ping) = framing.advance(&chunk);
+            pingora.write_all(&chunk.split_to(consumed)).await?;
+            // 同一次读取中已包含响应体数据时无需等待
+            if let Some(ping) = ping
+                && chunk.is_empty()
+            {
+                match wait_for_data(&mut reads, &mut pingora, ping, interval, idle_limit).await? {
+                    Some(data) => chunk = data,
+                    None => return Ok(()),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 等待 SSE 响应的首个数据块，期间按间隔写入注释帧；上游关闭时返回 `None`
+async fn wait_for_data<S, W>(
+    reads: &mut S,
+    pingora: &mut W,
+    ping: &'static [u8],
+    interval: Duration,
+    idle_limit: Duration,
+) -> io::Result<Option<Bytes>>
+where
+    S: Stream<Item = io::Result<Bytes>> + Send + Unpin,
+    W: AsyncWrite + Send + Unpin,
+{
+    let mut upstream = pin!(with_keepalive(reads, interval));
+    let waiting = async {
+        while let Some(item) = upstream.next().await {
+            match item? {
+                KeepaliveItem::Ping => pingora.write_all(ping).await?,
+                KeepaliveItem::Data(data) => return Ok(Some(data)),
+            }
+        }
+        Ok::<_, io::Error>(None)
+    };
+    tokio::time::timeout(idle_limit, waiting)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "等待上游 SSE 数据超时"))?
+}
+
+/// 上游响应分帧状态，用于定位响应头之后、响应体之前可插入注释帧的位置
+#[derive(Debug)]
+enum ResponseFraming {
+    /// 等待响应头（连接复用时为下一个响应）
+    Head(Vec<u8>),
+    /// 分块编码的响应体
+    Chunked(ChunkState),
+    /// 按 `Content-Length` 剩余的字节数
+    Length(u64),
+    /// 读到连接关闭为止：协议升级、未声明长度或无法识别的响应
+    UntilClose,
+}
+
+impl Default for ResponseFraming {
+    fn default() -> Self {
+        Self::Head(Vec::new())
+    }
+}
+
+impl ResponseFraming {
+    /// 消耗一段上游数据，返回应原样转发的字节数；刚读完可插入注释帧的 SSE 响应头时停下并返回注释帧
+    fn advance(&mut self, buf: &[u8]) -> (usize, Option<&'static [u8]>) {
+        let mut pos = 0;
+        while pos < buf.len() {
+            match self {
+                Self::Head(head) => {
+                    let before = head.len();
+                    head.extend_from_slice(&buf[pos..]);
+                    // 从上次末尾回退 3 字节查找，跨读取边界的空行也能识别
+                    let Some(end) = find_head_end(head, before.saturating_sub(3)) else {
+                        if head.len() > MAX_HEAD_BYTES {
+                            *self = Self::UntilClose;
+                        }
+                        return (buf.len(), None);
+                    };
+                    pos += end - before;
+                    let (next, ping) = framing_after_head(&head[..end]);
+                    *self = next;
+                    if ping.is_some() {
+                        return (pos, ping);
+                    }
+                }
+                Self::Chunked(state) => match state.advance(&buf[pos..]) {
+                    Some((consumed, finished)) => {
+                        pos += consumed;
+                        if finished {
+                            *self = Self::default();
+                        }
+                    }
+                    None => *self = Self::UntilClose,
+                },
+                Self::Length(remaining) => {
+                    pos += take_up_to(remaining, buf.len() - pos);
+                    if *remaining == 0 {
+                        *self = Self::default();
+                    }
+                }
+                Self::UntilClose => return (buf.len(), None),
+            }
+        }
+        (pos, None)
+    }
+}
+
+/// 分块编码解析状态
+#[derive(Debug)]
+enum ChunkState {
+    /// 分块长度行
+    Size(Vec<u8>),
+    /// 分块数据剩余字节数
+    Data(u64),
+    /// 分块数据之后的 CRLF 剩余字节数
+    DataEnd(u64),
+    /// 末尾分块之后的 trailer 行
+    Trailer(Vec<u8>),
+}
+
+impl ChunkState {
+    /// 消耗一段数据，返回消耗的字节数与响应体是否结束；格式无法识别时返回 `None`
+    fn advance(&mut self, buf: &[u8]) -> Option<(usize, bool)> {
+        let mut pos = 0;
+        while pos < buf.len() {
+            match self {
+                Self::Size(line) => {
+                    let Some(consumed) = read_line(line, &buf[pos..]) else {
+                        return (line.len() <= MAX_HEAD_BYTES).then_some((buf.len(), false));
+                    };
+                    pos += consumed;
+                    let size = parse_chunk_size(line)?;
+                    *self = if size == 0 {
+                        Self::Trailer(Vec::new())
+                    } else {
+                        Self::Data(size)
+                    };
+                }
+                Self::Data(remaining) => {
+                    pos += take_up_to(remaining, buf.len() - pos);
+                    if *remaining == 0 {
+                        *self = Self::DataEnd(2);
+                    }
+                }
+                Self::DataEnd(remaining) => {
+                    pos += take_up_to(remaining, buf.len() - pos);
+                    if *remaining == 0 {
+                        *self = Self::Size(Vec::new());
+                    }
+                }
+                Self::Trailer(line) => {
+                    let Some(consumed) = read_line(line, &buf[pos..]) else {
+                        return (line.len() <= MAX_HEAD_BYTES).then_some((buf.len(), false));
+                    };
+                    pos += consumed;
+                    if line.is_empty() {
+                        return Some((pos, true));
+                    }
+                    line.clear();
+                }
+            }
+        }
+        Some((pos, false))
+    }
+}
+
+/// 解析响应头，决定响应体的分帧方式；SSE 响应体之前可插入注释帧时一并返回对应格式的注释帧
+fn framing_after_head(head: &[u8]) -> (ResponseFraming, Option<&'static [u8]>) {
+    let text = String::from_utf8_lossy(head);
+    let mut lines = text.split("\r\n");
+    let Some(status) = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+    else {
+        return (ResponseFraming::UntilClose, None);
+    };
+
+    let (mut chunked, mut length, mut sse) = (false, None, false);
+    for (name, value) in lines.filter_map(|line| line.split_once(':')) {
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value
+                .rsplit(',')
+                .next()
+                .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"));
+        } else if name.eq_ignore_ascii_case("content-length") {
+            length = value.parse::<u64>().ok();
+        } else if name.eq_ignore_ascii_case("content-type") {
+            sse = value.to_ascii_lowercase().starts_with("text/event-stream");
+        }
+    }
+
+    match status {
+        101 => (ResponseFraming::UntilClose, None),
+        100..=199 | 204 | 304 => (ResponseFraming::default(), None),
+        _ if chunked => (
+            ResponseFraming::Chunked(ChunkState::Size(Vec::new())),
+            sse.then_some(SSE_PING_CHUNK),
+        ),
+        _ => match length {
+            Some(0) => (ResponseFraming::default(), None),
+            Some(length) => (ResponseFraming::Length(length), None),
+            None => (ResponseFraming::UntilClose, sse.then_some(SSE_PING_FRAME)),
+        },
+    }
+}
+
+/// 响应头结束位置（空行之后），从 `from` 开始查找
+fn find_head_end(buf: &[u8], from: usize) -> Option<usize> {
+    buf.get(from..)?
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|index| from + index + 4)
+}
+
+/// 读取一行追加到 `line`（去掉行尾 CRLF），返回消耗的字节数；本段数据没有行尾时全部追加并返回 `None`
+fn read_line(line: &mut Vec<u8>, buf: &[u8]) -> Option<usize> {
+    let Some(lf) = buf.iter().position(|&byte| byte == b'\n') else {
+        line.extend_from_slice(buf);
+        return None;
+    };
+    line.extend_from_slice(&buf[..lf]);
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Some(lf + 1)
+}
+
+/// 解析分块长度（十六进制，忽略分块扩展）
+fn parse_chunk_size(line: &[u8]) -> Option<u64> {
+    let line = std::str::from_utf8(line).ok()?;
+    let size = line.split(';').next().unwrap_or_default().trim();
+    u64::from_str_radix(size, 16).ok()
+}
+
+/// 从剩余长度中扣除本次可消耗的字节数
+fn take_up_to(remaining: &mut u64, available: usize) -> usize {
+    let taken = usize::try_from(*remaining).map_or(available, |remaining| remaining.min(available));
+    *remaining -= u64::try_from(taken).unwrap_or(*remaining);
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::event_stream::EventStreamData;
+    use std::convert::Infallible;
+    use tokio::io::AsyncReadExt;
+    use tokio_util::codec::Decoder;
+
+    #[test]
+    fn streaming_hint_from_accept_query_and_gemini_path() {
+        let mut header = RequestHeader::build("POST", b"/v1/chat/completions", None).unwrap();
+        assert!(!streaming_hint(&header));
+        header.insert_header("accept", "text/event-stream").unwrap();
+        assert!(streaming_hint(&header));
+
+        let header = RequestHeader::build(
+            "POST",
+            b"/v1beta/models/gemini-pro:streamGenerateContent",
+            None,
+        )
+        .unwrap();
+        assert!(streaming_hint(&header));
+
+        let header = RequestHeader::build(
+            "POST",
+            b"/v1beta/models/gemini-pro:generateContent?alt=sse",
+            None,
+        )
+        .unwrap();
+        assert!(streaming_hint(&header));
+    }
+
+    /// 注释帧按 SSE 原始格式展开
+    fn frame(item: Result<KeepaliveItem, Infallible>) -> Bytes {
+        match item.unwrap() {
+            KeepaliveItem::Ping => Bytes::from_static(SSE_PING_FRAME),
+            KeepaliveItem::Data(data) => data,
+        }
+    }
+
+    /// 上游先静默 `delay`，随后每 `gap` 输出一个数据块
+    fn delayed_upstream(
+        delay: Duration,
+        gap: Duration,
+        chunks: Vec<&'static str>,
+    ) -> impl Stream<Item = Result<Bytes, Infallible>> + Unpin {
+        Box::pin(stream::iter(chunks.into_iter().enumerate()).then(
+            move |(index, chunk)| async move {
+                tokio::time::sleep(if index == 0 { delay } else { gap }).await;
+                Ok(Bytes::from_static(chunk.as_bytes()))
+            },
+        ))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn keepalive_injected_before_first_chunk_only() {
+        let upstream = delayed_upstream(
+            Duration::from_secs(35),
+            Duration::from_secs(40),
+            vec![
+                "data: {\"delta\":\"hi\"}\n\n",
+                "data: {\"usage\":{\"output_tokens\":3}}\n\n",
+            ],
+        );
+
+        let output: Vec<Bytes> = with_keepalive(upstream, Duration::from_secs(10))
+            .map(frame)
+            .collect()
+            .await;
+
+        // 35 秒静默期间在 10/20/30 秒各插入一次；数据开始后即使间隔 40 秒也不再插入
+        let pings = output
+            .iter()
+            .take_while(|chunk| chunk.as_ref() == SSE_PING_FRAME)
+            .count();
+        assert_eq!(pings, 3);
+        assert_eq!(output.len(), 5);
+        assert!(
+            output[pings..]
+                .iter()
+                .all(|chunk| chunk.as_ref() != SSE_PING_FRAME)
+        );
+
+        // 注释帧不影响事件解析
+        let mut buf = BytesMut::new();
+        for chunk in &output {
+            buf.extend_from_slice(chunk);
+        }
+        let mut codec = EventStreamData::new();
+        let mut events = Vec::new();
+        while let Some(event) = codec.decode(&mut buf).unwrap() {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].data["usage"]["output_tokens"], 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_keepalive_when_upstream_is_prompt() {
+        let upstream = delayed_upstream(
+            Duration::from_millis(50),
+            Duration::from_millis(50),
+            vec!["data: {\"a\":1}\n\n"],
+        );
+
+        let output: Vec<Bytes> = with_keepalive(upstream, Duration::from_secs(10))
+            .map(frame)
+            .collect()
+            .await;
+
+        assert_eq!(output, vec![Bytes::from_static(b"data: {\"a\":1}\n\n")]);
+    }
+
+    #[test]
+    fn ping_follows_chunked_sse_head_split_across_reads() {
+        let mut framing = ResponseFraming::default();
+        let head = b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r";
+        assert_eq!(framing.advance(head), (head.len(), None));
+        assert_eq!(framing.advance(b"\n"), (1, Some(SSE_PING_CHUNK)));
+
+        // 首个分块之后不再返回注释帧，末尾分块结束后回到等待响应头
+        let body = b"e\r\ndata: {\"a\":1}\r\n0\r\n\r\n";
+        assert_eq!(framing.advance(body), (body.len(), None));
+        assert!(matches!(framing, ResponseFraming::Head(ref head) if head.is_empty()));
+    }
+
+    #[test]
+    fn no_ping_for_sized_or_non_sse_responses() {
+        let mut framing = ResponseFraming::default();
+        let sized =
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: 5\r\n\r\nhello";
+        assert_eq!(framing.advance(sized), (sized.len(), None));
+
+        // 连接复用时第二个响应照常识别
+        let json = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n";
+        assert_eq!(framing.advance(json), (json.len(), None));
+        assert!(matches!(framing, ResponseFraming::Chunked(_)));
+
+        let mut upgrade = ResponseFraming::default();
+        let head = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n";
+        assert_eq!(upgrade.advance(head), (head.len(), None));
+        assert!(matches!(upgrade, ResponseFraming::UntilClose));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn relay_pings_between_head_and_first_chunk() {
+        let (mut upstream, upstream_side) = tokio::io::duplex(1024);
+        let (pingora_side, mut pingora) = tokio::io::duplex(1024);
+        let relay = tokio::spawn(relay_responses(
+            upstream_side,
+            pingora_side,
+            Duration::from_secs(1),
+            Duration::from_secs(60),
+        ));
+
+        let head = b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n";
+        upstream.write_all(head).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        upstream.write_all(b"6\r\ndata: \r\n").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        upstream.write_all(b"0\r\n\r\n").await.unwrap();
+        drop(upstream);
+        relay.await.unwrap().unwrap();
+
+        let mut output = Vec::new();
+        pingora.read_to_end(&mut output).await.unwrap();
+        let mut expected = head.to_vec();
+        expected.extend_from_slice(SSE_PING_CHUNK);
+        expected.extend_from_slice(SSE_PING_CHUNK);
+        expected.extend_from_slice(b"6\r\ndata: \r\n0\r\n\r\n");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            String::from_utf8(expected).unwrap()
+        );
+    }
+}
@@ -2,9 +2,15 @@ use crate::app::context::AppContext;
 use crate::auth::api_key_usage_limit_service::ApiKeyUsageLimitService;
 use crate::collect::service::CollectService;
 use crate::key_pool::ApiKeySchedulerService;
+use crate::pricing::CreditsService;
+use crate::proxy::admission::AdmissionController;
 use crate::proxy::authentication_service::AuthenticationService;
+use crate::proxy::health_probe::HealthProbeService;
+use crate::proxy::maintenance::MaintenanceService;
+use crate::proxy::model_availability::ModelAvailabilityService;
 use crate::proxy::request_transform_service::RequestTransformService;
 use crate::proxy::response_transform_service::ResponseTransformService;
+use crate::proxy::retry_budget::RetryBudget;
 use crate::proxy::upstream_service::UpstreamService;
 use crate::trace::TraceManager;
 use std::ops::Deref;
@@ -21,6 +27,12 @@ pub struct ProxyServices {
     pub resp_transform_service: Arc<ResponseTransformService>,
     pub key_scheduler_service: Arc<ApiKeySchedulerService>,
     pub rate_limiter: Arc<ApiKeyUsageLimitService>,
+    pub model_availability: Arc<ModelAvailabilityService>,
+    pub maintenance: Arc<MaintenanceService>,
+    pub health_probe: Arc<HealthProbeService>,
+    pub admission: Arc<AdmissionController>,
+    pub credits: Arc<CreditsService>,
+    pub retry_budget: Arc<RetryBudget>,
 }
 
 /// 代理服务的共享状态
@@ -0,0 +1,458 @@
+//! # 强制系统提示词注入
+//!
+//! 服务 API 可配置必须附带的系统提示词（JSON 对象 `{"content": "...", "mode": "prepend"}`），
+//! 由各提供商策略在 `modify_request_body_json` 中写入请求体，客户端的消息保持不变：
+//! - `OpenAI`：Chat Completions 写入 `messages` 中的 `system` 消息，Responses API 写入 `instructions`
+//! - Anthropic：写入顶层 `system`（字符串或内容块数组）
+//! - Gemini：写入 `systemInstruction.parts`（Code Assist 请求体位于 `request` 内）
+//!
+//! `prepend`（缺省）在客户端的系统提示词之前插入独立的一段；`merge` 将提示词拼接到客户端第一段系统提示词的开头。
+//! 客户端未携带系统提示词时两种模式效果相同。
+
+use crate::error::Result;
+use crate::error::conversion::ConversionError;
+use crate::logging::{LogComponent, LogStage};
+use crate::proxy::ProxyContext;
+use crate::proxy::provider_strategy::ProviderType;
+use crate::{linfo, lwarn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// 系统提示词长度上限（字符数）
+const MAX_SYSTEM_PROMPT_CHARS: usize = 32_768;
+
+/// 注入方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SystemPromptMode {
+    /// 作为独立的一段插入到客户端系统提示词之前
+    #[default]
+    Prepend,
+    /// 拼接到客户端第一段系统提示词的开头
+    Merge,
+}
+
+/// 服务 API 的系统提示词配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemPromptConfig {
+    pub content: String,
+    #[serde(default)]
+    pub mode: SystemPromptMode,
+}
+
+/// 严格解析服务 API 的系统提示词配置
+pub fn parse_system_prompt(value: &Value) -> Result<SystemPromptConfig> {
+    let config: SystemPromptConfig = serde_json::from_value(value.clone()).map_err(|err| {
+        ConversionError::message(format!(
+            "system_prompt 必须是 {{\"content\": string, \"mode\": \"prepend\"|\"merge\"}}: {err}"
+        ))
+    })?;
+    if config.content.trim().is_empty() {
+        return Err(ConversionError::message("system_prompt.content 不能为空").into());
+    }
+    if config.content.chars().count() > MAX_SYSTEM_PROMPT_CHARS {
+        return Err(ConversionError::message(format!(
+            "system_prompt.content 最多 {MAX_SYSTEM_PROMPT_CHARS} 个字符"
+        ))
+        .into());
+    }
+    Ok(config)
+}
+
+/// 当前服务 API 是否配置了系统提示词
+#[must_use]
+pub fn is_configured(ctx: &ProxyContext) -> bool {
+    ctx.routing
+        .user_service_api
+        .as_ref()
+        .is_some_and(|api| api.system_prompt.is_some())
+}
+
+/// 按服务 API 配置向请求体注入系统提示词，返回 `true` 表示请求体已被修改
+///
+/// 已保存的配置无法解析时跳过注入并记录告警。
+pub fn apply(ctx: &ProxyContext, provider: ProviderType, body: &mut Value) -> bool {
+    let Some(value) = ctx
+        .routing
+        .user_service_api
+        .as_ref()
+        .and_then(|api| api.system_prompt.as_ref())
+    else {
+        return false;
+    };
+    let config = match parse_system_prompt(value) {
+        Ok(config) => config,
+        Err(err) => {
+            lwarn!(
+                &ctx.request_id,
+                LogStage::RequestModify,
+                LogComponent::RequestTransform,
+                "invalid_system_prompt",
+                "服务 API 的系统提示词配置无效，跳过注入",
+                error = %err
+            );
+            return false;
+        }
+    };
+
+    let injected = inject_system_prompt(provider, body, &config);
+    if injected {
+        linfo!(
+            &ctx.request_id,
+            LogStage::RequestModify,
+            LogComponent::RequestTransform,
+            "system_prompt_injected",
+            "已注入服务 API 的系统提示词",
+            provider = ?provider,
+            mode = ?config.mode
+        );
+    }
+    injected
+}
+
+/// 按提供商的请求体结构注入系统提示词；请求体不是对话请求时不做修改
+pub fn inject_system_prompt(
+    provider: ProviderType,
+    body: &mut Value,
+    config: &SystemPromptConfig,
+) -> bool {
+    match provider {
+        ProviderType::OpenAI => inject_openai(body, config),
+        ProviderType::Anthropic => inject_anthropic(body, config),
+        ProviderType::Gemini => inject_gemini(body, config),
+    }
+}
+
+/// `OpenAI`：Chat Completions 使用 `system`/`developer` 消息，Responses API 使用 `instructions`
+fn inject_openai(body: &mut Value, config: &SystemPromptConfig) -> bool {
+    let Some(object) = body.as_object_mut() else {
+        return false;
+    };
+
+    if let Some(messages) = object.get_mut("messages").and_then(Value::as_array_mut) {
+        let system_message = messages.iter_mut().find(|message| {
+            matches!(
+                message.get("role").and_then(Value::as_str),
+                Some("system" | "developer")
+            )
+        });
+        if config.mode == SystemPromptMode::Merge
+            && let Some(message) = system_message
+            && let Some(content) = message.get_mut("content")
+            && merge_into_content(content, &config.content)
+        {
+            return true;
+        }
+        messages.insert(0, json!({"role": "system", "content": config.content}));
+        return true;
+    }
+
+    if object.contains_key("input") {
+        // Responses API 只有一段 instructions，两种模式都拼接在客户端内容之前
+        let instructions = match object.get("instructions").and_then(Value::as_str) {
+            Some(existing) if !existing.is_empty() => merged_text(&config.content, existing),
+            _ => config.content.clone(),
+        };
+        object.insert("instructions".to_string(), Value::String(instructions));
+        return true;
+    }
+
+    false
+}
+
+/// Anthropic：顶层 `system` 可以是字符串或文本内容块数组
+fn inject_anthropic(body: &mut Value, config: &SystemPromptConfig) -> bool {
+    let Some(object) = body.as_object_mut() else {
+        return false;
+    };
+    if !object.contains_key("messages") {
+        return false;
+    }
+
+    let prepend = config.mode == SystemPromptMode::Prepend;
+    let block = json!({"type": "text", "text": config.content});
+    let injected = match object.get_mut("system") {
+        Some(system) if system.is_string() => {
+            if prepend {
+                let existing = system.take();
+                *system = json!([block, {"type": "text", "text": existing}]);
+                true
+            } else {
+                merge_into_content(system, &config.content)
+            }
+        }
+        Some(Value::Array(blocks)) => {
+            if prepend || !merge_into_content_blocks(blocks, &config.content) {
+                blocks.insert(0, block);
+            }
+            true
+        }
+        _ => false,
+    };
+    if !injected {
+        object.insert("system".to_string(), Value::String(config.content.clone()));
+    }
+    true
+}
+
+/// Gemini：`systemInstruction.parts`，兼容 `system_instruction` 写法与 Code Assist 的 `request` 包装
+fn inject_gemini(body: &mut Value, config: &SystemPromptConfig) -> bool {
+    let Some(target) = gemini_request_object(body) else {
+        return false;
+    };
+
+    let key = if target.contains_key("system_instruction") {
+        "system_instruction"
+    } else {
+        "systemInstruction"
+    };
+    let part = json!({"text": config.content});
+    let parts = target
+        .get_mut(key)
+        .and_then(|instruction| instruction.get_mut("parts"))
+        .and_then(Value::as_array_mut);
+
+    match parts {
+        Some(parts) => {
+            let merged = config.mode == SystemPromptMode::Merge
+                && parts
+                    .first_mut()
+                    .and_then(|first| first.get_mut("text"))
+                    .is_some_and(|text| merge_into_content(text, &config.content));
+            if !merged {
+                parts.insert(0, part);
+            }
+        }
+        None => {
+            target.insert(key.to_string(), json!({"parts": [part]}));
+        }
+    }
+    true
+}
+
+/// Gemini 请求体中包含 `contents` 的对象：标准请求体本身，或 Code Assist 请求体的 `request`
+fn gemini_request_object(body: &mut Value) -> Option<&mut Map<String, Value>> {
+    let wrapped = body
+        .get("request")
+        .is_some_and(|request| request.get("contents").is_some());
+    let target = if wrapped {
+        body.get_mut("request")?
+    } else {
+        body
+    };
+    target
+        .as_object_mut()
+        .filter(|object| object.contains_key("contents"))
+}
+
+/// 将提示词拼接到已有内容开头：字符串直接拼接，内容块数组拼接到第一个文本块
+///
+/// 内容不是字符串且没有文本块时返回 `false`，由调用方改为插入独立的一段。
+fn merge_into_content(content: &mut Value, prompt: &str) -> bool {
+    match content {
+        Value::String(existing) => {
+            *existing = merged_text(prompt, existing);
+            true
+        }
+        Value::Array(blocks) => merge_into_content_blocks(blocks, prompt),
+        _ => false,
+    }
+}
+
+fn merge_into_content_blocks(blocks: &mut [Value], prompt: &str) -> bool {
+    blocks
+        .iter_mut()
+        .find_map(|block| block.get_mut("text").filter(|text| text.is_string()))
+        .is_some_and(|text| merge_into_content(text, prompt))
+}
+
+fn merged_text(prompt: &str, existing: &str) -> String {
+    format!("{prompt}\n\n{existing}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = "Follow the company safety policy.";
+
+    fn config(mode: SystemPromptMode) -> SystemPromptConfig {
+        SystemPromptConfig {
+            content: POLICY.to_string(),
+            mode,
+        }
+    }
+
+    #[test]
+    fn openai_chat_prepends_system_message_and_keeps_client_messages() {
+        let mut body = json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "system", "content": "You are a helpful assistant."},
+                {"role": "user", "content": "hi"}
+            ]
+        });
+        assert!(inject_system_prompt(
+            ProviderType::OpenAI,
+            &mut body,
+            &config(SystemPromptMode::Prepend)
+        ));
+        assert_eq!(
+            body["messages"],
+            json!([
+                {"role": "system", "content": POLICY},
+                {"role": "system", "content": "You are a helpful assistant."},
+                {"role": "user", "content": "hi"}
+            ])
+        );
+
+        let mut body = json!({
+            "messages": [
+                {"role": "developer", "content": [{"type": "text", "text": "Be brief."}]},
+                {"role": "user", "content": "hi"}
+            ]
+        });
+        assert!(inject_system_prompt(
+            ProviderType::OpenAI,
+            &mut body,
+            &config(SystemPromptMode::Merge)
+        ));
+        assert_eq!(
+            body["messages"][0]["content"][0]["text"],
+            format!("{POLICY}\n\nBe brief.")
+        );
+        assert_eq!(body["messages"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn openai_responses_merges_into_instructions() {
+        let mut body = json!({"model": "gpt-4o", "input": "hi", "instructions": "Be brief."});
+        assert!(inject_system_prompt(
+            ProviderType::OpenAI,
+            &mut body,
+            &config(SystemPromptMode::Prepend)
+        ));
+        assert_eq!(body["instructions"], format!("{POLICY}\n\nBe brief."));
+        assert_eq!(body["input"], "hi");
+
+        let mut embeddings = json!({"model": "text-embedding-3-small", "prompt": "hi"});
+        assert!(!inject_system_prompt(
+            ProviderType::OpenAI,
+            &mut embeddings,
+            &config(SystemPromptMode::Prepend)
+        ));
+    }
+
+    #[test]
+    fn anthropic_handles_string_and_block_system() {
+        let messages = json!([{"role": "user", "content": "hi"}]);
+
+        let mut body = json!({"model": "claude-sonnet-4", "messages": messages});
+        assert!(inject_system_prompt(
+            ProviderType::Anthropic,
+            &mut body,
+            &config(SystemPromptMode::Prepend)
+        ));
+        assert_eq!(body["system"], POLICY);
+        assert_eq!(body["messages"], messages);
+
+        let mut body = json!({"messages": messages, "system": "Be brief."});
+        assert!(inject_system_prompt(
+            ProviderType::Anthropic,
+            &mut body,
+            &config(SystemPromptMode::Prepend)
+        ));
+        assert_eq!(
+            body["system"],
+            json!([
+                {"type": "text", "text": POLICY},
+                {"type": "text", "text": "Be brief."}
+            ])
+        );
+
+        let mut body = json!({
+            "messages": messages,
+            "system": [{"type": "text", "text": "Be brief.", "cache_control": {"type": "ephemeral"}}]
+        });
+        assert!(inject_system_prompt(
+            ProviderType::Anthropic,
+            &mut body,
+            &config(SystemPromptMode::Merge)
+        ));
+        assert_eq!(
+            body["system"],
+            json!([{
+                "type": "text",
+                "text": format!("{POLICY}\n\nBe brief."),
+                "cache_control": {"type": "ephemeral"}
+            }])
+        );
+    }
+
+    #[test]
+    fn gemini_handles_system_instruction_and_code_assist_wrapper() {
+        let contents = json!([{"role": "user", "parts": [{"text": "hi"}]}]);
+
+        let mut body = json!({"contents": contents});
+        assert!(inject_system_prompt(
+            ProviderType::Gemini,
+            &mut body,
+            &config(SystemPromptMode::Prepend)
+        ));
+        assert_eq!(
+            body["systemInstruction"],
+            json!({"parts": [{"text": POLICY}]})
+        );
+        assert_eq!(body["contents"], contents);
+
+        let mut body = json!({
+            "contents": contents,
+            "system_instruction": {"parts": [{"text": "Be brief."}]}
+        });
+        assert!(inject_system_prompt(
+            ProviderType::Gemini,
+            &mut body,
+            &config(SystemPromptMode::Prepend)
+        ));
+        assert_eq!(
+            body["system_instruction"]["parts"],
+            json!([{"text": POLICY}, {"text": "Be brief."}])
+        );
+
+        let mut body = json!({
+            "model": "gemini-2.5-pro",
+            "project": "demo",
+            "request": {
+                "contents": contents,
+                "systemInstruction": {"parts": [{"text": "Be brief."}]}
+            }
+        });
+        assert!(inject_system_prompt(
+            ProviderType::Gemini,
+            &mut body,
+            &config(SystemPromptMode::Merge)
+        ));
+        assert_eq!(
+            body["request"]["systemInstruction"]["parts"],
+            json!([{"text": format!("{POLICY}\n\nBe brief.")}])
+        );
+        assert!(body.get("systemInstruction").is_none());
+    }
+
+    #[test]
+    fn parse_validates_config() {
+        assert_eq!(
+            parse_system_prompt(&json!({"content": POLICY})).unwrap(),
+            config(SystemPromptMode::Prepend)
+        );
+        assert_eq!(
+            parse_system_prompt(&json!({"content": POLICY, "mode": "merge"}))
+                .unwrap()
+                .mode,
+            SystemPromptMode::Merge
+        );
+        assert!(parse_system_prompt(&json!({"content": "  "})).is_err());
+        assert!(parse_system_prompt(&json!({"content": POLICY, "mode": "replace"})).is_err());
+        assert!(parse_system_prompt(&json!(POLICY)).is_err());
+    }
+}
@@ -0,0 +1,537 @@
+//! # 工具定义校验
+//!
+//! 工具（函数调用）定义写错时，上游通常只返回含义模糊的 400。各服务商策略在转发前按自身格式校验
+//! 工具定义：`OpenAI` 的 `tools`（及旧版 `functions`）、Anthropic 的 `tools`、Gemini 的
+//! `functionDeclarations`，检查必填字段、名称规则与参数的 JSON Schema，命中时代理直接返回 400，
+//! 并指出出错的字段路径。
+//!
+//! 服务商内置工具（如 `web_search`、`code_interpreter`）结构各异，不做校验。与格式检测相同，
+//! 只有请求体已完整缓存（计划改写）时才能在转发前校验。
+
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// 参数 Schema 允许的最大嵌套层数，超过后不再深入校验
+const MAX_SCHEMA_DEPTH: usize = 32;
+
+/// JSON Schema 的基本类型
+const JSON_SCHEMA_TYPES: &[&str] = &[
+    "object", "array", "string", "number", "integer", "boolean", "null",
+];
+
+/// 工具定义校验失败
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolValidationError {
+    /// 出错的字段（按请求体中的路径表示）
+    pub field: String,
+    /// 具体原因
+    pub reason: String,
+}
+
+impl ToolValidationError {
+    fn new(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// 返回给客户端的说明
+    #[must_use]
+    pub fn message(&self) -> String {
+        format!("工具定义无效：{} {}", self.field, self.reason)
+    }
+}
+
+type ToolResult = std::result::Result<(), ToolValidationError>;
+
+/// 参数 Schema 的方言差异
+#[derive(Debug, Clone, Copy)]
+struct SchemaRules {
+    /// `type` 是否按 Gemini 的 OpenAPI 子集解析（大小写不敏感、不支持类型数组）
+    gemini: bool,
+    /// `array` 类型是否必须声明 `items`（`OpenAI` 会拒绝缺少 `items` 的数组）
+    array_requires_items: bool,
+}
+
+/// 校验 `OpenAI` 请求体中的工具定义
+///
+/// Chat Completions 使用 `{"type": "function", "function": {"name", "parameters"}}`，
+/// Responses 接口（路径含 `/responses`）把 `name`、`parameters` 放在工具顶层；旧版 `functions` 同样校验。
+pub fn validate_openai_tools(path: &str, body: &Value) -> ToolResult {
+    let rules = SchemaRules {
+        gemini: false,
+        array_requires_items: true,
+    };
+    let flat = path.contains("/responses");
+    let mut names = HashSet::new();
+
+    for (index, tool) in array_field(body, "tools")?.iter().enumerate() {
+        let field = format!("tools[{index}]");
+        let tool = as_object(tool, &field)?;
+        let kind = tool
+            .get("type")
+            .ok_or_else(|| ToolValidationError::new(format!("{field}.type"), "缺失"))?
+            .as_str()
+            .ok_or_else(|| ToolValidationError::new(format!("{field}.type"), "必须是字符串"))?;
+        if kind != "function" {
+            continue;
+        }
+
+        let (function, function_field) = if flat {
+            (tool, field)
+        } else {
+            let function_field = format!("{field}.function");
+            let function = tool
+                .get("function")
+                .ok_or_else(|| ToolValidationError::new(&function_field, "缺失"))?;
+            (as_object(function, &function_field)?, function_field)
+        };
+        validate_function(
+            function,
+            &function_field,
+            "parameters",
+            false,
+            rules,
+            &mut names,
+        )?;
+    }
+
+    for (index, function) in array_field(body, "functions")?.iter().enumerate() {
+        let field = format!("functions[{index}]");
+        let function = as_object(function, &field)?;
+        validate_function(function, &field, "parameters", false, rules, &mut names)?;
+    }
+    Ok(())
+}
+
+/// 校验 Anthropic Messages 请求体中的自定义工具（`type` 缺省或为 `custom`）
+pub fn validate_anthropic_tools(body: &Value) -> ToolResult {
+    let rules = SchemaRules {
+        gemini: false,
+        array_requires_items: false,
+    };
+    let mut names = HashSet::new();
+
+    for (index, tool) in array_field(body, "tools")?.iter().enumerate() {
+        let field = format!("tools[{index}]");
+        let tool = as_object(tool, &field)?;
+        match tool.get("type") {
+            None => {}
+            Some(Value::String(kind)) if kind == "custom" => {}
+            // 服务端工具（如 `web_search_20250305`）只需名称唯一
+            Some(Value::String(_)) => {
+                if let Some(name) = tool.get("name").and_then(Value::as_str) {
+                    ensure_unique(name, &format!("{field}.name"), &mut names)?;
+                }
+                continue;
+            }
+            Some(_) => {
+                return Err(ToolValidationError::new(
+                    format!("{field}.type"),
+                    "必须是字符串",
+                ));
+            }
+        }
+        validate_function(tool, &field, "input_schema", true, rules, &mut names)?;
+    }
+    Ok(())
+}
+
+/// 校验 Gemini 请求体中的 `functionDeclarations`（兼容 `function_declarations` 写法）
+pub fn validate_gemini_tools(body: &Value) -> ToolResult {
+    let rules = SchemaRules {
+        gemini: true,
+        array_requires_items: false,
+    };
+    let mut names = HashSet::new();
+
+    for (tool_index, tool) in array_field(body, "tools")?.iter().enumerate() {
+        let tool_field = format!("tools[{tool_index}]");
+        let tool = as_object(tool, &tool_field)?;
+        for key in ["functionDeclarations", "function_declarations"] {
+            let declarations = match tool.get(key) {
+                None => continue,
+                Some(Value::Array(declarations)) => declarations,
+                Some(_) => {
+                    return Err(ToolValidationError::new(
+                        format!("{tool_field}.{key}"),
+                        "必须是数组",
+                    ));
+                }
+            };
+            for (index, declaration) in declarations.iter().enumerate() {
+                let field = format!("{tool_field}.{key}[{index}]");
+                let declaration = as_object(declaration, &field)?;
+                validate_gemini_name(declaration, &field, &mut names)?;
+                if let Some(parameters) = declaration.get("parameters") {
+                    validate_parameters(parameters, &format!("{field}.parameters"), rules)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 校验单个函数：名称必填且符合 `^[a-zA-Z0-9_-]{1,64}$`、名称唯一、参数 Schema 合法
+fn validate_function(
+    function: &Map<String, Value>,
+    field: &str,
+    schema_key: &str,
+    schema_required: bool,
+    rules: SchemaRules,
+    names: &mut HashSet<String>,
+) -> ToolResult {
+    let name_field = format!("{field}.name");
+    let name = required_str(function, "name", &name_field)?;
+    if name.is_empty()
+        || name.len() > 64
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    {
+        return Err(ToolValidationError::new(
+            name_field,
+            format!("\"{name}\" 不合法，只能包含字母、数字、下划线与连字符，长度 1-64"),
+        ));
+    }
+    ensure_unique(name, &name_field, names)?;
+
+    if let Some(description) = function.get("description")
+        && !description.is_string()
+    {
+        return Err(ToolValidationError::new(
+            format!("{field}.description"),
+            "必须是字符串",
+        ));
+    }
+
+    let schema_field = format!("{field}.{schema_key}");
+    match function.get(schema_key) {
+        Some(schema) => validate_parameters(schema, &schema_field, rules),
+        None if schema_required => Err(ToolValidationError::new(schema_field, "缺失")),
+        None => Ok(()),
+    }
+}
+
+/// Gemini 函数名：字母或下划线开头，可包含字母、数字、下划线、点、冒号与连字符，最长 64
+fn validate_gemini_name(
+    declaration: &Map<String, Value>,
+    field: &str,
+    names: &mut HashSet<String>,
+) -> ToolResult {
+    let name_field = format!("{field}.name");
+    let name = required_str(declaration, "name", &name_field)?;
+    let valid_start = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    if !valid_start
+        || name.len() > 64
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-'))
+    {
+        return Err(ToolValidationError::new(
+            name_field,
+            format!(
+                "\"{name}\" 不合法，须以字母或下划线开头，只能包含字母、数字、下划线、点、冒号与连字符，长度不超过 64"
+            ),
+        ));
+    }
+    ensure_unique(name, &name_field, names)
+}
+
+/// 函数参数必须是 `type` 为 `object`（或省略）的 Schema
+fn validate_parameters(schema: &Value, field: &str, rules: SchemaRules) -> ToolResult {
+    let object = as_object(schema, field)?;
+    if let Some(kind) = object.get("type") {
+        let kinds = schema_types(kind, &format!("{field}.type"), rules)?;
+        if kinds.as_slice() != ["object"] {
+            return Err(ToolValidationError::new(
+                format!("{field}.type"),
+                format!("必须为 \"object\"，实际为 {kind}"),
+            ));
+        }
+    }
+    validate_schema(object, field, rules, 0)
+}
+
+/// 递归校验 Schema 的 `type`、`properties`、`required`、`items` 与 `enum`
+fn validate_schema(
+    schema: &Map<String, Value>,
+    field: &str,
+    rules: SchemaRules,
+    depth: usize,
+) -> ToolResult {
+    if depth >= MAX_SCHEMA_DEPTH {
+        return Ok(());
+    }
+
+    let kinds = match schema.get("type") {
+        Some(kind) => schema_types(kind, &format!("{field}.type"), rules)?,
+        None => Vec::new(),
+    };
+
+    let properties = match schema.get("properties") {
+        None => None,
+        Some(Value::Object(properties)) => Some(properties),
+        Some(_) => {
+            return Err(ToolValidationError::new(
+                format!("{field}.properties"),
+                "必须是对象",
+            ));
+        }
+    };
+    for (name, property) in properties.into_iter().flatten() {
+        let property_field = format!("{field}.properties.{name}");
+        validate_schema(
+            as_object(property, &property_field)?,
+            &property_field,
+            rules,
+            depth + 1,
+        )?;
+    }
+
+    if let Some(required) = schema.get("required") {
+        let required_field = format!("{field}.required");
+        let required = required
+            .as_array()
+            .ok_or_else(|| ToolValidationError::new(&required_field, "必须是字符串数组"))?;
+        for (index, name) in required.iter().enumerate() {
+            let name = name.as_str().ok_or_else(|| {
+                ToolValidationError::new(format!("{required_field}[{index}]"), "必须是字符串")
+            })?;
+            if !properties.is_some_and(|properties| properties.contains_key(name)) {
+                return Err(ToolValidationError::new(
+                    format!("{required_field}[{index}]"),
+                    format!("\"{name}\" 未在 properties 中定义"),
+                ));
+            }
+        }
+    }
+
+    match schema.get("items") {
+        Some(items) => {
+            let items_field = format!("{field}.items");
+            validate_schema(
+                as_object(items, &items_field)?,
+                &items_field,
+                rules,
+                depth + 1,
+            )?;
+        }
+        None if rules.array_requires_items && kinds.contains(&"array") => {
+            return Err(ToolValidationError::new(
+                format!("{field}.items"),
+                "类型为 array 时必须声明",
+            ));
+        }
+        None => {}
+    }
+
+    if let Some(values) = schema.get("enum")
+        && !values.as_array().is_some_and(|values| !values.is_empty())
+    {
+        return Err(ToolValidationError::new(
+            format!("{field}.enum"),
+            "必须是非空数组",
+        ));
+    }
+    Ok(())
+}
+
+/// 解析 `type`：JSON Schema 允许字符串或字符串数组；Gemini 只允许单个字符串，大小写不敏感
+fn schema_types<'a>(
+    kind: &'a Value,
+    field: &str,
+    rules: SchemaRules,
+) -> std::result::Result<Vec<&'static str>, ToolValidationError> {
+    let names: Vec<&'a str> = match kind {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) if !rules.gemini => names
+            .iter()
+            .map(|name| {
+                name.as_str()
+                    .ok_or_else(|| ToolValidationError::new(field, "必须是字符串或字符串数组"))
+            })
+            .collect::<std::result::Result<_, _>>()?,
+        _ if rules.gemini => return Err(ToolValidationError::new(field, "必须是字符串")),
+        _ => {
+            return Err(ToolValidationError::new(field, "必须是字符串或字符串数组"));
+        }
+    };
+
+    names
+        .into_iter()
+        .map(|name| {
+            let known = if rules.gemini {
+                JSON_SCHEMA_TYPES
+                    .iter()
+                    .find(|known| known.eq_ignore_ascii_case(name))
+            } else {
+                JSON_SCHEMA_TYPES.iter().find(|known| **known == name)
+            };
+            known.copied().ok_or_else(|| {
+                ToolValidationError::new(
+                    field,
+                    format!(
+                        "\"{name}\" 不是有效类型，可选值：{}",
+                        JSON_SCHEMA_TYPES.join(", ")
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// 读取数组字段，缺失时视为空
+fn array_field<'a>(
+    body: &'a Value,
+    key: &str,
+) -> std::result::Result<&'a [Value], ToolValidationError> {
+    match body.get(key) {
+        None | Some(Value::Null) => Ok(&[]),
+        Some(Value::Array(values)) => Ok(values),
+        Some(_) => Err(ToolValidationError::new(key, "必须是数组")),
+    }
+}
+
+fn as_object<'a>(
+    value: &'a Value,
+    field: &str,
+) -> std::result::Result<&'a Map<String, Value>, ToolValidationError> {
+    value
+        .as_object()
+        .ok_or_else(|| ToolValidationError::new(field, "必须是对象"))
+}
+
+fn required_str<'a>(
+    object: &'a Map<String, Value>,
+    key: &str,
+    field: &str,
+) -> std::result::Result<&'a str, ToolValidationError> {
+    object
+        .get(key)
+        .ok_or_else(|| ToolValidationError::new(field, "缺失"))?
+        .as_str()
+        .ok_or_else(|| ToolValidationError::new(field, "必须是字符串"))
+}
+
+fn ensure_unique(name: &str, field: &str, names: &mut HashSet<String>) -> ToolResult {
+    if names.insert(name.to_string()) {
+        Ok(())
+    } else {
+        Err(ToolValidationError::new(
+            field,
+            format!("\"{name}\" 与其他工具重名"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field(result: ToolResult) -> String {
+        result.expect_err("invalid tool definition").field
+    }
+
+    #[test]
+    fn valid_definitions_pass() {
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "city": {"type": "string"},
+                "days": {"type": ["integer", "null"]},
+                "tags": {"type": "array", "items": {"type": "string", "enum": ["a", "b"]}}
+            },
+            "required": ["city"]
+        });
+        assert!(
+            validate_openai_tools(
+                "/v1/chat/completions",
+                &json!({"tools": [
+                    {"type": "function", "function": {"name": "get_weather", "parameters": parameters}},
+                    {"type": "web_search"}
+                ]})
+            )
+            .is_ok()
+        );
+        assert!(
+            validate_openai_tools(
+                "/v1/responses",
+                &json!({"tools": [{"type": "function", "name": "get_weather", "parameters": parameters}]})
+            )
+            .is_ok()
+        );
+        assert!(validate_openai_tools("/v1/chat/completions", &json!({"model": "gpt-4o"})).is_ok());
+    }
+
+    #[test]
+    fn schema_errors_point_at_the_offending_field() {
+        let openai = |parameters: Value| {
+            field(validate_openai_tools(
+                "/v1/chat/completions",
+                &json!({"tools": [{"type": "function", "function": {"name": "f", "parameters": parameters}}]}),
+            ))
+        };
+
+        assert_eq!(
+            openai(json!({"type": "string"})),
+            "tools[0].function.parameters.type"
+        );
+        assert_eq!(
+            openai(json!({"type": "object", "properties": {"q": {"type": "text"}}})),
+            "tools[0].function.parameters.properties.q.type"
+        );
+        assert_eq!(
+            openai(
+                json!({"type": "object", "properties": {"q": {"type": "string"}}, "required": ["query"]})
+            ),
+            "tools[0].function.parameters.required[0]"
+        );
+        assert_eq!(
+            openai(json!({"type": "object", "properties": {"ids": {"type": "array"}}})),
+            "tools[0].function.parameters.properties.ids.items"
+        );
+        assert_eq!(
+            openai(
+                json!({"type": "object", "properties": {"mode": {"type": "string", "enum": []}}})
+            ),
+            "tools[0].function.parameters.properties.mode.enum"
+        );
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let err = validate_anthropic_tools(&json!({"tools": [
+            {"name": "lookup", "input_schema": {"type": "object"}},
+            {"name": "lookup", "input_schema": {"type": "object"}}
+        ]}))
+        .expect_err("duplicate tool names");
+        assert_eq!(err.field, "tools[1].name");
+        assert!(err.message().contains("重名"));
+    }
+
+    #[test]
+    fn gemini_types_are_case_insensitive() {
+        assert!(
+            validate_gemini_tools(&json!({"tools": [{"functionDeclarations": [{
+                "name": "get_weather",
+                "parameters": {"type": "OBJECT", "properties": {"city": {"type": "STRING"}}, "required": ["city"]}
+            }]}]}))
+            .is_ok()
+        );
+        assert_eq!(
+            field(validate_gemini_tools(
+                &json!({"tools": [{"functionDeclarations": [{
+                    "name": "get_weather",
+                    "parameters": {"type": ["object", "null"]}
+                }]}]})
+            )),
+            "tools[0].functionDeclarations[0].parameters.type"
+        );
+    }
+}
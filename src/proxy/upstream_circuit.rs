@@ -0,0 +1,210 @@
+//! # 上游熔断器
+//!
+//! 按服务商类型记录上游连续失败（连接失败、5xx）：达到阈值后熔断，熔断期间不再尝试连接；
+//! 熔断到期后放行一个探测请求，成功则恢复，失败则重新熔断。
+
+use crate::config::CircuitBreakerConfig;
+use dashmap::DashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, Copy)]
+enum CircuitState {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probe_started: Instant },
+}
+
+/// 熔断检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitDecision {
+    /// 正常放行
+    Allow,
+    /// 熔断到期，放行一个探测请求
+    Probe,
+    /// 熔断中
+    Reject {
+        /// 距离下一次探测的剩余时间
+        retry_after: Duration,
+    },
+}
+
+/// 上游熔断器
+pub struct UpstreamCircuitBreaker {
+    config: CircuitBreakerConfig,
+    states: DashMap<String, CircuitState>,
+}
+
+impl UpstreamCircuitBreaker {
+    #[must_use]
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            states: DashMap::new(),
+        }
+    }
+
+    /// 是否启用熔断
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 服务商配置的备用上游地址
+    #[must_use]
+    pub fn fallback_base_url(&self, provider: &str) -> Option<&str> {
+        self.config
+            .fallback_base_urls
+            .get(provider)
+            .map(String::as_str)
+    }
+
+    /// 检查服务商上游当前是否可用
+    ///
+    /// 探测请求的结果迟迟未回报时（如客户端提前断开），探测超过熔断时长后会再放行一个新的探测。
+    #[must_use]
+    pub fn check(&self, provider: &str) -> CircuitDecision {
+        if !self.config.enabled {
+            return CircuitDecision::Allow;
+        }
+
+        let now = Instant::now();
+        let open_duration = self.config.open_duration();
+        let Some(mut state) = self.states.get_mut(provider) else {
+            return CircuitDecision::Allow;
+        };
+
+        let reopen_at = match *state {
+            CircuitState::Closed { .. } => return CircuitDecision::Allow,
+            CircuitState::Open { until } => until,
+            CircuitState::HalfOpen { probe_started } => probe_started + open_duration,
+        };
+        if now < reopen_at {
+            return CircuitDecision::Reject {
+                retry_after: reopen_at - now,
+            };
+        }
+
+        *state = CircuitState::HalfOpen { probe_started: now };
+        CircuitDecision::Probe
+    }
+
+    /// 记录一次上游成功，关闭熔断并清零失败计数
+    pub fn record_success(&self, provider: &str) {
+        if self.config.enabled {
+            self.states.remove(provider);
+        }
+    }
+
+    /// 记录一次上游失败，返回本次失败是否触发熔断
+    pub fn record_failure(&self, provider: &str) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+
+        let now = Instant::now();
+        let open = CircuitState::Open {
+            until: now + self.config.open_duration(),
+        };
+        let mut state = self
+            .states
+            .entry(provider.to_string())
+            .or_insert(CircuitState::Closed { failures: 0 });
+
+        match *state {
+            CircuitState::Closed { failures } => {
+                let failures = failures.saturating_add(1);
+                if failures >= self.config.failure_threshold {
+                    *state = open;
+                    true
+                } else {
+                    *state = CircuitState::Closed { failures };
+                    false
+                }
+            }
+            CircuitState::HalfOpen { .. } => {
+                *state = open;
+                true
+            }
+            // 熔断前已发出的请求陆续失败，不延长熔断时间
+            CircuitState::Open { .. } => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(threshold: u32, open_secs: u64) -> UpstreamCircuitBreaker {
+        UpstreamCircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: threshold,
+            open_secs,
+            ..CircuitBreakerConfig::default()
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_after_consecutive_failures() {
+        let breaker = breaker(3, 30);
+
+        assert!(!breaker.record_failure("openai"));
+        assert!(!breaker.record_failure("openai"));
+        assert_eq!(breaker.check("openai"), CircuitDecision::Allow);
+        assert!(breaker.record_failure("openai"));
+
+        assert_eq!(
+            breaker.check("openai"),
+            CircuitDecision::Reject {
+                retry_after: Duration::from_secs(30)
+            }
+        );
+        assert_eq!(breaker.check("gemini"), CircuitDecision::Allow);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn success_resets_failure_count() {
+        let breaker = breaker(2, 30);
+
+        assert!(!breaker.record_failure("openai"));
+        breaker.record_success("openai");
+        assert!(!breaker.record_failure("openai"));
+        assert_eq!(breaker.check("openai"), CircuitDecision::Allow);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn half_open_allows_single_probe() {
+        let breaker = breaker(1, 30);
+        assert!(breaker.record_failure("openai"));
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert_eq!(breaker.check("openai"), CircuitDecision::Probe);
+        assert!(matches!(
+            breaker.check("openai"),
+            CircuitDecision::Reject { .. }
+        ));
+
+        // 探测失败重新熔断，探测成功则恢复
+        assert!(breaker.record_failure("openai"));
+        assert!(matches!(
+            breaker.check("openai"),
+            CircuitDecision::Reject { .. }
+        ));
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert_eq!(breaker.check("openai"), CircuitDecision::Probe);
+        breaker.record_success("openai");
+        assert_eq!(breaker.check("openai"), CircuitDecision::Allow);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn disabled_breaker_never_rejects() {
+        let breaker = UpstreamCircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            ..CircuitBreakerConfig::default()
+        });
+
+        assert!(!breaker.record_failure("openai"));
+        assert_eq!(breaker.check("openai"), CircuitDecision::Allow);
+    }
+}
@@ -0,0 +1,249 @@
+//! # 上游错误响应
+//!
+//! 服务 API 通过 `upstream_error_mode` 控制上游错误响应（状态码 >= 400）如何返回客户端：
+//! - `sanitized`：默认，响应体规范化为代理统一的错误格式，只保留上游的错误类型与（脱敏后的）错误信息，
+//!   其余字段（组织 ID、内部调试信息等）不返回；
+//! - `verbatim`：响应体与响应头原样透传，不做规范化，也不按配置移除响应头，便于集成方排查问题。
+//!
+//! 两种模式下追踪记录都保存上游原始响应体；代理仍会追加 CORS 头与 `x-request-id`。
+
+use crate::auth::utils::AuthUtils;
+use crate::error::{self, conversion::ConversionError};
+use crate::proxy::ProxyContext;
+use crate::proxy::response::payload_with_request_id;
+use bytes::{Bytes, BytesMut};
+use entity::user_service_apis;
+use serde_json::{Value, json};
+use std::str::FromStr;
+
+/// 规范化时最多缓存的上游错误响应体字节数，超出部分丢弃（只影响错误信息提取）
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+/// 规范化后错误信息的最大字符数
+const MAX_MESSAGE_CHARS: usize = 512;
+
+/// 错误信息中需要脱敏的凭证前缀
+const SECRET_PREFIXES: &[&str] = &["sk-", "AIza", "ya29.", "xai-", "gsk_"];
+
+/// 服务 API 的上游错误返回方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpstreamErrorMode {
+    /// 规范化并脱敏
+    #[default]
+    Sanitized,
+    /// 原样透传
+    Verbatim,
+}
+
+impl FromStr for UpstreamErrorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sanitized" => Ok(Self::Sanitized),
+            "verbatim" => Ok(Self::Verbatim),
+            _ => Err(format!("Unknown upstream error mode: {s}")),
+        }
+    }
+}
+
+impl UpstreamErrorMode {
+    /// 所有返回方式
+    pub const ALL: [Self; 2] = [Self::Sanitized, Self::Verbatim];
+
+    /// 写入前校验名称，未知名称返回列出可选值的错误
+    pub fn parse_known(s: &str) -> error::Result<Self> {
+        s.parse().map_err(|_| {
+            let known: Vec<&str> = Self::ALL.into_iter().map(Self::as_str).collect();
+            ConversionError::message(format!(
+                "未知的上游错误返回方式: {s}，可选值: {}",
+                known.join(", ")
+            ))
+            .into()
+        })
+    }
+
+    /// 转换为字符串
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Sanitized => "sanitized",
+            Self::Verbatim => "verbatim",
+        }
+    }
+
+    /// 服务 API 生效的返回方式，无法识别的取值按 `sanitized` 处理
+    #[must_use]
+    pub fn of(api: &user_service_apis::Model) -> Self {
+        api.upstream_error_mode.parse().unwrap_or_default()
+    }
+
+    /// 当前请求所属服务 API 的返回方式
+    #[must_use]
+    pub fn from_context(ctx: &ProxyContext) -> Self {
+        ctx.routing
+            .user_service_api
+            .as_ref()
+            .map_or(Self::Sanitized, Self::of)
+    }
+}
+
+/// 上游错误响应体规范化：缓存上游分块，流结束时输出统一格式的错误响应体
+#[derive(Debug)]
+pub struct UpstreamErrorSanitizer {
+    status: u16,
+    request_id: String,
+    buffered: BytesMut,
+}
+
+impl UpstreamErrorSanitizer {
+    #[must_use]
+    pub fn new(status: u16, request_id: &str) -> Self {
+        Self {
+            status,
+            request_id: request_id.to_string(),
+            buffered: BytesMut::new(),
+        }
+    }
+
+    /// 接收一个上游分块；流结束前不向下游输出，结束时返回规范化后的完整响应体
+    pub fn push(&mut self, chunk: Option<&Bytes>, end_of_stream: bool) -> Option<Bytes> {
+        if let Some(chunk) = chunk {
+            let room = MAX_ERROR_BODY_BYTES.saturating_sub(self.buffered.len());
+            self.buffered
+                .extend_from_slice(&chunk[..chunk.len().min(room)]);
+        }
+        if !end_of_stream {
+            return None;
+        }
+        let payload = payload_with_request_id(
+            &sanitize_error_body(self.status, &self.buffered),
+            &self.request_id,
+        );
+        Some(Bytes::from(
+            serde_json::to_vec(&payload).unwrap_or_default(),
+        ))
+    }
+}
+
+/// 将上游错误响应体规范化为 `{"error": {"type", "code", "message", "upstream_type"}}`
+///
+/// 依次从 `error.message`、`message`、字符串形式的 `error` 提取错误信息，无法解析时按状态码给出通用说明；
+/// 错误信息中形如凭证的片段会被脱敏。
+#[must_use]
+pub fn sanitize_error_body(status: u16, body: &[u8]) -> Value {
+    let parsed = serde_json::from_slice::<Value>(body).ok();
+    let error = parsed.as_ref().and_then(|value| value.get("error"));
+    let message = error
+        .and_then(|error| error.get("message"))
+        .or_else(|| parsed.as_ref().and_then(|value| value.get("message")))
+        .or_else(|| error.filter(|error| error.is_string()))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+        .map_or_else(|| generic_message(status).to_string(), redact_message);
+    // OpenAI、Anthropic 使用 `error.type`，Gemini 使用 `error.status`
+    let upstream_type = error
+        .and_then(|error| error.get("type").or_else(|| error.get("status")))
+        .and_then(Value::as_str);
+
+    let mut payload = json!({
+        "error": {
+            "type": "upstream_error",
+            "code": status,
+            "message": message
+        }
+    });
+    if let (Some(upstream_type), Some(error)) = (
+        upstream_type,
+        payload.get_mut("error").and_then(Value::as_object_mut),
+    ) {
+        error.insert("upstream_type".to_string(), json!(upstream_type));
+    }
+    payload
+}
+
+fn generic_message(status: u16) -> &'static str {
+    match status {
+        400 => "上游拒绝了请求",
+        401 | 403 => "上游认证失败",
+        404 => "上游资源不存在",
+        429 => "上游请求过于频繁",
+        500..=599 => "上游服务内部错误",
+        _ => "上游返回错误",
+    }
+}
+
+/// 脱敏并截断错误信息
+fn redact_message(message: &str) -> String {
+    let redacted: Vec<String> = message
+        .split(' ')
+        .map(|word| {
+            let token = word.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '-');
+            if is_secret(token) {
+                word.replace(token, &AuthUtils::sanitize_api_key(token))
+            } else {
+                word.to_string()
+            }
+        })
+        .collect();
+    redacted.join(" ").chars().take(MAX_MESSAGE_CHARS).collect()
+}
+
+fn is_secret(token: &str) -> bool {
+    token.len() >= 16
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && SECRET_PREFIXES
+            .iter()
+            .any(|prefix| token.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_message_and_type_from_provider_formats() {
+        let openai = br#"{"error": {"message": "Invalid model", "type": "invalid_request_error", "param": "model", "code": null}}"#;
+        assert_eq!(
+            sanitize_error_body(400, openai),
+            json!({"error": {
+                "type": "upstream_error",
+                "code": 400,
+                "message": "Invalid model",
+                "upstream_type": "invalid_request_error"
+            }})
+        );
+
+        let gemini =
+            br#"{"error": {"code": 429, "message": "Quota exceeded", "status": "RESOURCE_EXHAUSTED"}}"#;
+        assert_eq!(
+            sanitize_error_body(429, gemini)["error"]["upstream_type"],
+            "RESOURCE_EXHAUSTED"
+        );
+
+        let plain = br#"{"error": "bad gateway"}"#;
+        assert_eq!(
+            sanitize_error_body(502, plain)["error"]["message"],
+            "bad gateway"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_generic_message_and_redacts_keys() {
+        let html = b"<html><body>Internal Server Error</body></html>";
+        assert_eq!(
+            sanitize_error_body(500, html)["error"]["message"],
+            "上游服务内部错误"
+        );
+
+        let leaked =
+            br#"{"error": {"message": "Incorrect API key provided: sk-abcdefghijklmnopqrstuvwxyz."}}"#;
+        assert_eq!(
+            sanitize_error_body(401, leaked)["error"]["message"],
+            "Incorrect API key provided: sk-a***wxyz."
+        );
+    }
+}
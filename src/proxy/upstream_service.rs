@@ -1,41 +1,129 @@
 //! # 上游服务模块
 //!
 //! 负责所有与上游节点（Peer）相关的逻辑，包括根据服务商策略选择地址和配置连接参数。
+//! 服务商上游处于熔断状态时，在建立连接前快速失败或改走配置的备用地址。
+//! 连接池与保活参数来自 `[upstream_pool]` 配置，连接复用情况按进程累计供系统指标查询。
 
-use crate::error::{Context, Result, config::ConfigError};
-use crate::linfo;
+use crate::config::UpstreamPoolConfig;
+use crate::error::{Context, ProxyError, Result, config::ConfigError};
 use crate::logging::{LogComponent, LogStage};
 use crate::proxy::context::ProxyContext;
+use crate::proxy::sse_keepalive::SseKeepaliveRelay;
+use crate::proxy::upstream_circuit::{CircuitDecision, UpstreamCircuitBreaker};
 use crate::proxy::upstream_url::parse_base_url;
+use crate::{linfo, lwarn};
 use pingora_core::protocols::TcpKeepalive;
-use pingora_core::upstreams::peer::{ALPN, HttpPeer, Peer};
+use pingora_core::upstreams::peer::{ALPN, HttpPeer, Peer, PeerOptions};
 use sea_orm::DatabaseConnection;
+use serde::Serialize;
 use std::convert::TryFrom;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
+/// 启动以来建立的上游连接总数（含复用）
+static CONNECTIONS_TOTAL: AtomicU64 = AtomicU64::new(0);
+/// 启动以来复用连接池中已有连接的次数
+static CONNECTIONS_REUSED_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// 上游连接复用统计
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct UpstreamConnectionStats {
+    pub connections_total: u64,
+    pub reused_total: u64,
+    /// 复用率（0-1），尚无连接时为 0
+    pub reuse_rate: f64,
+}
+
+/// 记录一次上游连接，`reused` 表示复用了连接池中的连接
+pub fn record_upstream_connection(reused: bool) {
+    CONNECTIONS_TOTAL.fetch_add(1, Ordering::Relaxed);
+    if reused {
+        CONNECTIONS_REUSED_TOTAL.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// 当前进程的上游连接复用统计
+#[must_use]
+pub fn upstream_connection_stats() -> UpstreamConnectionStats {
+    let connections_total = CONNECTIONS_TOTAL.load(Ordering::Relaxed);
+    let reused_total = CONNECTIONS_REUSED_TOTAL.load(Ordering::Relaxed);
+    let reuse_rate = if connections_total == 0 {
+        0.0
+    } else {
+        f64::from(u32::try_from(reused_total).unwrap_or(u32::MAX))
+            / f64::from(u32::try_from(connections_total).unwrap_or(u32::MAX))
+    };
+    UpstreamConnectionStats {
+        connections_total,
+        reused_total,
+        reuse_rate,
+    }
+}
+
 /// 上游服务
 pub struct UpstreamService {
     db: Arc<DatabaseConnection>,
+    circuit_breaker: Arc<UpstreamCircuitBreaker>,
+    pool_config: UpstreamPoolConfig,
+    sse_keepalive: Option<SseKeepaliveRelay>,
 }
 
+/// 经 SSE keepalive 中转的连接使用的连接池分组，避免与直连的连接混用
+const SSE_KEEPALIVE_GROUP_KEY: u64 = 0x5353_455f_6b61_6c76;
+
+/// 上游 TCP 握手超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(6);
+
 impl UpstreamService {
     /// 创建新的上游服务
     #[must_use]
-    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<DatabaseConnection>, circuit_breaker: Arc<UpstreamCircuitBreaker>) -> Self {
+        Self {
+            db,
+            circuit_breaker,
+            pool_config: UpstreamPoolConfig::default(),
+            sse_keepalive: None,
+        }
+    }
+
+    /// 使用配置的连接池与保活参数
+    #[must_use]
+    pub fn with_pool_config(mut self, pool_config: UpstreamPoolConfig) -> Self {
+        self.pool_config = pool_config;
+        self
+    }
+
+    /// 启用 SSE keepalive 中转；`None` 表示关闭
+    #[must_use]
+    pub fn with_sse_keepalive(mut self, relay: Option<SseKeepaliveRelay>) -> Self {
+        self.sse_keepalive = relay;
+        self
+    }
+
+    /// 是否开启了 SSE keepalive 中转
+    #[must_use]
+    pub const fn sse_keepalive_enabled(&self) -> bool {
+        self.sse_keepalive.is_some()
     }
 
     /// 选择上游对等体
-    pub async fn select_peer(&self, ctx: &ProxyContext) -> Result<Box<HttpPeer>> {
+    pub async fn select_peer(&self, ctx: &mut ProxyContext) -> Result<Box<HttpPeer>> {
         let provider_type = ctx
             .routing
             .provider_type
             .as_ref()
             .ok_or_else(|| ConfigError::Load("Provider type not set in context".to_string()))?;
 
+        // 熔断中：有备用地址则改走备用地址，否则快速失败
+        let fallback_addr = self.circuit_fallback(&ctx.request_id, &provider_type.name)?;
+        let circuit_provider = (fallback_addr.is_none() && self.circuit_breaker.is_enabled())
+            .then(|| provider_type.name.clone());
+
         // 优先由 ProviderStrategy 决定上游地址
-        let upstream_addr = if let Some(strategy) = &ctx.routing.strategy {
+        let upstream_addr = if fallback_addr.is_some() {
+            fallback_addr
+        } else if let Some(strategy) = &ctx.routing.strategy {
             match strategy.select_upstream_host(ctx).await {
                 Ok(Some(host)) => Some(host),
                 _ => None,
@@ -62,28 +150,52 @@ impl UpstreamService {
             provider_url = provider_type.base_url
         );
 
-        let mut peer = HttpPeer::new(&parsed.addr, true, parsed.sni.clone());
+        // SSE keepalive 中转自行处理 TLS，Pingora 侧按明文 HTTP/1.1 连接；只有流式请求经中转
+        let relay = self
+            .sse_keepalive
+            .as_ref()
+            .filter(|_| ctx.request.is_streaming);
+
+        // 构造 HttpPeer 时同步解析地址，计入 DNS 阶段
+        ctx.trace.upstream_timing.start_dns();
+        let mut peer = HttpPeer::new(
+            &parsed.addr,
+            parsed.tls && relay.is_none(),
+            parsed.sni.clone(),
+        );
+        ctx.trace.upstream_timing.finish_dns();
 
         let timeout = u64::try_from(ctx.control.timeout_seconds.unwrap_or(30).max(0)).unwrap_or(30);
-        let read_timeout_secs = timeout * 2;
+        // 读写超时不超过请求总时长的剩余额度，上游停止输出时也能按时中断
+        let read_timeout = ctx
+            .total_timeout_remaining()
+            .map_or(Duration::from_secs(timeout * 2), |remaining| {
+                remaining.min(Duration::from_secs(timeout * 2))
+            });
 
         if let Some(options) = peer.get_mut_peer_options() {
-            options.alpn = ALPN::H2H1;
+            // WebSocket 升级依赖 HTTP/1.1 的 Upgrade 机制
+            options.alpn = if ctx.request.is_websocket || relay.is_some() {
+                ALPN::H1
+            } else {
+                ALPN::H2H1
+            };
             // [优化] 连接建立应该快速失败，不要等待业务超时
-            options.connection_timeout = Some(Duration::from_secs(6)); // TCP握手超时
+            options.connection_timeout = Some(CONNECT_TIMEOUT); // TCP握手超时
             options.total_connection_timeout = Some(Duration::from_secs(10)); // 含TLS握手超时
-            options.read_timeout = Some(Duration::from_secs(read_timeout_secs));
-            options.write_timeout = Some(Duration::from_secs(read_timeout_secs));
-            options.idle_timeout = Some(Duration::from_secs(20));
-            options.h2_ping_interval = Some(Duration::from_secs(20));
-            options.max_h2_streams = 100;
-            // 启用 TCP Keepalive，防止长连接在无数据传输时被中间设备断开
-            options.tcp_keepalive = Some(TcpKeepalive {
-                idle: Duration::from_secs(20),
-                interval: Duration::from_secs(5),
-                count: 5,
-                user_timeout: Duration::from_secs(timeout),
-            });
+            options.read_timeout = Some(read_timeout);
+            options.write_timeout = Some(read_timeout);
+            self.apply_pool_options(options, Duration::from_secs(timeout));
+            if let Some(relay) = relay {
+                options.custom_l4 = Some(Arc::new(relay.connector(
+                    parsed.tls.then(|| parsed.sni.clone()),
+                    CONNECT_TIMEOUT,
+                    read_timeout,
+                )));
+            }
+        }
+        if relay.is_some() {
+            peer.group_key = SSE_KEEPALIVE_GROUP_KEY;
         }
 
         linfo!(
@@ -96,6 +208,207 @@ impl UpstreamService {
             timeout = timeout,
         );
 
+        ctx.routing.circuit_provider = circuit_provider;
         Ok(Box::new(peer))
     }
+
+    /// 应用连接复用与保活参数
+    fn apply_pool_options(&self, options: &mut PeerOptions, user_timeout: Duration) {
+        let pool = &self.pool_config;
+        // 空闲连接在连接池中保留的时长，决定后续请求能否复用
+        options.idle_timeout = Some(pool.idle_timeout());
+        options.h2_ping_interval = pool.h2_ping_interval();
+        options.max_h2_streams = pool.max_h2_streams;
+        // 启用 TCP Keepalive，防止长连接在无数据传输时被中间设备断开
+        options.tcp_keepalive = Some(TcpKeepalive {
+            idle: Duration::from_secs(pool.tcp_keepalive_idle_secs),
+            interval: Duration::from_secs(pool.tcp_keepalive_interval_secs),
+            count: pool.tcp_keepalive_count,
+            user_timeout,
+        });
+    }
+
+    /// 检查服务商熔断状态，熔断中时返回备用地址；未配置备用地址则快速失败
+    fn circuit_fallback(&self, request_id: &str, provider: &str) -> Result<Option<String>> {
+        match self.circuit_breaker.check(provider) {
+            CircuitDecision::Allow => Ok(None),
+            CircuitDecision::Probe => {
+                linfo!(
+                    request_id,
+                    LogStage::UpstreamRequest,
+                    LogComponent::Upstream,
+                    "upstream_circuit_probe",
+                    "上游熔断到期，放行探测请求",
+                    provider = provider
+                );
+                Ok(None)
+            }
+            CircuitDecision::Reject { retry_after } => {
+                let Some(fallback) = self.circuit_breaker.fallback_base_url(provider) else {
+                    lwarn!(
+                        request_id,
+                        LogStage::UpstreamRequest,
+                        LogComponent::Upstream,
+                        "upstream_circuit_open",
+                        "上游已熔断，快速失败",
+                        provider = provider,
+                        retry_after_secs = retry_after.as_secs()
+                    );
+                    return Err(ProxyError::upstream_not_available(format!(
+                        "服务商 {provider} 上游已熔断，{} 秒后重试",
+                        retry_after.as_secs().max(1)
+                    )));
+                };
+                lwarn!(
+                    request_id,
+                    LogStage::UpstreamRequest,
+                    LogComponent::Upstream,
+                    "upstream_circuit_fallback",
+                    "上游已熔断，改用备用地址",
+                    provider = provider,
+                    fallback = fallback
+                );
+                Ok(Some(fallback.to_string()))
+            }
+        }
+    }
+
+    /// 记录上游请求结果（使用备用地址的请求不计入熔断统计）
+    pub fn record_upstream_result(&self, ctx: &ProxyContext, success: bool) {
+        let Some(provider) = ctx.routing.circuit_provider.as_deref() else {
+            return;
+        };
+        if success {
+            self.circuit_breaker.record_success(provider);
+        } else if self.circuit_breaker.record_failure(provider) {
+            lwarn!(
+                &ctx.request_id,
+                LogStage::UpstreamRequest,
+                LogComponent::Upstream,
+                "upstream_circuit_opened",
+                "上游连续失败，已熔断",
+                provider = provider
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CircuitBreakerConfig;
+    use entity::provider_types;
+    use std::collections::HashMap;
+
+    fn provider(name: &str, base_url: &str) -> provider_types::Model {
+        let now = chrono::Utc::now().naive_utc();
+        provider_types::Model {
+            id: 1,
+            name: name.to_string(),
+            display_name: name.to_string(),
+            auth_type: "api_key".to_string(),
+            base_url: base_url.to_string(),
+            is_active: true,
+            config_json: None,
+            token_mappings_json: None,
+            model_extraction_json: None,
+            auth_configs_json: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn context_for(provider_type: provider_types::Model) -> ProxyContext {
+        let mut ctx = ProxyContext::default();
+        ctx.request_id = "test-request".to_string();
+        ctx.routing.provider_type = Some(provider_type);
+        ctx
+    }
+
+    async fn service_with(fallbacks: &[(&str, &str)]) -> UpstreamService {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let breaker = UpstreamCircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 2,
+            open_secs: 30,
+            fallback_base_urls: fallbacks
+                .iter()
+                .map(|(name, url)| ((*name).to_string(), (*url).to_string()))
+                .collect::<HashMap<_, _>>(),
+        });
+        UpstreamService::new(Arc::new(db), Arc::new(breaker))
+    }
+
+    async fn trip(service: &UpstreamService, ctx: &mut ProxyContext) {
+        for _ in 0..2 {
+            service.select_peer(ctx).await.unwrap();
+            service.record_upstream_result(ctx, false);
+        }
+    }
+
+    #[tokio::test]
+    async fn open_circuit_fast_fails_while_healthy_provider_proceeds() {
+        let service = service_with(&[]).await;
+        let mut failing = context_for(provider("openai", "https://api.openai.com"));
+        let mut healthy = context_for(provider("claude", "https://api.anthropic.com"));
+
+        trip(&service, &mut failing).await;
+
+        let err = service.select_peer(&mut failing).await.unwrap_err();
+        assert_eq!(err.error_code(), "UPSTREAM_NOT_AVAILABLE");
+
+        let peer = service.select_peer(&mut healthy).await.unwrap();
+        assert_eq!(peer.sni, "api.anthropic.com");
+        assert_eq!(healthy.routing.circuit_provider.as_deref(), Some("claude"));
+    }
+
+    #[tokio::test]
+    async fn configured_pool_options_are_applied_to_peer() {
+        let service = service_with(&[])
+            .await
+            .with_pool_config(UpstreamPoolConfig {
+                pool_size: 512,
+                idle_timeout_secs: 90,
+                h2_ping_interval_secs: 0,
+                max_h2_streams: 250,
+                tcp_keepalive_idle_secs: 45,
+                tcp_keepalive_interval_secs: 10,
+                tcp_keepalive_count: 3,
+            });
+        let mut ctx = context_for(provider("openai", "https://api.openai.com"));
+
+        let peer = service.select_peer(&mut ctx).await.unwrap();
+        let options = &peer.options;
+        assert_eq!(options.idle_timeout, Some(Duration::from_secs(90)));
+        assert_eq!(options.h2_ping_interval, None);
+        assert_eq!(options.max_h2_streams, 250);
+        let keepalive = options.tcp_keepalive.as_ref().expect("tcp keepalive");
+        assert_eq!(keepalive.idle, Duration::from_secs(45));
+        assert_eq!(keepalive.interval, Duration::from_secs(10));
+        assert_eq!(keepalive.count, 3);
+    }
+
+    #[test]
+    fn connection_reuse_is_counted() {
+        let before = upstream_connection_stats();
+        record_upstream_connection(false);
+        record_upstream_connection(true);
+        let after = upstream_connection_stats();
+        assert!(after.connections_total >= before.connections_total + 2);
+        assert!(after.reused_total > before.reused_total);
+        assert!(after.reuse_rate > 0.0 && after.reuse_rate <= 1.0);
+    }
+
+    #[tokio::test]
+    async fn open_circuit_routes_to_configured_fallback() {
+        let service = service_with(&[("openai", "https://openai-backup.example.com")]).await;
+        let mut ctx = context_for(provider("openai", "https://api.openai.com"));
+
+        trip(&service, &mut ctx).await;
+
+        let peer = service.select_peer(&mut ctx).await.unwrap();
+        assert_eq!(peer.sni, "openai-backup.example.com");
+        // 备用地址的结果不计入主上游的熔断统计
+        assert_eq!(ctx.routing.circuit_provider, None);
+    }
 }
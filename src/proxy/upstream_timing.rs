@@ -0,0 +1,195 @@
+//! # 上游连接阶段耗时
+//!
+//! 排查延迟时需要区分 DNS 解析、建连、TLS 握手与等待首字节的耗时。各阶段取自 Pingora 的连接生命周期：
+//! - `dns`：构造 `HttpPeer` 时的地址解析；
+//! - `connect`：解析完成到 `connected_to_upstream` 回调（新建连接含 TCP 与 TLS，复用连接接近 0）；
+//! - `tcp_connect` / `tls_handshake`：新建连接时按连接摘要（`Digest`）中各层的建立时间拆分；
+//! - `first_byte`：连接就绪到收到上游响应头。
+//!
+//! 阶段记录在追踪记录的 `response_metadata.phases` 中，重试时只保留最后一次尝试的阶段。
+
+use std::time::{Instant, SystemTime};
+
+use chrono::{DateTime, Utc};
+use entity::proxy_tracing::PhaseInfo;
+use pingora_core::protocols::Digest;
+use serde_json::Value;
+
+/// 响应元数据中记录阶段耗时的字段
+pub const PHASES_KEY: &str = "phases";
+
+const PHASE_COMPLETED: &str = "completed";
+
+/// 阶段起止时刻：单调时钟用于计算耗时，墙上时间用于展示
+#[derive(Debug, Clone, Copy)]
+struct Mark {
+    instant: Instant,
+    at: DateTime<Utc>,
+}
+
+impl Mark {
+    fn now() -> Self {
+        Self {
+            instant: Instant::now(),
+            at: Utc::now(),
+        }
+    }
+}
+
+/// 单次上游尝试的阶段耗时
+#[derive(Debug, Clone, Default)]
+pub struct UpstreamTiming {
+    dns_started: Option<Mark>,
+    connect_started: Option<Mark>,
+    connected: Option<Mark>,
+    first_byte_recorded: bool,
+    phases: Vec<PhaseInfo>,
+}
+
+impl UpstreamTiming {
+    /// 开始解析上游地址
+    pub fn start_dns(&mut self) {
+        self.dns_started = Some(Mark::now());
+    }
+
+    /// 地址解析完成，随后开始建连
+    pub fn finish_dns(&mut self) {
+        let now = Mark::now();
+        if let Some(started) = self.dns_started.take() {
+            self.push_between("dns", started, now, None);
+        }
+        self.connect_started = Some(now);
+    }
+
+    /// 已连接上游；新建连接按摘要拆分 TCP 与 TLS 阶段
+    pub fn record_connected(&mut self, reused: bool, digest: Option<&Digest>) {
+        let now = Mark::now();
+        let Some(started) = self.connect_started.take() else {
+            self.connected = Some(now);
+            return;
+        };
+        self.push_between(
+            "connect",
+            started,
+            now,
+            reused.then(|| "reused".to_string()),
+        );
+
+        if !reused {
+            let layers: Vec<SystemTime> = digest
+                .map(|digest| {
+                    digest
+                        .timing_digest
+                        .iter()
+                        .flatten()
+                        .map(|timing| timing.established_ts)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if let Some(tcp_established) = layers.first() {
+                let tcp_established = DateTime::<Utc>::from(*tcp_established);
+                self.push_at("tcp_connect", started.at, tcp_established);
+                if let Some(tls_established) = layers.get(1) {
+                    self.push_at(
+                        "tls_handshake",
+                        tcp_established,
+                        DateTime::<Utc>::from(*tls_established),
+                    );
+                }
+            }
+        }
+        self.connected = Some(now);
+    }
+
+    /// 收到上游响应头（每次尝试只记录一次）
+    pub fn record_first_byte(&mut self) {
+        if self.first_byte_recorded {
+            return;
+        }
+        if let Some(connected) = self.connected {
+            self.push_between("first_byte", connected, Mark::now(), None);
+            self.first_byte_recorded = true;
+        }
+    }
+
+    /// 已记录的阶段
+    #[must_use]
+    pub fn phases(&self) -> &[PhaseInfo] {
+        &self.phases
+    }
+
+    /// 查找指定阶段
+    #[must_use]
+    pub fn phase(&self, name: &str) -> Option<&PhaseInfo> {
+        self.phases.iter().find(|phase| phase.phase == name)
+    }
+
+    fn push_between(&mut self, phase: &str, start: Mark, end: Mark, details: Option<String>) {
+        let duration_ms = u64::try_from(end.instant.duration_since(start.instant).as_millis())
+            .unwrap_or(u64::MAX);
+        self.phases.push(PhaseInfo {
+            phase: phase.to_string(),
+            start_time: start.at.naive_utc(),
+            end_time: Some(end.at.naive_utc()),
+            duration_ms: Some(duration_ms),
+            status: PHASE_COMPLETED.to_string(),
+            details,
+        });
+    }
+
+    fn push_at(&mut self, phase: &str, start: DateTime<Utc>, end: DateTime<Utc>) {
+        self.phases.push(PhaseInfo {
+            phase: phase.to_string(),
+            start_time: start.naive_utc(),
+            end_time: Some(end.naive_utc()),
+            duration_ms: u64::try_from((end - start).num_milliseconds().max(0)).ok(),
+            status: PHASE_COMPLETED.to_string(),
+            details: None,
+        });
+    }
+}
+
+/// 在响应元数据中记录阶段耗时；没有阶段时保持原样
+#[must_use]
+pub fn mark_phases(metadata: Option<Value>, phases: &[PhaseInfo]) -> Option<Value> {
+    if phases.is_empty() {
+        return metadata;
+    }
+    let mut fields = match metadata {
+        Some(Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    fields.insert(
+        PHASES_KEY.to_string(),
+        serde_json::to_value(phases).unwrap_or(Value::Null),
+    );
+    Some(Value::Object(fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_connection_skips_layer_phases() {
+        let mut timing = UpstreamTiming::default();
+        timing.start_dns();
+        timing.finish_dns();
+        timing.record_connected(true, None);
+        timing.record_first_byte();
+        timing.record_first_byte();
+
+        let names: Vec<_> = timing.phases().iter().map(|p| p.phase.as_str()).collect();
+        assert_eq!(names, vec!["dns", "connect", "first_byte"]);
+        assert_eq!(
+            timing.phase("connect").and_then(|p| p.details.as_deref()),
+            Some("reused")
+        );
+
+        let metadata =
+            mark_phases(Some(serde_json::json!({"id": "x"})), timing.phases()).expect("metadata");
+        assert_eq!(metadata["id"], "x");
+        assert_eq!(metadata[PHASES_KEY][2]["phase"], "first_byte");
+        assert!(mark_phases(None, &[]).is_none());
+    }
+}
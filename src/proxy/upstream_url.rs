@@ -1,6 +1,7 @@
 //! 上游地址解析工具
 //!
 //! 统一处理 `base_url` 可能包含的 scheme / path / port，并输出可用于 Pingora 的 `host:port`。
+//! 未写 scheme 时按 `https` 处理；显式写成 `http://` 的 `base_url` 以明文连接上游，不再走 TLS。
 
 use crate::ensure;
 use crate::error::{Result, config::ConfigError};
@@ -11,6 +12,8 @@ pub(crate) struct UpstreamAddress {
     pub addr: String,
     pub host_header: String,
     pub sni: String,
+    /// 是否使用 TLS；仅显式的 `http://` 地址走明文
+    pub tls: bool,
 }
 
 /// 解析上游 `base_url`，输出 Peer 地址与 Host/SNI
@@ -51,5 +54,29 @@ pub(crate) fn parse_base_url(raw: &str) -> Result<UpstreamAddress> {
         addr,
         host_header,
         sni,
+        tls: url.scheme() != "http",
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_http_scheme_dials_plaintext() {
+        let parsed = parse_base_url("http://127.0.0.1:8080/v1").unwrap();
+        assert!(!parsed.tls);
+        assert_eq!(parsed.addr, "127.0.0.1:8080");
+    }
+
+    #[test]
+    fn https_or_missing_scheme_dials_tls() {
+        let parsed = parse_base_url("https://api.openai.com").unwrap();
+        assert!(parsed.tls);
+        assert_eq!(parsed.addr, "api.openai.com:443");
+
+        let parsed = parse_base_url("api.openai.com").unwrap();
+        assert!(parsed.tls);
+        assert_eq!(parsed.sni, "api.openai.com");
+    }
+}
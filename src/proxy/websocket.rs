@@ -0,0 +1,375 @@
+//! # WebSocket 透传
+//!
+//! 实时类接口（如 `OpenAI` Realtime）通过 WebSocket 升级建立长连接。握手请求沿用普通代理流程
+//! （认证、注入上游凭证、选择上游），上游返回 101 后由 Pingora 双向透传数据帧；
+//! 这里只负责识别升级请求，以及旁路解析上游下发的文本消息，尽力从会话事件中统计用量。
+
+use crate::collect::types::TokenUsageMetrics;
+use crate::collect::usage_model;
+use crate::types::TokenCount;
+use bytes::{Buf, BytesMut};
+use entity::provider_types;
+use pingora_http::RequestHeader;
+use serde_json::{Value, json};
+
+/// 单条消息的解析上限，超过上限的消息只透传不解析
+const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+
+/// 判断请求是否为 WebSocket 升级握手
+#[must_use]
+pub fn is_websocket_upgrade(req: &RequestHeader) -> bool {
+    let upgrade = req
+        .headers
+        .get("upgrade")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("websocket"));
+    let connection = req
+        .headers
+        .get_all("connection")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+    upgrade && connection
+}
+
+/// 从握手 URL 的查询参数中读取模型名称（如 `/v1/realtime?model=gpt-4o-realtime-preview`）
+#[must_use]
+pub fn model_from_query(req: &RequestHeader) -> Option<String> {
+    req.uri
+        .query()?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "model")
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// WebSocket 帧解码器
+///
+/// 只还原文本消息（含分片），二进制与控制帧直接跳过；启用压缩扩展的帧无法解析，同样跳过。
+#[derive(Debug, Default)]
+pub struct WebSocketFrameDecoder {
+    buf: BytesMut,
+    /// 正在拼接的分片文本消息
+    message: Option<BytesMut>,
+    /// 当前分片消息是否被丢弃（非文本、已压缩或超过上限）
+    discarding: bool,
+    /// 超大帧剩余待跳过的字节数
+    skip_remaining: usize,
+}
+
+struct FrameHeader {
+    fin: bool,
+    compressed: bool,
+    opcode: u8,
+    mask: Option<[u8; 4]>,
+    header_len: usize,
+    payload_len: usize,
+}
+
+impl WebSocketFrameDecoder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一段原始字节，返回其中完整的文本消息
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut messages = Vec::new();
+        let mut chunk = chunk;
+
+        if self.skip_remaining > 0 {
+            let skipped = self.skip_remaining.min(chunk.len());
+            self.skip_remaining -= skipped;
+            chunk = &chunk[skipped..];
+        }
+        self.buf.extend_from_slice(chunk);
+
+        while self.skip_remaining == 0 {
+            let Some(header) = Self::parse_header(&self.buf) else {
+                break;
+            };
+
+            if header.payload_len > MAX_MESSAGE_BYTES {
+                let available = self.buf.len() - header.header_len;
+                let skipped = available.min(header.payload_len);
+                self.buf.advance(header.header_len + skipped);
+                self.skip_remaining = header.payload_len - skipped;
+                self.discard_frame(&header);
+                continue;
+            }
+
+            let frame_len = header.header_len + header.payload_len;
+            if self.buf.len() < frame_len {
+                break;
+            }
+            let mut frame = self.buf.split_to(frame_len);
+            frame.advance(header.header_len);
+            if let Some(mask) = header.mask {
+                for (i, byte) in frame.iter_mut().enumerate() {
+                    *byte ^= mask[i % 4];
+                }
+            }
+
+            if let Some(message) = self.on_frame(&header, frame) {
+                messages.push(message);
+            }
+        }
+
+        messages
+    }
+
+    fn parse_header(buf: &[u8]) -> Option<FrameHeader> {
+        let (&first, rest) = buf.split_first()?;
+        let (&second, rest) = rest.split_first()?;
+        let masked = second & 0x80 != 0;
+
+        let (payload_len, ext_len) = match second & 0x7f {
+            126 => (
+                usize::from(u16::from_be_bytes(rest.get(..2)?.try_into().ok()?)),
+                2,
+            ),
+            127 => (
+                usize::try_from(u64::from_be_bytes(rest.get(..8)?.try_into().ok()?))
+                    .unwrap_or(usize::MAX),
+                8,
+            ),
+            len => (usize::from(len), 0),
+        };
+        let mask = if masked {
+            Some(rest.get(ext_len..ext_len + 4)?.try_into().ok()?)
+        } else {
+            None
+        };
+
+        Some(FrameHeader {
+            fin: first & 0x80 != 0,
+            compressed: first & 0x40 != 0,
+            opcode: first & 0x0f,
+            mask,
+            header_len: 2 + ext_len + if masked { 4 } else { 0 },
+            payload_len,
+        })
+    }
+
+    fn discard_frame(&mut self, header: &FrameHeader) {
+        if header.opcode == OPCODE_TEXT || header.opcode == OPCODE_CONTINUATION {
+            self.message = None;
+            self.discarding = !header.fin;
+        }
+    }
+
+    fn on_frame(&mut self, header: &FrameHeader, payload: BytesMut) -> Option<String> {
+        match header.opcode {
+            OPCODE_TEXT if header.compressed => {
+                self.discard_frame(header);
+                None
+            }
+            OPCODE_TEXT if header.fin => {
+                self.message = None;
+                self.discarding = false;
+                String::from_utf8(payload.to_vec()).ok()
+            }
+            OPCODE_TEXT => {
+                self.message = Some(payload);
+                self.discarding = false;
+                None
+            }
+            OPCODE_CONTINUATION => {
+                if self.discarding {
+                    self.discarding = !header.fin;
+                    return None;
+                }
+                let message = self.message.as_mut()?;
+                if message.len() + payload.len() > MAX_MESSAGE_BYTES {
+                    self.discard_frame(header);
+                    return None;
+                }
+                message.extend_from_slice(&payload);
+                if !header.fin {
+                    return None;
+                }
+                let message = self.message.take()?;
+                String::from_utf8(message.to_vec()).ok()
+            }
+            // 控制帧（close/ping/pong）可以插在分片之间，不影响正在拼接的消息
+            opcode if opcode >= 0x8 => None,
+            // 二进制消息：后续分片一并跳过
+            _ => {
+                self.message = None;
+                self.discarding = !header.fin;
+                None
+            }
+        }
+    }
+}
+
+/// WebSocket 会话统计
+#[derive(Debug, Default)]
+pub struct WebSocketSession {
+    decoder: WebSocketFrameDecoder,
+    /// 上游下发的字节数
+    pub bytes_received: usize,
+    /// 上游下发的文本消息数
+    pub messages_received: usize,
+    /// 从会话事件中累计的用量
+    pub usage: TokenUsageMetrics,
+    /// 会话事件中声明的模型
+    pub model: Option<String>,
+}
+
+impl WebSocketSession {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 观察上游下发的一段数据
+    pub fn observe_upstream(&mut self, chunk: &[u8], provider: Option<&provider_types::Model>) {
+        self.bytes_received = self.bytes_received.saturating_add(chunk.len());
+        for message in self.decoder.feed(chunk) {
+            self.messages_received += 1;
+            if let Ok(event) = serde_json::from_str::<Value>(&message) {
+                self.observe_event(&event, provider);
+            }
+        }
+    }
+
+    /// 处理单个会话事件：`session.*` 事件携带模型，`response.done` 事件携带本轮用量
+    pub fn observe_event(&mut self, event: &Value, provider: Option<&provider_types::Model>) {
+        match event.get("type").and_then(Value::as_str) {
+            Some("session.created" | "session.updated") => {
+                if let Some(model) = event.pointer("/session/model").and_then(Value::as_str) {
+                    self.model = Some(model.to_string());
+                }
+            }
+            Some("response.done") => {
+                let Some(response) = event.get("response") else {
+                    return;
+                };
+                let usage = usage_model::extract_tokens_from_json(provider, response);
+                accumulate(&mut self.usage.prompt_tokens, usage.prompt_tokens);
+                accumulate(&mut self.usage.completion_tokens, usage.completion_tokens);
+                accumulate(&mut self.usage.total_tokens, usage.total_tokens);
+                accumulate(
+                    &mut self.usage.cache_create_tokens,
+                    usage.cache_create_tokens,
+                );
+                accumulate(&mut self.usage.cache_read_tokens, usage.cache_read_tokens);
+            }
+            _ => {}
+        }
+    }
+
+    /// 会话传输统计，写入追踪记录 `response_metadata`
+    #[must_use]
+    pub fn metadata(&self, bytes_sent: usize) -> Value {
+        json!({
+            "websocket": {
+                "bytes_sent": bytes_sent,
+                "bytes_received": self.bytes_received,
+                "messages_received": self.messages_received,
+            }
+        })
+    }
+}
+
+fn accumulate(total: &mut Option<TokenCount>, value: Option<TokenCount>) {
+    if let Some(value) = value {
+        *total = Some(total.unwrap_or(0) + value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASK: [u8; 4] = [1, 2, 3, 4];
+
+    fn frame(fin: bool, opcode: u8, payload: &[u8], masked: bool) -> Vec<u8> {
+        let mut out = vec![if fin { 0x80 } else { 0x00 } | opcode];
+        let mask_bit = if masked { 0x80 } else { 0x00 };
+        match payload.len() {
+            len if len < 126 => out.push(mask_bit | u8::try_from(len).unwrap()),
+            len if len <= usize::from(u16::MAX) => {
+                out.push(mask_bit | 126);
+                out.extend_from_slice(&u16::try_from(len).unwrap().to_be_bytes());
+            }
+            len => {
+                out.push(mask_bit | 127);
+                out.extend_from_slice(&u64::try_from(len).unwrap().to_be_bytes());
+            }
+        }
+        if masked {
+            out.extend_from_slice(&MASK);
+            out.extend(payload.iter().enumerate().map(|(i, b)| b ^ MASK[i % 4]));
+        } else {
+            out.extend_from_slice(payload);
+        }
+        out
+    }
+
+    #[test]
+    fn detects_upgrade_request() {
+        let mut req =
+            RequestHeader::build("GET", b"/v1/realtime?model=gpt-4o-realtime", None).unwrap();
+        assert!(!is_websocket_upgrade(&req));
+
+        req.insert_header("upgrade", "WebSocket").unwrap();
+        req.insert_header("connection", "keep-alive, Upgrade")
+            .unwrap();
+        assert!(is_websocket_upgrade(&req));
+        assert_eq!(model_from_query(&req).as_deref(), Some("gpt-4o-realtime"));
+    }
+
+    #[test]
+    fn decodes_text_across_chunks_and_fragments() {
+        let mut bytes = frame(false, OPCODE_TEXT, b"{\"type\":", false);
+        bytes.extend(frame(true, 0x9, b"ping", false));
+        bytes.extend(frame(true, OPCODE_CONTINUATION, b"\"pong\"}", false));
+        bytes.extend(frame(true, 0x2, &[0, 1, 2], false));
+        bytes.extend(frame(true, OPCODE_TEXT, b"masked", true));
+
+        let mut decoder = WebSocketFrameDecoder::new();
+        let (head, tail) = bytes.split_at(5);
+        assert!(decoder.feed(head).is_empty());
+        assert_eq!(decoder.feed(tail), vec!["{\"type\":\"pong\"}", "masked"]);
+    }
+
+    #[test]
+    fn skips_oversized_frames() {
+        let big = vec![b'x'; MAX_MESSAGE_BYTES + 1];
+        let bytes = [
+            frame(true, OPCODE_TEXT, &big, false),
+            frame(true, OPCODE_TEXT, b"after", false),
+        ]
+        .concat();
+
+        let mut decoder = WebSocketFrameDecoder::new();
+        let mut messages = Vec::new();
+        for chunk in bytes.chunks(64 * 1024) {
+            messages.extend(decoder.feed(chunk));
+        }
+        assert_eq!(messages, vec!["after"]);
+    }
+
+    #[test]
+    fn session_records_model_and_bytes() {
+        let mut session = WebSocketSession::new();
+        let created = json!({"type": "session.created", "session": {"model": "gpt-4o-realtime"}});
+        let bytes = frame(true, OPCODE_TEXT, created.to_string().as_bytes(), false);
+
+        session.observe_upstream(&bytes, None);
+
+        assert_eq!(session.model.as_deref(), Some("gpt-4o-realtime"));
+        assert_eq!(session.messages_received, 1);
+        assert_eq!(
+            session.metadata(42),
+            json!({"websocket": {"bytes_sent": 42, "bytes_received": bytes.len(), "messages_received": 1}})
+        );
+    }
+}
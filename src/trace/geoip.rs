@@ -0,0 +1,104 @@
+//! # 客户端 IP 地理信息
+//!
+//! 按真实客户端 IP 查询国家与 ASN，写入追踪记录用于滥用分析。
+//! 数据库在启动时加载一次；未配置、文件缺失或无法解析时对应字段留空，不影响请求转发。
+
+use crate::config::GeoIpConfig;
+use crate::logging::{LogComponent, LogStage};
+use crate::{linfo, lwarn};
+use maxminddb::{Reader, geoip2};
+use std::net::IpAddr;
+
+/// 单个客户端 IP 的地理信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeoInfo {
+    /// ISO 3166-1 国家代码（如 `GB`）
+    pub country: Option<String>,
+    /// 自治系统号
+    pub asn: Option<i64>,
+}
+
+/// `MaxMind` 数据库查询器
+#[derive(Default)]
+pub struct GeoIpLookup {
+    country: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIpLookup {
+    /// 按配置加载数据库；加载失败的数据库记录告警后跳过
+    #[must_use]
+    pub fn open(config: &GeoIpConfig) -> Self {
+        Self {
+            country: config
+                .country_database
+                .as_deref()
+                .and_then(|path| open_reader("country", path)),
+            asn: config
+                .asn_database
+                .as_deref()
+                .and_then(|path| open_reader("asn", path)),
+        }
+    }
+
+    /// 是否有可用的数据库
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.country.is_some() || self.asn.is_some()
+    }
+
+    /// 查询客户端 IP；IP 无法解析或库中没有记录时对应字段为 `None`
+    #[must_use]
+    pub fn lookup(&self, client_ip: &str) -> GeoInfo {
+        let Ok(ip) = client_ip.trim().parse::<IpAddr>() else {
+            return GeoInfo::default();
+        };
+
+        let country = self
+            .country
+            .as_ref()
+            .and_then(|reader| reader.lookup::<geoip2::Country>(ip).ok().flatten())
+            .and_then(|record| record.country)
+            .and_then(|country| country.iso_code)
+            .map(str::to_string);
+        let asn = self
+            .asn
+            .as_ref()
+            .and_then(|reader| reader.lookup::<geoip2::Asn>(ip).ok().flatten())
+            .and_then(|record| record.autonomous_system_number)
+            .map(i64::from);
+
+        GeoInfo { country, asn }
+    }
+}
+
+fn open_reader(kind: &str, path: &str) -> Option<Reader<Vec<u8>>> {
+    match Reader::open_readfile(path) {
+        Ok(reader) => {
+            linfo!(
+                "system",
+                LogStage::Startup,
+                LogComponent::Tracing,
+                "geoip_database_loaded",
+                "GeoIP 数据库已加载",
+                kind = kind,
+                path = path,
+                database_type = %reader.metadata.database_type
+            );
+            Some(reader)
+        }
+        Err(err) => {
+            lwarn!(
+                "system",
+                LogStage::Startup,
+                LogComponent::Tracing,
+                "geoip_database_unavailable",
+                "GeoIP 数据库无法加载，跳过地理信息记录",
+                kind = kind,
+                path = path,
+                error = %err
+            );
+            None
+        }
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::error::Result;
 use crate::logging::{LogComponent, LogStage};
+use crate::trace::live::{LiveTraceEvent, LiveTraceHub, LiveTraceKind};
 use crate::types::{ProviderTypeId, TokenCount, ratio_as_f64};
 use crate::{ldebug, lerror, linfo, lwarn};
 use chrono::Utc;
@@ -11,6 +12,7 @@ use entity::proxy_tracing;
 use sea_orm::{
     ColumnTrait, DatabaseConnection, EntityTrait, NotSet, QueryFilter, QuerySelect, Set,
 };
+use serde::Serialize;
 use std::sync::Arc;
 
 /// `简化完成追踪参数`（`用于complete_trace函数`）
@@ -26,12 +28,14 @@ pub struct SimpleCompleteTraceParams {
 }
 
 /// 完整完成追踪参数
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CompleteTraceParams {
     pub status_code: u16,
     pub is_success: bool,
     pub tokens_prompt: Option<TokenCount>,
     pub tokens_completion: Option<TokenCount>,
+    /// Token 数由本地分词器估算（上游未返回用量）
+    pub tokens_estimated: bool,
     pub error_type: Option<String>,
     pub error_message: Option<String>,
     pub retry_count: Option<i32>,
@@ -39,6 +43,18 @@ pub struct CompleteTraceParams {
     pub cache_read_tokens: Option<TokenCount>,
     pub cost: Option<f64>,
     pub cost_currency: Option<String>,
+    /// 代理对请求所做的调整等附加信息
+    pub request_metadata: Option<serde_json::Value>,
+    /// 按配置从响应体中提取的字段
+    pub response_metadata: Option<serde_json::Value>,
+    /// 采集的请求体（未开启采集时为 `None`）
+    pub request_body: Option<String>,
+    /// 请求体字节数
+    pub request_bytes: Option<u64>,
+    /// 响应体字节数
+    pub response_bytes: Option<u64>,
+    /// 服务 API 的响应时间目标（毫秒），总耗时超过时标记 `sla_breached`
+    pub sla_target_ms: Option<u64>,
 }
 
 /// 开始追踪参数
@@ -52,6 +68,8 @@ pub struct StartTraceParams {
     pub method: String,
     pub path: Option<String>,
     pub client_ip: Option<String>,
+    pub client_country: Option<String>,
+    pub client_asn: Option<i64>,
     pub user_agent: Option<String>,
 }
 
@@ -63,6 +81,8 @@ pub struct StartTraceParams {
 pub struct ImmediateProxyTracer {
     /// 数据库连接
     db: Arc<DatabaseConnection>,
+    /// 实时追踪广播
+    live: LiveTraceHub,
 }
 
 impl ImmediateProxyTracer {
@@ -76,7 +96,16 @@ impl ImmediateProxyTracer {
             "Initializing immediate proxy tracer with all requests traced"
         );
 
-        Self { db }
+        Self {
+            db,
+            live: LiveTraceHub::new(),
+        }
+    }
+
+    /// 实时追踪广播（管理端 SSE 订阅）
+    #[must_use]
+    pub const fn live(&self) -> &LiveTraceHub {
+        &self.live
     }
 
     /// 开始追踪请求 - 立即写入数据库
@@ -84,6 +113,23 @@ impl ImmediateProxyTracer {
         // 强制追踪所有请求，移除配置开关
 
         let now = Utc::now().naive_utc();
+        let live_event = self.live.has_subscribers().then(|| LiveTraceEvent {
+            kind: LiveTraceKind::Started,
+            request_id: params.request_id.clone(),
+            user_id: params.user_id,
+            user_service_api_id: params.user_service_api_id,
+            provider_type_id: params.provider_type_id,
+            method: params.method.clone(),
+            path: params.path.clone(),
+            model_used: None,
+            status_code: None,
+            is_success: false,
+            tokens_total: None,
+            cost: None,
+            duration_ms: None,
+            error_type: None,
+            timestamp: Utc::now(),
+        });
 
         // 创建初始追踪记录
         let trace_record = proxy_tracing::ActiveModel {
@@ -95,6 +141,8 @@ impl ImmediateProxyTracer {
             method: Set(params.method),
             path: Set(params.path),
             client_ip: Set(params.client_ip),
+            client_country: Set(params.client_country),
+            client_asn: Set(params.client_asn),
             user_agent: Set(params.user_agent),
             start_time: Set(Some(now)),
             is_success: Set(false), // 默认失败，响应时更新
@@ -104,18 +152,25 @@ impl ImmediateProxyTracer {
             tokens_prompt: NotSet,
             tokens_completion: NotSet,
             tokens_total: NotSet,
+            tokens_estimated: NotSet,
             token_efficiency_ratio: NotSet,
             cache_create_tokens: NotSet,
             cache_read_tokens: NotSet,
+            request_bytes: NotSet,
+            response_bytes: NotSet,
             cost: NotSet,
             cost_currency: NotSet,
             model_used: NotSet,
             error_type: NotSet,
             error_message: NotSet,
             retry_count: Set(Some(0)),
+            request_metadata: NotSet,
+            response_metadata: NotSet,
+            request_body: NotSet,
             provider_type_id: Set(params.provider_type_id),
             end_time: NotSet,
             duration_ms: NotSet,
+            sla_breached: NotSet,
         };
 
         // 立即写入数据库
@@ -135,6 +190,10 @@ impl ImmediateProxyTracer {
             user_provider_key_id = ?params.user_provider_key_id
         );
 
+        if let Some(event) = live_event {
+            self.live.publish(event);
+        }
+
         Ok(())
     }
 
@@ -241,6 +300,7 @@ impl ImmediateProxyTracer {
             is_success: params.is_success,
             tokens_prompt: params.tokens_prompt,
             tokens_completion: params.tokens_completion,
+            tokens_estimated: false,
             error_type: params.error_type,
             error_message: params.error_message,
             retry_count: None,
@@ -248,6 +308,12 @@ impl ImmediateProxyTracer {
             cache_read_tokens: None,
             cost: None,
             cost_currency: None,
+            request_metadata: None,
+            response_metadata: None,
+            request_body: None,
+            request_bytes: None,
+            response_bytes: None,
+            sla_target_ms: None,
         };
         self.complete_trace_with_stats(&params.request_id, complete_params)
             .await
@@ -321,6 +387,7 @@ impl ImmediateProxyTracer {
         } else {
             None
         };
+        let sla_breached = sla_breached(duration_ms, params.sla_target_ms);
 
         // 构建完成更新模型
         let complete_model = proxy_tracing::ActiveModel {
@@ -328,19 +395,26 @@ impl ImmediateProxyTracer {
             is_success: Set(params.is_success),
             end_time: Set(Some(end_time)),
             duration_ms: Set(duration_ms),
+            sla_breached: Set(sla_breached),
             tokens_prompt: Set(params.tokens_prompt.and_then(|t| i32::try_from(t).ok())),
             tokens_completion: Set(params.tokens_completion.and_then(|t| i32::try_from(t).ok())),
             tokens_total: Set(tokens_total.and_then(|t| i32::try_from(t).ok())),
+            tokens_estimated: Set(params.tokens_estimated),
             token_efficiency_ratio: Set(token_efficiency_ratio),
             cache_create_tokens: Set(params
                 .cache_create_tokens
                 .and_then(|t| i32::try_from(t).ok())),
             cache_read_tokens: Set(params.cache_read_tokens.and_then(|t| i32::try_from(t).ok())),
+            request_bytes: Set(params.request_bytes.and_then(|b| i64::try_from(b).ok())),
+            response_bytes: Set(params.response_bytes.and_then(|b| i64::try_from(b).ok())),
             cost: Set(params.cost),
             cost_currency: Set(params.cost_currency),
             error_type: Set(params.error_type),
             error_message: Set(params.error_message),
             retry_count: Set(params.retry_count),
+            request_metadata: Set(params.request_metadata),
+            response_metadata: Set(params.response_metadata),
+            request_body: Set(params.request_body),
             ..Default::default()
         };
 
@@ -365,8 +439,10 @@ impl ImmediateProxyTracer {
                 cache_create_tokens = ?params.cache_create_tokens,
                 cache_read_tokens = ?params.cache_read_tokens,
                 duration_ms = ?duration_ms,
+                sla_breached = sla_breached,
                 rows_affected = update_result.rows_affected
             );
+            self.publish_completed(request_id).await;
         } else {
             lerror!(
                 request_id,
@@ -380,6 +456,32 @@ impl ImmediateProxyTracer {
         Ok(())
     }
 
+    /// 有订阅者时读取完成后的记录并广播摘要；失败只记录日志，不影响追踪写入
+    async fn publish_completed(&self, request_id: &str) {
+        if !self.live.has_subscribers() {
+            return;
+        }
+
+        match proxy_tracing::Entity::find()
+            .filter(proxy_tracing::Column::RequestId.eq(request_id))
+            .one(&*self.db)
+            .await
+        {
+            Ok(Some(record)) => self.live.publish(LiveTraceEvent::completed(record)),
+            Ok(None) => {}
+            Err(err) => {
+                lwarn!(
+                    request_id,
+                    LogStage::Response,
+                    LogComponent::Tracing,
+                    "live_trace_publish_failed",
+                    "读取完成的追踪记录失败，跳过实时推送",
+                    error = %err
+                );
+            }
+        }
+    }
+
     /// 查询进行中的请求（未完成的追踪记录）
     pub async fn get_active_requests(&self, limit: u64) -> Result<Vec<proxy_tracing::Model>> {
         let records = proxy_tracing::Entity::find()
@@ -419,14 +521,25 @@ impl ImmediateProxyTracer {
     }
 }
 
+/// 总耗时是否超过响应时间目标；未配置目标或耗时未知时不标记
+fn sla_breached(duration_ms: Option<i64>, target_ms: Option<u64>) -> bool {
+    match (duration_ms.and_then(|ms| u64::try_from(ms).ok()), target_ms) {
+        (Some(duration), Some(target)) => duration > target,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::trace::live::LiveTraceFilter;
     use chrono::Utc;
+    use futures::StreamExt;
     use migration::{Migrator, MigratorTrait};
     use sea_orm::{Database, EntityTrait, PaginatorTrait, Set};
     use serial_test::serial;
     use std::sync::Arc;
+    use std::time::Duration;
 
     async fn setup_test_db() -> Arc<DatabaseConnection> {
         let db = Database::connect("sqlite::memory:")
@@ -438,12 +551,7 @@ mod tests {
         Arc::new(db)
     }
 
-    #[tokio::test]
-    #[serial]
-    async fn test_immediate_trace_lifecycle() {
-        let db = setup_test_db().await;
-        let tracer = ImmediateProxyTracer::new(db.clone());
-
+    async fn seed_user_and_service_api(db: &DatabaseConnection) {
         // Insert a user record
         let user = entity::users::ActiveModel {
             id: Set(999), // 使用不同的ID避免冲突
@@ -457,10 +565,7 @@ mod tests {
             updated_at: Set(Utc::now().naive_utc()),
             ..Default::default()
         };
-        entity::users::Entity::insert(user)
-            .exec(&*db)
-            .await
-            .unwrap();
+        entity::users::Entity::insert(user).exec(db).await.unwrap();
 
         // Insert a user_service_api record
         let user_service_api = entity::user_service_apis::ActiveModel {
@@ -475,15 +580,14 @@ mod tests {
             ..Default::default()
         };
         entity::user_service_apis::Entity::insert(user_service_api)
-            .exec(&*db)
+            .exec(db)
             .await
             .unwrap();
+    }
 
-        let request_id = "test_immediate_12345".to_string();
-
-        // 开始追踪
-        let start_params = StartTraceParams {
-            request_id: request_id.clone(),
+    fn start_params(request_id: &str) -> StartTraceParams {
+        StartTraceParams {
+            request_id: request_id.to_string(),
             user_service_api_id: 999,
             user_id: Some(999),
             provider_type_id: Some(1),
@@ -491,10 +595,25 @@ mod tests {
             method: "POST".to_string(),
             path: Some("/v1/chat/completions".to_string()),
             client_ip: Some("127.0.0.1".to_string()),
+            client_country: None,
+            client_asn: None,
             user_agent: Some("test-client/1.0".to_string()),
-        };
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_immediate_trace_lifecycle() {
+        let db = setup_test_db().await;
+        let tracer = ImmediateProxyTracer::new(db.clone());
+
+        seed_user_and_service_api(&db).await;
+
+        let request_id = "test_immediate_12345".to_string();
+
+        // 开始追踪
         tracer
-            .start_trace(start_params)
+            .start_trace(start_params(&request_id))
             .await
             .expect("Failed to start trace");
 
@@ -528,4 +647,57 @@ mod tests {
 
         assert_eq!(count, 1, "Should have exactly one trace record");
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_completed_trace_delivered_to_live_subscriber() {
+        let db = setup_test_db().await;
+        let tracer = ImmediateProxyTracer::new(db.clone());
+        seed_user_and_service_api(&db).await;
+
+        let other_user = tracer.live().subscribe(LiveTraceFilter {
+            user_id: Some(1000),
+            ..LiveTraceFilter::default()
+        });
+        let subscriber = tracer.live().subscribe(LiveTraceFilter {
+            user_id: Some(999),
+            ..LiveTraceFilter::default()
+        });
+        futures::pin_mut!(subscriber, other_user);
+
+        let request_id = "test_live_12345";
+        tracer
+            .start_trace(start_params(request_id))
+            .await
+            .expect("Failed to start trace");
+        tracer
+            .complete_trace(SimpleCompleteTraceParams {
+                request_id: request_id.to_string(),
+                status_code: 200,
+                is_success: true,
+                tokens_prompt: Some(100),
+                tokens_completion: Some(50),
+                error_type: None,
+                error_message: None,
+            })
+            .await
+            .expect("Failed to complete trace");
+
+        // 未订阅进行中事件，收到的第一条即为完成事件
+        let event = tokio::time::timeout(Duration::from_secs(1), subscriber.next())
+            .await
+            .expect("live event not delivered")
+            .expect("live stream closed");
+        assert_eq!(event.kind, LiveTraceKind::Completed);
+        assert_eq!(event.request_id, request_id);
+        assert_eq!(event.status_code, Some(200));
+        assert_eq!(event.tokens_total, Some(150));
+
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), other_user.next())
+                .await
+                .is_err(),
+            "events of other users must be filtered out"
+        );
+    }
 }
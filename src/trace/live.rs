@@ -0,0 +1,134 @@
+//! # 实时追踪广播
+//!
+//! 追踪器在请求开始与完成时发布摘要事件，供管理端以 SSE 实时查看流量。
+//! 广播通道容量有限：订阅者消费过慢时只会丢失最旧的事件，不会阻塞追踪写入。
+
+use crate::types::ProviderTypeId;
+use chrono::{DateTime, Utc};
+use entity::proxy_tracing;
+use futures::Stream;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// 广播通道容量
+const LIVE_TRACE_CAPACITY: usize = 256;
+
+/// 实时追踪事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveTraceKind {
+    /// 请求开始（进行中）
+    Started,
+    /// 请求完成
+    Completed,
+}
+
+/// 实时追踪摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveTraceEvent {
+    pub kind: LiveTraceKind,
+    pub request_id: String,
+    pub user_id: Option<i32>,
+    pub user_service_api_id: i32,
+    pub provider_type_id: Option<ProviderTypeId>,
+    pub method: String,
+    pub path: Option<String>,
+    pub model_used: Option<String>,
+    pub status_code: Option<i32>,
+    pub is_success: bool,
+    pub tokens_total: Option<i32>,
+    pub cost: Option<f64>,
+    pub duration_ms: Option<i64>,
+    pub error_type: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl LiveTraceEvent {
+    /// 由已完成的追踪记录构造事件
+    #[must_use]
+    pub fn completed(record: proxy_tracing::Model) -> Self {
+        Self {
+            kind: LiveTraceKind::Completed,
+            request_id: record.request_id,
+            user_id: record.user_id,
+            user_service_api_id: record.user_service_api_id,
+            provider_type_id: record.provider_type_id,
+            method: record.method,
+            path: record.path,
+            model_used: record.model_used,
+            status_code: record.status_code,
+            is_success: record.is_success,
+            tokens_total: record.tokens_total,
+            cost: record.cost,
+            duration_ms: record.duration_ms,
+            error_type: record.error_type,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// 订阅过滤条件
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LiveTraceFilter {
+    pub user_id: Option<i32>,
+    pub provider_type_id: Option<ProviderTypeId>,
+    /// 是否同时推送进行中的请求
+    pub include_in_progress: bool,
+}
+
+impl LiveTraceFilter {
+    /// 判断事件是否满足过滤条件
+    #[must_use]
+    pub fn matches(&self, event: &LiveTraceEvent) -> bool {
+        (self.include_in_progress || event.kind == LiveTraceKind::Completed)
+            && self.user_id.is_none_or(|id| event.user_id == Some(id))
+            && self
+                .provider_type_id
+                .is_none_or(|id| event.provider_type_id == Some(id))
+    }
+}
+
+/// 实时追踪广播中心
+#[derive(Debug, Clone)]
+pub struct LiveTraceHub {
+    sender: broadcast::Sender<LiveTraceEvent>,
+}
+
+impl Default for LiveTraceHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveTraceHub {
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(LIVE_TRACE_CAPACITY);
+        Self { sender }
+    }
+
+    /// 当前是否有订阅者；没有订阅者时追踪器跳过事件构造
+    #[must_use]
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// 发布事件（无订阅者时直接丢弃）
+    pub fn publish(&self, event: LiveTraceEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// 订阅满足过滤条件的事件流；消费过慢时跳过被覆盖的事件
+    pub fn subscribe(&self, filter: LiveTraceFilter) -> impl Stream<Item = LiveTraceEvent> + use<> {
+        let receiver = self.sender.subscribe();
+        futures::stream::unfold(receiver, move |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if filter.matches(&event) => return Some((event, receiver)),
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
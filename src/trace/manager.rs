@@ -5,19 +5,120 @@ use std::sync::Arc;
 
 use crate::auth::api_key_usage_limit_service::ApiKeyUsageLimitService;
 use crate::collect::types::CollectedMetrics;
+use crate::config::MAX_CAPTURED_REQUEST_BODY_BYTES;
+use crate::error::tls::TlsError;
 use crate::logging::{LogComponent, LogStage, log_proxy_failure_details};
 use crate::proxy::ProxyContext;
+use crate::trace::geoip::{GeoInfo, GeoIpLookup};
 use crate::trace::immediate::{CompleteTraceParams, ImmediateProxyTracer, StartTraceParams};
+use crate::trace::sink::{TraceExporter, TraceRecord};
+use crate::trace::usage_counter::UsageCounter;
+use crate::trace::writer::TraceWriter;
 use crate::{error::Context, error::Result, linfo, lwarn};
 use flate2::read::GzDecoder;
-use pingora_core::Error as PingoraError;
+use pingora_core::{Error as PingoraError, ErrorSource, ErrorType};
 use serde_json::json;
 use std::io::Read;
 
+/// 响应下发过程中的连接中断类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamAbortKind {
+    /// 客户端在响应未结束时断开连接
+    ClientDisconnected,
+    /// 上游在响应未结束时断开连接或读写超时
+    UpstreamDisconnected,
+    /// 请求超过总时长上限被代理中断
+    TotalTimeout,
+    /// 流式响应的估算费用超过单请求费用上限被代理中断
+    CostLimitReached,
+}
+
+impl StreamAbortKind {
+    /// 客户端断开时写入追踪记录的状态码（沿用 nginx 的 499 约定）
+    pub const CLIENT_CLOSED_STATUS: u16 = 499;
+
+    /// 根据上下文与 Pingora 错误判断是否属于“响应已开始后中断”
+    ///
+    /// 超过单请求费用上限、请求总时长上限的失败分别视为 `CostLimitReached`、`TotalTimeout`；其余情况仅当上游已返回成功状态头后
+    /// 发生连接类错误时才视为中断，响应开始前的失败仍按普通失败请求处理。
+    #[must_use]
+    pub fn detect(ctx: &ProxyContext, error: Option<&PingoraError>) -> Option<Self> {
+        let err = error?;
+        if ctx.response.cost_limit_reached {
+            return Some(Self::CostLimitReached);
+        }
+        if ctx.total_timeout_exceeded() {
+            return Some(Self::TotalTimeout);
+        }
+        if ctx
+            .response
+            .details
+            .status_code
+            .is_none_or(|status| status >= 400)
+        {
+            return None;
+        }
+        if !matches!(
+            err.etype,
+            ErrorType::ConnectionClosed
+                | ErrorType::ReadError
+                | ErrorType::WriteError
+                | ErrorType::ReadTimedout
+                | ErrorType::WriteTimedout
+                | ErrorType::H2Error
+        ) {
+            return None;
+        }
+        match err.esource {
+            ErrorSource::Downstream => Some(Self::ClientDisconnected),
+            _ => Some(Self::UpstreamDisconnected),
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ClientDisconnected => "client_disconnected",
+            Self::UpstreamDisconnected => "upstream_disconnected",
+            Self::TotalTimeout => "total_timeout",
+            Self::CostLimitReached => "cost_limit_reached",
+        }
+    }
+
+    /// 追踪记录中使用的状态码
+    #[must_use]
+    pub const fn status_code(self) -> u16 {
+        match self {
+            Self::ClientDisconnected => Self::CLIENT_CLOSED_STATUS,
+            Self::UpstreamDisconnected => 502,
+            Self::TotalTimeout => 504,
+            Self::CostLimitReached => 402,
+        }
+    }
+
+    const fn source(self) -> &'static str {
+        match self {
+            Self::ClientDisconnected => "downstream",
+            Self::UpstreamDisconnected => "upstream",
+            Self::TotalTimeout | Self::CostLimitReached => "proxy",
+        }
+    }
+}
+
 /// 统一的请求追踪管理器
 pub struct TraceManager {
     tracer: Option<Arc<ImmediateProxyTracer>>,
     rate_limiter: Arc<ApiKeyUsageLimitService>,
+    /// 请求体采集上限（字节），`None` 表示不采集
+    request_body_capture_limit: Option<usize>,
+    /// 异步写入队列，`None` 时请求结束时同步写库
+    writer: Option<Arc<TraceWriter>>,
+    /// 外部输出，`None` 时只写数据库
+    exporter: Option<Arc<TraceExporter>>,
+    /// 客户端 IP 地理信息查询，`None` 时不记录国家与 ASN
+    geoip: Option<Arc<GeoIpLookup>>,
+    /// 服务 API 用量内存计数，`None` 时不计数
+    usage_counter: Option<Arc<UsageCounter>>,
 }
 
 impl TraceManager {
@@ -29,7 +130,80 @@ impl TraceManager {
         Self {
             tracer,
             rate_limiter,
+            request_body_capture_limit: None,
+            writer: None,
+            exporter: None,
+            geoip: None,
+            usage_counter: None,
+        }
+    }
+
+    /// 设置请求体采集上限，`None` 表示不采集
+    #[must_use]
+    pub const fn with_request_body_capture(mut self, limit: Option<usize>) -> Self {
+        self.request_body_capture_limit = limit;
+        self
+    }
+
+    /// 设置异步写入队列，完成记录入队后立即返回
+    #[must_use]
+    pub fn with_writer(mut self, writer: Arc<TraceWriter>) -> Self {
+        self.writer = Some(writer);
+        self
+    }
+
+    /// 设置外部输出，完成记录同时推送到外部接收端
+    #[must_use]
+    pub fn with_exporter(mut self, exporter: Arc<TraceExporter>) -> Self {
+        self.exporter = Some(exporter);
+        self
+    }
+
+    /// 设置客户端 IP 地理信息查询，没有可用数据库时忽略
+    #[must_use]
+    pub fn with_geoip(mut self, geoip: Arc<GeoIpLookup>) -> Self {
+        self.geoip = geoip.is_enabled().then_some(geoip);
+        self
+    }
+
+    /// 设置服务 API 用量计数器
+    #[must_use]
+    pub fn with_usage_counter(mut self, counter: Arc<UsageCounter>) -> Self {
+        self.usage_counter = Some(counter);
+        self
+    }
+
+    /// 写入完成记录：配置了写入队列时入队，否则同步写库；配置了外部输出时同时推送
+    async fn complete_trace(
+        &self,
+        tracer: &ImmediateProxyTracer,
+        request_id: &str,
+        params: CompleteTraceParams,
+    ) -> Result<()> {
+        if let Some(exporter) = &self.exporter {
+            exporter.send(TraceRecord::new(request_id, params.clone()));
+        }
+        if let Some(writer) = &self.writer {
+            writer.enqueue(request_id, params).await;
+            return Ok(());
         }
+        tracer.complete_trace_with_stats(request_id, params).await
+    }
+
+    /// 采集发往上游的请求体（策略改写后的版本）；超过上限、缓存被截断或非 UTF-8 时不采集
+    ///
+    /// 命中定向调试采集的请求不受 `trace.capture_request_body` 限制，按最大上限采集。
+    fn captured_request_body(&self, ctx: &ProxyContext) -> Option<String> {
+        let limit = if ctx.trace.debug_capture {
+            MAX_CAPTURED_REQUEST_BODY_BYTES
+        } else {
+            self.request_body_capture_limit?
+        };
+        let body = &ctx.request.body;
+        if body.is_empty() || ctx.request.body_truncated || body.len() > limit {
+            return None;
+        }
+        std::str::from_utf8(body).ok().map(str::to_string)
     }
 
     /// 开始追踪（认证成功后调用）
@@ -50,6 +224,11 @@ impl TraceManager {
             return Ok(false);
         };
 
+        let geo = match (&self.geoip, client_ip.as_deref()) {
+            (Some(geoip), Some(ip)) => geoip.lookup(ip),
+            _ => GeoInfo::default(),
+        };
+
         let params = StartTraceParams {
             request_id: request_id.to_string(),
             user_service_api_id,
@@ -59,6 +238,8 @@ impl TraceManager {
             method: method.to_string(),
             path,
             client_ip,
+            client_country: geo.country,
+            client_asn: geo.asn,
             user_agent,
         };
 
@@ -139,35 +320,42 @@ impl TraceManager {
                 return Ok(());
             };
 
-            tracer
-                .complete_trace_with_stats(
+            self.complete_trace(
+                tracer,
+                &metrics.request_id,
+                CompleteTraceParams {
+                    status_code: metrics.status_code,
+                    is_success: true,
+                    tokens_prompt: metrics.usage.prompt_tokens,
+                    tokens_completion: metrics.usage.completion_tokens,
+                    tokens_estimated: metrics.usage.estimated,
+                    error_type: None,
+                    error_message: None,
+                    retry_count: i32::try_from(ctx.control.retry.retry_count).ok(),
+                    cache_create_tokens: metrics.usage.cache_create_tokens,
+                    cache_read_tokens: metrics.usage.cache_read_tokens,
+                    cost: metrics.cost.value,
+                    cost_currency: metrics.cost.currency.clone(),
+                    request_metadata: request_metadata(ctx),
+                    response_metadata: metrics.response_metadata.clone(),
+                    request_body: self.captured_request_body(ctx),
+                    request_bytes: Some(metrics.request_bytes),
+                    response_bytes: Some(metrics.response_bytes),
+                    sla_target_ms: sla_target_ms(ctx),
+                },
+            )
+            .await
+            .inspect_err(|err| {
+                err.log();
+                lwarn!(
                     &metrics.request_id,
-                    CompleteTraceParams {
-                        status_code: metrics.status_code,
-                        is_success: true,
-                        tokens_prompt: metrics.usage.prompt_tokens,
-                        tokens_completion: metrics.usage.completion_tokens,
-                        error_type: None,
-                        error_message: None,
-                        retry_count: i32::try_from(ctx.control.retry.retry_count).ok(),
-                        cache_create_tokens: metrics.usage.cache_create_tokens,
-                        cache_read_tokens: metrics.usage.cache_read_tokens,
-                        cost: metrics.cost.value,
-                        cost_currency: metrics.cost.currency.clone(),
-                    },
-                )
-                .await
-                .inspect_err(|err| {
-                    err.log();
-                    lwarn!(
-                        &metrics.request_id,
-                        LogStage::Error,
-                        LogComponent::Tracing,
-                        "success_trace_complete_failed",
-                        "成功请求追踪完成失败",
-                        error = format!("{:?}", err)
-                    );
-                })?;
+                    LogStage::Error,
+                    LogComponent::Tracing,
+                    "success_trace_complete_failed",
+                    "成功请求追踪完成失败",
+                    error = format!("{:?}", err)
+                );
+            })?;
         }
         self.update_rate_limits(metrics, ctx).await;
         Ok(())
@@ -206,13 +394,21 @@ impl TraceManager {
             },
             |err| {
                 let err_type = format!("{:?}", err.etype);
-                let structured = json!({
+                let mut structured = json!({
                     "source": "pingora",
                     "kind": "pingora_error",
                     "error_type": err_type,
                     "message": err.to_string()
-                })
-                .to_string();
+                });
+                if let Some(tls) = TlsError::from_pingora(err) {
+                    structured["kind"] = json!("tls_error");
+                    structured["tls"] = json!({
+                        "reason": tls.kind(),
+                        "description": tls.description(),
+                        "detail": tls.to_string()
+                    });
+                }
+                let structured = structured.to_string();
                 (Some(err_type), Some(structured))
             },
         );
@@ -222,6 +418,7 @@ impl TraceManager {
             is_success: false,
             tokens_prompt: metrics.and_then(|m| m.usage.prompt_tokens),
             tokens_completion: metrics.and_then(|m| m.usage.completion_tokens),
+            tokens_estimated: metrics.is_some_and(|m| m.usage.estimated),
             error_type,
             error_message,
             retry_count: i32::try_from(ctx.control.retry.retry_count).ok(),
@@ -229,12 +426,17 @@ impl TraceManager {
             cache_read_tokens: metrics.and_then(|m| m.usage.cache_read_tokens),
             cost: metrics.and_then(|m| m.cost.value),
             cost_currency: metrics.and_then(|m| m.cost.currency.clone()),
+            request_metadata: request_metadata(ctx),
+            response_metadata: metrics.and_then(|m| m.response_metadata.clone()),
+            request_body: self.captured_request_body(ctx),
+            request_bytes: Some(metrics.map_or_else(|| ctx.request_bytes(), |m| m.request_bytes)),
+            response_bytes: Some(
+                metrics.map_or_else(|| ctx.response_bytes(), |m| m.response_bytes),
+            ),
+            sla_target_ms: sla_target_ms(ctx),
         };
 
-        if let Err(e) = tracer
-            .complete_trace_with_stats(&ctx.request_id, params)
-            .await
-        {
+        if let Err(e) = self.complete_trace(tracer, &ctx.request_id, params).await {
             lwarn!(
                 &ctx.request_id,
                 LogStage::Error,
@@ -246,6 +448,79 @@ impl TraceManager {
         }
     }
 
+    /// 记录中途中断的流式请求
+    ///
+    /// 以中断类型作为状态落库，并保留中断前已解析到的部分用量，避免追踪记录长期处于进行中。
+    pub async fn record_aborted(
+        &self,
+        metrics: &CollectedMetrics,
+        kind: StreamAbortKind,
+        error: Option<&PingoraError>,
+        ctx: &ProxyContext,
+    ) {
+        lwarn!(
+            &ctx.request_id,
+            LogStage::ResponseFailure,
+            LogComponent::Tracing,
+            "stream_aborted",
+            "响应下发过程中连接中断",
+            abort_kind = kind.as_str(),
+            upstream_status = ?ctx.response.details.status_code,
+            response_body_size = ctx.response.body_received_size,
+            tokens_prompt = ?metrics.usage.prompt_tokens,
+            tokens_completion = ?metrics.usage.completion_tokens
+        );
+
+        if ctx.is_trace_started()
+            && let Some(tracer) = &self.tracer
+        {
+            let error_message = json!({
+                "source": kind.source(),
+                "kind": kind.as_str(),
+                "error_type": error.map(|err| format!("{:?}", err.etype)),
+                "message": error.map(ToString::to_string),
+                "upstream_status": ctx.response.details.status_code,
+                "response_bytes": ctx.response.body_received_size
+            })
+            .to_string();
+
+            let params = CompleteTraceParams {
+                status_code: kind.status_code(),
+                is_success: false,
+                tokens_prompt: metrics.usage.prompt_tokens,
+                tokens_completion: metrics.usage.completion_tokens,
+                tokens_estimated: metrics.usage.estimated,
+                error_type: Some(kind.as_str().to_string()),
+                error_message: Some(error_message),
+                retry_count: i32::try_from(ctx.control.retry.retry_count).ok(),
+                cache_create_tokens: metrics.usage.cache_create_tokens,
+                cache_read_tokens: metrics.usage.cache_read_tokens,
+                cost: metrics.cost.value,
+                cost_currency: metrics.cost.currency.clone(),
+                request_metadata: request_metadata(ctx),
+                response_metadata: metrics.response_metadata.clone(),
+                request_body: self.captured_request_body(ctx),
+                request_bytes: Some(metrics.request_bytes),
+                response_bytes: Some(metrics.response_bytes),
+                sla_target_ms: sla_target_ms(ctx),
+            };
+
+            if let Err(e) = self.complete_trace(tracer, &ctx.request_id, params).await {
+                lwarn!(
+                    &ctx.request_id,
+                    LogStage::Error,
+                    LogComponent::Tracing,
+                    "aborted_trace_complete_failed",
+                    "中断请求追踪完成失败",
+                    error = format!("{:?}", e)
+                );
+            }
+        }
+
+        // 中断前已消耗的用量同样计入限额
+        self.update_rate_limits(metrics, ctx).await;
+    }
+
     async fn update_rate_limits(&self, metrics: &CollectedMetrics, ctx: &ProxyContext) {
         let Some(user_api) = ctx.routing.user_service_api.as_ref() else {
             return;
@@ -254,6 +529,14 @@ impl TraceManager {
         self.update_request_cache(metrics, user_api).await;
         self.update_token_cache(metrics, user_api).await;
         self.update_cost_cache(metrics, user_api).await;
+
+        if let Some(counter) = &self.usage_counter {
+            let tokens = metrics
+                .usage
+                .total_tokens
+                .map_or(0, |tokens| i64::try_from(tokens).unwrap_or(i64::MAX));
+            counter.record(user_api.id, tokens, metrics.cost.value.unwrap_or(0.0));
+        }
     }
 
     async fn update_request_cache(
@@ -337,6 +620,31 @@ impl TraceManager {
     }
 }
 
+/// 汇总代理对请求所做的调整与密钥选择依据，均为空时不写入
+fn request_metadata(ctx: &ProxyContext) -> Option<serde_json::Value> {
+    let mut metadata = serde_json::Map::new();
+    if !ctx.request.parameter_adjustments.is_empty() {
+        metadata.insert(
+            "parameter_adjustments".to_string(),
+            json!(ctx.request.parameter_adjustments),
+        );
+    }
+    if let Some(selection_debug) = &ctx.trace.selection_debug {
+        metadata.insert("selection_debug".to_string(), selection_debug.clone());
+    }
+    (!metadata.is_empty()).then_some(serde_json::Value::Object(metadata))
+}
+
+/// 服务 API 配置的响应时间目标（毫秒），未配置或非正数时不标记超时
+fn sla_target_ms(ctx: &ProxyContext) -> Option<u64> {
+    ctx.routing
+        .user_service_api
+        .as_ref()
+        .and_then(|api| api.sla_target_ms)
+        .and_then(|ms| u64::try_from(ms).ok())
+        .filter(|ms| *ms > 0)
+}
+
 fn decode_response_body(ctx: &ProxyContext) -> Option<String> {
     if ctx.response.body.is_empty() {
         return None;
@@ -360,3 +668,208 @@ fn decode_response_body(ctx: &ProxyContext) -> Option<String> {
 
     Some(String::from_utf8_lossy(raw_bytes).into_owned())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheManager;
+    use crate::collect::types::{CollectedCost, TokenUsageMetrics};
+    use crate::proxy::response_transform_service::ResponseTransformService;
+    use crate::utils::request_id;
+    use chrono::Utc;
+    use entity::proxy_tracing;
+    use migration::{Migrator, MigratorTrait};
+    use pingora_http::ResponseHeader;
+    use sea_orm::{ColumnTrait, Database, DatabaseConnection, EntityTrait, QueryFilter, Set};
+    use serial_test::serial;
+
+    async fn setup_test_db() -> Arc<DatabaseConnection> {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to test database");
+        Migrator::up(&db, None)
+            .await
+            .expect("Failed to run migrations");
+
+        let now = Utc::now().naive_utc();
+        entity::users::Entity::insert(entity::users::ActiveModel {
+            id: Set(1101),
+            username: Set("trace_abort_user".to_string()),
+            password_hash: Set("...".to_string()),
+            email: Set("trace_abort@test.com".to_string()),
+            salt: Set("salt".to_string()),
+            is_admin: Set(false),
+            is_active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .exec(&db)
+        .await
+        .unwrap();
+        entity::user_service_apis::Entity::insert(entity::user_service_apis::ActiveModel {
+            id: Set(1101),
+            user_id: Set(1101),
+            provider_type_id: Set(1),
+            api_key: Set("test-api-key-1101".to_string()),
+            name: Set(Some("Abort API".to_string())),
+            is_active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .exec(&db)
+        .await
+        .unwrap();
+
+        Arc::new(db)
+    }
+
+    fn streaming_ctx(request_id: &str) -> ProxyContext {
+        let mut ctx = ProxyContext {
+            request_id: request_id.to_string(),
+            ..Default::default()
+        };
+        ctx.mark_trace_started();
+        ctx.response.details.status_code = Some(200);
+        ctx.response.is_sse = true;
+        let chunk = b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n";
+        ctx.response.body.extend_from_slice(chunk);
+        ctx.response.body_received_size = chunk.len();
+        ctx
+    }
+
+    #[test]
+    fn detect_ignores_errors_before_response_started() {
+        let ctx = ProxyContext::default();
+        let err = PingoraError::new_down(ErrorType::ConnectionClosed);
+        assert_eq!(StreamAbortKind::detect(&ctx, Some(err.as_ref())), None);
+
+        let ctx = streaming_ctx("req-detect");
+        let err = PingoraError::new_up(ErrorType::ReadTimedout);
+        assert_eq!(
+            StreamAbortKind::detect(&ctx, Some(err.as_ref())),
+            Some(StreamAbortKind::UpstreamDisconnected)
+        );
+        assert_eq!(StreamAbortKind::detect(&ctx, None), None);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn client_disconnect_mid_stream_finalizes_trace() {
+        let db = setup_test_db().await;
+        let tracer = Arc::new(ImmediateProxyTracer::new(db.clone()));
+        let rate_limiter = Arc::new(ApiKeyUsageLimitService::new(
+            Arc::new(CacheManager::memory_only()),
+            db.clone(),
+        ));
+        let manager = TraceManager::new(Some(tracer.clone()), rate_limiter);
+
+        let request_id = "req-client-abort";
+        manager
+            .start_trace(
+                request_id,
+                1101,
+                Some(1101),
+                Some(1),
+                None,
+                "POST",
+                Some("/v1/chat/completions".to_string()),
+                None,
+                None,
+            )
+            .await
+            .expect("start trace");
+
+        let ctx = streaming_ctx(request_id);
+        let err = PingoraError::new_down(ErrorType::ConnectionClosed);
+        let kind = StreamAbortKind::detect(&ctx, Some(err.as_ref())).expect("abort detected");
+        assert_eq!(kind, StreamAbortKind::ClientDisconnected);
+
+        let metrics = CollectedMetrics {
+            request_id: request_id.to_string(),
+            user_id: Some(1101),
+            user_service_api_id: Some(1101),
+            provider_type_id: Some(1),
+            model: Some("gpt-4o".to_string()),
+            usage: TokenUsageMetrics {
+                prompt_tokens: Some(12),
+                completion_tokens: Some(3),
+                total_tokens: Some(15),
+                ..Default::default()
+            },
+            cost: CollectedCost::default(),
+            duration_ms: 10,
+            status_code: 200,
+            request_bytes: 0,
+            response_bytes: 0,
+            response_metadata: None,
+        };
+        manager
+            .record_aborted(&metrics, kind, Some(err.as_ref()), &ctx)
+            .await;
+
+        let record = proxy_tracing::Entity::find()
+            .filter(proxy_tracing::Column::RequestId.eq(request_id))
+            .one(&*db)
+            .await
+            .unwrap()
+            .expect("trace record");
+        assert!(record.end_time.is_some());
+        assert!(!record.is_success);
+        assert_eq!(record.status_code, Some(499));
+        assert_eq!(record.error_type.as_deref(), Some("client_disconnected"));
+        assert_eq!(record.tokens_prompt, Some(12));
+        assert_eq!(record.tokens_completion, Some(3));
+        assert!(tracer.get_active_requests(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn response_request_id_matches_persisted_trace() {
+        let db = setup_test_db().await;
+        let tracer = Arc::new(ImmediateProxyTracer::new(db.clone()));
+        let rate_limiter = Arc::new(ApiKeyUsageLimitService::new(
+            Arc::new(CacheManager::memory_only()),
+            db.clone(),
+        ));
+        let manager = TraceManager::new(Some(tracer), rate_limiter);
+
+        let ctx = ProxyContext::new_request();
+        assert!(request_id::is_valid(&ctx.request_id), "{}", ctx.request_id);
+
+        let mut response = ResponseHeader::build(200, None).unwrap();
+        response
+            .insert_header(request_id::REQUEST_ID_HEADER, "upstream-req-1")
+            .unwrap();
+        ResponseTransformService::apply_request_id_header(&mut response, &ctx).unwrap();
+        let header_id = response.headers[request_id::REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        manager
+            .start_trace(
+                &ctx.request_id,
+                1101,
+                Some(1101),
+                Some(1),
+                None,
+                "POST",
+                Some("/v1/chat/completions".to_string()),
+                None,
+                None,
+            )
+            .await
+            .expect("start trace");
+
+        let record = proxy_tracing::Entity::find()
+            .filter(proxy_tracing::Column::RequestId.eq(header_id.as_str()))
+            .one(&*db)
+            .await
+            .unwrap()
+            .expect("trace record");
+        assert_eq!(record.request_id, header_id);
+        assert!(request_id::is_valid(&record.request_id));
+    }
+}
@@ -1,9 +1,26 @@
+pub mod geoip;
 pub mod immediate;
+pub mod live;
 pub mod manager;
+pub mod shadow_comparison;
+pub mod sink;
+pub mod spend_anomaly;
+pub mod usage_counter;
+pub mod writer;
 
+pub use geoip::{GeoInfo, GeoIpLookup};
 pub use immediate::ImmediateProxyTracer;
-pub use manager::TraceManager;
+pub use live::{LiveTraceEvent, LiveTraceFilter, LiveTraceHub};
+pub use manager::{StreamAbortKind, TraceManager};
+pub use shadow_comparison::{
+    DeltaDistribution, ShadowComparisonRecord, ShadowComparisonStore, ShadowDiffSummary,
+    ShadowRequestDiff,
+};
+pub use sink::{DatabaseTraceSink, HttpTraceSink, TraceExporter, TraceRecord, TraceSink};
+pub use spend_anomaly::SpendAnomalyDetectionTask;
 use std::sync::Arc;
+pub use usage_counter::{UsageCounter, UsageDelta};
+pub use writer::TraceWriter;
 
 /// 追踪系统入口（TraceSystem）
 ///
@@ -20,9 +37,21 @@ impl ApiKeyTraceService {
         Self { tracer }
     }
 
+    /// 获取即时写入追踪器（非可选）
+    #[must_use]
+    pub fn tracer(&self) -> Arc<ImmediateProxyTracer> {
+        Arc::clone(&self.tracer)
+    }
+
     /// 获取即时写入追踪器
     #[must_use]
     pub fn immediate_tracer(&self) -> Option<Arc<ImmediateProxyTracer>> {
         Some(self.tracer.clone())
     }
+
+    /// 获取实时追踪广播
+    #[must_use]
+    pub fn live_traces(&self) -> LiveTraceHub {
+        self.tracer.live().clone()
+    }
 }
@@ -0,0 +1,241 @@
+//! # 影子路由对比记录
+//!
+//! 影子路由把同一请求同时发往主服务商与影子服务商，影子响应不返回给客户端，
+//! 两边的结果写入 `shadow_comparisons`。管理端 `GET /api/shadow/diffs` 按时间范围汇总：
+//! - 状态码一致率：只统计影子请求收到响应的记录；
+//! - Token 差值与延迟差值：影子减主请求，两边都有数值的记录才计入，给出最值、均值与分位数；
+//! - 逐请求差异：供排查单个请求。
+
+use crate::error::{Context, Result};
+use crate::types::conversion::{ratio_as_f64, ratio_as_percentage};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use entity::shadow_comparisons;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// 一次影子请求的对比结果
+#[derive(Debug, Clone, Default)]
+pub struct ShadowComparisonRecord {
+    pub request_id: String,
+    pub user_service_api_id: Option<i32>,
+    pub model: Option<String>,
+    pub primary_provider_type_id: i32,
+    pub shadow_provider_type_id: i32,
+    pub primary_status_code: u16,
+    /// 影子请求未收到响应时为空
+    pub shadow_status_code: Option<u16>,
+    pub primary_tokens_total: Option<i32>,
+    pub shadow_tokens_total: Option<i32>,
+    pub primary_duration_ms: i64,
+    pub shadow_duration_ms: Option<i64>,
+    pub shadow_error: Option<String>,
+}
+
+/// 对比记录读写
+#[derive(Clone)]
+pub struct ShadowComparisonStore {
+    db: Arc<DatabaseConnection>,
+}
+
+impl ShadowComparisonStore {
+    #[must_use]
+    pub const fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// 写入一条对比记录
+    pub async fn record(
+        &self,
+        record: ShadowComparisonRecord,
+        created_at: DateTime<Utc>,
+    ) -> Result<shadow_comparisons::Model> {
+        shadow_comparisons::ActiveModel {
+            request_id: Set(record.request_id),
+            user_service_api_id: Set(record.user_service_api_id),
+            model: Set(record.model),
+            primary_provider_type_id: Set(record.primary_provider_type_id),
+            shadow_provider_type_id: Set(record.shadow_provider_type_id),
+            primary_status_code: Set(i32::from(record.primary_status_code)),
+            shadow_status_code: Set(record.shadow_status_code.map(i32::from)),
+            primary_tokens_total: Set(record.primary_tokens_total),
+            shadow_tokens_total: Set(record.shadow_tokens_total),
+            primary_duration_ms: Set(record.primary_duration_ms),
+            shadow_duration_ms: Set(record.shadow_duration_ms),
+            shadow_error: Set(record.shadow_error),
+            created_at: Set(created_at.naive_utc()),
+            ..Default::default()
+        }
+        .insert(self.db.as_ref())
+        .await
+        .context("Failed to record shadow comparison")
+    }
+
+    /// 查询 `[start, end)` 内的对比记录，按时间倒序
+    pub async fn list(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<shadow_comparisons::Model>> {
+        shadow_comparisons::Entity::find()
+            .filter(shadow_comparisons::Column::CreatedAt.gte(start))
+            .filter(shadow_comparisons::Column::CreatedAt.lt(end))
+            .order_by_desc(shadow_comparisons::Column::CreatedAt)
+            .order_by_desc(shadow_comparisons::Column::Id)
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to load shadow comparisons")
+    }
+}
+
+/// 差值分布（影子减主请求）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DeltaDistribution {
+    /// 计入的记录数
+    pub samples: u64,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    pub mean: Option<f64>,
+    pub p50: Option<i64>,
+    pub p90: Option<i64>,
+    pub p99: Option<i64>,
+}
+
+impl DeltaDistribution {
+    /// 按最近秩法计算分位数
+    #[must_use]
+    pub fn from_deltas(mut deltas: Vec<i64>) -> Self {
+        if deltas.is_empty() {
+            return Self::default();
+        }
+        deltas.sort_unstable();
+        let samples = u64::try_from(deltas.len()).unwrap_or(u64::MAX);
+        let sum: i64 = deltas
+            .iter()
+            .fold(0, |acc, delta| acc.saturating_add(*delta));
+        let mean = ratio_as_f64(sum.unsigned_abs(), samples)
+            .map(|mean| if sum < 0 { -mean } else { mean });
+        let percentile = |pct: u64| {
+            let rank = (pct * samples).div_ceil(100).max(1);
+            usize::try_from(rank - 1)
+                .ok()
+                .and_then(|index| deltas.get(index).copied())
+        };
+        Self {
+            samples,
+            min: deltas.first().copied(),
+            max: deltas.last().copied(),
+            mean,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+        }
+    }
+}
+
+/// 时间范围内的汇总指标
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShadowDiffSummary {
+    pub total_requests: u64,
+    /// 影子请求收到响应的记录数
+    pub compared_requests: u64,
+    /// 影子请求未收到响应的记录数
+    pub shadow_failures: u64,
+    pub status_matches: u64,
+    /// 状态码一致率（百分比，分母为 `compared_requests`）
+    pub status_match_rate: f64,
+    pub token_delta: DeltaDistribution,
+    pub latency_delta_ms: DeltaDistribution,
+}
+
+impl ShadowDiffSummary {
+    #[must_use]
+    pub fn from_rows(rows: &[shadow_comparisons::Model]) -> Self {
+        let diffs: Vec<ShadowRequestDiff> = rows.iter().map(ShadowRequestDiff::from).collect();
+        let count = |predicate: fn(&ShadowRequestDiff) -> bool| {
+            u64::try_from(diffs.iter().filter(|diff| predicate(diff)).count()).unwrap_or(u64::MAX)
+        };
+        let compared_requests = count(|diff| diff.shadow_status_code.is_some());
+        let status_matches = count(|diff| diff.status_match == Some(true));
+        Self {
+            total_requests: u64::try_from(diffs.len()).unwrap_or(u64::MAX),
+            compared_requests,
+            shadow_failures: count(|diff| diff.shadow_status_code.is_none()),
+            status_matches,
+            status_match_rate: ratio_as_percentage(status_matches, compared_requests),
+            token_delta: DeltaDistribution::from_deltas(
+                diffs.iter().filter_map(|diff| diff.token_delta).collect(),
+            ),
+            latency_delta_ms: DeltaDistribution::from_deltas(
+                diffs
+                    .iter()
+                    .filter_map(|diff| diff.latency_delta_ms)
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// 单个请求的差异
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShadowRequestDiff {
+    pub id: i32,
+    pub request_id: String,
+    pub user_service_api_id: Option<i32>,
+    pub model: Option<String>,
+    pub primary_provider_type_id: i32,
+    pub shadow_provider_type_id: i32,
+    pub primary_status_code: i32,
+    pub shadow_status_code: Option<i32>,
+    /// 影子请求未收到响应时为空
+    pub status_match: Option<bool>,
+    pub primary_tokens_total: Option<i32>,
+    pub shadow_tokens_total: Option<i32>,
+    pub token_delta: Option<i64>,
+    pub primary_duration_ms: i64,
+    pub shadow_duration_ms: Option<i64>,
+    pub latency_delta_ms: Option<i64>,
+    pub shadow_error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl ShadowRequestDiff {
+    /// 状态码不一致或影子请求失败
+    #[must_use]
+    pub fn is_mismatch(&self) -> bool {
+        self.status_match != Some(true)
+    }
+}
+
+impl From<&shadow_comparisons::Model> for ShadowRequestDiff {
+    fn from(row: &shadow_comparisons::Model) -> Self {
+        Self {
+            id: row.id,
+            request_id: row.request_id.clone(),
+            user_service_api_id: row.user_service_api_id,
+            model: row.model.clone(),
+            primary_provider_type_id: row.primary_provider_type_id,
+            shadow_provider_type_id: row.shadow_provider_type_id,
+            primary_status_code: row.primary_status_code,
+            shadow_status_code: row.shadow_status_code,
+            status_match: row
+                .shadow_status_code
+                .map(|status| status == row.primary_status_code),
+            primary_tokens_total: row.primary_tokens_total,
+            shadow_tokens_total: row.shadow_tokens_total,
+            token_delta: row
+                .primary_tokens_total
+                .zip(row.shadow_tokens_total)
+                .map(|(primary, shadow)| i64::from(shadow) - i64::from(primary)),
+            primary_duration_ms: row.primary_duration_ms,
+            shadow_duration_ms: row.shadow_duration_ms,
+            latency_delta_ms: row
+                .shadow_duration_ms
+                .map(|shadow| shadow.saturating_sub(row.primary_duration_ms)),
+            shadow_error: row.shadow_error.clone(),
+            created_at: row.created_at,
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! # 追踪记录输出目标
+//!
+//! [`TraceSink`] 抽象了请求完成记录的去向：[`DatabaseTraceSink`] 写入 `proxy_tracing`（追踪写入队列
+//! 使用），[`HttpTraceSink`] 以 JSON 批量 POST 到外部接收端。外部接收端由 [`TraceExporter`] 驱动：
+//! 记录先进入有界队列，攒满 `batch_size` 或等待 `flush_interval_ms` 后整批推送，失败按指数退避重试，
+//! 重试耗尽或队列写满时丢弃并计数，不影响请求与数据库写入。
+
+use crate::config::HttpTraceSinkConfig;
+use crate::error::Result;
+use crate::logging::{LogComponent, LogStage};
+use crate::trace::immediate::{CompleteTraceParams, ImmediateProxyTracer};
+use crate::{linfo, lwarn};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant};
+
+/// 停止任务时等待剩余记录推送完的最长时间
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 等待队列的结果
+enum NextRecord {
+    Record(TraceRecord),
+    TimedOut,
+    Closed,
+}
+
+/// 一条请求完成记录
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceRecord {
+    pub request_id: String,
+    /// 记录生成时间
+    pub completed_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub params: CompleteTraceParams,
+}
+
+impl TraceRecord {
+    #[must_use]
+    pub fn new(request_id: &str, params: CompleteTraceParams) -> Self {
+        Self {
+            request_id: request_id.to_string(),
+            completed_at: Utc::now(),
+            params,
+        }
+    }
+}
+
+/// 追踪记录输出目标
+#[async_trait::async_trait]
+pub trait TraceSink: Send + Sync {
+    /// 输出目标名称（用于日志）
+    fn name(&self) -> &'static str;
+
+    /// 写入一批记录；返回错误时由调用方决定是否重试整批
+    async fn write_batch(&self, records: &[TraceRecord]) -> Result<()>;
+}
+
+/// 写入数据库的输出目标
+pub struct DatabaseTraceSink {
+    tracer: Arc<ImmediateProxyTracer>,
+}
+
+impl DatabaseTraceSink {
+    #[must_use]
+    pub const fn new(tracer: Arc<ImmediateProxyTracer>) -> Self {
+        Self { tracer }
+    }
+}
+
+#[async_trait::async_trait]
+impl TraceSink for DatabaseTraceSink {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+
+    /// 逐条写库；单条失败只记录日志，不影响同批其他记录
+    async fn write_batch(&self, records: &[TraceRecord]) -> Result<()> {
+        for record in records {
+            if let Err(err) = self
+                .tracer
+                .complete_trace_with_stats(&record.request_id, record.params.clone())
+                .await
+            {
+                lwarn!(
+                    &record.request_id,
+                    LogStage::Db,
+                    LogComponent::Tracing,
+                    "trace_write_failed",
+                    "追踪记录写入失败",
+                    error = %err
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 以 `{"records": [...]}` 批量 POST 到外部接收端的输出目标
+pub struct HttpTraceSink {
+    client: reqwest::Client,
+    config: HttpTraceSinkConfig,
+}
+
+impl HttpTraceSink {
+    #[must_use]
+    pub fn new(config: HttpTraceSinkConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TraceSink for HttpTraceSink {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn write_batch(&self, records: &[TraceRecord]) -> Result<()> {
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .timeout(self.config.timeout())
+            .json(&json!({ "records": records }));
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// 外部输出任务：批量推送并在失败时重试
+pub struct TraceExporter {
+    sink: Arc<dyn TraceSink>,
+    config: HttpTraceSinkConfig,
+    sender: mpsc::Sender<TraceRecord>,
+    receiver: Mutex<Option<mpsc::Receiver<TraceRecord>>>,
+    exported: AtomicU64,
+    dropped: AtomicU64,
+    shutdown: Notify,
+    handle: RwLock<Option<JoinHandle<()>>>,
+}
+
+impl TraceExporter {
+    /// 按配置创建 HTTP 外部输出，未配置时返回 `None`
+    #[must_use]
+    pub fn from_config(config: &crate::config::TraceSinkConfig) -> Option<Self> {
+        let http = config.http.clone()?;
+        Some(Self::new(Arc::new(HttpTraceSink::new(http.clone())), http))
+    }
+
+    /// 使用指定输出目标创建，批量与重试参数取自 `config`
+    #[must_use]
+    pub fn new(sink: Arc<dyn TraceSink>, config: HttpTraceSinkConfig) -> Self {
+        let (sender, receiver) = mpsc::channel(config.capacity);
+        Self {
+            sink,
+            config,
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            exported: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            shutdown: Notify::new(),
+            handle: RwLock::new(None),
+        }
+    }
+
+    /// 已推送成功的记录数
+    #[must_use]
+    pub fn exported(&self) -> u64 {
+        self.exported.load(Ordering::Relaxed)
+    }
+
+    /// 因队列写满或重试耗尽丢弃的记录数
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 放入一条记录，队列已满时丢弃并计数，不等待
+    pub fn send(&self, record: TraceRecord) {
+        if let Err(err) = self.sender.try_send(record) {
+            let record = err.into_inner();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            lwarn!(
+                &record.request_id,
+                LogStage::BackgroundTask,
+                LogComponent::Tracing,
+                "trace_export_dropped",
+                "追踪外部输出队列已满，丢弃记录",
+                sink = self.sink.name(),
+                dropped = self.dropped()
+            );
+        }
+    }
+
+    /// 启动后台推送任务
+    pub async fn start(self: &Arc<Self>) -> Result<()> {
+        let mut handle = self.handle.write().await;
+        if handle.is_some() {
+            return Ok(());
+        }
+        let Some(receiver) = self
+            .receiver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        else {
+            return Ok(());
+        };
+
+        let exporter = Arc::clone(self);
+        *handle = Some(tokio::spawn(async move { exporter.run(receiver).await }));
+        drop(handle);
+        linfo!(
+            "system",
+            LogStage::Startup,
+            LogComponent::Tracing,
+            "trace_exporter_started",
+            "追踪外部输出任务已启动",
+            sink = self.sink.name(),
+            batch_size = self.config.batch_size,
+            flush_interval_ms = self.config.flush_interval_ms
+        );
+        Ok(())
+    }
+
+    /// 停止任务：在限定时间内推送完剩余记录
+    pub async fn stop(&self) {
+        let handle = { self.handle.write().await.take() };
+        let Some(mut handle) = handle else {
+            return;
+        };
+        self.shutdown.notify_one();
+        if time::timeout(SHUTDOWN_FLUSH_TIMEOUT, &mut handle)
+            .await
+            .is_err()
+        {
+            lwarn!(
+                "system",
+                LogStage::Shutdown,
+                LogComponent::Tracing,
+                "trace_exporter_flush_timeout",
+                "停止时追踪外部输出未推送完",
+                sink = self.sink.name()
+            );
+            handle.abort();
+            let _ = handle.await;
+        }
+    }
+
+    async fn run(&self, mut receiver: mpsc::Receiver<TraceRecord>) {
+        loop {
+            let mut batch = Vec::with_capacity(self.config.batch_size);
+            // 收到第一条记录后开始计时，攒满一批或到时即推送
+            let mut deadline = None;
+            let closing = loop {
+                tokio::select! {
+                    next = Self::next_record(&mut receiver, deadline) => match next {
+                        NextRecord::Record(record) => {
+                            batch.push(record);
+                            if batch.len() >= self.config.batch_size {
+                                break false;
+                            }
+                            if deadline.is_none() {
+                                deadline = Some(Instant::now() + self.config.flush_interval());
+                            }
+                        }
+                        NextRecord::TimedOut => break false,
+                        NextRecord::Closed => break true,
+                    },
+                    () = self.shutdown.notified() => break true,
+                }
+            };
+
+            if !batch.is_empty() {
+                self.deliver(batch).await;
+            }
+            if closing {
+                self.drain(&mut receiver).await;
+                return;
+            }
+        }
+    }
+
+    /// 等待下一条记录，设置了截止时间时到时返回
+    async fn next_record(
+        receiver: &mut mpsc::Receiver<TraceRecord>,
+        deadline: Option<Instant>,
+    ) -> NextRecord {
+        let next = match deadline {
+            Some(deadline) => match time::timeout_at(deadline, receiver.recv()).await {
+                Ok(next) => next,
+                Err(_) => return NextRecord::TimedOut,
+            },
+            None => receiver.recv().await,
+        };
+        next.map_or(NextRecord::Closed, NextRecord::Record)
+    }
+
+    /// 停止时推送队列中剩余的记录
+    async fn drain(&self, receiver: &mut mpsc::Receiver<TraceRecord>) {
+        let mut batch = Vec::with_capacity(self.config.batch_size);
+        while let Ok(record) = receiver.try_recv() {
+            batch.push(record);
+            if batch.len() == self.config.batch_size {
+                self.deliver(std::mem::take(&mut batch)).await;
+            }
+        }
+        if !batch.is_empty() {
+            self.deliver(batch).await;
+        }
+    }
+
+    /// 推送一批记录，失败按指数退避重试，重试耗尽后丢弃整批
+    async fn deliver(&self, batch: Vec<TraceRecord>) {
+        let count = u64::try_from(batch.len()).unwrap_or(u64::MAX);
+        let mut backoff = self.config.retry_backoff();
+        let mut attempt = 0;
+        loop {
+            match self.sink.write_batch(&batch).await {
+                Ok(()) => {
+                    self.exported.fetch_add(count, Ordering::Relaxed);
+                    return;
+                }
+                Err(err) if attempt < self.config.max_retries => {
+                    attempt += 1;
+                    lwarn!(
+                        "system",
+                        LogStage::BackgroundTask,
+                        LogComponent::Tracing,
+                        "trace_export_retry",
+                        "追踪外部输出推送失败，稍后重试",
+                        sink = self.sink.name(),
+                        records = batch.len(),
+                        attempt = attempt,
+                        backoff_ms = backoff.as_millis(),
+                        error = %err
+                    );
+                    time::sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                }
+                Err(err) => {
+                    self.dropped.fetch_add(count, Ordering::Relaxed);
+                    lwarn!(
+                        "system",
+                        LogStage::BackgroundTask,
+                        LogComponent::Tracing,
+                        "trace_export_failed",
+                        "追踪外部输出重试耗尽，丢弃本批记录",
+                        sink = self.sink.name(),
+                        records = batch.len(),
+                        error = %err
+                    );
+                    return;
+                }
+            }
+        }
+    }
+}
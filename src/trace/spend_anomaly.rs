@@ -0,0 +1,265 @@
+//! # 消费异常检测任务
+//!
+//! 周期性地从 `proxy_tracing` 按用户汇总消费：
+//! - 当前消费：最近一小时内的费用合计；
+//! - 基线：当前一小时之前 `baseline_hours` 小时内的费用合计除以小时数（无请求的小时按 0 计入）。
+//!
+//! 当前消费不低于 `min_hourly_spend` 且超过基线的 `multiplier` 倍时写入 `spend_anomaly_flags`，
+//! 记录告警日志并在配置了 webhook 时推送。同一用户在一个检测窗口内只标记一次。
+
+use crate::app::task_scheduler::{TaskRunRecorder, track_run};
+use crate::config::SpendAnomalyConfig;
+use crate::error::{Context, Result};
+use crate::logging::{LogComponent, LogStage};
+use crate::{lerror, linfo, lwarn};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use entity::{proxy_tracing, spend_anomaly_flags};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, Set,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time;
+
+/// webhook 推送超时
+const WEBHOOK_TIMEOUT: StdDuration = StdDuration::from_secs(5);
+
+/// 当前消费是否超过基线的倍数阈值
+#[must_use]
+pub const fn is_anomalous(
+    current_spend: f64,
+    baseline_hourly_spend: f64,
+    config: &SpendAnomalyConfig,
+) -> bool {
+    current_spend >= config.min_hourly_spend
+        && current_spend > baseline_hourly_spend * config.multiplier
+}
+
+/// 消费异常检测后台任务
+#[derive(Clone)]
+pub struct SpendAnomalyDetectionTask {
+    db: Arc<DatabaseConnection>,
+    config: SpendAnomalyConfig,
+    client: reqwest::Client,
+    handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    recorder: Option<TaskRunRecorder>,
+}
+
+impl SpendAnomalyDetectionTask {
+    #[must_use]
+    pub fn new(db: Arc<DatabaseConnection>, config: SpendAnomalyConfig) -> Self {
+        Self {
+            db,
+            config,
+            client: reqwest::Client::new(),
+            handle: Arc::new(RwLock::new(None)),
+            recorder: None,
+        }
+    }
+
+    /// 每轮检测后记录心跳与执行历史
+    #[must_use]
+    pub fn with_run_recorder(mut self, recorder: TaskRunRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// 启动任务：按配置间隔执行检测
+    pub async fn start(&self) -> Result<()> {
+        if !self.config.enabled {
+            linfo!(
+                "system",
+                LogStage::Startup,
+                LogComponent::Statistics,
+                "spend_anomaly_detection_disabled",
+                "消费异常检测未启用"
+            );
+            return Ok(());
+        }
+        if self.handle.read().await.is_some() {
+            return Ok(());
+        }
+
+        let task = self.clone();
+        let handle = tokio::spawn(async move {
+            let mut ticker = time::interval(task.config.check_interval());
+            loop {
+                ticker.tick().await;
+                if let Err(err) = track_run(task.recorder.as_ref(), task.detect(Utc::now())).await {
+                    lerror!(
+                        "system",
+                        LogStage::BackgroundTask,
+                        LogComponent::Statistics,
+                        "spend_anomaly_detection_failed",
+                        "消费异常检测失败",
+                        error = %err
+                    );
+                }
+            }
+        });
+
+        *self.handle.write().await = Some(handle);
+        linfo!(
+            "system",
+            LogStage::Startup,
+            LogComponent::Statistics,
+            "spend_anomaly_detection_started",
+            "消费异常检测任务已启动",
+            interval_secs = self.config.check_interval_secs,
+            baseline_hours = self.config.baseline_hours,
+            multiplier = self.config.multiplier
+        );
+        Ok(())
+    }
+
+    /// 停止任务
+    pub async fn stop(&self) {
+        let handle = { self.handle.write().await.take() };
+
+        if let Some(handle) = handle {
+            handle.abort();
+            let _ = handle.await;
+        }
+    }
+
+    /// 以 `now` 为窗口终点执行一次检测，返回本次新写入的标记
+    pub async fn detect(&self, now: DateTime<Utc>) -> Result<Vec<spend_anomaly_flags::Model>> {
+        let window_end = now.naive_utc();
+        let window_start = window_end - Duration::hours(1);
+        let baseline_start = window_start - Duration::hours(i64::from(self.config.baseline_hours));
+        let baseline_hours = f64::from(self.config.baseline_hours);
+
+        let current = self.spend_by_user(window_start, window_end).await?;
+        if current.is_empty() {
+            return Ok(Vec::new());
+        }
+        let baseline = self.spend_by_user(baseline_start, window_start).await?;
+        let already_flagged: HashSet<i32> = spend_anomaly_flags::Entity::find()
+            .select_only()
+            .column(spend_anomaly_flags::Column::UserId)
+            .filter(spend_anomaly_flags::Column::CreatedAt.gte(window_start))
+            .into_tuple::<i32>()
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to load recent spend anomaly flags")?
+            .into_iter()
+            .collect();
+
+        let mut users: Vec<_> = current.into_iter().collect();
+        users.sort_unstable_by_key(|(user_id, _)| *user_id);
+
+        let mut flags = Vec::new();
+        for (user_id, current_spend) in users {
+            let baseline_hourly_spend =
+                baseline.get(&user_id).copied().unwrap_or_default() / baseline_hours;
+            if already_flagged.contains(&user_id)
+                || !is_anomalous(current_spend, baseline_hourly_spend, &self.config)
+            {
+                continue;
+            }
+
+            let flag = spend_anomaly_flags::ActiveModel {
+                user_id: Set(user_id),
+                window_start: Set(window_start),
+                window_end: Set(window_end),
+                current_spend: Set(current_spend),
+                baseline_hourly_spend: Set(baseline_hourly_spend),
+                multiplier: Set(self.config.multiplier),
+                acknowledged_at: Set(None),
+                created_at: Set(window_end),
+                ..Default::default()
+            }
+            .insert(self.db.as_ref())
+            .await
+            .context("Failed to insert spend anomaly flag")?;
+
+            lwarn!(
+                "system",
+                LogStage::BackgroundTask,
+                LogComponent::Statistics,
+                "spend_anomaly_flagged",
+                "用户小时消费超过基线阈值",
+                user_id = user_id,
+                current_spend = current_spend,
+                baseline_hourly_spend = baseline_hourly_spend,
+                multiplier = self.config.multiplier
+            );
+            self.notify(&flag).await;
+            flags.push(flag);
+        }
+        Ok(flags)
+    }
+
+    /// 按用户汇总 `[start, end)` 时间段内的费用
+    async fn spend_by_user(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<HashMap<i32, f64>> {
+        let rows = proxy_tracing::Entity::find()
+            .select_only()
+            .column(proxy_tracing::Column::UserId)
+            .column_as(proxy_tracing::Column::Cost.sum(), "total_cost")
+            .filter(proxy_tracing::Column::UserId.is_not_null())
+            .filter(proxy_tracing::Column::CreatedAt.gte(start))
+            .filter(proxy_tracing::Column::CreatedAt.lt(end))
+            .group_by(proxy_tracing::Column::UserId)
+            .into_tuple::<(Option<i32>, Option<f64>)>()
+            .all(self.db.as_ref())
+            .await
+            .context("Failed to aggregate spend by user")?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(user_id, cost)| Some((user_id?, cost.unwrap_or_default())))
+            .collect())
+    }
+
+    /// 推送异常标记到 webhook；失败只记录日志，不影响检测结果
+    async fn notify(&self, flag: &spend_anomaly_flags::Model) {
+        let Some(url) = self.config.webhook_url.as_deref() else {
+            return;
+        };
+        let payload = serde_json::json!({
+            "event": "spend_anomaly",
+            "flag": flag,
+        });
+        let result = self
+            .client
+            .post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        if let Err(err) = result {
+            lwarn!(
+                "system",
+                LogStage::BackgroundTask,
+                LogComponent::Statistics,
+                "spend_anomaly_webhook_failed",
+                "消费异常 webhook 推送失败",
+                flag_id = flag.id,
+                error = %err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anomaly_requires_multiplier_and_minimum_spend() {
+        let config = SpendAnomalyConfig::default();
+        assert!(is_anomalous(10.0, 1.0, &config));
+        assert!(!is_anomalous(3.0, 1.0, &config));
+        // 低于最小消费时即使没有基线也不标记
+        assert!(!is_anomalous(0.5, 0.0, &config));
+        assert!(is_anomalous(1.0, 0.0, &config));
+    }
+}
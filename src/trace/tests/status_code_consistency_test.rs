@@ -88,6 +88,8 @@ mod tests {
             method: "POST".to_string(),
             path: Some("/v1/chat/completions".to_string()),
             client_ip: Some("127.0.0.1".to_string()),
+            client_country: None,
+            client_asn: None,
             user_agent: Some("test-client".to_string()),
         };
 
@@ -126,6 +128,8 @@ mod tests {
             method: "POST".to_string(),
             path: Some("/v1/chat/completions".to_string()),
             client_ip: Some("127.0.0.1".to_string()),
+            client_country: None,
+            client_asn: None,
             user_agent: Some("test-client".to_string()),
         };
 
@@ -164,6 +168,8 @@ mod tests {
             method: "POST".to_string(),
             path: Some("/v1/chat/completions".to_string()),
             client_ip: Some("127.0.0.1".to_string()),
+            client_country: None,
+            client_asn: None,
             user_agent: Some("test-client".to_string()),
         };
 
@@ -215,6 +221,8 @@ mod tests {
             method: "POST".to_string(),
             path: Some("/v1/chat/completions".to_string()),
             client_ip: Some("127.0.0.1".to_string()),
+            client_country: None,
+            client_asn: None,
             user_agent: Some("test-client".to_string()),
         };
 
@@ -268,6 +276,8 @@ mod tests {
             method: "POST".to_string(),
             path: Some("/v1/chat/completions".to_string()),
             client_ip: Some("127.0.0.1".to_string()),
+            client_country: None,
+            client_asn: None,
             user_agent: Some("test-client".to_string()),
         };
 
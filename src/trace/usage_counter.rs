@@ -0,0 +1,287 @@
+//! # 服务 API 用量计数
+//!
+//! 请求结束时把请求数、token 数与费用累加到内存计数器，后台任务按间隔或累计更新次数
+//! 把增量批量写入 `api_key_usage_counters`（按服务 API、按 UTC 日期汇总）。
+//! 写库失败时增量合并回计数器，下次写入时重试；停止任务时写入剩余计数。
+
+use crate::app::task_scheduler::{TaskRunRecorder, track_run};
+use crate::config::UsageCounterConfig;
+use crate::error::{Context, Result};
+use crate::logging::{LogComponent, LogStage};
+use crate::{linfo, lwarn};
+use chrono::{NaiveDate, Utc};
+use entity::api_key_usage_counters;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::{Mutex as AsyncMutex, Notify, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time;
+
+/// 单个服务 API 单日尚未写库的用量增量
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageDelta {
+    pub requests: i64,
+    pub tokens: i64,
+    pub cost: f64,
+}
+
+impl UsageDelta {
+    fn merge(&mut self, other: Self) {
+        self.requests = self.requests.saturating_add(other.requests);
+        self.tokens = self.tokens.saturating_add(other.tokens);
+        self.cost += other.cost;
+    }
+}
+
+/// 计数键：服务 API ID 与 UTC 日期
+type CounterKey = (i32, NaiveDate);
+
+/// 内存用量计数器与后台写入任务
+pub struct UsageCounter {
+    db: Arc<DatabaseConnection>,
+    config: UsageCounterConfig,
+    counters: Mutex<HashMap<CounterKey, UsageDelta>>,
+    /// 上次写入以来的更新次数
+    updates: AtomicUsize,
+    flush_requested: Notify,
+    /// 串行化写库，停止任务时不会与进行中的定期写入交错
+    flush_lock: AsyncMutex<()>,
+    handle: RwLock<Option<JoinHandle<()>>>,
+    recorder: Option<TaskRunRecorder>,
+}
+
+impl UsageCounter {
+    #[must_use]
+    pub fn new(db: Arc<DatabaseConnection>, config: UsageCounterConfig) -> Self {
+        Self {
+            db,
+            config,
+            counters: Mutex::new(HashMap::new()),
+            updates: AtomicUsize::new(0),
+            flush_requested: Notify::new(),
+            flush_lock: AsyncMutex::new(()),
+            handle: RwLock::new(None),
+            recorder: None,
+        }
+    }
+
+    /// 每次定期写入后记录心跳与执行历史
+    #[must_use]
+    pub fn with_run_recorder(mut self, recorder: TaskRunRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// 是否启用用量计数
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 累加一次请求的用量；累计更新次数达到阈值时唤醒后台任务提前写入
+    pub fn record(&self, user_service_api_id: i32, tokens: i64, cost: f64) {
+        if !self.config.enabled {
+            return;
+        }
+        let key = (user_service_api_id, Utc::now().date_naive());
+        self.lock_counters()
+            .entry(key)
+            .or_default()
+            .merge(UsageDelta {
+                requests: 1,
+                tokens,
+                cost,
+            });
+        if self.updates.fetch_add(1, Ordering::AcqRel) + 1 >= self.config.flush_max_updates {
+            self.flush_requested.notify_one();
+        }
+    }
+
+    /// 指定服务 API 尚未写库的用量（跨日期合计）
+    #[must_use]
+    pub fn pending(&self, user_service_api_id: i32) -> UsageDelta {
+        self.lock_counters()
+            .iter()
+            .filter(|((api_id, _), _)| *api_id == user_service_api_id)
+            .fold(UsageDelta::default(), |mut total, (_, delta)| {
+                total.merge(*delta);
+                total
+            })
+    }
+
+    /// 上次写入以来的更新次数
+    #[must_use]
+    pub fn pending_updates(&self) -> usize {
+        self.updates.load(Ordering::Acquire)
+    }
+
+    /// 把当前增量写入数据库，返回写入的计数行数
+    ///
+    /// 写入失败时增量合并回计数器，不会丢失。
+    pub async fn flush(&self) -> Result<usize> {
+        let _guard = self.flush_lock.lock().await;
+        let batch = {
+            let mut counters = self.lock_counters();
+            self.updates.store(0, Ordering::Release);
+            std::mem::take(&mut *counters)
+        };
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        match self.write_batch(&batch).await {
+            Ok(()) => Ok(batch.len()),
+            Err(err) => {
+                self.restore(batch);
+                Err(err)
+            }
+        }
+    }
+
+    /// 启动后台写入任务
+    pub async fn start(self: &Arc<Self>) -> Result<()> {
+        if !self.config.enabled {
+            linfo!(
+                "system",
+                LogStage::Startup,
+                LogComponent::Statistics,
+                "usage_counter_disabled",
+                "用量计数未启用"
+            );
+            return Ok(());
+        }
+        if self.handle.read().await.is_some() {
+            return Ok(());
+        }
+
+        let counter = Arc::clone(self);
+        let handle = tokio::spawn(async move { counter.run().await });
+
+        *self.handle.write().await = Some(handle);
+        linfo!(
+            "system",
+            LogStage::Startup,
+            LogComponent::Statistics,
+            "usage_counter_started",
+            "用量计数写入任务已启动",
+            flush_interval_secs = self.config.flush_interval_secs,
+            flush_max_updates = self.config.flush_max_updates
+        );
+        Ok(())
+    }
+
+    /// 停止任务并写入剩余计数
+    pub async fn stop(&self) {
+        let handle = { self.handle.write().await.take() };
+        if let Some(handle) = handle {
+            // 等待进行中的写入结束后再中止任务，避免已取出的增量随任务一起丢弃
+            let guard = self.flush_lock.lock().await;
+            handle.abort();
+            let _ = handle.await;
+            drop(guard);
+        }
+
+        if let Err(err) = self.flush().await {
+            lwarn!(
+                "system",
+                LogStage::Shutdown,
+                LogComponent::Statistics,
+                "usage_counter_final_flush_failed",
+                "停止时用量计数写入失败",
+                error = %err
+            );
+        }
+    }
+
+    async fn run(&self) {
+        let mut ticker = time::interval(self.config.flush_interval());
+        // interval 的第一次 tick 立即完成
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                () = self.flush_requested.notified() => {}
+            }
+            if let Err(err) = track_run(self.recorder.as_ref(), self.flush()).await {
+                lwarn!(
+                    "system",
+                    LogStage::Db,
+                    LogComponent::Statistics,
+                    "usage_counter_flush_failed",
+                    "用量计数写入失败，将在下次写入时重试",
+                    error = %err
+                );
+            }
+        }
+    }
+
+    async fn write_batch(&self, batch: &HashMap<CounterKey, UsageDelta>) -> Result<()> {
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to begin usage counter transaction")?;
+        let now = Utc::now().naive_utc();
+
+        for (&(user_service_api_id, usage_date), delta) in batch {
+            let existing = api_key_usage_counters::Entity::find()
+                .filter(api_key_usage_counters::Column::UserServiceApiId.eq(user_service_api_id))
+                .filter(api_key_usage_counters::Column::UsageDate.eq(usage_date))
+                .one(&txn)
+                .await
+                .context("Failed to load usage counter")?;
+
+            if let Some(row) = existing {
+                let request_count = row.request_count.saturating_add(delta.requests);
+                let total_tokens = row.total_tokens.saturating_add(delta.tokens);
+                let total_cost = row.total_cost + delta.cost;
+                let mut active: api_key_usage_counters::ActiveModel = row.into();
+                active.request_count = Set(request_count);
+                active.total_tokens = Set(total_tokens);
+                active.total_cost = Set(total_cost);
+                active.updated_at = Set(now);
+                active
+                    .update(&txn)
+                    .await
+                    .context("Failed to update usage counter")?;
+            } else {
+                api_key_usage_counters::ActiveModel {
+                    user_service_api_id: Set(user_service_api_id),
+                    usage_date: Set(usage_date),
+                    request_count: Set(delta.requests),
+                    total_tokens: Set(delta.tokens),
+                    total_cost: Set(delta.cost),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await
+                .context("Failed to insert usage counter")?;
+            }
+        }
+
+        txn.commit()
+            .await
+            .context("Failed to commit usage counters")
+    }
+
+    fn restore(&self, batch: HashMap<CounterKey, UsageDelta>) {
+        let restored = batch.len();
+        let mut counters = self.lock_counters();
+        for (key, delta) in batch {
+            counters.entry(key).or_default().merge(delta);
+        }
+        drop(counters);
+        self.updates.fetch_add(restored, Ordering::AcqRel);
+    }
+
+    fn lock_counters(&self) -> MutexGuard<'_, HashMap<CounterKey, UsageDelta>> {
+        self.counters.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
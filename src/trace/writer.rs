@@ -0,0 +1,254 @@
+//! # 追踪异步写入
+//!
+//! 请求结束时把完成参数放入有界队列后立即返回，后台任务按入队顺序交给输出目标
+//! （默认为数据库，见 [`DatabaseTraceSink`]），请求延迟不再受数据库写入延迟影响。
+//! 队列写满时按 [`TraceOverflowPolicy`] 丢弃最早的记录或短暂等待空位，丢弃的记录单独计数。
+
+use crate::config::{TraceOverflowPolicy, TraceWriterConfig};
+use crate::error::Result;
+use crate::logging::{LogComponent, LogStage};
+use crate::trace::immediate::{CompleteTraceParams, ImmediateProxyTracer};
+use crate::trace::sink::{DatabaseTraceSink, TraceRecord, TraceSink};
+use crate::{linfo, lwarn};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant};
+
+/// 停止任务时等待队列写完的最长时间
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 进程启动以来因队列写满丢弃的追踪记录数
+static DROPPED_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// 入队结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnqueueOutcome {
+    /// 已入队
+    Queued,
+    /// 已入队，为腾出空位丢弃了最早排队的一条记录
+    DroppedOldest,
+    /// 等待空位超时，本条记录被丢弃
+    Dropped,
+}
+
+/// 追踪写入队列与后台写入任务
+pub struct TraceWriter {
+    sink: Arc<dyn TraceSink>,
+    config: TraceWriterConfig,
+    queue: Mutex<VecDeque<TraceRecord>>,
+    /// 已入队但尚未写完的记录数（含正在写入的一条）
+    pending: AtomicUsize,
+    dropped: AtomicU64,
+    item_ready: Notify,
+    space_ready: Notify,
+    idle: Notify,
+    handle: RwLock<Option<JoinHandle<()>>>,
+}
+
+impl TraceWriter {
+    /// 写入数据库的队列
+    #[must_use]
+    pub fn new(tracer: Arc<ImmediateProxyTracer>, config: TraceWriterConfig) -> Self {
+        Self::with_sink(Arc::new(DatabaseTraceSink::new(tracer)), config)
+    }
+
+    /// 写入指定输出目标的队列
+    #[must_use]
+    pub fn with_sink(sink: Arc<dyn TraceSink>, config: TraceWriterConfig) -> Self {
+        Self {
+            sink,
+            queue: Mutex::new(VecDeque::with_capacity(config.capacity)),
+            config,
+            pending: AtomicUsize::new(0),
+            dropped: AtomicU64::new(0),
+            item_ready: Notify::new(),
+            space_ready: Notify::new(),
+            idle: Notify::new(),
+            handle: RwLock::new(None),
+        }
+    }
+
+    /// 是否启用异步写入
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 当前排队中的记录数（不含正在写入的一条）
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.lock_queue().len()
+    }
+
+    /// 本队列丢弃的记录数
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 启动后台写入任务
+    pub async fn start(self: &Arc<Self>) -> Result<()> {
+        if !self.config.enabled {
+            linfo!(
+                "system",
+                LogStage::Startup,
+                LogComponent::Tracing,
+                "trace_writer_disabled",
+                "追踪写入队列未启用，请求结束时同步写库"
+            );
+            return Ok(());
+        }
+        if self.handle.read().await.is_some() {
+            return Ok(());
+        }
+
+        let writer = Arc::clone(self);
+        let handle = tokio::spawn(async move { writer.run().await });
+
+        *self.handle.write().await = Some(handle);
+        linfo!(
+            "system",
+            LogStage::Startup,
+            LogComponent::Tracing,
+            "trace_writer_started",
+            "追踪写入任务已启动",
+            capacity = self.config.capacity,
+            overflow_policy = ?self.config.overflow_policy
+        );
+        Ok(())
+    }
+
+    /// 停止任务：先在限定时间内写完排队中的记录
+    pub async fn stop(&self) {
+        let handle = { self.handle.write().await.take() };
+
+        if let Some(handle) = handle {
+            if time::timeout(SHUTDOWN_FLUSH_TIMEOUT, self.flush())
+                .await
+                .is_err()
+            {
+                lwarn!(
+                    "system",
+                    LogStage::Shutdown,
+                    LogComponent::Tracing,
+                    "trace_writer_flush_timeout",
+                    "停止时追踪写入队列未写完",
+                    pending = self.pending.load(Ordering::Acquire)
+                );
+            }
+            handle.abort();
+            let _ = handle.await;
+        }
+    }
+
+    /// 放入一条完成记录
+    ///
+    /// 队列未满时立即返回；写满时按配置的策略丢弃最早的记录，或等待空位直到超时后丢弃本条记录。
+    pub async fn enqueue(&self, request_id: &str, params: CompleteTraceParams) -> EnqueueOutcome {
+        let trace = TraceRecord::new(request_id, params);
+        let deadline = Instant::now() + self.config.block_timeout();
+
+        loop {
+            // 先登记等待再检查队列，避免错过检查后到达的空位通知
+            let space = self.space_ready.notified();
+            tokio::pin!(space);
+            space.as_mut().enable();
+
+            {
+                let mut queue = self.lock_queue();
+                if queue.len() < self.config.capacity {
+                    queue.push_back(trace);
+                    drop(queue);
+                    self.pending.fetch_add(1, Ordering::AcqRel);
+                    self.item_ready.notify_one();
+                    return EnqueueOutcome::Queued;
+                }
+                if self.config.overflow_policy == TraceOverflowPolicy::DropOldest {
+                    let oldest = queue.pop_front();
+                    queue.push_back(trace);
+                    drop(queue);
+                    self.item_ready.notify_one();
+                    if let Some(oldest) = oldest {
+                        self.record_dropped(&oldest.request_id);
+                    }
+                    return EnqueueOutcome::DroppedOldest;
+                }
+            }
+
+            if time::timeout_at(deadline, space).await.is_err() {
+                self.record_dropped(&trace.request_id);
+                return EnqueueOutcome::Dropped;
+            }
+        }
+    }
+
+    /// 等待已入队的记录全部写完
+    ///
+    /// 需要后台任务已启动，否则会一直等待；调用方应自行设置超时。
+    pub async fn flush(&self) {
+        loop {
+            let idle = self.idle.notified();
+            tokio::pin!(idle);
+            idle.as_mut().enable();
+            if self.pending.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    async fn run(&self) {
+        loop {
+            let next = self.lock_queue().pop_front();
+            let Some(trace) = next else {
+                self.item_ready.notified().await;
+                continue;
+            };
+            self.space_ready.notify_one();
+
+            if let Err(err) = self.sink.write_batch(std::slice::from_ref(&trace)).await {
+                lwarn!(
+                    &trace.request_id,
+                    LogStage::Db,
+                    LogComponent::Tracing,
+                    "trace_write_failed",
+                    "追踪记录写入失败",
+                    sink = self.sink.name(),
+                    error = %err
+                );
+            }
+
+            if self.pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+                self.idle.notify_waiters();
+            }
+        }
+    }
+
+    fn record_dropped(&self, request_id: &str) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        let total = DROPPED_TOTAL.fetch_add(1, Ordering::Relaxed) + 1;
+        lwarn!(
+            request_id,
+            LogStage::Db,
+            LogComponent::Tracing,
+            "trace_write_dropped",
+            "追踪写入队列已满，丢弃追踪记录",
+            overflow_policy = ?self.config.overflow_policy,
+            dropped_total = total
+        );
+    }
+
+    fn lock_queue(&self) -> MutexGuard<'_, VecDeque<TraceRecord>> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// 进程启动以来因队列写满丢弃的追踪记录总数
+#[must_use]
+pub fn dropped_total() -> u64 {
+    DROPPED_TOTAL.load(Ordering::Relaxed)
+}
@@ -39,6 +39,12 @@ impl EventStreamData {
         }
     }
 
+    /// 当前事件已累积、尚未解析的 `data` 字节数
+    #[must_use]
+    pub const fn pending_data_len(&self) -> usize {
+        self.buffer.len()
+    }
+
     fn process_line(&mut self, line: &str) -> Option<EventStream> {
         if line.is_empty() {
             if self.has_any {
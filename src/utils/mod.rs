@@ -1,3 +1,4 @@
 //! Utils 模块
 
 pub mod event_stream;
+pub mod request_id;
@@ -0,0 +1,72 @@
+//! 请求 ID 生成
+//!
+//! 代理与管理端统一使用 ULID 作为 `request_id`：26 位 Crockford Base32，
+//! 前 48 位为毫秒时间戳，按字典序即按生成时间排序，便于在追踪记录与日志中检索。
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// ULID 字符长度
+pub const REQUEST_ID_LEN: usize = 26;
+/// 返回给客户端的请求 ID 响应头
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const TIMESTAMP_MASK: u128 = (1 << 48) - 1;
+const RANDOMNESS_MASK: u128 = (1 << 80) - 1;
+
+/// 生成新的请求 ID（ULID）
+#[must_use]
+pub fn generate() -> String {
+    let timestamp_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    encode(timestamp_ms, rand::random::<u128>())
+}
+
+/// 是否为合法的 ULID 请求 ID
+#[must_use]
+pub fn is_valid(request_id: &str) -> bool {
+    request_id.len() == REQUEST_ID_LEN
+        // 首字符只承载 3 位，超过 7 会溢出 128 位
+        && request_id.as_bytes()[0] <= b'7'
+        && request_id
+            .bytes()
+            .all(|byte| CROCKFORD_ALPHABET.contains(&byte))
+}
+
+fn encode(timestamp_ms: u128, randomness: u128) -> String {
+    let value = ((timestamp_ms & TIMESTAMP_MASK) << 80) | (randomness & RANDOMNESS_MASK);
+    (0..REQUEST_ID_LEN)
+        .rev()
+        .map(|index| {
+            let digit = usize::try_from((value >> (index * 5)) & 0x1F).unwrap_or_default();
+            char::from(CROCKFORD_ALPHABET[digit])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_timestamp_prefix_and_validates_format() {
+        assert_eq!(encode(0, 0), "00000000000000000000000000");
+        assert_eq!(encode(1, 0), "00000000010000000000000000");
+        // ULID 规范示例时间戳 1469918176385 -> 01ARYZ6S41
+        assert!(encode(1_469_918_176_385, u128::MAX).starts_with("01ARYZ6S41"));
+
+        let id = generate();
+        assert!(is_valid(&id), "{id}");
+        assert!(!is_valid("8ZZZZZZZZZZZZZZZZZZZZZZZZZ"));
+        assert!(!is_valid("01ARYZ6S41TSV4RRFFQ69G5FAI"));
+        assert!(!is_valid("550e8400-e29b-41d4-a716-446655440000"));
+    }
+
+    #[test]
+    fn ids_sort_by_creation_time() {
+        let earlier = encode(1_700_000_000_000, u128::MAX);
+        let later = encode(1_700_000_000_001, 0);
+        assert!(earlier < later);
+    }
+}
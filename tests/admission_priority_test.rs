@@ -0,0 +1,135 @@
+//! 全局并发优先级准入测试
+//!
+//! 并发达到上限后，释放的空位先交给优先级最高的排队请求；队列已满时挤出优先级更低的请求，
+//! 排队超时返回 503。
+
+use api_proxy::config::ConcurrencyConfig;
+use api_proxy::proxy::admission::AdmissionController;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const LOW_PRIORITY: i32 = 0;
+const HIGH_PRIORITY: i32 = 10;
+
+/// 等待后台任务进入预期状态的超时，避免测试挂起
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn controller(max_queue_size: usize, queue_timeout_ms: u64) -> Arc<AdmissionController> {
+    Arc::new(AdmissionController::new(ConcurrencyConfig {
+        enabled: true,
+        max_concurrent_requests: 1,
+        max_queue_size,
+        queue_timeout_ms,
+        retry_after_secs: 2,
+    }))
+}
+
+async fn wait_until_queued(controller: &AdmissionController, queued: usize) {
+    tokio::time::timeout(WAIT_TIMEOUT, async {
+        while controller.queued() < queued {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("requests queued in time");
+}
+
+/// 排队申请空位，获得后上报标签并持有空位直到收到释放信号
+fn spawn_request(
+    controller: &Arc<AdmissionController>,
+    priority: i32,
+    label: &'static str,
+    admitted: mpsc::UnboundedSender<&'static str>,
+) -> mpsc::UnboundedSender<()> {
+    let controller = Arc::clone(controller);
+    let (release_tx, mut release_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let permit = controller.acquire(priority).await.expect("admitted");
+        admitted.send(label).expect("report admission");
+        release_rx.recv().await;
+        drop(permit);
+    });
+    release_tx
+}
+
+#[tokio::test]
+async fn high_priority_request_is_admitted_before_queued_low_priority() {
+    let controller = controller(8, 10_000);
+    let running = controller.acquire(LOW_PRIORITY).await.expect("free slot");
+    assert_eq!(controller.in_flight(), 1);
+
+    let (admitted_tx, mut admitted_rx) = mpsc::unbounded_channel();
+    let release_low = spawn_request(&controller, LOW_PRIORITY, "low", admitted_tx.clone());
+    wait_until_queued(&controller, 1).await;
+    let release_high = spawn_request(&controller, HIGH_PRIORITY, "high", admitted_tx);
+    wait_until_queued(&controller, 2).await;
+
+    // 先排队的低优先级请求在高优先级请求之后才获得空位
+    drop(running);
+    assert_eq!(admitted_rx.recv().await, Some("high"));
+    assert_eq!(controller.in_flight(), 1);
+    assert_eq!(controller.queued(), 1);
+
+    release_high.send(()).expect("release high");
+    assert_eq!(admitted_rx.recv().await, Some("low"));
+    release_low.send(()).expect("release low");
+
+    tokio::time::timeout(WAIT_TIMEOUT, async {
+        while controller.in_flight() > 0 {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("slots released");
+    assert_eq!(controller.queued(), 0);
+}
+
+#[tokio::test]
+async fn full_queue_evicts_lower_priority_waiter() {
+    let controller = controller(1, 10_000);
+    let running = controller.acquire(LOW_PRIORITY).await.expect("free slot");
+
+    let low = tokio::spawn({
+        let controller = Arc::clone(&controller);
+        async move { controller.acquire(LOW_PRIORITY).await.map(drop) }
+    });
+    wait_until_queued(&controller, 1).await;
+
+    // 同优先级不能挤出已排队的请求
+    let rejected = controller
+        .acquire(LOW_PRIORITY)
+        .await
+        .err()
+        .expect("queue full");
+    assert_eq!(rejected.retry_after_secs(), Some(2));
+
+    let high = tokio::spawn({
+        let controller = Arc::clone(&controller);
+        async move { controller.acquire(HIGH_PRIORITY).await.map(drop) }
+    });
+    let evicted = low.await.expect("low task").err().expect("low evicted");
+    assert_eq!(evicted.retry_after_secs(), Some(2));
+
+    wait_until_queued(&controller, 1).await;
+    drop(running);
+    high.await.expect("high task").expect("high admitted");
+    assert_eq!(controller.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn queued_request_times_out_and_frees_queue() {
+    let controller = controller(4, 50);
+    let running = controller.acquire(LOW_PRIORITY).await.expect("free slot");
+
+    let err = controller
+        .acquire(HIGH_PRIORITY)
+        .await
+        .err()
+        .expect("queue timeout");
+    assert_eq!(err.retry_after_secs(), Some(2));
+    assert_eq!(controller.queued(), 0);
+
+    drop(running);
+    assert_eq!(controller.in_flight(), 0);
+}
@@ -0,0 +1,287 @@
+//! 缓存降级测试
+//!
+//! Redis 不可达时缓存进入降级：读取视为未命中、写入跳过，限流检查放行并累计放行次数；
+//! 后台重连探测到 Redis 恢复后退出降级，缓存与限流计数恢复正常；降级期间跳过的删除在恢复时补做。
+//! 测试用一个只实现少量命令的 RESP 服务模拟 Redis，先不启动以模拟故障，再在同一端口启动以模拟恢复。
+
+use api_proxy::auth::api_key_usage_limit_service::ApiKeyUsageLimitService;
+use api_proxy::cache::{CacheManager, fail_open_total};
+use api_proxy::config::{CacheConfig, CacheType, RedisConfig};
+use sea_orm::Database;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+type Store = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// 最小 RESP 服务：支持 PING/GET/SET/SETEX/INCRBY/EXPIRE/DEL/EXISTS，其余命令一律返回 OK
+struct FakeRedis {
+    handle: JoinHandle<()>,
+}
+
+impl FakeRedis {
+    async fn start(port: u16) -> Self {
+        Self::start_with_store(port, Store::default()).await
+    }
+
+    /// 使用给定数据启动，模拟降级前已写入 Redis 的缓存
+    async fn start_with_store(port: u16, store: Store) -> Self {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .expect("bind fake redis");
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, Arc::clone(&store)));
+            }
+        });
+        Self { handle }
+    }
+}
+
+impl Drop for FakeRedis {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve(stream: TcpStream, store: Store) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    while let Some(args) = read_command(&mut reader).await {
+        let reply = execute(&args, &store);
+        if writer.write_all(&reply).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn read_command(
+    reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
+) -> Option<Vec<Vec<u8>>> {
+    let count = read_prefixed(reader, b'*').await?;
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len = read_prefixed(reader, b'$').await?;
+        let mut buf = vec![0; len + 2];
+        reader.read_exact(&mut buf).await.ok()?;
+        buf.truncate(len);
+        args.push(buf);
+    }
+    Some(args)
+}
+
+async fn read_prefixed(
+    reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
+    prefix: u8,
+) -> Option<usize> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.ok()? == 0 {
+        return None;
+    }
+    let line = line.trim_end();
+    line.strip_prefix(char::from(prefix))?.parse().ok()
+}
+
+fn execute(args: &[Vec<u8>], store: &Store) -> Vec<u8> {
+    let command = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+    let key = args
+        .get(1)
+        .map(|key| String::from_utf8_lossy(key).into_owned());
+    let mut store = store.lock().unwrap();
+    match (command.as_str(), key) {
+        ("PING", _) => b"+PONG\r\n".to_vec(),
+        ("GET", Some(key)) => store.get(&key).map_or_else(
+            || b"$-1\r\n".to_vec(),
+            |value| {
+                let mut reply = format!("${}\r\n", value.len()).into_bytes();
+                reply.extend_from_slice(value);
+                reply.extend_from_slice(b"\r\n");
+                reply
+            },
+        ),
+        ("SET", Some(key)) => {
+            store.insert(key, args[2].clone());
+            b"+OK\r\n".to_vec()
+        }
+        ("SETEX", Some(key)) => {
+            store.insert(key, args[3].clone());
+            b"+OK\r\n".to_vec()
+        }
+        ("INCRBY" | "INCR", Some(key)) => {
+            let delta: i64 = args
+                .get(2)
+                .map_or(1, |delta| String::from_utf8_lossy(delta).parse().unwrap());
+            let current: i64 = store
+                .get(&key)
+                .map_or(0, |value| String::from_utf8_lossy(value).parse().unwrap());
+            let next = current + delta;
+            store.insert(key, next.to_string().into_bytes());
+            format!(":{next}\r\n").into_bytes()
+        }
+        ("EXPIRE", Some(_)) => b":1\r\n".to_vec(),
+        ("DEL", Some(_)) => {
+            let removed = args[1..]
+                .iter()
+                .filter(|key| store.remove(&*String::from_utf8_lossy(key)).is_some())
+                .count();
+            format!(":{removed}\r\n").into_bytes()
+        }
+        ("EXISTS", Some(key)) => {
+            format!(":{}\r\n", usize::from(store.contains_key(&key))).into_bytes()
+        }
+        _ => b"+OK\r\n".to_vec(),
+    }
+}
+
+/// 取一个当前空闲的端口，此时没有服务监听
+async fn free_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind free port");
+    listener.local_addr().expect("local addr").port()
+}
+
+fn redis_cache_config(port: u16) -> CacheConfig {
+    CacheConfig {
+        cache_type: CacheType::Redis,
+        redis: Some(RedisConfig {
+            url: format!("redis://127.0.0.1:{port}/0"),
+            port,
+            reconnect_interval_ms: 50,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+async fn wait_until_recovered(cache: &CacheManager) {
+    for _ in 0..100 {
+        if !cache.is_degraded() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("cache did not recover after redis came back");
+}
+
+#[tokio::test]
+async fn redis_outage_fails_open_and_reconnect_restores_caching() {
+    let port = free_port().await;
+    let cache = Arc::new(CacheManager::new(&redis_cache_config(port)).expect("create cache"));
+    let db = Arc::new(
+        Database::connect("sqlite::memory:")
+            .await
+            .expect("connect test db"),
+    );
+    let limiter = ApiKeyUsageLimitService::new(cache.clone(), db);
+    let fail_open_before = fail_open_total();
+
+    // Redis 不可达：超过上限的请求也放行
+    for _ in 0..3 {
+        let outcome = limiter
+            .check_per_minute(1, "/v1/chat/completions", 1)
+            .await
+            .expect("rate limit fails open");
+        assert!(outcome.allowed);
+    }
+    assert!(cache.is_degraded());
+    assert!(fail_open_total() >= fail_open_before + 3);
+
+    // 降级期间读取视为未命中（认证回退数据库），写入跳过
+    cache
+        .set("auth:api_key:test", &"cached".to_string(), None)
+        .await
+        .expect("set is skipped");
+    assert_eq!(
+        cache
+            .get::<String>("auth:api_key:test")
+            .await
+            .expect("get is a miss"),
+        None
+    );
+
+    // Redis 恢复后后台重连退出降级
+    let _redis = FakeRedis::start(port).await;
+    wait_until_recovered(&cache).await;
+
+    cache
+        .set(
+            "auth:api_key:test",
+            &"cached".to_string(),
+            Some(Duration::from_secs(60)),
+        )
+        .await
+        .expect("set after recovery");
+    assert_eq!(
+        cache
+            .get::<String>("auth:api_key:test")
+            .await
+            .expect("get after recovery")
+            .as_deref(),
+        Some("cached")
+    );
+
+    // 限流恢复计数
+    let first = limiter
+        .check_per_minute(2, "/v1/chat/completions", 1)
+        .await
+        .expect("rate limit check");
+    assert!(first.allowed);
+    assert_eq!(first.current, 1);
+    let second = limiter
+        .check_per_minute(2, "/v1/chat/completions", 1)
+        .await
+        .expect("rate limit check");
+    assert!(!second.allowed);
+    assert_eq!(second.current, 2);
+}
+
+#[tokio::test]
+async fn deletes_skipped_during_outage_are_replayed_on_reconnect() {
+    let port = free_port().await;
+    let cache = CacheManager::new(&redis_cache_config(port)).expect("create cache");
+
+    // Redis 不可达：删除被跳过，但会被记录
+    assert_eq!(
+        cache
+            .get::<String>("auth:api_key:revoked")
+            .await
+            .expect("get is a miss"),
+        None
+    );
+    assert!(cache.is_degraded());
+    cache
+        .delete("auth:api_key:revoked")
+        .await
+        .expect("delete is skipped");
+
+    // Redis 恢复时仍保留降级前写入的旧值
+    let store = Store::default();
+    store.lock().unwrap().extend([
+        ("auth:api_key:revoked".to_string(), b"\"stale\"".to_vec()),
+        ("auth:api_key:other".to_string(), b"\"kept\"".to_vec()),
+    ]);
+    let _redis = FakeRedis::start_with_store(port, Arc::clone(&store)).await;
+    wait_until_recovered(&cache).await;
+
+    // 退出降级前已补删，旧值不会再被读到
+    assert!(!store.lock().unwrap().contains_key("auth:api_key:revoked"));
+    assert_eq!(
+        cache
+            .get::<String>("auth:api_key:revoked")
+            .await
+            .expect("get after recovery"),
+        None
+    );
+    assert_eq!(
+        cache
+            .get::<String>("auth:api_key:other")
+            .await
+            .expect("get after recovery")
+            .as_deref(),
+        Some("kept")
+    );
+}
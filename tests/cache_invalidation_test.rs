@@ -0,0 +1,121 @@
+//! 跨实例缓存失效测试
+//!
+//! 两个实例各自持有本地缓存，共享同一广播通道（模拟 Redis 发布/订阅）：
+//! 一个实例发布的失效事件会清理另一个实例的缓存条目并触发其本地回调；
+//! 内存缓存后端不广播，只清理本实例。
+
+use api_proxy::cache::{
+    CacheInvalidator, CacheKey, CacheManager, InvalidationEvent, InvalidationTransport,
+};
+use api_proxy::config::CacheConfig;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const USER_ID: i32 = 3900;
+const SERVICE_API_ID: i32 = 5800;
+
+struct Instance {
+    cache: Arc<CacheManager>,
+    invalidator: Arc<CacheInvalidator>,
+    notified: Arc<AtomicUsize>,
+}
+
+async fn instance(transport: InvalidationTransport) -> Instance {
+    let cache = Arc::new(CacheManager::memory_only());
+    let invalidator = Arc::new(CacheInvalidator::new(cache.clone(), transport));
+    let notified = Arc::new(AtomicUsize::new(0));
+    let counter = notified.clone();
+    invalidator.add_listener(Box::new(move |event| {
+        if let InvalidationEvent::ServiceApi { service_api_id, .. } = event
+            && *service_api_id == SERVICE_API_ID
+        {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }
+    }));
+    invalidator.start().await.expect("start invalidator");
+    Instance {
+        cache,
+        invalidator,
+        notified,
+    }
+}
+
+fn service_api_key() -> String {
+    CacheKey::UserApiConfig {
+        user_id: USER_ID,
+        api_id: SERVICE_API_ID,
+    }
+    .build()
+}
+
+async fn seed(instance: &Instance, key: &str) {
+    instance
+        .cache
+        .set(key, &"cached".to_string(), None)
+        .await
+        .expect("seed cache");
+}
+
+async fn exists(instance: &Instance, key: &str) -> bool {
+    instance.cache.exists(key).await.expect("check cache")
+}
+
+#[tokio::test]
+async fn invalidation_published_by_one_instance_clears_the_other() {
+    let (sender, _) = broadcast::channel(16);
+    let a = instance(InvalidationTransport::Local(sender.clone())).await;
+    let b = instance(InvalidationTransport::Local(sender)).await;
+    let key = service_api_key();
+    seed(&a, &key).await;
+    seed(&b, &key).await;
+
+    a.invalidator
+        .publish(InvalidationEvent::ServiceApi {
+            user_id: USER_ID,
+            service_api_id: SERVICE_API_ID,
+        })
+        .await
+        .expect("publish invalidation");
+    assert!(!exists(&a, &key).await);
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while exists(&b, &key).await || b.notified.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("invalidation reaches the other instance");
+
+    // 发布方只在本地生效一次，不会再处理自己广播的事件
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(a.notified.load(Ordering::SeqCst), 1);
+    assert_eq!(b.notified.load(Ordering::SeqCst), 1);
+
+    a.invalidator.stop().await;
+    b.invalidator.stop().await;
+}
+
+#[tokio::test]
+async fn memory_backend_only_invalidates_locally() {
+    let transport =
+        InvalidationTransport::from_config(&CacheConfig::default()).expect("transport from config");
+    assert!(matches!(transport, InvalidationTransport::Disabled));
+
+    let a = instance(transport).await;
+    let b = instance(InvalidationTransport::Disabled).await;
+    assert!(!a.invalidator.is_distributed());
+
+    let key = "api_proxy:test:invalidation".to_string();
+    seed(&a, &key).await;
+    seed(&b, &key).await;
+
+    a.invalidator
+        .publish(InvalidationEvent::CacheKey { key: key.clone() })
+        .await
+        .expect("publish invalidation");
+
+    assert!(!exists(&a, &key).await);
+    assert!(exists(&b, &key).await);
+}
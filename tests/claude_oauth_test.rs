@@ -5,6 +5,7 @@
 //! - 授权 URL 生成时 scope 参数正确
 //! - URL 编码正确
 
+use api_proxy::auth::pkce::ChallengeMethod;
 use api_proxy::auth::types::{OAuthAuthorizeConfig, OAuthProviderConfig, OAuthTokenConfig};
 use api_proxy::provider::build_authorize_url;
 use entity::oauth_client_sessions::Model;
@@ -20,6 +21,7 @@ fn create_test_session() -> Model {
         provider_type_id: Some(1),
         code_verifier: "test_code_verifier_012".to_string(),
         code_challenge: "test_code_challenge_789".to_string(),
+        redirect_uri: None,
         state: "test_claude_state_456".to_string(),
         name: "Test Claude Session".to_string(),
         description: Some("Test session for Claude OAuth flow".to_string()),
@@ -43,8 +45,10 @@ fn create_claude_config() -> OAuthProviderConfig {
         client_id: "9d1c250a-e61b-44d9-88ed-5944d1962f5e".to_string(),
         client_secret: None,
         redirect_uri: "https://console.anthropic.com/oauth/code/callback".to_string(),
+        redirect_uris: Vec::new(),
         scopes: "org:create_api_key user:profile user:inference".to_string(),
         pkce_required: true,
+        pkce_method: ChallengeMethod::S256,
         authorize: OAuthAuthorizeConfig {
             url: "https://claude.ai/oauth/authorize".to_string(),
             method: "GET".to_string(),
@@ -0,0 +1,262 @@
+//! 集成测试公共设施
+//!
+//! - 内存数据库与种子数据：`setup_db` 执行全部迁移，`user` / `provider` / `provider_key` / `service_api`
+//!   生成填好必填字段的记录，测试按需用结构体更新语法覆盖个别字段后交给对应的 `insert_*` 写入；
+//! - 代理端到端：`start_proxy` 写入完整调用链路并启动真实的 Pingora 代理服务，服务商地址指向测试内的模拟上游。
+
+#![allow(dead_code)]
+
+use api_proxy::AppConfig;
+use api_proxy::app::context::AppContext;
+use api_proxy::dual_port_setup::build_proxy_state;
+use api_proxy::proxy::ProxyService;
+use chrono::Utc;
+use entity::{provider_types, user_provider_keys, user_service_apis, users};
+use migration::{Migrator, MigratorTrait};
+use pingora_core::server::configuration::ServerConf;
+use pingora_core::services::Service;
+use sea_orm::{Database, DatabaseConnection, EntityTrait, Set};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+const USER_ID: i32 = 9100;
+const PROVIDER_TYPE_ID: i32 = 910;
+const PROVIDER_KEY_ID: i32 = 9110;
+const SERVICE_API_ID: i32 = 9120;
+/// 客户端调用代理使用的服务 API 密钥
+pub const CLIENT_API_KEY: &str = "e2e-client-key";
+/// 代理注入上游请求的服务商密钥
+pub const UPSTREAM_API_KEY: &str = "sk-e2e-upstream";
+
+/// 运行中的代理
+pub struct RunningProxy {
+    /// 代理监听地址
+    pub addr: SocketAddr,
+    /// 代理使用的数据库，用于查询请求追踪
+    pub db: Arc<DatabaseConnection>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl Drop for RunningProxy {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+/// 连接内存数据库并执行全部迁移
+pub async fn setup_db() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("connect test db");
+    Migrator::up(&db, None).await.expect("run migrations");
+    db
+}
+
+/// 启用中的普通用户，用户名与邮箱按 `id` 生成
+pub fn user(id: i32) -> users::ActiveModel {
+    let now = Utc::now().naive_utc();
+    users::ActiveModel {
+        id: Set(id),
+        username: Set(format!("test_user_{id}")),
+        password_hash: Set("hashed".to_string()),
+        email: Set(format!("user{id}@test.com")),
+        salt: Set("salt".to_string()),
+        is_admin: Set(false),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+}
+
+/// 启用中的 API Key 认证服务商
+pub fn provider(id: i32, name: &str) -> provider_types::ActiveModel {
+    let now = Utc::now().naive_utc();
+    provider_types::ActiveModel {
+        id: Set(id),
+        name: Set(name.to_string()),
+        display_name: Set(name.to_string()),
+        auth_type: Set("api_key".to_string()),
+        base_url: Set(format!("https://provider{id}.test")),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+}
+
+/// 启用中的健康上游密钥
+pub fn provider_key(
+    id: i32,
+    user_id: i32,
+    provider_type_id: i32,
+) -> user_provider_keys::ActiveModel {
+    let now = Utc::now().naive_utc();
+    user_provider_keys::ActiveModel {
+        id: Set(id),
+        user_id: Set(user_id),
+        provider_type_id: Set(provider_type_id),
+        api_key: Set(format!("sk-test-{id}")),
+        auth_type: Set("api_key".to_string()),
+        name: Set(format!("key-{id}")),
+        is_active: Set(true),
+        health_status: Set("healthy".to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+}
+
+/// 启用中的服务 API，密钥池为 `key_ids`
+pub fn service_api(
+    id: i32,
+    user_id: i32,
+    provider_type_id: i32,
+    key_ids: &[i32],
+) -> user_service_apis::ActiveModel {
+    let now = Utc::now().naive_utc();
+    user_service_apis::ActiveModel {
+        id: Set(id),
+        user_id: Set(user_id),
+        provider_type_id: Set(provider_type_id),
+        api_key: Set(format!("service-api-{id}")),
+        user_provider_keys_ids: Set(json!(key_ids)),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+}
+
+/// 写入用户
+pub async fn insert_user(db: &DatabaseConnection, model: users::ActiveModel) {
+    users::Entity::insert(model)
+        .exec(db)
+        .await
+        .expect("insert user");
+}
+
+/// 写入服务商
+pub async fn insert_provider(db: &DatabaseConnection, model: provider_types::ActiveModel) {
+    provider_types::Entity::insert(model)
+        .exec(db)
+        .await
+        .expect("insert provider");
+}
+
+/// 写入上游密钥
+pub async fn insert_provider_key(db: &DatabaseConnection, model: user_provider_keys::ActiveModel) {
+    user_provider_keys::Entity::insert(model)
+        .exec(db)
+        .await
+        .expect("insert provider key");
+}
+
+/// 写入服务 API
+pub async fn insert_service_api(db: &DatabaseConnection, model: user_service_apis::ActiveModel) {
+    user_service_apis::Entity::insert(model)
+        .exec(db)
+        .await
+        .expect("insert service api");
+}
+
+/// 启动代理，服务商地址为 `upstream_base_url`；`configure` 用于调整默认配置
+pub async fn start_proxy(
+    upstream_base_url: &str,
+    configure: impl FnOnce(&mut AppConfig),
+) -> RunningProxy {
+    let db = setup_db().await;
+    seed(&db, upstream_base_url).await;
+    let db = Arc::new(db);
+
+    let mut config = AppConfig::default();
+    // 追踪直接写库，测试结束前即可查询
+    config.trace_writer.enabled = false;
+    configure(&mut config);
+    let context = AppContext::bootstrap(Arc::new(config), db.clone())
+        .await
+        .expect("bootstrap app context");
+    let proxy = ProxyService::new(build_proxy_state(&context)).expect("create proxy service");
+
+    let addr = free_local_addr().await;
+    let mut service = pingora_proxy::http_proxy_service(&Arc::new(ServerConf::default()), proxy);
+    service.add_tcp(&addr.to_string());
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        service.start_service(None, shutdown_rx, 1).await;
+    });
+    wait_until_listening(addr).await;
+
+    RunningProxy { addr, db, shutdown }
+}
+
+/// 写入调用链路所需的用户、服务商、上游密钥与服务 API
+async fn seed(db: &DatabaseConnection, upstream_base_url: &str) {
+    insert_user(db, user(USER_ID)).await;
+    // 名称包含 openai 以使用 OpenAI 策略（Bearer 认证）
+    insert_provider(
+        db,
+        provider_types::ActiveModel {
+            base_url: Set(upstream_base_url.to_string()),
+            ..provider(PROVIDER_TYPE_ID, "openai_e2e")
+        },
+    )
+    .await;
+    insert_provider_key(
+        db,
+        user_provider_keys::ActiveModel {
+            api_key: Set(UPSTREAM_API_KEY.to_string()),
+            ..provider_key(PROVIDER_KEY_ID, USER_ID, PROVIDER_TYPE_ID)
+        },
+    )
+    .await;
+    insert_service_api(
+        db,
+        user_service_apis::ActiveModel {
+            api_key: Set(CLIENT_API_KEY.to_string()),
+            ..service_api(
+                SERVICE_API_ID,
+                USER_ID,
+                PROVIDER_TYPE_ID,
+                &[PROVIDER_KEY_ID],
+            )
+        },
+    )
+    .await;
+}
+
+/// 绑定随机端口后释放，供代理监听
+async fn free_local_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind free port");
+    listener.local_addr().expect("free port addr")
+}
+
+async fn wait_until_listening(addr: SocketAddr) {
+    for _ in 0..100 {
+        if TcpStream::connect(addr).await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("proxy did not start listening on {addr}");
+}
+
+/// 读取一个完整的 HTTP 请求头（到空行为止）
+pub async fn read_request_head(socket: &mut TcpStream) -> String {
+    use tokio::io::AsyncReadExt;
+
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        let read = socket.read(&mut byte).await.expect("read request head");
+        assert!(read > 0, "connection closed before request head");
+        head.push(byte[0]);
+    }
+    String::from_utf8(head).expect("request head is utf-8")
+}
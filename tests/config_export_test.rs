@@ -0,0 +1,237 @@
+//! 用户配置导出/导入集成测试
+//!
+//! 关注点：
+//! 1. 导出 → 导入往返后非敏感字段保持一致
+//! 2. 默认脱敏：密钥不导出，导入后服务 API 密钥重新生成、提供商密钥停用待补录
+//! 3. 不支持的导入包版本被拒绝
+//! 4. 导入时调度策略别名统一存为规范名称
+
+use api_proxy::management::services::config_export::CONFIG_BUNDLE_VERSION;
+use api_proxy::management::services::{ConfigBundle, ConfigExportService, ExportQuery};
+use chrono::Utc;
+use entity::{provider_types, user_provider_keys, user_service_apis, users};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ColumnTrait, Database, EntityTrait, QueryFilter, Set};
+use std::sync::Arc;
+
+async fn setup_test_db() -> Arc<sea_orm::DatabaseConnection> {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("connect test db");
+    Migrator::up(&db, None).await.expect("run migrations");
+    Arc::new(db)
+}
+
+async fn seed_user(db: &sea_orm::DatabaseConnection, id: i32) {
+    let now = Utc::now().naive_utc();
+    users::Entity::insert(users::ActiveModel {
+        id: Set(id),
+        username: Set(format!("export_user_{id}")),
+        password_hash: Set("hashed".to_string()),
+        email: Set(format!("export_{id}@test.com")),
+        salt: Set("salt".to_string()),
+        is_admin: Set(false),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .expect("insert user");
+}
+
+async fn openai_provider_type_id(db: &sea_orm::DatabaseConnection) -> i32 {
+    provider_types::Entity::find()
+        .filter(provider_types::Column::Name.eq("openai"))
+        .filter(provider_types::Column::AuthType.eq("api_key"))
+        .one(db)
+        .await
+        .expect("query provider type")
+        .expect("seeded openai provider type")
+        .id
+}
+
+async fn seed_config(db: &sea_orm::DatabaseConnection, user_id: i32) {
+    let provider_type_id = openai_provider_type_id(db).await;
+    let now = Utc::now().naive_utc();
+
+    let mut key_ids = Vec::new();
+    for (name, weight) in [("primary", 3), ("backup", 1)] {
+        let key = user_provider_keys::Entity::insert(user_provider_keys::ActiveModel {
+            user_id: Set(user_id),
+            provider_type_id: Set(provider_type_id),
+            api_key: Set(format!("sk-upstream-{name}")),
+            auth_type: Set("api_key".to_string()),
+            name: Set(name.to_string()),
+            weight: Set(Some(weight)),
+            max_requests_per_minute: Set(Some(60)),
+            is_active: Set(true),
+            health_status: Set("healthy".to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .exec(db)
+        .await
+        .expect("insert provider key");
+        key_ids.push(key.last_insert_id);
+    }
+
+    user_service_apis::Entity::insert(user_service_apis::ActiveModel {
+        user_id: Set(user_id),
+        provider_type_id: Set(provider_type_id),
+        user_provider_keys_ids: Set(serde_json::json!(key_ids)),
+        api_key: Set(format!("sk-usr-export-{user_id}")),
+        name: Set(Some("team api".to_string())),
+        description: Set(Some("shared by the team".to_string())),
+        scheduling_strategy: Set(Some("weighted".to_string())),
+        retry_count: Set(Some(2)),
+        max_request_per_min: Set(Some(120)),
+        max_tokens_per_day: Set(Some(1_000_000)),
+        log_mode: Set(true),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .expect("insert service api");
+}
+
+#[tokio::test]
+async fn export_import_round_trip_preserves_non_secret_fields() {
+    let db = setup_test_db().await;
+    seed_user(&db, 3001).await;
+    seed_user(&db, 3002).await;
+    seed_config(&db, 3001).await;
+
+    let service = ConfigExportService::new(db.clone());
+    let exported = service
+        .export(3001, &ExportQuery::default())
+        .await
+        .expect("export");
+    assert_eq!(exported.version, CONFIG_BUNDLE_VERSION);
+    assert!(
+        exported
+            .provider_keys
+            .iter()
+            .all(|key| key.api_key.is_none())
+    );
+    assert!(
+        exported
+            .service_apis
+            .iter()
+            .all(|api| api.api_key.is_none())
+    );
+
+    // 经过 JSON 序列化，模拟下载后再上传
+    let payload = serde_json::to_value(&exported).unwrap();
+    let bundle = ConfigBundle::from_value(payload).expect("parse bundle");
+    let report = service.import(3002, &bundle).await.expect("import");
+
+    assert_eq!(report.provider_keys_created, 2);
+    assert_eq!(report.provider_keys_disabled, vec!["primary", "backup"]);
+    assert!(report.skipped.is_empty());
+    assert_eq!(report.service_apis.len(), 1);
+    assert!(report.service_apis[0].api_key_regenerated);
+    assert!(report.service_apis[0].api_key.starts_with("sk-usr-"));
+
+    let reimported = service
+        .export(3002, &ExportQuery::default())
+        .await
+        .expect("export imported user");
+
+    assert_eq!(reimported.provider_keys.len(), exported.provider_keys.len());
+    for (original, imported) in exported.provider_keys.iter().zip(&reimported.provider_keys) {
+        assert_eq!(imported.provider_type, original.provider_type);
+        assert_eq!(imported.name, original.name);
+        assert_eq!(imported.weight, original.weight);
+        assert_eq!(
+            imported.max_requests_per_minute,
+            original.max_requests_per_minute
+        );
+        // 脱敏导入的密钥需要补录后才能启用
+        assert!(!imported.is_active);
+    }
+
+    // 服务 API 关联按新的密钥 ID 重建，其余字段逐一保持
+    let imported_refs: Vec<i32> = reimported
+        .provider_keys
+        .iter()
+        .map(|key| key.ref_id)
+        .collect();
+    let mut expected_api = exported.service_apis[0].clone();
+    expected_api.provider_key_refs = imported_refs;
+    assert_eq!(reimported.service_apis, vec![expected_api]);
+}
+
+#[tokio::test]
+async fn import_with_secrets_keeps_provider_keys_active() {
+    let db = setup_test_db().await;
+    seed_user(&db, 3101).await;
+    seed_user(&db, 3102).await;
+    seed_config(&db, 3101).await;
+
+    let service = ConfigExportService::new(db.clone());
+    let exported = service
+        .export(
+            3101,
+            &ExportQuery {
+                include_secrets: true,
+            },
+        )
+        .await
+        .expect("export");
+    assert_eq!(
+        exported.provider_keys[0].api_key.as_deref(),
+        Some("sk-upstream-primary")
+    );
+
+    let report = service.import(3102, &exported).await.expect("import");
+
+    assert!(report.provider_keys_disabled.is_empty());
+    // 服务 API 密钥全局唯一，原实例中仍被占用，因此重新生成
+    assert!(report.service_apis[0].api_key_regenerated);
+    assert_ne!(report.service_apis[0].api_key, "sk-usr-export-3101");
+}
+
+#[tokio::test]
+async fn import_stores_canonical_scheduling_strategy() {
+    let db = setup_test_db().await;
+    seed_user(&db, 3201).await;
+    seed_user(&db, 3202).await;
+    seed_config(&db, 3201).await;
+
+    let service = ConfigExportService::new(db.clone());
+    let mut exported = service
+        .export(3201, &ExportQuery::default())
+        .await
+        .expect("export");
+    exported.service_apis[0].scheduling_strategy = Some("W".to_string());
+
+    service.import(3202, &exported).await.expect("import");
+
+    let imported = user_service_apis::Entity::find()
+        .filter(user_service_apis::Column::UserId.eq(3202))
+        .one(db.as_ref())
+        .await
+        .expect("query imported service api")
+        .expect("imported service api");
+    assert_eq!(imported.scheduling_strategy.as_deref(), Some("weighted"));
+}
+
+#[test]
+fn rejects_unsupported_bundle_version() {
+    let payload = serde_json::json!({
+        "version": CONFIG_BUNDLE_VERSION + 1,
+        "exported_at": Utc::now(),
+        "provider_keys": [],
+        "service_apis": [],
+    });
+    assert!(ConfigBundle::from_value(payload).is_err());
+
+    let missing_version = serde_json::json!({ "provider_keys": [] });
+    assert!(ConfigBundle::from_value(missing_version).is_err());
+}
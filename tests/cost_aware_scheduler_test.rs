@@ -0,0 +1,187 @@
+//! 成本感知调度测试
+//!
+//! 验证 `cost_aware` 策略在两个定价不同的服务商之间选择更便宜的健康密钥，并遵守延迟上限。
+
+mod common;
+
+use api_proxy::config::CostAwareConfig;
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService, SelectionContext};
+use chrono::Utc;
+use entity::{model_pricing, model_pricing_tiers, user_provider_keys, user_service_apis};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use std::sync::Arc;
+use std::time::Duration;
+
+const USER_ID: i32 = 2300;
+const CHEAP_PROVIDER_ID: i32 = 330;
+const PRICEY_PROVIDER_ID: i32 = 331;
+const SERVICE_API_ID: i32 = 4300;
+const PRICEY_KEY_ID: i32 = 5300;
+const CHEAP_KEY_ID: i32 = 5301;
+const MODEL: &str = "shared-model";
+
+async fn setup() -> (Arc<DatabaseConnection>, user_service_apis::Model) {
+    let db = common::setup_db().await;
+    let now = Utc::now().naive_utc();
+    common::insert_user(&db, common::user(USER_ID)).await;
+
+    // (服务商, 密钥, 输入单价, 输出单价)
+    for (provider_id, key_id, prompt_price, completion_price) in [
+        (PRICEY_PROVIDER_ID, PRICEY_KEY_ID, 0.000_010, 0.000_030),
+        (CHEAP_PROVIDER_ID, CHEAP_KEY_ID, 0.000_001, 0.000_002),
+    ] {
+        common::insert_provider(
+            &db,
+            common::provider(provider_id, &format!("cost_provider_{provider_id}")),
+        )
+        .await;
+        common::insert_provider_key(&db, common::provider_key(key_id, USER_ID, provider_id)).await;
+
+        let pricing_id = provider_id * 100;
+        model_pricing::Entity::insert(model_pricing::ActiveModel {
+            id: Set(pricing_id),
+            provider_type_id: Set(provider_id),
+            model_name: Set(MODEL.to_string()),
+            cost_currency: Set("USD".to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .exec(&db)
+        .await
+        .expect("insert model pricing");
+
+        for (offset, token_type, price) in [
+            (1, "prompt", prompt_price),
+            (2, "completion", completion_price),
+        ] {
+            model_pricing_tiers::Entity::insert(model_pricing_tiers::ActiveModel {
+                id: Set(pricing_id + offset),
+                model_pricing_id: Set(pricing_id),
+                token_type: Set(token_type.to_string()),
+                min_tokens: Set(0),
+                max_tokens: Set(None),
+                price_per_token: Set(price),
+                created_at: Set(now),
+                updated_at: Set(now),
+            })
+            .exec(&db)
+            .await
+            .expect("insert pricing tier");
+        }
+    }
+
+    common::insert_service_api(
+        &db,
+        user_service_apis::ActiveModel {
+            scheduling_strategy: Set(Some("cost_aware".to_string())),
+            ..common::service_api(
+                SERVICE_API_ID,
+                USER_ID,
+                PRICEY_PROVIDER_ID,
+                &[PRICEY_KEY_ID, CHEAP_KEY_ID],
+            )
+        },
+    )
+    .await;
+
+    let service_api = user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(&db)
+        .await
+        .expect("load service api")
+        .expect("service api exists");
+    (Arc::new(db), service_api)
+}
+
+fn scheduler(db: &Arc<DatabaseConnection>) -> ApiKeySchedulerService {
+    ApiKeySchedulerService::new(db.clone(), Arc::new(ApiKeyHealthService::new(db.clone())))
+}
+
+fn selection_context(request_id: &str, model: Option<&str>) -> SelectionContext {
+    SelectionContext::new(
+        request_id.to_string(),
+        USER_ID,
+        SERVICE_API_ID,
+        PRICEY_PROVIDER_ID,
+        "/v1/chat/completions".to_string(),
+    )
+    .with_model(model.map(ToString::to_string))
+}
+
+async fn select(
+    scheduler: &ApiKeySchedulerService,
+    service_api: &user_service_apis::Model,
+    model: Option<&str>,
+) -> i32 {
+    scheduler
+        .select_api_key_from_service_api(service_api, &selection_context("cost-req", model))
+        .await
+        .expect("select key")
+        .selected_key
+        .id
+}
+
+#[tokio::test]
+async fn cheaper_healthy_provider_is_selected() {
+    let (db, service_api) = setup().await;
+    let scheduler = scheduler(&db);
+
+    for _ in 0..3 {
+        assert_eq!(
+            select(&scheduler, &service_api, Some(MODEL)).await,
+            CHEAP_KEY_ID
+        );
+    }
+    // 模型未知时按服务商平均单价比价
+    assert_eq!(select(&scheduler, &service_api, None).await, CHEAP_KEY_ID);
+}
+
+#[tokio::test]
+async fn unhealthy_cheap_key_falls_back_to_next_cheapest() {
+    let (db, service_api) = setup().await;
+    let scheduler = scheduler(&db);
+
+    let mut cheap_key: user_provider_keys::ActiveModel =
+        user_provider_keys::Entity::find_by_id(CHEAP_KEY_ID)
+            .one(db.as_ref())
+            .await
+            .unwrap()
+            .unwrap()
+            .into();
+    cheap_key.health_status = Set("unhealthy".to_string());
+    cheap_key.update(db.as_ref()).await.unwrap();
+
+    assert_eq!(
+        select(&scheduler, &service_api, Some(MODEL)).await,
+        PRICEY_KEY_ID
+    );
+}
+
+#[tokio::test]
+async fn slow_cheap_key_is_skipped_by_latency_guard() {
+    let (db, service_api) = setup().await;
+    let scheduler = scheduler(&db).with_cost_aware(CostAwareConfig {
+        max_latency_ms: 1_000,
+        ..CostAwareConfig::default()
+    });
+
+    scheduler
+        .latency_stats()
+        .record(CHEAP_KEY_ID, Duration::from_secs(5));
+    scheduler
+        .latency_stats()
+        .record(PRICEY_KEY_ID, Duration::from_millis(300));
+    assert_eq!(
+        select(&scheduler, &service_api, Some(MODEL)).await,
+        PRICEY_KEY_ID
+    );
+
+    // 所有密钥都超过上限时忽略延迟上限，仍然选择最便宜的
+    scheduler
+        .latency_stats()
+        .record(PRICEY_KEY_ID, Duration::from_secs(30));
+    assert_eq!(
+        select(&scheduler, &service_api, Some(MODEL)).await,
+        CHEAP_KEY_ID
+    );
+}
@@ -0,0 +1,170 @@
+//! 积分额度测试
+//!
+//! 转发前预留积分，请求成功后按模型权重扣减余额并释放预留；可用积分不足时拒绝请求；
+//! 请求失败只释放预留，余额不变。
+
+mod common;
+
+use api_proxy::config::{CreditsConfig, ModelCreditCost};
+use api_proxy::error::ProxyError;
+use api_proxy::error::auth::{AuthError, UsageLimitKind};
+use api_proxy::pricing::{CreditsService, TokenUsage};
+use api_proxy::proxy::response::build_rejection_response;
+use entity::users;
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const USER_ID: i32 = 4100;
+const UNLIMITED_USER_ID: i32 = 4101;
+
+async fn setup(balance: Option<i64>) -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+
+    for (id, credits_balance) in [(USER_ID, balance), (UNLIMITED_USER_ID, None)] {
+        common::insert_user(
+            &db,
+            users::ActiveModel {
+                credits_balance: Set(credits_balance),
+                ..common::user(id)
+            },
+        )
+        .await;
+    }
+
+    Arc::new(db)
+}
+
+fn config(reserve_credits: u64) -> CreditsConfig {
+    CreditsConfig {
+        reserve_credits,
+        models: HashMap::from([(
+            "gpt-4o".to_string(),
+            ModelCreditCost {
+                input_per_1k: 5,
+                output_per_1k: 15,
+            },
+        )]),
+        ..Default::default()
+    }
+}
+
+async fn credits_of(db: &DatabaseConnection) -> (Option<i64>, i64) {
+    let user = users::Entity::find_by_id(USER_ID)
+        .one(db)
+        .await
+        .expect("load user")
+        .expect("user exists");
+    (user.credits_balance, user.credits_reserved)
+}
+
+#[tokio::test]
+async fn completed_request_deducts_weighted_credits() {
+    let db = setup(Some(100)).await;
+    let credits = CreditsService::new(db.clone(), config(2));
+
+    let reservation = credits
+        .reserve(USER_ID, "req-credits-1")
+        .await
+        .expect("reserve credits")
+        .expect("credits enabled");
+    assert_eq!(reservation.amount, 2);
+    assert_eq!(credits_of(&db).await, (Some(100), 2));
+
+    // 输入 1000 + 缓存 200 按 5 计，输出 2000 按 15 计：(1200 × 5 + 2000 × 15) / 1000 = 36
+    let usage = TokenUsage {
+        prompt_tokens: Some(1000),
+        completion_tokens: Some(2000),
+        cache_read_tokens: Some(200),
+        ..Default::default()
+    };
+    let deducted = credits
+        .settle(reservation, Some("gpt-4o"), &usage, "req-credits-1")
+        .await
+        .expect("settle credits");
+    assert_eq!(deducted, 36);
+    assert_eq!(credits_of(&db).await, (Some(64), 0));
+
+    // 未配置的模型使用默认权重，不足 1 积分向上取整
+    let reservation = credits
+        .reserve(USER_ID, "req-credits-2")
+        .await
+        .expect("reserve credits")
+        .expect("credits enabled");
+    let usage = TokenUsage {
+        prompt_tokens: Some(10),
+        completion_tokens: Some(5),
+        ..Default::default()
+    };
+    let deducted = credits
+        .settle(reservation, Some("unknown-model"), &usage, "req-credits-2")
+        .await
+        .expect("settle credits");
+    assert_eq!(deducted, 1);
+    assert_eq!(credits_of(&db).await, (Some(63), 0));
+}
+
+#[tokio::test]
+async fn insufficient_balance_is_rejected_before_forwarding() {
+    let db = setup(Some(3)).await;
+    let credits = CreditsService::new(db.clone(), config(2));
+
+    // 第一个请求预留 2 积分后只剩 1 积分可用
+    credits
+        .reserve(USER_ID, "req-credits-1")
+        .await
+        .expect("reserve credits")
+        .expect("credits enabled");
+    let err = credits
+        .reserve(USER_ID, "req-credits-2")
+        .await
+        .expect_err("insufficient credits");
+    let ProxyError::Authentication(AuthError::UsageLimitExceeded(info)) = &err else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(info.kind, UsageLimitKind::Credits);
+    assert_eq!(info.limit, Some(2.0));
+    assert_eq!(info.current, Some(1.0));
+
+    let rejection = build_rejection_response(&err).expect("rejection response");
+    assert_eq!(rejection.status, 429);
+    assert_eq!(rejection.reason.as_str(), "credits_insufficient");
+    assert_eq!(credits_of(&db).await, (Some(3), 2));
+
+    // 未设置积分余额的用户不受限制
+    assert!(
+        credits
+            .reserve(UNLIMITED_USER_ID, "req-credits-3")
+            .await
+            .expect("reserve credits")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn failed_request_releases_reservation_without_deduction() {
+    let db = setup(Some(2)).await;
+    let credits = CreditsService::new(db.clone(), config(2));
+
+    let reservation = credits
+        .reserve(USER_ID, "req-credits-1")
+        .await
+        .expect("reserve credits")
+        .expect("credits enabled");
+    assert!(credits.reserve(USER_ID, "req-credits-2").await.is_err());
+
+    credits
+        .release(reservation, "req-credits-1")
+        .await
+        .expect("release credits");
+    assert_eq!(credits_of(&db).await, (Some(2), 0));
+
+    // 预留释放后可以再次预留
+    assert!(
+        credits
+            .reserve(USER_ID, "req-credits-3")
+            .await
+            .expect("reserve credits")
+            .is_some()
+    );
+}
@@ -0,0 +1,77 @@
+//! 服务 API 自定义响应头测试
+//!
+//! 覆盖 `response_headers` 的解析校验：配置的头部写入响应并覆盖上游同名头部，
+//! 受保护的传输层与代理头部不能被配置覆盖。
+
+use api_proxy::proxy::response_transform_service::{
+    ResponseTransformService, is_protected_response_header, parse_custom_response_headers,
+};
+use pingora_http::ResponseHeader;
+use serde_json::json;
+
+fn upstream_response() -> ResponseHeader {
+    let mut resp = ResponseHeader::build(200, None).unwrap();
+    for (name, value) in [
+        ("content-type", "application/json"),
+        ("x-powered-by", "upstream"),
+    ] {
+        resp.append_header(name, value).unwrap();
+    }
+    resp
+}
+
+#[test]
+fn configured_headers_appear_on_response() {
+    let headers = parse_custom_response_headers(&json!({
+        "X-Powered-By": "Customer",
+        "X-Support-Contact": "support@example.com"
+    }))
+    .expect("valid headers");
+
+    let mut resp = upstream_response();
+    ResponseTransformService::apply_custom_headers(&mut resp, headers).unwrap();
+
+    let header = |name: &str| resp.headers.get(name).and_then(|v| v.to_str().ok());
+    assert_eq!(header("x-powered-by"), Some("Customer"));
+    assert_eq!(header("x-support-contact"), Some("support@example.com"));
+    assert_eq!(resp.headers.get_all("x-powered-by").iter().count(), 1);
+    assert_eq!(header("content-type"), Some("application/json"));
+}
+
+#[test]
+fn protected_headers_cannot_be_overridden() {
+    for name in [
+        "Content-Length",
+        "transfer-encoding",
+        "Connection",
+        "content-type",
+        "x-request-id",
+        "Access-Control-Allow-Origin",
+        "proxy-authenticate",
+        "sec-websocket-accept",
+    ] {
+        assert!(is_protected_response_header(name), "{name}");
+        let err = parse_custom_response_headers(&json!({ name: "override" }))
+            .expect_err("protected header must be rejected");
+        assert_eq!(err.status_code(), http::StatusCode::BAD_REQUEST, "{name}");
+    }
+    assert!(!is_protected_response_header("x-powered-by"));
+}
+
+#[test]
+fn invalid_header_config_is_rejected() {
+    assert!(parse_custom_response_headers(&json!(["x-powered-by"])).is_err());
+    assert!(parse_custom_response_headers(&json!({"bad header": "v"})).is_err());
+    assert!(parse_custom_response_headers(&json!({"x-brand": 1})).is_err());
+    assert!(parse_custom_response_headers(&json!({"x-brand": "line\nbreak"})).is_err());
+
+    let too_many: serde_json::Map<_, _> = (0..33)
+        .map(|i| (format!("x-brand-{i}"), json!("v")))
+        .collect();
+    assert!(parse_custom_response_headers(&serde_json::Value::Object(too_many)).is_err());
+    assert!(
+        parse_custom_response_headers(&json!({}))
+            .expect("empty object")
+            .is_empty()
+    );
+}
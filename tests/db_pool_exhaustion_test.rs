@@ -0,0 +1,66 @@
+//! 数据库连接池耗尽测试
+//!
+//! 使用单连接的连接池并在事务中占住唯一连接，再发起查询触发获取连接超时，
+//! 验证错误映射为带 `Retry-After` 的 503 而不是 500，并单独计数。
+
+use api_proxy::error::database::{POOL_EXHAUSTED_RETRY_AFTER_SECS, pool_exhausted_total};
+use api_proxy::error::{Context, ProxyError};
+use api_proxy::management::response::app_error;
+use api_proxy::proxy::response::build_rejection_response;
+use entity::users;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectOptions, Database, DbErr, EntityTrait, TransactionTrait};
+use std::time::Duration;
+
+#[tokio::test]
+async fn pool_acquire_timeout_maps_to_service_unavailable() {
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    options
+        .max_connections(1)
+        .acquire_timeout(Duration::from_millis(200))
+        .sqlx_logging(false);
+    let db = Database::connect(options).await.expect("connect test db");
+    Migrator::up(&db, None).await.expect("run migrations");
+
+    let before = pool_exhausted_total();
+    let txn = db.begin().await.expect("hold the only connection");
+    let err = users::Entity::find()
+        .all(&db)
+        .await
+        .context("Failed to load users")
+        .unwrap_err();
+    txn.rollback().await.expect("release connection");
+
+    assert_eq!(err.status_code(), http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(err.error_code(), "SERVICE_UNAVAILABLE");
+    assert_eq!(
+        err.retry_after_secs(),
+        Some(POOL_EXHAUSTED_RETRY_AFTER_SECS)
+    );
+    assert!(pool_exhausted_total() > before);
+
+    let rejection = build_rejection_response(&err).expect("proxy rejection");
+    assert_eq!(rejection.status, 503);
+    assert_eq!(
+        rejection.retry_after_secs,
+        Some(POOL_EXHAUSTED_RETRY_AFTER_SECS)
+    );
+
+    let response = app_error(err);
+    assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        response.headers()[http::header::RETRY_AFTER],
+        POOL_EXHAUSTED_RETRY_AFTER_SECS.to_string()
+    );
+
+    // 连接释放后查询恢复正常
+    assert!(users::Entity::find().all(&db).await.is_ok());
+}
+
+#[test]
+fn other_database_errors_stay_internal() {
+    let err: ProxyError = DbErr::Custom("constraint violated".to_string()).into();
+    assert!(matches!(err, ProxyError::Database(_)));
+    assert_eq!(err.status_code(), http::StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(err.retry_after_secs().is_none());
+}
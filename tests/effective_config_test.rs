@@ -0,0 +1,224 @@
+//! 生效配置预览测试
+//!
+//! 预览与请求链路共用取值逻辑：服务 API 字段优先，其次是服务商 `config_json` 与配置文件，
+//! 都未配置时使用内置默认值；每个值标注的来源应与实际生效的那一层一致。
+
+use api_proxy::config::{AppConfig, ProviderRateLimit};
+use api_proxy::proxy::effective_config::{
+    ConfigSource, DEFAULT_TIMEOUT_SECS, EffectiveConfig, Resolved,
+};
+use chrono::Utc;
+use entity::{provider_types, user_service_apis};
+use serde_json::{Value, json};
+
+const USER_ID: i32 = 4400;
+const PROVIDER_TYPE_ID: i32 = 540;
+const SERVICE_API_ID: i32 = 6100;
+
+fn provider(config_json: Option<&str>) -> provider_types::Model {
+    let now = Utc::now().naive_utc();
+    provider_types::Model {
+        id: PROVIDER_TYPE_ID,
+        name: "openai".to_string(),
+        display_name: "OpenAI".to_string(),
+        auth_type: "api_key".to_string(),
+        base_url: "https://api.openai.com".to_string(),
+        is_active: true,
+        config_json: config_json.map(str::to_string),
+        token_mappings_json: None,
+        model_extraction_json: None,
+        auth_configs_json: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+fn service_api() -> user_service_apis::Model {
+    let now = Utc::now().naive_utc();
+    user_service_apis::Model {
+        id: SERVICE_API_ID,
+        user_id: USER_ID,
+        provider_type_id: PROVIDER_TYPE_ID,
+        user_provider_keys_ids: json!([]),
+        api_key: "effective-config-key".to_string(),
+        previous_api_key: None,
+        previous_api_key_expires_at: None,
+        name: None,
+        description: None,
+        scheduling_strategy: None,
+        retry_count: None,
+        timeout_seconds: None,
+        max_response_duration_seconds: None,
+        sla_target_ms: None,
+        max_request_per_min: None,
+        max_requests_per_day: None,
+        max_tokens_per_day: None,
+        max_cost_per_day: None,
+        max_cost_per_request: None,
+        log_mode: false,
+        selection_debug: false,
+        stream_policy: "allow".to_string(),
+        upstream_error_mode: "sanitized".to_string(),
+        priority: 0,
+        request_transform_rules: None,
+        response_headers: None,
+        allowed_paths: None,
+        system_prompt: None,
+        key_tag: None,
+        model_routes: None,
+        expires_at: None,
+        is_active: true,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+const fn resolved<T>(value: T, source: ConfigSource) -> Resolved<T> {
+    Resolved { value, source }
+}
+
+#[test]
+fn unset_values_fall_back_to_defaults() {
+    let effective =
+        EffectiveConfig::resolve(&AppConfig::default(), &service_api(), &provider(None));
+
+    assert_eq!(
+        effective.timeout_seconds,
+        resolved(DEFAULT_TIMEOUT_SECS, ConfigSource::Default)
+    );
+    assert_eq!(effective.retry_count, resolved(0, ConfigSource::Default));
+    assert_eq!(
+        effective.scheduling_strategy,
+        resolved("round_robin", ConfigSource::Default)
+    );
+    assert_eq!(
+        effective.stream_policy,
+        resolved("allow", ConfigSource::Default)
+    );
+    assert_eq!(
+        effective.upstream_error_mode,
+        resolved("sanitized", ConfigSource::Default)
+    );
+    assert_eq!(
+        effective.max_request_per_min,
+        resolved(Value::Null, ConfigSource::Default)
+    );
+    assert_eq!(
+        effective.provider_max_requests_per_min,
+        resolved(Value::Null, ConfigSource::Default)
+    );
+    assert_eq!(
+        effective.max_tokens_ceiling,
+        resolved(Value::Null, ConfigSource::Default)
+    );
+}
+
+#[test]
+fn service_api_values_take_precedence() {
+    let mut config = AppConfig::default();
+    config.total_timeout.default_secs = 600;
+    config
+        .total_timeout
+        .providers
+        .insert("openai".to_string(), 900);
+
+    let mut api = service_api();
+    api.timeout_seconds = Some(45);
+    api.max_response_duration_seconds = Some(120);
+    api.retry_count = Some(3);
+    api.scheduling_strategy = Some("weighted".to_string());
+    api.stream_policy = "force-off".to_string();
+    api.upstream_error_mode = "verbatim".to_string();
+    api.max_request_per_min = Some(60);
+    api.key_tag = Some("prod".to_string());
+
+    let effective = EffectiveConfig::resolve(&config, &api, &provider(None));
+
+    assert_eq!(
+        effective.timeout_seconds,
+        resolved(45, ConfigSource::ServiceApi)
+    );
+    assert_eq!(
+        effective.total_timeout_seconds,
+        resolved(120, ConfigSource::ServiceApi)
+    );
+    assert_eq!(effective.retry_count, resolved(3, ConfigSource::ServiceApi));
+    assert_eq!(
+        effective.scheduling_strategy,
+        resolved("weighted", ConfigSource::ServiceApi)
+    );
+    assert_eq!(
+        effective.stream_policy,
+        resolved("force-off", ConfigSource::ServiceApi)
+    );
+    assert_eq!(
+        effective.upstream_error_mode,
+        resolved("verbatim", ConfigSource::ServiceApi)
+    );
+    assert_eq!(
+        effective.max_request_per_min,
+        resolved(json!(60), ConfigSource::ServiceApi)
+    );
+    assert_eq!(
+        effective.key_tag,
+        resolved(json!("prod"), ConfigSource::ServiceApi)
+    );
+}
+
+#[test]
+fn global_config_applies_when_service_api_is_unset_or_out_of_bounds() {
+    let mut config = AppConfig::default();
+    config.timeout_bounds.min_secs = 10;
+    config.timeout_bounds.max_secs = 300;
+    config.total_timeout.default_secs = 600;
+    config
+        .total_timeout
+        .providers
+        .insert("openai".to_string(), 900);
+    config.rate_limit.providers.push(ProviderRateLimit {
+        provider_type_id: PROVIDER_TYPE_ID,
+        max_requests_per_min: 1000,
+    });
+
+    let mut api = service_api();
+    api.timeout_seconds = Some(3600);
+
+    let effective = EffectiveConfig::resolve(&config, &api, &provider(None));
+
+    assert_eq!(
+        effective.timeout_seconds,
+        resolved(300, ConfigSource::Global)
+    );
+    assert_eq!(
+        effective.total_timeout_seconds,
+        resolved(900, ConfigSource::Global)
+    );
+    assert_eq!(
+        effective.provider_max_requests_per_min,
+        resolved(json!(1000), ConfigSource::Global)
+    );
+}
+
+#[test]
+fn provider_config_json_values_are_annotated_as_provider() {
+    let provider = provider(Some(
+        r#"{"max_tokens_ceiling": {"max": 4096}, "model_rate_limits": {"gpt-4o": {"requests_per_minute": 20}}}"#,
+    ));
+
+    let effective = EffectiveConfig::resolve(&AppConfig::default(), &service_api(), &provider);
+
+    assert_eq!(
+        effective.max_tokens_ceiling,
+        resolved(
+            json!({"max": 4096, "inject_when_absent": false}),
+            ConfigSource::Provider
+        )
+    );
+    assert_eq!(
+        effective.model_rate_limits,
+        resolved(
+            json!({"gpt-4o": {"requests_per_minute": 20}}),
+            ConfigSource::Provider
+        )
+    );
+}
@@ -0,0 +1,182 @@
+//! 客户端 IP 地理信息测试
+//!
+//! 测试库按 `MaxMind` DB 格式生成，只包含 81.2.69.0/24 一条记录（GB / AS20712），
+//! 同一个库同时作为国家库与 ASN 库使用。
+
+use api_proxy::config::GeoIpConfig;
+use api_proxy::trace::{GeoInfo, GeoIpLookup};
+use std::path::PathBuf;
+
+const FIXTURE_PREFIX: [u8; 3] = [81, 2, 69];
+const FIXTURE_PREFIX_LEN: u32 = 24;
+
+/// `MaxMind` DB 数据段取值
+enum Value {
+    Str(&'static str),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    Map(Vec<(&'static str, Value)>),
+    Array(Vec<Value>),
+}
+
+fn control(out: &mut Vec<u8>, type_num: u8, size: usize) {
+    let (size_bits, extra) = if size < 29 {
+        (u8::try_from(size).unwrap(), None)
+    } else {
+        (29, Some(u8::try_from(size - 29).unwrap()))
+    };
+    if type_num <= 7 {
+        out.push((type_num << 5) | size_bits);
+    } else {
+        out.push(size_bits);
+        out.push(type_num - 7);
+    }
+    out.extend(extra);
+}
+
+fn encode_uint(out: &mut Vec<u8>, type_num: u8, bytes: &[u8]) {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    control(out, type_num, bytes.len() - start);
+    out.extend_from_slice(&bytes[start..]);
+}
+
+fn encode(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Str(s) => {
+            control(out, 2, s.len());
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::U16(v) => encode_uint(out, 5, &v.to_be_bytes()),
+        Value::U32(v) => encode_uint(out, 6, &v.to_be_bytes()),
+        Value::U64(v) => encode_uint(out, 9, &v.to_be_bytes()),
+        Value::Map(entries) => {
+            control(out, 7, entries.len());
+            for (key, value) in entries {
+                encode(&Value::Str(*key), out);
+                encode(value, out);
+            }
+        }
+        Value::Array(items) => {
+            control(out, 11, items.len());
+            for item in items {
+                encode(item, out);
+            }
+        }
+    }
+}
+
+/// 生成 IPv4 测试库：搜索树逐位匹配前缀，其余分支指向“无记录”
+fn fixture_db() -> Vec<u8> {
+    let node_count = FIXTURE_PREFIX_LEN;
+    let prefix = u32::from_be_bytes([FIXTURE_PREFIX[0], FIXTURE_PREFIX[1], FIXTURE_PREFIX[2], 0]);
+    // 数据记录位于数据段偏移 0
+    let data_pointer = node_count + 16;
+
+    let mut out = Vec::new();
+    for node in 0..node_count {
+        let bit = (prefix >> (31 - node)) & 1;
+        let next = if node + 1 == node_count {
+            data_pointer
+        } else {
+            node + 1
+        };
+        let (left, right) = if bit == 0 {
+            (next, node_count)
+        } else {
+            (node_count, next)
+        };
+        out.extend_from_slice(&left.to_be_bytes()[1..]);
+        out.extend_from_slice(&right.to_be_bytes()[1..]);
+    }
+    out.extend_from_slice(&[0; 16]);
+
+    let record = Value::Map(vec![
+        (
+            "country",
+            Value::Map(vec![
+                ("iso_code", Value::Str("GB")),
+                (
+                    "names",
+                    Value::Map(vec![("en", Value::Str("United Kingdom"))]),
+                ),
+            ]),
+        ),
+        ("autonomous_system_number", Value::U32(20712)),
+        (
+            "autonomous_system_organization",
+            Value::Str("Andrews & Arnold Ltd"),
+        ),
+    ]);
+    encode(&record, &mut out);
+
+    out.extend_from_slice(b"\xAB\xCD\xEFMaxMind.com");
+    let metadata = Value::Map(vec![
+        ("binary_format_major_version", Value::U16(2)),
+        ("binary_format_minor_version", Value::U16(0)),
+        ("build_epoch", Value::U64(1_700_000_000)),
+        ("database_type", Value::Str("ApiProxy-Test-Country-ASN")),
+        (
+            "description",
+            Value::Map(vec![("en", Value::Str("api-proxy test database"))]),
+        ),
+        ("ip_version", Value::U16(4)),
+        ("languages", Value::Array(vec![Value::Str("en")])),
+        ("node_count", Value::U32(node_count)),
+        ("record_size", Value::U16(24)),
+    ]);
+    encode(&metadata, &mut out);
+    out
+}
+
+fn write_fixture(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "api-proxy-geoip-{name}-{}.mmdb",
+        std::process::id()
+    ));
+    std::fs::write(&path, fixture_db()).expect("write fixture geoip db");
+    path
+}
+
+fn lookup_for(path: &str) -> GeoIpLookup {
+    GeoIpLookup::open(&GeoIpConfig {
+        country_database: Some(path.to_string()),
+        asn_database: Some(path.to_string()),
+    })
+}
+
+#[test]
+fn known_ip_maps_to_country_and_asn() {
+    let path = write_fixture("known");
+    let lookup = lookup_for(path.to_str().unwrap());
+
+    assert!(lookup.is_enabled());
+    assert_eq!(
+        lookup.lookup("81.2.69.160"),
+        GeoInfo {
+            country: Some("GB".to_string()),
+            asn: Some(20712),
+        }
+    );
+
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn unknown_or_invalid_ip_has_no_geo_info() {
+    let path = write_fixture("unknown");
+    let lookup = lookup_for(path.to_str().unwrap());
+
+    assert_eq!(lookup.lookup("8.8.8.8"), GeoInfo::default());
+    assert_eq!(lookup.lookup("not-an-ip"), GeoInfo::default());
+
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn missing_database_fails_open() {
+    let lookup = lookup_for("/nonexistent/GeoLite2-Country.mmdb");
+
+    assert!(!lookup.is_enabled());
+    assert_eq!(lookup.lookup("81.2.69.160"), GeoInfo::default());
+}
@@ -0,0 +1,162 @@
+//! 套餐内免费额度测试
+//!
+//! 免费额度内的请求应付费用为 0，超出部分按原价计费，已消耗额度按月累计。
+
+mod common;
+
+use api_proxy::pricing::{PricingCalculatorService, TokenUsage};
+use chrono::Utc;
+use entity::{model_pricing, model_pricing_tiers, user_included_quota_usage, users};
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use std::sync::Arc;
+
+const USER_ID: i32 = 3100;
+const PROVIDER_TYPE_ID: i32 = 420;
+const MODEL: &str = "quota-model";
+const PROMPT_PRICE: f64 = 0.001;
+const EPSILON: f64 = 1e-9;
+
+async fn setup(
+    included_tokens: Option<i64>,
+    included_requests: Option<i64>,
+) -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    let now = Utc::now().naive_utc();
+
+    common::insert_user(
+        &db,
+        users::ActiveModel {
+            included_tokens_per_month: Set(included_tokens),
+            included_requests_per_month: Set(included_requests),
+            ..common::user(USER_ID)
+        },
+    )
+    .await;
+
+    common::insert_provider(&db, common::provider(PROVIDER_TYPE_ID, "quota_provider")).await;
+
+    let pricing_id = model_pricing::Entity::insert(model_pricing::ActiveModel {
+        provider_type_id: Set(PROVIDER_TYPE_ID),
+        model_name: Set(MODEL.to_string()),
+        cost_currency: Set("USD".to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&db)
+    .await
+    .expect("insert model pricing")
+    .last_insert_id;
+
+    model_pricing_tiers::Entity::insert(model_pricing_tiers::ActiveModel {
+        model_pricing_id: Set(pricing_id),
+        token_type: Set("prompt".to_string()),
+        min_tokens: Set(0),
+        max_tokens: Set(None),
+        price_per_token: Set(PROMPT_PRICE),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&db)
+    .await
+    .expect("insert pricing tier");
+
+    Arc::new(db)
+}
+
+fn prompt_usage(tokens: u64) -> TokenUsage {
+    TokenUsage {
+        prompt_tokens: Some(tokens),
+        ..Default::default()
+    }
+}
+
+async fn billed_cost(service: &PricingCalculatorService, tokens: u64) -> (f64, f64) {
+    let usage = prompt_usage(tokens);
+    let result = service
+        .calculate_cost(MODEL, PROVIDER_TYPE_ID, &usage, "quota-req")
+        .await
+        .expect("calculate cost");
+    let billed = service
+        .apply_included_quota(USER_ID, &usage, result, "quota-req")
+        .await
+        .expect("apply included quota");
+    (billed.total_cost, billed.quota_covered_cost)
+}
+
+async fn quota_usage(db: &DatabaseConnection) -> user_included_quota_usage::Model {
+    user_included_quota_usage::Entity::find()
+        .one(db)
+        .await
+        .expect("load quota usage")
+        .expect("quota usage recorded")
+}
+
+#[tokio::test]
+async fn requests_within_free_quota_are_not_billed() {
+    let db = setup(None, Some(2)).await;
+    let service = PricingCalculatorService::new(db.clone());
+
+    for _ in 0..2 {
+        let (billed, covered) = billed_cost(&service, 500).await;
+        assert!(
+            billed.abs() < EPSILON,
+            "expected zero billable, got {billed}"
+        );
+        assert!((covered - 500.0 * PROMPT_PRICE).abs() < EPSILON);
+    }
+
+    // 请求数额度用完后按原价计费
+    let (billed, covered) = billed_cost(&service, 500).await;
+    assert!((billed - 500.0 * PROMPT_PRICE).abs() < EPSILON);
+    assert!(covered.abs() < EPSILON);
+
+    let usage = quota_usage(db.as_ref()).await;
+    assert_eq!(usage.used_requests, 2);
+    assert_eq!(usage.period, Utc::now().format("%Y-%m").to_string());
+}
+
+#[tokio::test]
+async fn tokens_beyond_free_quota_are_billed_on_excess() {
+    let db = setup(Some(1_000), None).await;
+    let service = PricingCalculatorService::new(db.clone());
+
+    let (billed, _) = billed_cost(&service, 600).await;
+    assert!(
+        billed.abs() < EPSILON,
+        "expected zero billable, got {billed}"
+    );
+
+    // 剩余 400 token 免费，超出的 200 token 计费
+    let (billed, covered) = billed_cost(&service, 600).await;
+    assert!(
+        (billed - 200.0 * PROMPT_PRICE).abs() < EPSILON,
+        "got {billed}"
+    );
+    assert!((covered - 400.0 * PROMPT_PRICE).abs() < EPSILON);
+
+    let (billed, _) = billed_cost(&service, 600).await;
+    assert!((billed - 600.0 * PROMPT_PRICE).abs() < EPSILON);
+
+    let usage = quota_usage(db.as_ref()).await;
+    assert_eq!(usage.used_tokens, 1_000);
+    assert_eq!(usage.used_requests, 0);
+}
+
+#[tokio::test]
+async fn users_without_quota_are_billed_in_full() {
+    let db = setup(None, None).await;
+    let service = PricingCalculatorService::new(db.clone());
+
+    let (billed, covered) = billed_cost(&service, 600).await;
+    assert!((billed - 600.0 * PROMPT_PRICE).abs() < EPSILON);
+    assert!(covered.abs() < EPSILON);
+    assert!(
+        user_included_quota_usage::Entity::find()
+            .one(db.as_ref())
+            .await
+            .expect("load quota usage")
+            .is_none()
+    );
+}
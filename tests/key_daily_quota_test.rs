@@ -0,0 +1,162 @@
+//! 服务商密钥每日请求配额测试
+//!
+//! 验证达到 `max_requests_per_day` 的密钥在选择时被跳过，所有密钥用尽时返回配额错误。
+
+use api_proxy::cache::CacheManager;
+use api_proxy::error::{ProxyError, key_pool::KeyPoolError};
+use api_proxy::key_pool::{
+    ApiKeyDailyQuota, ApiKeyHealthService, ApiKeySchedulerService, SelectionContext,
+};
+use chrono::Utc;
+use chrono_tz::Tz;
+use entity::{provider_types, user_provider_keys, user_service_apis, users};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, DatabaseConnection, EntityTrait, Set};
+use serde_json::json;
+use std::sync::Arc;
+
+const USER_ID: i32 = 2100;
+const PROVIDER_TYPE_ID: i32 = 310;
+const SERVICE_API_ID: i32 = 4100;
+
+async fn setup_test_db() -> Arc<DatabaseConnection> {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("connect test db");
+    Migrator::up(&db, None).await.expect("run migrations");
+    Arc::new(db)
+}
+
+async fn seed(db: &DatabaseConnection, key_ids: &[i32]) -> user_service_apis::Model {
+    let now = Utc::now().naive_utc();
+    users::Entity::insert(users::ActiveModel {
+        id: Set(USER_ID),
+        username: Set("quota_user".to_string()),
+        password_hash: Set("hashed".to_string()),
+        email: Set("quota@test.com".to_string()),
+        salt: Set("salt".to_string()),
+        is_admin: Set(false),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .expect("insert user");
+
+    provider_types::Entity::insert(provider_types::ActiveModel {
+        id: Set(PROVIDER_TYPE_ID),
+        name: Set("quota_provider".to_string()),
+        display_name: Set("Quota Provider".to_string()),
+        auth_type: Set("api_key".to_string()),
+        base_url: Set("https://api.quota.test".to_string()),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .expect("insert provider");
+
+    for key_id in key_ids {
+        user_provider_keys::Entity::insert(user_provider_keys::ActiveModel {
+            id: Set(*key_id),
+            user_id: Set(USER_ID),
+            provider_type_id: Set(PROVIDER_TYPE_ID),
+            api_key: Set(format!("sk-quota-{key_id}")),
+            auth_type: Set("api_key".to_string()),
+            name: Set(format!("Quota Key {key_id}")),
+            max_requests_per_day: Set(Some(1)),
+            is_active: Set(true),
+            health_status: Set("healthy".to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .exec(db)
+        .await
+        .expect("insert provider key");
+    }
+
+    user_service_apis::Entity::insert(user_service_apis::ActiveModel {
+        id: Set(SERVICE_API_ID),
+        user_id: Set(USER_ID),
+        provider_type_id: Set(PROVIDER_TYPE_ID),
+        api_key: Set("quota-service-api".to_string()),
+        user_provider_keys_ids: Set(json!(key_ids)),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .expect("insert service api");
+
+    user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(db)
+        .await
+        .expect("load service api")
+        .expect("service api exists")
+}
+
+fn selection_context(request_id: &str) -> SelectionContext {
+    SelectionContext::new(
+        request_id.to_string(),
+        USER_ID,
+        SERVICE_API_ID,
+        PROVIDER_TYPE_ID,
+        "/v1/chat/completions".to_string(),
+    )
+}
+
+#[tokio::test]
+async fn key_at_daily_cap_is_skipped() {
+    let db = setup_test_db().await;
+    let service_api = seed(&db, &[6001, 6002]).await;
+    let cache = Arc::new(CacheManager::memory_only());
+
+    let scheduler =
+        ApiKeySchedulerService::new(db.clone(), Arc::new(ApiKeyHealthService::new(db.clone())))
+            .with_daily_quota(ApiKeyDailyQuota::new(cache.clone(), Tz::UTC));
+
+    // 密钥 6001 今天已用完唯一的名额
+    let key = user_provider_keys::Entity::find_by_id(6001)
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    ApiKeyDailyQuota::new(cache, Tz::UTC)
+        .acquire(&key, Utc::now())
+        .await
+        .unwrap();
+
+    let selected = scheduler
+        .select_api_key_from_service_api(&service_api, &selection_context("quota-req-1"))
+        .await
+        .expect("key 6002 still has quota");
+    assert_eq!(selected.selected_key.id, 6002);
+
+    // 6002 用掉最后的名额后被标记为限流，直到下一次配额重置
+    let marked = user_provider_keys::Entity::find_by_id(6002)
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(marked.health_status, "rate_limited");
+    assert!(marked.rate_limit_resets_at.is_some());
+
+    let err = scheduler
+        .select_api_key_from_service_api(&service_api, &selection_context("quota-req-2"))
+        .await
+        .expect_err("all keys exhausted");
+    assert!(matches!(
+        err,
+        ProxyError::KeyPool(KeyPoolError::DailyQuotaExhausted {
+            service_api_id: SERVICE_API_ID
+        })
+    ));
+    assert_eq!(err.status_code().as_u16(), 429);
+}
@@ -0,0 +1,202 @@
+//! 密钥选择候选上限测试
+//!
+//! 开启 `max_candidates` 后只在按健康状态、权重排序的前 N 个密钥之间轮询，同层密钥随候选集
+//! 重新加载轮换进入；候选集中没有可用密钥时回退到全部密钥；上限可配置，0 表示不限制。
+
+mod common;
+
+use api_proxy::config::KeySelectionConfig;
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService, SelectionContext};
+use chrono::{Duration, Utc};
+use entity::{user_provider_keys, user_service_apis};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+const USER_ID: i32 = 3700;
+const PROVIDER_TYPE_ID: i32 = 480;
+const SERVICE_API_ID: i32 = 5600;
+/// 7700..=7705，其中 7701、7702 权重最高，7700 次之，其余权重相同
+const KEY_IDS: [i32; 6] = [7700, 7701, 7702, 7703, 7704, 7705];
+const HEAVY_KEY_IDS: [i32; 2] = [7701, 7702];
+const MEDIUM_KEY_ID: i32 = 7700;
+
+async fn setup() -> (Arc<DatabaseConnection>, user_service_apis::Model) {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(&db, common::provider(PROVIDER_TYPE_ID, "key_cap_provider")).await;
+
+    for key_id in KEY_IDS {
+        let weight = if HEAVY_KEY_IDS.contains(&key_id) {
+            5
+        } else if key_id == MEDIUM_KEY_ID {
+            2
+        } else {
+            1
+        };
+        common::insert_provider_key(
+            &db,
+            user_provider_keys::ActiveModel {
+                weight: Set(Some(weight)),
+                ..common::provider_key(key_id, USER_ID, PROVIDER_TYPE_ID)
+            },
+        )
+        .await;
+    }
+
+    common::insert_service_api(
+        &db,
+        user_service_apis::ActiveModel {
+            scheduling_strategy: Set(Some("round_robin".to_string())),
+            ..common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_TYPE_ID, &KEY_IDS)
+        },
+    )
+    .await;
+
+    let service_api = user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(&db)
+        .await
+        .expect("load service api")
+        .expect("service api exists");
+    (Arc::new(db), service_api)
+}
+
+fn scheduler(db: &Arc<DatabaseConnection>, max_candidates: usize) -> ApiKeySchedulerService {
+    ApiKeySchedulerService::new(db.clone(), Arc::new(ApiKeyHealthService::new(db.clone())))
+        .with_key_selection(KeySelectionConfig {
+            max_candidates,
+            candidate_cache_ms: 0,
+            new_key_warmup_secs: 0,
+        })
+}
+
+async fn selected_ids(
+    scheduler: &ApiKeySchedulerService,
+    service_api: &user_service_apis::Model,
+    rounds: usize,
+) -> BTreeSet<i32> {
+    let mut ids = BTreeSet::new();
+    for round in 0..rounds {
+        let context = SelectionContext::new(
+            format!("key-cap-req-{round}"),
+            USER_ID,
+            SERVICE_API_ID,
+            PROVIDER_TYPE_ID,
+            "/v1/chat/completions".to_string(),
+        );
+        let result = scheduler
+            .select_api_key_from_service_api(service_api, &context)
+            .await
+            .expect("select key");
+        ids.insert(result.selected_key.id);
+    }
+    ids
+}
+
+async fn update_key(db: &DatabaseConnection, key: user_provider_keys::ActiveModel) {
+    key.update(db).await.expect("update key");
+}
+
+#[tokio::test]
+async fn capped_selection_rotates_within_top_candidates() {
+    let (db, service_api) = setup().await;
+    let scheduler = scheduler(&db, 2);
+
+    assert_eq!(
+        selected_ids(&scheduler, &service_api, 6).await,
+        BTreeSet::from(HEAVY_KEY_IDS)
+    );
+
+    // 不健康的密钥排到候选集之外，由下一个健康密钥补位
+    update_key(
+        &db,
+        user_provider_keys::ActiveModel {
+            id: Set(7701),
+            health_status: Set("unhealthy".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(
+        selected_ids(&scheduler, &service_api, 6).await,
+        BTreeSet::from([MEDIUM_KEY_ID, 7702])
+    );
+}
+
+#[tokio::test]
+async fn capped_selection_rotates_tied_keys_across_reloads() {
+    let (db, service_api) = setup().await;
+    for key_id in KEY_IDS {
+        update_key(
+            &db,
+            user_provider_keys::ActiveModel {
+                id: Set(key_id),
+                weight: Set(Some(1)),
+                ..Default::default()
+            },
+        )
+        .await;
+    }
+    let scheduler = scheduler(&db, 2);
+
+    // 权重相同：每次重新加载候选集时起点后移，ID 靠后的密钥同样会被选中
+    let selected = selected_ids(&scheduler, &service_api, 6).await;
+    assert!(
+        selected.iter().any(|id| !KEY_IDS[..2].contains(id)),
+        "keys outside the first two should be selected: {selected:?}"
+    );
+    assert!(selected.len() >= 3, "{selected:?}");
+}
+
+#[tokio::test]
+async fn capped_set_without_usable_keys_falls_back_to_all_keys() {
+    let (db, service_api) = setup().await;
+    let scheduler = scheduler(&db, 2);
+
+    // 过期的密钥仍然健康、权重最高，会占满候选集
+    for key_id in HEAVY_KEY_IDS {
+        update_key(
+            &db,
+            user_provider_keys::ActiveModel {
+                id: Set(key_id),
+                expires_at: Set(Some(Utc::now().naive_utc() - Duration::hours(1))),
+                ..Default::default()
+            },
+        )
+        .await;
+    }
+
+    assert_eq!(
+        selected_ids(&scheduler, &service_api, 4).await,
+        BTreeSet::from([7700, 7703, 7704, 7705])
+    );
+}
+
+#[tokio::test]
+async fn cap_is_configurable() {
+    let (db, service_api) = setup().await;
+
+    assert_eq!(
+        selected_ids(&scheduler(&db, 0), &service_api, 6).await,
+        BTreeSet::from(KEY_IDS)
+    );
+    assert_eq!(
+        selected_ids(&scheduler(&db, 3), &service_api, 6).await,
+        BTreeSet::from([7700, 7701, 7702])
+    );
+
+    let config: KeySelectionConfig =
+        toml::from_str("max_candidates = 25\ncandidate_cache_ms = 500").expect("parse config");
+    assert_eq!(config.max_candidates(), Some(25));
+    assert!(config.validate().is_ok());
+    assert_eq!(KeySelectionConfig::default().max_candidates(), None);
+    assert!(
+        KeySelectionConfig {
+            max_candidates: 10,
+            candidate_cache_ms: 120_000,
+            new_key_warmup_secs: 0,
+        }
+        .validate()
+        .is_err()
+    );
+}
@@ -0,0 +1,154 @@
+//! 密钥标签测试
+//!
+//! 服务 API 配置 `key_tag` 后只在带有该标签的密钥之间选择；未配置时使用全部密钥；
+//! 没有带该标签的可用密钥时返回错误而不是退回到其他密钥。
+
+mod common;
+
+use api_proxy::error::ProxyError;
+use api_proxy::error::key_pool::KeyPoolError;
+use api_proxy::key_pool::key_tags::{key_has_tag, key_tags};
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService, SelectionContext};
+use entity::{user_provider_keys, user_service_apis};
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+const USER_ID: i32 = 4200;
+const PROVIDER_TYPE_ID: i32 = 520;
+const SERVICE_API_ID: i32 = 5900;
+const KEY_IDS: [i32; 4] = [7900, 7901, 7902, 7903];
+/// 7900、7902 带 `prod` 标签，7901 带 `dev` 标签，7903 没有标签
+const PROD_KEY_IDS: [i32; 2] = [7900, 7902];
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(&db, common::provider(PROVIDER_TYPE_ID, "key_tags_provider")).await;
+
+    for key_id in KEY_IDS {
+        let tags = match key_id {
+            7900 => Some(json!(["prod", "high-quota"])),
+            7901 => Some(json!(["dev"])),
+            7902 => Some(json!(["prod"])),
+            _ => None,
+        };
+        common::insert_provider_key(
+            &db,
+            user_provider_keys::ActiveModel {
+                tags: Set(tags),
+                ..common::provider_key(key_id, USER_ID, PROVIDER_TYPE_ID)
+            },
+        )
+        .await;
+    }
+
+    Arc::new(db)
+}
+
+async fn service_api(db: &DatabaseConnection, key_tag: Option<&str>) -> user_service_apis::Model {
+    common::insert_service_api(
+        db,
+        user_service_apis::ActiveModel {
+            scheduling_strategy: Set(Some("round_robin".to_string())),
+            key_tag: Set(key_tag.map(ToString::to_string)),
+            ..common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_TYPE_ID, &KEY_IDS)
+        },
+    )
+    .await;
+
+    user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(db)
+        .await
+        .expect("load service api")
+        .expect("service api exists")
+}
+
+fn scheduler(db: &Arc<DatabaseConnection>) -> ApiKeySchedulerService {
+    ApiKeySchedulerService::new(db.clone(), Arc::new(ApiKeyHealthService::new(db.clone())))
+}
+
+fn context(round: usize) -> SelectionContext {
+    SelectionContext::new(
+        format!("key-tags-req-{round}"),
+        USER_ID,
+        SERVICE_API_ID,
+        PROVIDER_TYPE_ID,
+        "/v1/chat/completions".to_string(),
+    )
+}
+
+async fn selected_ids(
+    scheduler: &ApiKeySchedulerService,
+    service_api: &user_service_apis::Model,
+    rounds: usize,
+) -> BTreeSet<i32> {
+    let mut ids = BTreeSet::new();
+    for round in 0..rounds {
+        let result = scheduler
+            .select_api_key_from_service_api(service_api, &context(round))
+            .await
+            .expect("select key");
+        ids.insert(result.selected_key.id);
+    }
+    ids
+}
+
+#[tokio::test]
+async fn tag_filters_candidate_keys() {
+    let db = setup().await;
+    let keys = user_provider_keys::Entity::find()
+        .all(&*db)
+        .await
+        .expect("load keys");
+    let prod: BTreeSet<i32> = keys
+        .iter()
+        .filter(|key| key_has_tag(key, " prod "))
+        .map(|key| key.id)
+        .collect();
+    assert_eq!(prod, BTreeSet::from(PROD_KEY_IDS));
+
+    let tagged = keys.iter().find(|key| key.id == 7900).expect("key 7900");
+    assert_eq!(key_tags(tagged), vec!["prod", "high-quota"]);
+    let untagged = keys.iter().find(|key| key.id == 7903).expect("key 7903");
+    assert!(key_tags(untagged).is_empty());
+}
+
+#[tokio::test]
+async fn service_api_restricted_to_tag_only_selects_matching_keys() {
+    let db = setup().await;
+    let service_api = service_api(&db, Some("prod")).await;
+
+    assert_eq!(
+        selected_ids(&scheduler(&db), &service_api, 8).await,
+        BTreeSet::from(PROD_KEY_IDS)
+    );
+}
+
+#[tokio::test]
+async fn service_api_without_tag_uses_all_keys() {
+    let db = setup().await;
+    let service_api = service_api(&db, None).await;
+
+    assert_eq!(
+        selected_ids(&scheduler(&db), &service_api, 8).await,
+        BTreeSet::from(KEY_IDS)
+    );
+}
+
+#[tokio::test]
+async fn tag_without_matching_keys_is_rejected() {
+    let db = setup().await;
+    let service_api = service_api(&db, Some("staging")).await;
+
+    let err = scheduler(&db)
+        .select_api_key_from_service_api(&service_api, &context(0))
+        .await
+        .expect_err("no key carries the tag");
+    assert!(matches!(
+        err,
+        ProxyError::KeyPool(KeyPoolError::NoKeysMatchingTag { service_api_id, ref tag })
+            if service_api_id == SERVICE_API_ID && tag == "staging"
+    ));
+}
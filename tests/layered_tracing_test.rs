@@ -219,6 +219,7 @@ async fn test_record_success_updates_trace() {
             total_tokens: Some(180),
             cache_create_tokens: Some(0),
             cache_read_tokens: Some(0),
+            estimated: false,
         },
         cost: CollectedCost {
             value: Some(2.5),
@@ -226,6 +227,9 @@ async fn test_record_success_updates_trace() {
         },
         duration_ms: 345,
         status_code: 200,
+        request_bytes: 512,
+        response_bytes: 2048,
+        response_metadata: None,
     };
 
     trace_manager
@@ -247,6 +251,8 @@ async fn test_record_success_updates_trace() {
     assert_eq!(record.tokens_total, Some(180));
     assert_eq!(record.cost, Some(2.5));
     assert_eq!(record.cost_currency, Some("USD".to_string()));
+    assert_eq!(record.request_bytes, Some(512));
+    assert_eq!(record.response_bytes, Some(2048));
     assert!(record.end_time.is_some());
     assert!(record.duration_ms.is_some());
 }
@@ -278,6 +284,8 @@ async fn test_record_failure_updates_trace() {
 
     let mut ctx = build_context(request_id);
     ctx.mark_trace_started();
+    ctx.request.body_received_size = 64;
+    ctx.response.body_received_size = 17;
 
     trace_manager.record_failure(None, 502, None, &ctx).await;
 
@@ -290,6 +298,8 @@ async fn test_record_failure_updates_trace() {
 
     assert_eq!(record.status_code, Some(502));
     assert!(!record.is_success);
+    assert_eq!(record.request_bytes, Some(64));
+    assert_eq!(record.response_bytes, Some(17));
     assert!(record.error_type.is_some());
     assert!(record.end_time.is_some());
 }
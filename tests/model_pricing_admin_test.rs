@@ -0,0 +1,187 @@
+//! 模型定价管理集成测试
+//!
+//! 覆盖阶梯连续性校验与定价缓存失效。
+
+mod common;
+
+use api_proxy::management::middleware::AuthContext;
+use api_proxy::management::services::{
+    CreateModelPricingRequest, CreatePricingTierRequest, PricingService, PricingTierInput,
+};
+use api_proxy::pricing::pricing_cache_generation;
+use chrono_tz::Asia::Shanghai;
+use entity::model_pricing_tiers;
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+use std::sync::Arc;
+
+const PROVIDER_TYPE_ID: i32 = 410;
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "pricing_admin_provider"),
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+const fn admin() -> AuthContext {
+    AuthContext {
+        user_id: 1,
+        is_admin: true,
+    }
+}
+
+fn tier(
+    token_type: &str,
+    min_tokens: i32,
+    max_tokens: Option<i32>,
+    price: f64,
+) -> PricingTierInput {
+    PricingTierInput {
+        token_type: token_type.to_string(),
+        min_tokens,
+        max_tokens,
+        price_per_token: price,
+    }
+}
+
+fn create_request(model_name: &str, tiers: Vec<PricingTierInput>) -> CreateModelPricingRequest {
+    CreateModelPricingRequest {
+        provider_type_id: PROVIDER_TYPE_ID,
+        model_name: model_name.to_string(),
+        description: None,
+        cost_currency: None,
+        tiers,
+    }
+}
+
+#[tokio::test]
+async fn create_model_with_continuous_tiers() {
+    let db = setup().await;
+    let service = PricingService::new(db.clone());
+    let generation = pricing_cache_generation();
+
+    let created = service
+        .create(
+            &admin(),
+            &create_request(
+                "tiered-model",
+                vec![
+                    tier("prompt", 0, Some(199_999), 0.000_001),
+                    tier("prompt", 200_000, None, 0.000_002),
+                    tier("completion", 0, None, 0.000_004),
+                ],
+            ),
+            Shanghai,
+        )
+        .await
+        .expect("create model pricing");
+
+    assert_eq!(created.model_name, "tiered-model");
+    assert_eq!(created.cost_currency, "USD");
+    assert_eq!(created.tiers.len(), 3);
+    assert!(pricing_cache_generation() > generation);
+
+    // 在无上限阶梯之后追加阶梯会与之重叠
+    let appended = service
+        .create_tier(
+            &admin(),
+            &CreatePricingTierRequest {
+                model_pricing_id: created.id,
+                token_type: "completion".to_string(),
+                min_tokens: 100_000,
+                max_tokens: None,
+                price_per_token: 0.000_008,
+            },
+            Shanghai,
+        )
+        .await;
+    assert!(appended.is_err());
+
+    // 删除首个阶梯会留下空档
+    let first_prompt_tier = created
+        .tiers
+        .iter()
+        .find(|item| item.token_type == "prompt" && item.min_tokens == 0)
+        .expect("first prompt tier");
+    assert!(
+        service
+            .delete_tier(&admin(), first_prompt_tier.id, Shanghai)
+            .await
+            .is_err()
+    );
+
+    let stored = model_pricing_tiers::Entity::find()
+        .count(db.as_ref())
+        .await
+        .expect("count tiers");
+    assert_eq!(stored, 3);
+}
+
+#[tokio::test]
+async fn reject_overlapping_or_gapped_tiers() {
+    let db = setup().await;
+    let service = PricingService::new(db.clone());
+
+    let overlapping = service
+        .create(
+            &admin(),
+            &create_request(
+                "overlap-model",
+                vec![
+                    tier("prompt", 0, Some(199_999), 0.000_001),
+                    tier("prompt", 100_000, None, 0.000_002),
+                ],
+            ),
+            Shanghai,
+        )
+        .await;
+    assert!(overlapping.is_err());
+
+    let gapped = service
+        .create(
+            &admin(),
+            &create_request(
+                "gap-model",
+                vec![
+                    tier("prompt", 0, Some(99_999), 0.000_001),
+                    tier("prompt", 200_000, None, 0.000_002),
+                ],
+            ),
+            Shanghai,
+        )
+        .await;
+    assert!(gapped.is_err());
+
+    let bounded = service
+        .create(
+            &admin(),
+            &create_request("bounded-model", vec![tier("prompt", 0, Some(99_999), 0.1)]),
+            Shanghai,
+        )
+        .await;
+    assert!(bounded.is_err());
+
+    // 非管理员不能修改定价
+    let user = AuthContext {
+        user_id: 2,
+        is_admin: false,
+    };
+    let forbidden = service
+        .create(
+            &user,
+            &create_request("user-model", vec![tier("prompt", 0, None, 0.1)]),
+            Shanghai,
+        )
+        .await;
+    assert!(forbidden.is_err());
+
+    let stored = model_pricing_tiers::Entity::find()
+        .count(db.as_ref())
+        .await
+        .expect("count tiers");
+    assert_eq!(stored, 0);
+}
@@ -0,0 +1,97 @@
+//! 模型级限流测试
+//!
+//! 服务商 `config_json.model_rate_limits` 为模型设置每分钟上限后，该模型的请求超限被拒绝，
+//! 同一服务商下的其他模型不受影响；计数与提供商全局限流相互独立。
+
+use api_proxy::auth::api_key_usage_limit_service::ApiKeyUsageLimitService;
+use api_proxy::cache::CacheManager;
+use api_proxy::proxy::model_rate_limit::ModelRateLimits;
+use chrono::Utc;
+use entity::provider_types;
+use sea_orm::Database;
+use std::sync::Arc;
+
+const PROVIDER_TYPE_ID: i32 = 530;
+
+fn provider(config_json: &str) -> provider_types::Model {
+    let now = Utc::now().naive_utc();
+    provider_types::Model {
+        id: PROVIDER_TYPE_ID,
+        name: "openai".to_string(),
+        display_name: "OpenAI".to_string(),
+        auth_type: "api_key".to_string(),
+        base_url: "https://api.openai.com".to_string(),
+        is_active: true,
+        config_json: Some(config_json.to_string()),
+        token_mappings_json: None,
+        model_extraction_json: None,
+        auth_configs_json: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+async fn limiter() -> ApiKeyUsageLimitService {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("connect test db");
+    ApiKeyUsageLimitService::new(Arc::new(CacheManager::memory_only()), Arc::new(db))
+}
+
+/// 模拟代理在转发前的检查：模型未配置上限时直接放行
+async fn admit(limiter: &ApiKeyUsageLimitService, limits: &ModelRateLimits, model: &str) -> bool {
+    let Some(limit) = limits.limit_for(model) else {
+        return true;
+    };
+    limiter
+        .check_model_per_minute(PROVIDER_TYPE_ID, model, i64::from(limit))
+        .await
+        .expect("model rate limit check")
+        .allowed
+}
+
+#[tokio::test]
+async fn rate_limited_model_is_throttled_while_other_model_is_unaffected() {
+    let provider = provider(r#"{"model_rate_limits": {"gpt-4o": {"requests_per_minute": 2}}}"#);
+    let limits = ModelRateLimits::from_provider(&provider).expect("model limits configured");
+    let limiter = limiter().await;
+
+    assert!(admit(&limiter, &limits, "gpt-4o").await);
+    assert!(admit(&limiter, &limits, "gpt-4o").await);
+    assert!(!admit(&limiter, &limits, "gpt-4o").await);
+
+    for _ in 0..5 {
+        assert!(admit(&limiter, &limits, "gpt-4o-mini").await);
+    }
+    assert!(!admit(&limiter, &limits, "gpt-4o").await);
+}
+
+#[tokio::test]
+async fn models_under_prefix_rule_are_counted_separately() {
+    let provider = provider(r#"{"model_rate_limits": {"o1*": {"requests_per_minute": 1}}}"#);
+    let limits = ModelRateLimits::from_provider(&provider).expect("model limits configured");
+    let limiter = limiter().await;
+
+    assert!(admit(&limiter, &limits, "o1-preview").await);
+    assert!(!admit(&limiter, &limits, "o1-preview").await);
+    assert!(admit(&limiter, &limits, "o1-mini").await);
+    assert!(!admit(&limiter, &limits, "o1-mini").await);
+}
+
+#[tokio::test]
+async fn invalid_or_missing_config_disables_model_limits() {
+    assert_eq!(
+        ModelRateLimits::from_provider(&provider(r#"{"max_tokens_ceiling": {"max": 4096}}"#)),
+        None
+    );
+    assert_eq!(
+        ModelRateLimits::from_provider(&provider(
+            r#"{"model_rate_limits": {"gpt-4o": {"requests_per_minute": "many"}}}"#
+        )),
+        None
+    );
+    assert_eq!(
+        ModelRateLimits::from_provider(&provider(r#"{"model_rate_limits": {}}"#)),
+        None
+    );
+}
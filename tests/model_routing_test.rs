@@ -0,0 +1,231 @@
+//! 按模型路由测试
+//!
+//! 验证服务 API 的 `model_routes` 把不同模型路由到各自的服务商与密钥，未命中规则时回退到默认服务商，
+//! 以及规则在写入时校验服务商与密钥的归属。
+
+mod common;
+
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService, SelectionContext};
+use api_proxy::management::services::ServiceApiService;
+use api_proxy::management::services::service_apis::{
+    CreateUserServiceKeyRequest, UpdateUserServiceKeyRequest,
+};
+use api_proxy::proxy::model_routing::ModelRoutes;
+use entity::{provider_types, user_service_apis};
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const USER_ID: i32 = 4500;
+const DEFAULT_PROVIDER_ID: i32 = 550;
+const ROUTED_PROVIDER_ID: i32 = 551;
+const INACTIVE_PROVIDER_ID: i32 = 552;
+const SERVICE_API_ID: i32 = 6200;
+const DEFAULT_KEY_ID: i32 = 8200;
+const ROUTED_KEY_ID: i32 = 8201;
+/// 属于路由服务商、但不在服务 API 密钥池中的密钥
+const DEDICATED_KEY_ID: i32 = 8202;
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+
+    for (provider_id, is_active) in [
+        (DEFAULT_PROVIDER_ID, true),
+        (ROUTED_PROVIDER_ID, true),
+        (INACTIVE_PROVIDER_ID, false),
+    ] {
+        common::insert_provider(
+            &db,
+            provider_types::ActiveModel {
+                is_active: Set(is_active),
+                ..common::provider(provider_id, &format!("routing_provider_{provider_id}"))
+            },
+        )
+        .await;
+    }
+
+    for (key_id, provider_id) in [
+        (DEFAULT_KEY_ID, DEFAULT_PROVIDER_ID),
+        (ROUTED_KEY_ID, ROUTED_PROVIDER_ID),
+        (DEDICATED_KEY_ID, ROUTED_PROVIDER_ID),
+    ] {
+        common::insert_provider_key(&db, common::provider_key(key_id, USER_ID, provider_id)).await;
+    }
+
+    let model_routes = json!([
+        {"pattern": "claude-3-haiku", "provider_type_id": ROUTED_PROVIDER_ID, "user_provider_keys_ids": [DEDICATED_KEY_ID]},
+        {"pattern": "claude-*", "provider_type_id": ROUTED_PROVIDER_ID}
+    ]);
+    common::insert_service_api(
+        &db,
+        user_service_apis::ActiveModel {
+            model_routes: Set(Some(model_routes)),
+            ..common::service_api(
+                SERVICE_API_ID,
+                USER_ID,
+                DEFAULT_PROVIDER_ID,
+                &[DEFAULT_KEY_ID, ROUTED_KEY_ID],
+            )
+        },
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+async fn service_api(db: &DatabaseConnection) -> user_service_apis::Model {
+    user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(db)
+        .await
+        .expect("load service api")
+        .expect("service api exists")
+}
+
+/// 按认证阶段的方式选择密钥：命中规则时固定服务商并使用规则的密钥
+async fn select_key(db: &Arc<DatabaseConnection>, model: &str) -> (i32, i32) {
+    let api = service_api(db).await;
+    let scheduler =
+        ApiKeySchedulerService::new(db.clone(), Arc::new(ApiKeyHealthService::new(db.clone())));
+    let route = ModelRoutes::of(&api).and_then(|routes| routes.route_for(model).cloned());
+    let (provider_id, key_ids) = route.map_or((api.provider_type_id, None), |route| {
+        (route.provider_type_id, route.user_provider_keys_ids)
+    });
+
+    let context = SelectionContext::new(
+        format!("model-routing-{model}"),
+        USER_ID,
+        SERVICE_API_ID,
+        provider_id,
+        "/v1/chat/completions".to_string(),
+    )
+    .with_pinned_provider(true)
+    .with_key_ids(key_ids);
+    let selected = scheduler
+        .select_api_key_from_service_api(&api, &context)
+        .await
+        .expect("select key")
+        .selected_key;
+    (provider_id, selected.id)
+}
+
+fn create_request(model_routes: Value) -> CreateUserServiceKeyRequest {
+    serde_json::from_value(json!({
+        "name": "model routing",
+        "provider_type_id": DEFAULT_PROVIDER_ID,
+        "user_provider_keys_ids": [DEFAULT_KEY_ID, ROUTED_KEY_ID],
+        "model_routes": model_routes
+    }))
+    .expect("create request")
+}
+
+fn update_request(body: Value) -> UpdateUserServiceKeyRequest {
+    serde_json::from_value(body).expect("update request")
+}
+
+#[tokio::test]
+async fn models_route_to_distinct_providers() {
+    let db = setup().await;
+
+    assert_eq!(
+        select_key(&db, "claude-3-5-sonnet").await,
+        (ROUTED_PROVIDER_ID, ROUTED_KEY_ID)
+    );
+    // 更具体的规则排在前面，使用规则指定的密钥而非服务 API 的密钥池
+    assert_eq!(
+        select_key(&db, "claude-3-haiku").await,
+        (ROUTED_PROVIDER_ID, DEDICATED_KEY_ID)
+    );
+}
+
+#[tokio::test]
+async fn unmatched_model_falls_back_to_default_provider() {
+    let db = setup().await;
+    let api = service_api(&db).await;
+    let routes = ModelRoutes::of(&api).expect("routes configured");
+    assert!(routes.route_for("gpt-4o").is_none());
+
+    assert_eq!(
+        select_key(&db, "gpt-4o").await,
+        (DEFAULT_PROVIDER_ID, DEFAULT_KEY_ID)
+    );
+
+    // 未配置规则时所有模型都使用默认服务商
+    let mut api = api;
+    api.model_routes = None;
+    assert!(ModelRoutes::of(&api).is_none());
+}
+
+#[tokio::test]
+async fn invalid_routes_are_rejected_at_write_time() {
+    let db = setup().await;
+    let service = ServiceApiService::from_db(&db);
+    let timezone = chrono_tz::UTC;
+
+    for invalid in [
+        // 服务商不存在
+        json!([{"pattern": "claude-*", "provider_type_id": 999}]),
+        // 服务商已停用
+        json!([{"pattern": "claude-*", "provider_type_id": INACTIVE_PROVIDER_ID}]),
+        // 密钥不属于规则的服务商
+        json!([{"pattern": "claude-*", "provider_type_id": ROUTED_PROVIDER_ID, "user_provider_keys_ids": [DEFAULT_KEY_ID]}]),
+        // 格式错误
+        json!([{"pattern": "claude-*-opus", "provider_type_id": ROUTED_PROVIDER_ID}]),
+    ] {
+        assert!(
+            service
+                .create(USER_ID, &create_request(invalid.clone()), &timezone)
+                .await
+                .is_err(),
+            "{invalid}"
+        );
+    }
+
+    // 未指定密钥时，密钥池中必须有该服务商的密钥
+    assert!(
+        service
+            .update(
+                SERVICE_API_ID,
+                USER_ID,
+                &update_request(json!({
+                    "user_provider_keys_ids": [DEFAULT_KEY_ID],
+                    "model_routes": [{"pattern": "claude-*", "provider_type_id": ROUTED_PROVIDER_ID}]
+                })),
+            )
+            .await
+            .is_err()
+    );
+
+    let created = service
+        .create(
+            USER_ID,
+            &create_request(json!([
+                {"pattern": " claude-* ", "provider_type_id": ROUTED_PROVIDER_ID}
+            ])),
+            &timezone,
+        )
+        .await
+        .expect("valid routes accepted");
+    let detail = service
+        .detail(created.id, USER_ID, &timezone)
+        .await
+        .expect("detail");
+    assert_eq!(
+        detail.model_routes,
+        Some(json!([{"pattern": "claude-*", "provider_type_id": ROUTED_PROVIDER_ID}]))
+    );
+
+    service
+        .update(
+            created.id,
+            USER_ID,
+            &update_request(json!({"model_routes": null})),
+        )
+        .await
+        .expect("clear routes");
+    let detail = service
+        .detail(created.id, USER_ID, &timezone)
+        .await
+        .expect("detail");
+    assert!(detail.model_routes.is_none());
+}
@@ -0,0 +1,186 @@
+//! 按模型拆分用量统计测试
+//!
+//! 两个模型与未识别模型的追踪按 `model_used` 汇总请求数、Token 与费用，
+//! 时间区间按调用方时区解析，区间外的追踪不计入。
+
+mod common;
+
+use api_proxy::management::services::StatisticsService;
+use api_proxy::management::services::statistics::{TimeRangeQuery, UNKNOWN_MODEL};
+use api_proxy::types::TimezoneContext;
+use chrono::{NaiveDate, NaiveDateTime};
+use chrono_tz::Asia::Shanghai;
+use entity::proxy_tracing;
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+
+const USER_ID: i32 = 2900;
+const PROVIDER_TYPE_ID: i32 = 390;
+const SERVICE_API_ID: i32 = 4900;
+
+/// UTC 时间
+fn utc(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 11, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+struct TraceSeed {
+    model: Option<&'static str>,
+    is_success: bool,
+    tokens: (i32, i32),
+    cost: f64,
+    created_at: NaiveDateTime,
+}
+
+async fn setup(traces: &[TraceSeed]) -> DatabaseConnection {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "breakdown_provider"),
+    )
+    .await;
+    common::insert_service_api(
+        &db,
+        common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_TYPE_ID, &[]),
+    )
+    .await;
+
+    for (index, trace) in traces.iter().enumerate() {
+        let (prompt, completion) = trace.tokens;
+        proxy_tracing::Entity::insert(proxy_tracing::ActiveModel {
+            user_service_api_id: Set(SERVICE_API_ID),
+            request_id: Set(format!("req-breakdown-{index}")),
+            method: Set("POST".to_string()),
+            path: Set(Some("/v1/chat/completions".to_string())),
+            status_code: Set(Some(if trace.is_success { 200 } else { 500 })),
+            tokens_prompt: Set(Some(prompt)),
+            tokens_completion: Set(Some(completion)),
+            tokens_total: Set(Some(prompt + completion)),
+            cost: Set(Some(trace.cost)),
+            user_id: Set(Some(USER_ID)),
+            provider_type_id: Set(Some(PROVIDER_TYPE_ID)),
+            model_used: Set(trace.model.map(str::to_string)),
+            is_success: Set(trace.is_success),
+            created_at: Set(trace.created_at),
+            ..Default::default()
+        })
+        .exec(&db)
+        .await
+        .expect("insert trace");
+    }
+    db
+}
+
+#[tokio::test]
+async fn traces_are_rolled_up_per_model() {
+    // 上海时间 2025-11-18 全天 = UTC 11-17 16:00 至 11-18 16:00
+    let db = setup(&[
+        TraceSeed {
+            model: Some("gpt-4o"),
+            is_success: true,
+            tokens: (100, 50),
+            cost: 0.5,
+            created_at: utc(17, 23, 0),
+        },
+        TraceSeed {
+            model: Some("gpt-4o"),
+            is_success: false,
+            tokens: (40, 0),
+            cost: 0.1,
+            created_at: utc(18, 2, 0),
+        },
+        TraceSeed {
+            model: Some("claude-sonnet-4"),
+            is_success: true,
+            tokens: (200, 80),
+            cost: 1.25,
+            created_at: utc(18, 3, 0),
+        },
+        TraceSeed {
+            model: None,
+            is_success: false,
+            tokens: (0, 0),
+            cost: 0.0,
+            created_at: utc(18, 4, 0),
+        },
+        TraceSeed {
+            model: Some("  "),
+            is_success: false,
+            tokens: (0, 0),
+            cost: 0.0,
+            created_at: utc(18, 5, 0),
+        },
+        // 上海时间 11-17 23:30，不在区间内
+        TraceSeed {
+            model: Some("gpt-4o"),
+            is_success: true,
+            tokens: (1000, 1000),
+            cost: 9.0,
+            created_at: utc(17, 15, 30),
+        },
+    ])
+    .await;
+
+    let response = StatisticsService::from_db(&db)
+        .models_breakdown(
+            USER_ID,
+            &TimeRangeQuery {
+                range: None,
+                start: Some("2025-11-18".to_string()),
+                end: Some("2025-11-18".to_string()),
+            },
+            &TimezoneContext { timezone: Shanghai },
+        )
+        .await
+        .expect("models breakdown");
+
+    assert_eq!(response.start, "2025-11-18T00:00:00+08:00");
+    assert_eq!(response.end, "2025-11-19T00:00:00+08:00");
+    assert_eq!(response.models.len(), 3);
+
+    let model = |name: &str| {
+        response
+            .models
+            .iter()
+            .find(|entry| entry.model == name)
+            .unwrap_or_else(|| panic!("missing model {name}"))
+    };
+
+    let gpt = model("gpt-4o");
+    assert_eq!(gpt.requests, 2);
+    assert_eq!(gpt.successful_requests, 1);
+    assert_eq!(gpt.failed_requests, 1);
+    assert_eq!(gpt.tokens_prompt, 140);
+    assert_eq!(gpt.tokens_completion, 50);
+    assert_eq!(gpt.tokens_total, 190);
+    assert!((gpt.cost - 0.6).abs() < 1e-9);
+
+    let claude = model("claude-sonnet-4");
+    assert_eq!(claude.requests, 1);
+    assert_eq!(claude.tokens_total, 280);
+    assert!((claude.cost - 1.25).abs() < 1e-9);
+
+    let unknown = model(UNKNOWN_MODEL);
+    assert_eq!(unknown.requests, 2);
+    assert_eq!(unknown.failed_requests, 2);
+    assert_eq!(unknown.tokens_total, 0);
+}
+
+#[tokio::test]
+async fn invalid_range_is_rejected() {
+    let db = setup(&[]).await;
+    let result = StatisticsService::from_db(&db)
+        .models_breakdown(
+            USER_ID,
+            &TimeRangeQuery {
+                range: None,
+                start: Some("2025-11-20".to_string()),
+                end: Some("2025-11-18".to_string()),
+            },
+            &TimezoneContext { timezone: Shanghai },
+        )
+        .await;
+    assert!(result.is_err());
+}
@@ -5,11 +5,19 @@
 //! 2. URL 参数不重复
 //! 3. PKCE 参数按开关添加
 //! 4. 配置参数可覆盖基础参数（如 `response_type`）
+//! 5. 回调地址选择与 PKCE 方法
+//! 6. 数据库中的 PKCE 方法只接受 `plain`/`S256`
 
+use api_proxy::auth::pkce::ChallengeMethod;
 use api_proxy::auth::types::{OAuthAuthorizeConfig, OAuthProviderConfig, OAuthTokenConfig};
-use api_proxy::provider::{ProviderConfigBuilder, build_authorize_url};
+use api_proxy::provider::{ApiKeyProviderConfig, ProviderConfigBuilder, build_authorize_url};
 use entity::oauth_client_sessions::Model;
+use entity::provider_types;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, EntityTrait, Set};
+use serde_json::json;
 use std::collections::HashMap;
+use std::sync::Arc;
 use url::Url;
 
 fn create_test_session() -> Model {
@@ -21,6 +29,7 @@ fn create_test_session() -> Model {
         provider_type_id: Some(1),
         code_verifier: "test_code_verifier_012".to_string(),
         code_challenge: "test_code_challenge_789".to_string(),
+        redirect_uri: None,
         state: "test_state_456".to_string(),
         name: "Test OpenAI Session".to_string(),
         description: Some("Test session for OAuth flow".to_string()),
@@ -44,8 +53,10 @@ fn create_openai_config() -> OAuthProviderConfig {
         client_id: "test_client_id".to_string(),
         client_secret: Some("test_client_secret".to_string()),
         redirect_uri: "http://localhost:1455/auth/callback".to_string(),
+        redirect_uris: Vec::new(),
         scopes: "openid profile email offline_access".to_string(),
         pkce_required: true,
+        pkce_method: ChallengeMethod::S256,
         authorize: OAuthAuthorizeConfig {
             url: "https://auth.openai.com/oauth/authorize".to_string(),
             method: "GET".to_string(),
@@ -240,3 +251,107 @@ fn test_oauth_config_builder() {
         Some(&serde_json::Value::String("custom_value".to_string()))
     );
 }
+
+#[tokio::test]
+async fn test_select_configured_redirect_uri() {
+    let mut config = create_openai_config();
+    config.redirect_uris = vec!["http://localhost:8080/auth/callback".to_string()];
+
+    assert_eq!(
+        config.select_redirect_uri(None).unwrap(),
+        "http://localhost:1455/auth/callback"
+    );
+    assert_eq!(
+        config
+            .select_redirect_uri(Some("http://localhost:8080/auth/callback"))
+            .unwrap(),
+        "http://localhost:8080/auth/callback"
+    );
+    assert!(
+        config
+            .select_redirect_uri(Some("https://evil.example.com/callback"))
+            .is_err()
+    );
+
+    // 未配置默认地址时回退到列表第一项
+    config.redirect_uri = String::new();
+    assert_eq!(
+        config.select_redirect_uri(None).unwrap(),
+        "http://localhost:8080/auth/callback"
+    );
+}
+
+#[tokio::test]
+async fn test_oauth_url_uses_session_redirect_uri_and_pkce_method() {
+    let mut session = create_test_session();
+    session.redirect_uri = Some("http://localhost:8080/auth/callback".to_string());
+    let mut config = create_openai_config();
+    config.redirect_uris = vec!["http://localhost:8080/auth/callback".to_string()];
+    config.pkce_method = ChallengeMethod::Plain;
+
+    let url = build_authorize_url(&config, &session).unwrap();
+    let parsed_url = Url::parse(&url).unwrap();
+    let params: HashMap<String, String> = parsed_url.query_pairs().into_owned().collect();
+
+    assert_eq!(
+        params.get("redirect_uri"),
+        Some(&"http://localhost:8080/auth/callback".to_string())
+    );
+    assert_eq!(
+        params.get("code_challenge_method"),
+        Some(&"plain".to_string())
+    );
+}
+
+/// 写入指定 `pkce_method` 的 OAuth 提供商后加载配置
+async fn load_config_with_pkce_method(
+    method: &str,
+) -> api_proxy::error::Result<OAuthProviderConfig> {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let now = chrono::Utc::now().naive_utc();
+    let flow = json!({"url": "https://auth.example.com/oauth/token", "method": "POST"});
+    provider_types::Entity::insert(provider_types::ActiveModel {
+        name: Set("pkce_provider".to_string()),
+        display_name: Set("PKCE Provider".to_string()),
+        auth_type: Set("oauth".to_string()),
+        base_url: Set("https://api.example.com".to_string()),
+        is_active: Set(true),
+        auth_configs_json: Set(Some(
+            json!({
+                "client_id": "client",
+                "scopes": "openid",
+                "pkce_required": true,
+                "pkce_method": method,
+                "authorize": {"url": "https://auth.example.com/oauth/authorize", "method": "GET"},
+                "exchange": flow,
+                "refresh": flow,
+            })
+            .to_string(),
+        )),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&db)
+    .await
+    .unwrap();
+
+    ApiKeyProviderConfig::new(Arc::new(db))
+        .get_config("pkce_provider:oauth")
+        .await
+}
+
+#[tokio::test]
+async fn test_pkce_method_from_db_is_validated() {
+    let plain = load_config_with_pkce_method("PLAIN").await.unwrap();
+    assert_eq!(plain.pkce_method, ChallengeMethod::Plain);
+    let s256 = load_config_with_pkce_method("s256").await.unwrap();
+    assert_eq!(s256.pkce_method, ChallengeMethod::S256);
+
+    let err = load_config_with_pkce_method("S512").await.unwrap_err();
+    assert!(
+        matches!(err, api_proxy::error::ProxyError::Config(_)),
+        "{err:?}"
+    );
+}
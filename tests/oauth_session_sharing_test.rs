@@ -0,0 +1,183 @@
+//! OAuth 会话共享策略测试
+//!
+//! `strict` 下同一会话不能被第二个有效密钥引用；`shared` 下允许共用，
+//! 刷新队列中该会话始终只有一项，最后一个引用它的密钥删除后才移出。
+
+mod common;
+
+use api_proxy::auth::api_key_oauth_refresh_service::ApiKeyOAuthRefreshService;
+use api_proxy::auth::api_key_oauth_state_service::ApiKeyOAuthStateService;
+use api_proxy::auth::api_key_oauth_token_refresh_task::ApiKeyOAuthTokenRefreshTask;
+use api_proxy::config::OAuthSessionPolicy;
+use api_proxy::management::services::{CreateProviderKeyRequest, ProviderKeyService};
+use api_proxy::provider::ApiKeyProviderConfig;
+use api_proxy::types::TimezoneContext;
+use chrono::{Duration, Utc};
+use chrono_tz::Asia::Shanghai;
+use entity::{oauth_client_sessions, provider_types};
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use std::sync::Arc;
+
+const USER_ID: i32 = 4700;
+const PROVIDER_TYPE_ID: i32 = 570;
+const SESSION_ID: &str = "oauth-shared-session";
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(
+        &db,
+        provider_types::ActiveModel {
+            auth_type: Set("oauth".to_string()),
+            ..common::provider(PROVIDER_TYPE_ID, "oauth_sharing_provider")
+        },
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+/// 插入已授权、一天后过期的会话（刷新时间远在测试结束之后）
+async fn insert_authorized_session(db: &DatabaseConnection) {
+    let now = Utc::now().naive_utc();
+    oauth_client_sessions::Entity::insert(oauth_client_sessions::ActiveModel {
+        session_id: Set(SESSION_ID.to_string()),
+        user_id: Set(USER_ID),
+        provider_name: Set("oauth_sharing_provider".to_string()),
+        provider_type_id: Set(Some(PROVIDER_TYPE_ID)),
+        code_verifier: Set("verifier".to_string()),
+        code_challenge: Set("challenge".to_string()),
+        state: Set("state".to_string()),
+        name: Set("Shared Session".to_string()),
+        status: Set("authorized".to_string()),
+        access_token: Set(Some("access-token".to_string())),
+        refresh_token: Set(Some("refresh-token".to_string())),
+        expires_at: Set(now + Duration::days(1)),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .expect("insert oauth session");
+}
+
+/// 启动刷新任务；启动时会清理未被密钥引用的会话，因此需在插入会话前启动
+async fn start_refresh_task(db: &Arc<DatabaseConnection>) -> Arc<ApiKeyOAuthTokenRefreshTask> {
+    let state = Arc::new(ApiKeyOAuthStateService::new(db.clone()));
+    let refresh = Arc::new(ApiKeyOAuthRefreshService::new(
+        reqwest::Client::new(),
+        state.clone(),
+        Arc::new(ApiKeyProviderConfig::new(db.clone())),
+    ));
+    let task = Arc::new(ApiKeyOAuthTokenRefreshTask::new(refresh, state));
+    task.start().await.expect("start refresh task");
+    task
+}
+
+const fn timezone() -> TimezoneContext {
+    TimezoneContext { timezone: Shanghai }
+}
+
+fn oauth_key(name: &str) -> CreateProviderKeyRequest {
+    CreateProviderKeyRequest {
+        provider_type_id: PROVIDER_TYPE_ID,
+        name: name.to_string(),
+        api_key: Some(SESSION_ID.to_string()),
+        auth_type: "oauth".to_string(),
+        weight: Some(1),
+        max_requests_per_minute: None,
+        max_tokens_prompt_per_minute: None,
+        max_requests_per_day: None,
+        is_active: Some(true),
+        project_id: None,
+        tags: None,
+    }
+}
+
+async fn create_key(service: &ProviderKeyService<'_>, name: &str) -> i32 {
+    let response = service
+        .create(USER_ID, &timezone(), &oauth_key(name))
+        .await
+        .expect("create oauth key");
+    let id = response.data["id"].as_i64().expect("created key id");
+    i32::try_from(id).expect("key id fits i32")
+}
+
+#[tokio::test]
+async fn strict_policy_rejects_session_reuse() {
+    let db = setup().await;
+    insert_authorized_session(&db).await;
+    let service =
+        ProviderKeyService::from_db(db.as_ref()).with_session_policy(OAuthSessionPolicy::Strict);
+
+    let first = create_key(&service, "Strict Key A").await;
+    let err = service
+        .create(USER_ID, &timezone(), &oauth_key("Strict Key B"))
+        .await
+        .expect_err("session already used");
+    assert!(
+        err.to_string().contains("已被其他provider key使用"),
+        "{err}"
+    );
+
+    // 原密钥删除后会话重新可用
+    service
+        .delete(USER_ID, &timezone(), first)
+        .await
+        .expect("delete first key");
+    create_key(&service, "Strict Key B").await;
+}
+
+#[tokio::test]
+async fn shared_policy_allows_reuse_without_duplicate_schedules() {
+    let db = setup().await;
+    let task = start_refresh_task(&db).await;
+    insert_authorized_session(&db).await;
+    let service = ProviderKeyService::from_db(db.as_ref())
+        .with_refresh_task(task.clone())
+        .with_session_policy(OAuthSessionPolicy::Shared);
+
+    let first = create_key(&service, "Shared Key A").await;
+    let second = create_key(&service, "Shared Key B").await;
+    assert_ne!(first, second);
+    assert_eq!(
+        task.scheduled_sessions().await.expect("scheduled sessions"),
+        [SESSION_ID]
+    );
+
+    // 仍有密钥引用时保留刷新计划
+    service
+        .delete(USER_ID, &timezone(), first)
+        .await
+        .expect("delete first key");
+    assert_eq!(
+        task.scheduled_sessions().await.expect("scheduled sessions"),
+        [SESSION_ID]
+    );
+
+    // 恢复时会话已由另一密钥调度，不重复入队
+    service
+        .restore(USER_ID, &timezone(), first)
+        .await
+        .expect("restore first key");
+    assert_eq!(
+        task.scheduled_sessions().await.expect("scheduled sessions"),
+        [SESSION_ID]
+    );
+
+    for key_id in [first, second] {
+        service
+            .delete(USER_ID, &timezone(), key_id)
+            .await
+            .expect("delete key");
+    }
+    assert!(
+        task.scheduled_sessions()
+            .await
+            .expect("scheduled sessions")
+            .is_empty()
+    );
+
+    task.stop().await.expect("stop refresh task");
+}
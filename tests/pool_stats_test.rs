@@ -0,0 +1,179 @@
+//! 密钥池状态汇总测试
+//!
+//! 构造健康、限流（冷却中与已到期）、不健康、停用与已删除的密钥，
+//! 验证按服务商汇总的计数以及普通用户只能查看自己的密钥。
+
+mod common;
+
+use api_proxy::key_pool::PoolStats;
+use api_proxy::management::middleware::AuthContext;
+use api_proxy::management::services::{PoolHealthService, PoolStatsQuery};
+use chrono::{Duration, NaiveDateTime, Utc};
+use entity::user_provider_keys;
+use sea_orm::{DatabaseConnection, Set};
+use std::sync::Arc;
+
+const OWNER_ID: i32 = 3400;
+const OTHER_USER_ID: i32 = 3401;
+const PROVIDER_TYPE_ID: i32 = 450;
+const OTHER_PROVIDER_TYPE_ID: i32 = 451;
+const FIRST_KEY_ID: i32 = 7500;
+
+struct KeySpec {
+    user_id: i32,
+    provider_type_id: i32,
+    is_active: bool,
+    health_status: &'static str,
+    rate_limit_resets_at: Option<NaiveDateTime>,
+    deleted_at: Option<NaiveDateTime>,
+}
+
+impl KeySpec {
+    const fn new(health_status: &'static str) -> Self {
+        Self {
+            user_id: OWNER_ID,
+            provider_type_id: PROVIDER_TYPE_ID,
+            is_active: true,
+            health_status,
+            rate_limit_resets_at: None,
+            deleted_at: None,
+        }
+    }
+}
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    let now = Utc::now().naive_utc();
+
+    common::insert_user(&db, common::user(OWNER_ID)).await;
+    common::insert_user(&db, common::user(OTHER_USER_ID)).await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "pool_stats_primary"),
+    )
+    .await;
+    common::insert_provider(
+        &db,
+        common::provider(OTHER_PROVIDER_TYPE_ID, "pool_stats_secondary"),
+    )
+    .await;
+
+    let keys = [
+        KeySpec::new("healthy"),
+        KeySpec::new("healthy"),
+        KeySpec {
+            rate_limit_resets_at: Some(now + Duration::minutes(5)),
+            ..KeySpec::new("rate_limited")
+        },
+        KeySpec {
+            rate_limit_resets_at: Some(now - Duration::minutes(5)),
+            ..KeySpec::new("rate_limited")
+        },
+        KeySpec::new("unhealthy"),
+        KeySpec {
+            is_active: false,
+            ..KeySpec::new("healthy")
+        },
+        KeySpec {
+            deleted_at: Some(now),
+            ..KeySpec::new("healthy")
+        },
+        KeySpec {
+            user_id: OTHER_USER_ID,
+            provider_type_id: OTHER_PROVIDER_TYPE_ID,
+            ..KeySpec::new("healthy")
+        },
+    ];
+    for (key_id, key) in (FIRST_KEY_ID..).zip(keys) {
+        common::insert_provider_key(
+            &db,
+            user_provider_keys::ActiveModel {
+                is_active: Set(key.is_active),
+                health_status: Set(key.health_status.to_string()),
+                rate_limit_resets_at: Set(key.rate_limit_resets_at),
+                deleted_at: Set(key.deleted_at),
+                ..common::provider_key(key_id, key.user_id, key.provider_type_id)
+            },
+        )
+        .await;
+    }
+
+    Arc::new(db)
+}
+
+#[tokio::test]
+async fn admin_sees_aggregated_counts_per_provider() {
+    let service = PoolHealthService::new(setup().await);
+    let admin = AuthContext {
+        user_id: 1,
+        is_admin: true,
+    };
+
+    let pools = service
+        .pool_stats(&admin, &PoolStatsQuery::default())
+        .await
+        .expect("pool stats");
+
+    assert_eq!(pools.len(), 2);
+    assert_eq!(pools[0].provider_type_id, PROVIDER_TYPE_ID);
+    assert_eq!(pools[0].provider_name, "pool_stats_primary");
+    assert_eq!(
+        pools[0].stats,
+        PoolStats {
+            total: 6,
+            active: 5,
+            healthy: 2,
+            rate_limited: 2,
+            cooling_down: 1,
+            unhealthy: 1,
+        }
+    );
+    assert_eq!(pools[1].provider_type_id, OTHER_PROVIDER_TYPE_ID);
+    assert_eq!(pools[1].stats.total, 1);
+    assert_eq!(pools[1].stats.healthy, 1);
+
+    let filtered = service
+        .pool_stats(
+            &admin,
+            &PoolStatsQuery {
+                user_id: Some(OTHER_USER_ID),
+            },
+        )
+        .await
+        .expect("pool stats for user");
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].provider_type_id, OTHER_PROVIDER_TYPE_ID);
+
+    let json = serde_json::to_value(&pools[0]).expect("serialize");
+    assert_eq!(json["cooling_down"], 1);
+    assert_eq!(json["display_name"], "pool_stats_primary display");
+}
+
+#[tokio::test]
+async fn regular_user_only_sees_own_keys() {
+    let service = PoolHealthService::new(setup().await);
+    let owner = AuthContext {
+        user_id: OWNER_ID,
+        is_admin: false,
+    };
+
+    let pools = service
+        .pool_stats(&owner, &PoolStatsQuery::default())
+        .await
+        .expect("pool stats");
+    assert_eq!(pools.len(), 1);
+    assert_eq!(pools[0].provider_type_id, PROVIDER_TYPE_ID);
+    assert_eq!(pools[0].stats.total, 6);
+
+    assert!(
+        service
+            .pool_stats(
+                &owner,
+                &PoolStatsQuery {
+                    user_id: Some(OTHER_USER_ID),
+                },
+            )
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,164 @@
+//! 用户服务商封禁测试
+//!
+//! 管理员封禁的服务商在代理端以 403 拒绝，未封禁的服务商照常放行。
+
+mod common;
+
+use api_proxy::auth::api_key_manager::ApiKeyManager;
+use api_proxy::auth::api_key_usage_limit_service::ApiKeyUsageLimitService;
+use api_proxy::auth::jwt::JwtManager;
+use api_proxy::auth::service::ApiKeyAuthenticationService;
+use api_proxy::auth::types::AuthConfig;
+use api_proxy::cache::CacheManager;
+use api_proxy::config::CacheConfig;
+use api_proxy::error::reject::RejectReason;
+use api_proxy::error::{ProxyError, auth::AuthError};
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService};
+use api_proxy::management::middleware::AuthContext;
+use api_proxy::management::services::{ProviderBlocksService, SetProviderBlocksRequest};
+use api_proxy::proxy::AuthenticationService;
+use entity::{provider_types, user_provider_blocks};
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+use std::sync::Arc;
+
+const USER_ID: i32 = 3200;
+const BLOCKED_PROVIDER_ID: i32 = 430;
+const ALLOWED_PROVIDER_ID: i32 = 431;
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+
+    for provider_id in [BLOCKED_PROVIDER_ID, ALLOWED_PROVIDER_ID] {
+        common::insert_provider(
+            &db,
+            common::provider(provider_id, &format!("block_provider_{provider_id}")),
+        )
+        .await;
+    }
+
+    Arc::new(db)
+}
+
+fn authentication(db: &Arc<DatabaseConnection>) -> AuthenticationService {
+    let cache = Arc::new(CacheManager::memory_only());
+    let scheduler = Arc::new(ApiKeySchedulerService::new(
+        db.clone(),
+        Arc::new(ApiKeyHealthService::new(db.clone())),
+    ));
+    let auth_service = Arc::new(ApiKeyAuthenticationService::new(
+        Arc::new(JwtManager::new(&AuthConfig::default()).expect("jwt manager")),
+        Arc::new(ApiKeyManager::new(
+            db.clone(),
+            cache.clone(),
+            Arc::new(CacheConfig::default()),
+        )),
+        db.clone(),
+    ));
+    AuthenticationService::new(
+        auth_service,
+        db.clone(),
+        cache.clone(),
+        scheduler,
+        Arc::new(ApiKeyUsageLimitService::new(cache, db.clone())),
+    )
+}
+
+const fn admin() -> AuthContext {
+    AuthContext {
+        user_id: 1,
+        is_admin: true,
+    }
+}
+
+async fn provider(db: &DatabaseConnection, id: i32) -> provider_types::Model {
+    provider_types::Entity::find_by_id(id)
+        .one(db)
+        .await
+        .expect("load provider")
+        .expect("provider exists")
+}
+
+#[tokio::test]
+async fn blocked_provider_is_rejected_and_allowed_one_proceeds() {
+    let db = setup().await;
+    let authentication = authentication(&db);
+    let blocks = ProviderBlocksService::new(db.clone());
+
+    let listed = blocks
+        .set(
+            &admin(),
+            USER_ID,
+            &SetProviderBlocksRequest {
+                provider_type_ids: vec![BLOCKED_PROVIDER_ID, BLOCKED_PROVIDER_ID],
+            },
+        )
+        .await
+        .expect("set provider blocks");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].provider_type_id, BLOCKED_PROVIDER_ID);
+
+    let blocked = provider(db.as_ref(), BLOCKED_PROVIDER_ID).await;
+    let err = authentication
+        .ensure_provider_not_blocked(USER_ID, &blocked, "block-denied")
+        .await
+        .unwrap_err();
+    let ProxyError::Authentication(auth_err) = &err else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert!(matches!(auth_err, AuthError::ProviderNotAllowed(_)));
+    assert_eq!(
+        RejectReason::from_auth_error(auth_err),
+        RejectReason::ProviderNotAllowed
+    );
+    assert_eq!(err.status_code(), http::StatusCode::FORBIDDEN);
+
+    let allowed = provider(db.as_ref(), ALLOWED_PROVIDER_ID).await;
+    authentication
+        .ensure_provider_not_blocked(USER_ID, &allowed, "block-allowed")
+        .await
+        .expect("allowed provider proceeds");
+
+    // 解除封禁后放行
+    let listed = blocks
+        .remove(&admin(), USER_ID, BLOCKED_PROVIDER_ID)
+        .await
+        .expect("remove provider block");
+    assert!(listed.is_empty());
+    authentication
+        .ensure_provider_not_blocked(USER_ID, &blocked, "block-cleared")
+        .await
+        .expect("unblocked provider proceeds");
+}
+
+#[tokio::test]
+async fn provider_blocks_require_admin_and_known_providers() {
+    let db = setup().await;
+    let blocks = ProviderBlocksService::new(db.clone());
+
+    let user = AuthContext {
+        user_id: USER_ID,
+        is_admin: false,
+    };
+    let request = SetProviderBlocksRequest {
+        provider_type_ids: vec![BLOCKED_PROVIDER_ID],
+    };
+    assert!(blocks.set(&user, USER_ID, &request).await.is_err());
+
+    let unknown = SetProviderBlocksRequest {
+        provider_type_ids: vec![BLOCKED_PROVIDER_ID, 99_999],
+    };
+    assert!(blocks.set(&admin(), USER_ID, &unknown).await.is_err());
+    assert!(
+        blocks
+            .remove(&admin(), USER_ID, BLOCKED_PROVIDER_ID)
+            .await
+            .is_err()
+    );
+
+    let stored = user_provider_blocks::Entity::find()
+        .count(db.as_ref())
+        .await
+        .expect("count blocks");
+    assert_eq!(stored, 0);
+}
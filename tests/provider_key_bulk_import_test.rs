@@ -0,0 +1,149 @@
+//! 提供商密钥批量导入测试
+//!
+//! 混合批次中重名与格式错误的行只在报告中标记，不影响其它行的创建；创建的密钥可被调度。
+
+mod common;
+
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService, SelectionContext};
+use api_proxy::management::services::ProviderKeyService;
+use api_proxy::management::services::provider_keys::{
+    BulkImportFormat, BulkImportRowStatus, parse_bulk_import,
+};
+use api_proxy::types::TimezoneContext;
+use chrono_tz::Asia::Shanghai;
+use entity::{user_provider_keys, user_service_apis};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+use std::sync::Arc;
+
+const USER_ID: i32 = 3600;
+const PROVIDER_TYPE_ID: i32 = 470;
+const SERVICE_API_ID: i32 = 5500;
+const EXISTING_KEY_ID: i32 = 7600;
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "bulk_import_provider"),
+    )
+    .await;
+
+    common::insert_provider_key(
+        &db,
+        user_provider_keys::ActiveModel {
+            name: Set("Existing Key".to_string()),
+            is_active: Set(false),
+            ..common::provider_key(EXISTING_KEY_ID, USER_ID, PROVIDER_TYPE_ID)
+        },
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+const fn timezone() -> TimezoneContext {
+    TimezoneContext { timezone: Shanghai }
+}
+
+#[tokio::test]
+async fn mixed_batch_reports_duplicates_without_aborting() {
+    let db = setup().await;
+    let body = json!([
+        {"provider_type_id": PROVIDER_TYPE_ID, "name": "Imported A", "auth_type": "api_key", "api_key": "sk-a"},
+        {"provider_type_id": PROVIDER_TYPE_ID, "name": "Existing Key", "auth_type": "api_key", "api_key": "sk-dup"},
+        {"provider_type_id": PROVIDER_TYPE_ID, "name": "Imported A", "auth_type": "api_key", "api_key": "sk-a2"},
+        {"provider_type_id": PROVIDER_TYPE_ID, "name": "No Credential", "auth_type": "api_key"},
+        {"name": "Missing Provider", "auth_type": "api_key", "api_key": "sk-x"},
+        {"provider_type_id": PROVIDER_TYPE_ID, "name": "Imported B", "auth_type": "api_key", "api_key": "sk-b", "weight": 2}
+    ])
+    .to_string();
+    let rows = parse_bulk_import(&body, BulkImportFormat::Json).expect("parse batch");
+
+    let report = ProviderKeyService::from_db(db.as_ref())
+        .bulk_import(USER_ID, &timezone(), rows)
+        .await;
+
+    assert_eq!(report.total, 6);
+    assert_eq!(report.created, 2);
+    assert_eq!(report.duplicates, 2);
+    assert_eq!(report.failed, 2);
+    let statuses: Vec<_> = report.results.iter().map(|r| r.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            BulkImportRowStatus::Created,
+            BulkImportRowStatus::Duplicate,
+            BulkImportRowStatus::Duplicate,
+            BulkImportRowStatus::Failed,
+            BulkImportRowStatus::Failed,
+            BulkImportRowStatus::Created,
+        ]
+    );
+    assert_eq!(report.results[4].name.as_deref(), Some("Missing Provider"));
+    assert!(report.results[3].error.is_some());
+
+    let imported = user_provider_keys::Entity::find()
+        .filter(user_provider_keys::Column::UserId.eq(USER_ID))
+        .filter(user_provider_keys::Column::Id.ne(EXISTING_KEY_ID))
+        .all(db.as_ref())
+        .await
+        .expect("load imported keys");
+    let mut names: Vec<_> = imported.iter().map(|key| key.name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["Imported A", "Imported B"]);
+    let imported_b = imported
+        .iter()
+        .find(|key| key.name == "Imported B")
+        .expect("imported b");
+    assert_eq!(report.results[5].id, Some(imported_b.id));
+    assert_eq!(imported_b.weight, Some(2));
+
+    // 导入的密钥进入调度密钥池
+    common::insert_service_api(
+        db.as_ref(),
+        common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_TYPE_ID, &[imported_b.id]),
+    )
+    .await;
+    let service_api = user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(db.as_ref())
+        .await
+        .expect("load service api")
+        .expect("service api exists");
+    let scheduler =
+        ApiKeySchedulerService::new(db.clone(), Arc::new(ApiKeyHealthService::new(db.clone())));
+    let selected = scheduler
+        .select_api_key_from_service_api(
+            &service_api,
+            &SelectionContext::new(
+                "bulk-import-req".to_string(),
+                USER_ID,
+                SERVICE_API_ID,
+                PROVIDER_TYPE_ID,
+                "/v1/chat/completions".to_string(),
+            ),
+        )
+        .await
+        .expect("select imported key");
+    assert_eq!(selected.selected_key.id, imported_b.id);
+}
+
+#[tokio::test]
+async fn csv_batch_is_imported() {
+    let db = setup().await;
+    let csv = format!(
+        "provider_type_id,name,auth_type,api_key,is_active\n\
+         {PROVIDER_TYPE_ID},\"Csv, One\",api_key,sk-csv-1,true\n\
+         {PROVIDER_TYPE_ID},Existing Key,api_key,sk-csv-dup,true\n"
+    );
+    let rows = parse_bulk_import(&csv, BulkImportFormat::Csv).expect("parse csv");
+
+    let report = ProviderKeyService::from_db(db.as_ref())
+        .bulk_import(USER_ID, &timezone(), rows)
+        .await;
+
+    assert_eq!(report.created, 1);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.results[0].name.as_deref(), Some("Csv, One"));
+}
@@ -0,0 +1,166 @@
+//! 提供商密钥软删除测试
+//!
+//! 删除只记录删除时间：密钥从列表与调度密钥池中消失，追踪记录仍然关联；恢复后重新可见、可被调度。
+
+mod common;
+
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService, SelectionContext};
+use api_proxy::management::services::{ProviderKeyService, ProviderKeysListQuery};
+use api_proxy::types::TimezoneContext;
+use chrono::Utc;
+use chrono_tz::Asia::Shanghai;
+use entity::{proxy_tracing, user_provider_keys, user_service_apis};
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde_json::Value;
+use std::sync::Arc;
+
+const USER_ID: i32 = 3000;
+const PROVIDER_TYPE_ID: i32 = 400;
+const SERVICE_API_ID: i32 = 5000;
+const KEY_ID: i32 = 7001;
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    let now = Utc::now().naive_utc();
+
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "soft_delete_provider"),
+    )
+    .await;
+
+    common::insert_provider_key(&db, common::provider_key(KEY_ID, USER_ID, PROVIDER_TYPE_ID)).await;
+    common::insert_service_api(
+        &db,
+        common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_TYPE_ID, &[KEY_ID]),
+    )
+    .await;
+
+    proxy_tracing::Entity::insert(proxy_tracing::ActiveModel {
+        user_service_api_id: Set(SERVICE_API_ID),
+        user_provider_key_id: Set(Some(KEY_ID)),
+        request_id: Set("req-soft-delete".to_string()),
+        method: Set("POST".to_string()),
+        status_code: Set(Some(200)),
+        user_id: Set(Some(USER_ID)),
+        provider_type_id: Set(Some(PROVIDER_TYPE_ID)),
+        is_success: Set(true),
+        created_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&db)
+    .await
+    .expect("insert trace");
+
+    Arc::new(db)
+}
+
+const fn timezone() -> TimezoneContext {
+    TimezoneContext { timezone: Shanghai }
+}
+
+async fn listed_key_ids(service: &ProviderKeyService<'_>) -> Vec<i64> {
+    let query = ProviderKeysListQuery {
+        page: None,
+        limit: None,
+        search: None,
+        provider: None,
+        status: None,
+    };
+    let response = service
+        .list(USER_ID, &timezone(), &query)
+        .await
+        .expect("list provider keys");
+    response.data["provider_keys"]
+        .as_array()
+        .expect("provider key list")
+        .iter()
+        .filter_map(|key| key["id"].as_i64())
+        .collect()
+}
+
+async fn select_key(db: &Arc<DatabaseConnection>) -> Option<i32> {
+    let scheduler =
+        ApiKeySchedulerService::new(db.clone(), Arc::new(ApiKeyHealthService::new(db.clone())));
+    let service_api = user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(db.as_ref())
+        .await
+        .expect("load service api")
+        .expect("service api exists");
+    let context = SelectionContext::new(
+        "soft-delete-req".to_string(),
+        USER_ID,
+        SERVICE_API_ID,
+        PROVIDER_TYPE_ID,
+        "/v1/chat/completions".to_string(),
+    );
+    scheduler
+        .select_api_key_from_service_api(&service_api, &context)
+        .await
+        .ok()
+        .map(|result| result.selected_key.id)
+}
+
+#[tokio::test]
+async fn soft_deleted_key_is_hidden_and_leaves_pool() {
+    let db = setup().await;
+    let service = ProviderKeyService::from_db(db.as_ref());
+    assert_eq!(listed_key_ids(&service).await, vec![i64::from(KEY_ID)]);
+    assert_eq!(select_key(&db).await, Some(KEY_ID));
+
+    let response = service
+        .delete(USER_ID, &timezone(), KEY_ID)
+        .await
+        .expect("delete key");
+    assert!(response.data["deleted_at"].is_string());
+
+    assert!(listed_key_ids(&service).await.is_empty());
+    assert!(service.detail(USER_ID, &timezone(), KEY_ID).await.is_err());
+    assert_eq!(select_key(&db).await, None);
+
+    // 行仍然存在，追踪记录保持关联
+    let row = user_provider_keys::Entity::find_by_id(KEY_ID)
+        .one(db.as_ref())
+        .await
+        .expect("load key row")
+        .expect("key row kept");
+    assert!(row.deleted_at.is_some());
+    let trace = proxy_tracing::Entity::find()
+        .one(db.as_ref())
+        .await
+        .expect("load trace")
+        .expect("trace kept");
+    assert_eq!(trace.user_provider_key_id, Some(KEY_ID));
+
+    // 重复删除视为不存在
+    assert!(service.delete(USER_ID, &timezone(), KEY_ID).await.is_err());
+}
+
+#[tokio::test]
+async fn restored_key_is_listed_and_selectable_again() {
+    let db = setup().await;
+    let service = ProviderKeyService::from_db(db.as_ref());
+
+    // 未删除的密钥不能恢复
+    assert!(service.restore(USER_ID, &timezone(), KEY_ID).await.is_err());
+
+    service
+        .delete(USER_ID, &timezone(), KEY_ID)
+        .await
+        .expect("delete key");
+    let response = service
+        .restore(USER_ID, &timezone(), KEY_ID)
+        .await
+        .expect("restore key");
+    assert_eq!(response.data["id"], Value::from(KEY_ID));
+
+    assert_eq!(listed_key_ids(&service).await, vec![i64::from(KEY_ID)]);
+    assert_eq!(select_key(&db).await, Some(KEY_ID));
+    let row = user_provider_keys::Entity::find_by_id(KEY_ID)
+        .one(db.as_ref())
+        .await
+        .expect("load key row")
+        .expect("key row kept");
+    assert!(row.deleted_at.is_none());
+}
@@ -0,0 +1,151 @@
+//! `X-Provider` 服务商覆盖测试
+//!
+//! 验证请求指定的服务商必须属于服务 API 自身或其密钥池，覆盖后只在该服务商的密钥中选择。
+
+mod common;
+
+use api_proxy::auth::api_key_manager::ApiKeyManager;
+use api_proxy::auth::api_key_usage_limit_service::ApiKeyUsageLimitService;
+use api_proxy::auth::jwt::JwtManager;
+use api_proxy::auth::service::ApiKeyAuthenticationService;
+use api_proxy::auth::types::AuthConfig;
+use api_proxy::cache::CacheManager;
+use api_proxy::config::CacheConfig;
+use api_proxy::error::reject::RejectReason;
+use api_proxy::error::{ProxyError, auth::AuthError};
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService, SelectionContext};
+use api_proxy::proxy::AuthenticationService;
+use entity::user_service_apis;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use std::sync::Arc;
+
+const USER_ID: i32 = 2400;
+const DEFAULT_PROVIDER_ID: i32 = 340;
+const POOL_PROVIDER_ID: i32 = 341;
+const OTHER_PROVIDER_ID: i32 = 342;
+const SERVICE_API_ID: i32 = 4400;
+const DEFAULT_KEY_ID: i32 = 5400;
+const POOL_KEY_ID: i32 = 5401;
+const OTHER_KEY_ID: i32 = 5402;
+
+async fn setup() -> (Arc<DatabaseConnection>, user_service_apis::Model) {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+
+    // 第三个服务商的密钥属于同一用户，但不在服务 API 的密钥池中
+    for (provider_id, key_id) in [
+        (DEFAULT_PROVIDER_ID, DEFAULT_KEY_ID),
+        (POOL_PROVIDER_ID, POOL_KEY_ID),
+        (OTHER_PROVIDER_ID, OTHER_KEY_ID),
+    ] {
+        common::insert_provider(
+            &db,
+            common::provider(provider_id, &format!("override_provider_{provider_id}")),
+        )
+        .await;
+        common::insert_provider_key(&db, common::provider_key(key_id, USER_ID, provider_id)).await;
+    }
+
+    common::insert_service_api(
+        &db,
+        common::service_api(
+            SERVICE_API_ID,
+            USER_ID,
+            DEFAULT_PROVIDER_ID,
+            &[DEFAULT_KEY_ID, POOL_KEY_ID],
+        ),
+    )
+    .await;
+
+    let service_api = user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(&db)
+        .await
+        .expect("load service api")
+        .expect("service api exists");
+    (Arc::new(db), service_api)
+}
+
+fn services(db: &Arc<DatabaseConnection>) -> (AuthenticationService, Arc<ApiKeySchedulerService>) {
+    let cache = Arc::new(CacheManager::memory_only());
+    let scheduler = Arc::new(ApiKeySchedulerService::new(
+        db.clone(),
+        Arc::new(ApiKeyHealthService::new(db.clone())),
+    ));
+    let auth_service = Arc::new(ApiKeyAuthenticationService::new(
+        Arc::new(JwtManager::new(&AuthConfig::default()).expect("jwt manager")),
+        Arc::new(ApiKeyManager::new(
+            db.clone(),
+            cache.clone(),
+            Arc::new(CacheConfig::default()),
+        )),
+        db.clone(),
+    ));
+    let authentication = AuthenticationService::new(
+        auth_service,
+        db.clone(),
+        cache.clone(),
+        scheduler.clone(),
+        Arc::new(ApiKeyUsageLimitService::new(cache, db.clone())),
+    );
+    (authentication, scheduler)
+}
+
+#[tokio::test]
+async fn allowed_provider_override_routes_to_its_keys() {
+    let (db, service_api) = setup().await;
+    let (authentication, scheduler) = services(&db);
+
+    assert!(
+        authentication
+            .resolve_provider_override(None, &service_api, "override-none")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let provider = authentication
+        .resolve_provider_override(Some(" Override_Provider_341 "), &service_api, "override-ok")
+        .await
+        .unwrap()
+        .expect("override applied");
+    assert_eq!(provider.id, POOL_PROVIDER_ID);
+
+    let context = SelectionContext::new(
+        "override-ok".to_string(),
+        USER_ID,
+        SERVICE_API_ID,
+        provider.id,
+        "/v1/chat/completions".to_string(),
+    )
+    .with_pinned_provider(true);
+    for _ in 0..3 {
+        let selected = scheduler
+            .select_api_key_from_service_api(&service_api, &context)
+            .await
+            .expect("select key")
+            .selected_key;
+        assert_eq!(selected.id, POOL_KEY_ID);
+    }
+}
+
+#[tokio::test]
+async fn unauthorized_provider_override_is_rejected() {
+    let (db, service_api) = setup().await;
+    let (authentication, _) = services(&db);
+
+    for requested in ["override_provider_342", "no_such_provider", ""] {
+        let err = authentication
+            .resolve_provider_override(Some(requested), &service_api, "override-denied")
+            .await
+            .unwrap_err();
+        let ProxyError::Authentication(auth_err) = &err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert!(matches!(auth_err, AuthError::ProviderNotAllowed(_)));
+        assert_eq!(
+            RejectReason::from_auth_error(auth_err),
+            RejectReason::ProviderNotAllowed
+        );
+        assert_eq!(err.status_code(), http::StatusCode::FORBIDDEN);
+    }
+}
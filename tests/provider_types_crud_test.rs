@@ -1,5 +1,6 @@
 //! Provider Types CRUD 集成测试
 
+use api_proxy::error::{ProxyError, auth::AuthError};
 use api_proxy::management::middleware::AuthContext;
 use api_proxy::management::services::{
     CreateProviderTypeRequest, ProviderTypesCrudService, UpdateProviderTypeRequest,
@@ -68,6 +69,29 @@ async fn create_update_delete_provider_type() {
         .await;
     assert!(dup.is_err());
 
+    // 未知认证类型显式拒绝
+    let unknown = service
+        .create(
+            &admin(),
+            &CreateProviderTypeRequest {
+                name: "test".to_string(),
+                display_name: "Test Provider".to_string(),
+                auth_type: "oauth2".to_string(),
+                base_url: "example.com".to_string(),
+                is_active: Some(true),
+                config_json: None,
+                token_mappings_json: None,
+                model_extraction_json: None,
+                auth_configs_json: Some(serde_json::json!({})),
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        unknown,
+        ProxyError::Authentication(AuthError::UnsupportedAuthType(_))
+    ));
+
     // 同 name 不同 auth_type 允许
     let created_oauth = service
         .create(
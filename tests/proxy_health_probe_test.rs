@@ -0,0 +1,123 @@
+//! 代理端口探针测试
+//!
+//! 验证 `/readyz` 按真实依赖状态返回：依赖均可用时 200，数据库不可达或没有健康密钥时 503；
+//! 缓存降级只在结果中报告，仍返回 200。
+
+mod common;
+
+use api_proxy::cache::CacheManager;
+use api_proxy::config::{CacheConfig, CacheType, RedisConfig};
+use api_proxy::proxy::health_probe::{HealthProbeService, ProbeKind};
+use entity::user_provider_keys;
+use sea_orm::{DatabaseConnection, Set};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+const USER_ID: i32 = 2600;
+const PROVIDER_TYPE_ID: i32 = 360;
+const KEY_ID: i32 = 6600;
+
+async fn setup_test_db() -> Arc<DatabaseConnection> {
+    Arc::new(common::setup_db().await)
+}
+
+async fn seed_key(db: &DatabaseConnection, health_status: &str) {
+    common::insert_user(db, common::user(USER_ID)).await;
+    common::insert_provider(db, common::provider(PROVIDER_TYPE_ID, "probe_provider")).await;
+    common::insert_provider_key(
+        db,
+        user_provider_keys::ActiveModel {
+            health_status: Set(health_status.to_string()),
+            ..common::provider_key(KEY_ID, USER_ID, PROVIDER_TYPE_ID)
+        },
+    )
+    .await;
+}
+
+fn probe_service(db: &Arc<DatabaseConnection>) -> HealthProbeService {
+    HealthProbeService::new(db.clone(), Arc::new(CacheManager::memory_only()))
+}
+
+#[tokio::test]
+async fn readyz_returns_200_when_dependencies_are_healthy() {
+    let db = setup_test_db().await;
+    seed_key(&db, "healthy").await;
+    let probe = probe_service(&db);
+
+    let (status, payload) = probe.respond(ProbeKind::Readiness).await;
+    assert_eq!(status, 200);
+    assert_eq!(payload["status"], "ready");
+    assert_eq!(payload["checks"]["database"], true);
+    assert_eq!(payload["checks"]["cache"], "ok");
+    assert_eq!(payload["checks"]["healthy_keys"], 1);
+
+    let (status, payload) = probe.respond(ProbeKind::Liveness).await;
+    assert_eq!(status, 200);
+    assert_eq!(payload["status"], "ok");
+}
+
+#[tokio::test]
+async fn readyz_returns_503_without_healthy_keys() {
+    let db = setup_test_db().await;
+    seed_key(&db, "unhealthy").await;
+
+    let (status, payload) = probe_service(&db).respond(ProbeKind::Readiness).await;
+    assert_eq!(status, 503);
+    assert_eq!(payload["status"], "not_ready");
+    assert_eq!(payload["checks"]["database"], true);
+    assert_eq!(payload["checks"]["healthy_keys"], 0);
+}
+
+#[tokio::test]
+async fn readyz_returns_503_when_database_is_down() {
+    let db = setup_test_db().await;
+    seed_key(&db, "healthy").await;
+    let probe = probe_service(&db);
+    db.close_by_ref().await.expect("close test db");
+
+    let (status, payload) = probe.respond(ProbeKind::Readiness).await;
+    assert_eq!(status, 503);
+    assert_eq!(payload["checks"]["database"], false);
+    assert_eq!(payload["checks"]["healthy_keys"], 0);
+
+    // 存活探针不依赖数据库
+    let (status, _) = probe.respond(ProbeKind::Liveness).await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn readyz_reports_degraded_cache_but_stays_ready() {
+    let db = setup_test_db().await;
+    seed_key(&db, "healthy").await;
+
+    // 指向一个没有服务监听的端口，Redis 连接失败后缓存进入降级
+    let port = {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind free port");
+        listener.local_addr().expect("local addr").port()
+    };
+    let cache = CacheManager::new(&CacheConfig {
+        cache_type: CacheType::Redis,
+        redis: Some(RedisConfig {
+            url: format!("redis://127.0.0.1:{port}/0"),
+            port,
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .expect("create cache");
+    let probe = HealthProbeService::new(db.clone(), Arc::new(cache));
+
+    let (status, payload) = probe.respond(ProbeKind::Readiness).await;
+    assert_eq!(status, 200);
+    assert_eq!(payload["status"], "ready");
+    assert_eq!(payload["checks"]["database"], true);
+    assert_eq!(payload["checks"]["cache"], "degraded");
+    assert_eq!(payload["checks"]["healthy_keys"], 1);
+
+    // 降级期间后续探测同样报告降级，不摘除实例
+    let (status, payload) = probe.respond(ProbeKind::Readiness).await;
+    assert_eq!(status, 200);
+    assert_eq!(payload["checks"]["cache"], "degraded");
+}
@@ -0,0 +1,96 @@
+//! 上游 429 密钥冷却测试
+//!
+//! 未携带 `Retry-After` 的 429 按配置的默认冷却写入 `rate_limit_resets_at`；
+//! 连续 429 按倍数递增且不超过上限；携带 `Retry-After` 时按其冷却。
+
+mod common;
+
+use api_proxy::config::RateLimitCooldownConfig;
+use api_proxy::key_pool::{ApiKeyHealthService, HealthUpdate};
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use std::time::Duration;
+
+const USER_ID: i32 = 4000;
+const PROVIDER_TYPE_ID: i32 = 500;
+const KEY_ID: i32 = 7800;
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(&db, common::provider(PROVIDER_TYPE_ID, "cooldown_provider")).await;
+    common::insert_provider_key(&db, common::provider_key(KEY_ID, USER_ID, PROVIDER_TYPE_ID)).await;
+
+    Arc::new(db)
+}
+
+fn rate_limited(retry_after: Option<Duration>) -> HealthUpdate {
+    HealthUpdate::RateLimited {
+        retry_after,
+        reason: "上游限流 (HTTP 429)".to_string(),
+    }
+}
+
+/// 应用一次 429，返回写入的冷却时长（秒）
+async fn cooldown_after_429(health: &ApiKeyHealthService, retry_after: Option<Duration>) -> i64 {
+    let before = Utc::now().naive_utc();
+    health
+        .apply_health_update(KEY_ID, &rate_limited(retry_after))
+        .await
+        .expect("apply rate limit");
+    let key = health.get_key_by_id(KEY_ID).await.expect("key exists");
+    assert_eq!(key.health_status, "rate_limited");
+    let resets_at: NaiveDateTime = key.rate_limit_resets_at.expect("resets_at recorded");
+    (resets_at - before).num_seconds()
+}
+
+#[tokio::test]
+async fn rate_limit_without_retry_after_uses_default_cooldown() {
+    let db = setup().await;
+    let health = ApiKeyHealthService::new(db).with_cooldown(RateLimitCooldownConfig {
+        default_secs: 90,
+        ..Default::default()
+    });
+
+    assert_eq!(cooldown_after_429(&health, None).await, 90);
+    // 未开启递增时连续 429 仍使用默认冷却
+    assert_eq!(cooldown_after_429(&health, None).await, 90);
+    // Retry-After 优先于默认冷却
+    assert_eq!(
+        cooldown_after_429(&health, Some(Duration::from_secs(15))).await,
+        15
+    );
+}
+
+#[tokio::test]
+async fn repeated_rate_limits_escalate_up_to_the_cap() {
+    let db = setup().await;
+    let health = ApiKeyHealthService::new(db).with_cooldown(RateLimitCooldownConfig {
+        default_secs: 60,
+        escalation_multiplier: 2,
+        max_secs: 300,
+        escalation_window_secs: 600,
+    });
+
+    let mut cooldowns = Vec::new();
+    for _ in 0..4 {
+        cooldowns.push(cooldown_after_429(&health, None).await);
+    }
+    assert_eq!(cooldowns, vec![60, 120, 240, 300]);
+}
+
+#[test]
+fn cooldown_escalation_is_capped() {
+    let config = RateLimitCooldownConfig {
+        default_secs: 30,
+        escalation_multiplier: 3,
+        max_secs: 600,
+        escalation_window_secs: 60,
+    };
+    assert_eq!(config.cooldown_for(1), Duration::from_secs(30));
+    assert_eq!(config.cooldown_for(2), Duration::from_secs(90));
+    assert_eq!(config.cooldown_for(3), Duration::from_secs(270));
+    assert_eq!(config.cooldown_for(4), Duration::from_secs(600));
+    assert_eq!(config.cooldown_for(u32::MAX), Duration::from_secs(600));
+}
@@ -0,0 +1,80 @@
+//! 请求体改写规则测试
+//!
+//! 覆盖服务 API 上配置的 `request_transform_rules`：规则解析校验与按顺序改写请求体
+
+use api_proxy::collect::field_extractor::{
+    TransformRule, apply_transform_rules, parse_transform_rules,
+};
+use serde_json::json;
+
+fn sample_body() -> serde_json::Value {
+    json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "hi"}],
+        "max_tokens": 256,
+        "user": "internal-user-id",
+        "metadata": {"trace": "abc"}
+    })
+}
+
+#[test]
+fn rename_and_remove_rules_rewrite_body() {
+    let rules = parse_transform_rules(&json!([
+        {"op": "rename", "from": "max_tokens", "to": "max_completion_tokens"},
+        {"op": "remove", "path": "user"},
+        {"op": "remove", "path": "metadata.trace"}
+    ]))
+    .expect("valid rules");
+
+    let mut body = sample_body();
+    assert!(apply_transform_rules(&rules, &mut body));
+
+    assert_eq!(
+        body,
+        json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "hi"}],
+            "max_completion_tokens": 256,
+            "metadata": {}
+        })
+    );
+}
+
+#[test]
+fn set_rule_creates_nested_fields() {
+    let rules = parse_transform_rules(&json!([
+        {"op": "set", "path": "stream_options.include_usage", "value": true}
+    ]))
+    .expect("valid rules");
+
+    let mut body = sample_body();
+    assert!(apply_transform_rules(&rules, &mut body));
+    assert_eq!(body["stream_options"]["include_usage"], json!(true));
+}
+
+#[test]
+fn rules_targeting_missing_fields_leave_body_untouched() {
+    let rules = vec![
+        TransformRule::Remove {
+            path: "temperature".to_string(),
+        },
+        TransformRule::Rename {
+            from: "top_k".to_string(),
+            to: "topK".to_string(),
+        },
+    ];
+
+    let mut body = sample_body();
+    assert!(!apply_transform_rules(&rules, &mut body));
+    assert_eq!(body, sample_body());
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    assert!(parse_transform_rules(&json!({"op": "remove", "path": "user"})).is_err());
+    assert!(parse_transform_rules(&json!([{"op": "drop", "path": "user"}])).is_err());
+    assert!(parse_transform_rules(&json!([{"op": "remove", "path": "a..b"}])).is_err());
+    assert!(parse_transform_rules(&json!([{"op": "rename", "from": "a", "to": "a.b"}])).is_err());
+    assert!(parse_transform_rules(&json!([{"op": "set", "path": "a"}])).is_err());
+    assert!(parse_transform_rules(&json!([])).unwrap().is_empty());
+}
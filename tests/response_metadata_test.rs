@@ -0,0 +1,86 @@
+//! 响应元数据提取测试
+//!
+//! 按 `trace.response_metadata_fields` 配置的路径从响应体中提取字段，写入追踪记录
+
+use api_proxy::collect::field_extractor::ResponseMetadataExtractor;
+use api_proxy::collect::usage_model::extract_response_metadata;
+use api_proxy::proxy::ProxyContext;
+use bytes::BytesMut;
+use serde_json::json;
+
+fn metadata_fields() -> Vec<String> {
+    vec![
+        "model".to_string(),
+        "system_fingerprint".to_string(),
+        "choices.0.finish_reason".to_string(),
+    ]
+}
+
+fn openai_completion() -> serde_json::Value {
+    json!({
+        "id": "chatcmpl-123",
+        "object": "chat.completion",
+        "model": "gpt-4o-2024-08-06",
+        "system_fingerprint": "fp_44709d6fcb",
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": "Hello!"},
+            "finish_reason": "stop"
+        }],
+        "usage": {"prompt_tokens": 9, "completion_tokens": 12, "total_tokens": 21}
+    })
+}
+
+fn context_with_body(content_type: &str, body: &str) -> ProxyContext {
+    let mut ctx = ProxyContext::default();
+    ctx.response.details.content_type = Some(content_type.to_string());
+    ctx.response.body = BytesMut::from(body.as_bytes());
+    ctx
+}
+
+#[test]
+fn extracts_configured_fields_from_openai_response() {
+    let paths = metadata_fields();
+    let mut extractor = ResponseMetadataExtractor::new(&paths);
+    extractor.observe(&openai_completion());
+
+    assert_eq!(
+        extractor.finish(),
+        Some(json!({
+            "model": "gpt-4o-2024-08-06",
+            "system_fingerprint": "fp_44709d6fcb",
+            "choices.0.finish_reason": "stop"
+        }))
+    );
+}
+
+#[test]
+fn missing_fields_are_omitted() {
+    let paths = vec!["model".to_string(), "service_tier".to_string()];
+    let ctx = context_with_body("application/json", &openai_completion().to_string());
+
+    assert_eq!(
+        extract_response_metadata(&ctx, &paths),
+        Some(json!({"model": "gpt-4o-2024-08-06"}))
+    );
+    assert_eq!(extract_response_metadata(&ctx, &[]), None);
+}
+
+#[test]
+fn streaming_response_keeps_last_non_null_values() {
+    let body = concat!(
+        "data: {\"model\":\"gpt-4o-mini\",\"system_fingerprint\":\"fp_1\",\"choices\":[{\"delta\":{\"content\":\"Hi\"},\"finish_reason\":null}]}\n\n",
+        "data: {\"model\":\"gpt-4o-mini\",\"system_fingerprint\":\"fp_1\",\"choices\":[{\"delta\":{},\"finish_reason\":\"length\"}]}\n\n",
+        "data: [DONE]\n\n",
+    );
+    let ctx = context_with_body("text/event-stream", body);
+
+    assert_eq!(
+        extract_response_metadata(&ctx, &metadata_fields()),
+        Some(json!({
+            "model": "gpt-4o-mini",
+            "system_fingerprint": "fp_1",
+            "choices.0.finish_reason": "length"
+        }))
+    );
+}
@@ -0,0 +1,190 @@
+//! 密钥选择依据追踪测试
+//!
+//! 服务 API 开启 `selection_debug` 后，加权调度的候选集、各候选评分与选中的密钥写入追踪记录的
+//! `request_metadata.selection_debug`；未开启时不记录。
+
+mod common;
+
+use api_proxy::auth::api_key_usage_limit_service::ApiKeyUsageLimitService;
+use api_proxy::cache::CacheManager;
+use api_proxy::collect::types::{CollectedCost, CollectedMetrics, TokenUsageMetrics};
+use api_proxy::key_pool::{ApiKeyHealthService, ApiKeySchedulerService, SelectionContext};
+use api_proxy::proxy::ProxyContext;
+use api_proxy::trace::{ImmediateProxyTracer, TraceManager};
+use entity::{proxy_tracing, user_provider_keys, user_service_apis};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use std::sync::Arc;
+
+const USER_ID: i32 = 2800;
+const PROVIDER_TYPE_ID: i32 = 380;
+const SERVICE_API_ID: i32 = 4800;
+const HEAVY_KEY_ID: i32 = 6801;
+const LIGHT_KEY_ID: i32 = 6802;
+const UNHEALTHY_KEY_ID: i32 = 6803;
+
+async fn setup(selection_debug: bool) -> (Arc<DatabaseConnection>, user_service_apis::Model) {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "selection_provider"),
+    )
+    .await;
+
+    // (密钥, 权重, 健康状态)
+    for (key_id, weight, health_status) in [
+        (HEAVY_KEY_ID, 3, "healthy"),
+        (LIGHT_KEY_ID, 1, "healthy"),
+        (UNHEALTHY_KEY_ID, 5, "unhealthy"),
+    ] {
+        common::insert_provider_key(
+            &db,
+            user_provider_keys::ActiveModel {
+                weight: Set(Some(weight)),
+                health_status: Set(health_status.to_string()),
+                ..common::provider_key(key_id, USER_ID, PROVIDER_TYPE_ID)
+            },
+        )
+        .await;
+    }
+
+    common::insert_service_api(
+        &db,
+        user_service_apis::ActiveModel {
+            scheduling_strategy: Set(Some("weighted".to_string())),
+            selection_debug: Set(selection_debug),
+            ..common::service_api(
+                SERVICE_API_ID,
+                USER_ID,
+                PROVIDER_TYPE_ID,
+                &[HEAVY_KEY_ID, LIGHT_KEY_ID, UNHEALTHY_KEY_ID],
+            )
+        },
+    )
+    .await;
+
+    let service_api = user_service_apis::Entity::find_by_id(SERVICE_API_ID)
+        .one(&db)
+        .await
+        .expect("load service api")
+        .expect("service api exists");
+    (Arc::new(db), service_api)
+}
+
+/// 按服务 API 的开关选择密钥，再像代理一样把选择依据写入追踪记录
+async fn select_and_trace(
+    db: &Arc<DatabaseConnection>,
+    service_api: &user_service_apis::Model,
+    request_id: &str,
+) -> proxy_tracing::Model {
+    let scheduler =
+        ApiKeySchedulerService::new(db.clone(), Arc::new(ApiKeyHealthService::new(db.clone())));
+    let context = SelectionContext::new(
+        request_id.to_string(),
+        USER_ID,
+        SERVICE_API_ID,
+        PROVIDER_TYPE_ID,
+        "/v1/chat/completions".to_string(),
+    )
+    .with_selection_debug(service_api.selection_debug);
+    let selection = scheduler
+        .select_api_key_from_service_api(service_api, &context)
+        .await
+        .expect("select key");
+
+    let manager = TraceManager::new(
+        Some(Arc::new(ImmediateProxyTracer::new(db.clone()))),
+        Arc::new(ApiKeyUsageLimitService::new(
+            Arc::new(CacheManager::memory_only()),
+            db.clone(),
+        )),
+    );
+    manager
+        .start_trace(
+            request_id,
+            SERVICE_API_ID,
+            Some(USER_ID),
+            Some(PROVIDER_TYPE_ID),
+            Some(selection.selected_key.id),
+            "POST",
+            Some("/v1/chat/completions".to_string()),
+            None,
+            None,
+        )
+        .await
+        .expect("start trace");
+
+    let mut ctx = ProxyContext {
+        request_id: request_id.to_string(),
+        ..Default::default()
+    };
+    ctx.mark_trace_started();
+    ctx.trace.selection_debug = selection.selection_debug;
+
+    let metrics = CollectedMetrics {
+        request_id: request_id.to_string(),
+        user_id: Some(USER_ID),
+        user_service_api_id: Some(SERVICE_API_ID),
+        provider_type_id: Some(PROVIDER_TYPE_ID),
+        model: None,
+        usage: TokenUsageMetrics::default(),
+        cost: CollectedCost::default(),
+        duration_ms: 10,
+        status_code: 200,
+        request_bytes: 0,
+        response_bytes: 0,
+        response_metadata: None,
+    };
+    manager
+        .record_success(&metrics, &ctx)
+        .await
+        .expect("record success");
+
+    proxy_tracing::Entity::find()
+        .filter(proxy_tracing::Column::RequestId.eq(request_id))
+        .one(db.as_ref())
+        .await
+        .expect("load trace")
+        .expect("trace exists")
+}
+
+#[tokio::test]
+async fn weighted_selection_rationale_is_recorded_in_trace() {
+    let (db, service_api) = setup(true).await;
+    let record = select_and_trace(&db, &service_api, "selection-debug-weighted").await;
+
+    let metadata = record.request_metadata.expect("request metadata recorded");
+    let debug = &metadata["selection_debug"];
+    assert_eq!(debug["strategy"], "weighted");
+    assert_eq!(debug["candidate_count"], 2);
+    assert_eq!(
+        debug["selected_key_id"],
+        record.user_provider_key_id.unwrap()
+    );
+
+    let candidates = debug["candidates"].as_array().expect("candidates");
+    let score = |key_id: i32| {
+        candidates
+            .iter()
+            .find(|candidate| candidate["key_id"] == key_id)
+            .and_then(|candidate| candidate["score"].as_f64())
+            .expect("candidate score")
+    };
+    assert!((score(HEAVY_KEY_ID) - 0.75).abs() < f64::EPSILON);
+    assert!((score(LIGHT_KEY_ID) - 0.25).abs() < f64::EPSILON);
+    assert_eq!(candidates[0]["weight"], 3);
+    assert_eq!(candidates[0]["health_status"], "healthy");
+
+    let excluded = debug["excluded"].as_array().expect("excluded keys");
+    assert_eq!(excluded.len(), 1);
+    assert_eq!(excluded[0]["key_id"], UNHEALTHY_KEY_ID);
+    assert_eq!(excluded[0]["health_status"], "unhealthy");
+}
+
+#[tokio::test]
+async fn selection_rationale_is_not_recorded_when_disabled() {
+    let (db, service_api) = setup(false).await;
+    let record = select_and_trace(&db, &service_api, "selection-debug-disabled").await;
+
+    assert!(record.request_metadata.is_none());
+}
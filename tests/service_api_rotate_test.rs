@@ -0,0 +1,157 @@
+//! 服务 API Key 轮换测试
+//!
+//! 验证轮换后旧 Key 立即失效、新 Key 可用，以及指定宽限期时旧 Key 在宽限期内仍可认证。
+
+mod common;
+
+use api_proxy::auth::api_key_manager::ApiKeyManager;
+use api_proxy::auth::jwt::JwtManager;
+use api_proxy::auth::service::ApiKeyAuthenticationService;
+use api_proxy::auth::types::AuthConfig;
+use api_proxy::cache::CacheManager;
+use api_proxy::config::CacheConfig;
+use api_proxy::management::services::ServiceApiService;
+use api_proxy::management::services::service_apis::RotateUserServiceKeyRequest;
+use chrono::{Duration, Utc};
+use entity::user_service_apis;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use std::sync::Arc;
+
+const USER_ID: i32 = 3500;
+const PROVIDER_ID: i32 = 460;
+const SERVICE_API_ID: i32 = 5400;
+const ORIGINAL_KEY: &str = "rotate-original-service-api";
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(&db, common::provider(PROVIDER_ID, "rotate_provider")).await;
+    common::insert_service_api(
+        &db,
+        user_service_apis::ActiveModel {
+            api_key: Set(ORIGINAL_KEY.to_string()),
+            name: Set(Some("rotate me".to_string())),
+            ..common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_ID, &[])
+        },
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+fn auth_service(db: &Arc<DatabaseConnection>) -> ApiKeyAuthenticationService {
+    ApiKeyAuthenticationService::new(
+        Arc::new(JwtManager::new(&AuthConfig::default()).expect("jwt manager")),
+        Arc::new(ApiKeyManager::new(
+            db.clone(),
+            Arc::new(CacheManager::memory_only()),
+            Arc::new(CacheConfig::default()),
+        )),
+        db.clone(),
+    )
+}
+
+#[tokio::test]
+async fn rotation_invalidates_old_key_and_returns_working_new_key() {
+    let db = setup().await;
+    let auth = auth_service(&db);
+    assert!(
+        auth.authenticate_user_service_api(ORIGINAL_KEY)
+            .await
+            .is_ok()
+    );
+
+    let rotated = ServiceApiService::from_db(&db)
+        .rotate(
+            SERVICE_API_ID,
+            USER_ID,
+            &RotateUserServiceKeyRequest::default(),
+        )
+        .await
+        .expect("rotate key");
+
+    assert_eq!(rotated.id, SERVICE_API_ID);
+    assert_ne!(rotated.api_key, ORIGINAL_KEY);
+    assert!(rotated.previous_key_expires_at.is_none());
+    assert!(
+        auth.authenticate_user_service_api(ORIGINAL_KEY)
+            .await
+            .is_err()
+    );
+    let authenticated = auth
+        .authenticate_user_service_api(&rotated.api_key)
+        .await
+        .expect("new key authenticates");
+    assert_eq!(authenticated.id, SERVICE_API_ID);
+    assert_eq!(authenticated.name.as_deref(), Some("rotate me"));
+
+    // 其他用户不能轮换
+    assert!(
+        ServiceApiService::from_db(&db)
+            .rotate(
+                SERVICE_API_ID,
+                USER_ID + 1,
+                &RotateUserServiceKeyRequest::default()
+            )
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn old_key_stays_valid_during_grace_period() {
+    let db = setup().await;
+    let auth = auth_service(&db);
+    let service = ServiceApiService::from_db(&db);
+
+    let rotated = service
+        .rotate(
+            SERVICE_API_ID,
+            USER_ID,
+            &RotateUserServiceKeyRequest {
+                grace_period_seconds: Some(600),
+            },
+        )
+        .await
+        .expect("rotate key with grace period");
+
+    assert!(rotated.previous_key_expires_at.is_some());
+    assert!(
+        auth.authenticate_user_service_api(ORIGINAL_KEY)
+            .await
+            .is_ok()
+    );
+    assert!(
+        auth.authenticate_user_service_api(&rotated.api_key)
+            .await
+            .is_ok()
+    );
+
+    // 宽限期结束后旧 Key 失效
+    user_service_apis::ActiveModel {
+        id: Set(SERVICE_API_ID),
+        previous_api_key_expires_at: Set(Some(Utc::now().naive_utc() - Duration::seconds(1))),
+        ..Default::default()
+    }
+    .update(db.as_ref())
+    .await
+    .expect("expire grace period");
+    assert!(
+        auth.authenticate_user_service_api(ORIGINAL_KEY)
+            .await
+            .is_err()
+    );
+
+    assert!(
+        service
+            .rotate(
+                SERVICE_API_ID,
+                USER_ID,
+                &RotateUserServiceKeyRequest {
+                    grace_period_seconds: Some(-1),
+                },
+            )
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,229 @@
+//! 影子路由差异分析测试
+//!
+//! 写入几条对比记录，验证状态码一致率、Token 与延迟差值分布的计算，
+//! 以及时间范围、只看不一致记录与管理员权限。
+
+use api_proxy::management::middleware::AuthContext;
+use api_proxy::management::services::{ShadowDiffQuery, ShadowDiffService};
+use api_proxy::trace::{ShadowComparisonRecord, ShadowComparisonStore};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, DatabaseConnection};
+use std::sync::Arc;
+
+const PRIMARY_PROVIDER_ID: i32 = 1;
+const SHADOW_PROVIDER_ID: i32 = 2;
+
+const fn admin() -> AuthContext {
+    AuthContext {
+        user_id: 1,
+        is_admin: true,
+    }
+}
+
+fn base_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 8, 21, 10, 0, 0).unwrap()
+}
+
+fn comparison(
+    request_id: &str,
+    statuses: (u16, Option<u16>),
+    tokens: (Option<i32>, Option<i32>),
+    durations: (i64, Option<i64>),
+) -> ShadowComparisonRecord {
+    ShadowComparisonRecord {
+        request_id: request_id.to_string(),
+        model: Some("gpt-4o".to_string()),
+        primary_provider_type_id: PRIMARY_PROVIDER_ID,
+        shadow_provider_type_id: SHADOW_PROVIDER_ID,
+        primary_status_code: statuses.0,
+        shadow_status_code: statuses.1,
+        primary_tokens_total: tokens.0,
+        shadow_tokens_total: tokens.1,
+        primary_duration_ms: durations.0,
+        shadow_duration_ms: durations.1,
+        shadow_error: statuses.1.is_none().then(|| "upstream timeout".to_string()),
+        ..Default::default()
+    }
+}
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("connect test db");
+    Migrator::up(&db, None).await.expect("run migrations");
+    let db = Arc::new(db);
+
+    let store = ShadowComparisonStore::new(db.clone());
+    let rows = [
+        comparison(
+            "req-1",
+            (200, Some(200)),
+            (Some(100), Some(100)),
+            (1000, Some(1150)),
+        ),
+        comparison(
+            "req-2",
+            (200, Some(200)),
+            (Some(200), Some(250)),
+            (500, Some(400)),
+        ),
+        comparison(
+            "req-3",
+            (429, Some(200)),
+            (Some(80), Some(60)),
+            (300, Some(450)),
+        ),
+        comparison("req-4", (200, None), (Some(120), None), (800, Some(1200))),
+    ];
+    for (minute, row) in (0_i64..).zip(rows) {
+        store
+            .record(row, base_time() + Duration::minutes(minute))
+            .await
+            .expect("record comparison");
+    }
+    // 范围之外的记录不计入
+    store
+        .record(
+            comparison(
+                "req-old",
+                (500, Some(200)),
+                (Some(1), Some(1000)),
+                (1, Some(9000)),
+            ),
+            base_time() - Duration::days(2),
+        )
+        .await
+        .expect("record comparison");
+    db
+}
+
+fn range_query() -> ShadowDiffQuery {
+    ShadowDiffQuery {
+        start: Some(base_time() - Duration::hours(1)),
+        end: Some(base_time() + Duration::hours(1)),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn summary_aggregates_status_tokens_and_latency() {
+    let db = setup().await;
+    let result = ShadowDiffService::new(db)
+        .diffs(&admin(), &range_query())
+        .await
+        .expect("load diffs");
+    let summary = &result.summary;
+
+    assert_eq!(summary.total_requests, 4);
+    assert_eq!(summary.compared_requests, 3);
+    assert_eq!(summary.shadow_failures, 1);
+    assert_eq!(summary.status_matches, 2);
+    assert!((summary.status_match_rate - 200.0 / 3.0).abs() < 1e-9);
+
+    // Token 差值：0、50、-20（req-4 影子无 Token 数，不计入）
+    let tokens = &summary.token_delta;
+    assert_eq!(tokens.samples, 3);
+    assert_eq!((tokens.min, tokens.max), (Some(-20), Some(50)));
+    assert_eq!(tokens.mean, Some(10.0));
+    assert_eq!(
+        (tokens.p50, tokens.p90, tokens.p99),
+        (Some(0), Some(50), Some(50))
+    );
+
+    // 延迟差值：150、-100、150、400
+    let latency = &summary.latency_delta_ms;
+    assert_eq!(latency.samples, 4);
+    assert_eq!((latency.min, latency.max), (Some(-100), Some(400)));
+    assert_eq!(latency.mean, Some(150.0));
+    assert_eq!(
+        (latency.p50, latency.p90, latency.p99),
+        (Some(150), Some(400), Some(400))
+    );
+
+    // 逐请求差异按时间倒序
+    let ids: Vec<&str> = result
+        .diffs
+        .iter()
+        .map(|diff| diff.request_id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["req-4", "req-3", "req-2", "req-1"]);
+    assert_eq!(result.diffs[0].status_match, None);
+    assert_eq!(result.diffs[0].token_delta, None);
+    assert_eq!(result.diffs[1].status_match, Some(false));
+    assert_eq!(result.diffs[1].token_delta, Some(-20));
+    assert_eq!(result.diffs[1].latency_delta_ms, Some(150));
+}
+
+#[tokio::test]
+async fn mismatches_only_and_limit_filter_diffs_but_not_summary() {
+    let db = setup().await;
+    let service = ShadowDiffService::new(db);
+
+    let mismatches = service
+        .diffs(
+            &admin(),
+            &ShadowDiffQuery {
+                mismatches_only: true,
+                ..range_query()
+            },
+        )
+        .await
+        .expect("load diffs");
+    let ids: Vec<&str> = mismatches
+        .diffs
+        .iter()
+        .map(|diff| diff.request_id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["req-4", "req-3"]);
+    assert_eq!(mismatches.summary.total_requests, 4);
+
+    let limited = service
+        .diffs(
+            &admin(),
+            &ShadowDiffQuery {
+                limit: Some(1),
+                ..range_query()
+            },
+        )
+        .await
+        .expect("load diffs");
+    assert_eq!(limited.diffs.len(), 1);
+    assert_eq!(limited.summary.total_requests, 4);
+}
+
+#[tokio::test]
+async fn empty_range_and_permissions() {
+    let db = setup().await;
+    let service = ShadowDiffService::new(db);
+
+    let empty = service
+        .diffs(
+            &admin(),
+            &ShadowDiffQuery {
+                start: Some(base_time() + Duration::days(1)),
+                end: Some(base_time() + Duration::days(2)),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("load diffs");
+    assert_eq!(empty.summary.total_requests, 0);
+    assert!(empty.summary.status_match_rate.abs() < f64::EPSILON);
+    assert_eq!(empty.summary.token_delta.samples, 0);
+    assert_eq!(empty.summary.token_delta.p50, None);
+    assert!(empty.diffs.is_empty());
+
+    let inverted = ShadowDiffQuery {
+        start: Some(base_time()),
+        end: Some(base_time() - Duration::hours(1)),
+        ..Default::default()
+    };
+    assert!(service.diffs(&admin(), &inverted).await.is_err());
+
+    let user = AuthContext {
+        user_id: 2,
+        is_admin: false,
+    };
+    assert!(service.diffs(&user, &range_query()).await.is_err());
+}
@@ -0,0 +1,173 @@
+//! 响应时间目标（SLA）测试
+//!
+//! 验证请求完成时总耗时超过服务 API 的 `sla_target_ms` 会在追踪记录中标记 `sla_breached`，
+//! 以及超时率统计只按已完成的请求汇总。
+
+mod common;
+
+use api_proxy::management::services::ServiceApiService;
+use api_proxy::management::services::service_apis::UsageStatsQuery;
+use api_proxy::trace::immediate::{CompleteTraceParams, ImmediateProxyTracer, StartTraceParams};
+use chrono::{Duration, Utc};
+use entity::{proxy_tracing, user_service_apis};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+use std::sync::Arc;
+
+const USER_ID: i32 = 4600;
+const PROVIDER_TYPE_ID: i32 = 560;
+const SERVICE_API_ID: i32 = 6300;
+const SLA_TARGET_MS: i32 = 2000;
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(&db, common::provider(PROVIDER_TYPE_ID, "sla_provider")).await;
+    common::insert_service_api(
+        &db,
+        user_service_apis::ActiveModel {
+            sla_target_ms: Set(Some(SLA_TARGET_MS)),
+            ..common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_TYPE_ID, &[])
+        },
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+/// 开始追踪，并把开始时间回拨 `elapsed_ms` 以模拟请求耗时
+async fn start_trace(
+    db: &DatabaseConnection,
+    tracer: &ImmediateProxyTracer,
+    request_id: &str,
+    elapsed_ms: i64,
+) {
+    tracer
+        .start_trace(StartTraceParams {
+            request_id: request_id.to_string(),
+            user_service_api_id: SERVICE_API_ID,
+            user_id: Some(USER_ID),
+            provider_type_id: Some(PROVIDER_TYPE_ID),
+            user_provider_key_id: None,
+            method: "POST".to_string(),
+            path: Some("/v1/chat/completions".to_string()),
+            client_ip: None,
+            client_country: None,
+            client_asn: None,
+            user_agent: None,
+        })
+        .await
+        .expect("start trace");
+
+    proxy_tracing::Entity::update_many()
+        .filter(proxy_tracing::Column::RequestId.eq(request_id))
+        .set(proxy_tracing::ActiveModel {
+            start_time: Set(Some(
+                Utc::now().naive_utc() - Duration::milliseconds(elapsed_ms),
+            )),
+            ..Default::default()
+        })
+        .exec(db)
+        .await
+        .expect("rewind start time");
+}
+
+fn complete_params(sla_target_ms: Option<u64>) -> CompleteTraceParams {
+    CompleteTraceParams {
+        status_code: 200,
+        is_success: true,
+        tokens_prompt: Some(10),
+        tokens_completion: Some(5),
+        tokens_estimated: false,
+        error_type: None,
+        error_message: None,
+        retry_count: Some(0),
+        cache_create_tokens: None,
+        cache_read_tokens: None,
+        cost: None,
+        cost_currency: None,
+        request_metadata: None,
+        response_metadata: None,
+        request_body: None,
+        request_bytes: Some(100),
+        response_bytes: Some(200),
+        sla_target_ms,
+    }
+}
+
+async fn load_trace(db: &DatabaseConnection, request_id: &str) -> proxy_tracing::Model {
+    proxy_tracing::Entity::find()
+        .filter(proxy_tracing::Column::RequestId.eq(request_id))
+        .one(db)
+        .await
+        .expect("load trace")
+        .expect("trace exists")
+}
+
+#[tokio::test]
+async fn slow_request_is_marked_breached() {
+    let db = setup().await;
+    let tracer = ImmediateProxyTracer::new(db.clone());
+    let target = Some(u64::try_from(SLA_TARGET_MS).unwrap());
+
+    for (request_id, elapsed_ms, sla_target_ms) in [
+        ("sla-slow", 5_000, target),
+        ("sla-fast", 0, target),
+        ("sla-no-target", 5_000, None),
+    ] {
+        start_trace(&db, &tracer, request_id, elapsed_ms).await;
+        tracer
+            .complete_trace_with_stats(request_id, complete_params(sla_target_ms))
+            .await
+            .expect("complete trace");
+    }
+
+    let slow = load_trace(&db, "sla-slow").await;
+    assert!(slow.duration_ms.unwrap() >= 5_000);
+    assert!(slow.sla_breached);
+    assert!(!load_trace(&db, "sla-fast").await.sla_breached);
+    // 未配置目标时不标记
+    assert!(!load_trace(&db, "sla-no-target").await.sla_breached);
+}
+
+#[tokio::test]
+async fn breach_rate_counts_completed_requests_only() {
+    let db = setup().await;
+    let tracer = ImmediateProxyTracer::new(db.clone());
+    let target = Some(u64::try_from(SLA_TARGET_MS).unwrap());
+
+    for (request_id, elapsed_ms) in [
+        ("sla-rate-1", 3_000),
+        ("sla-rate-2", 0),
+        ("sla-rate-3", 0),
+        ("sla-rate-4", 0),
+    ] {
+        start_trace(&db, &tracer, request_id, elapsed_ms).await;
+        tracer
+            .complete_trace_with_stats(request_id, complete_params(target))
+            .await
+            .expect("complete trace");
+    }
+    // 进行中的请求不计入
+    start_trace(&db, &tracer, "sla-rate-pending", 10_000).await;
+
+    let query: UsageStatsQuery =
+        serde_json::from_value(json!({"time_range": "7days"})).expect("query");
+    let stats = ServiceApiService::from_db(&db)
+        .sla_stats(SERVICE_API_ID, USER_ID, &query, &chrono_tz::UTC)
+        .await
+        .expect("sla stats");
+
+    assert_eq!(stats.sla_target_ms, Some(SLA_TARGET_MS));
+    assert_eq!(stats.total_requests, 4);
+    assert_eq!(stats.breached_requests, 1);
+    assert!((stats.breach_rate - 25.0).abs() < f64::EPSILON);
+
+    // 其他用户无法查看
+    assert!(
+        ServiceApiService::from_db(&db)
+            .sla_stats(SERVICE_API_ID, USER_ID + 1, &query, &chrono_tz::UTC)
+            .await
+            .is_err()
+    );
+}
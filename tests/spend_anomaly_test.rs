@@ -0,0 +1,178 @@
+//! 消费异常检测测试
+//!
+//! 构造两个用户的历史消费：一个在当前小时出现消费尖峰，一个保持平稳，
+//! 验证只有尖峰用户被标记、webhook 收到通知且同一窗口内不重复标记。
+
+mod common;
+
+use api_proxy::config::SpendAnomalyConfig;
+use api_proxy::trace::SpendAnomalyDetectionTask;
+use axum::Router;
+use axum::extract::State;
+use axum::routing::post;
+use chrono::{Duration, NaiveDateTime, Utc};
+use entity::{proxy_tracing, spend_anomaly_flags};
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait, Set};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+const STEADY_USER_ID: i32 = 2500;
+const SPIKE_USER_ID: i32 = 2501;
+const PROVIDER_TYPE_ID: i32 = 350;
+const BASELINE_HOURS: u32 = 24;
+
+/// 模拟 webhook：把收到的请求体转发到通道
+async fn spawn_webhook() -> (String, mpsc::UnboundedReceiver<Value>) {
+    async fn receive(
+        State(sender): State<mpsc::UnboundedSender<Value>>,
+        axum::Json(body): axum::Json<Value>,
+    ) {
+        let _ = sender.send(body);
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind mock webhook");
+    let addr = listener.local_addr().expect("mock webhook addr");
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .with_state(sender);
+    tokio::spawn(async move {
+        axum::serve(listener, app)
+            .await
+            .expect("serve mock webhook");
+    });
+    (format!("http://{addr}/hook"), receiver)
+}
+
+async fn insert_trace(
+    db: &DatabaseConnection,
+    user_id: i32,
+    service_api_id: i32,
+    cost: f64,
+    created_at: NaiveDateTime,
+) {
+    proxy_tracing::Entity::insert(proxy_tracing::ActiveModel {
+        user_service_api_id: Set(service_api_id),
+        request_id: Set(format!(
+            "req-spend-{user_id}-{}",
+            created_at.and_utc().timestamp()
+        )),
+        method: Set("POST".to_string()),
+        path: Set(Some("/v1/chat/completions".to_string())),
+        status_code: Set(Some(200)),
+        cost: Set(Some(cost)),
+        user_id: Set(Some(user_id)),
+        provider_type_id: Set(Some(PROVIDER_TYPE_ID)),
+        is_success: Set(true),
+        created_at: Set(created_at),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .expect("insert trace");
+}
+
+async fn setup(now: NaiveDateTime) -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "spend_anomaly_provider"),
+    )
+    .await;
+
+    for user_id in [STEADY_USER_ID, SPIKE_USER_ID] {
+        common::insert_user(&db, common::user(user_id)).await;
+        let service_api_id = user_id + 2000;
+        common::insert_service_api(
+            &db,
+            common::service_api(service_api_id, user_id, PROVIDER_TYPE_ID, &[]),
+        )
+        .await;
+
+        // 基线：之前每小时稳定消费 1.0
+        for hour in 1..=i64::from(BASELINE_HOURS) {
+            let created_at = now - Duration::hours(hour) - Duration::minutes(30);
+            insert_trace(&db, user_id, service_api_id, 1.0, created_at).await;
+        }
+    }
+
+    // 当前小时：平稳用户略有上升，尖峰用户消费暴涨
+    insert_trace(
+        &db,
+        STEADY_USER_ID,
+        STEADY_USER_ID + 2000,
+        0.7,
+        now - Duration::minutes(40),
+    )
+    .await;
+    insert_trace(
+        &db,
+        STEADY_USER_ID,
+        STEADY_USER_ID + 2000,
+        0.8,
+        now - Duration::minutes(10),
+    )
+    .await;
+    insert_trace(
+        &db,
+        SPIKE_USER_ID,
+        SPIKE_USER_ID + 2000,
+        6.0,
+        now - Duration::minutes(40),
+    )
+    .await;
+    insert_trace(
+        &db,
+        SPIKE_USER_ID,
+        SPIKE_USER_ID + 2000,
+        6.0,
+        now - Duration::minutes(10),
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+#[tokio::test]
+async fn spend_spike_is_flagged_and_steady_usage_is_not() {
+    let now = Utc::now();
+    let db = setup(now.naive_utc()).await;
+    let (webhook_url, mut webhook) = spawn_webhook().await;
+    let task = SpendAnomalyDetectionTask::new(
+        db.clone(),
+        SpendAnomalyConfig {
+            enabled: true,
+            baseline_hours: BASELINE_HOURS,
+            multiplier: 3.0,
+            min_hourly_spend: 1.0,
+            webhook_url: Some(webhook_url),
+            ..SpendAnomalyConfig::default()
+        },
+    );
+
+    let flags = task.detect(now).await.expect("detect");
+    assert_eq!(flags.len(), 1);
+    let flag = &flags[0];
+    assert_eq!(flag.user_id, SPIKE_USER_ID);
+    assert!((flag.current_spend - 12.0).abs() < 1e-9);
+    assert!((flag.baseline_hourly_spend - 1.0).abs() < 1e-9);
+    assert!(flag.acknowledged_at.is_none());
+
+    let notification = webhook.recv().await.expect("webhook notified");
+    assert_eq!(notification["event"], "spend_anomaly");
+    assert_eq!(notification["flag"]["user_id"], SPIKE_USER_ID);
+
+    // 同一窗口内再次检测不重复标记
+    assert!(task.detect(now).await.expect("detect again").is_empty());
+    assert_eq!(
+        spend_anomaly_flags::Entity::find()
+            .count(db.as_ref())
+            .await
+            .unwrap(),
+        1
+    );
+}
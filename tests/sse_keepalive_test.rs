@@ -0,0 +1,99 @@
+//! SSE keepalive 端到端测试
+//!
+//! 模拟上游返回 SSE 响应头后静默一段时间再输出事件，经代理转发后，
+//! 客户端应在首个事件之前按间隔收到 `: ping` 注释帧，事件开始后不再收到。
+
+mod common;
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const KEEPALIVE_INTERVAL_MS: u64 = 1_000;
+const UPSTREAM_SILENCE_MS: u64 = 2_500;
+const REQUEST_BODY: &str =
+    r#"{"model":"gpt-4o","stream":true,"messages":[{"role":"user","content":"hi"}]}"#;
+
+/// 模拟上游：校验注入的认证头，先发 SSE 响应头，静默后再逐个输出事件
+async fn spawn_silent_sse_upstream() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind upstream");
+    let addr = listener.local_addr().expect("upstream addr");
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("accept upstream");
+        let head = common::read_request_head(&mut socket).await;
+        assert!(
+            head.contains(&format!("Bearer {}", common::UPSTREAM_API_KEY)),
+            "{head}"
+        );
+        let mut body = vec![0u8; REQUEST_BODY.len()];
+        socket
+            .read_exact(&mut body)
+            .await
+            .expect("read request body");
+
+        socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n",
+            )
+            .await
+            .expect("write response head");
+        tokio::time::sleep(Duration::from_millis(UPSTREAM_SILENCE_MS)).await;
+        write_chunk(&mut socket, "data: {\"delta\":\"hi\"}\n\n").await;
+        // 数据开始后即使再次长时间静默也不应插入注释帧
+        tokio::time::sleep(Duration::from_millis(UPSTREAM_SILENCE_MS)).await;
+        write_chunk(&mut socket, "data: [DONE]\n\n").await;
+        socket
+            .write_all(b"0\r\n\r\n")
+            .await
+            .expect("write last chunk");
+    });
+    format!("http://{addr}")
+}
+
+async fn write_chunk(socket: &mut TcpStream, data: &str) {
+    socket
+        .write_all(format!("{:x}\r\n{data}\r\n", data.len()).as_bytes())
+        .await
+        .expect("write chunk");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keepalive_pings_sent_while_upstream_is_silent() {
+    let upstream = spawn_silent_sse_upstream().await;
+    let proxy = common::start_proxy(&upstream, |config| {
+        config.streaming.sse_keepalive_interval_ms = KEEPALIVE_INTERVAL_MS;
+    })
+    .await;
+
+    let mut client = TcpStream::connect(proxy.addr).await.expect("connect proxy");
+    let request = format!(
+        "POST /v1/chat/completions HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\n\
+Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{REQUEST_BODY}",
+        common::CLIENT_API_KEY,
+        REQUEST_BODY.len()
+    );
+    client
+        .write_all(request.as_bytes())
+        .await
+        .expect("write request");
+
+    let mut response = Vec::new();
+    tokio::time::timeout(Duration::from_secs(20), client.read_to_end(&mut response))
+        .await
+        .expect("response completes")
+        .expect("read response");
+    let response = String::from_utf8(response).expect("response is utf-8");
+
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    let first_event = response.find("data: {").expect("first event forwarded");
+    assert!(response.contains("data: [DONE]"), "{response}");
+    // 静默 2.5 秒、间隔 1 秒：首个事件之前至少两次注释帧，之后没有
+    assert!(
+        response[..first_event].matches(": ping\n\n").count() >= 2,
+        "{response}"
+    );
+    assert!(!response[first_event..].contains(": ping"), "{response}");
+}
@@ -0,0 +1,172 @@
+//! 服务 API 流式策略测试
+//!
+//! `deny` 拒绝流式请求并返回 400；`force-off` 将流式请求改写为非流式（含 Gemini 路径）；
+//! `allow` 原样转发。
+//! 旧的 `force_non_streaming` 开关由迁移合并为 `force-off`。
+
+mod common;
+
+use api_proxy::error::reject::RejectReason;
+use api_proxy::proxy::ProxyContext;
+use api_proxy::proxy::non_streaming::{self, StreamPolicy};
+use api_proxy::proxy::response::build_streaming_denied_response;
+use entity::user_service_apis;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, EntityTrait, Set};
+use serde_json::{Value, json};
+
+const USER_ID: i32 = 3800;
+const PROVIDER_TYPE_ID: i32 = 490;
+const ALLOW_API_ID: i32 = 5700;
+const DENY_API_ID: i32 = 5701;
+const FORCE_OFF_API_ID: i32 = 5702;
+const LEGACY_FORCE_API_ID: i32 = 5703;
+
+const CHAT_PATH: &str = "/v1/chat/completions";
+const GEMINI_STREAM_PATH: &str = "/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse";
+
+async fn setup() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("connect test db");
+    // 先迁移到合并旧开关之前，开启旧开关的服务 API 由最后一个迁移改为 `force-off`
+    let before_fold = u32::try_from(Migrator::migrations().len() - 1).expect("migration count");
+    Migrator::up(&db, Some(before_fold))
+        .await
+        .expect("run migrations before fold");
+
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "stream_policy_provider"),
+    )
+    .await;
+
+    for (id, policy) in [
+        (ALLOW_API_ID, None),
+        (DENY_API_ID, Some("deny")),
+        (FORCE_OFF_API_ID, Some("force-off")),
+        (LEGACY_FORCE_API_ID, None),
+    ] {
+        let mut model = common::service_api(id, USER_ID, PROVIDER_TYPE_ID, &[]);
+        if let Some(policy) = policy {
+            model.stream_policy = Set(policy.to_string());
+        }
+        common::insert_service_api(&db, model).await;
+    }
+    db.execute_unprepared(&format!(
+        "UPDATE user_service_apis SET force_non_streaming = 1 WHERE id = {LEGACY_FORCE_API_ID}"
+    ))
+    .await
+    .expect("enable legacy force_non_streaming");
+
+    Migrator::up(&db, None).await.expect("run migrations");
+    db
+}
+
+async fn context_for(db: &DatabaseConnection, api_id: i32) -> ProxyContext {
+    let service_api = user_service_apis::Entity::find_by_id(api_id)
+        .one(db)
+        .await
+        .expect("load service api")
+        .expect("service api exists");
+    let mut ctx = ProxyContext::default();
+    ctx.routing.user_service_api = Some(service_api);
+    ctx
+}
+
+fn streaming_body() -> Value {
+    json!({
+        "model": "gpt-4o",
+        "stream": true,
+        "stream_options": {"include_usage": true},
+        "messages": [{"role": "user", "content": "hi"}]
+    })
+}
+
+fn path_of(path_and_query: &str) -> &str {
+    path_and_query.split('?').next().unwrap_or(path_and_query)
+}
+
+#[tokio::test]
+async fn deny_rejects_streaming_requests() {
+    let db = setup().await;
+    let ctx = context_for(&db, DENY_API_ID).await;
+
+    assert_eq!(StreamPolicy::from_context(&ctx), StreamPolicy::Deny);
+    assert!(non_streaming::is_denied(&ctx));
+    assert!(!non_streaming::is_forced(&ctx));
+
+    assert!(non_streaming::requests_streaming(
+        CHAT_PATH,
+        &streaming_body()
+    ));
+    assert!(non_streaming::requests_streaming(
+        path_of(GEMINI_STREAM_PATH),
+        &json!({"contents": []})
+    ));
+    // 非流式请求不受影响
+    assert!(!non_streaming::requests_streaming(
+        CHAT_PATH,
+        &json!({"model": "gpt-4o", "stream": false})
+    ));
+
+    let rejection = build_streaming_denied_response();
+    assert_eq!(rejection.status, 400);
+    assert_eq!(rejection.reason, RejectReason::StreamingNotAllowed);
+    assert_eq!(
+        rejection.payload["error"]["reason_code"],
+        "streaming_not_allowed"
+    );
+}
+
+#[tokio::test]
+async fn force_off_rewrites_streaming_requests() {
+    let db = setup().await;
+    for api_id in [FORCE_OFF_API_ID, LEGACY_FORCE_API_ID] {
+        let ctx = context_for(&db, api_id).await;
+        let stored = ctx.routing.user_service_api.as_ref().unwrap();
+        assert_eq!(stored.stream_policy, "force-off");
+        assert_eq!(StreamPolicy::from_context(&ctx), StreamPolicy::ForceOff);
+        assert!(non_streaming::is_forced(&ctx));
+        assert!(!non_streaming::is_denied(&ctx));
+    }
+
+    let mut body = streaming_body();
+    assert!(non_streaming::disable_body_streaming(&mut body));
+    assert_eq!(body["stream"], false);
+    assert!(body.get("stream_options").is_none());
+    assert!(!non_streaming::requests_streaming(CHAT_PATH, &body));
+
+    assert_eq!(
+        non_streaming::non_streaming_gemini_path(GEMINI_STREAM_PATH).as_deref(),
+        Some("/v1beta/models/gemini-2.5-pro:generateContent")
+    );
+}
+
+#[tokio::test]
+async fn allow_passes_streaming_requests_through() {
+    let db = setup().await;
+    let ctx = context_for(&db, ALLOW_API_ID).await;
+
+    assert_eq!(
+        ctx.routing
+            .user_service_api
+            .as_ref()
+            .map(|api| api.stream_policy.as_str()),
+        Some("allow")
+    );
+    assert_eq!(StreamPolicy::from_context(&ctx), StreamPolicy::Allow);
+    assert!(!non_streaming::is_denied(&ctx));
+    assert!(!non_streaming::is_forced(&ctx));
+    assert!(non_streaming::requests_streaming(
+        CHAT_PATH,
+        &streaming_body()
+    ));
+
+    // 没有服务 API 时同样放行
+    assert_eq!(
+        StreamPolicy::from_context(&ProxyContext::default()),
+        StreamPolicy::Allow
+    );
+}
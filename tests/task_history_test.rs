@@ -0,0 +1,184 @@
+//! 后台任务执行历史测试
+//!
+//! 通过调度器执行成功与失败的任务，验证每次执行都写入 `task_run_history`，
+//! 周期性任务按每轮执行写入，失败记录携带错误信息，并可按任务类型与时间范围过滤。
+
+use std::sync::Arc;
+
+use api_proxy::app::{ScheduledTask, TaskRunHistory, TaskRunHistoryQuery, TaskScheduler, TaskType};
+use api_proxy::config::SpendAnomalyConfig;
+use api_proxy::error::ProxyError;
+use api_proxy::trace::SpendAnomalyDetectionTask;
+use chrono::{Duration, Utc};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, DatabaseConnection};
+
+async fn setup() -> Arc<DatabaseConnection> {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("connect test db");
+    Migrator::up(&db, None).await.expect("run migrations");
+    Arc::new(db)
+}
+
+#[tokio::test]
+async fn successful_run_records_history_row() {
+    let db = setup().await;
+    let history = TaskRunHistory::new(db);
+    let scheduler = TaskScheduler::new().with_run_history(history.clone());
+    scheduler
+        .register(
+            ScheduledTask::builder(TaskType::ModelPricingRefresh)
+                .on_start(|| async { Ok(()) })
+                .build(),
+        )
+        .await;
+
+    scheduler.start_all().await.expect("task should start");
+
+    let runs = history
+        .list(&TaskRunHistoryQuery::default())
+        .await
+        .expect("list history");
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run.task_type, "model_pricing_refresh");
+    assert_eq!(run.status, "success");
+    assert!(run.error.is_none());
+    assert!(run.finished_at >= run.started_at);
+    assert!(run.duration_ms >= 0);
+}
+
+#[tokio::test]
+async fn failed_run_records_error() {
+    let db = setup().await;
+    let history = TaskRunHistory::new(db);
+    let scheduler = TaskScheduler::new().with_run_history(history.clone());
+    scheduler
+        .register(
+            ScheduledTask::builder(TaskType::ApiKeyRateLimitCache)
+                .on_start(|| async { Err(ProxyError::from("warmup failed")) })
+                .build(),
+        )
+        .await;
+
+    assert!(scheduler.start_all().await.is_err());
+
+    let runs = history
+        .list(&TaskRunHistoryQuery::default())
+        .await
+        .expect("list history");
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].task_type, "api_key_rate_limit_cache");
+    assert_eq!(runs[0].status, "failed");
+    assert!(
+        runs[0]
+            .error
+            .as_deref()
+            .is_some_and(|error| error.contains("warmup failed"))
+    );
+}
+
+#[tokio::test]
+async fn periodic_task_records_each_tick() {
+    let db = setup().await;
+    let history = TaskRunHistory::new(db.clone());
+    let scheduler = TaskScheduler::new().with_run_history(history.clone());
+    let task = Arc::new(
+        SpendAnomalyDetectionTask::new(
+            db,
+            SpendAnomalyConfig {
+                enabled: true,
+                check_interval_secs: 1,
+                ..SpendAnomalyConfig::default()
+            },
+        )
+        .with_run_recorder(scheduler.recorder(TaskType::SpendAnomalyDetection)),
+    );
+    scheduler
+        .register(
+            ScheduledTask::builder(TaskType::SpendAnomalyDetection)
+                .periodic()
+                .on_start({
+                    let task = task.clone();
+                    move || {
+                        let task = task.clone();
+                        async move { task.start().await }
+                    }
+                })
+                .build(),
+        )
+        .await;
+
+    scheduler.start_all().await.expect("task should start");
+    // 首轮检测立即执行，1 秒后执行第二轮；启动本身不计为一次执行
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    task.stop().await;
+
+    let runs = history
+        .list(&TaskRunHistoryQuery::default())
+        .await
+        .expect("list history");
+    assert_eq!(runs.len(), 2);
+    assert!(
+        runs.iter()
+            .all(|run| run.task_type == "spend_anomaly_detection" && run.status == "success")
+    );
+
+    let heartbeat = scheduler
+        .heartbeats()
+        .get(TaskType::SpendAnomalyDetection)
+        .expect("heartbeat registered");
+    assert_eq!(heartbeat.run_count, 2);
+    assert!(heartbeat.last_success_at.is_some());
+}
+
+#[tokio::test]
+async fn history_filters_by_task_type_and_time_range() {
+    let db = setup().await;
+    let history = TaskRunHistory::new(db);
+    let now = Utc::now();
+    let elapsed = std::time::Duration::from_millis(5);
+    history
+        .record(
+            TaskType::TraceWriter,
+            now - Duration::hours(3),
+            elapsed,
+            None,
+        )
+        .await
+        .expect("record old run");
+    history
+        .record(TaskType::TraceWriter, now, elapsed, Some("flush failed"))
+        .await
+        .expect("record recent run");
+    history
+        .record(TaskType::UsageCounter, now, elapsed, None)
+        .await
+        .expect("record other task");
+
+    let trace_writer_runs = history
+        .list(&TaskRunHistoryQuery {
+            task_type: Some("trace_writer".to_string()),
+            ..Default::default()
+        })
+        .await
+        .expect("filter by task type");
+    assert_eq!(trace_writer_runs.len(), 2);
+    // 按开始时间倒序
+    assert_eq!(trace_writer_runs[0].status, "failed");
+    assert_eq!(trace_writer_runs[1].status, "success");
+
+    let recent_runs = history
+        .list(&TaskRunHistoryQuery {
+            task_type: Some("trace_writer".to_string()),
+            start_time: Some(now - Duration::hours(1)),
+            end_time: Some(now + Duration::minutes(1)),
+            limit: None,
+        })
+        .await
+        .expect("filter by time range");
+    assert_eq!(recent_runs.len(), 1);
+    assert_eq!(recent_runs[0].error.as_deref(), Some("flush failed"));
+    assert_eq!(recent_runs[0].duration_ms, 5);
+}
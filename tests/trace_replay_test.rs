@@ -0,0 +1,246 @@
+//! 追踪请求回放测试
+//!
+//! 验证采集的请求体按指定密钥直连上游回放，结果标记为回放且不改动原追踪记录。
+
+use api_proxy::error::ProxyError;
+use api_proxy::management::middleware::AuthContext;
+use api_proxy::management::services::{TraceReplayRequest, TraceReplayService};
+use axum::Router;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use chrono::Utc;
+use entity::{provider_types, proxy_tracing, user_provider_keys, user_service_apis, users};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, DatabaseConnection, EntityTrait, PaginatorTrait, Set};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+const USER_ID: i32 = 2200;
+const PROVIDER_TYPE_ID: i32 = 320;
+const SERVICE_API_ID: i32 = 4200;
+const KEY_ID: i32 = 5200;
+const TRACE_ID: i32 = 6200;
+const UNCAPTURED_TRACE_ID: i32 = 6201;
+
+const ADMIN: AuthContext = AuthContext {
+    user_id: 1,
+    is_admin: true,
+};
+
+/// 模拟上游：校验密钥并回显请求体与用量
+async fn spawn_mock_upstream() -> String {
+    async fn chat(headers: HeaderMap, body: String) -> (StatusCode, axum::Json<Value>) {
+        if headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            != Some("Bearer sk-replay")
+        {
+            return (
+                StatusCode::UNAUTHORIZED,
+                axum::Json(json!({"error": "bad key"})),
+            );
+        }
+        let request: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+        (
+            StatusCode::OK,
+            axum::Json(json!({
+                "model": "gpt-4o-mini",
+                "echo": request,
+                "usage": {"prompt_tokens": 7, "completion_tokens": 3, "total_tokens": 10}
+            })),
+        )
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind mock upstream");
+    let addr = listener.local_addr().expect("mock upstream addr");
+    let app = Router::new().route("/v1/chat/completions", post(chat));
+    tokio::spawn(async move {
+        axum::serve(listener, app)
+            .await
+            .expect("serve mock upstream");
+    });
+    format!("http://{addr}")
+}
+
+async fn setup(base_url: &str) -> Arc<DatabaseConnection> {
+    let db = Database::connect("sqlite::memory:")
+        .await
+        .expect("connect test db");
+    Migrator::up(&db, None).await.expect("run migrations");
+    let now = Utc::now().naive_utc();
+
+    users::Entity::insert(users::ActiveModel {
+        id: Set(USER_ID),
+        username: Set("replay_user".to_string()),
+        password_hash: Set("hashed".to_string()),
+        email: Set("replay@test.com".to_string()),
+        salt: Set("salt".to_string()),
+        is_admin: Set(false),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&db)
+    .await
+    .expect("insert user");
+
+    provider_types::Entity::insert(provider_types::ActiveModel {
+        id: Set(PROVIDER_TYPE_ID),
+        name: Set("openai_replay_test".to_string()),
+        display_name: Set("OpenAI Replay Test".to_string()),
+        auth_type: Set("api_key".to_string()),
+        base_url: Set(base_url.to_string()),
+        is_active: Set(true),
+        token_mappings_json: Set(Some(
+            json!({
+                "tokens_prompt": {"type": "direct", "path": "usage.prompt_tokens"},
+                "tokens_completion": {"type": "direct", "path": "usage.completion_tokens"},
+                "tokens_total": {"type": "direct", "path": "usage.total_tokens"}
+            })
+            .to_string(),
+        )),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&db)
+    .await
+    .expect("insert provider");
+
+    user_provider_keys::Entity::insert(user_provider_keys::ActiveModel {
+        id: Set(KEY_ID),
+        user_id: Set(USER_ID),
+        provider_type_id: Set(PROVIDER_TYPE_ID),
+        api_key: Set("sk-replay".to_string()),
+        auth_type: Set("api_key".to_string()),
+        name: Set("Replay Key".to_string()),
+        is_active: Set(true),
+        health_status: Set("healthy".to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&db)
+    .await
+    .expect("insert provider key");
+
+    user_service_apis::Entity::insert(user_service_apis::ActiveModel {
+        id: Set(SERVICE_API_ID),
+        user_id: Set(USER_ID),
+        provider_type_id: Set(PROVIDER_TYPE_ID),
+        api_key: Set("replay-service-api".to_string()),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&db)
+    .await
+    .expect("insert service api");
+
+    for (id, request_body) in [
+        (
+            TRACE_ID,
+            Some(json!({"model": "gpt-4o-mini", "messages": [{"role": "user", "content": "hi"}]})),
+        ),
+        (UNCAPTURED_TRACE_ID, None),
+    ] {
+        proxy_tracing::Entity::insert(proxy_tracing::ActiveModel {
+            id: Set(id),
+            user_service_api_id: Set(SERVICE_API_ID),
+            user_provider_key_id: Set(Some(KEY_ID)),
+            request_id: Set(format!("req-replay-{id}")),
+            method: Set("POST".to_string()),
+            path: Set(Some("/v1/chat/completions".to_string())),
+            status_code: Set(Some(500)),
+            user_id: Set(Some(USER_ID)),
+            provider_type_id: Set(Some(PROVIDER_TYPE_ID)),
+            request_body: Set(request_body.map(|body| body.to_string())),
+            is_success: Set(false),
+            created_at: Set(now),
+            ..Default::default()
+        })
+        .exec(&db)
+        .await
+        .expect("insert trace");
+    }
+
+    Arc::new(db)
+}
+
+#[tokio::test]
+async fn replays_captured_request_against_upstream() {
+    let base_url = spawn_mock_upstream().await;
+    let db = setup(&base_url).await;
+    let service = TraceReplayService::new(db.clone(), reqwest::Client::new());
+
+    let result = service
+        .replay(&ADMIN, TRACE_ID, &TraceReplayRequest::default())
+        .await
+        .expect("replay succeeds");
+
+    assert!(result.replay);
+    assert_eq!(result.status_code, 200);
+    assert_eq!(result.provider_key_id, KEY_ID);
+    assert_eq!(result.original_request_id, format!("req-replay-{TRACE_ID}"));
+    assert_eq!(result.response_body["echo"]["messages"][0]["content"], "hi");
+    assert_eq!(result.usage.prompt_tokens, Some(7));
+    assert_eq!(result.usage.completion_tokens, Some(3));
+    assert_eq!(result.usage.total_tokens, Some(10));
+
+    // 回放单独统计：不新增追踪记录，也不改写原记录
+    assert_eq!(
+        proxy_tracing::Entity::find()
+            .count(db.as_ref())
+            .await
+            .unwrap(),
+        2
+    );
+    let original = proxy_tracing::Entity::find_by_id(TRACE_ID)
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(original.status_code, Some(500));
+    assert_eq!(original.tokens_total, None);
+}
+
+#[tokio::test]
+async fn rejects_non_admin_and_uncaptured_traces() {
+    let base_url = spawn_mock_upstream().await;
+    let db = setup(&base_url).await;
+    let service = TraceReplayService::new(db, reqwest::Client::new());
+
+    let user = AuthContext {
+        user_id: USER_ID,
+        is_admin: false,
+    };
+    let err = service
+        .replay(&user, TRACE_ID, &TraceReplayRequest::default())
+        .await
+        .expect_err("non-admin rejected");
+    assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+
+    let err: ProxyError = service
+        .replay(&ADMIN, UNCAPTURED_TRACE_ID, &TraceReplayRequest::default())
+        .await
+        .expect_err("uncaptured trace rejected");
+    assert_eq!(err.error_code(), "MANAGEMENT_TRACE_NOT_REPLAYABLE");
+    assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+    let err = service
+        .replay(
+            &ADMIN,
+            TRACE_ID,
+            &TraceReplayRequest {
+                provider_key_id: Some(9999),
+            },
+        )
+        .await
+        .expect_err("missing key rejected");
+    assert_eq!(err.error_code(), "MANAGEMENT_TRACE_NOT_REPLAYABLE");
+}
@@ -0,0 +1,198 @@
+//! 追踪外部输出测试
+//!
+//! 用模拟 HTTP 接收端验证完成记录按批推送、附带配置的请求头，推送失败时重试，
+//! 以及停止任务时推送剩余记录。
+
+use api_proxy::config::{HttpTraceSinkConfig, TraceSinkConfig};
+use api_proxy::trace::immediate::CompleteTraceParams;
+use api_proxy::trace::{TraceExporter, TraceRecord};
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+/// 接收到的一次推送：鉴权头与请求体
+type Delivery = (Option<String>, Value);
+
+#[derive(Clone)]
+struct MockSink {
+    sender: mpsc::UnboundedSender<Delivery>,
+    /// 前几次请求返回 500
+    failures: Arc<AtomicUsize>,
+}
+
+/// 模拟接收端：前 `failures` 次请求返回 500，之后把请求转发到通道
+async fn spawn_sink(failures: usize) -> (String, mpsc::UnboundedReceiver<Delivery>) {
+    async fn receive(
+        State(sink): State<MockSink>,
+        headers: HeaderMap,
+        axum::Json(body): axum::Json<Value>,
+    ) -> StatusCode {
+        let remaining = sink.failures.load(Ordering::SeqCst);
+        if remaining > 0 {
+            sink.failures.store(remaining - 1, Ordering::SeqCst);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+        let authorization = headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+        let _ = sink.sender.send((authorization, body));
+        StatusCode::OK
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind mock sink");
+    let addr = listener.local_addr().expect("mock sink addr");
+    let app = Router::new()
+        .route("/traces", post(receive))
+        .with_state(MockSink {
+            sender,
+            failures: Arc::new(AtomicUsize::new(failures)),
+        });
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.expect("serve mock sink");
+    });
+    (format!("http://{addr}/traces"), receiver)
+}
+
+fn sink_config(url: String, batch_size: usize, flush_interval_ms: u64) -> TraceSinkConfig {
+    let config: HttpTraceSinkConfig = serde_json::from_value(json!({
+        "url": url,
+        "headers": { "Authorization": "Bearer sink-token" },
+        "batch_size": batch_size,
+        "flush_interval_ms": flush_interval_ms,
+        "retry_backoff_ms": 10,
+    }))
+    .expect("sink config");
+    TraceSinkConfig { http: Some(config) }
+}
+
+fn record(request_id: &str) -> TraceRecord {
+    TraceRecord::new(
+        request_id,
+        CompleteTraceParams {
+            status_code: 200,
+            is_success: true,
+            tokens_prompt: Some(10),
+            tokens_completion: Some(5),
+            tokens_estimated: false,
+            error_type: None,
+            error_message: None,
+            retry_count: Some(0),
+            cache_create_tokens: None,
+            cache_read_tokens: None,
+            cost: Some(0.01),
+            cost_currency: Some("USD".to_string()),
+            request_metadata: None,
+            response_metadata: Some(json!({"model": "gpt-4o"})),
+            request_body: Some(r#"{"model":"gpt-4o"}"#.to_string()),
+            request_bytes: Some(18),
+            response_bytes: Some(200),
+            sla_target_ms: None,
+        },
+    )
+}
+
+async fn next_delivery(receiver: &mut mpsc::UnboundedReceiver<Delivery>) -> Delivery {
+    tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .expect("delivery within timeout")
+        .expect("sink channel open")
+}
+
+fn request_ids(body: &Value) -> Vec<&str> {
+    body["records"]
+        .as_array()
+        .expect("records array")
+        .iter()
+        .map(|record| record["request_id"].as_str().expect("request_id"))
+        .collect()
+}
+
+#[tokio::test]
+async fn records_are_pushed_as_a_batch() {
+    let (url, mut receiver) = spawn_sink(0).await;
+    // 刷新间隔足够长，只有攒满一批才会推送
+    let exporter = Arc::new(
+        TraceExporter::from_config(&sink_config(url, 3, 60_000)).expect("exporter configured"),
+    );
+    exporter.start().await.expect("start exporter");
+
+    for request_id in ["sink-1", "sink-2", "sink-3"] {
+        exporter.send(record(request_id));
+    }
+
+    let (authorization, body) = next_delivery(&mut receiver).await;
+    assert_eq!(authorization.as_deref(), Some("Bearer sink-token"));
+    assert_eq!(request_ids(&body), ["sink-1", "sink-2", "sink-3"]);
+    let first = &body["records"][0];
+    assert_eq!(first["status_code"], 200);
+    assert_eq!(first["tokens_prompt"], 10);
+    assert_eq!(first["response_metadata"]["model"], "gpt-4o");
+    assert_eq!(first["request_body"], r#"{"model":"gpt-4o"}"#);
+    assert!(first["completed_at"].is_string());
+
+    exporter.stop().await;
+    assert_eq!(exporter.exported(), 3);
+    assert_eq!(exporter.dropped(), 0);
+}
+
+#[tokio::test]
+async fn failed_push_is_retried() {
+    let (url, mut receiver) = spawn_sink(2).await;
+    let exporter = Arc::new(
+        TraceExporter::from_config(&sink_config(url, 10, 20)).expect("exporter configured"),
+    );
+    exporter.start().await.expect("start exporter");
+
+    exporter.send(record("sink-retry-1"));
+    exporter.send(record("sink-retry-2"));
+
+    // 前两次返回 500，第三次成功，整批只送达一次
+    let (_, body) = next_delivery(&mut receiver).await;
+    assert_eq!(request_ids(&body), ["sink-retry-1", "sink-retry-2"]);
+
+    exporter.stop().await;
+    assert!(receiver.try_recv().is_err());
+    assert_eq!(exporter.exported(), 2);
+    assert_eq!(exporter.dropped(), 0);
+}
+
+#[tokio::test]
+async fn stop_flushes_pending_records() {
+    let (url, mut receiver) = spawn_sink(0).await;
+    let exporter = Arc::new(
+        TraceExporter::from_config(&sink_config(url, 100, 60_000)).expect("exporter configured"),
+    );
+    exporter.start().await.expect("start exporter");
+
+    exporter.send(record("sink-pending-1"));
+    exporter.send(record("sink-pending-2"));
+    exporter.stop().await;
+
+    let (_, body) = next_delivery(&mut receiver).await;
+    assert_eq!(request_ids(&body), ["sink-pending-1", "sink-pending-2"]);
+}
+
+#[test]
+fn sink_is_disabled_without_http_config() {
+    assert!(TraceExporter::from_config(&TraceSinkConfig::default()).is_none());
+
+    let mut invalid = sink_config("ftp://collector.test".to_string(), 10, 1000);
+    assert!(invalid.validate().is_err());
+    invalid.http.as_mut().expect("http config").url = "https://collector.test".to_string();
+    assert!(invalid.validate().is_ok());
+
+    let headers: HashMap<String, String> = invalid.http.expect("http config").headers;
+    assert_eq!(headers["Authorization"], "Bearer sink-token");
+}
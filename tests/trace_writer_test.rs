@@ -0,0 +1,228 @@
+//! 追踪异步写入队列测试
+//!
+//! 验证完成记录入队后由后台任务写库，以及队列写满时 `drop_oldest` 与 `block` 两种策略的行为。
+
+mod common;
+
+use api_proxy::config::{TraceOverflowPolicy, TraceWriterConfig};
+use api_proxy::trace::immediate::{CompleteTraceParams, ImmediateProxyTracer, StartTraceParams};
+use api_proxy::trace::writer::{EnqueueOutcome, TraceWriter};
+use entity::proxy_tracing;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::Arc;
+use std::time::Duration;
+
+const USER_ID: i32 = 2700;
+const PROVIDER_TYPE_ID: i32 = 370;
+const SERVICE_API_ID: i32 = 4700;
+
+/// 等待后台写完的超时，避免测试在写入任务异常时挂起
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn setup_test_db() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(&db, common::provider(PROVIDER_TYPE_ID, "writer_provider")).await;
+    common::insert_service_api(
+        &db,
+        common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_TYPE_ID, &[]),
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+async fn start_traces(tracer: &ImmediateProxyTracer, request_ids: &[&str]) {
+    for request_id in request_ids {
+        tracer
+            .start_trace(StartTraceParams {
+                request_id: (*request_id).to_string(),
+                user_service_api_id: SERVICE_API_ID,
+                user_id: Some(USER_ID),
+                provider_type_id: Some(PROVIDER_TYPE_ID),
+                user_provider_key_id: None,
+                method: "POST".to_string(),
+                path: Some("/v1/chat/completions".to_string()),
+                client_ip: None,
+                client_country: None,
+                client_asn: None,
+                user_agent: None,
+            })
+            .await
+            .expect("start trace");
+    }
+}
+
+fn complete_params() -> CompleteTraceParams {
+    CompleteTraceParams {
+        status_code: 200,
+        is_success: true,
+        tokens_prompt: Some(10),
+        tokens_completion: Some(5),
+        tokens_estimated: false,
+        error_type: None,
+        error_message: None,
+        retry_count: Some(0),
+        cache_create_tokens: None,
+        cache_read_tokens: None,
+        cost: Some(0.01),
+        cost_currency: Some("USD".to_string()),
+        request_metadata: None,
+        response_metadata: None,
+        request_body: None,
+        request_bytes: Some(100),
+        response_bytes: Some(200),
+        sla_target_ms: None,
+    }
+}
+
+fn writer_config(capacity: usize, overflow_policy: TraceOverflowPolicy) -> TraceWriterConfig {
+    TraceWriterConfig {
+        enabled: true,
+        capacity,
+        overflow_policy,
+        block_timeout_ms: 50,
+    }
+}
+
+async fn completed_status(db: &DatabaseConnection, request_id: &str) -> Option<i32> {
+    proxy_tracing::Entity::find()
+        .filter(proxy_tracing::Column::RequestId.eq(request_id))
+        .one(db)
+        .await
+        .expect("load trace")
+        .expect("trace exists")
+        .status_code
+}
+
+#[tokio::test]
+async fn queued_traces_are_flushed_by_background_writer() {
+    let db = setup_test_db().await;
+    let tracer = Arc::new(ImmediateProxyTracer::new(db.clone()));
+    let request_ids = ["writer-flush-1", "writer-flush-2", "writer-flush-3"];
+    start_traces(&tracer, &request_ids).await;
+
+    let writer = Arc::new(TraceWriter::new(
+        tracer,
+        writer_config(16, TraceOverflowPolicy::DropOldest),
+    ));
+    writer.start().await.expect("start writer");
+    for request_id in request_ids {
+        assert_eq!(
+            writer.enqueue(request_id, complete_params()).await,
+            EnqueueOutcome::Queued
+        );
+    }
+    tokio::time::timeout(FLUSH_TIMEOUT, writer.flush())
+        .await
+        .expect("flush in time");
+
+    for request_id in request_ids {
+        assert_eq!(completed_status(&db, request_id).await, Some(200));
+    }
+    assert_eq!(writer.dropped(), 0);
+    writer.stop().await;
+}
+
+#[tokio::test]
+async fn full_queue_drops_oldest_trace() {
+    let db = setup_test_db().await;
+    let tracer = Arc::new(ImmediateProxyTracer::new(db.clone()));
+    let request_ids = ["writer-drop-1", "writer-drop-2", "writer-drop-3"];
+    start_traces(&tracer, &request_ids).await;
+
+    // 写入任务尚未启动，队列只会增长
+    let writer = Arc::new(TraceWriter::new(
+        tracer,
+        writer_config(2, TraceOverflowPolicy::DropOldest),
+    ));
+    assert_eq!(
+        writer.enqueue(request_ids[0], complete_params()).await,
+        EnqueueOutcome::Queued
+    );
+    assert_eq!(
+        writer.enqueue(request_ids[1], complete_params()).await,
+        EnqueueOutcome::Queued
+    );
+    assert_eq!(
+        writer.enqueue(request_ids[2], complete_params()).await,
+        EnqueueOutcome::DroppedOldest
+    );
+    assert_eq!(writer.queued(), 2);
+    assert_eq!(writer.dropped(), 1);
+
+    writer.start().await.expect("start writer");
+    tokio::time::timeout(FLUSH_TIMEOUT, writer.flush())
+        .await
+        .expect("flush in time");
+
+    assert_eq!(completed_status(&db, request_ids[0]).await, None);
+    assert_eq!(completed_status(&db, request_ids[1]).await, Some(200));
+    assert_eq!(completed_status(&db, request_ids[2]).await, Some(200));
+    writer.stop().await;
+}
+
+#[tokio::test]
+async fn full_queue_blocks_briefly_then_drops_new_trace() {
+    let db = setup_test_db().await;
+    let tracer = Arc::new(ImmediateProxyTracer::new(db.clone()));
+    let request_ids = ["writer-block-1", "writer-block-2"];
+    start_traces(&tracer, &request_ids).await;
+
+    let config = writer_config(1, TraceOverflowPolicy::Block);
+    let block_timeout = config.block_timeout();
+    let writer = Arc::new(TraceWriter::new(tracer, config));
+    assert_eq!(
+        writer.enqueue(request_ids[0], complete_params()).await,
+        EnqueueOutcome::Queued
+    );
+
+    let started = tokio::time::Instant::now();
+    assert_eq!(
+        writer.enqueue(request_ids[1], complete_params()).await,
+        EnqueueOutcome::Dropped
+    );
+    assert!(started.elapsed() >= block_timeout);
+    assert_eq!(writer.queued(), 1);
+    assert_eq!(writer.dropped(), 1);
+
+    writer.start().await.expect("start writer");
+    tokio::time::timeout(FLUSH_TIMEOUT, writer.flush())
+        .await
+        .expect("flush in time");
+    assert_eq!(completed_status(&db, request_ids[0]).await, Some(200));
+    assert_eq!(completed_status(&db, request_ids[1]).await, None);
+    writer.stop().await;
+}
+
+#[tokio::test]
+async fn blocked_enqueue_resumes_when_writer_frees_space() {
+    let db = setup_test_db().await;
+    let tracer = Arc::new(ImmediateProxyTracer::new(db.clone()));
+    let request_ids = ["writer-resume-1", "writer-resume-2", "writer-resume-3"];
+    start_traces(&tracer, &request_ids).await;
+
+    let writer = Arc::new(TraceWriter::new(
+        tracer,
+        TraceWriterConfig {
+            block_timeout_ms: 2_000,
+            ..writer_config(1, TraceOverflowPolicy::Block)
+        },
+    ));
+    writer.start().await.expect("start writer");
+    for request_id in request_ids {
+        assert_eq!(
+            writer.enqueue(request_id, complete_params()).await,
+            EnqueueOutcome::Queued
+        );
+    }
+    tokio::time::timeout(FLUSH_TIMEOUT, writer.flush())
+        .await
+        .expect("flush in time");
+
+    for request_id in request_ids {
+        assert_eq!(completed_status(&db, request_id).await, Some(200));
+    }
+    assert_eq!(writer.dropped(), 0);
+    writer.stop().await;
+}
@@ -0,0 +1,167 @@
+//! 上游错误返回方式测试
+//!
+//! `verbatim` 原样透传上游错误响应的响应体与响应头；`sanitized`（默认）将错误响应体规范化为统一格式、
+//! 脱敏错误信息，并改写响应头使之与新的响应体一致。
+
+mod common;
+
+use api_proxy::proxy::ProxyContext;
+use api_proxy::proxy::response_transform_service::ResponseTransformService;
+use api_proxy::proxy::upstream_error::UpstreamErrorMode;
+use bytes::Bytes;
+use entity::user_service_apis;
+use pingora_http::ResponseHeader;
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde_json::{Value, json};
+
+const USER_ID: i32 = 4300;
+const PROVIDER_TYPE_ID: i32 = 531;
+const SANITIZED_API_ID: i32 = 6000;
+const VERBATIM_API_ID: i32 = 6001;
+
+const UPSTREAM_ERROR_BODY: &str = r#"{"error": {"message": "Incorrect API key provided: sk-abcdefghijklmnopqrstuvwxyz.", "type": "invalid_request_error", "param": null, "code": "invalid_api_key"}, "organization": "org-internal"}"#;
+
+async fn setup() -> DatabaseConnection {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(
+        &db,
+        common::provider(PROVIDER_TYPE_ID, "upstream_error_provider"),
+    )
+    .await;
+
+    for (id, mode) in [
+        (SANITIZED_API_ID, None),
+        (VERBATIM_API_ID, Some("verbatim")),
+    ] {
+        let mut model = common::service_api(id, USER_ID, PROVIDER_TYPE_ID, &[]);
+        if let Some(mode) = mode {
+            model.upstream_error_mode = Set(mode.to_string());
+        }
+        common::insert_service_api(&db, model).await;
+    }
+    db
+}
+
+async fn context_for(db: &DatabaseConnection, api_id: i32) -> ProxyContext {
+    let service_api = user_service_apis::Entity::find_by_id(api_id)
+        .one(db)
+        .await
+        .expect("load service api")
+        .expect("service api exists");
+    let mut ctx = ProxyContext::default();
+    ctx.routing.user_service_api = Some(service_api);
+    ctx
+}
+
+fn upstream_error_response() -> ResponseHeader {
+    let mut resp = ResponseHeader::build(401, None).unwrap();
+    for (name, value) in [
+        ("content-type", "application/json"),
+        ("content-length", "196"),
+        ("x-ratelimit-remaining", "42"),
+    ] {
+        resp.append_header(name, value).unwrap();
+    }
+    resp
+}
+
+/// 模拟 `response_body_filter`：按分块交给改写器，未配置改写器时原样输出
+fn client_body(ctx: &mut ProxyContext, upstream_body: &str) -> Vec<u8> {
+    let (head, tail) = upstream_body.split_at(upstream_body.len() / 2);
+    let chunks = [
+        (Bytes::from(head.to_string()), false),
+        (Bytes::from(tail.to_string()), true),
+    ];
+    let mut output = Vec::new();
+    for (chunk, end_of_stream) in chunks {
+        let mut body = Some(chunk);
+        if let Some(sanitizer) = ctx.response.upstream_error.as_mut() {
+            body = sanitizer.push(body.as_ref(), end_of_stream);
+        }
+        if let Some(body) = body {
+            output.extend_from_slice(&body);
+        }
+    }
+    output
+}
+
+#[tokio::test]
+async fn verbatim_passes_upstream_error_through_unchanged() {
+    let db = setup().await;
+    let mut ctx = context_for(&db, VERBATIM_API_ID).await;
+    assert_eq!(
+        UpstreamErrorMode::from_context(&ctx),
+        UpstreamErrorMode::Verbatim
+    );
+
+    let mut resp = upstream_error_response();
+    let verbatim = ResponseTransformService::prepare_upstream_error(&mut resp, &mut ctx)
+        .expect("prepare upstream error");
+    assert!(verbatim);
+    assert!(ctx.response.upstream_error.is_none());
+    assert_eq!(resp.headers.get("content-length").unwrap(), "196");
+    assert_eq!(
+        resp.headers.get("content-type").unwrap(),
+        "application/json"
+    );
+
+    assert_eq!(
+        client_body(&mut ctx, UPSTREAM_ERROR_BODY),
+        UPSTREAM_ERROR_BODY.as_bytes()
+    );
+}
+
+#[tokio::test]
+async fn sanitized_is_default_and_normalizes_upstream_error() {
+    let db = setup().await;
+    let mut ctx = context_for(&db, SANITIZED_API_ID).await;
+    ctx.request_id = "req-upstream-error".to_string();
+    assert_eq!(
+        UpstreamErrorMode::from_context(&ctx),
+        UpstreamErrorMode::Sanitized
+    );
+
+    let mut resp = upstream_error_response();
+    let verbatim = ResponseTransformService::prepare_upstream_error(&mut resp, &mut ctx)
+        .expect("prepare upstream error");
+    assert!(!verbatim);
+    assert!(resp.headers.get("content-length").is_none());
+    assert_eq!(
+        resp.headers.get("content-type").unwrap(),
+        "application/json; charset=utf-8"
+    );
+
+    let body: Value = serde_json::from_slice(&client_body(&mut ctx, UPSTREAM_ERROR_BODY))
+        .expect("sanitized body is json");
+    assert_eq!(
+        body,
+        json!({"error": {
+            "type": "upstream_error",
+            "code": 401,
+            "message": "Incorrect API key provided: sk-a***wxyz.",
+            "upstream_type": "invalid_request_error",
+            "request_id": "req-upstream-error"
+        }})
+    );
+}
+
+#[tokio::test]
+async fn successful_responses_are_not_touched() {
+    let db = setup().await;
+    let mut ctx = context_for(&db, SANITIZED_API_ID).await;
+
+    let mut resp = ResponseHeader::build(200, None).unwrap();
+    resp.append_header("content-length", "2").unwrap();
+    let verbatim = ResponseTransformService::prepare_upstream_error(&mut resp, &mut ctx)
+        .expect("prepare upstream error");
+    assert!(!verbatim);
+    assert!(ctx.response.upstream_error.is_none());
+    assert_eq!(resp.headers.get("content-length").unwrap(), "2");
+}
+
+#[test]
+fn unknown_mode_is_rejected() {
+    assert!(UpstreamErrorMode::parse_known("verbatim").is_ok());
+    assert!(UpstreamErrorMode::parse_known("raw").is_err());
+}
@@ -0,0 +1,159 @@
+//! 上游连接阶段耗时测试
+//!
+//! 使用 Pingora 连接器连接模拟上游，按代理中的调用顺序记录阶段，验证 DNS、建连与首字节阶段均被记录；
+//! 并经真实代理转发请求，验证各阶段写入追踪记录的 `response_metadata.phases`。
+
+mod common;
+
+use std::time::Duration;
+
+use api_proxy::proxy::upstream_timing::{PHASES_KEY, UpstreamTiming};
+use entity::proxy_tracing;
+use pingora_core::connectors::http::v1::Connector;
+use pingora_core::upstreams::peer::HttpPeer;
+use pingora_http::RequestHeader;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const UPSTREAM_DELAY_MS: u64 = 30;
+const REQUEST_BODY: &str = r#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}]}"#;
+
+#[tokio::test]
+async fn records_connect_and_first_byte_phases_for_successful_request() {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("listener addr");
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("accept");
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await;
+        tokio::time::sleep(Duration::from_millis(UPSTREAM_DELAY_MS)).await;
+        socket
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+            .await
+            .expect("write response");
+    });
+
+    let mut timing = UpstreamTiming::default();
+    timing.start_dns();
+    let peer = HttpPeer::new(&addr.to_string(), false, String::new());
+    timing.finish_dns();
+
+    let connector = Connector::new(None);
+    let (mut session, reused) = connector
+        .get_http_session(&peer)
+        .await
+        .expect("connect upstream");
+    assert!(!reused);
+    timing.record_connected(reused, Some(session.digest()));
+
+    let request = RequestHeader::build("GET", b"/v1/models", None).expect("build request");
+    session
+        .write_request_header(Box::new(request))
+        .await
+        .expect("write request");
+    session.read_response().await.expect("read response");
+    timing.record_first_byte();
+    assert_eq!(
+        session.resp_header().map(|header| header.status.as_u16()),
+        Some(200)
+    );
+
+    let names: Vec<_> = timing
+        .phases()
+        .iter()
+        .map(|phase| phase.phase.as_str())
+        .collect();
+    assert_eq!(names, vec!["dns", "connect", "tcp_connect", "first_byte"]);
+    assert!(timing.phase("tls_handshake").is_none());
+    assert!(
+        timing
+            .phase("connect")
+            .is_some_and(|phase| phase.details.is_none())
+    );
+    let first_byte_ms = timing
+        .phase("first_byte")
+        .and_then(|phase| phase.duration_ms)
+        .expect("first byte duration");
+    assert!(first_byte_ms >= UPSTREAM_DELAY_MS);
+}
+
+/// 轮询追踪记录，直到响应元数据中出现阶段信息
+async fn wait_for_recorded_phases(db: &DatabaseConnection) -> Vec<serde_json::Value> {
+    for _ in 0..100 {
+        let phases = proxy_tracing::Entity::find()
+            .all(db)
+            .await
+            .expect("load traces")
+            .into_iter()
+            .find_map(|trace| {
+                trace
+                    .response_metadata?
+                    .get(PHASES_KEY)?
+                    .as_array()
+                    .cloned()
+            });
+        if let Some(phases) = phases {
+            return phases;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("trace with upstream phases was not written");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn proxied_request_records_phases_in_response_metadata() {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind listener");
+    let addr = listener.local_addr().expect("listener addr");
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("accept");
+        common::read_request_head(&mut socket).await;
+        let mut body = vec![0u8; REQUEST_BODY.len()];
+        socket
+            .read_exact(&mut body)
+            .await
+            .expect("read request body");
+        tokio::time::sleep(Duration::from_millis(UPSTREAM_DELAY_MS)).await;
+        socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}",
+            )
+            .await
+            .expect("write response");
+    });
+    let proxy = common::start_proxy(&format!("http://{addr}"), |_| {}).await;
+
+    let mut client = TcpStream::connect(proxy.addr).await.expect("connect proxy");
+    let request = format!(
+        "POST /v1/chat/completions HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\n\
+Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{REQUEST_BODY}",
+        common::CLIENT_API_KEY,
+        REQUEST_BODY.len()
+    );
+    client
+        .write_all(request.as_bytes())
+        .await
+        .expect("write request");
+    let mut response = Vec::new();
+    client
+        .read_to_end(&mut response)
+        .await
+        .expect("read response");
+    assert!(response.starts_with(b"HTTP/1.1 200"));
+
+    // upstream_peer 记录 dns，connected_to_upstream 记录 connect/tcp_connect，response_filter 记录 first_byte
+    let phases = wait_for_recorded_phases(&proxy.db).await;
+    let names: Vec<_> = phases
+        .iter()
+        .filter_map(|phase| phase["phase"].as_str())
+        .collect();
+    assert_eq!(names, vec!["dns", "connect", "tcp_connect", "first_byte"]);
+    let first_byte_ms = phases[3]["duration_ms"]
+        .as_u64()
+        .expect("first byte duration");
+    assert!(first_byte_ms >= UPSTREAM_DELAY_MS);
+}
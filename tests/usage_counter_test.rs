@@ -0,0 +1,134 @@
+//! 服务 API 用量计数测试
+//!
+//! 验证用量先在内存中累加、批量写入汇总表，达到更新次数阈值时提前写入，以及停止任务时写入剩余计数。
+
+mod common;
+
+use api_proxy::config::UsageCounterConfig;
+use api_proxy::trace::{UsageCounter, UsageDelta};
+use chrono::Utc;
+use entity::api_key_usage_counters;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use std::sync::Arc;
+use std::time::Duration;
+
+const USER_ID: i32 = 3300;
+const PROVIDER_TYPE_ID: i32 = 440;
+const SERVICE_API_ID: i32 = 5300;
+const EPSILON: f64 = 1e-9;
+
+/// 等待后台写入的超时，避免测试在写入任务异常时挂起
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn setup_test_db() -> Arc<DatabaseConnection> {
+    let db = common::setup_db().await;
+    common::insert_user(&db, common::user(USER_ID)).await;
+    common::insert_provider(&db, common::provider(PROVIDER_TYPE_ID, "counter_provider")).await;
+    common::insert_service_api(
+        &db,
+        common::service_api(SERVICE_API_ID, USER_ID, PROVIDER_TYPE_ID, &[]),
+    )
+    .await;
+
+    Arc::new(db)
+}
+
+fn config(flush_interval_secs: u64, flush_max_updates: usize) -> UsageCounterConfig {
+    UsageCounterConfig {
+        enabled: true,
+        flush_interval_secs,
+        flush_max_updates,
+    }
+}
+
+async fn stored_counters(db: &DatabaseConnection) -> Vec<api_key_usage_counters::Model> {
+    api_key_usage_counters::Entity::find()
+        .all(db)
+        .await
+        .expect("load usage counters")
+}
+
+fn assert_counter(row: &api_key_usage_counters::Model, requests: i64, tokens: i64, cost: f64) {
+    assert_eq!(row.user_service_api_id, SERVICE_API_ID);
+    assert_eq!(row.usage_date, Utc::now().date_naive());
+    assert_eq!(row.request_count, requests);
+    assert_eq!(row.total_tokens, tokens);
+    assert!(
+        (row.total_cost - cost).abs() < EPSILON,
+        "got {}",
+        row.total_cost
+    );
+}
+
+#[tokio::test]
+async fn counters_accumulate_in_memory_and_flush_into_one_row() {
+    let db = setup_test_db().await;
+    let counter = UsageCounter::new(db.clone(), config(3600, 1000));
+
+    counter.record(SERVICE_API_ID, 100, 0.25);
+    counter.record(SERVICE_API_ID, 250, 0.5);
+    assert_eq!(
+        counter.pending(SERVICE_API_ID),
+        UsageDelta {
+            requests: 2,
+            tokens: 350,
+            cost: 0.75,
+        }
+    );
+    assert!(stored_counters(db.as_ref()).await.is_empty());
+
+    assert_eq!(counter.flush().await.expect("flush"), 1);
+    assert_eq!(counter.pending(SERVICE_API_ID), UsageDelta::default());
+    assert_eq!(counter.pending_updates(), 0);
+
+    // 再次写入时累加到同一行
+    counter.record(SERVICE_API_ID, 50, 0.125);
+    counter.flush().await.expect("flush");
+    let rows = stored_counters(db.as_ref()).await;
+    assert_eq!(rows.len(), 1);
+    assert_counter(&rows[0], 3, 400, 0.875);
+
+    // 没有新增量时不写库
+    assert_eq!(counter.flush().await.expect("flush"), 0);
+}
+
+#[tokio::test]
+async fn reaching_update_threshold_triggers_background_flush() {
+    let db = setup_test_db().await;
+    let counter = Arc::new(UsageCounter::new(db.clone(), config(3600, 2)));
+    counter.start().await.expect("start counter");
+
+    counter.record(SERVICE_API_ID, 10, 0.1);
+    counter.record(SERVICE_API_ID, 20, 0.2);
+
+    tokio::time::timeout(FLUSH_TIMEOUT, async {
+        while stored_counters(db.as_ref()).await.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("threshold flush");
+
+    let rows = stored_counters(db.as_ref()).await;
+    assert_counter(&rows[0], 2, 30, 0.3);
+    counter.stop().await;
+}
+
+#[tokio::test]
+async fn graceful_stop_flushes_remaining_counts() {
+    let db = setup_test_db().await;
+    let counter = Arc::new(UsageCounter::new(db.clone(), config(3600, 1000)));
+    counter.start().await.expect("start counter");
+
+    counter.record(SERVICE_API_ID, 42, 0.5);
+    assert!(stored_counters(db.as_ref()).await.is_empty());
+
+    tokio::time::timeout(FLUSH_TIMEOUT, counter.stop())
+        .await
+        .expect("stop counter");
+
+    let rows = stored_counters(db.as_ref()).await;
+    assert_eq!(rows.len(), 1);
+    assert_counter(&rows[0], 1, 42, 0.5);
+    assert_eq!(counter.pending(SERVICE_API_ID), UsageDelta::default());
+}
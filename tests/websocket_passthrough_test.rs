@@ -0,0 +1,300 @@
+//! WebSocket 透传测试
+//!
+//! 模拟 Realtime 上游：完成升级握手后回显客户端消息，并下发 `response.done` 事件；
+//! 验证握手识别，以及从真实连接读到的上游数据（任意分段）能被会话统计正确解析；
+//! 并经真实代理完成升级，验证上游认证头注入与双向帧转发。
+
+mod common;
+
+use api_proxy::proxy::websocket::{WebSocketSession, is_websocket_upgrade, model_from_query};
+use entity::provider_types;
+use pingora_http::RequestHeader;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const HANDSHAKE: &str = "GET /v1/realtime?model=gpt-4o-realtime-preview HTTP/1.1\r\n\
+Host: localhost\r\n\
+Upgrade: websocket\r\n\
+Connection: Upgrade\r\n\
+Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+Sec-WebSocket-Version: 13\r\n\r\n";
+
+/// 构造单帧文本消息；客户端发出的帧需要掩码
+fn text_frame(payload: &[u8], mask: Option<[u8; 4]>) -> Vec<u8> {
+    let mut out = vec![0x81];
+    let mask_bit = if mask.is_some() { 0x80 } else { 0x00 };
+    if payload.len() < 126 {
+        out.push(mask_bit | u8::try_from(payload.len()).unwrap());
+    } else {
+        out.push(mask_bit | 126);
+        out.extend_from_slice(&u16::try_from(payload.len()).unwrap().to_be_bytes());
+    }
+    if let Some(mask) = mask {
+        out.extend_from_slice(&mask);
+        out.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    } else {
+        out.extend_from_slice(payload);
+    }
+    out
+}
+
+fn realtime_provider() -> provider_types::Model {
+    let now = chrono::Utc::now().naive_utc();
+    provider_types::Model {
+        id: 9101,
+        name: "openai".to_string(),
+        display_name: "OpenAI".to_string(),
+        auth_type: "api_key".to_string(),
+        base_url: "api.openai.com".to_string(),
+        is_active: true,
+        config_json: None,
+        token_mappings_json: Some(
+            json!({
+                "tokens_prompt": {"type": "direct", "path": "usage.input_tokens"},
+                "tokens_completion": {"type": "direct", "path": "usage.output_tokens"},
+                "tokens_total": {"type": "direct", "path": "usage.total_tokens"}
+            })
+            .to_string(),
+        ),
+        model_extraction_json: None,
+        auth_configs_json: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+async fn read_until(stream: &mut TcpStream, marker: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(marker.len()).any(|w| w == marker) {
+        let n = stream.read(&mut chunk).await.expect("read");
+        assert!(n > 0, "connection closed early");
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    buf
+}
+
+/// 模拟上游：接受升级握手后回显一条消息，再下发 `response.done`
+async fn spawn_mock_upstream() -> std::net::SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.expect("accept");
+        let request = read_until(&mut stream, b"\r\n\r\n").await;
+        assert!(request.starts_with(b"GET /v1/realtime"));
+
+        stream
+            .write_all(
+                b"HTTP/1.1 101 Switching Protocols\r\n\
+Upgrade: websocket\r\n\
+Connection: Upgrade\r\n\
+Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n",
+            )
+            .await
+            .expect("write handshake");
+
+        // 读取一个带掩码的短文本帧并回显
+        let mut header = [0u8; 6];
+        stream.read_exact(&mut header).await.expect("frame header");
+        let len = usize::from(header[1] & 0x7f);
+        let mask = [header[2], header[3], header[4], header[5]];
+        let mut payload = vec![0u8; len];
+        stream
+            .read_exact(&mut payload)
+            .await
+            .expect("frame payload");
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+        stream
+            .write_all(&text_frame(&payload, None))
+            .await
+            .expect("echo");
+
+        let done = json!({
+            "type": "response.done",
+            "response": {"usage": {"input_tokens": 12, "output_tokens": 30, "total_tokens": 42}}
+        });
+        stream
+            .write_all(&text_frame(done.to_string().as_bytes(), None))
+            .await
+            .expect("response.done");
+    });
+
+    addr
+}
+
+#[tokio::test]
+async fn upgrade_echo_and_session_events() {
+    let addr = spawn_mock_upstream().await;
+    let mut client = TcpStream::connect(addr).await.expect("connect");
+
+    let mut req =
+        RequestHeader::build("GET", b"/v1/realtime?model=gpt-4o-realtime-preview", None).unwrap();
+    req.insert_header("upgrade", "websocket").unwrap();
+    req.insert_header("connection", "Upgrade").unwrap();
+    assert!(is_websocket_upgrade(&req));
+    assert_eq!(
+        model_from_query(&req).as_deref(),
+        Some("gpt-4o-realtime-preview")
+    );
+
+    client
+        .write_all(HANDSHAKE.as_bytes())
+        .await
+        .expect("handshake");
+    let response = read_until(&mut client, b"\r\n\r\n").await;
+    assert!(response.starts_with(b"HTTP/1.1 101"));
+
+    let message = json!({"type": "conversation.item.create"}).to_string();
+    client
+        .write_all(&text_frame(message.as_bytes(), Some([7, 3, 1, 9])))
+        .await
+        .expect("send message");
+
+    let provider = realtime_provider();
+    let mut session = WebSocketSession::new();
+    let mut chunk = [0u8; 7];
+    while session.messages_received < 2 {
+        let n = client.read(&mut chunk).await.expect("read frames");
+        assert!(n > 0, "upstream closed early");
+        session.observe_upstream(&chunk[..n], Some(&provider));
+    }
+
+    assert_eq!(session.messages_received, 2);
+    assert_eq!(session.usage.prompt_tokens, Some(12));
+    assert_eq!(session.usage.completion_tokens, Some(30));
+    assert_eq!(session.usage.total_tokens, Some(42));
+    assert_eq!(
+        session.metadata(message.len())["websocket"]["bytes_received"],
+        json!(session.bytes_received)
+    );
+}
+
+/// 从缓冲区解析一个完整的短帧（负载小于 126 字节），按需去掉掩码；不完整时继续读取
+async fn read_frame(stream: &mut TcpStream, buf: &mut Vec<u8>) -> Vec<u8> {
+    let mut chunk = [0u8; 1024];
+    loop {
+        if buf.len() >= 2 {
+            let len = usize::from(buf[1] & 0x7f);
+            assert!(len < 126, "test frames are short");
+            let mask_len = if (buf[1] & 0x80) == 0 { 0 } else { 4 };
+            let total = 2 + mask_len + len;
+            if buf.len() >= total {
+                let frame: Vec<u8> = buf.drain(..total).collect();
+                let mut payload = frame[2 + mask_len..].to_vec();
+                if mask_len > 0 {
+                    for (i, byte) in payload.iter_mut().enumerate() {
+                        *byte ^= frame[2 + i % 4];
+                    }
+                }
+                return payload;
+            }
+        }
+        let n = stream.read(&mut chunk).await.expect("read frame");
+        assert!(n > 0, "connection closed before frame");
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// 读取 HTTP 头，返回头部文本与之后已读到的数据
+async fn read_head(stream: &mut TcpStream) -> (String, Vec<u8>) {
+    let mut buf = read_until(stream, b"\r\n\r\n").await;
+    let end = buf
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .expect("head end")
+        + 4;
+    let rest = buf.split_off(end);
+    (String::from_utf8(buf).expect("utf-8 head"), rest)
+}
+
+/// 经代理连接的模拟上游：校验注入的认证头，先主动下发一帧并等待客户端回显，再回显客户端消息
+async fn spawn_proxied_upstream() -> (String, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    let upstream = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.expect("accept");
+        let (head, mut buf) = read_head(&mut stream).await;
+        stream
+            .write_all(
+                b"HTTP/1.1 101 Switching Protocols\r\n\
+Upgrade: websocket\r\n\
+Connection: Upgrade\r\n\
+Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n",
+            )
+            .await
+            .expect("write handshake");
+
+        stream
+            .write_all(&text_frame(b"server-hello", None))
+            .await
+            .expect("send server frame");
+        assert_eq!(read_frame(&mut stream, &mut buf).await, b"server-hello");
+
+        let message = read_frame(&mut stream, &mut buf).await;
+        stream
+            .write_all(&text_frame(&message, None))
+            .await
+            .expect("echo");
+        head
+    });
+
+    (format!("http://{addr}"), upstream)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn upgrade_through_proxy_injects_auth_and_relays_frames() {
+    let (upstream_url, upstream) = spawn_proxied_upstream().await;
+    let proxy = common::start_proxy(&upstream_url, |_| {}).await;
+
+    let mut client = TcpStream::connect(proxy.addr).await.expect("connect proxy");
+    let handshake = HANDSHAKE.replacen(
+        "Host: localhost\r\n",
+        &format!(
+            "Host: localhost\r\nAuthorization: Bearer {}\r\n",
+            common::CLIENT_API_KEY
+        ),
+        1,
+    );
+    client
+        .write_all(handshake.as_bytes())
+        .await
+        .expect("handshake");
+    let (response, mut buf) = read_head(&mut client).await;
+    assert!(response.starts_with("HTTP/1.1 101"), "{response}");
+
+    // 上游 -> 客户端，客户端回显
+    let server_frame = read_frame(&mut client, &mut buf).await;
+    assert_eq!(server_frame, b"server-hello");
+    client
+        .write_all(&text_frame(&server_frame, Some([1, 2, 3, 4])))
+        .await
+        .expect("echo server frame");
+
+    // 客户端 -> 上游，上游回显
+    let message = json!({"type": "conversation.item.create"}).to_string();
+    client
+        .write_all(&text_frame(message.as_bytes(), Some([7, 3, 1, 9])))
+        .await
+        .expect("send message");
+    assert_eq!(read_frame(&mut client, &mut buf).await, message.as_bytes());
+
+    // 上游收到的是服务商密钥，客户端密钥不会透传
+    let head = upstream
+        .await
+        .expect("upstream assertions")
+        .to_ascii_lowercase();
+    assert!(
+        head.contains(&format!(
+            "authorization: bearer {}",
+            common::UPSTREAM_API_KEY
+        )),
+        "{head}"
+    );
+    assert!(!head.contains(common::CLIENT_API_KEY), "{head}");
+    assert!(head.contains("upgrade: websocket"), "{head}");
+}
@@ -142,7 +142,7 @@ export interface OAuthCallbackResponse {
 
 // OAuth轮询状态响应
 export interface OAuthPollingStatusResponse {
-  status: 'pending' | 'authorized' | 'error' | 'expired' | 'revoked'
+  status: 'pending' | 'authorized' | 'error' | 'expired' | 'revoked' | 'needs_attention'
   access_token?: string
   refresh_token?: string
   id_token?: string